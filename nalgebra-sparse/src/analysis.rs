@@ -0,0 +1,1699 @@
+//! Structural and numerical analysis of sparse matrices.
+//!
+//! The types in this module are meant to describe a sparse matrix without having to probe its
+//! internals manually. This is mostly useful when logging or debugging what a solver or
+//! factorization is being given.
+
+use crate::{
+    control::Control,
+    convert::utils::CountToOffsetIter,
+    cs::{Compression, CsMatrix},
+    error::{OperationError, OperationErrorKind},
+    factorization::{elimination_tree, nonzero_pattern},
+};
+use nalgebra::Scalar;
+use num_traits::Signed;
+use std::{borrow::Borrow, cmp::Ordering, collections::HashMap, fmt, mem::size_of_val};
+
+/// A summary of the statistics of a `CsMatrix`.
+///
+/// This is produced by [`CsMatrix::summary`], and implements `Display` so that it can be printed
+/// or logged directly.
+///
+/// # Example
+///
+/// ```rust
+/// # use nalgebra_sparse::cs::CsrMatrix;
+/// let matrix = CsrMatrix::try_from_parts(
+///     3,
+///     3,
+///     vec![0, 2, 3],
+///     vec![0, 1, 1, 2],
+///     vec![4.0, -1.0, 3.0, 2.0],
+/// )
+/// .unwrap();
+///
+/// let summary = matrix.summary();
+///
+/// assert_eq!(summary.shape, (3, 3));
+/// assert_eq!(summary.nnz, 4);
+/// assert_eq!(summary.lower_bandwidth, 0);
+/// assert_eq!(summary.upper_bandwidth, 1);
+/// assert_eq!(summary.min_value, Some(-1.0));
+/// assert_eq!(summary.max_value, Some(4.0));
+/// assert!(!summary.is_structurally_symmetric);
+///
+/// println!("{}", summary);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixSummary<T> {
+    /// The shape of the matrix, as `(nrows, ncols)`.
+    pub shape: (usize, usize),
+
+    /// The number of explicitly stored entries in the matrix.
+    pub nnz: usize,
+
+    /// The fraction of the matrix that is explicitly stored, i.e. `nnz / (nrows * ncols)`.
+    ///
+    /// Matrices with zero rows or columns have a density of zero.
+    pub density: f64,
+
+    /// The largest distance below the diagonal of any explicit entry, i.e. the maximum of `row -
+    /// col` over all explicit entries where `row > col`.
+    pub lower_bandwidth: usize,
+
+    /// The largest distance above the diagonal of any explicit entry, i.e. the maximum of `col -
+    /// row` over all explicit entries where `col > row`.
+    pub upper_bandwidth: usize,
+
+    /// Whether the sparsity pattern of the matrix is symmetric.
+    ///
+    /// This is always `false` for non-square matrices.
+    pub is_structurally_symmetric: bool,
+
+    /// Whether the matrix is numerically symmetric, i.e. whether `A[(i, j)] == A[(j, i)]` for
+    /// every explicit entry.
+    ///
+    /// NOTE: Since this is evaluated against the explicit entries of the matrix, a matrix with an
+    /// explicit zero mirrored by an implicit zero is not considered symmetric.
+    pub is_symmetric: bool,
+
+    /// The smallest explicit value in the matrix, or `None` if the matrix has no explicit entries
+    /// (or no explicit entries that can be compared, e.g. only `NaN`s).
+    pub min_value: Option<T>,
+
+    /// The largest explicit value in the matrix, or `None` if the matrix has no explicit entries
+    /// (or no explicit entries that can be compared, e.g. only `NaN`s).
+    pub max_value: Option<T>,
+
+    /// The number of bytes used by the offsets, indices, and data of the matrix.
+    ///
+    /// This does not account for any excess capacity in the underlying storage, nor the size of
+    /// the `CsMatrix` struct itself.
+    pub memory_usage: usize,
+}
+
+impl<T> fmt::Display for MatrixSummary<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (nrows, ncols) = self.shape;
+
+        writeln!(f, "shape:      {} × {}", nrows, ncols)?;
+        writeln!(
+            f,
+            "nnz:        {} (density {:.4}%)",
+            self.nnz,
+            self.density * 100.0
+        )?;
+        writeln!(
+            f,
+            "bandwidth:  lower {}, upper {}",
+            self.lower_bandwidth, self.upper_bandwidth
+        )?;
+        writeln!(
+            f,
+            "symmetric:  structurally {}, numerically {}",
+            yes_or_no(self.is_structurally_symmetric),
+            yes_or_no(self.is_symmetric)
+        )?;
+
+        match (&self.min_value, &self.max_value) {
+            (Some(min), Some(max)) => writeln!(f, "values:     min {}, max {}", min, max)?,
+            _ => writeln!(f, "values:     none")?,
+        }
+
+        write!(f, "memory:     {} bytes", self.memory_usage)
+    }
+}
+
+fn yes_or_no(flag: bool) -> &'static str {
+    if flag {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// Computes the summary of a sparse matrix.
+///
+/// See [`CsMatrix::summary`].
+pub(crate) fn summarize<T, MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> MatrixSummary<T>
+where
+    T: Scalar + PartialOrd,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let (nrows, ncols) = matrix.shape();
+    let nnz = matrix.nnz();
+
+    let density = if nrows == 0 || ncols == 0 {
+        0.0
+    } else {
+        nnz as f64 / (nrows as f64 * ncols as f64)
+    };
+
+    let mut lower_bandwidth = 0;
+    let mut upper_bandwidth = 0;
+
+    let mut min_value: Option<&T> = None;
+    let mut max_value: Option<&T> = None;
+
+    let is_square = nrows == ncols;
+    let mut is_structurally_symmetric = is_square;
+    let mut is_symmetric = is_square;
+
+    for (major, minor, value) in matrix.triplet_iter() {
+        // Mapping (major, minor) to (row, col) is the same as mapping (row, col) to (major, minor),
+        // since the compression only decides which of the two indices comes first.
+        let row = C::nmajor(major, minor);
+        let col = C::nminor(major, minor);
+
+        if row > col {
+            lower_bandwidth = lower_bandwidth.max(row - col);
+        } else {
+            upper_bandwidth = upper_bandwidth.max(col - row);
+        }
+
+        // Values that cannot be compared (e.g. NaN) are never picked as the min / max.
+        let is_comparable = value.partial_cmp(value).is_some();
+
+        if min_value.map_or(is_comparable, |min| value < min) {
+            min_value = Some(value);
+        }
+
+        if max_value.map_or(is_comparable, |max| value > max) {
+            max_value = Some(value);
+        }
+
+        if is_structurally_symmetric {
+            // The matrix is square here, so `minor` is also a valid major index, and the mirrored
+            // entry is found at `(minor, major)` in major / minor terms regardless of compression.
+            match matrix.get_lane(minor).and_then(|mut lane| {
+                lane.find(|&(index, _)| index == major)
+                    .map(|(_, mirrored)| mirrored)
+            }) {
+                Some(mirrored) => is_symmetric = is_symmetric && mirrored == value,
+                None => {
+                    is_structurally_symmetric = false;
+                    is_symmetric = false;
+                }
+            }
+        }
+    }
+
+    let (offsets, indices, data) = matrix.cs_data();
+    let memory_usage = size_of_val(offsets) + size_of_val(indices) + size_of_val(data);
+
+    MatrixSummary {
+        shape: (nrows, ncols),
+        nnz,
+        density,
+        lower_bandwidth,
+        upper_bandwidth,
+        is_structurally_symmetric,
+        is_symmetric,
+        min_value: min_value.cloned(),
+        max_value: max_value.cloned(),
+        memory_usage,
+    }
+}
+
+/// Statistics on the spans of the major lanes of a `CsMatrix`.
+///
+/// The span of a lane is the distance between its first and last explicit entry, inclusive, i.e.
+/// the number of entries a dense representation of the lane would need to cover all of its
+/// explicit entries. Empty lanes have a span of zero. This is produced by
+/// [`CsMatrix::lane_span_statistics`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LaneSpanStatistics {
+    /// The smallest span of any lane.
+    pub min: usize,
+
+    /// The largest span of any lane.
+    pub max: usize,
+
+    /// The mean span across all lanes, or zero if the matrix has no lanes.
+    pub mean: f64,
+
+    /// The number of lanes without any explicit entries.
+    pub empty_lanes: usize,
+}
+
+/// Computes the span of a lane from its (sorted) minor indices.
+pub(crate) fn lane_span(indices: &[usize]) -> usize {
+    match (indices.first(), indices.last()) {
+        (Some(first), Some(last)) => last - first + 1,
+        _ => 0,
+    }
+}
+
+/// Computes the bandwidth of a sparse matrix.
+///
+/// See [`CsMatrix::bandwidth`].
+pub(crate) fn bandwidth<T, MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> usize
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    // The distance to the diagonal does not depend on which of the indices is the row.
+    matrix
+        .triplet_iter()
+        .map(|(major, minor, _)| major.max(minor) - major.min(minor))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Computes the (lower) profile of a sparse matrix.
+///
+/// See [`CsMatrix::profile`].
+pub(crate) fn profile<T, MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> usize
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let mut first_columns = (0..matrix.nrows()).collect::<Vec<_>>();
+
+    for (major, minor, _) in matrix.triplet_iter() {
+        let row = C::nmajor(major, minor);
+        let col = C::nminor(major, minor);
+
+        first_columns[row] = first_columns[row].min(col);
+    }
+
+    first_columns
+        .iter()
+        .enumerate()
+        .map(|(row, &first_column)| row - first_column)
+        .sum()
+}
+
+/// Computes the statistics of the spans of the major lanes of a sparse matrix.
+///
+/// See [`CsMatrix::lane_span_statistics`].
+pub(crate) fn lane_span_statistics<T, MO, MI, D, C>(
+    matrix: &CsMatrix<T, MO, MI, D, C>,
+) -> LaneSpanStatistics
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let nmajor = matrix.nmajor();
+
+    let mut min = None;
+    let mut max = 0;
+    let mut total = 0;
+    let mut empty_lanes = 0;
+
+    for span in matrix.lane_spans() {
+        min = Some(min.map_or(span, |min: usize| min.min(span)));
+        max = max.max(span);
+        total += span;
+
+        if span == 0 {
+            empty_lanes += 1;
+        }
+    }
+
+    let mean = if nmajor == 0 {
+        0.0
+    } else {
+        total as f64 / nmajor as f64
+    };
+
+    LaneSpanStatistics {
+        min: min.unwrap_or(0),
+        max,
+        mean,
+        empty_lanes,
+    }
+}
+
+/// The distribution of the number of explicit entries per row or column of a `CsMatrix`.
+///
+/// The shape of this distribution decides which kernels, formats and partitionings suit a
+/// matrix: matrices from meshes or stencils have nearly constant row counts, whereas adjacency
+/// matrices of real-world graphs (social networks, web graphs) are dominated by a few rows with
+/// very many entries. This is produced by [`CsMatrix::row_nnz_distribution`] and
+/// [`CsMatrix::column_nnz_distribution`].
+///
+/// # Example
+///
+/// ```rust
+/// # use nalgebra_sparse::cs::CsrMatrix;
+/// let matrix = CsrMatrix::try_from_parts(
+///     4,
+///     4,
+///     vec![0, 4, 5, 5],
+///     vec![0, 1, 2, 3, 0, 0],
+///     vec![1.0; 6],
+/// )
+/// .unwrap();
+///
+/// let rows = matrix.row_nnz_distribution();
+///
+/// assert_eq!((rows.min, rows.max, rows.empty_lanes), (0, 4, 1));
+/// assert_eq!(rows.mean, 1.5);
+/// assert_eq!(rows.quantile(0.5), 1);
+/// // One empty row, two rows with a single entry, and one with four entries
+/// assert_eq!(rows.histogram, vec![1, 2, 0, 1]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct NnzDistribution {
+    /// The smallest number of entries in any lane.
+    pub min: usize,
+
+    /// The largest number of entries in any lane.
+    pub max: usize,
+
+    /// The mean number of entries per lane, or zero if there are no lanes.
+    pub mean: f64,
+
+    /// The (population) variance of the number of entries per lane, or zero if there are no
+    /// lanes.
+    pub variance: f64,
+
+    /// The number of lanes without any explicit entries.
+    pub empty_lanes: usize,
+
+    /// A histogram of the number of entries per lane, in logarithmic buckets: `histogram[0]`
+    /// counts the empty lanes, and `histogram[k]` counts the lanes with between `2ᵏ⁻¹` and
+    /// `2ᵏ - 1` entries. Trailing empty buckets are omitted.
+    pub histogram: Vec<usize>,
+
+    /// A fit of a power law to the tail of the distribution, or `None` if there are too few
+    /// non-empty lanes, or their numbers of entries are all equal.
+    pub power_law: Option<PowerLawFit>,
+
+    sorted_counts: Vec<usize>,
+}
+
+impl NnzDistribution {
+    /// The `q`-quantile of the number of entries per lane, i.e. the smallest count such that at
+    /// least a fraction `q` of the lanes have at most that many entries.
+    ///
+    /// Returns zero if there are no lanes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not within `[0, 1]`.
+    #[must_use]
+    pub fn quantile(&self, q: f64) -> usize {
+        assert!(
+            (0.0..=1.0).contains(&q),
+            "The quantile must be within [0, 1], but is {}.",
+            q
+        );
+
+        let n = self.sorted_counts.len();
+        if n == 0 {
+            return 0;
+        }
+
+        let rank = (q * n as f64).ceil() as usize;
+        self.sorted_counts[rank.clamp(1, n) - 1]
+    }
+}
+
+/// A power law `P(X = x) ∝ x^(-exponent)` fitted to the lanes with at least `min_count` entries.
+///
+/// The exponent is the (approximate) maximum likelihood estimate for discrete data, and
+/// `min_count` is chosen to minimize the Kolmogorov-Smirnov distance between the fitted and the
+/// observed distribution, following Clauset, Shalizi and Newman, "Power-law distributions in
+/// empirical data" (2009).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerLawFit {
+    /// The exponent of the power law.
+    pub exponent: f64,
+
+    /// The number of entries above which the power law describes the lanes.
+    pub min_count: usize,
+
+    /// The largest number of entries in any lane.
+    pub max_count: usize,
+
+    /// The number of lanes with at least `min_count` entries, i.e. the size of the fitted tail.
+    pub tail_lanes: usize,
+
+    /// The Kolmogorov-Smirnov distance between the fitted and the observed distribution of the
+    /// tail.
+    pub ks_distance: f64,
+}
+
+impl PowerLawFit {
+    /// A heuristic check of whether the tail really follows a power law: it must contain at least
+    /// 50 lanes, cover at least a decade of counts, and have a Kolmogorov-Smirnov distance of at
+    /// most 0.1 to the fit.
+    ///
+    /// This does not replace a goodness-of-fit test, but it reliably tells the heavy-tailed
+    /// degree distributions of graphs apart from the narrow ones of meshes and stencils.
+    #[must_use]
+    pub fn is_plausible(&self) -> bool {
+        self.tail_lanes >= 50 && self.max_count >= 10 * self.min_count && self.ks_distance <= 0.1
+    }
+}
+
+/// Computes the distribution of the given numbers of entries per lane.
+pub(crate) fn nnz_distribution(mut counts: Vec<usize>) -> NnzDistribution {
+    counts.sort_unstable();
+
+    let n = counts.len();
+    let (mean, variance) = if n == 0 {
+        (0.0, 0.0)
+    } else {
+        let mean = counts.iter().sum::<usize>() as f64 / n as f64;
+        let variance = counts
+            .iter()
+            .map(|&count| (count as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n as f64;
+
+        (mean, variance)
+    };
+
+    let mut histogram = Vec::new();
+    for &count in &counts {
+        // 0 goes into bucket 0, and [2ᵏ⁻¹, 2ᵏ) into bucket k
+        let bucket = (usize::BITS - count.leading_zeros()) as usize;
+
+        if histogram.len() <= bucket {
+            histogram.resize(bucket + 1, 0);
+        }
+        histogram[bucket] += 1;
+    }
+
+    NnzDistribution {
+        min: counts.first().copied().unwrap_or(0),
+        max: counts.last().copied().unwrap_or(0),
+        mean,
+        variance,
+        empty_lanes: counts.partition_point(|&count| count == 0),
+        histogram,
+        power_law: fit_power_law(&counts),
+        sorted_counts: counts,
+    }
+}
+
+/// Fits a power law to the tail of the sorted `counts`, trying every distinct count as the lower
+/// bound of the tail.
+fn fit_power_law(counts: &[usize]) -> Option<PowerLawFit> {
+    const MIN_TAIL: usize = 10;
+
+    let positive = &counts[counts.partition_point(|&count| count == 0)..];
+
+    if positive.len() < MIN_TAIL || positive.first() == positive.last() {
+        return None;
+    }
+
+    let mut best: Option<PowerLawFit> = None;
+    let mut start = 0;
+
+    while positive.len() - start >= MIN_TAIL {
+        let tail = &positive[start..];
+        let min_count = tail[0];
+        let shift = min_count as f64 - 0.5;
+        let n = tail.len() as f64;
+
+        let log_sum = tail
+            .iter()
+            .map(|&count| (count as f64 / shift).ln())
+            .sum::<f64>();
+        let exponent = 1.0 + n / log_sum;
+
+        // Compare the complementary CDFs P(X >= x) at every distinct count of the tail
+        let mut ks_distance = 0.0f64;
+        let mut position = 0;
+
+        while position < tail.len() {
+            let count = tail[position];
+            let observed = (tail.len() - position) as f64 / n;
+            let fitted = ((count as f64 - 0.5) / shift).powf(1.0 - exponent);
+            ks_distance = ks_distance.max((observed - fitted).abs());
+
+            position += tail[position..].partition_point(|&other| other == count);
+        }
+
+        if best.is_none_or(|best| ks_distance < best.ks_distance) {
+            best = Some(PowerLawFit {
+                exponent,
+                min_count,
+                max_count: tail[tail.len() - 1],
+                tail_lanes: tail.len(),
+                ks_distance,
+            });
+        }
+
+        start += tail.partition_point(|&other| other == min_count);
+    }
+
+    best
+}
+
+/// The predicted cost of a Cholesky factorization of a matrix under a particular ordering.
+///
+/// This is produced by [`compare_orderings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderingReport {
+    /// The name given to the ordering.
+    pub name: String,
+
+    /// The number of explicit entries in the Cholesky factor `L`.
+    pub factor_nnz: usize,
+
+    /// The number of entries in `L` that are not present in the lower triangle of the matrix.
+    pub fill: usize,
+
+    /// The (approximate) number of floating-point operations needed to compute `L`, i.e. the sum
+    /// of the squares of the number of entries in each column of `L`.
+    pub flops: usize,
+
+    /// The bandwidth of the permuted matrix (see [`CsMatrix::bandwidth`]).
+    pub bandwidth: usize,
+
+    /// The profile of the permuted matrix (see [`CsMatrix::profile`]).
+    pub profile: usize,
+}
+
+impl fmt::Display for OrderingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: nnz(L) {}, fill {}, flops {}, bandwidth {}, profile {}",
+            self.name, self.factor_nnz, self.fill, self.flops, self.bandwidth, self.profile
+        )
+    }
+}
+
+/// Predicts the cost of a Cholesky factorization of `matrix` under each of the given orderings.
+///
+/// Each candidate is a name and a permutation, where `permutation[new] = old`, i.e. row and column
+/// `new` of the permuted matrix are row and column `permutation[new]` of `matrix`. The natural
+/// ordering is `(0..n).collect()`. Orderings computed elsewhere (e.g. AMD or reverse
+/// Cuthill-McKee) can be compared by passing them in alongside any user-defined orderings.
+///
+/// Only the sparsity pattern of the matrix is used, and the pattern is assumed to be symmetric.
+/// The reports are returned in the same order as the candidates.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if the matrix is
+/// not square, or if any of the candidates is not a permutation of `0..n`.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra_sparse::{analysis::compare_orderings, cs::CscMatrix};
+/// # use nalgebra::DMatrix;
+///
+/// // An "arrow" matrix with a dense first row and column.
+/// let dense = DMatrix::from_fn(5, 5, |i, j| if i == 0 || j == 0 || i == j { 1.0 } else { 0.0 });
+/// let matrix = CscMatrix::from(&dense);
+///
+/// let natural = (0..5).collect::<Vec<_>>();
+/// let reversed = (0..5).rev().collect::<Vec<_>>();
+///
+/// let reports = compare_orderings(&matrix, &[("natural", &natural), ("reversed", &reversed)])
+///     .unwrap();
+///
+/// for report in &reports {
+///     println!("{}", report);
+/// }
+///
+/// // Eliminating the dense row / column last avoids all fill-in.
+/// assert!(reports[0].fill > 0);
+/// assert_eq!(reports[1].fill, 0);
+/// ```
+pub fn compare_orderings<T, MO, MI, D, C>(
+    matrix: &CsMatrix<T, MO, MI, D, C>,
+    candidates: &[(&str, &[usize])],
+) -> Result<Vec<OrderingReport>, OperationError>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let (nrows, ncols) = matrix.shape();
+
+    if nrows != ncols {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("Orderings can only be compared for square matrices."),
+        ));
+    }
+
+    let _span = span!(
+        "compare_orderings",
+        n = nrows,
+        candidates = candidates.len()
+    );
+
+    candidates
+        .iter()
+        .map(|&(name, permutation)| {
+            let permuted = permute_pattern(matrix, permutation).ok_or_else(|| {
+                OperationError::from_kind_and_message(
+                    OperationErrorKind::InvalidPattern,
+                    format!(
+                        "The ordering '{}' is not a permutation of 0..{}.",
+                        name, nrows
+                    ),
+                )
+            })?;
+
+            Ok(ordering_report(name, &permuted))
+        })
+        .collect()
+}
+
+/// Symmetrically permutes the pattern of a square matrix, such that entry `(i, j)` of the result
+/// corresponds to entry `(permutation[i], permutation[j])` of the input.
+///
+/// Returns `None` if `permutation` is not a permutation of `0..n`.
+fn permute_pattern<T, MO, MI, D, C>(
+    matrix: &CsMatrix<T, MO, MI, D, C>,
+    permutation: &[usize],
+) -> Option<CsMatrix<(), Vec<usize>, Vec<usize>, Vec<()>, C>>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let n = matrix.nmajor();
+    let inverse = invert_permutation(permutation, n)?;
+
+    let mut counts = Vec::with_capacity(n);
+    let mut indices = Vec::with_capacity(matrix.nnz());
+
+    for &old in permutation {
+        let lane = matrix.get_lane(old).expect("The permutation is in bounds.");
+        let start = indices.len();
+
+        indices.extend(lane.map(|(minor, _)| inverse[minor]));
+        indices[start..].sort_unstable();
+
+        counts.push(indices.len() - start);
+    }
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+    let data = vec![(); indices.len()];
+
+    Some(unsafe { CsMatrix::from_parts_unchecked(n, n, offsets, indices, data) })
+}
+
+/// Inverts a permutation of `0..n` given as `permutation[new] = old`, such that
+/// `inverse[old] = new`.
+///
+/// Returns `None` if `permutation` is not a permutation of `0..n`.
+fn invert_permutation(permutation: &[usize], n: usize) -> Option<Vec<usize>> {
+    if permutation.len() != n {
+        return None;
+    }
+
+    let mut inverse = vec![None; n];
+
+    for (new, &old) in permutation.iter().enumerate() {
+        match inverse.get_mut(old) {
+            Some(slot @ None) => *slot = Some(new),
+            _ => return None,
+        }
+    }
+
+    Some(inverse.into_iter().flatten().collect())
+}
+
+fn ordering_report<C>(
+    name: &str,
+    permuted: &CsMatrix<(), Vec<usize>, Vec<usize>, Vec<()>, C>,
+) -> OrderingReport
+where
+    C: Compression,
+{
+    // The pattern holds one lane per row of L, with the column indices of that row.
+    let pattern = nonzero_pattern(permuted, &elimination_tree(permuted), &Control::default());
+
+    let mut column_counts = vec![0usize; permuted.nmajor()];
+
+    for &column in &pattern.indices {
+        column_counts[column] += 1;
+    }
+
+    let lower_nnz = permuted
+        .triplet_iter()
+        .filter(|&(major, minor, _)| minor <= major)
+        .count();
+
+    let factor_nnz = pattern.indices.len();
+
+    OrderingReport {
+        name: String::from(name),
+        factor_nnz,
+        fill: factor_nnz.saturating_sub(lower_nnz),
+        flops: column_counts.iter().map(|count| count * count).sum(),
+        bandwidth: permuted.bandwidth(),
+        profile: permuted.profile(),
+    }
+}
+
+/// The number of differing entries that are printed by the `Display` implementation of a
+/// [`ComparisonReport`].
+const DISPLAYED_DIFFERENCES: usize = 10;
+
+/// An explicit entry that is present in both compared matrices, but with values that differ by
+/// more than the tolerance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryDifference<T> {
+    /// The row of the entry.
+    pub row: usize,
+
+    /// The column of the entry.
+    pub column: usize,
+
+    /// The value of the entry in the first matrix.
+    pub a: T,
+
+    /// The value of the entry in the second matrix.
+    pub b: T,
+
+    /// The absolute difference between the two values.
+    pub difference: T,
+}
+
+/// A structured description of where and how two sparse matrices differ.
+///
+/// This is produced by [`compare_report`]. Every list of entries contains `(row, column)`
+/// positions, regardless of the compression of the compared matrices.
+///
+/// Entries that are only stored in one of the matrices are reported as a structural difference
+/// regardless of their value, i.e. an explicit zero in one matrix does not match an implicit zero
+/// in the other. When debugging a kernel, this is usually exactly the kind of difference that one
+/// is looking for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonReport<T> {
+    /// The shape of both matrices, as (nrows, ncols).
+    pub shape: (usize, usize),
+
+    /// The `(row, column, value)` triplets that are only explicitly stored in the first matrix.
+    pub only_in_a: Vec<(usize, usize, T)>,
+
+    /// The `(row, column, value)` triplets that are only explicitly stored in the second matrix.
+    pub only_in_b: Vec<(usize, usize, T)>,
+
+    /// The entries that are stored in both matrices but differ by more than the tolerance, sorted
+    /// from the largest to the smallest difference.
+    pub differing: Vec<EntryDifference<T>>,
+}
+
+impl<T> ComparisonReport<T> {
+    /// Returns true if the two matrices have the same pattern, and all of their values agree
+    /// within the tolerance.
+    #[must_use]
+    pub fn is_match(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.differing.is_empty()
+    }
+
+    /// Returns true if the two matrices have the same pattern, regardless of their values.
+    #[must_use]
+    pub fn patterns_match(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty()
+    }
+
+    /// The (at most) `n` entries with the largest differences between the two matrices.
+    #[must_use]
+    pub fn worst(&self, n: usize) -> &[EntryDifference<T>] {
+        &self.differing[..n.min(self.differing.len())]
+    }
+}
+
+impl<T> fmt::Display for ComparisonReport<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_match() {
+            return write!(f, "{}×{} matrices match", self.shape.0, self.shape.1);
+        }
+
+        write!(
+            f,
+            "{}×{} matrices differ: {} entries only in A, {} entries only in B, {} entries differ \
+             beyond the tolerance",
+            self.shape.0,
+            self.shape.1,
+            self.only_in_a.len(),
+            self.only_in_b.len(),
+            self.differing.len()
+        )?;
+
+        for (row, column, value) in self.only_in_a.iter().take(DISPLAYED_DIFFERENCES) {
+            write!(f, "\n  only in A: ({}, {}) = {}", row, column, value)?;
+        }
+
+        for (row, column, value) in self.only_in_b.iter().take(DISPLAYED_DIFFERENCES) {
+            write!(f, "\n  only in B: ({}, {}) = {}", row, column, value)?;
+        }
+
+        for entry in self.worst(DISPLAYED_DIFFERENCES) {
+            write!(
+                f,
+                "\n  differs: ({}, {}) = {} vs {} (difference {})",
+                entry.row, entry.column, entry.a, entry.b, entry.difference
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares two sparse matrices entry by entry, and reports where and how they differ.
+///
+/// Two values `a` and `b` at the same position are considered to differ if `|a - b| > tolerance`.
+/// See [`ComparisonReport`] for how entries that are only stored in one of the matrices are
+/// treated.
+///
+/// This is meant for debugging, e.g. when a kernel produces a different result than a reference
+/// implementation. Unlike a boolean comparison, the report lists the offending entries, and its
+/// `Display` implementation prints the worst of them.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if the two
+/// matrices do not have the same shape.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra_sparse::{analysis::compare_report, cs::CsrMatrix};
+///
+/// let a = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![0, 1], vec![1.0, 2.0]).unwrap();
+/// let b = CsrMatrix::try_from_parts(2, 2, vec![0, 2], vec![0, 1, 1], vec![1.0, 5.0, 2.5])
+///     .unwrap();
+///
+/// let report = compare_report(&a, &b, 1e-6).unwrap();
+///
+/// assert!(!report.is_match());
+/// assert_eq!(report.only_in_b, vec![(0, 1, 5.0)]);
+/// assert_eq!(report.differing[0].difference, 0.5);
+/// ```
+pub fn compare_report<T, MO1, MI1, D1, MO2, MI2, D2, C>(
+    a: &CsMatrix<T, MO1, MI1, D1, C>,
+    b: &CsMatrix<T, MO2, MI2, D2, C>,
+    tolerance: T,
+) -> Result<ComparisonReport<T>, OperationError>
+where
+    T: Scalar + Signed + PartialOrd,
+    MO1: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    D1: Borrow<[T]>,
+    MO2: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D2: Borrow<[T]>,
+    C: Compression,
+{
+    if a.shape() != b.shape() {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("Only matrices with the same shape can be compared."),
+        ));
+    }
+
+    let _span = span!(
+        "compare_report",
+        nrows = a.nrows(),
+        ncols = a.ncols(),
+        nnz_a = a.nnz(),
+        nnz_b = b.nnz()
+    );
+
+    let mut report = ComparisonReport {
+        shape: a.shape(),
+        only_in_a: Vec::new(),
+        only_in_b: Vec::new(),
+        differing: Vec::new(),
+    };
+
+    for (major, (lane_a, lane_b)) in a.iter().zip(b.iter()).enumerate() {
+        let position = |minor| (C::nmajor(major, minor), C::nminor(major, minor));
+
+        let mut lane_a = lane_a.peekable();
+        let mut lane_b = lane_b.peekable();
+
+        loop {
+            let order = match (lane_a.peek(), lane_b.peek()) {
+                (Some((i, _)), Some((j, _))) => i.cmp(j),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+
+            match order {
+                Ordering::Equal => {
+                    let (i, va) = lane_a.next().unwrap();
+                    let (_, vb) = lane_b.next().unwrap();
+                    let difference = (va.clone() - vb.clone()).abs();
+
+                    if difference > tolerance {
+                        let (row, column) = position(i);
+
+                        report.differing.push(EntryDifference {
+                            row,
+                            column,
+                            a: va.clone(),
+                            b: vb.clone(),
+                            difference,
+                        });
+                    }
+                }
+                Ordering::Less => {
+                    let (i, va) = lane_a.next().unwrap();
+                    let (row, column) = position(i);
+                    report.only_in_a.push((row, column, va.clone()));
+                }
+                Ordering::Greater => {
+                    let (j, vb) = lane_b.next().unwrap();
+                    let (row, column) = position(j);
+                    report.only_in_b.push((row, column, vb.clone()));
+                }
+            }
+        }
+    }
+
+    report.differing.sort_by(|x, y| {
+        y.difference
+            .partial_cmp(&x.difference)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    Ok(report)
+}
+
+/// Checks whether `b` is `a` with its rows and columns permuted.
+///
+/// Both permutations follow the same convention as [`compare_orderings`], i.e.
+/// `row_permutation[new] = old`: the matrices are equal under the permutations if entry `(i, j)`
+/// of `b` is entry `(row_permutation[i], column_permutation[j])` of `a` for all `i` and `j`. This
+/// is useful to verify an ordering, or to check that two assembly procedures which number the
+/// degrees of freedom differently produce the same matrix.
+///
+/// As with [`compare_report`], the comparison is structural: an explicit zero in one matrix does
+/// not match an implicit zero in the other.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if the two
+/// matrices do not have the same shape, or if either of the permutations is not a permutation of
+/// the rows or columns respectively.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra_sparse::{analysis::equal_under_permutation, cs::CsrMatrix};
+///
+/// let a = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![1, 0], vec![1, 2]).unwrap();
+/// let b = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![0, 1], vec![2, 1]).unwrap();
+///
+/// // Swapping the rows of `a` gives `b`.
+/// assert!(equal_under_permutation(&a, &b, &[1, 0], &[0, 1]).unwrap());
+/// assert!(!equal_under_permutation(&a, &b, &[0, 1], &[0, 1]).unwrap());
+/// ```
+pub fn equal_under_permutation<T, MO1, MI1, D1, MO2, MI2, D2, C>(
+    a: &CsMatrix<T, MO1, MI1, D1, C>,
+    b: &CsMatrix<T, MO2, MI2, D2, C>,
+    row_permutation: &[usize],
+    column_permutation: &[usize],
+) -> Result<bool, OperationError>
+where
+    T: Scalar,
+    MO1: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    D1: Borrow<[T]>,
+    MO2: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D2: Borrow<[T]>,
+    C: Compression,
+{
+    let (nrows, ncols) = a.shape();
+
+    if b.shape() != (nrows, ncols) {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("Only matrices with the same shape can be compared."),
+        ));
+    }
+
+    let inverse_rows = invert_permutation(row_permutation, nrows).ok_or_else(|| {
+        OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!("The row permutation is not a permutation of 0..{}.", nrows),
+        )
+    })?;
+    let inverse_columns = invert_permutation(column_permutation, ncols).ok_or_else(|| {
+        OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!(
+                "The column permutation is not a permutation of 0..{}.",
+                ncols
+            ),
+        )
+    })?;
+
+    if a.nnz() != b.nnz() {
+        return Ok(false);
+    }
+
+    let (b_offsets, b_indices, b_data) = b.cs_data();
+    let b_nnz = b_indices.len();
+
+    // As both matrices hold the same number of entries, every entry of `a` that is found in `b`
+    // accounts for a distinct entry of `b`.
+    let equal = a.triplet_iter().all(|(major, minor, value)| {
+        let (row, column) = (C::nmajor(major, minor), C::nminor(major, minor));
+        let (row, column) = (inverse_rows[row], inverse_columns[column]);
+        let (major, minor) = (C::nmajor(row, column), C::nminor(row, column));
+
+        let lane = b_offsets[major]..b_offsets.get(major + 1).copied().unwrap_or(b_nnz);
+
+        b_indices[lane.clone()]
+            .binary_search(&minor)
+            .is_ok_and(|k| b_data[lane.start + k] == *value)
+    });
+
+    Ok(equal)
+}
+
+/// Finds the permutation of the rows of `a` which produces `b`, if there is one.
+///
+/// Each row is identified by its signature, i.e. the column indices of its explicit entries. If
+/// every row of `a` has a distinct signature, the only candidate is the permutation that matches
+/// the signatures of `a` and `b`, which is then checked against the values with
+/// [`equal_under_permutation`]. The returned permutation follows the `permutation[new] = old`
+/// convention, so that row `i` of `b` is row `permutation[i]` of `a`. `None` is returned if `b`
+/// is not a row permutation of `a`.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if the two
+/// matrices do not have the same shape, or if two rows of `a` share the same signature, in which
+/// case the permutation cannot be determined from the patterns alone.
+pub fn find_row_permutation<T, MO1, MI1, D1, MO2, MI2, D2, C>(
+    a: &CsMatrix<T, MO1, MI1, D1, C>,
+    b: &CsMatrix<T, MO2, MI2, D2, C>,
+) -> Result<Option<Vec<usize>>, OperationError>
+where
+    T: Scalar,
+    MO1: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    D1: Borrow<[T]>,
+    MO2: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D2: Borrow<[T]>,
+    C: Compression,
+{
+    let (nrows, ncols) = a.shape();
+
+    if b.shape() != (nrows, ncols) {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("Only matrices with the same shape can be compared."),
+        ));
+    }
+
+    let mut rows_by_signature = HashMap::with_capacity(nrows);
+
+    for (row, signature) in row_signatures(a).into_iter().enumerate() {
+        if rows_by_signature.insert(signature, row).is_some() {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from(
+                    "The rows of the first matrix do not have unique signatures, so the \
+                     permutation cannot be determined from the patterns.",
+                ),
+            ));
+        }
+    }
+
+    let permutation = row_signatures(b)
+        .iter()
+        .map(|signature| rows_by_signature.get(signature).copied())
+        .collect::<Option<Vec<_>>>();
+
+    let permutation = match permutation {
+        Some(permutation) if invert_permutation(&permutation, nrows).is_some() => permutation,
+        _ => return Ok(None),
+    };
+
+    let identity = (0..ncols).collect::<Vec<_>>();
+
+    if equal_under_permutation(a, b, &permutation, &identity)? {
+        Ok(Some(permutation))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The sorted column indices of the explicit entries of every row of `matrix`.
+fn row_signatures<T, MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> Vec<Vec<usize>>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let mut signatures = vec![Vec::new(); matrix.nrows()];
+
+    // Lanes are visited in order, so the columns of every row are pushed in increasing order for
+    // both compressions.
+    for (major, minor, _) in matrix.triplet_iter() {
+        signatures[C::nmajor(major, minor)].push(C::nminor(major, minor));
+    }
+
+    signatures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        coo::CooMatrix,
+        cs::{CscMatrix, CsrMatrix},
+        factorization::CsCholesky,
+        proptest::*,
+        SparseEntry,
+    };
+    use nalgebra::{DMatrix, SMatrix};
+    use proptest::prelude::*;
+
+    #[test]
+    fn compare_report_rejects_mismatched_shapes() {
+        let err = compare_report(
+            &CsrMatrix::<i32>::identity(2),
+            &CsrMatrix::<i32>::identity(3),
+            0,
+        )
+        .unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn compare_report_lists_worst_offenders_first() {
+        let a =
+            CsrMatrix::try_from_parts(1, 3, vec![0], vec![0, 1, 2], vec![1.0, 2.0, 3.0]).unwrap();
+        let b =
+            CsrMatrix::try_from_parts(1, 3, vec![0], vec![0, 1, 2], vec![1.5, 2.0, 5.0]).unwrap();
+
+        let report = compare_report(&a, &b, 0.1).unwrap();
+
+        assert!(report.patterns_match());
+        assert_eq!(report.worst(1)[0].column, 2);
+        assert_eq!(report.worst(5).len(), 2);
+        assert_eq!(
+            report.to_string(),
+            "1×3 matrices differ: 0 entries only in A, 0 entries only in B, 2 entries differ \
+             beyond the tolerance\n  differs: (0, 2) = 3 vs 5 (difference 2)\n  differs: (0, 0) \
+             = 1 vs 1.5 (difference 0.5)"
+        );
+    }
+
+    #[test]
+    fn summary_of_empty_matrix() {
+        let summary = CsrMatrix::<f64>::zeros(0, 0).summary();
+
+        assert_eq!(summary.shape, (0, 0));
+        assert_eq!(summary.nnz, 0);
+        assert_eq!(summary.density, 0.0);
+        assert_eq!(summary.lower_bandwidth, 0);
+        assert_eq!(summary.upper_bandwidth, 0);
+        assert!(summary.is_structurally_symmetric);
+        assert!(summary.is_symmetric);
+        assert_eq!(summary.min_value, None);
+        assert_eq!(summary.max_value, None);
+        assert_eq!(summary.memory_usage, 0);
+    }
+
+    #[test]
+    fn summary_of_known_matrix() {
+        #[rustfmt::skip]
+        let dense = SMatrix::<i32, 4, 4>::from_row_slice(&[
+            2, 0, 5, 0,
+            0, 3, 0, 0,
+            5, 0, 1, 0,
+            0, 7, 0, -4,
+        ]);
+
+        let csr = CsrMatrix::from(&dense);
+        let csc = CscMatrix::from(&dense);
+
+        let csr_summary = csr.summary();
+        let csc_summary = csc.summary();
+
+        assert_eq!(csr_summary.shape, (4, 4));
+        assert_eq!(csr_summary.nnz, 7);
+        assert_eq!(csr_summary.density, 7.0 / 16.0);
+        assert_eq!(csr_summary.lower_bandwidth, 2);
+        assert_eq!(csr_summary.upper_bandwidth, 2);
+        assert!(!csr_summary.is_structurally_symmetric);
+        assert!(!csr_summary.is_symmetric);
+        assert_eq!(csr_summary.min_value, Some(-4));
+        assert_eq!(csr_summary.max_value, Some(7));
+        assert_eq!(
+            csr_summary.memory_usage,
+            (4 + 7) * std::mem::size_of::<usize>() + 7 * std::mem::size_of::<i32>()
+        );
+
+        assert_eq!(csr_summary, csc_summary);
+    }
+
+    #[test]
+    fn summary_detects_numeric_asymmetry() {
+        #[rustfmt::skip]
+        let dense = SMatrix::<f64, 3, 3>::from_row_slice(&[
+            1.0, 2.0, 0.0,
+            2.0, 1.0, 3.0,
+            0.0, 4.0, 1.0,
+        ]);
+
+        let summary = CsrMatrix::from(&dense).summary();
+
+        assert!(summary.is_structurally_symmetric);
+        assert!(!summary.is_symmetric);
+    }
+
+    #[test]
+    fn summary_of_rectangular_matrix_is_not_symmetric() {
+        let summary = CsrMatrix::<f64>::zeros(2, 3).summary();
+
+        assert!(!summary.is_structurally_symmetric);
+        assert!(!summary.is_symmetric);
+    }
+
+    #[test]
+    fn summary_display_contains_statistics() {
+        let summary = CsrMatrix::<f64>::identity(3).summary();
+        let display = summary.to_string();
+
+        assert!(display.contains("3 × 3"));
+        assert!(display.contains("lower 0, upper 0"));
+        assert!(display.contains("structurally yes, numerically yes"));
+        assert!(display.contains("min 1, max 1"));
+    }
+
+    #[test]
+    fn bandwidth_profile_and_lane_spans_of_known_matrix() {
+        #[rustfmt::skip]
+        let dense = SMatrix::<i32, 4, 4>::from_row_slice(&[
+            1, 0, 0, 2,
+            0, 1, 0, 0,
+            3, 0, 1, 0,
+            0, 0, 4, 1,
+        ]);
+
+        let csr = CsrMatrix::from(&dense);
+        let csc = CscMatrix::from(&dense);
+
+        assert_eq!(csr.bandwidth(), 3);
+        assert_eq!(csc.bandwidth(), 3);
+
+        // Rows 2 and 3 start 2 and 1 entries before the diagonal, respectively.
+        assert_eq!(csr.profile(), 3);
+        assert_eq!(csc.profile(), 3);
+
+        assert_eq!(csr.lane_spans().collect::<Vec<_>>(), vec![4, 1, 3, 2]);
+        assert_eq!(csc.lane_spans().collect::<Vec<_>>(), vec![3, 1, 2, 4]);
+
+        let statistics = csr.lane_span_statistics();
+
+        assert_eq!(statistics.min, 1);
+        assert_eq!(statistics.max, 4);
+        assert_eq!(statistics.mean, 2.5);
+        assert_eq!(statistics.empty_lanes, 0);
+    }
+
+    #[test]
+    fn lane_span_statistics_count_empty_lanes() {
+        let statistics = CsrMatrix::<f64>::zeros(3, 2).lane_span_statistics();
+
+        assert_eq!(statistics.min, 0);
+        assert_eq!(statistics.max, 0);
+        assert_eq!(statistics.mean, 0.0);
+        assert_eq!(statistics.empty_lanes, 3);
+
+        let statistics = CsrMatrix::<f64>::zeros(0, 0).lane_span_statistics();
+
+        assert_eq!(statistics.mean, 0.0);
+        assert_eq!(statistics.empty_lanes, 0);
+    }
+
+    #[test]
+    fn scrambled_ordering_increases_bandwidth_and_profile() {
+        let n = 8;
+        let tridiagonal =
+            DMatrix::from_fn(n, n, |i, j| if i.max(j) - i.min(j) <= 1 { 1 } else { 0 });
+
+        // Interleave the two halves of the unknowns, which pulls neighbours apart.
+        let permutation = (0..n)
+            .map(|i| if i % 2 == 0 { i / 2 } else { n / 2 + i / 2 })
+            .collect::<Vec<_>>();
+        let scrambled =
+            DMatrix::from_fn(n, n, |i, j| tridiagonal[(permutation[i], permutation[j])]);
+
+        let natural = CsrMatrix::from(&tridiagonal);
+        let scrambled = CsrMatrix::from(&scrambled);
+
+        assert_eq!(natural.bandwidth(), 1);
+        assert_eq!(natural.profile(), n - 1);
+
+        assert!(scrambled.bandwidth() > natural.bandwidth());
+        assert!(scrambled.profile() > natural.profile());
+        assert!(scrambled.lane_span_statistics().mean > natural.lane_span_statistics().mean);
+    }
+
+    /// A matrix with a dense first row and column, and a full diagonal.
+    fn arrow(n: usize) -> CscMatrix<f64> {
+        CscMatrix::from(&DMatrix::from_fn(n, n, |i, j| {
+            if i == 0 || j == 0 || i == j {
+                1.0
+            } else {
+                0.0
+            }
+        }))
+    }
+
+    #[test]
+    fn ordering_report_predicts_arrow_fill() {
+        let n = 6;
+        let matrix = arrow(n);
+
+        let natural = (0..n).collect::<Vec<_>>();
+        let reversed = (0..n).rev().collect::<Vec<_>>();
+
+        let reports =
+            compare_orderings(&matrix, &[("natural", &natural), ("reversed", &reversed)]).unwrap();
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].name, "natural");
+        assert_eq!(reports[1].name, "reversed");
+
+        // Eliminating the dense node first fills in the whole lower triangle.
+        assert_eq!(reports[0].factor_nnz, n * (n + 1) / 2);
+        assert_eq!(reports[0].fill, n * (n + 1) / 2 - (2 * n - 1));
+
+        // Eliminating it last produces no fill at all.
+        assert_eq!(reports[1].factor_nnz, 2 * n - 1);
+        assert_eq!(reports[1].fill, 0);
+        assert!(reports[1].flops < reports[0].flops);
+
+        assert_eq!(reports[0].bandwidth, reports[1].bandwidth);
+        assert!(reports[1].profile < reports[0].profile);
+    }
+
+    #[test]
+    fn ordering_report_agrees_with_factorization() {
+        let matrix = CscMatrix::from(&DMatrix::from_fn(5, 5, |i, j| {
+            if i == j {
+                4.0
+            } else if (i + j) % 3 == 0 {
+                -1.0
+            } else {
+                0.0
+            }
+        }));
+
+        let natural = (0..5).collect::<Vec<_>>();
+        let report = &compare_orderings(&matrix, &[("natural", &natural)]).unwrap()[0];
+
+        let l = CsCholesky::factor(&matrix).unwrap().take_l();
+        assert_eq!(report.factor_nnz, l.nnz());
+    }
+
+    #[test]
+    fn compare_orderings_rejects_invalid_permutations() {
+        let matrix = arrow(3);
+
+        for permutation in [vec![0, 1], vec![0, 1, 1], vec![0, 1, 3]].iter() {
+            let error = compare_orderings(&matrix, &[("bad", permutation)]).unwrap_err();
+            assert!(matches!(error.kind(), OperationErrorKind::InvalidPattern));
+        }
+
+        let error = compare_orderings(&CscMatrix::<f64>::zeros(2, 3), &[]).unwrap_err();
+        assert!(matches!(error.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn find_row_permutation_requires_unique_signatures() {
+        // Both rows of `a` only hold an entry in column 0.
+        let a = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![0, 0], vec![1, 2]).unwrap();
+
+        let err = find_row_permutation(&a, &a).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+
+        let a = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![0, 1], vec![1, 2]).unwrap();
+        let b = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![1, 0], vec![2, 1]).unwrap();
+        let c = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![1, 0], vec![2, 3]).unwrap();
+
+        assert_eq!(find_row_permutation(&a, &b).unwrap(), Some(vec![1, 0]));
+        assert_eq!(find_row_permutation(&a, &c).unwrap(), None);
+    }
+
+    #[test]
+    fn equal_under_permutation_rejects_invalid_permutations() {
+        let a = CsrMatrix::<i32>::identity(3);
+
+        for (rows, columns) in [(&[0, 1, 1][..], &[0, 1, 2][..]), (&[0, 1, 2], &[0, 1])] {
+            let err = equal_under_permutation(&a, &a, rows, columns).unwrap_err();
+            assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn bandwidth_and_profile_agree_with_dense(csc in csc_strategy()) {
+            let summary = csc.summary();
+            prop_assert_eq!(
+                csc.bandwidth(),
+                summary.lower_bandwidth.max(summary.upper_bandwidth)
+            );
+
+            let expected_profile = (0..csc.nrows())
+                .filter_map(|i| {
+                    // CSC triplets are (col, row, value).
+                    csc.triplet_iter()
+                        .filter(|&(_, row, _)| row == i)
+                        .map(|(col, _, _)| col)
+                        .min()
+                        .map(|first| i.saturating_sub(first))
+                })
+                .sum::<usize>();
+
+            prop_assert_eq!(csc.profile(), expected_profile);
+        }
+
+        #[test]
+        fn lane_spans_cover_every_lane(csr in csr_strategy()) {
+            let spans = csr.lane_spans().collect::<Vec<_>>();
+            prop_assert_eq!(spans.len(), csr.nrows());
+
+            for (span, lane) in spans.iter().zip(csr.iter()) {
+                prop_assert!(*span >= lane.len());
+            }
+
+            let statistics = csr.lane_span_statistics();
+            prop_assert_eq!(statistics.max, spans.iter().copied().max().unwrap_or(0));
+            prop_assert_eq!(statistics.empty_lanes, csr.iter().filter(|lane| lane.len() == 0).count());
+        }
+
+        #[test]
+        fn summary_agrees_with_dense(csr in csr_strategy()) {
+            let summary = csr.summary();
+            let dense = DMatrix::from(&csr);
+
+            let (lower, upper) = csr
+                .triplet_iter()
+                .fold((0, 0), |(lower, upper), (i, j, _)| {
+                    if i > j {
+                        (lower.max(i - j), upper)
+                    } else {
+                        (lower, upper.max(j - i))
+                    }
+                });
+
+            prop_assert_eq!(summary.shape, dense.shape());
+            prop_assert_eq!(summary.lower_bandwidth, lower);
+            prop_assert_eq!(summary.upper_bandwidth, upper);
+            prop_assert_eq!(summary.min_value, csr.triplet_iter().map(|(_, _, &v)| v).min());
+            prop_assert_eq!(summary.max_value, csr.triplet_iter().map(|(_, _, &v)| v).max());
+
+            if summary.is_symmetric {
+                prop_assert_eq!(&dense, &dense.transpose());
+            }
+        }
+
+        #[test]
+        fn compare_report_of_matrix_with_itself_matches(csr in csr_strategy()) {
+            let report = compare_report(&csr, &csr, 0).unwrap();
+
+            prop_assert!(report.is_match());
+            prop_assert_eq!(report.to_string(), format!("{}×{} matrices match", csr.nrows(), csr.ncols()));
+        }
+
+        #[test]
+        fn compare_report_accounts_for_every_entry(
+            (a, b) in (PROPTEST_MATRIX_DIM, PROPTEST_MATRIX_DIM).prop_flat_map(|(m, n)| {
+                let matrix = || csc(PROPTEST_I32_VALUE_STRATEGY, m..=m, n..=n, PROPTEST_MAX_NNZ);
+                (matrix(), matrix())
+            })
+        ) {
+            let report = compare_report(&a, &b, 1).unwrap();
+            let (dense_a, dense_b) = (DMatrix::from(&a), DMatrix::from(&b));
+
+            for &(i, j, v) in &report.only_in_a {
+                prop_assert_eq!(a.get_entry(i, j), Some(SparseEntry::NonZero(&v)));
+                prop_assert_eq!(b.get_entry(i, j), Some(SparseEntry::Zero));
+            }
+
+            for &(i, j, v) in &report.only_in_b {
+                prop_assert_eq!(b.get_entry(i, j), Some(SparseEntry::NonZero(&v)));
+                prop_assert_eq!(a.get_entry(i, j), Some(SparseEntry::Zero));
+            }
+
+            let both = a
+                .triplet_iter()
+                .map(|(j, i, _)| (i, j))
+                .filter(|&(i, j)| matches!(b.get_entry(i, j), Some(SparseEntry::NonZero(_))))
+                .filter(|&(i, j)| (dense_a[(i, j)] - dense_b[(i, j)]).abs() > 1)
+                .count();
+
+            prop_assert_eq!(report.differing.len(), both);
+            prop_assert!(report
+                .differing
+                .windows(2)
+                .all(|w| w[0].difference >= w[1].difference));
+            prop_assert_eq!(report.patterns_match(), a.cs_data().0 == b.cs_data().0 && a.cs_data().1 == b.cs_data().1);
+        }
+
+        #[test]
+        fn summary_of_transpose_swaps_bandwidths(csc in csc_strategy()) {
+            let summary = csc.summary();
+            let transpose_summary = csc.transpose().summary();
+
+            prop_assert_eq!(summary.lower_bandwidth, transpose_summary.upper_bandwidth);
+            prop_assert_eq!(summary.upper_bandwidth, transpose_summary.lower_bandwidth);
+            prop_assert_eq!(summary.is_symmetric, transpose_summary.is_symmetric);
+        }
+
+        #[test]
+        fn permuted_matrices_are_equal_under_permutation(
+            (a, rows, columns) in csc_strategy().prop_flat_map(|a| {
+                let (nrows, ncols) = a.shape();
+                let rows = Just((0..nrows).collect::<Vec<_>>()).prop_shuffle();
+                let columns = Just((0..ncols).collect::<Vec<_>>()).prop_shuffle();
+                (Just(a), rows, columns)
+            })
+        ) {
+            let inverse_rows = invert_permutation(&rows, a.nrows()).unwrap();
+            let inverse_columns = invert_permutation(&columns, a.ncols()).unwrap();
+
+            let mut coo = CooMatrix::new(a.nrows(), a.ncols());
+            for (column, row, &value) in a.triplet_iter() {
+                coo.push(inverse_rows[row], inverse_columns[column], value);
+            }
+            let b = CscMatrix::from(coo);
+
+            prop_assert!(equal_under_permutation(&a, &b, &rows, &columns).unwrap());
+
+            let identity = (0..a.ncols()).collect::<Vec<_>>();
+            let b_rows_only = {
+                let mut coo = CooMatrix::new(a.nrows(), a.ncols());
+                for (column, row, &value) in a.triplet_iter() {
+                    coo.push(inverse_rows[row], column, value);
+                }
+                CscMatrix::from(coo)
+            };
+
+            prop_assert!(equal_under_permutation(&a, &b_rows_only, &rows, &identity).unwrap());
+
+            if let Ok(found) = find_row_permutation(&a, &b_rows_only) {
+                let found = found.unwrap();
+                prop_assert!(equal_under_permutation(&a, &b_rows_only, &found, &identity).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn nnz_distribution_detects_power_laws() {
+        // Row i of a Zipf-like matrix has about n / (i + 1) entries, so the number of rows with at
+        // least d entries falls off like 1 / d, i.e. the counts follow a power law with exponent 2
+        let n = 2000;
+        let mut coo = CooMatrix::new(n, n);
+        for i in 0..n {
+            for j in 0..n / (i + 1) {
+                coo.push(i, j, 1.0);
+            }
+        }
+
+        let zipf = CsrMatrix::from(coo).row_nnz_distribution();
+        let fit = zipf.power_law.unwrap();
+        assert!(fit.is_plausible());
+        assert!((fit.exponent - 2.0).abs() < 0.25, "{:?}", fit);
+        assert!(zipf.variance > zipf.mean * zipf.mean);
+
+        // A tridiagonal matrix has two or three entries in every row
+        let mut coo = CooMatrix::new(n, n);
+        for i in 0..n {
+            for j in i.saturating_sub(1)..(i + 2).min(n) {
+                coo.push(i, j, 1.0);
+            }
+        }
+
+        let tridiagonal = CsrMatrix::from(coo).row_nnz_distribution();
+        assert_eq!((tridiagonal.min, tridiagonal.max), (2, 3));
+        assert_eq!(tridiagonal.histogram, vec![0, 0, n]);
+        assert!(!tridiagonal.power_law.is_some_and(|fit| fit.is_plausible()));
+    }
+
+    proptest! {
+        #[test]
+        fn nnz_distributions_agree_with_counts(csr in csr_strategy()) {
+            let csc = crate::convert::serial::convert_csr_csc(&csr);
+            let mut row_counts = vec![0; csr.nrows()];
+            let mut column_counts = vec![0; csr.ncols()];
+
+            for (i, j, _) in csr.triplet_iter() {
+                row_counts[i] += 1;
+                column_counts[j] += 1;
+            }
+
+            for (distribution, counts) in [
+                (csr.row_nnz_distribution(), &row_counts),
+                (csc.row_nnz_distribution(), &row_counts),
+                (csr.column_nnz_distribution(), &column_counts),
+                (csc.column_nnz_distribution(), &column_counts),
+            ] {
+                prop_assert_eq!(distribution.min, counts.iter().copied().min().unwrap_or(0));
+                prop_assert_eq!(distribution.max, counts.iter().copied().max().unwrap_or(0));
+                prop_assert_eq!(distribution.histogram.iter().sum::<usize>(), counts.len());
+                prop_assert_eq!(distribution.quantile(1.0), distribution.max);
+                prop_assert_eq!(distribution.quantile(0.0), distribution.min);
+
+                let total = counts.iter().sum::<usize>() as f64;
+                prop_assert!((distribution.mean * counts.len() as f64 - total).abs() < 1e-9);
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! Types for controlling long-running operations.
+//!
+//! Some operations (e.g. factorizations or sparse-matrix products on large matrices) can take a
+//! long time to complete. Functions that accept a [`Control`] periodically check whether they
+//! have been asked to stop, and return early with a "cancelled" error if so. This allows e.g.
+//! interactive applications to abort an operation cleanly instead of having to kill the thread
+//! performing it.
+//!
+//! A [`Control`] can also carry a progress callback, which is invoked with a [`Progress`] as the
+//! operation advances through each of its stages. This can be used to drive e.g. progress bars in
+//! GUIs or command-line applications.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra_sparse::{
+//!     control::{CancellationToken, Control},
+//!     cs::CscMatrix,
+//!     factorization::{CholeskyError, CsCholesky},
+//! };
+//!
+//! let matrix = CscMatrix::<f64>::identity(10);
+//! let token = CancellationToken::new();
+//!
+//! // The token can be cloned and sent to another thread, e.g. a UI thread, which calls
+//! // `token.cancel()` when the user requests it.
+//! token.cancel();
+//!
+//! let control = Control::new().with_cancellation_token(&token);
+//! let result = CsCholesky::factor_with_control(&matrix, &control);
+//!
+//! assert!(matches!(result, Err(CholeskyError::Cancelled)));
+//! ```
+//!
+//! Progress can be reported in a similar way:
+//!
+//! ```rust
+//! use nalgebra_sparse::{control::Control, cs::CscMatrix, factorization::CsCholesky};
+//!
+//! let matrix = CscMatrix::<f64>::identity(10);
+//!
+//! let print_progress = |progress| println!("{}", progress);
+//! let control = Control::new().with_progress(&print_progress);
+//!
+//! let cholesky = CsCholesky::factor_with_control(&matrix, &control).unwrap();
+//! ```
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// The stage of an operation that a [`Progress`] report refers to.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stage {
+    /// Computing the sparsity pattern of a factorization.
+    SymbolicFactorization,
+
+    /// Computing the values of a factorization.
+    NumericFactorization,
+
+    /// Computing a sparse-matrix product.
+    MatrixProduct,
+
+    /// Converting a matrix from one format to another.
+    Conversion,
+
+    /// Reading a matrix from a file.
+    Reading,
+
+    /// Iterating a solver towards the solution of a system.
+    IterativeSolve,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            Stage::SymbolicFactorization => "symbolic factorization",
+            Stage::NumericFactorization => "numeric factorization",
+            Stage::MatrixProduct => "matrix product",
+            Stage::Conversion => "conversion",
+            Stage::Reading => "reading",
+            Stage::IterativeSolve => "iterative solve",
+        };
+
+        f.write_str(description)
+    }
+}
+
+/// A report of how far along an operation is within one of its stages.
+///
+/// The units of `completed` and `total` depend on the stage, but are typically lanes (i.e. rows or
+/// columns) of the matrix being produced.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// The stage of the operation this report refers to.
+    pub stage: Stage,
+
+    /// The number of units of work completed so far in this stage.
+    pub completed: usize,
+
+    /// The total number of units of work in this stage.
+    pub total: usize,
+}
+
+impl Progress {
+    /// The fraction of the stage that has been completed, between `0.0` and `1.0`.
+    ///
+    /// Stages with no work to do are considered complete.
+    #[must_use]
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.completed as f64 / self.total as f64
+        }
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} / {} ({:.1}%)",
+            self.stage,
+            self.completed,
+            self.total,
+            self.fraction() * 100.0
+        )
+    }
+}
+
+/// A thread-safe flag that can be used to request the cancellation of an operation.
+///
+/// Cloning a `CancellationToken` produces a new handle to the same underlying flag, so that one
+/// clone can be passed to an operation (through a [`Control`]) while another is used to cancel it,
+/// possibly from a different thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a new token that has not been cancelled.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of every operation observing this token (or any of its clones).
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if cancellation has been requested.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// A set of hooks that a long-running operation checks while it runs.
+///
+/// By default, a `Control` never requests that an operation stop and does not report progress,
+/// which is equivalent to calling the variant of the operation that does not take a `Control` at
+/// all.
+///
+/// A stop can be requested through a [`CancellationToken`], through an arbitrary `should_stop`
+/// callback, or both. Operations check these hooks periodically (e.g. once per lane or column)
+/// rather than continuously, so there may be a short delay between a stop being requested and the
+/// operation returning. Progress is reported at the same points.
+#[derive(Clone, Copy, Default)]
+pub struct Control<'a> {
+    token: Option<&'a CancellationToken>,
+    should_stop: Option<&'a dyn Fn() -> bool>,
+    progress: Option<&'a dyn Fn(Progress)>,
+}
+
+impl<'a> Control<'a> {
+    /// Creates a new `Control` that never requests a stop.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the operation once `token` has been cancelled.
+    #[must_use]
+    pub fn with_cancellation_token(self, token: &'a CancellationToken) -> Self {
+        Self {
+            token: Some(token),
+            ..self
+        }
+    }
+
+    /// Stops the operation once `should_stop` returns `true`.
+    ///
+    /// NOTE: The callback is invoked often, so it should be cheap to evaluate.
+    #[must_use]
+    pub fn with_should_stop(self, should_stop: &'a dyn Fn() -> bool) -> Self {
+        Self {
+            should_stop: Some(should_stop),
+            ..self
+        }
+    }
+
+    /// Invokes `progress` as the operation advances.
+    ///
+    /// NOTE: The callback is invoked often, so it should be cheap to evaluate. Callers that e.g.
+    /// redraw a progress bar may want to throttle the updates themselves.
+    #[must_use]
+    pub fn with_progress(self, progress: &'a dyn Fn(Progress)) -> Self {
+        Self {
+            progress: Some(progress),
+            ..self
+        }
+    }
+
+    /// Returns `true` if the operation being controlled should stop as soon as possible.
+    #[must_use]
+    pub fn should_stop(&self) -> bool {
+        if let Some(token) = self.token {
+            if token.is_cancelled() {
+                return true;
+            }
+        }
+
+        match self.should_stop {
+            Some(should_stop) => should_stop(),
+            None => false,
+        }
+    }
+
+    /// Reports that `completed` out of `total` units of work have been done in `stage`.
+    pub(crate) fn report(&self, stage: Stage, completed: usize, total: usize) {
+        if let Some(progress) = self.progress {
+            progress(Progress {
+                stage,
+                completed,
+                total,
+            });
+        }
+    }
+
+    /// Reports progress and then returns `true` if the operation should stop.
+    ///
+    /// This is the check that operations are expected to perform at the start of every unit of
+    /// work.
+    pub(crate) fn checkpoint(&self, stage: Stage, completed: usize, total: usize) -> bool {
+        self.report(stage, completed, total);
+        self.should_stop()
+    }
+}
+
+impl<'a> fmt::Debug for Control<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Control")
+            .field("token", &self.token)
+            .field("should_stop", &self.should_stop.map(|_| "Fn() -> bool"))
+            .field("progress", &self.progress.map(|_| "Fn(Progress)"))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        cell::{Cell, RefCell},
+        thread,
+    };
+
+    #[test]
+    fn default_control_never_stops() {
+        assert!(!Control::new().should_stop());
+        assert!(!Control::default().should_stop());
+    }
+
+    #[test]
+    fn control_stops_once_token_is_cancelled() {
+        let token = CancellationToken::new();
+        let control = Control::new().with_cancellation_token(&token);
+
+        assert!(!control.should_stop());
+
+        let remote = token.clone();
+        thread::spawn(move || remote.cancel()).join().unwrap();
+
+        assert!(token.is_cancelled());
+        assert!(control.should_stop());
+    }
+
+    #[test]
+    fn control_stops_once_callback_returns_true() {
+        let calls = Cell::new(0);
+        let should_stop = || {
+            calls.set(calls.get() + 1);
+            calls.get() > 2
+        };
+
+        let control = Control::new().with_should_stop(&should_stop);
+
+        assert!(!control.should_stop());
+        assert!(!control.should_stop());
+        assert!(control.should_stop());
+    }
+
+    #[test]
+    fn control_reports_progress() {
+        let reports = RefCell::new(Vec::new());
+        let progress = |progress| reports.borrow_mut().push(progress);
+
+        let control = Control::new().with_progress(&progress);
+
+        assert!(!control.checkpoint(Stage::MatrixProduct, 0, 2));
+        control.report(Stage::MatrixProduct, 2, 2);
+
+        let reports = reports.into_inner();
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].fraction(), 0.0);
+        assert_eq!(reports[1].fraction(), 1.0);
+        assert_eq!(reports[1].to_string(), "matrix product: 2 / 2 (100.0%)");
+    }
+
+    #[test]
+    fn empty_stage_is_complete() {
+        let progress = Progress {
+            stage: Stage::Conversion,
+            completed: 0,
+            total: 0,
+        };
+
+        assert_eq!(progress.fraction(), 1.0);
+    }
+}
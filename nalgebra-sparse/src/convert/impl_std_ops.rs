@@ -1,7 +1,11 @@
 use crate::{
     convert::serial::*,
     coo::CooMatrix,
-    cs::{CompressedColumnStorage, CompressedRowStorage, CsMatrix, CscMatrix, CsrMatrix},
+    cs::{
+        CompressedColumnStorage, CompressedRowStorage, Compression, CsMatrix, CscMatrix, CsrMatrix,
+    },
+    interleaved::InterleavedCsMatrix,
+    runlength::RunLengthCsMatrix,
 };
 use nalgebra::{storage::RawStorage, ClosedAdd, DMatrix, Dim, Matrix, Scalar};
 use num_traits::Zero;
@@ -140,13 +144,106 @@ where
     }
 }
 
+impl<'a, T, MO, MI, D, C> From<&'a CsMatrix<T, MO, MI, D, C>> for InterleavedCsMatrix<T, C>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    fn from(matrix: &'a CsMatrix<T, MO, MI, D, C>) -> Self {
+        convert_cs_interleaved(matrix)
+    }
+}
+
+impl<T, C> From<InterleavedCsMatrix<T, C>> for CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C>
+where
+    T: Scalar,
+    C: Compression,
+{
+    fn from(matrix: InterleavedCsMatrix<T, C>) -> Self {
+        convert_interleaved_cs(matrix)
+    }
+}
+
+impl<'a, T, MO, MI, D, C> From<&'a CsMatrix<T, MO, MI, D, C>> for RunLengthCsMatrix<T, C>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    fn from(matrix: &'a CsMatrix<T, MO, MI, D, C>) -> Self {
+        convert_cs_run_length(matrix)
+    }
+}
+
+impl<T, C> From<RunLengthCsMatrix<T, C>> for CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C>
+where
+    T: Scalar,
+    C: Compression,
+{
+    fn from(matrix: RunLengthCsMatrix<T, C>) -> Self {
+        convert_run_length_cs(matrix)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::proptest::*;
+    use crate::{proptest::*, SparseEntry};
     use proptest::prelude::*;
 
     proptest! {
+        #[test]
+        fn csr_from_interleaved_from_csr_is_reflective(csr in csr_strategy()) {
+            let interleaved = InterleavedCsMatrix::from(&csr);
+
+            prop_assert_eq!(interleaved.shape(), csr.shape());
+            prop_assert_eq!(interleaved.nnz(), csr.nnz());
+
+            for (i, j, v) in csr.triplet_iter() {
+                prop_assert_eq!(interleaved.get_entry(i, j), csr.get_entry(i, j));
+                prop_assert_eq!(interleaved.get_entry(i, j), Some(SparseEntry::NonZero(v)));
+            }
+
+            let final_csr = CsrMatrix::from(interleaved);
+            prop_assert_eq!(final_csr.cs_data(), csr.cs_data());
+        }
+
+        #[test]
+        fn csc_from_interleaved_from_csc_is_reflective(csc in csc_strategy()) {
+            let interleaved = InterleavedCsMatrix::from(&csc);
+
+            prop_assert!(interleaved
+                .triplet_iter()
+                .zip(csc.triplet_iter())
+                .all(|(a, b)| a == b));
+
+            let final_csc = CscMatrix::from(interleaved);
+            prop_assert_eq!(final_csc.cs_data(), csc.cs_data());
+        }
+
+        #[test]
+        fn csr_from_run_length_from_csr_is_reflective(csr in csr_strategy()) {
+            let run_length = RunLengthCsMatrix::from(&csr);
+
+            prop_assert_eq!(run_length.shape(), csr.shape());
+            prop_assert_eq!(run_length.nnz(), csr.nnz());
+            prop_assert!(run_length.nruns() <= csr.nnz());
+
+            let final_csr = CsrMatrix::from(run_length);
+            prop_assert_eq!(final_csr.cs_data(), csr.cs_data());
+        }
+
+        #[test]
+        fn csc_from_run_length_from_csc_is_reflective(csc in csc_strategy()) {
+            let final_csc = CscMatrix::from(RunLengthCsMatrix::from(&csc));
+            prop_assert_eq!(final_csc.cs_data(), csc.cs_data());
+        }
+
         #[test]
         fn dense_from_coo_from_dense_is_reflective(dense in dense_strategy()) {
             let final_dense = DMatrix::from(&CooMatrix::from(&dense));
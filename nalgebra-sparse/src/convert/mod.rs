@@ -31,10 +31,13 @@
 //! ```
 //!
 //! The routines available here are able to provide more specialized APIs, giving
-//! more control over the conversion process. The routines are organized by backends.
-//! Currently, only the [`serial`] backend is available.
-//! In the future, backends that offer parallel routines may become available.
+//! more control over the conversion process. The routines are organized by backends: the
+//! [`serial`] backend is always available, and the `parallel` backend, which assembles and
+//! converts COO matrices on the `rayon` thread pool, is available when the `rayon` feature is
+//! enabled.
 
+#[cfg(feature = "rayon")]
+pub mod parallel;
 pub mod serial;
 
 mod impl_std_ops;
@@ -0,0 +1,291 @@
+//! Parallel routines for assembling and converting matrices, powered by `rayon`.
+//!
+//! This module is only available when the `rayon` feature is enabled. Assembly, e.g. of the
+//! global matrix of a finite element discretization from the matrices of its elements, is
+//! embarrassingly parallel: [`assemble_csr`] lets every thread push the triplets of its share of
+//! the work into a [`CooMatrix`] of its own, and [`merge_coo_csr`] merges such matrices straight
+//! into a CSR matrix. The merge sorts every matrix in parallel, and then compresses contiguous
+//! ranges of rows that hold roughly the same number of triplets in parallel, so no single thread
+//! ever has to touch all triplets.
+//!
+//! Like the serial conversions, duplicate triplets are summed, and explicit zeros are kept. The
+//! duplicates of an entry are summed in the order of the matrices they belong to, so the result
+//! is deterministic for a given sequence of matrices. Note however that [`assemble_csr`] splits
+//! the work between threads differently from run to run, so sums of floating-point values may
+//! differ in their last bits between runs.
+
+use super::utils::CountToOffsetIter;
+use crate::{
+    coo::{CooMatrix, CooOrder},
+    cs::{CsMatrix, CsrMatrix},
+    error::{OperationError, OperationErrorKind},
+    ops::parallel::spmm::balanced_lanes,
+};
+use nalgebra::Scalar;
+use rayon::prelude::*;
+use std::ops::Add;
+
+/// Assembles a CSR matrix from the triplets that `assemble` pushes for every item, in parallel.
+///
+/// Every thread processes a share of `items`, and passes each of them to `assemble` together
+/// with an `nrows × ncols` [`CooMatrix`] that only this thread pushes to. The matrices of all
+/// threads are then merged with [`merge_coo_csr`].
+///
+/// # Panics
+///
+/// Panics if `assemble` panics, e.g. because it pushes a triplet that is out of bounds, or if it
+/// replaces the matrix it is given with one of a different shape.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::{DMatrix, Matrix2};
+/// use nalgebra_sparse::convert::parallel::assemble_csr;
+///
+/// // The stiffness matrix of 99 linear elements on the unit interval.
+/// let n = 100;
+/// let h = 1.0 / (n - 1) as f64;
+/// let element = Matrix2::new(1.0, -1.0, -1.0, 1.0) / h;
+///
+/// let stiffness = assemble_csr(n, n, 0..n - 1, |coo, e| coo.push_matrix(e, e, &element));
+///
+/// assert_eq!(stiffness.nnz(), 3 * n - 2);
+/// assert!(DMatrix::from(&stiffness).row_sum().amax() < 1e-9);
+/// ```
+pub fn assemble_csr<T, I, F>(nrows: usize, ncols: usize, items: I, assemble: F) -> CsrMatrix<T>
+where
+    T: Scalar + Add<Output = T> + Send + Sync,
+    I: IntoParallelIterator,
+    F: Fn(&mut CooMatrix<T>, I::Item) + Sync + Send,
+{
+    let _span = span!("parallel_assemble_csr", nrows = nrows, ncols = ncols);
+
+    let parts = items
+        .into_par_iter()
+        .fold(
+            || CooMatrix::new(nrows, ncols),
+            |mut coo, item| {
+                assemble(&mut coo, item);
+                coo
+            },
+        )
+        .collect();
+
+    merge_coo_csr(nrows, ncols, parts)
+        .expect("The assembly must not change the shape of the matrices it is given")
+}
+
+/// Merges several [`CooMatrix`] of the same shape into a single CSR matrix, in parallel.
+///
+/// The result is the sum of all matrices, and equals the serial conversion of a single
+/// [`CooMatrix`] that holds the triplets of all of them, in order.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if any of the matrices is not `nrows × ncols`.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra_sparse::{coo::CooMatrix, convert::parallel::merge_coo_csr};
+///
+/// let mut first = CooMatrix::new(2, 3);
+/// first.push(1, 2, 1);
+/// first.push(0, 0, 2);
+///
+/// let mut second = CooMatrix::new(2, 3);
+/// second.push(1, 2, 3);
+///
+/// let csr = merge_coo_csr(2, 3, vec![first, second]).unwrap();
+///
+/// assert_eq!(csr.get_entry(0, 0).unwrap().into_value(), 2);
+/// assert_eq!(csr.get_entry(1, 2).unwrap().into_value(), 4);
+/// assert_eq!(csr.nnz(), 2);
+/// ```
+pub fn merge_coo_csr<T>(
+    nrows: usize,
+    ncols: usize,
+    mut parts: Vec<CooMatrix<T>>,
+) -> Result<CsrMatrix<T>, OperationError>
+where
+    T: Scalar + Add<Output = T> + Send + Sync,
+{
+    if parts
+        .iter()
+        .any(|coo| coo.nrows() != nrows || coo.ncols() != ncols)
+    {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("All matrices must have the shape of the merged matrix"),
+        ));
+    }
+
+    let span = span!(
+        "parallel_merge_coo_csr",
+        nrows = nrows,
+        ncols = ncols,
+        nparts = parts.len(),
+        nnz = tracing::field::Empty,
+    );
+
+    parts
+        .par_iter_mut()
+        .for_each(|coo| coo.sort(CooOrder::RowMajor));
+
+    // The number of triplets before every row, to balance the ranges of rows
+    let counts = parts
+        .par_iter()
+        .fold(
+            || vec![0; nrows],
+            |mut counts, coo| {
+                for &i in coo.row_indices() {
+                    counts[i] += 1;
+                }
+
+                counts
+            },
+        )
+        .reduce(
+            || vec![0; nrows],
+            |mut left, right| {
+                left.iter_mut().zip(right).for_each(|(l, r)| *l += r);
+                left
+            },
+        );
+
+    let total = counts.iter().sum();
+    let starts = CountToOffsetIter::new(counts).collect::<Vec<_>>();
+
+    let threads = rayon::current_num_threads();
+    let chunks = balanced_lanes(&starts, total, 4 * threads)
+        .into_par_iter()
+        .map(|range| {
+            let mut triplets = Vec::new();
+
+            for coo in &parts {
+                let rows = coo.row_indices();
+                let first = rows.partition_point(|&i| i < range.start);
+                let last = rows.partition_point(|&i| i < range.end);
+
+                triplets.extend(
+                    rows[first..last]
+                        .iter()
+                        .zip(&coo.col_indices()[first..last])
+                        .zip(&coo.values()[first..last])
+                        .map(|((&i, &j), v)| (i, j, v.clone())),
+                );
+            }
+
+            // Stable, so that duplicates are summed in the order of the matrices
+            triplets.sort_by_key(|(i, j, _)| (*i, *j));
+
+            let mut counts = vec![0; range.len()];
+            let mut indices = Vec::with_capacity(triplets.len());
+            let mut data: Vec<T> = Vec::with_capacity(triplets.len());
+            let mut previous = None;
+
+            for (i, j, v) in triplets {
+                if previous == Some((i, j)) {
+                    let last = data.last_mut().unwrap();
+                    *last = last.clone() + v;
+                } else {
+                    counts[i - range.start] += 1;
+                    indices.push(j);
+                    data.push(v);
+                    previous = Some((i, j));
+                }
+            }
+
+            (counts, indices, data)
+        })
+        .collect::<Vec<_>>();
+
+    let nnz = chunks.iter().map(|(_, indices, _)| indices.len()).sum();
+    let mut counts = Vec::with_capacity(nrows);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+
+    for (chunk_counts, chunk_indices, chunk_data) in chunks {
+        counts.extend(chunk_counts);
+        indices.extend(chunk_indices);
+        data.extend(chunk_data);
+    }
+
+    record!(span, nnz = nnz);
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+
+    Ok(unsafe { CsMatrix::from_parts_unchecked(nrows, ncols, offsets, indices, data) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{convert::serial::convert_coo_csr, proptest::coo_with_duplicates};
+    use matrixcompare::prop_assert_matrix_eq;
+    use proptest::prelude::*;
+
+    #[test]
+    fn merge_coo_csr_rejects_mismatched_shapes() {
+        let parts = vec![CooMatrix::<i32>::new(2, 3), CooMatrix::new(3, 2)];
+        let err = merge_coo_csr(2, 3, parts).unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn merge_coo_csr_handles_empty_inputs() {
+        let csr = merge_coo_csr::<i32>(4, 3, Vec::new()).unwrap();
+        assert_eq!(csr.cs_data(), CsrMatrix::zeros(4, 3).cs_data());
+        assert_eq!(csr.shape(), (4, 3));
+
+        let csr = merge_coo_csr::<i32>(0, 0, vec![CooMatrix::new(0, 0)]).unwrap();
+        assert_eq!(csr.shape(), (0, 0));
+    }
+
+    #[test]
+    fn assemble_csr_sums_overlapping_blocks() {
+        // Every item adds a 3 × 3 block of ones on the diagonal, overlapping its neighbours.
+        let csr = assemble_csr(101, 101, (0..50).into_par_iter(), |coo, item: usize| {
+            for i in 2 * item..2 * item + 3 {
+                for j in 2 * item..2 * item + 3 {
+                    coo.push(i, j, 1);
+                }
+            }
+        });
+
+        let mut expected = CooMatrix::new(101, 101);
+
+        for item in 0..50 {
+            for i in 2 * item..2 * item + 3 {
+                for j in 2 * item..2 * item + 3 {
+                    expected.push(i, j, 1);
+                }
+            }
+        }
+
+        assert_eq!(csr.cs_data(), convert_coo_csr(expected).cs_data());
+    }
+
+    proptest! {
+        #[test]
+        fn merge_coo_csr_agrees_with_serial_conversion(
+            coo in coo_with_duplicates(-5..5i32, 0..=12, 0..=12, 60, 4),
+            nparts in 1..5usize,
+        ) {
+            let (nrows, ncols) = (coo.nrows(), coo.ncols());
+            let mut parts = vec![CooMatrix::new(nrows, ncols); nparts];
+
+            for (k, (i, j, v)) in coo.triplet_iter().enumerate() {
+                parts[k % nparts].push(i, j, *v);
+            }
+
+            let merged = merge_coo_csr(nrows, ncols, parts).unwrap();
+            let serial = convert_coo_csr(coo);
+
+            prop_assert_matrix_eq!(merged, serial);
+            prop_assert_eq!(merged.pattern(), serial.pattern());
+        }
+    }
+}
@@ -5,10 +5,14 @@
 //! control to the user.
 use super::utils;
 use crate::{
+    control::{Control, Stage},
     coo::CooMatrix,
     cs::{
         CompressedColumnStorage, CompressedRowStorage, Compression, CsMatrix, CscMatrix, CsrMatrix,
     },
+    error::{OperationError, OperationErrorKind},
+    interleaved::InterleavedCsMatrix,
+    runlength::{find_runs, RunLengthCsMatrix},
 };
 use nalgebra::{ClosedAdd, DMatrix, Dim, Matrix, RawStorage, Scalar};
 use num_traits::Zero;
@@ -53,7 +57,27 @@ pub fn convert_coo_csr<T>(coo: CooMatrix<T>) -> CsrMatrix<T>
 where
     T: Scalar + Add<Output = T>,
 {
-    convert_coo_cs(coo, &Add::add)
+    convert_coo_cs(coo, &Add::add, &Control::default())
+        .expect("The default control never requests a stop.")
+}
+
+/// Converts a [`CooMatrix`] to a [`CsrMatrix`], stopping early if `control` requests it.
+///
+/// `control` is checked, and progress is reported, once per lane of the output matrix.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::Cancelled`] if `control` requested a stop before the conversion was
+/// complete.
+pub fn convert_coo_csr_with_control<T>(
+    coo: CooMatrix<T>,
+    control: &Control<'_>,
+) -> Result<CsrMatrix<T>, OperationError>
+where
+    T: Scalar + Add<Output = T>,
+{
+    convert_coo_cs(coo, &Add::add, control)
 }
 
 /// Converts a [`CsrMatrix`] to a [`CooMatrix`].
@@ -132,7 +156,27 @@ pub fn convert_coo_csc<T>(coo: CooMatrix<T>) -> CscMatrix<T>
 where
     T: Scalar + Add<Output = T>,
 {
-    convert_coo_cs(coo, &Add::add)
+    convert_coo_cs(coo, &Add::add, &Control::default())
+        .expect("The default control never requests a stop.")
+}
+
+/// Converts a [`CooMatrix`] to a [`CscMatrix`], stopping early if `control` requests it.
+///
+/// `control` is checked, and progress is reported, once per lane of the output matrix.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::Cancelled`] if `control` requested a stop before the conversion was
+/// complete.
+pub fn convert_coo_csc_with_control<T>(
+    coo: CooMatrix<T>,
+    control: &Control<'_>,
+) -> Result<CscMatrix<T>, OperationError>
+where
+    T: Scalar + Add<Output = T>,
+{
+    convert_coo_cs(coo, &Add::add, control)
 }
 
 /// Converts a [`CscMatrix`] to a [`CooMatrix`].
@@ -215,6 +259,12 @@ where
     D: Borrow<[T]>,
 {
     let (nrows, ncols) = csr.shape();
+    let _span = span!(
+        "convert_csr_csc",
+        nrows = nrows,
+        ncols = ncols,
+        nnz = csr.nnz()
+    );
 
     let (counts, indices_and_data) = csr
         .minor_lane_iter()
@@ -252,6 +302,12 @@ where
     D: Borrow<[T]>,
 {
     let (nrows, ncols) = csc.shape();
+    let _span = span!(
+        "convert_csc_csr",
+        nrows = nrows,
+        ncols = ncols,
+        nnz = csc.nnz()
+    );
 
     let (counts, indices_and_data) = csc
         .minor_lane_iter()
@@ -278,11 +334,141 @@ where
     unsafe { CsrMatrix::from_parts_unchecked(nrows, ncols, offsets, indices, data) }
 }
 
+/// Converts a [`CsMatrix`] to an [`InterleavedCsMatrix`] with the same compression.
+pub fn convert_cs_interleaved<T, MO, MI, D, C>(
+    cs: &CsMatrix<T, MO, MI, D, C>,
+) -> InterleavedCsMatrix<T, C>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let (nrows, ncols) = cs.shape();
+    let _span = span!(
+        "convert_cs_interleaved",
+        nrows = nrows,
+        ncols = ncols,
+        nnz = cs.nnz()
+    );
+
+    let (offsets, indices, data) = cs.cs_data();
+
+    let entries = indices
+        .iter()
+        .copied()
+        .zip(data.iter().cloned())
+        .collect::<Vec<_>>();
+
+    unsafe { InterleavedCsMatrix::from_parts_unchecked(nrows, ncols, offsets.to_vec(), entries) }
+}
+
+/// Converts an [`InterleavedCsMatrix`] to a [`CsMatrix`] with the same compression.
+pub fn convert_interleaved_cs<T, C>(
+    interleaved: InterleavedCsMatrix<T, C>,
+) -> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C>
+where
+    T: Scalar,
+    C: Compression,
+{
+    let (nrows, ncols) = interleaved.shape();
+    let _span = span!(
+        "convert_interleaved_cs",
+        nrows = nrows,
+        ncols = ncols,
+        nnz = interleaved.nnz()
+    );
+
+    let (offsets, entries) = interleaved.disassemble();
+    let (indices, data) = entries.into_iter().unzip::<_, _, Vec<_>, Vec<_>>();
+
+    unsafe { CsMatrix::from_parts_unchecked(nrows, ncols, offsets, indices, data) }
+}
+
+/// Converts a [`CsMatrix`] to a [`RunLengthCsMatrix`] with the same compression.
+pub fn convert_cs_run_length<T, MO, MI, D, C>(
+    cs: &CsMatrix<T, MO, MI, D, C>,
+) -> RunLengthCsMatrix<T, C>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let (nrows, ncols) = cs.shape();
+    let _span = span!(
+        "convert_cs_run_length",
+        nrows = nrows,
+        ncols = ncols,
+        nnz = cs.nnz(),
+        nruns = tracing::field::Empty
+    );
+
+    let (offsets, indices, data) = cs.cs_data();
+
+    let mut run_offsets = Vec::with_capacity(offsets.len());
+    let mut runs = Vec::new();
+
+    for (major_index, &offset) in offsets.iter().enumerate() {
+        let upper = offsets
+            .get(major_index + 1)
+            .copied()
+            .unwrap_or(indices.len());
+
+        run_offsets.push(runs.len());
+        find_runs(&indices[offset..upper], &mut runs);
+    }
+
+    record!(_span, nruns = runs.len());
+
+    unsafe {
+        RunLengthCsMatrix::from_parts_unchecked(
+            nrows,
+            ncols,
+            offsets.to_vec(),
+            run_offsets,
+            runs,
+            data.to_vec(),
+        )
+    }
+}
+
+/// Converts a [`RunLengthCsMatrix`] to a [`CsMatrix`] with the same compression.
+pub fn convert_run_length_cs<T, C>(
+    run_length: RunLengthCsMatrix<T, C>,
+) -> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C>
+where
+    T: Scalar,
+    C: Compression,
+{
+    let (nrows, ncols) = run_length.shape();
+    let _span = span!(
+        "convert_run_length_cs",
+        nrows = nrows,
+        ncols = ncols,
+        nnz = run_length.nnz()
+    );
+
+    let (offsets, _, runs, data) = run_length.disassemble();
+
+    let indices = runs
+        .into_iter()
+        .flat_map(|(start, len)| start..start + len)
+        .collect();
+
+    unsafe { CsMatrix::from_parts_unchecked(nrows, ncols, offsets, indices, data) }
+}
+
 /// Converts a COO matrix to a CsMatrix, resolving duplicates with the provided combinator.
+///
+/// `control` is checked, and progress is reported, once per major lane of the output.
 fn convert_coo_cs<T, C, F>(
     coo: CooMatrix<T>,
     combinator: F,
-) -> CsMatrix<F::Output, Vec<usize>, Vec<usize>, Vec<F::Output>, C>
+    control: &Control<'_>,
+) -> Result<CsMatrix<F::Output, Vec<usize>, Vec<usize>, Vec<F::Output>, C>, OperationError>
 where
     T: Scalar,
     C: Compression,
@@ -293,6 +479,14 @@ where
 
     let nmajor = C::nmajor(nrows, ncols);
 
+    let span = span!(
+        "convert_coo_cs",
+        nrows = nrows,
+        ncols = ncols,
+        coo_nnz = coo.nnz(),
+        nnz = tracing::field::Empty,
+    );
+
     let (coo_rows, coo_cols, coo_data) = coo.disassemble();
 
     let mut triplets = coo_rows
@@ -331,8 +525,25 @@ where
     let mut data = Vec::<T>::with_capacity(triplets.len());
 
     let mut i_prev = None;
+    let mut next_lane = 0;
+
+    let checkpoint = |lane| {
+        if control.checkpoint(Stage::Conversion, lane, nmajor) {
+            Err(OperationError::from_kind_and_message(
+                OperationErrorKind::Cancelled,
+                String::from("The conversion was cancelled before completion."),
+            ))
+        } else {
+            Ok(())
+        }
+    };
 
     for ((i, j), val) in triplets {
+        while next_lane <= i {
+            checkpoint(next_lane)?;
+            next_lane += 1;
+        }
+
         // This checks for duplicates, and resolves them with the appropriate combinator.
         //
         // We can check for duplicates merely by seeing if the last i and j are the same, since we
@@ -358,9 +569,18 @@ where
         i_prev = Some(i);
     }
 
+    // Lanes after the last triplet are empty, but are still checked and reported
+    while next_lane < nmajor {
+        checkpoint(next_lane)?;
+        next_lane += 1;
+    }
+
+    control.report(Stage::Conversion, nmajor, nmajor);
+    record!(span, nnz = indices.len());
+
     let offsets = utils::CountToOffsetIter::new(counts).collect();
 
-    unsafe { CsMatrix::from_parts_unchecked(nrows, ncols, offsets, indices, data) }
+    Ok(unsafe { CsMatrix::from_parts_unchecked(nrows, ncols, offsets, indices, data) })
 }
 
 #[cfg(test)]
@@ -370,6 +590,45 @@ mod tests {
     use matrixcompare::{assert_matrix_eq, prop_assert_matrix_eq};
     use nalgebra::SMatrix;
     use proptest::prelude::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn coo_to_csr_with_control_reports_progress_for_every_lane() {
+        // The last row is empty
+        let mut coo = CooMatrix::new(5, 3);
+        coo.push(0, 0, 1);
+        coo.push(3, 2, 2);
+        coo.push(3, 2, 3);
+
+        let reports = RefCell::new(Vec::new());
+        let progress =
+            |progress: crate::control::Progress| reports.borrow_mut().push(progress.completed);
+        let control = Control::new().with_progress(&progress);
+
+        let csr = convert_coo_csr_with_control(coo.clone(), &control).unwrap();
+
+        assert_matrix_eq!(csr, convert_coo_csr(coo));
+        assert_eq!(reports.into_inner(), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn coo_to_csc_with_cancelled_control_fails_with_cancelled() {
+        let mut coo = CooMatrix::new(2, 2);
+        coo.push(1, 1, 1.0);
+
+        let should_stop = || true;
+        let control = Control::new().with_should_stop(&should_stop);
+
+        let error = convert_coo_csc_with_control(coo, &control).unwrap_err();
+
+        assert!(matches!(error.kind(), OperationErrorKind::Cancelled));
+
+        // Without any triplets, only the checkpoints of the empty lanes can stop the conversion.
+        let error =
+            convert_coo_csc_with_control(CooMatrix::<f64>::new(2, 2), &control).unwrap_err();
+
+        assert!(matches!(error.kind(), OperationErrorKind::Cancelled));
+    }
 
     #[test]
     fn coo_from_dense_and_dense_from_coo_are_symmetric() {
@@ -1,6 +1,19 @@
 //! An implementation of the COO sparse matrix format.
 
-use super::error::SparseFormatError;
+use super::error::{SparseFormatError, SparseFormatErrorKind};
+use crate::cs::{Compression, CsMatrix};
+use std::borrow::Borrow;
+use std::iter::FromIterator;
+
+/// The order of the triplets of a COO matrix after [sorting](CooMatrix::sort).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CooOrder {
+    /// Sorts by row index, and then by column index within every row.
+    RowMajor,
+
+    /// Sorts by column index, and then by row index within every column.
+    ColumnMajor,
+}
 
 /// A COO representation of a sparse matrix.
 ///
@@ -45,40 +58,183 @@ pub struct CooMatrix<T> {
     values: Vec<T>,
 }
 
+/// A block of triplets that can be pushed into a [`CooMatrix`] with
+/// [`push_matrix`](CooMatrix::push_matrix).
+///
+/// This is implemented for dense matrices, for CSR and CSC matrices and for COO matrices, so
+/// that block systems can be assembled from sub-blocks in any of these formats without
+/// converting them first.
+pub trait CooBlock<T> {
+    /// The number of rows and columns of the block.
+    fn block_shape(&self) -> (usize, usize);
+
+    /// The number of triplets that pushing the block adds.
+    fn block_nnz(&self) -> usize;
+
+    /// Calls `f(i, j, v)` for every triplet of the block, with indices relative to the block.
+    fn for_each_triplet<F: FnMut(usize, usize, T)>(&self, f: F);
+}
+
+/// Every entry of a dense block is pushed, including zeros, in column-major order.
+impl<T, R, C, S> CooBlock<T> for na::Matrix<T, R, C, S>
+where
+    T: na::Scalar,
+    R: na::Dim,
+    C: na::Dim,
+    S: na::RawStorage<T, R, C>,
+{
+    fn block_shape(&self) -> (usize, usize) {
+        self.shape()
+    }
+
+    fn block_nnz(&self) -> usize {
+        self.len()
+    }
+
+    fn for_each_triplet<F: FnMut(usize, usize, T)>(&self, mut f: F) {
+        for (col_idx, col) in self.column_iter().enumerate() {
+            for (row_idx, v) in col.iter().enumerate() {
+                f(row_idx, col_idx, v.clone());
+            }
+        }
+    }
+}
+
+/// The explicit entries of a compressed block are pushed in the order in which they are stored.
+impl<T, MO, MI, D, Comp> CooBlock<T> for CsMatrix<T, MO, MI, D, Comp>
+where
+    T: na::Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    Comp: Compression,
+{
+    fn block_shape(&self) -> (usize, usize) {
+        self.shape()
+    }
+
+    fn block_nnz(&self) -> usize {
+        self.nnz()
+    }
+
+    fn for_each_triplet<F: FnMut(usize, usize, T)>(&self, mut f: F) {
+        for (major, minor, v) in self.triplet_iter() {
+            f(
+                Comp::nmajor(major, minor),
+                Comp::nminor(major, minor),
+                v.clone(),
+            );
+        }
+    }
+}
+
+/// The triplets of a COO block are pushed as they are, including any duplicates.
+impl<T: na::Scalar> CooBlock<T> for CooMatrix<T> {
+    fn block_shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    fn block_nnz(&self) -> usize {
+        self.nnz()
+    }
+
+    fn for_each_triplet<F: FnMut(usize, usize, T)>(&self, mut f: F) {
+        for (i, j, v) in self.triplet_iter() {
+            f(i, j, v.clone());
+        }
+    }
+}
+
 impl<T: na::Scalar> CooMatrix<T> {
-    /// Pushes a dense matrix into the sparse one.
+    /// Pushes a dense or sparse block into the sparse one.
+    ///
+    /// This adds the block `m` starting at the `r`th row and `c`th column to the matrix, i.e. every
+    /// triplet `(i, j, v)` of `m` is pushed as `(r + i, c + j, v)`. Dense blocks push all of their
+    /// entries, while CSR, CSC and COO blocks only push their explicitly stored entries. See
+    /// [`CooBlock`] for details.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use nalgebra::DMatrix;
+    /// # use nalgebra_sparse::{coo::CooMatrix, cs::CsrMatrix};
+    /// let mut coo = CooMatrix::new(4, 4);
     ///
-    /// This adds the dense matrix `m` starting at the `r`th row and `c`th column
-    /// to the matrix.
+    /// // Assemble a block-diagonal matrix from a dense and a sparse block.
+    /// coo.push_matrix(0, 0, &DMatrix::from_element(2, 2, 1.0));
+    /// coo.push_matrix(2, 2, &CsrMatrix::<f64>::identity(2));
+    ///
+    /// assert_eq!(coo.nnz(), 6);
+    /// assert_eq!(coo.triplet_iter().last(), Some((3, 3, &1.0)));
+    /// ```
     ///
     /// Panics
     /// ------
     ///
-    /// Panics if any part of the dense matrix is out of bounds of the sparse matrix
-    /// when inserted at `(r, c)`.
+    /// Panics if any part of the block is out of bounds of the sparse matrix when inserted at
+    /// `(r, c)`.
     #[inline]
-    pub fn push_matrix<R: na::Dim, C: na::Dim, S: nalgebra::storage::RawStorage<T, R, C>>(
+    pub fn push_matrix<M: CooBlock<T> + ?Sized>(&mut self, r: usize, c: usize, m: &M) {
+        let (block_nrows, block_ncols) = m.block_shape();
+        assert!(r + block_nrows <= self.nrows);
+        assert!(c + block_ncols <= self.ncols);
+
+        self.reserve(m.block_nnz());
+
+        m.for_each_triplet(|i, j, v| {
+            self.row_indices.push(r + i);
+            self.col_indices.push(c + j);
+            self.values.push(v);
+        });
+    }
+
+    /// Pushes a dense or sparse block into the sparse one, or returns an error if it does not fit.
+    ///
+    /// This is the non-panicking variant of [`push_matrix`](Self::push_matrix). Returns an error
+    /// with kind [`IndexOutOfBounds`](SparseFormatErrorKind::IndexOutOfBounds), and leaves
+    /// the matrix unchanged, if any part of the block is out of bounds of the sparse matrix when
+    /// inserted at `(r, c)`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use nalgebra::DMatrix;
+    /// # use nalgebra_sparse::coo::CooMatrix;
+    /// let mut coo = CooMatrix::new(3, 3);
+    /// let block = DMatrix::from_element(2, 2, 1.0);
+    ///
+    /// assert!(coo.try_push_matrix(1, 1, &block).is_ok());
+    /// assert!(coo.try_push_matrix(2, 0, &block).is_err());
+    /// assert_eq!(coo.nnz(), 4);
+    /// ```
+    pub fn try_push_matrix<M: CooBlock<T> + ?Sized>(
         &mut self,
         r: usize,
         c: usize,
-        m: &na::Matrix<T, R, C, S>,
-    ) {
-        let block_nrows = m.nrows();
-        let block_ncols = m.ncols();
-        let max_row_with_block = r + block_nrows - 1;
-        let max_col_with_block = c + block_ncols - 1;
-        assert!(max_row_with_block < self.nrows);
-        assert!(max_col_with_block < self.ncols);
-
-        self.reserve(block_ncols * block_nrows);
-
-        for (col_idx, col) in m.column_iter().enumerate() {
-            for (row_idx, v) in col.iter().enumerate() {
-                self.row_indices.push(r + row_idx);
-                self.col_indices.push(c + col_idx);
-                self.values.push(v.clone());
-            }
+        m: &M,
+    ) -> Result<(), SparseFormatError> {
+        let (block_nrows, block_ncols) = m.block_shape();
+
+        if r.checked_add(block_nrows)
+            .is_none_or(|end| end > self.nrows)
+        {
+            return Err(SparseFormatError::from_kind_and_msg(
+                SparseFormatErrorKind::IndexOutOfBounds,
+                "Block rows out of bounds.",
+            ));
+        } else if c
+            .checked_add(block_ncols)
+            .is_none_or(|end| end > self.ncols)
+        {
+            return Err(SparseFormatError::from_kind_and_msg(
+                SparseFormatErrorKind::IndexOutOfBounds,
+                "Block cols out of bounds.",
+            ));
         }
+
+        self.push_matrix(r, c, m);
+
+        Ok(())
     }
 }
 
@@ -107,6 +263,38 @@ impl<T> CooMatrix<T> {
         Self::new(nrows, ncols)
     }
 
+    /// Construct a zero COO matrix of the given dimensions, with room for at least `capacity`
+    /// triplets.
+    ///
+    /// The matrix is empty, just like one obtained with [`new`](Self::new), but triplets can be
+    /// pushed until `capacity` is reached without reallocating any of the triplet arrays. This is
+    /// useful in e.g. finite element assembly, where an upper bound on the number of triplets is
+    /// usually known up front.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if any of the individual allocation of triplet arrays fails.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use nalgebra_sparse::coo::CooMatrix;
+    /// let mut coo = CooMatrix::with_capacity(4, 4, 10);
+    /// assert_eq!(coo.nnz(), 0);
+    /// assert!(coo.capacity() >= 10);
+    ///
+    /// coo.push(1, 0, 3.0);
+    /// ```
+    pub fn with_capacity(nrows: usize, ncols: usize, capacity: usize) -> Self {
+        Self {
+            nrows,
+            ncols,
+            row_indices: Vec::with_capacity(capacity),
+            col_indices: Vec::with_capacity(capacity),
+            values: Vec::with_capacity(capacity),
+        }
+    }
+
     /// Try to construct a COO matrix from the given dimensions and a collection of
     /// (i, j, v) triplets.
     ///
@@ -192,6 +380,36 @@ impl<T> CooMatrix<T> {
         self.values.reserve(additional);
     }
 
+    /// Shrinks the capacity of the triplet arrays as much as possible.
+    ///
+    /// This releases the memory that was reserved for, but not used by, the triplets, e.g. after
+    /// assembling with a generous [`with_capacity`](Self::with_capacity) estimate.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use nalgebra_sparse::coo::CooMatrix;
+    /// let mut coo = CooMatrix::with_capacity(4, 4, 100);
+    /// coo.push(1, 0, 3.0);
+    /// coo.shrink_to_fit();
+    /// assert!(coo.capacity() < 100);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.row_indices.shrink_to_fit();
+        self.col_indices.shrink_to_fit();
+        self.values.shrink_to_fit();
+    }
+
+    /// The number of triplets the matrix can hold without reallocating.
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.row_indices
+            .capacity()
+            .min(self.col_indices.capacity())
+            .min(self.values.capacity())
+    }
+
     /// Push a single triplet to the matrix.
     ///
     /// This adds the value `v` to the `i`th row and `j`th column in the matrix.
@@ -209,6 +427,314 @@ impl<T> CooMatrix<T> {
         self.values.push(v);
     }
 
+    /// Push a single triplet to the matrix, or return an error if it is out of bounds.
+    ///
+    /// This is the non-panicking variant of [`push`](Self::push), for triplets from untrusted
+    /// sources such as files. Returns an error with kind
+    /// [`IndexOutOfBounds`](SparseFormatErrorKind::IndexOutOfBounds), and leaves the
+    /// matrix unchanged, if `i` or `j` is out of bounds.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use nalgebra_sparse::{coo::CooMatrix, error::SparseFormatErrorKind};
+    /// let mut coo = CooMatrix::new(2, 2);
+    /// assert!(coo.try_push(1, 0, 1.0).is_ok());
+    ///
+    /// let err = coo.try_push(2, 0, 2.0).unwrap_err();
+    /// assert_eq!(err.kind(), &SparseFormatErrorKind::IndexOutOfBounds);
+    /// assert_eq!(coo.nnz(), 1);
+    /// ```
+    #[inline]
+    pub fn try_push(&mut self, i: usize, j: usize, v: T) -> Result<(), SparseFormatError> {
+        self.check_bounds(i, j)?;
+        self.row_indices.push(i);
+        self.col_indices.push(j);
+        self.values.push(v);
+
+        Ok(())
+    }
+
+    /// Push every triplet `(i, j, v)` of an iterator to the matrix, or return an error if any of
+    /// them is out of bounds.
+    ///
+    /// This is the non-panicking variant of [`Extend::extend`]. The triplets are validated as
+    /// they are pushed, so the iterator is only traversed once. If any triplet is out of bounds,
+    /// the triplets pushed before it are removed again, the matrix is left unchanged and an error
+    /// with kind [`IndexOutOfBounds`](SparseFormatErrorKind::IndexOutOfBounds) is
+    /// returned. The remaining triplets of the iterator are not consumed in that case.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use nalgebra_sparse::coo::CooMatrix;
+    /// let mut coo = CooMatrix::new(2, 2);
+    /// coo.try_extend(vec![(0, 0, 1.0), (1, 1, 2.0)]).unwrap();
+    ///
+    /// assert!(coo.try_extend(vec![(0, 1, 3.0), (0, 2, 4.0)]).is_err());
+    /// assert_eq!(coo.nnz(), 2);
+    /// ```
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), SparseFormatError>
+    where
+        I: IntoIterator<Item = (usize, usize, T)>,
+    {
+        let iter = iter.into_iter();
+        let nnz = self.nnz();
+        self.reserve(iter.size_hint().0);
+
+        for (i, j, v) in iter {
+            if let Err(err) = self.try_push(i, j, v) {
+                self.row_indices.truncate(nnz);
+                self.col_indices.truncate(nnz);
+                self.values.truncate(nnz);
+
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Push a batch of triplets to the matrix.
+    ///
+    /// The triplets `(rows[k], cols[k], values[k])` are appended in order. The lengths and the
+    /// bounds of all indices are checked once for the whole batch, before anything is pushed,
+    /// which makes this considerably cheaper than calling [`push`](Self::push) per triplet
+    /// when assembling large matrices.
+    ///
+    /// Returns an error, and leaves the matrix unchanged, if the three arrays do not have the
+    /// same length or any index is out of bounds.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use nalgebra_sparse::coo::CooMatrix;
+    /// let mut coo = CooMatrix::new(3, 3);
+    /// coo.push_triplets(&[0, 2], &[1, 0], vec![1.0, 2.0]).unwrap();
+    /// assert_eq!(coo.nnz(), 2);
+    ///
+    /// // Nothing is pushed if any triplet is invalid.
+    /// assert!(coo.push_triplets(&[1, 3], &[1, 1], vec![3.0, 4.0]).is_err());
+    /// assert_eq!(coo.nnz(), 2);
+    /// ```
+    pub fn push_triplets(
+        &mut self,
+        rows: &[usize],
+        cols: &[usize],
+        mut values: Vec<T>,
+    ) -> Result<(), SparseFormatError> {
+        use crate::error::SparseFormatErrorKind::*;
+
+        if rows.len() != cols.len() {
+            return Err(SparseFormatError::from_kind_and_msg(
+                InvalidStructure,
+                "Number of row and col indices must be the same.",
+            ));
+        } else if cols.len() != values.len() {
+            return Err(SparseFormatError::from_kind_and_msg(
+                InvalidStructure,
+                "Number of col indices and values must be the same.",
+            ));
+        }
+
+        if rows.iter().any(|i| *i >= self.nrows) {
+            return Err(SparseFormatError::from_kind_and_msg(
+                IndexOutOfBounds,
+                "Row index out of bounds.",
+            ));
+        } else if cols.iter().any(|j| *j >= self.ncols) {
+            return Err(SparseFormatError::from_kind_and_msg(
+                IndexOutOfBounds,
+                "Col index out of bounds.",
+            ));
+        }
+
+        self.row_indices.extend_from_slice(rows);
+        self.col_indices.extend_from_slice(cols);
+        self.values.append(&mut values);
+
+        Ok(())
+    }
+
+    /// Checks that `(i, j)` is within the bounds of the matrix.
+    fn check_bounds(&self, i: usize, j: usize) -> Result<(), SparseFormatError> {
+        if i >= self.nrows {
+            Err(SparseFormatError::from_kind_and_msg(
+                SparseFormatErrorKind::IndexOutOfBounds,
+                "Row index out of bounds.",
+            ))
+        } else if j >= self.ncols {
+            Err(SparseFormatError::from_kind_and_msg(
+                SparseFormatErrorKind::IndexOutOfBounds,
+                "Col index out of bounds.",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Keeps only the triplets `(i, j, v)` for which `keep` returns `true`, and returns the number
+    /// of removed triplets.
+    ///
+    /// The triplets are compacted in place in a single pass, preserving their order. Duplicate
+    /// triplets are tested individually.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use nalgebra_sparse::coo::CooMatrix;
+    /// let mut coo = CooMatrix::try_from_triplets(3, 3, vec![0, 1, 2], vec![1, 1, 0], vec![1, 2, 3])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(coo.retain(|i, _, _| i != 1), 1);
+    /// assert_eq!(coo.row_indices(), &[0, 2]);
+    /// ```
+    pub fn retain<F>(&mut self, mut keep: F) -> usize
+    where
+        F: FnMut(usize, usize, &T) -> bool,
+    {
+        let nnz = self.values.len();
+        let mut write = 0;
+
+        for read in 0..nnz {
+            if keep(
+                self.row_indices[read],
+                self.col_indices[read],
+                &self.values[read],
+            ) {
+                self.row_indices[write] = self.row_indices[read];
+                self.col_indices[write] = self.col_indices[read];
+                self.values.swap(write, read);
+                write += 1;
+            }
+        }
+
+        self.row_indices.truncate(write);
+        self.col_indices.truncate(write);
+        self.values.truncate(write);
+
+        nnz - write
+    }
+
+    /// Sorts the triplets by their indices, in the given order.
+    ///
+    /// The sort is stable, so duplicate triplets keep their relative order. Sorting does not
+    /// change the matrix that is represented, only the order in which its triplets are stored.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use nalgebra_sparse::coo::{CooMatrix, CooOrder};
+    /// let mut coo = CooMatrix::try_from_triplets(2, 2, vec![1, 0, 0], vec![0, 1, 0], vec![1, 2, 3])
+    ///     .unwrap();
+    ///
+    /// coo.sort(CooOrder::RowMajor);
+    /// assert_eq!(coo.values(), &[3, 2, 1]);
+    ///
+    /// coo.sort(CooOrder::ColumnMajor);
+    /// assert_eq!(coo.values(), &[3, 1, 2]);
+    /// ```
+    pub fn sort(&mut self, order: CooOrder) {
+        let mut triplets = self.take_triplets();
+
+        match order {
+            CooOrder::RowMajor => triplets.sort_by_key(|(i, j, _)| (*i, *j)),
+            CooOrder::ColumnMajor => triplets.sort_by_key(|(i, j, _)| (*j, *i)),
+        }
+
+        self.put_triplets(triplets);
+    }
+
+    /// Sums duplicate triplets into a single triplet, and returns the number of removed
+    /// triplets.
+    ///
+    /// This is [`combine_duplicates`](Self::combine_duplicates) with addition as the
+    /// combinator, and likewise leaves the triplets sorted in row-major order.
+    pub fn sum_duplicates(&mut self) -> usize
+    where
+        T: std::ops::Add<Output = T>,
+    {
+        self.combine_duplicates(|a, b| a + b)
+    }
+
+    /// Combines duplicate triplets into a single triplet with the provided combinator, and
+    /// returns the number of removed triplets.
+    ///
+    /// The triplets are sorted in row-major order first, see [`sort`](Self::sort). The values of
+    /// each set of duplicates are then folded with `combinator` in the order in which they were
+    /// stored, e.g. `combinator(combinator(a, b), c)` for three duplicates `a`, `b` and `c`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use nalgebra_sparse::coo::CooMatrix;
+    /// let mut coo = CooMatrix::try_from_triplets(
+    ///     2,
+    ///     2,
+    ///     vec![1, 0, 1, 1],
+    ///     vec![1, 0, 1, 1],
+    ///     vec![1, 2, 3, 4],
+    /// )
+    /// .unwrap();
+    ///
+    /// // Keep the last value stored for every entry.
+    /// assert_eq!(coo.combine_duplicates(|_, later| later), 2);
+    /// assert_eq!(coo.row_indices(), &[0, 1]);
+    /// assert_eq!(coo.values(), &[2, 4]);
+    /// ```
+    pub fn combine_duplicates<F>(&mut self, mut combinator: F) -> usize
+    where
+        F: FnMut(T, T) -> T,
+    {
+        let nnz = self.nnz();
+        let mut triplets = self.take_triplets();
+        triplets.sort_by_key(|(i, j, _)| (*i, *j));
+
+        let mut combined: Vec<(usize, usize, T)> = Vec::with_capacity(nnz);
+
+        for (i, j, v) in triplets {
+            match combined.pop() {
+                Some((i_prev, j_prev, v_prev)) if (i_prev, j_prev) == (i, j) => {
+                    combined.push((i, j, combinator(v_prev, v)));
+                }
+                Some(previous) => {
+                    combined.push(previous);
+                    combined.push((i, j, v));
+                }
+                None => combined.push((i, j, v)),
+            }
+        }
+
+        self.put_triplets(combined);
+
+        nnz - self.nnz()
+    }
+
+    /// Moves the triplets out of the matrix, leaving it empty.
+    fn take_triplets(&mut self) -> Vec<(usize, usize, T)> {
+        let row_indices = std::mem::take(&mut self.row_indices);
+        let col_indices = std::mem::take(&mut self.col_indices);
+        let values = std::mem::take(&mut self.values);
+
+        row_indices
+            .into_iter()
+            .zip(col_indices)
+            .zip(values)
+            .map(|((i, j), v)| (i, j, v))
+            .collect()
+    }
+
+    /// Stores the given triplets, which must be in bounds, in an empty matrix.
+    fn put_triplets(&mut self, triplets: Vec<(usize, usize, T)>) {
+        self.reserve(triplets.len());
+
+        for (i, j, v) in triplets {
+            self.row_indices.push(i);
+            self.col_indices.push(j);
+            self.values.push(v);
+        }
+    }
+
     /// The number of rows in the matrix.
     #[inline]
     #[must_use]
@@ -274,3 +800,52 @@ impl<T> CooMatrix<T> {
         (self.row_indices, self.col_indices, self.values)
     }
 }
+
+impl<T> Extend<(usize, usize, T)> for CooMatrix<T> {
+    /// Pushes every triplet `(i, j, v)` of the iterator to the matrix.
+    ///
+    /// Panics
+    /// ------
+    ///
+    /// Panics if any `i` or `j` is out of bounds, like [`CooMatrix::push`]. The triplets before
+    /// the offending one have been pushed at that point.
+    fn extend<I: IntoIterator<Item = (usize, usize, T)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+
+        for (i, j, v) in iter {
+            self.push(i, j, v);
+        }
+    }
+}
+
+impl<T> FromIterator<(usize, usize, T)> for CooMatrix<T> {
+    /// Collects triplets `(i, j, v)` into a COO matrix.
+    ///
+    /// The matrix is just large enough to hold every triplet, i.e. its number of rows and columns
+    /// are one more than the largest row and column index. Use [`CooMatrix::new`] and
+    /// [`Extend`] instead to collect into a matrix of given dimensions.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use nalgebra_sparse::coo::CooMatrix;
+    /// let coo: CooMatrix<_> = vec![(0, 1, 1.0), (2, 0, 2.0)].into_iter().collect();
+    /// assert_eq!((coo.nrows(), coo.ncols()), (3, 2));
+    /// ```
+    fn from_iter<I: IntoIterator<Item = (usize, usize, T)>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut coo = Self::new(0, 0);
+        coo.reserve(iter.size_hint().0);
+
+        for (i, j, v) in iter {
+            coo.nrows = coo.nrows.max(i + 1);
+            coo.ncols = coo.ncols.max(j + 1);
+            coo.row_indices.push(i);
+            coo.col_indices.push(j);
+            coo.values.push(v);
+        }
+
+        coo
+    }
+}
@@ -1,13 +1,33 @@
 //! A type for representing compressed sparse (row-major / column-major) matrices.
 
 use super::{
-    error::{SparseFormatError, SparsityPatternFormatError},
+    analysis::{
+        bandwidth, lane_span, lane_span_statistics, nnz_distribution, profile, summarize,
+        LaneSpanStatistics, MatrixSummary, NnzDistribution,
+    },
+    error::{OperationError, OperationErrorKind, SparseFormatError, SparsityPatternFormatError},
     factorization::CsCholesky,
-    SparseEntry,
+    ops::serial::contraction::{sp_cs_diag_product, sp_cs_frobenius_inner_product, sp_cs_trace},
+    pattern::SparsityPattern,
+    vector::{CsVector, CsVectorView},
+    SparseEntry, SparseEntryMut,
+};
+use nalgebra::{
+    DMatrix, DVector, Dim, Matrix, RawStorage, RawStorageMut, RealField, Scalar, Vector,
+};
+use num_traits::{One, Zero};
+use std::{
+    borrow::{Borrow, BorrowMut},
+    cmp::Ord,
+    cmp::Ordering,
+    iter::FromIterator,
+    marker::PhantomData,
+    ops::{AddAssign, DivAssign, Mul, MulAssign, Neg, Range},
+    sync::Arc,
 };
-use nalgebra::{RealField, Scalar};
-use num_traits::One;
-use std::{borrow::Borrow, cmp::Ord, cmp::Ordering, marker::PhantomData};
+
+#[cfg(feature = "smallvec")]
+use smallvec::SmallVec;
 
 /// An empty type to represent CSC-like storage convention.
 #[derive(Debug, Clone, Copy)]
@@ -116,7 +136,12 @@ mod private {
 ///    not to say that no mutability is allowed, but in almost every case borrowed mutability does
 ///    not make a lot of sense. e.g. you cannot add a new non-zero element to the matrix without
 ///    fully re-computing the offsets and indices, so you are better off owning the type as `self`
-///    and constructing a new `CsMatrix` rather than borrowing as `&mut self`.
+///    and constructing a new `CsMatrix` rather than borrowing as `&mut self`. The one exception
+///    is updating the values of existing entries, which leaves the sparsity pattern untouched:
+///    e.g. `+=` and `-=` accumulate a matrix with the same pattern in place (see
+///    [`spadd_assign`](crate::ops::serial::spadd::spadd_assign)). Methods such as
+///    [`CsMatrix::add_dense_block`] which may grow the pattern of an owned matrix rebuild its
+///    offsets, indices and data as a whole, so the invariants still hold after every call.
 /// 3. **Shape** and **Sizes**. Matrix shape and offsets / indices / data lengths are validated
 ///    upon construction. Thanks to the previous point (immutability), it is possible to guarantee
 ///    correctness of the data layout for the lifetime of the object, until it is consumed.
@@ -228,6 +253,52 @@ pub type CsrMatrix<T> = CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, CompressedRo
 /// An alias for producing an owned, column-major compressed sparse matrix.
 pub type CscMatrix<T> = CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, CompressedColumnStorage>;
 
+/// An alias for a row-major compressed sparse matrix whose sparsity pattern is shared.
+///
+/// The offsets and indices are reference-counted, so that any number of matrices with the same
+/// structure, e.g. the Jacobians of every time step of a simulation, share a single allocation of
+/// the pattern while owning their values. Cloning such a matrix only copies its values. See
+/// [`CsMatrix::into_shared`] and [`CsMatrix::with_values`].
+pub type SharedCsrMatrix<T> = CsMatrix<T, Arc<[usize]>, Arc<[usize]>, Vec<T>, CompressedRowStorage>;
+
+/// An alias for a column-major compressed sparse matrix whose sparsity pattern is shared.
+///
+/// See [`SharedCsrMatrix`].
+pub type SharedCscMatrix<T> =
+    CsMatrix<T, Arc<[usize]>, Arc<[usize]>, Vec<T>, CompressedColumnStorage>;
+
+/// An alias for an owned, row-major compressed sparse matrix that stores its data inline.
+///
+/// Matrices with at most `NROWS` rows and `NNZ` explicit entries do not allocate on the heap, which
+/// is useful for applications that handle a very large number of tiny matrices (e.g. per-element
+/// matrices in finite element assembly). Larger matrices spill over onto the heap, as with any
+/// `SmallVec`.
+///
+/// Requires the `smallvec` feature.
+#[cfg(feature = "smallvec")]
+pub type SmallCsrMatrix<T, const NROWS: usize, const NNZ: usize> = CsMatrix<
+    T,
+    SmallVec<[usize; NROWS]>,
+    SmallVec<[usize; NNZ]>,
+    SmallVec<[T; NNZ]>,
+    CompressedRowStorage,
+>;
+
+/// An alias for an owned, column-major compressed sparse matrix that stores its data inline.
+///
+/// Matrices with at most `NCOLS` columns and `NNZ` explicit entries do not allocate on the heap.
+/// See [`SmallCsrMatrix`] for more details.
+///
+/// Requires the `smallvec` feature.
+#[cfg(feature = "smallvec")]
+pub type SmallCscMatrix<T, const NCOLS: usize, const NNZ: usize> = CsMatrix<
+    T,
+    SmallVec<[usize; NCOLS]>,
+    SmallVec<[usize; NNZ]>,
+    SmallVec<[T; NNZ]>,
+    CompressedColumnStorage,
+>;
+
 impl<T, MajorOffsets, MinorIndices, Data, CompressionKind>
     CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressionKind>
 where
@@ -317,74 +388,17 @@ where
         indices: MinorIndices,
         data: Data,
     ) -> Result<Self, SparseFormatError> {
-        let nmajor = CompressionKind::nmajor(nrows, ncols);
-        let nminor = CompressionKind::nminor(nrows, ncols);
-        let borrowed_offsets = offsets.borrow();
-        let borrowed_indices = indices.borrow();
-
-        if borrowed_offsets.len() != nmajor {
-            // size mismatch
-            return Err(SparsityPatternFormatError::InvalidOffsetArrayLength.into());
-        }
-
-        if let Some(first) = borrowed_offsets.first() {
-            if *first != 0 {
-                // First entry exists and is not zero
-                return Err(SparsityPatternFormatError::InvalidFirstOffset.into());
-            }
-        }
-
-        if borrowed_indices.len() != data.borrow().len() {
+        if indices.borrow().len() != data.borrow().len() {
             // size mismatch
             return Err(SparsityPatternFormatError::DataAndIndicesSizeMismatch.into());
         }
 
-        if borrowed_indices.iter().any(|&index| index >= nminor) {
-            // Index out-of-bounds
-            return Err(SparsityPatternFormatError::MinorIndexOutOfBounds.into());
-        }
-
-        for major_index in 0..nmajor {
-            let lower = borrowed_offsets[major_index];
-
-            let lane_indices = if major_index + 1 < nmajor {
-                let upper = borrowed_offsets[major_index + 1];
-
-                if lower > upper {
-                    // Offsets do not monotonically increase
-                    return Err(SparsityPatternFormatError::NonmonotonicOffsets.into());
-                }
-
-                &borrowed_indices[lower..upper]
-            } else {
-                &borrowed_indices[lower..]
-            };
-
-            if !lane_indices.is_empty() {
-                if let Some(err) = lane_indices
-                    .iter()
-                    .zip(&lane_indices[1..])
-                    .filter_map(|(lower_index, upper_index)| {
-                        match lower_index.cmp(upper_index) {
-                            Ordering::Less => None,
-                            Ordering::Equal => {
-                                // Duplicates detected
-                                Some(Err(SparsityPatternFormatError::DuplicateEntry.into()))
-                            }
-                            Ordering::Greater => {
-                                // Indices in lane do not monotonically increase
-                                Some(Err(
-                                    SparsityPatternFormatError::NonmonotonicMinorIndices.into()
-                                ))
-                            }
-                        }
-                    })
-                    .next()
-                {
-                    return err;
-                }
-            }
-        }
+        validate_pattern(
+            CompressionKind::nmajor(nrows, ncols),
+            CompressionKind::nminor(nrows, ncols),
+            offsets.borrow(),
+            indices.borrow(),
+        )?;
 
         Ok(unsafe { Self::from_parts_unchecked(nrows, ncols, offsets, indices, data) })
     }
@@ -409,8 +423,8 @@ where
     /// pattern data.
     ///
     /// This function can be useful when needing to pass an "owned" copy of the data around, but
-    /// not wanting to make a true copy of the data. For example, standard operations like `Add`,
-    /// `Mul`, `Sub`, etc. require that the types passed in are owned.
+    /// not wanting to make a true copy of the data. For example, the standard operations like
+    /// `Add`, `Mul`, `Sub`, etc. on matrix values consume their operands.
     ///
     /// This can be used in most places where `.clone()` is used for the same semantic effect while
     /// reducing the overall memory footprint of your program.
@@ -433,6 +447,9 @@ where
     /// // Here, rather than do two full copies + allocs, we instead take views of the matrix,
     /// // which won't allocate extra memory for the operation.
     /// let C2 = A.to_view() + A.to_view();
+    ///
+    /// // Operations on references do the same under the hood.
+    /// let C3 = &A + &A;
     /// # }
     /// ```
     pub fn to_view(&self) -> CsMatrix<T, &[usize], &[usize], &[T], CompressionKind> {
@@ -448,6 +465,43 @@ where
         }
     }
 
+    /// Copies the data of this matrix into a matrix with different (owned) storage types.
+    ///
+    /// Since every operation in this crate is generic over the storage of its inputs, this is
+    /// mostly useful to move between e.g. `Vec`-backed storage and inline storage such as
+    /// `SmallVec`, or to take an owned copy of a view.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nalgebra_sparse::cs::{CsMatrix, CsrMatrix, CompressedRowStorage};
+    /// let matrix = CsrMatrix::<f64>::identity(3);
+    ///
+    /// // Boxed slices do not carry any spare capacity around.
+    /// let boxed: CsMatrix<f64, Box<[usize]>, Box<[usize]>, Box<[f64]>, CompressedRowStorage> =
+    ///     matrix.to_storage();
+    ///
+    /// assert_eq!(boxed.cs_data(), matrix.cs_data());
+    /// ```
+    pub fn to_storage<NewOffsets, NewIndices, NewData>(
+        &self,
+    ) -> CsMatrix<T, NewOffsets, NewIndices, NewData, CompressionKind>
+    where
+        NewOffsets: Borrow<[usize]> + FromIterator<usize>,
+        NewIndices: Borrow<[usize]> + FromIterator<usize>,
+        NewData: Borrow<[T]> + FromIterator<T>,
+    {
+        let (offsets, indices, data) = self.cs_data();
+
+        CsMatrix {
+            shape: self.shape,
+            offsets: offsets.iter().copied().collect(),
+            indices: indices.iter().copied().collect(),
+            data: data.iter().cloned().collect(),
+            _phantom: PhantomData,
+        }
+    }
+
     /// Produces an immutable view of the transpose of the data by borrowing the underlying lanes
     /// and sparsity pattern data.
     pub fn transpose(&self) -> CsMatrix<T, &[usize], &[usize], &[T], CompressionKind::Transpose> {
@@ -505,6 +559,37 @@ where
         Some(entry)
     }
 
+    /// The positions in the data of the entries on the `k`-th diagonal of the matrix, i.e. of the
+    /// entries `(i, i + k)`, where negative `k` are below the main diagonal. Positions that are
+    /// not stored are `None`.
+    fn diagonal_positions(&self, k: isize) -> Vec<Option<usize>> {
+        let (nrows, ncols) = self.shape;
+        let (row_start, col_start) = if k >= 0 {
+            (0, k.unsigned_abs())
+        } else {
+            (k.unsigned_abs(), 0)
+        };
+        let len = nrows
+            .saturating_sub(row_start)
+            .min(ncols.saturating_sub(col_start));
+        let (offsets, indices, _) = self.cs_data();
+
+        (0..len)
+            .map(|i| {
+                let (row, col) = (row_start + i, col_start + i);
+                let major = CompressionKind::nmajor(row, col);
+                let minor = CompressionKind::nminor(row, col);
+                let start = offsets[major];
+                let end = offsets.get(major + 1).copied().unwrap_or(indices.len());
+
+                indices[start..end]
+                    .binary_search(&minor)
+                    .ok()
+                    .map(|local| start + local)
+            })
+            .collect()
+    }
+
     /// An iterator that iterates through every implicit and explicit entry in the matrix.
     pub fn all_entries(&self) -> AllElementsIter<'_, T> {
         let minor_length = self.nminor();
@@ -560,6 +645,30 @@ where
         })
     }
 
+    /// Borrows a major-axis lane as a sparse vector whose length is the minor dimension.
+    ///
+    /// Returns `None` iff the major index does not correspond to a lane in the `CsMatrix`.
+    fn lane_vector(&self, major_index: usize) -> Option<CsVectorView<'_, T>> {
+        if major_index >= self.nmajor() {
+            return None;
+        }
+
+        let (offsets, indices, data) = self.cs_data();
+        let lower = offsets[major_index];
+        let upper = offsets
+            .get(major_index + 1)
+            .copied()
+            .unwrap_or(indices.len());
+
+        Some(unsafe {
+            CsVector::from_parts_unchecked(
+                self.nminor(),
+                &indices[lower..upper],
+                &data[lower..upper],
+            )
+        })
+    }
+
     /// An iterator that iterates across every major lane of the `CsMatrix`, in order.
     pub fn iter(&self) -> CsMatrixIter<'_, T> {
         let (offsets, indices, data) = self.cs_data();
@@ -591,113 +700,2215 @@ where
             data,
         }
     }
-}
 
-impl<T, MajorOffsets, MinorIndices, Data, CompressionKind>
-    CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressionKind>
-where
-    T: Scalar + RealField,
-    MajorOffsets: Borrow<[usize]>,
-    MinorIndices: Borrow<[usize]>,
-    Data: Borrow<[T]>,
-    CompressionKind: Compression,
-{
-    /// Gets the Cholesky factorization of the matrix, if it exists.
+    /// The bandwidth of the matrix, i.e. the largest distance `|row - col|` of any explicit entry
+    /// from the diagonal.
     ///
-    /// The factorization will exist only if this matrix is symmetric and positive definite.
-    pub fn cholesky(&self) -> Option<CsCholesky<T>> {
-        CsCholesky::factor(&self).ok()
+    /// Matrices without any explicit entries have a bandwidth of zero. See
+    /// [`CsMatrix::summary`] for the lower and upper bandwidths separately.
+    #[must_use]
+    pub fn bandwidth(&self) -> usize {
+        bandwidth(self)
     }
-}
 
-impl<T, MajorOffsets, MinorIndices, Data>
-    CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressedRowStorage>
-where
-    T: Scalar,
-    MajorOffsets: Borrow<[usize]>,
-    MinorIndices: Borrow<[usize]>,
-    Data: Borrow<[T]>,
-{
-    /// Gets a value in the sparse matrix from a `(row, column)` index pair.
+    /// The profile (or envelope size) of the matrix.
     ///
-    /// This function will return `None` if and only if the requested entry is out-of-bounds of the
-    /// underlying matrix.
-    #[inline]
-    pub fn get_entry(&self, row: usize, column: usize) -> Option<SparseEntry<'_, T>> {
-        self.get_entry_major_minor(row, column)
+    /// This is the sum over all rows of the distance between the diagonal and the first explicit
+    /// entry of that row, ignoring rows whose first entry is on or above the diagonal. For
+    /// symmetric matrices this is the number of entries in the envelope of the lower triangle,
+    /// which bounds the fill-in of a Cholesky factorization, and is what bandwidth- and
+    /// profile-reducing orderings (e.g. reverse Cuthill-McKee) aim to minimize.
+    #[must_use]
+    pub fn profile(&self) -> usize {
+        profile(self)
+    }
+
+    /// An iterator over the span of every major lane of the matrix, in order.
+    ///
+    /// The span of a lane is the distance between its first and last explicit entry, inclusive.
+    /// Empty lanes have a span of zero.
+    pub fn lane_spans(&self) -> impl Iterator<Item = usize> + '_ {
+        let (offsets, indices, _) = self.cs_data();
+
+        offsets.iter().enumerate().map(move |(major, &offset)| {
+            let upper = offsets.get(major + 1).copied().unwrap_or(indices.len());
+            lane_span(&indices[offset..upper])
+        })
+    }
+
+    /// Computes statistics over the spans of every major lane of the matrix.
+    ///
+    /// See [`CsMatrix::lane_spans`] for the definition of the span of a lane.
+    #[must_use]
+    pub fn lane_span_statistics(&self) -> LaneSpanStatistics {
+        lane_span_statistics(self)
+    }
+
+    /// Computes the distribution of the number of explicit entries per row of the matrix.
+    ///
+    /// See [`NnzDistribution`] for the statistics that are computed.
+    #[must_use]
+    pub fn row_nnz_distribution(&self) -> NnzDistribution {
+        // Whether the lanes of the matrix are its rows
+        if CompressionKind::nmajor(1, 0) == 1 {
+            nnz_distribution(self.major_nnz_counts())
+        } else {
+            nnz_distribution(self.minor_nnz_counts())
+        }
+    }
+
+    /// Computes the distribution of the number of explicit entries per column of the matrix.
+    ///
+    /// See [`NnzDistribution`] for the statistics that are computed.
+    #[must_use]
+    pub fn column_nnz_distribution(&self) -> NnzDistribution {
+        if CompressionKind::nmajor(1, 0) == 1 {
+            nnz_distribution(self.minor_nnz_counts())
+        } else {
+            nnz_distribution(self.major_nnz_counts())
+        }
+    }
+
+    /// The number of explicit entries in every major lane.
+    fn major_nnz_counts(&self) -> Vec<usize> {
+        let (offsets, indices, _) = self.cs_data();
+
+        offsets
+            .iter()
+            .enumerate()
+            .map(|(major, &offset)| {
+                offsets.get(major + 1).copied().unwrap_or(indices.len()) - offset
+            })
+            .collect()
+    }
+
+    /// The number of explicit entries in every minor lane.
+    fn minor_nnz_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.nminor()];
+
+        for &minor in self.cs_data().1 {
+            counts[minor] += 1;
+        }
+
+        counts
+    }
+
+    /// Copies the sparsity pattern of the matrix into a standalone [`SparsityPattern`].
+    ///
+    /// The major lanes of the pattern are the major lanes of the matrix, so this is the row
+    /// pattern of a CSR matrix and the column pattern of a CSC matrix.
+    #[must_use]
+    pub fn pattern(&self) -> SparsityPattern {
+        let (offsets, indices, _) = self.cs_data();
+
+        unsafe {
+            SparsityPattern::from_offsets_and_indices_unchecked(
+                self.nminor(),
+                offsets.to_vec(),
+                indices.to_vec(),
+            )
+        }
+    }
+
+    /// Returns `true` if both matrices have the same shape and sparsity pattern.
+    ///
+    /// If both matrices store their offsets and indices in the same allocations, e.g. because they
+    /// are [shared](SharedCsrMatrix) or views of the same matrix, this takes `O(1)`. Otherwise,
+    /// the patterns are compared entry by entry.
+    #[must_use]
+    pub fn same_pattern<U, MO, MI, D>(
+        &self,
+        other: &CsMatrix<U, MO, MI, D, CompressionKind>,
+    ) -> bool
+    where
+        U: Scalar,
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[U]>,
+    {
+        let (offsets, indices, _) = self.cs_data();
+        let (other_offsets, other_indices, _) = other.cs_data();
+
+        self.shape == other.shape()
+            && (std::ptr::eq(offsets, other_offsets) || offsets == other_offsets)
+            && (std::ptr::eq(indices, other_indices) || indices == other_indices)
+    }
+
+    /// Applies `f` to every explicitly stored value, producing a matrix with the same sparsity
+    /// pattern and the transformed, possibly differently-typed, values.
+    ///
+    /// The offsets and indices of the result are clones of those of the matrix, so a view keeps
+    /// borrowing the pattern it was created from. To move the pattern of an owned matrix into the
+    /// result instead of copying it, see [`CsMatrix::into_map_values`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra::DMatrix;
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let a = CsrMatrix::from(&DMatrix::from_row_slice(2, 2, &[-1.5f64, 0.0, 0.0, 2.0]));
+    ///
+    /// let single: CsrMatrix<f32> = a.map_values(|&v| v as f32);
+    /// let magnitudes = a.to_view().map_values(|v| v.abs());
+    ///
+    /// assert_eq!(single.cs_data().2, &[-1.5f32, 2.0]);
+    /// assert_eq!(magnitudes.cs_data().2, &[1.5, 2.0]);
+    /// ```
+    #[must_use]
+    pub fn map_values<U, F>(
+        &self,
+        f: F,
+    ) -> CsMatrix<U, MajorOffsets, MinorIndices, Vec<U>, CompressionKind>
+    where
+        U: Scalar,
+        F: FnMut(&T) -> U,
+        MajorOffsets: Clone,
+        MinorIndices: Clone,
+    {
+        let data = self.data.borrow().iter().map(f).collect();
+
+        CsMatrix {
+            shape: self.shape,
+            offsets: self.offsets.clone(),
+            indices: self.indices.clone(),
+            data,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Behaves like [`CsMatrix::map_values`], but also passes the row and column of every entry
+    /// to `f`, as `f(row, column, value)`.
+    #[must_use]
+    pub fn map_with_indices<U, F>(
+        &self,
+        mut f: F,
+    ) -> CsMatrix<U, MajorOffsets, MinorIndices, Vec<U>, CompressionKind>
+    where
+        U: Scalar,
+        F: FnMut(usize, usize, &T) -> U,
+        MajorOffsets: Clone,
+        MinorIndices: Clone,
+    {
+        let data = self
+            .iter()
+            .enumerate()
+            .flat_map(|(major, lane)| lane.map(move |(minor, value)| (major, minor, value)))
+            .map(|(major, minor, value)| {
+                f(
+                    CompressionKind::nmajor(major, minor),
+                    CompressionKind::nminor(major, minor),
+                    value,
+                )
+            })
+            .collect();
+
+        CsMatrix {
+            shape: self.shape,
+            offsets: self.offsets.clone(),
+            indices: self.indices.clone(),
+            data,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Behaves like [`CsMatrix::map_values`], but consumes the matrix, so that its offsets and
+    /// indices are moved into the result rather than copied.
+    #[must_use]
+    pub fn into_map_values<U, F>(
+        self,
+        f: F,
+    ) -> CsMatrix<U, MajorOffsets, MinorIndices, Vec<U>, CompressionKind>
+    where
+        U: Scalar,
+        F: FnMut(T) -> U,
+        Data: IntoIterator<Item = T>,
+    {
+        CsMatrix {
+            shape: self.shape,
+            offsets: self.offsets,
+            indices: self.indices,
+            data: self.data.into_iter().map(f).collect(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Extracts the rectangular block of the matrix spanned by `rows` and `cols` into a new,
+    /// owned sparse matrix of the same compression.
+    ///
+    /// The explicit entries of the block (including explicit zeros) keep their values, and their
+    /// indices are shifted so that the block starts at `(0, 0)`. Within every lane, the entries of
+    /// the block are found by binary search. To take a range of whole rows of a CSR matrix (or
+    /// columns of a CSC matrix) without copying the entries, see [`CsMatrix::rows`] and
+    /// [`CsMatrix::columns`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if either range is decreasing or extends past the shape of the matrix.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra::DMatrix;
+    /// use nalgebra_sparse::cs::CscMatrix;
+    ///
+    /// let dense = DMatrix::from_fn(4, 4, |i, j| match j.wrapping_sub(i) {
+    ///     0 => 2.0,
+    ///     1 => -1.0,
+    ///     _ => 0.0,
+    /// });
+    /// let matrix = CscMatrix::from(&dense);
+    ///
+    /// let block = matrix.submatrix(1..3, 2..4);
+    ///
+    /// assert_eq!(DMatrix::from(&block), dense.slice((1, 2), (2, 2)));
+    /// ```
+    #[must_use]
+    pub fn submatrix(
+        &self,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, CompressionKind> {
+        let (majors, minors) = self.block_lanes(&rows, &cols);
+        let (offsets, indices, data) = self.cs_data();
+
+        let mut block_offsets = Vec::with_capacity(majors.len());
+        let mut block_indices = Vec::new();
+        let mut block_data = Vec::new();
+
+        for major in majors {
+            block_offsets.push(block_indices.len());
+
+            let lane = offsets[major]..offsets.get(major + 1).copied().unwrap_or(indices.len());
+            let lane_indices = &indices[lane.clone()];
+
+            let lower = lane.start + lane_indices.partition_point(|&j| j < minors.start);
+            let upper = lane.start + lane_indices.partition_point(|&j| j < minors.end);
+
+            block_indices.extend(indices[lower..upper].iter().map(|j| j - minors.start));
+            block_data.extend_from_slice(&data[lower..upper]);
+        }
+
+        unsafe {
+            CsMatrix::from_parts_unchecked(
+                rows.len(),
+                cols.len(),
+                block_offsets,
+                block_indices,
+                block_data,
+            )
+        }
+    }
+
+    /// Gathers the rows `row_indices` and columns `col_indices` of the matrix, in that order, into
+    /// a new owned sparse matrix of the same compression.
+    ///
+    /// Entry `(i, j)` of the result is entry `(row_indices[i], col_indices[j])` of the matrix, so
+    /// this is the sparse equivalent of SciPy's `A[np.ix_(row_indices, col_indices)]`. The indices
+    /// may be in any order and may be repeated, e.g. to permute the matrix or to duplicate some of
+    /// its rows. For contiguous ranges, [`CsMatrix::submatrix`] is cheaper.
+    ///
+    /// The lanes of the result are built from the selected lanes of the matrix in
+    /// `O(nnz + nminor)`, where `nnz` counts the entries of the selected lanes, plus the cost of
+    /// sorting the entries of every lane if the minor indices are not selected in increasing order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the indices is out of bounds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra::DMatrix;
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let dense = DMatrix::from_row_slice(3, 3, &[1, 0, 2, 0, 3, 0, 4, 0, 5]);
+    /// let matrix = CsrMatrix::from(&dense);
+    ///
+    /// let selected = matrix.select(&[2, 0, 2], &[2, 0]);
+    ///
+    /// assert_eq!(DMatrix::from(&selected), DMatrix::from_row_slice(3, 2, &[5, 4, 2, 1, 5, 4]));
+    /// ```
+    #[must_use]
+    pub fn select(
+        &self,
+        row_indices: &[usize],
+        col_indices: &[usize],
+    ) -> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, CompressionKind> {
+        let (nrows, ncols) = self.shape;
+
+        if let Some(&i) = row_indices.iter().find(|&&i| i >= nrows) {
+            panic!(
+                "Row index {} is out of bounds for a matrix with {} rows.",
+                i, nrows
+            );
+        }
+
+        if let Some(&j) = col_indices.iter().find(|&&j| j >= ncols) {
+            panic!(
+                "Column index {} is out of bounds for a matrix with {} columns.",
+                j, ncols
+            );
+        }
+
+        // Whether the lanes of the matrix are its rows
+        let (major_indices, minor_indices) = if CompressionKind::nmajor(1, 0) == 1 {
+            (row_indices, col_indices)
+        } else {
+            (col_indices, row_indices)
+        };
+
+        // For every minor index of the matrix, the positions at which it was selected, stored
+        // like the lanes of a compressed matrix
+        let mut position_offsets = vec![0; self.nminor() + 1];
+
+        for &minor in minor_indices {
+            position_offsets[minor + 1] += 1;
+        }
+
+        for k in 0..self.nminor() {
+            position_offsets[k + 1] += position_offsets[k];
+        }
+
+        let mut positions = vec![0; minor_indices.len()];
+        let mut next = position_offsets.clone();
+
+        for (position, &minor) in minor_indices.iter().enumerate() {
+            positions[next[minor]] = position;
+            next[minor] += 1;
+        }
+
+        let sorted = minor_indices.windows(2).all(|pair| pair[0] < pair[1]);
+
+        let (offsets, indices, data) = self.cs_data();
+
+        let mut selected_offsets = Vec::with_capacity(major_indices.len());
+        let mut selected_entries: Vec<(usize, T)> = Vec::new();
+
+        for &major in major_indices {
+            let lane_start = selected_entries.len();
+            selected_offsets.push(lane_start);
+
+            let lane = offsets[major]..offsets.get(major + 1).copied().unwrap_or(indices.len());
+
+            for k in lane {
+                let minor = indices[k];
+
+                for &position in &positions[position_offsets[minor]..position_offsets[minor + 1]] {
+                    selected_entries.push((position, data[k].clone()));
+                }
+            }
+
+            if !sorted {
+                selected_entries[lane_start..].sort_unstable_by_key(|(position, _)| *position);
+            }
+        }
+
+        let (selected_indices, selected_data) = selected_entries.into_iter().unzip();
+
+        unsafe {
+            CsMatrix::from_parts_unchecked(
+                row_indices.len(),
+                col_indices.len(),
+                selected_offsets,
+                selected_indices,
+                selected_data,
+            )
+        }
+    }
+
+    /// Returns the lower triangular part of the matrix relative to the `k`-th diagonal, i.e. the
+    /// entries `(i, j)` with `j - i <= k`, as a new matrix of the same shape and compression.
+    ///
+    /// `k = 0` keeps the main diagonal, `k = -1` gives the strictly lower triangular part, and
+    /// positive `k` also keep the first `k` diagonals above the main diagonal. This is the usual
+    /// way of extracting the stored half of a symmetric matrix, or the Gauss-Seidel splitting
+    /// `D + L` of a system matrix.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra::DMatrix;
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let a = CsrMatrix::from(&DMatrix::from_row_slice(2, 3, &[1, 2, 3, 4, 5, 6]));
+    ///
+    /// assert_eq!(
+    ///     DMatrix::from(&a.lower_triangle(0)),
+    ///     DMatrix::from_row_slice(2, 3, &[1, 0, 0, 4, 5, 0])
+    /// );
+    /// assert_eq!(
+    ///     DMatrix::from(&a.upper_triangle(1)),
+    ///     DMatrix::from_row_slice(2, 3, &[0, 2, 3, 0, 0, 6])
+    /// );
+    /// ```
+    #[must_use]
+    pub fn lower_triangle(
+        &self,
+        k: isize,
+    ) -> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, CompressionKind> {
+        self.filter_by_offset(|offset| offset <= k)
+    }
+
+    /// Returns the upper triangular part of the matrix relative to the `k`-th diagonal, i.e. the
+    /// entries `(i, j)` with `j - i >= k`, as a new matrix of the same shape and compression.
+    ///
+    /// `k = 0` keeps the main diagonal, `k = 1` gives the strictly upper triangular part, and
+    /// negative `k` also keep the first `-k` diagonals below the main diagonal. See
+    /// [`lower_triangle`](Self::lower_triangle) for an example.
+    #[must_use]
+    pub fn upper_triangle(
+        &self,
+        k: isize,
+    ) -> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, CompressionKind> {
+        self.filter_by_offset(|offset| offset >= k)
+    }
+
+    /// Copies the entries `(i, j)` for which `keep(j - i)` holds into a new matrix, in one pass
+    /// over the lanes.
+    fn filter_by_offset<F>(
+        &self,
+        keep: F,
+    ) -> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, CompressionKind>
+    where
+        F: Fn(isize) -> bool,
+    {
+        let mut offsets = Vec::with_capacity(self.nmajor());
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+
+        for (major, lane) in self.iter().enumerate() {
+            offsets.push(indices.len());
+
+            for (minor, value) in lane {
+                let row = CompressionKind::nmajor(major, minor) as isize;
+                let col = CompressionKind::nminor(major, minor) as isize;
+
+                if keep(col - row) {
+                    indices.push(minor);
+                    data.push(value.clone());
+                }
+            }
+        }
+
+        CsMatrix {
+            shape: self.shape,
+            offsets,
+            indices,
+            data,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Borrows the lanes in `majors` as the parts of a matrix: the offsets of the lanes are copied
+    /// and rebased to start at zero, but their indices and values are borrowed.
+    fn lane_range_parts(&self, majors: Range<usize>) -> (Vec<usize>, &[usize], &[T]) {
+        let (offsets, indices, data) = self.cs_data();
+        let lane_start = |major: usize| offsets.get(major).copied().unwrap_or(indices.len());
+
+        let lower = lane_start(majors.start);
+        let upper = lane_start(majors.end);
+
+        let offsets = offsets[majors]
+            .iter()
+            .map(|offset| offset - lower)
+            .collect();
+
+        (offsets, &indices[lower..upper], &data[lower..upper])
+    }
+
+    /// Translates the `rows` and `cols` ranges of a block of the matrix into the corresponding
+    /// ranges of major and minor indices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either range is decreasing or extends past the shape of the matrix.
+    fn block_lanes(
+        &self,
+        rows: &Range<usize>,
+        cols: &Range<usize>,
+    ) -> (Range<usize>, Range<usize>) {
+        let (nrows, ncols) = self.shape;
+
+        assert!(
+            rows.start <= rows.end && rows.end <= nrows,
+            "Row range {:?} is out of bounds for a matrix with {} rows.",
+            rows,
+            nrows
+        );
+        assert!(
+            cols.start <= cols.end && cols.end <= ncols,
+            "Column range {:?} is out of bounds for a matrix with {} columns.",
+            cols,
+            ncols
+        );
+
+        let majors = CompressionKind::nmajor(rows.start, cols.start)
+            ..CompressionKind::nmajor(rows.end, cols.end);
+        let minors = CompressionKind::nminor(rows.start, cols.start)
+            ..CompressionKind::nminor(rows.end, cols.end);
+
+        (majors, minors)
+    }
+}
+
+impl<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+    CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+where
+    T: Scalar + RealField,
+    MajorOffsets: Borrow<[usize]>,
+    MinorIndices: Borrow<[usize]>,
+    Data: Borrow<[T]>,
+    CompressionKind: Compression,
+{
+    /// Gets the Cholesky factorization of the matrix, if it exists.
+    ///
+    /// The factorization will exist only if this matrix is symmetric and positive definite.
+    pub fn cholesky(&self) -> Option<CsCholesky<T>> {
+        CsCholesky::factor(&self).ok()
+    }
+}
+
+impl<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+    CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+where
+    T: Scalar + PartialOrd,
+    MajorOffsets: Borrow<[usize]>,
+    MinorIndices: Borrow<[usize]>,
+    Data: Borrow<[T]>,
+    CompressionKind: Compression,
+{
+    /// Computes a summary of the statistics of this matrix, such as its shape, density, bandwidth,
+    /// symmetry and value range.
+    ///
+    /// The returned [`MatrixSummary`] implements `Display`, which makes it convenient for logging
+    /// what a solver or factorization is being fed. Computing the summary requires a pass over all
+    /// the explicit entries in the matrix, plus a lookup of the mirrored entry for each explicit
+    /// entry of square matrices.
+    pub fn summary(&self) -> MatrixSummary<T> {
+        summarize(self)
+    }
+}
+
+impl<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+    CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+where
+    T: Scalar + Zero,
+    MajorOffsets: Borrow<[usize]>,
+    MinorIndices: Borrow<[usize]>,
+    Data: Borrow<[T]>,
+    CompressionKind: Compression,
+{
+    /// Returns an owned matrix with the same shape and sparsity pattern as this matrix, where
+    /// every explicit entry is an explicit zero.
+    ///
+    /// This is useful as a buffer of the same layout as `self`, e.g. to accumulate gradients with
+    /// respect to the values of a sparse matrix (see the [`gradient`](crate::ops::serial::gradient)
+    /// module).
+    #[must_use]
+    pub fn zeros_like(&self) -> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, CompressionKind> {
+        let (offsets, indices, _) = self.cs_data();
+
+        CsMatrix {
+            shape: self.shape,
+            offsets: offsets.to_vec(),
+            indices: indices.to_vec(),
+            data: vec![T::zero(); indices.len()],
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the main diagonal of the matrix as a dense vector of length `min(nrows, ncols)`.
+    ///
+    /// Diagonal entries that are not stored are zero.
+    #[must_use]
+    pub fn diagonal(&self) -> DVector<T> {
+        self.diagonal_offset(0)
+    }
+
+    /// Returns the `k`-th diagonal of the matrix as a dense vector, i.e. the entries `(i, i + k)`.
+    ///
+    /// Positive `k` select diagonals above the main diagonal and negative `k` select diagonals
+    /// below it. Diagonals that lie entirely outside of the matrix are empty, and entries that are
+    /// not stored are zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra::{DMatrix, DVector};
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let a = CsrMatrix::from(&DMatrix::from_row_slice(2, 3, &[1, 2, 0, 4, 5, 6]));
+    ///
+    /// assert_eq!(a.diagonal(), DVector::from_vec(vec![1, 5]));
+    /// assert_eq!(a.diagonal_offset(1), DVector::from_vec(vec![2, 6]));
+    /// assert_eq!(a.diagonal_offset(2), DVector::from_vec(vec![0]));
+    /// assert_eq!(a.diagonal_offset(-1), DVector::from_vec(vec![4]));
+    /// assert_eq!(a.diagonal_offset(-2).len(), 0);
+    /// ```
+    #[must_use]
+    pub fn diagonal_offset(&self, k: isize) -> DVector<T> {
+        let data = self.data.borrow();
+        let positions = self.diagonal_positions(k);
+
+        DVector::from_iterator(
+            positions.len(),
+            positions.into_iter().map(|position| match position {
+                Some(position) => data[position].clone(),
+                None => T::zero(),
+            }),
+        )
+    }
+
+    /// Splits a square matrix `A = D + L + U` into its diagonal `D`, its strictly lower triangular
+    /// part `L` and its strictly upper triangular part `U`.
+    ///
+    /// This is the split used by the classical stationary methods and their preconditioners
+    /// (Jacobi, Gauss-Seidel, SSOR). Diagonal entries that are not stored are zero in `D`, and `L`
+    /// and `U` keep the compression and the order of the entries of `self`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if the matrix
+    /// is not square.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra::{DMatrix, DVector};
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let a = CsrMatrix::from(&DMatrix::from_row_slice(2, 2, &[1, 2, 3, 4]));
+    /// let (d, l, u) = a.split_dlu().unwrap();
+    ///
+    /// assert_eq!(d, DVector::from_vec(vec![1, 4]));
+    /// assert_eq!(DMatrix::from(&l), DMatrix::from_row_slice(2, 2, &[0, 0, 3, 0]));
+    /// assert_eq!(DMatrix::from(&u), DMatrix::from_row_slice(2, 2, &[0, 2, 0, 0]));
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn split_dlu(
+        &self,
+    ) -> Result<
+        (
+            DVector<T>,
+            CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, CompressionKind>,
+            CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, CompressionKind>,
+        ),
+        OperationError,
+    > {
+        let (nrows, ncols) = self.shape;
+
+        if nrows != ncols {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!(
+                    "Only square matrices can be split into D + L + U, but the matrix has shape \
+                     ({}, {}).",
+                    nrows, ncols
+                ),
+            ));
+        }
+
+        let mut diagonal = DVector::zeros(nrows);
+        let mut lower = (Vec::with_capacity(nrows), Vec::new(), Vec::new());
+        let mut upper = (Vec::with_capacity(nrows), Vec::new(), Vec::new());
+
+        for (major, lane) in self.iter().enumerate() {
+            lower.0.push(lower.1.len());
+            upper.0.push(upper.1.len());
+
+            for (minor, value) in lane {
+                let row = CompressionKind::nmajor(major, minor);
+                let col = CompressionKind::nminor(major, minor);
+
+                let part = match row.cmp(&col) {
+                    Ordering::Equal => {
+                        diagonal[row] = value.clone();
+                        continue;
+                    }
+                    Ordering::Greater => &mut lower,
+                    Ordering::Less => &mut upper,
+                };
+
+                part.1.push(minor);
+                part.2.push(value.clone());
+            }
+        }
+
+        let into_matrix = |(offsets, indices, data)| CsMatrix {
+            shape: self.shape,
+            offsets,
+            indices,
+            data,
+            _phantom: PhantomData,
+        };
+
+        Ok((diagonal, into_matrix(lower), into_matrix(upper)))
+    }
+
+    /// Extracts the rectangular block of the matrix spanned by `rows` and `cols` into a dense
+    /// matrix, without constructing an intermediate sparse submatrix.
+    ///
+    /// This is the usual way of gathering a frontal matrix or the blocks of a Schur complement
+    /// out of a larger sparse matrix. See [`CsMatrix::write_dense_block`] to write the block into
+    /// existing storage instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either range is decreasing or extends past the shape of the matrix.
+    #[must_use]
+    pub fn to_dense_block(&self, rows: Range<usize>, cols: Range<usize>) -> DMatrix<T> {
+        let mut block = DMatrix::zeros(rows.len(), cols.len());
+        self.scatter_block(rows, cols, &mut block);
+        block
+    }
+
+    /// Writes the rectangular block of the matrix spanned by `rows` and `cols` into `out`.
+    ///
+    /// Every entry of `out` is overwritten: positions which are not explicitly stored in `self`
+    /// are set to zero. `out` may be any mutable dense matrix or slice, e.g. a
+    /// [`DMatrixSliceMut`](nalgebra::DMatrixSliceMut) into a larger frontal matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either range is decreasing or extends past the shape of the matrix, or if the
+    /// shape of `out` is not `(rows.len(), cols.len())`.
+    pub fn write_dense_block<R, C, S>(
+        &self,
+        rows: Range<usize>,
+        cols: Range<usize>,
+        out: &mut Matrix<T, R, C, S>,
+    ) where
+        R: Dim,
+        C: Dim,
+        S: RawStorageMut<T, R, C>,
+    {
+        assert_eq!(
+            out.shape(),
+            (rows.len(), cols.len()),
+            "The output matrix must have the shape of the requested block."
+        );
+
+        out.fill(T::zero());
+        self.scatter_block(rows, cols, out);
+    }
+
+    /// Copies the explicit entries of the block spanned by `rows` and `cols` into `out`, which is
+    /// assumed to have the shape of the block and to already hold zeros.
+    fn scatter_block<R, C, S>(
+        &self,
+        rows: Range<usize>,
+        cols: Range<usize>,
+        out: &mut Matrix<T, R, C, S>,
+    ) where
+        R: Dim,
+        C: Dim,
+        S: RawStorageMut<T, R, C>,
+    {
+        let (majors, minors) = self.block_lanes(&rows, &cols);
+        let (offsets, indices, data) = self.cs_data();
+        let nnz = indices.len();
+
+        for major in majors.clone() {
+            let lane = offsets[major]..offsets.get(major + 1).copied().unwrap_or(nnz);
+            let lane_indices = &indices[lane.clone()];
+            let lane_data = &data[lane];
+
+            let first = lane_indices.partition_point(|&minor| minor < minors.start);
+
+            for (&minor, value) in lane_indices[first..].iter().zip(&lane_data[first..]) {
+                if minor >= minors.end {
+                    break;
+                }
+
+                let (i, j) = (
+                    CompressionKind::nmajor(major - majors.start, minor - minors.start),
+                    CompressionKind::nminor(major - majors.start, minor - minors.start),
+                );
+
+                out[(i, j)] = value.clone();
+            }
+        }
+    }
+}
+
+impl<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+    CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+where
+    T: Scalar,
+    MajorOffsets: Borrow<[usize]>,
+    MinorIndices: Borrow<[usize]>,
+    Data: BorrowMut<[T]>,
+    CompressionKind: Compression,
+{
+    /// Borrows the major offsets and minor indices of the matrix immutably, and its data mutably.
+    ///
+    /// Only the values can be modified, so the sparsity pattern of the matrix stays valid.
+    pub(crate) fn cs_data_mut(&mut self) -> (&[usize], &[usize], &mut [T]) {
+        (
+            self.offsets.borrow(),
+            self.indices.borrow(),
+            self.data.borrow_mut(),
+        )
+    }
+
+    /// Gets a mutable reference to a value in the sparse matrix from a `(row, column)` index
+    /// pair.
+    ///
+    /// The entry is found by binary search within its lane. This function will return `None` if
+    /// and only if the requested entry is out-of-bounds of the underlying matrix, and
+    /// [`SparseEntryMut::Zero`] if it is not stored, since entries outside of the sparsity pattern
+    /// cannot be modified in place.
+    #[must_use]
+    pub fn get_entry_mut(&mut self, row: usize, column: usize) -> Option<SparseEntryMut<'_, T>> {
+        let position = self.entry_position(row, column)?;
+
+        Some(match position {
+            Some(position) => SparseEntryMut::NonZero(&mut self.data.borrow_mut()[position]),
+            None => SparseEntryMut::Zero,
+        })
+    }
+
+    /// Gets a mutable reference to the stored value at `(row, column)`.
+    ///
+    /// This is the fast path to tweak individual coefficients of an assembled matrix: it finds the
+    /// entry by binary search within its lane, and neither allocates nor changes the sparsity
+    /// pattern. Use [`CsMatrix::get_entry_mut`] if the entry might not be stored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry is out of bounds or not stored in the sparsity pattern.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let mut a = CsrMatrix::<f64>::identity(3);
+    /// *a.entry(1, 1) += 2.0;
+    ///
+    /// assert_eq!(a.get_entry(1, 1).unwrap().into_value(), 3.0);
+    /// ```
+    pub fn entry(&mut self, row: usize, column: usize) -> &mut T {
+        match self.entry_position(row, column) {
+            Some(Some(position)) => &mut self.data.borrow_mut()[position],
+            Some(None) => panic!(
+                "The entry ({}, {}) is not stored in the sparsity pattern.",
+                row, column
+            ),
+            None => panic!(
+                "The entry ({}, {}) is out of bounds for a matrix of shape {:?}.",
+                row, column, self.shape
+            ),
+        }
+    }
+
+    /// The position in the data of the entry `(row, column)`, or `Some(None)` if it is not
+    /// stored, or `None` if it is out of bounds.
+    fn entry_position(&self, row: usize, column: usize) -> Option<Option<usize>> {
+        let (nrows, ncols) = self.shape;
+
+        if row >= nrows || column >= ncols {
+            return None;
+        }
+
+        let major = CompressionKind::nmajor(row, column);
+        let minor = CompressionKind::nminor(row, column);
+        let (offsets, indices, _) = self.cs_data();
+        let start = offsets[major];
+        let end = offsets.get(major + 1).copied().unwrap_or(indices.len());
+
+        Some(
+            indices[start..end]
+                .binary_search(&minor)
+                .ok()
+                .map(|local| start + local),
+        )
+    }
+
+    /// Multiplies every stored value of the matrix by `factor`, in place.
+    ///
+    /// Unlike `matrix * factor`, this does not allocate a new value buffer. Explicit zeros are kept
+    /// in the sparsity pattern, and values which become zero are not removed from it either.
+    pub fn scale_mut(&mut self, factor: T)
+    where
+        T: MulAssign,
+    {
+        for value in self.data.borrow_mut() {
+            *value *= factor.clone();
+        }
+    }
+
+    /// Divides every stored value of the matrix by `divisor`, in place.
+    ///
+    /// Unlike `matrix / divisor`, this does not allocate a new value buffer. No check is made that
+    /// `divisor` is non-zero.
+    pub fn unscale_mut(&mut self, divisor: T)
+    where
+        T: DivAssign,
+    {
+        for value in self.data.borrow_mut() {
+            *value /= divisor.clone();
+        }
+    }
+
+    /// Negates every stored value of the matrix, in place.
+    ///
+    /// Unlike `-matrix`, this does not allocate a new value buffer. The sparsity pattern is left
+    /// as is, including any explicit zeros.
+    pub fn negate_mut(&mut self)
+    where
+        T: Neg<Output = T>,
+    {
+        for value in self.data.borrow_mut() {
+            *value = -value.clone();
+        }
+    }
+
+    /// Overwrites the entries of the main diagonal with `values`.
+    ///
+    /// Only diagonal entries that are already stored in the sparsity pattern can be set, so zero
+    /// values for entries that are not stored are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`OperationErrorKind::InvalidPattern`] if a non-zero value is given for a
+    /// diagonal entry that is not stored in the sparsity pattern. The error lists these entries,
+    /// and the matrix is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` does not have `min(nrows, ncols)` entries.
+    pub fn set_diagonal<R, S>(&mut self, values: &Vector<T, R, S>) -> Result<(), OperationError>
+    where
+        T: Zero,
+        R: Dim,
+        S: RawStorage<T, R>,
+    {
+        let positions = self.stored_diagonal_positions(values)?;
+        let data = self.data.borrow_mut();
+
+        for (position, value) in positions.into_iter().zip(values.iter()) {
+            if let Some(position) = position {
+                data[position] = value.clone();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds `values` to the entries of the main diagonal, e.g. to apply the damping `A + λ I` of
+    /// the Levenberg–Marquardt method without rebuilding the matrix.
+    ///
+    /// Only diagonal entries that are already stored in the sparsity pattern can be updated, so
+    /// zero values for entries that are not stored are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`OperationErrorKind::InvalidPattern`] if a non-zero value is given for a
+    /// diagonal entry that is not stored in the sparsity pattern. The error lists these entries,
+    /// and the matrix is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` does not have `min(nrows, ncols)` entries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra::{DMatrix, DVector};
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let mut a = CsrMatrix::from(&DMatrix::from_row_slice(2, 2, &[1.0, 2.0, 0.0, 4.0]));
+    ///
+    /// a.add_to_diagonal(&DVector::from_element(2, 0.5)).unwrap();
+    /// assert_eq!(a.diagonal(), DVector::from_vec(vec![1.5, 4.5]));
+    ///
+    /// let mut b = CsrMatrix::from(&DMatrix::from_row_slice(2, 2, &[0.0, 2.0, 3.0, 4.0]));
+    /// assert!(b.add_to_diagonal(&DVector::from_element(2, 0.5)).is_err());
+    /// ```
+    pub fn add_to_diagonal<R, S>(&mut self, values: &Vector<T, R, S>) -> Result<(), OperationError>
+    where
+        T: Zero + AddAssign,
+        R: Dim,
+        S: RawStorage<T, R>,
+    {
+        let positions = self.stored_diagonal_positions(values)?;
+        let data = self.data.borrow_mut();
+
+        for (position, value) in positions.into_iter().zip(values.iter()) {
+            if let Some(position) = position {
+                data[position] += value.clone();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The positions of the stored entries of the main diagonal, checking that every non-zero
+    /// entry of `values` has a stored counterpart.
+    fn stored_diagonal_positions<R, S>(
+        &self,
+        values: &Vector<T, R, S>,
+    ) -> Result<Vec<Option<usize>>, OperationError>
+    where
+        T: Zero,
+        R: Dim,
+        S: RawStorage<T, R>,
+    {
+        let positions = self.diagonal_positions(0);
+
+        assert_eq!(
+            values.len(),
+            positions.len(),
+            "The number of values must match the length of the diagonal."
+        );
+
+        let missing: Vec<_> = positions
+            .iter()
+            .zip(values.iter())
+            .enumerate()
+            .filter(|(_, (position, value))| position.is_none() && !value.is_zero())
+            .map(|(i, _)| i)
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!(
+                    "The diagonal entries at {:?} are not stored in the sparsity pattern.",
+                    missing
+                ),
+            ));
+        }
+
+        Ok(positions)
+    }
+}
+
+impl<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+    CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MajorOffsets: Borrow<[usize]>,
+    MinorIndices: Borrow<[usize]>,
+    Data: Borrow<[T]>,
+    CompressionKind: Compression,
+{
+    /// Computes the trace of the matrix, i.e. the sum of the entries on its diagonal.
+    ///
+    /// Returns an error if the matrix is not square. See
+    /// [`sp_cs_trace`](crate::ops::serial::contraction::sp_cs_trace).
+    pub fn trace(&self) -> Result<T, OperationError> {
+        sp_cs_trace(self)
+    }
+
+    /// Computes `tr(self * other)` without forming the product.
+    ///
+    /// `other` must have the transposed compression and the transposed shape of `self`. See
+    /// [`sp_cs_diag_product`](crate::ops::serial::contraction::sp_cs_diag_product).
+    pub fn diag_product<MO, MI, D>(
+        &self,
+        other: &CsMatrix<T, MO, MI, D, CompressionKind::Transpose>,
+    ) -> Result<T, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        sp_cs_diag_product(self, other)
+    }
+
+    /// Computes the Frobenius inner product of `self` and `other`, i.e. the sum of the products of
+    /// the entries they have in common.
+    ///
+    /// `other` must have the same compression and shape as `self`. See
+    /// [`sp_cs_frobenius_inner_product`](crate::ops::serial::contraction::sp_cs_frobenius_inner_product).
+    pub fn frobenius_inner_product<MO, MI, D>(
+        &self,
+        other: &CsMatrix<T, MO, MI, D, CompressionKind>,
+    ) -> Result<T, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        sp_cs_frobenius_inner_product(self, other)
+    }
+}
+
+impl<T, MajorOffsets, MinorIndices, Data>
+    CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressedRowStorage>
+where
+    T: Scalar,
+    MajorOffsets: Borrow<[usize]>,
+    MinorIndices: Borrow<[usize]>,
+    Data: Borrow<[T]>,
+{
+    /// Gets a value in the sparse matrix from a `(row, column)` index pair.
+    ///
+    /// This function will return `None` if and only if the requested entry is out-of-bounds of the
+    /// underlying matrix.
+    #[inline]
+    pub fn get_entry(&self, row: usize, column: usize) -> Option<SparseEntry<'_, T>> {
+        self.get_entry_major_minor(row, column)
+    }
+
+    /// Borrows the rows in `rows` as a matrix with the same columns, without copying their entries.
+    ///
+    /// Only the offsets of the selected rows are copied (and shifted), so this costs
+    /// `O(rows.len())` regardless of the number of entries in the rows. See
+    /// [`CsMatrix::submatrix`] to also select a range of columns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rows` is decreasing or extends past the number of rows of the matrix.
+    #[must_use]
+    pub fn rows(
+        &self,
+        rows: Range<usize>,
+    ) -> CsMatrix<T, Vec<usize>, &[usize], &[T], CompressedRowStorage> {
+        self.block_lanes(&rows, &(0..self.ncols()));
+
+        let nrows = rows.len();
+        let (offsets, indices, data) = self.lane_range_parts(rows);
+
+        unsafe { CsMatrix::from_parts_unchecked(nrows, self.ncols(), offsets, indices, data) }
+    }
+
+    /// Borrows row `row` of the matrix as a sparse vector, without copying.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` is out of bounds.
+    #[must_use]
+    pub fn row(&self, row: usize) -> CsVectorView<'_, T> {
+        self.lane_vector(row).expect("Row index out of bounds.")
+    }
+}
+
+impl<T, MajorOffsets, MinorIndices, Data>
+    CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressedColumnStorage>
+where
+    T: Scalar,
+    MajorOffsets: Borrow<[usize]>,
+    MinorIndices: Borrow<[usize]>,
+    Data: Borrow<[T]>,
+{
+    /// Gets a value in the sparse matrix from a `(row, column)` index pair.
+    ///
+    /// This function will return `None` if and only if the requested entry is out-of-bounds of the
+    /// underlying matrix.
+    #[inline]
+    pub fn get_entry(&self, row: usize, column: usize) -> Option<SparseEntry<'_, T>> {
+        self.get_entry_major_minor(column, row)
+    }
+
+    /// Borrows the columns in `cols` as a matrix with the same rows, without copying their
+    /// entries.
+    ///
+    /// Only the offsets of the selected columns are copied (and shifted), so this costs
+    /// `O(cols.len())` regardless of the number of entries in the columns. See
+    /// [`CsMatrix::submatrix`] to also select a range of rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cols` is decreasing or extends past the number of columns of the matrix.
+    #[must_use]
+    pub fn columns(
+        &self,
+        cols: Range<usize>,
+    ) -> CsMatrix<T, Vec<usize>, &[usize], &[T], CompressedColumnStorage> {
+        self.block_lanes(&(0..self.nrows()), &cols);
+
+        let ncols = cols.len();
+        let (offsets, indices, data) = self.lane_range_parts(cols);
+
+        unsafe { CsMatrix::from_parts_unchecked(self.nrows(), ncols, offsets, indices, data) }
+    }
+
+    /// Borrows column `col` of the matrix as a sparse vector, without copying.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `col` is out of bounds.
+    #[must_use]
+    pub fn col(&self, col: usize) -> CsVectorView<'_, T> {
+        self.lane_vector(col).expect("Column index out of bounds.")
+    }
+}
+
+/// A compressed sparse matrix, abstracting over the direction of its compression and the
+/// storage of its data.
+///
+/// Every [`CsMatrix`] implements this trait, so generic algorithms can accept either a CSR or a
+/// CSC matrix (owned or borrowed) through a single bound, instead of being written once for every
+/// compression:
+///
+/// ```
+/// use nalgebra_sparse::cs::{CompressedMatrix, CscMatrix, CsrMatrix};
+///
+/// /// Sums the entries of every row, regardless of how the matrix is compressed.
+/// fn row_sums<M: CompressedMatrix<f64>>(matrix: &M) -> Vec<f64> {
+///     let mut sums = vec![0.0; matrix.nrows()];
+///
+///     for (row, _, value) in matrix.row_col_iter() {
+///         sums[row] += value;
+///     }
+///
+///     sums
+/// }
+///
+/// let csr = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![0, 0, 1], vec![1.0, 2.0, 3.0])
+///     .unwrap();
+/// let csc = CscMatrix::from(csr.clone());
+///
+/// assert_eq!(row_sums(&csr), vec![1.0, 5.0]);
+/// assert_eq!(row_sums(&csc), vec![1.0, 5.0]);
+/// ```
+///
+/// Algorithms that need direct access to the compressed representation can work on the lanes
+/// (see [`CompressedMatrix::lanes`]) and translate `(major, minor)` index pairs back into
+/// `(row, column)` pairs with [`CompressedMatrix::row_col`]. Functions that take a `CsMatrix` can
+/// be called through [`CompressedMatrix::to_view`].
+pub trait CompressedMatrix<T: Scalar> {
+    /// The compression of the matrix, i.e. [`CompressedRowStorage`] or
+    /// [`CompressedColumnStorage`].
+    type Compression: Compression;
+
+    /// The shape of the matrix, as `(nrows, ncols)`.
+    fn shape(&self) -> (usize, usize);
+
+    /// The number of rows in the matrix.
+    fn nrows(&self) -> usize {
+        self.shape().0
+    }
+
+    /// The number of columns in the matrix.
+    fn ncols(&self) -> usize {
+        self.shape().1
+    }
+
+    /// The number of lanes along the major (compressed) dimension.
+    fn nmajor(&self) -> usize {
+        let (nrows, ncols) = self.shape();
+        Self::Compression::nmajor(nrows, ncols)
+    }
+
+    /// The length of every lane, i.e. the size of the minor dimension.
+    fn nminor(&self) -> usize {
+        let (nrows, ncols) = self.shape();
+        Self::Compression::nminor(nrows, ncols)
+    }
+
+    /// The number of explicitly stored entries in the matrix.
+    fn nnz(&self) -> usize;
+
+    /// Borrows the major offsets, minor indices and data of the compressed representation.
+    fn cs_data(&self) -> (&[usize], &[usize], &[T]);
+
+    /// Borrows the matrix as a `CsMatrix` view with the same compression.
+    fn to_view(&self) -> CsMatrix<T, &[usize], &[usize], &[T], Self::Compression>;
+
+    /// An iterator over the explicit entries of the lane with the given major index, or `None` if
+    /// the index is out of bounds.
+    fn lane(&self, major_index: usize) -> Option<CsLaneIter<'_, T>>;
+
+    /// An iterator over all the lanes of the matrix, in order of their major index.
+    fn lanes(&self) -> CsMatrixIter<'_, T>;
+
+    /// Gets a value in the sparse matrix from a `(row, column)` index pair.
+    ///
+    /// This function will return `None` if and only if the requested entry is out-of-bounds of the
+    /// underlying matrix.
+    fn get_entry(&self, row: usize, column: usize) -> Option<SparseEntry<'_, T>>;
+
+    /// Translates a `(major, minor)` index pair of the compressed representation into a
+    /// `(row, column)` pair.
+    ///
+    /// As this is an involution, it equally translates a `(row, column)` pair into a
+    /// `(major, minor)` pair.
+    fn row_col(major_index: usize, minor_index: usize) -> (usize, usize) {
+        (
+            Self::Compression::nmajor(major_index, minor_index),
+            Self::Compression::nminor(major_index, minor_index),
+        )
+    }
+
+    /// An iterator over every explicit entry as a `(row, column, value)` triplet, in the order in
+    /// which they are stored.
+    fn row_col_iter(&self) -> CsRowColIter<'_, T, Self::Compression> {
+        CsRowColIter {
+            lanes: self.lanes().enumerate(),
+            current: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, MajorOffsets, MinorIndices, Data, CompressionKind> CompressedMatrix<T>
+    for CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+where
+    T: Scalar,
+    MajorOffsets: Borrow<[usize]>,
+    MinorIndices: Borrow<[usize]>,
+    Data: Borrow<[T]>,
+    CompressionKind: Compression,
+{
+    type Compression = CompressionKind;
+
+    fn shape(&self) -> (usize, usize) {
+        CsMatrix::shape(self)
+    }
+
+    fn nnz(&self) -> usize {
+        CsMatrix::nnz(self)
+    }
+
+    fn cs_data(&self) -> (&[usize], &[usize], &[T]) {
+        CsMatrix::cs_data(self)
+    }
+
+    fn to_view(&self) -> CsMatrix<T, &[usize], &[usize], &[T], CompressionKind> {
+        CsMatrix::to_view(self)
+    }
+
+    fn lane(&self, major_index: usize) -> Option<CsLaneIter<'_, T>> {
+        self.get_lane(major_index)
+    }
+
+    fn lanes(&self) -> CsMatrixIter<'_, T> {
+        self.iter()
+    }
+
+    fn get_entry(&self, row: usize, column: usize) -> Option<SparseEntry<'_, T>> {
+        let (major_index, minor_index) = Self::row_col(row, column);
+        self.get_entry_major_minor(major_index, minor_index)
+    }
+}
+
+impl<T, C> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C>
+where
+    T: Scalar,
+    C: Compression,
+{
+    /// Returns an owned `CsMatrix` of shape `(nrows, ncols)` entirely comprised of implicit zeros,
+    pub fn zeros(nrows: usize, ncols: usize) -> Self {
+        let nmajor = C::nmajor(nrows, ncols);
+
+        Self {
+            shape: (nrows, ncols),
+            offsets: vec![0; nmajor],
+            indices: Vec::new(),
+            data: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a matrix from a sparsity pattern and one value for every explicit entry of the
+    /// pattern, in major -> minor order.
+    ///
+    /// The major lanes of the pattern become the major lanes of the matrix, so the pattern's
+    /// major dimension is the number of rows of a CSR matrix and the number of columns of a CSC
+    /// matrix.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SparseFormatError`] if the number of values is not equal to the number of
+    /// explicit entries in the pattern.
+    pub fn try_from_pattern_and_values(
+        pattern: SparsityPattern,
+        data: Vec<T>,
+    ) -> Result<Self, SparseFormatError> {
+        if pattern.nnz() != data.len() {
+            return Err(SparsityPatternFormatError::DataAndIndicesSizeMismatch.into());
+        }
+
+        let nmajor = pattern.major_dim();
+        let nminor = pattern.minor_dim();
+        let (offsets, indices) = pattern.disassemble();
+
+        Ok(Self {
+            shape: (C::nmajor(nmajor, nminor), C::nminor(nmajor, nminor)),
+            offsets,
+            indices,
+            data,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Moves the offsets and indices of the matrix behind reference counts, so that the pattern
+    /// can be shared with other matrices. See [`SharedCsrMatrix`].
+    #[must_use]
+    pub fn into_shared(self) -> CsMatrix<T, Arc<[usize]>, Arc<[usize]>, Vec<T>, C> {
+        CsMatrix {
+            shape: self.shape,
+            offsets: self.offsets.into(),
+            indices: self.indices.into(),
+            data: self.data,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Concatenates `blocks` along the diagonal, i.e. assembles the block diagonal matrix
+    /// `diag(B₀, B₁, ...)`.
+    ///
+    /// The blocks may have any shape, including empty ones. Every block is copied lane by lane in a
+    /// single pass, and the offsets, indices and values of the result are allocated with their
+    /// exact final sizes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra::DMatrix;
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let a = CsrMatrix::from(&DMatrix::from_row_slice(2, 2, &[1, 2, 0, 3]));
+    /// let b = CsrMatrix::<i32>::identity(1);
+    ///
+    /// let block_diag = CsrMatrix::block_diag(&[&a, &b]);
+    ///
+    /// assert_eq!(
+    ///     DMatrix::from(&block_diag),
+    ///     DMatrix::from_row_slice(3, 3, &[1, 2, 0, 0, 3, 0, 0, 0, 1])
+    /// );
+    /// ```
+    pub fn block_diag<MO, MI, D>(blocks: &[&CsMatrix<T, MO, MI, D, C>]) -> Self
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let nrows = blocks.iter().map(|block| block.nrows()).sum();
+        let ncols = blocks.iter().map(|block| block.ncols()).sum();
+        let nnz = blocks.iter().map(|block| block.nnz()).sum();
+
+        let mut offsets = Vec::with_capacity(C::nmajor(nrows, ncols));
+        let mut indices = Vec::with_capacity(nnz);
+        let mut data = Vec::with_capacity(nnz);
+        let mut minor_offset = 0;
+
+        for block in blocks {
+            let (block_offsets, block_indices, block_data) = block.cs_data();
+            let base = indices.len();
+
+            offsets.extend(block_offsets.iter().map(|&offset| base + offset));
+            indices.extend(block_indices.iter().map(|&index| minor_offset + index));
+            data.extend_from_slice(block_data);
+
+            minor_offset += C::nminor(block.nrows(), block.ncols());
+        }
+
+        Self {
+            shape: (nrows, ncols),
+            offsets,
+            indices,
+            data,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a matrix by evaluating `f(row, column)` at every position of the matrix, storing
+    /// an explicit entry wherever it returns `Some`.
+    ///
+    /// The positions are visited in major -> minor order. This makes `nrows * ncols` calls, so for
+    /// banded matrices (e.g. stencil operators) prefer [`from_fn_banded`](Self::from_fn_banded).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let matrix = CsrMatrix::from_fn(3, 3, |i, j| if i <= j { Some(i + j) } else { None });
+    ///
+    /// assert_eq!(matrix.nnz(), 6);
+    /// ```
+    pub fn from_fn<F>(nrows: usize, ncols: usize, f: F) -> Self
+    where
+        F: FnMut(usize, usize) -> Option<T>,
+    {
+        Self::from_fn_within(nrows, ncols, usize::MAX, f)
+    }
+
+    /// Like [`from_fn`](Self::from_fn), but only evaluates `f(row, column)` for the positions with
+    /// `|row - column| <= bandwidth`. All other positions are implicit zeros.
+    ///
+    /// This makes `O((2 * bandwidth + 1) * nmajor)` calls instead of `nrows * ncols`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// // The 1D Laplacian stencil.
+    /// let laplacian = CsrMatrix::from_fn_banded(4, 4, 1, |i, j| {
+    ///     Some(if i == j { 2.0 } else { -1.0 })
+    /// });
+    ///
+    /// assert_eq!(laplacian.nnz(), 10);
+    /// ```
+    pub fn from_fn_banded<F>(nrows: usize, ncols: usize, bandwidth: usize, f: F) -> Self
+    where
+        F: FnMut(usize, usize) -> Option<T>,
+    {
+        Self::from_fn_within(nrows, ncols, bandwidth, f)
+    }
+
+    fn from_fn_within<F>(nrows: usize, ncols: usize, bandwidth: usize, mut f: F) -> Self
+    where
+        F: FnMut(usize, usize) -> Option<T>,
+    {
+        let nmajor = C::nmajor(nrows, ncols);
+        let nminor = C::nminor(nrows, ncols);
+
+        let mut offsets = Vec::with_capacity(nmajor);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+
+        for major in 0..nmajor {
+            offsets.push(indices.len());
+
+            let lower = major.saturating_sub(bandwidth);
+            let upper = major
+                .saturating_add(bandwidth)
+                .saturating_add(1)
+                .min(nminor);
+
+            for minor in lower..upper {
+                if let Some(value) = f(C::nmajor(major, minor), C::nminor(major, minor)) {
+                    indices.push(minor);
+                    data.push(value);
+                }
+            }
+        }
+
+        unsafe { Self::from_parts_unchecked(nrows, ncols, offsets, indices, data) }
+    }
+
+    /// Takes the transpose of the current matrix by taking ownership of the underlying data.
+    ///
+    /// Behaves like [`CsMatrix::transpose`], but takes `self` instead of `&self`.
+    pub fn transpose_owned(self) -> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C::Transpose> {
+        let (nrows, ncols) = self.shape;
+
+        CsMatrix {
+            shape: (ncols, nrows),
+            offsets: self.offsets,
+            indices: self.indices,
+            data: self.data,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Removes every explicitly stored zero from the sparsity pattern, in place.
+    ///
+    /// Chains of additions and subtractions leave explicit zeros wherever entries cancel out, and
+    /// every subsequent operation has to visit them. This compacts the offsets, indices and values
+    /// without reallocating, and returns the number of removed entries. Call `shrink_to_fit` on
+    /// the parts (see [`disassemble`](Self::disassemble)) to also release the memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let a = CsrMatrix::<i32>::identity(3);
+    /// let mut difference = &a - &a;
+    /// assert_eq!(difference.nnz(), 3);
+    ///
+    /// assert_eq!(difference.prune_zeros(), 3);
+    /// assert_eq!(difference.nnz(), 0);
+    /// ```
+    pub fn prune_zeros(&mut self) -> usize
+    where
+        T: Zero,
+    {
+        self.compact(|_, _, value| !value.is_zero())
+    }
+
+    /// Removes every stored entry whose magnitude is smaller than `tolerance` from the sparsity
+    /// pattern, in place, and returns the number of removed entries.
+    ///
+    /// This behaves like [`prune_zeros`](Self::prune_zeros), but also drops the tiny values that
+    /// floating-point cancellation leaves behind. A `tolerance` of zero removes nothing.
+    pub fn drop_small(&mut self, tolerance: T) -> usize
+    where
+        T: RealField,
+    {
+        self.compact(|_, _, value| value.clone().abs() >= tolerance)
+    }
+
+    /// Keeps only the entries `(row, column, value)` for which `keep` returns `true`, and returns
+    /// the number of removed entries.
+    ///
+    /// The offsets, indices and values are compacted in place in a single pass, without
+    /// reallocating. This is e.g. how the rows and columns of Dirichlet boundary nodes are masked
+    /// out of an assembled system.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra_sparse::cs::CscMatrix;
+    ///
+    /// let mut a = CscMatrix::<f64>::identity(4) * 2.0;
+    /// let boundary = [false, true, false, true];
+    ///
+    /// assert_eq!(a.retain(|i, j, _| !boundary[i] && !boundary[j]), 2);
+    /// assert_eq!(a.nnz(), 2);
+    /// ```
+    pub fn retain<F>(&mut self, mut keep: F) -> usize
+    where
+        F: FnMut(usize, usize, &T) -> bool,
+    {
+        self.compact(|major, minor, value| {
+            keep(C::nmajor(major, minor), C::nminor(major, minor), value)
+        })
+    }
+
+    /// Keeps the entries `(major, minor, value)` for which `keep` returns `true`, compacting the
+    /// storage in place, and returns the number of removed entries.
+    fn compact<F>(&mut self, mut keep: F) -> usize
+    where
+        F: FnMut(usize, usize, &T) -> bool,
+    {
+        let nnz = self.indices.len();
+        let mut write = 0;
+
+        for major in 0..self.offsets.len() {
+            let start = self.offsets[major];
+            let end = self.offsets.get(major + 1).copied().unwrap_or(nnz);
+            self.offsets[major] = write;
+
+            for read in start..end {
+                if keep(major, self.indices[read], &self.data[read]) {
+                    self.indices[write] = self.indices[read];
+                    self.data.swap(write, read);
+                    write += 1;
+                }
+            }
+        }
+
+        self.indices.truncate(write);
+        self.data.truncate(write);
+
+        nnz - write
+    }
+}
+
+impl<T, C> CsMatrix<T, Arc<[usize]>, Arc<[usize]>, Vec<T>, C>
+where
+    T: Scalar,
+    C: Compression,
+{
+    /// Creates a matrix with the values `data` that shares the sparsity pattern of this matrix.
+    ///
+    /// The values are given in the same order as the values of this matrix. No part of the
+    /// pattern is copied, and [`same_pattern`](CsMatrix::same_pattern) recognizes the two matrices
+    /// in `O(1)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SparseFormatError`] if the number of values is not equal to the number of
+    /// explicit entries in the pattern.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let a = CsrMatrix::<f64>::identity(3).into_shared();
+    /// let b = a.with_values(vec![1.0, 2.0, 3.0]).unwrap();
+    ///
+    /// assert!(a.same_pattern(&b));
+    /// assert_eq!(b.cs_data().2, &[1.0, 2.0, 3.0]);
+    /// ```
+    pub fn with_values<U: Scalar>(
+        &self,
+        data: Vec<U>,
+    ) -> Result<CsMatrix<U, Arc<[usize]>, Arc<[usize]>, Vec<U>, C>, SparseFormatError> {
+        if data.len() != self.nnz() {
+            return Err(SparsityPatternFormatError::DataAndIndicesSizeMismatch.into());
+        }
+
+        Ok(CsMatrix {
+            shape: self.shape,
+            offsets: Arc::clone(&self.offsets),
+            indices: Arc::clone(&self.indices),
+            data,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+/// What [`CsMatrix::add_dense_block`] does with the non-zero entries of a dense block that fall
+/// outside of the sparsity pattern of the matrix.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum MissingEntries {
+    /// Fail with an [`OperationErrorKind::InvalidPattern`](crate::error::OperationErrorKind)
+    /// error, leaving the matrix unchanged.
+    #[default]
+    Error,
+
+    /// Insert the missing entries into the sparsity pattern of the matrix.
+    ///
+    /// This has to rebuild the indices and data of the matrix, so it is much slower than adding
+    /// into entries that are already stored.
+    Extend,
+}
+
+impl<T, C> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C>
+where
+    T: Scalar + Zero + AddAssign,
+    C: Compression,
+{
+    /// Adds a dense block into the rectangular region of the matrix spanned by `rows` and `cols`.
+    ///
+    /// This is the inverse of [`CsMatrix::to_dense_block`], and scatters e.g. an updated frontal
+    /// matrix or element matrix back into a sparse matrix. Entries of `block` are added into the
+    /// stored entries at the same position. Zero entries of `block` are skipped when they have no
+    /// stored counterpart, and `missing` decides what happens to the non-zero ones.
+    ///
+    /// # Errors
+    ///
+    /// With [`MissingEntries::Error`], this function fails and produces an [`OperationError`] with
+    /// kind [`OperationErrorKind::InvalidPattern`](crate::error::OperationErrorKind) if a non-zero
+    /// entry of `block` has no stored counterpart. The matrix is left unchanged in that case.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either range is decreasing or extends past the shape of the matrix, or if the
+    /// shape of `block` is not `(rows.len(), cols.len())`.
+    pub fn add_dense_block<R, Cb, S>(
+        &mut self,
+        rows: Range<usize>,
+        cols: Range<usize>,
+        block: &Matrix<T, R, Cb, S>,
+        missing: MissingEntries,
+    ) -> Result<(), OperationError>
+    where
+        R: Dim,
+        Cb: Dim,
+        S: RawStorage<T, R, Cb>,
+    {
+        assert_eq!(
+            block.shape(),
+            (rows.len(), cols.len()),
+            "The dense block must have the shape of the region it is added to."
+        );
+
+        let (majors, minors) = self.block_lanes(&rows, &cols);
+        let nnz = self.indices.len();
+
+        let lane_of = |offsets: &[usize], major: usize| {
+            offsets[major]..offsets.get(major + 1).copied().unwrap_or(nnz)
+        };
+        let block_value = |major: usize, minor: usize| {
+            let (a, b) = (major - majors.start, minor - minors.start);
+            &block[(C::nmajor(a, b), C::nminor(a, b))]
+        };
+
+        let mut misses = 0;
+
+        for major in majors.clone() {
+            let lane = lane_of(&self.offsets, major);
+            let lane_indices = &self.indices[lane];
+            let mut k = lane_indices.partition_point(|&minor| minor < minors.start);
+
+            for minor in minors.clone() {
+                if lane_indices.get(k) == Some(&minor) {
+                    k += 1;
+                } else if !block_value(major, minor).is_zero() {
+                    misses += 1;
+                }
+            }
+        }
+
+        if misses == 0 {
+            for major in majors.clone() {
+                let lane = lane_of(&self.offsets, major);
+                let first =
+                    lane.start + self.indices[lane.clone()].partition_point(|&m| m < minors.start);
+
+                for k in first..lane.end {
+                    let minor = self.indices[k];
+
+                    if minor >= minors.end {
+                        break;
+                    }
+
+                    self.data[k] += block_value(major, minor).clone();
+                }
+            }
+
+            return Ok(());
+        }
+
+        if missing == MissingEntries::Error {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!(
+                    "{} non-zero entries of the dense block fall outside of the sparsity pattern",
+                    misses
+                ),
+            ));
+        }
+
+        let mut offsets = Vec::with_capacity(self.offsets.len());
+        let mut indices = Vec::with_capacity(nnz + misses);
+        let mut data = Vec::with_capacity(nnz + misses);
+
+        for major in 0..self.offsets.len() {
+            offsets.push(indices.len());
+
+            let lane = lane_of(&self.offsets, major);
+
+            if !majors.contains(&major) {
+                indices.extend_from_slice(&self.indices[lane.clone()]);
+                data.extend_from_slice(&self.data[lane]);
+                continue;
+            }
+
+            let mut k = lane.start;
+
+            while k < lane.end && self.indices[k] < minors.start {
+                indices.push(self.indices[k]);
+                data.push(self.data[k].clone());
+                k += 1;
+            }
+
+            for minor in minors.clone() {
+                let value = block_value(major, minor).clone();
+
+                if k < lane.end && self.indices[k] == minor {
+                    let mut sum = self.data[k].clone();
+                    sum += value;
+
+                    indices.push(minor);
+                    data.push(sum);
+                    k += 1;
+                } else if !value.is_zero() {
+                    indices.push(minor);
+                    data.push(value);
+                }
+            }
+
+            indices.extend_from_slice(&self.indices[k..lane.end]);
+            data.extend_from_slice(&self.data[k..lane.end]);
+        }
+
+        self.offsets = offsets;
+        self.indices = indices;
+        self.data = data;
+
+        Ok(())
+    }
+}
+
+impl<T, C> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C>
+where
+    T: Scalar + One,
+    C: Compression,
+{
+    /// Produces an owned identity matrix of shape `(n, n)` in CSC format.
+    #[inline]
+    pub fn identity(n: usize) -> Self {
+        let offsets = (0..n).collect();
+        let indices = (0..n).collect();
+        let data = vec![T::one(); n];
+
+        Self {
+            shape: (n, n),
+            offsets,
+            indices,
+            data,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Checks that `offsets` and `indices` describe a valid compressed sparsity pattern with `nmajor`
+/// lanes of length `nminor`.
+///
+/// This performs every check done by [`CsMatrix::try_from_parts`] except for the check that the
+/// data and indices have the same length.
+pub(crate) fn validate_pattern(
+    nmajor: usize,
+    nminor: usize,
+    offsets: &[usize],
+    indices: &[usize],
+) -> Result<(), SparseFormatError> {
+    Ok(check_pattern(nmajor, nminor, offsets, indices)?)
+}
+
+/// Like [`validate_pattern`], but returns the underlying [`SparsityPatternFormatError`].
+pub(crate) fn check_pattern(
+    nmajor: usize,
+    nminor: usize,
+    offsets: &[usize],
+    indices: &[usize],
+) -> Result<(), SparsityPatternFormatError> {
+    if offsets.len() != nmajor {
+        // size mismatch
+        return Err(SparsityPatternFormatError::InvalidOffsetArrayLength);
+    }
+
+    if let Some(first) = offsets.first() {
+        if *first != 0 {
+            // First entry exists and is not zero
+            return Err(SparsityPatternFormatError::InvalidFirstOffset);
+        }
+    }
+
+    if offsets.windows(2).any(|pair| pair[0] > pair[1])
+        || matches!(offsets.last(), Some(&last) if last > indices.len())
+    {
+        // Offsets do not monotonically increase, or the final lane starts past the end of the
+        // indices. Either way, some lane could not be sliced out of the indices.
+        return Err(SparsityPatternFormatError::NonmonotonicOffsets);
+    }
+
+    if indices.iter().any(|&index| index >= nminor) {
+        // Index out-of-bounds
+        return Err(SparsityPatternFormatError::MinorIndexOutOfBounds);
+    }
+
+    for major_index in 0..nmajor {
+        let lower = offsets[major_index];
+
+        let lane_indices = if major_index + 1 < nmajor {
+            &indices[lower..offsets[major_index + 1]]
+        } else {
+            &indices[lower..]
+        };
+
+        if !lane_indices.is_empty() {
+            if let Some(err) = lane_indices
+                .iter()
+                .zip(&lane_indices[1..])
+                .filter_map(|(lower_index, upper_index)| {
+                    match lower_index.cmp(upper_index) {
+                        Ordering::Less => None,
+                        Ordering::Equal => {
+                            // Duplicates detected
+                            Some(Err(SparsityPatternFormatError::DuplicateEntry))
+                        }
+                        Ordering::Greater => {
+                            // Indices in lane do not monotonically increase
+                            Some(Err(SparsityPatternFormatError::NonmonotonicMinorIndices))
+                        }
+                    }
+                })
+                .next()
+            {
+                return err;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl<T> CsrMatrix<T>
+where
+    T: Scalar,
+{
+    /// Creates a matrix with `nrows` rows from a function producing the `(column, value)` entries
+    /// of every row, in strictly increasing order of column.
+    ///
+    /// This is the lane-wise counterpart of [`from_fn`](Self::from_fn), for operators whose
+    /// entries are easier to enumerate than to test for, such as stencils.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`CsrBuilder::push_row`] if the entries of a row are out of bounds or not
+    /// strictly increasing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nalgebra_sparse::cs::CsrMatrix;
+    ///
+    /// let n = 4;
+    /// let laplacian = CsrMatrix::from_row_fn(n, n, |i| {
+    ///     let lower = (i > 0).then(|| (i - 1, -1.0));
+    ///     let upper = (i + 1 < n).then(|| (i + 1, -1.0));
+    ///
+    ///     lower.into_iter().chain(Some((i, 2.0))).chain(upper)
+    /// })
+    /// .unwrap();
+    ///
+    /// assert_eq!(laplacian.nnz(), 10);
+    /// ```
+    pub fn from_row_fn<F, I>(
+        nrows: usize,
+        ncols: usize,
+        mut f: F,
+    ) -> Result<Self, SparseFormatError>
+    where
+        F: FnMut(usize) -> I,
+        I: IntoIterator<Item = (usize, T)>,
+    {
+        let mut builder = CsrBuilder::with_capacity(nrows, ncols, 0);
+
+        for i in 0..nrows {
+            let (indices, values): (Vec<_>, Vec<_>) = f(i).into_iter().unzip();
+            builder.push_row(indices, values)?;
+        }
+
+        Ok(builder.build())
     }
 }
 
-impl<T, MajorOffsets, MinorIndices, Data>
-    CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressedColumnStorage>
-where
-    T: Scalar,
-    MajorOffsets: Borrow<[usize]>,
-    MinorIndices: Borrow<[usize]>,
-    Data: Borrow<[T]>,
-{
-    /// Gets a value in the sparse matrix from a `(row, column)` index pair.
-    ///
-    /// This function will return `None` if and only if the requested entry is out-of-bounds of the
-    /// underlying matrix.
-    #[inline]
-    pub fn get_entry(&self, row: usize, column: usize) -> Option<SparseEntry<'_, T>> {
-        self.get_entry_major_minor(column, row)
-    }
+/// A builder which assembles a [`CsrMatrix`] one row at a time.
+///
+/// This is intended for producers that generate the rows of a matrix in order, such as file
+/// parsers or generators. Every row is appended directly to the compressed buffers, so unlike
+/// collecting into a [`CooMatrix`](crate::coo::CooMatrix) first, no row indices are stored and no
+/// sorting or conversion is needed once all rows have been pushed.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra_sparse::cs::CsrBuilder;
+///
+/// let mut builder = CsrBuilder::new(3);
+///
+/// builder.push_row(vec![0, 2], vec![1.0, 2.0]).unwrap();
+/// builder.push_row(vec![], vec![]).unwrap();
+/// builder.push_row(vec![1], vec![3.0]).unwrap();
+///
+/// let matrix = builder.build();
+///
+/// assert_eq!(matrix.shape(), (3, 3));
+/// assert_eq!(matrix.cs_data(), (&[0, 2, 2][..], &[0, 2, 1][..], &[1.0, 2.0, 3.0][..]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrBuilder<T> {
+    ncols: usize,
+    offsets: Vec<usize>,
+    indices: Vec<usize>,
+    data: Vec<T>,
 }
 
-impl<T, C> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C>
+impl<T> CsrBuilder<T>
 where
     T: Scalar,
-    C: Compression,
 {
-    /// Returns an owned `CsMatrix` of shape `(nrows, ncols)` entirely comprised of implicit zeros,
-    pub fn zeros(nrows: usize, ncols: usize) -> Self {
-        let nmajor = C::nmajor(nrows, ncols);
+    /// Creates an empty builder for a matrix with `ncols` columns.
+    pub fn new(ncols: usize) -> Self {
+        Self::with_capacity(0, ncols, 0)
+    }
 
+    /// Creates an empty builder for a matrix with `ncols` columns, with space reserved for `nrows`
+    /// rows and `nnz` explicit entries.
+    ///
+    /// `nrows` is only a capacity hint: the number of rows of the matrix is the number of rows
+    /// that are pushed.
+    pub fn with_capacity(nrows: usize, ncols: usize, nnz: usize) -> Self {
         Self {
-            shape: (nrows, ncols),
-            offsets: vec![0; nmajor],
-            indices: Vec::new(),
-            data: Vec::new(),
-            _phantom: PhantomData,
+            ncols,
+            offsets: Vec::with_capacity(nrows),
+            indices: Vec::with_capacity(nnz),
+            data: Vec::with_capacity(nnz),
         }
     }
 
-    /// Takes the transpose of the current matrix by taking ownership of the underlying data.
+    /// The number of rows pushed so far.
+    #[must_use]
+    pub fn nrows(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// The number of columns of the matrix being built.
+    #[must_use]
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// The number of explicit entries pushed so far.
+    #[must_use]
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Appends a row to the matrix, given the column indices of its entries in strictly
+    /// increasing order and their values.
     ///
-    /// Behaves like [`CsMatrix::transpose`], but takes `self` instead of `&self`.
-    pub fn transpose_owned(self) -> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C::Transpose> {
-        let (nrows, ncols) = self.shape;
+    /// # Errors
+    ///
+    /// Fails if the number of indices and values differ, if a column index is out of bounds, or if
+    /// the indices are not strictly increasing. The builder is left unchanged in that case, so
+    /// pushing can carry on with the next row.
+    pub fn push_row<I, V>(&mut self, sorted_indices: I, values: V) -> Result<(), SparseFormatError>
+    where
+        I: IntoIterator<Item = usize>,
+        V: IntoIterator<Item = T>,
+    {
+        let start = self.indices.len();
+
+        match self.extend_row(sorted_indices, values) {
+            Ok(()) => {
+                self.offsets.push(start);
+                Ok(())
+            }
+            Err(err) => {
+                self.indices.truncate(start);
+                self.data.truncate(start);
+                Err(err.into())
+            }
+        }
+    }
 
-        CsMatrix {
-            shape: (ncols, nrows),
-            offsets: self.offsets,
-            indices: self.indices,
-            data: self.data,
-            _phantom: PhantomData,
+    /// Consumes the builder and returns the matrix, whose number of rows is the number of rows
+    /// that were pushed.
+    #[must_use]
+    pub fn build(self) -> CsrMatrix<T> {
+        let nrows = self.offsets.len();
+
+        // Every row was validated as it was pushed.
+        unsafe {
+            CsMatrix::from_parts_unchecked(nrows, self.ncols, self.offsets, self.indices, self.data)
         }
     }
-}
 
-impl<T, C> CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C>
-where
-    T: Scalar + One,
-    C: Compression,
-{
-    /// Produces an owned identity matrix of shape `(n, n)` in CSC format.
-    #[inline]
-    pub fn identity(n: usize) -> Self {
-        let offsets = (0..n).collect();
-        let indices = (0..n).collect();
-        let data = vec![T::one(); n];
+    fn extend_row<I, V>(
+        &mut self,
+        sorted_indices: I,
+        values: V,
+    ) -> Result<(), SparsityPatternFormatError>
+    where
+        I: IntoIterator<Item = usize>,
+        V: IntoIterator<Item = T>,
+    {
+        let start = self.indices.len();
+
+        let mut indices = sorted_indices.into_iter();
+        let mut values = values.into_iter();
+
+        loop {
+            let (index, value) = match (indices.next(), values.next()) {
+                (Some(index), Some(value)) => (index, value),
+                (None, None) => return Ok(()),
+                _ => return Err(SparsityPatternFormatError::DataAndIndicesSizeMismatch),
+            };
 
-        Self {
-            shape: (n, n),
-            offsets,
-            indices,
-            data,
-            _phantom: PhantomData,
+            if index >= self.ncols {
+                return Err(SparsityPatternFormatError::MinorIndexOutOfBounds);
+            }
+
+            match self.indices[start..]
+                .last()
+                .map(|previous| previous.cmp(&index))
+            {
+                Some(Ordering::Equal) => return Err(SparsityPatternFormatError::DuplicateEntry),
+                Some(Ordering::Greater) => {
+                    return Err(SparsityPatternFormatError::NonmonotonicMinorIndices)
+                }
+                _ => {}
+            }
+
+            self.indices.push(index);
+            self.data.push(value);
         }
     }
 }
@@ -1024,11 +3235,45 @@ impl<'a, T> Iterator for CsMinorLaneIter<'a, T> {
     }
 }
 
+/// An iterator over the explicit entries of a compressed sparse matrix as `(row, column, value)`
+/// triplets.
+///
+/// See [`CompressedMatrix::row_col_iter`].
+#[derive(Debug, Clone)]
+pub struct CsRowColIter<'a, T, C> {
+    lanes: std::iter::Enumerate<CsMatrixIter<'a, T>>,
+    current: Option<(usize, CsLaneIter<'a, T>)>,
+    _phantom: PhantomData<C>,
+}
+
+impl<'a, T, C> Iterator for CsRowColIter<'a, T, C>
+where
+    C: Compression,
+{
+    type Item = (usize, usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((major_index, lane)) = &mut self.current {
+                if let Some((minor_index, value)) = lane.next() {
+                    return Some((
+                        C::nmajor(*major_index, minor_index),
+                        C::nminor(*major_index, minor_index),
+                        value,
+                    ));
+                }
+            }
+
+            self.current = Some(self.lanes.next()?);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{error::*, proptest::*};
-    use nalgebra::{DMatrix, SMatrix};
+    use nalgebra::{proptest::matrix, DMatrix, SMatrix};
     use proptest::prelude::*;
 
     #[test]
@@ -1410,6 +3655,196 @@ mod tests {
         assert!(row_iter.next().is_none());
     }
 
+    #[cfg(feature = "smallvec")]
+    #[test]
+    fn small_matrices_work_with_generic_kernels() {
+        use crate::ops::serial::spmm::spmm_csr_csc;
+        use smallvec::smallvec;
+
+        let small = SmallCsrMatrix::<i32, 3, 4>::try_from_parts(
+            3,
+            3,
+            smallvec![0, 2, 3],
+            smallvec![0, 2, 1, 2],
+            smallvec![1, 2, 3, 4],
+        )
+        .unwrap();
+
+        let (offsets, indices, data) = small.to_view().disassemble();
+
+        assert!(!small.clone().disassemble().0.spilled());
+        assert_eq!(offsets, &[0, 2, 3]);
+        assert_eq!(indices, &[0, 2, 1, 2]);
+        assert_eq!(data, &[1, 2, 3, 4]);
+
+        let csr: CsrMatrix<i32> = small.to_storage();
+        let round_trip: SmallCsrMatrix<i32, 3, 4> = csr.to_storage();
+
+        assert_eq!(round_trip.cs_data(), small.cs_data());
+
+        let eye = CscMatrix::<i32>::identity(3);
+        let product = spmm_csr_csc(small.to_view(), eye).unwrap();
+
+        assert_eq!(DMatrix::from(&product), DMatrix::from(&csr));
+    }
+
+    #[test]
+    fn dense_block_writes_into_slice_of_larger_matrix() {
+        let csr = CsrMatrix::try_from_parts(
+            3,
+            4,
+            vec![0, 2, 3],
+            vec![0, 3, 1, 1, 2],
+            vec![1, 2, 3, 4, 5],
+        )
+        .unwrap();
+
+        let mut front = DMatrix::from_element(4, 4, -1);
+        csr.write_dense_block(1..3, 1..4, &mut front.slice_mut((1, 1), (2, 3)));
+
+        let expected = DMatrix::from_row_slice(
+            4,
+            4,
+            &[
+                -1, -1, -1, -1, //
+                -1, 3, 0, 0, //
+                -1, 4, 5, 0, //
+                -1, -1, -1, -1,
+            ],
+        );
+
+        assert_eq!(front, expected);
+        assert_eq!(csr.to_dense_block(0..0, 1..3), DMatrix::zeros(0, 2));
+    }
+
+    #[test]
+    fn add_dense_block_errors_or_extends_on_missing_entries() {
+        let mut csr = CsrMatrix::try_from_parts(
+            3,
+            3,
+            vec![0, 2, 3],
+            vec![0, 2, 1, 0, 2],
+            vec![1.0, 2.0, 3.0, 4.0, 5.0],
+        )
+        .unwrap();
+
+        let block = DMatrix::from_row_slice(2, 2, &[10.0, 0.0, 0.0, 20.0]);
+        csr.add_dense_block(1..3, 1..3, &block, MissingEntries::Error)
+            .unwrap();
+
+        let expected = DMatrix::from_row_slice(
+            3,
+            3,
+            &[
+                1.0, 0.0, 2.0, //
+                0.0, 13.0, 0.0, //
+                4.0, 0.0, 25.0,
+            ],
+        );
+        assert_eq!(DMatrix::from(&csr), expected);
+        assert_eq!(csr.nnz(), 5);
+
+        let block = DMatrix::from_row_slice(1, 2, &[7.0, 8.0]);
+        let err = csr
+            .add_dense_block(0..1, 0..2, &block, MissingEntries::Error)
+            .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+        assert_eq!(DMatrix::from(&csr), expected);
+
+        csr.add_dense_block(0..1, 0..2, &block, MissingEntries::Extend)
+            .unwrap();
+        assert_eq!(csr.nnz(), 6);
+        assert_eq!(csr.get_entry(0, 0), Some(SparseEntry::NonZero(&8.0)));
+        assert_eq!(csr.get_entry(0, 1), Some(SparseEntry::NonZero(&8.0)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn dense_block_out_of_bounds_panics() {
+        let csc = CscMatrix::<f64>::identity(3);
+        let _ = csc.to_dense_block(1..4, 0..3);
+    }
+
+    #[test]
+    #[should_panic(expected = "not stored")]
+    fn entry_outside_of_the_pattern_panics() {
+        let mut csr = CsrMatrix::<f64>::identity(3);
+        *csr.entry(0, 1) = 1.0;
+    }
+
+    #[test]
+    fn mutable_entries_of_explicit_zeros_are_stored() {
+        let mut csr = CsrMatrix::try_from_parts(1, 1, vec![0], vec![0], vec![0]).unwrap();
+        let mut csc = crate::convert::serial::convert_csr_csc(&csr);
+
+        assert_eq!(
+            csr.get_entry_mut(0, 0).map(SparseEntryMut::into_value),
+            Some(0)
+        );
+        assert_eq!(
+            csc.get_entry_mut(0, 0).map(SparseEntryMut::into_value),
+            Some(0)
+        );
+        assert!(matches!(
+            csr.get_entry_mut(0, 0),
+            Some(SparseEntryMut::NonZero(_))
+        ));
+
+        *csr.entry(0, 0) += 1;
+        *csc.entry(0, 0) += 1;
+
+        assert_eq!(csr.cs_data(), (&[0][..], &[0][..], &[1][..]));
+        assert_eq!(csc.cs_data(), (&[0][..], &[0][..], &[1][..]));
+    }
+
+    #[test]
+    fn diagonal_operations_on_matrices_without_rows() {
+        let mut csr = CsrMatrix::<i32>::zeros(0, 1);
+        let mut csc = CscMatrix::<i32>::zeros(0, 1);
+        let values = DVector::zeros(0);
+
+        for k in -1..=2 {
+            assert!(csr.diagonal_offset(k).is_empty());
+            assert!(csc.diagonal_offset(k).is_empty());
+        }
+
+        csr.add_to_diagonal(&values).unwrap();
+        csc.set_diagonal(&values).unwrap();
+
+        assert_eq!(csr.shape(), (0, 1));
+        assert_eq!(csc.cs_data(), CscMatrix::<i32>::zeros(0, 1).cs_data());
+    }
+
+    #[test]
+    fn csr_builder_rejects_invalid_rows_without_losing_previous_ones() {
+        let mut builder = CsrBuilder::with_capacity(3, 4, 4);
+
+        builder.push_row(vec![1, 3], vec![1, 2]).unwrap();
+
+        let err = builder.push_row(vec![2, 2], vec![3, 4]).unwrap_err();
+        assert_eq!(err.kind(), &SparseFormatErrorKind::DuplicateEntry);
+
+        let err = builder.push_row(vec![2, 0], vec![3, 4]).unwrap_err();
+        assert_eq!(err.kind(), &SparseFormatErrorKind::InvalidStructure);
+
+        let err = builder.push_row(vec![0, 4], vec![3, 4]).unwrap_err();
+        assert_eq!(err.kind(), &SparseFormatErrorKind::IndexOutOfBounds);
+
+        let err = builder.push_row(vec![0, 1], vec![3]).unwrap_err();
+        assert_eq!(err.kind(), &SparseFormatErrorKind::InvalidStructure);
+
+        assert_eq!((builder.nrows(), builder.nnz()), (1, 2));
+
+        builder.push_row(vec![0], vec![5]).unwrap();
+
+        let matrix = builder.build();
+
+        assert_eq!(matrix.shape(), (2, 4));
+        assert_eq!(
+            matrix.cs_data(),
+            (&[0, 2][..], &[1, 3, 0][..], &[1, 2, 5][..])
+        );
+    }
     proptest! {
         #[test]
         fn csc_double_transpose_is_identity(csc in csc_strategy()) {
@@ -1459,6 +3894,72 @@ mod tests {
             prop_assert_eq!(csr.nnz(), csr_transpose.nnz());
         }
 
+        #[test]
+        fn csr_dense_block_agrees_with_dense(
+            (csr, rows, cols) in csr_strategy().prop_flat_map(|csr| {
+                let (nrows, ncols) = csr.shape();
+                let rows = (0..=nrows).prop_flat_map(move |a| (Just(a), a..=nrows));
+                let cols = (0..=ncols).prop_flat_map(move |a| (Just(a), a..=ncols));
+                (Just(csr), rows, cols)
+            })
+        ) {
+            let dense = DMatrix::from(&csr);
+            let block = csr.to_dense_block(rows.0..rows.1, cols.0..cols.1);
+            let expected = dense.slice((rows.0, cols.0), (rows.1 - rows.0, cols.1 - cols.0));
+
+            prop_assert_eq!(block, expected.into_owned());
+        }
+
+        #[test]
+        fn csc_dense_block_agrees_with_dense(
+            (csc, rows, cols) in csc_strategy().prop_flat_map(|csc| {
+                let (nrows, ncols) = csc.shape();
+                let rows = (0..=nrows).prop_flat_map(move |a| (Just(a), a..=nrows));
+                let cols = (0..=ncols).prop_flat_map(move |a| (Just(a), a..=ncols));
+                (Just(csc), rows, cols)
+            })
+        ) {
+            let dense = DMatrix::from(&csc);
+            let block = csc.to_dense_block(rows.0..rows.1, cols.0..cols.1);
+            let expected = dense.slice((rows.0, cols.0), (rows.1 - rows.0, cols.1 - cols.0));
+
+            prop_assert_eq!(block, expected.into_owned());
+        }
+
+        #[test]
+        fn csc_add_dense_block_agrees_with_dense(
+            (csc, rows, cols, block) in csc_strategy().prop_flat_map(|csc| {
+                let (nrows, ncols) = csc.shape();
+                let rows = (0..=nrows).prop_flat_map(move |a| (Just(a), a..=nrows));
+                let cols = (0..=ncols).prop_flat_map(move |a| (Just(a), a..=ncols));
+                (Just(csc), rows, cols)
+            })
+            .prop_flat_map(|(csc, rows, cols)| {
+                let block = proptest::collection::vec(-2..=2, (rows.1 - rows.0) * (cols.1 - cols.0))
+                    .prop_map(move |values| {
+                        DMatrix::from_vec(rows.1 - rows.0, cols.1 - cols.0, values)
+                    });
+                (Just(csc), Just(rows), Just(cols), block)
+            })
+        ) {
+            let mut expected = DMatrix::from(&csc);
+            let mut region = expected.slice_mut((rows.0, cols.0), (rows.1 - rows.0, cols.1 - cols.0));
+            region += &block;
+
+            let mut extended = csc.clone();
+            extended
+                .add_dense_block(rows.0..rows.1, cols.0..cols.1, &block, MissingEntries::Extend)
+                .unwrap();
+
+            prop_assert_eq!(DMatrix::from(&extended), expected.clone());
+
+            let mut checked = csc.clone();
+            match checked.add_dense_block(rows.0..rows.1, cols.0..cols.1, &block, MissingEntries::Error) {
+                Ok(()) => prop_assert_eq!(DMatrix::from(&checked), expected),
+                Err(_) => prop_assert_eq!(DMatrix::from(&checked), DMatrix::from(&csc)),
+            }
+        }
+
         #[test]
         fn zero_matrix_valid_data(nrows in 0..500usize, ncols in 0..500usize) {
             let mat = CsrMatrix::<f32>::zeros(nrows, ncols);
@@ -1477,8 +3978,8 @@ mod tests {
 
             prop_assert!(offsets.iter().all(|&o| o == 0usize));
             prop_assert_eq!(offsets.len(), nrows);
-            prop_assert_eq!(indices, &[]);
-            prop_assert_eq!(data, &[]);
+            prop_assert_eq!(indices, &[] as &[usize]);
+            prop_assert_eq!(data, &[] as &[f32]);
 
             prop_assert!(mat.triplet_iter().next().is_none());
 
@@ -1560,5 +4061,335 @@ mod tests {
                 }
             }
         }
+
+        #[test]
+        fn compressed_matrix_is_independent_of_compression(csr in csr_strategy()) {
+            fn to_dense<M: CompressedMatrix<i32>>(matrix: &M) -> DMatrix<i32> {
+                let mut dense = DMatrix::zeros(matrix.nrows(), matrix.ncols());
+
+                for (row, col, value) in matrix.row_col_iter() {
+                    dense[(row, col)] = *value;
+                }
+
+                dense
+            }
+
+            let csc = CscMatrix::from(csr.clone());
+            let expected = DMatrix::from(&csr);
+
+            prop_assert_eq!(to_dense(&csr), expected.clone());
+            prop_assert_eq!(to_dense(&csc), expected.clone());
+            prop_assert_eq!(to_dense(&csc.to_view()), expected.clone());
+            prop_assert_eq!(CompressedMatrix::nmajor(&csc), csr.ncols());
+            prop_assert_eq!(CompressedMatrix::nnz(&csc), csr.nnz());
+
+            for i in 0..csr.nrows() {
+                for j in 0..csr.ncols() {
+                    let entry = CompressedMatrix::get_entry(&csc, i, j).unwrap().into_value();
+                    prop_assert_eq!(entry, expected[(i, j)]);
+                }
+            }
+        }
+
+        #[test]
+        fn csr_builder_reproduces_matrix_from_its_rows(csr in csr_strategy()) {
+            let mut builder = CsrBuilder::new(csr.ncols());
+
+            for lane in csr.iter() {
+                let (indices, values): (Vec<_>, Vec<_>) = lane.map(|(j, v)| (j, *v)).unzip();
+                builder.push_row(indices, values).unwrap();
+            }
+
+            let built = builder.build();
+
+            prop_assert_eq!(built.shape(), csr.shape());
+            prop_assert_eq!(built.cs_data(), csr.cs_data());
+        }
+
+        #[test]
+        fn from_fn_agrees_with_dense(csr in csr_strategy(), bandwidth in 0usize..4) {
+            let dense = DMatrix::from(&csr);
+            let entry = |i: usize, j: usize| Some(dense[(i, j)]).filter(|v| *v != 0);
+
+            let from_csr = CsrMatrix::from_fn(csr.nrows(), csr.ncols(), entry);
+            let from_csc = CscMatrix::from_fn(csr.nrows(), csr.ncols(), entry);
+
+            prop_assert_eq!(DMatrix::from(&from_csr), dense.clone());
+            prop_assert_eq!(DMatrix::from(&from_csc), dense.clone());
+
+            let banded = CscMatrix::from_fn_banded(csr.nrows(), csr.ncols(), bandwidth, entry);
+            let expected = DMatrix::from_fn(csr.nrows(), csr.ncols(), |i, j| {
+                if i.max(j) - i.min(j) <= bandwidth { dense[(i, j)] } else { 0 }
+            });
+            prop_assert_eq!(DMatrix::from(&banded), expected);
+
+            let from_rows = CsrMatrix::from_row_fn(csr.nrows(), csr.ncols(), |i| {
+                csr.row(i).iter().map(|(j, v)| (j, *v)).collect::<Vec<_>>()
+            })
+            .unwrap();
+            prop_assert_eq!(from_rows.cs_data(), csr.cs_data());
+        }
+
+        #[test]
+        fn submatrix_agrees_with_dense(
+            (csr, rows, cols) in csr_strategy().prop_flat_map(|csr| {
+                let (nrows, ncols) = csr.shape();
+                let range = |n: usize| (0..=n, 0..=n).prop_map(|(a, b)| a.min(b)..a.max(b));
+                (Just(csr), range(nrows), range(ncols))
+            })
+        ) {
+            let dense = DMatrix::from(&csr);
+            let csc = CscMatrix::from(csr.clone());
+            let expected = dense.slice((rows.start, cols.start), (rows.len(), cols.len()));
+
+            prop_assert_eq!(DMatrix::from(&csr.submatrix(rows.clone(), cols.clone())), expected);
+            prop_assert_eq!(DMatrix::from(&csc.submatrix(rows.clone(), cols.clone())), expected);
+
+            let row_block = csr.rows(rows.clone());
+            prop_assert_eq!(row_block.shape(), (rows.len(), csr.ncols()));
+            prop_assert_eq!(DMatrix::from(&row_block), dense.rows(rows.start, rows.len()));
+
+            let column_block = csc.columns(cols.clone());
+            prop_assert_eq!(column_block.shape(), (csc.nrows(), cols.len()));
+            prop_assert_eq!(DMatrix::from(&column_block), dense.columns(cols.start, cols.len()));
+        }
+
+        #[test]
+        fn select_agrees_with_dense(
+            (csr, rows, cols) in csr_strategy().prop_flat_map(|csr| {
+                let (nrows, ncols) = csr.shape();
+                let indices = |n: usize| {
+                    if n == 0 {
+                        Just(Vec::new()).boxed()
+                    } else {
+                        proptest::collection::vec(0..n, 0..2 * n).boxed()
+                    }
+                };
+                (Just(csr), indices(nrows), indices(ncols))
+            })
+        ) {
+            let dense = DMatrix::from(&csr);
+            let csc = CscMatrix::from(csr.clone());
+            let expected = DMatrix::from_fn(rows.len(), cols.len(), |i, j| dense[(rows[i], cols[j])]);
+
+            prop_assert_eq!(DMatrix::from(&csr.select(&rows, &cols)), expected.clone());
+            prop_assert_eq!(DMatrix::from(&csc.select(&rows, &cols)), expected);
+        }
+
+        #[test]
+        fn block_diag_agrees_with_dense(
+            blocks in proptest::collection::vec(csr_strategy(), 0..4),
+        ) {
+            let block_refs: Vec<_> = blocks.iter().collect();
+            let csr = CsrMatrix::block_diag(&block_refs);
+
+            let cscs: Vec<_> = blocks.iter().cloned().map(CscMatrix::from).collect();
+            let csc_refs: Vec<_> = cscs.iter().collect();
+            let csc = CscMatrix::block_diag(&csc_refs);
+
+            let nrows = blocks.iter().map(|block| block.nrows()).sum();
+            let ncols = blocks.iter().map(|block| block.ncols()).sum();
+            let mut expected = DMatrix::zeros(nrows, ncols);
+            let (mut i, mut j) = (0, 0);
+
+            for block in &blocks {
+                expected
+                    .slice_mut((i, j), block.shape())
+                    .copy_from(&DMatrix::from(block));
+                i += block.nrows();
+                j += block.ncols();
+            }
+
+            prop_assert_eq!(csr.nnz(), blocks.iter().map(|block| block.nnz()).sum::<usize>());
+            prop_assert_eq!(DMatrix::from(&csr), expected.clone());
+            prop_assert_eq!(DMatrix::from(&csc), expected);
+        }
+
+        #[test]
+        fn diagonal_operations_agree_with_dense(
+            (csr, values) in csr_strategy().prop_flat_map(|csr| {
+                let n = csr.nrows().min(csr.ncols());
+                (Just(csr), matrix(PROPTEST_I32_VALUE_STRATEGY, n, 1))
+            }),
+        ) {
+            let dense = DMatrix::from(&csr);
+            let csc = CscMatrix::from(csr.clone());
+            let (nrows, ncols) = csr.shape();
+
+            for k in -(nrows as isize) - 1..=ncols as isize + 1 {
+                let expected: Vec<_> = (0..nrows)
+                    .filter_map(|i| {
+                        let j = i as isize + k;
+                        (0..ncols as isize).contains(&j).then(|| dense[(i, j as usize)])
+                    })
+                    .collect();
+
+                let expected = DVector::from_vec(expected);
+                prop_assert_eq!(csr.diagonal_offset(k), expected.clone());
+                prop_assert_eq!(csc.diagonal_offset(k), expected);
+            }
+
+            let values = values.column(0).into_owned();
+            let missing = (0..values.len())
+                .any(|i| values[i] != 0 && csr.get_entry(i, i) == Some(SparseEntry::Zero));
+
+            let mut added = csr.clone();
+            let mut set = csc.clone();
+
+            if missing {
+                prop_assert!(added.add_to_diagonal(&values).is_err());
+                prop_assert!(set.set_diagonal(&values).is_err());
+                prop_assert_eq!(DMatrix::from(&added), dense.clone());
+                prop_assert_eq!(DMatrix::from(&set), dense);
+            } else {
+                added.add_to_diagonal(&values).unwrap();
+                set.set_diagonal(&values).unwrap();
+                prop_assert_eq!(added.diagonal(), csr.diagonal() + &values);
+                prop_assert_eq!(set.diagonal(), values);
+                prop_assert_eq!(added.pattern(), csr.pattern());
+            }
+        }
+
+        #[test]
+        fn triangles_agree_with_dense(csr in csr_strategy()) {
+            let dense = DMatrix::from(&csr);
+            let csc = CscMatrix::from(csr.clone());
+            let (nrows, ncols) = csr.shape();
+
+            for k in -(nrows as isize) - 1..=ncols as isize + 1 {
+                let lower = DMatrix::from_fn(nrows, ncols, |i, j| {
+                    if j as isize - i as isize <= k { dense[(i, j)] } else { 0 }
+                });
+                let upper = DMatrix::from_fn(nrows, ncols, |i, j| {
+                    if j as isize - i as isize >= k { dense[(i, j)] } else { 0 }
+                });
+
+                prop_assert_eq!(DMatrix::from(&csr.lower_triangle(k)), lower.clone());
+                prop_assert_eq!(DMatrix::from(&csc.lower_triangle(k)), lower);
+                prop_assert_eq!(DMatrix::from(&csr.upper_triangle(k)), upper.clone());
+                prop_assert_eq!(DMatrix::from(&csc.upper_triangle(k)), upper);
+                prop_assert_eq!(
+                    csr.lower_triangle(k).nnz() + csr.upper_triangle(k + 1).nnz(),
+                    csr.nnz()
+                );
+            }
+        }
+
+        #[test]
+        fn pruning_keeps_the_matrix_and_removes_small_entries(csr in csr_strategy()) {
+            let dense = DMatrix::from(&csr);
+            let mut pruned = csr.clone();
+            let zeros = csr.cs_data().2.iter().filter(|&&v| v == 0).count();
+
+            prop_assert_eq!(pruned.prune_zeros(), zeros);
+            prop_assert_eq!(pruned.nnz(), csr.nnz() - zeros);
+            prop_assert!(pruned.cs_data().2.iter().all(|&v| v != 0));
+            prop_assert_eq!(DMatrix::from(&pruned), dense.clone());
+
+            let values = CscMatrix::from(&dense.map(|v| v as f64));
+            let mut dropped = values.clone();
+            let small = values.cs_data().2.iter().filter(|v| v.abs() < 2.5).count();
+
+            prop_assert_eq!(dropped.drop_small(2.5), small);
+            prop_assert_eq!(
+                DMatrix::from(&dropped),
+                dense.map(|v| if (v as f64).abs() < 2.5 { 0.0 } else { v as f64 })
+            );
+        }
+
+        #[test]
+        fn retain_agrees_with_dense_masking(csc in csc_strategy()) {
+            let dense = DMatrix::from(&csc);
+            let keep = |i: usize, j: usize, v: &i32| !(i + j).is_multiple_of(3) && *v >= 0;
+            let mut retained = csc.clone();
+            let kept = csc.triplet_iter().filter(|&(j, i, v)| keep(i, j, v)).count();
+
+            prop_assert_eq!(retained.retain(keep), csc.nnz() - kept);
+            prop_assert_eq!(retained.nnz(), kept);
+            prop_assert_eq!(
+                DMatrix::from(&retained),
+                DMatrix::from_fn(dense.nrows(), dense.ncols(), |i, j| {
+                    if keep(i, j, &dense[(i, j)]) { dense[(i, j)] } else { 0 }
+                })
+            );
+        }
+
+        #[test]
+        fn mapping_values_keeps_the_pattern(csc in csc_strategy()) {
+            let dense = DMatrix::from(&csc);
+
+            let halved = csc.map_values(|&v| f64::from(v) * 0.5);
+            prop_assert_eq!(halved.cs_data().0, csc.cs_data().0);
+            prop_assert_eq!(halved.cs_data().1, csc.cs_data().1);
+            prop_assert_eq!(DMatrix::from(&halved), dense.map(|v| f64::from(v) * 0.5));
+
+            let indexed = csc.to_view().map_with_indices(|i, j, &v| v * 10 + (i * 3 + j) as i32);
+            let (offsets, indices, _) = csc.cs_data();
+            prop_assert!(std::ptr::eq(indexed.cs_data().0, offsets));
+            prop_assert!(std::ptr::eq(indexed.cs_data().1, indices));
+            prop_assert_eq!(
+                DMatrix::from(&indexed),
+                DMatrix::from_fn(dense.nrows(), dense.ncols(), |i, j| {
+                    if matches!(csc.get_entry(i, j), Some(crate::SparseEntry::NonZero(_))) {
+                        dense[(i, j)] * 10 + (i * 3 + j) as i32
+                    } else {
+                        0
+                    }
+                })
+            );
+
+            let negated = csc.clone().into_map_values(|v| -i64::from(v));
+            prop_assert_eq!(DMatrix::from(&negated), dense.map(|v| -i64::from(v)));
+        }
+
+        #[test]
+        fn shared_patterns_are_shared_and_compare_equal(csr in csr_strategy()) {
+            let shared = csr.clone().into_shared();
+            let halved = shared.map_values(|&v| f64::from(v) * 0.5);
+            let negated = shared.with_values(shared.cs_data().2.iter().map(|v| -v).collect()).unwrap();
+
+            prop_assert!(std::ptr::eq(shared.cs_data().0, negated.cs_data().0));
+            prop_assert!(std::ptr::eq(shared.cs_data().1, halved.cs_data().1));
+            prop_assert!(shared.same_pattern(&halved));
+            prop_assert!(shared.same_pattern(&csr));
+            prop_assert!(csr.same_pattern(&negated.to_view()));
+            prop_assert_eq!(DMatrix::from(&negated), -DMatrix::from(&csr));
+            prop_assert!(shared.with_values(vec![0; csr.nnz() + 1]).is_err());
+
+            let mut sum = shared.clone();
+            crate::ops::serial::spadd::spadd_assign(&mut sum, negated).unwrap();
+            prop_assert!(sum.cs_data().2.iter().all(|&v| v == 0));
+
+            let transposed = CsrMatrix::from(&DMatrix::from(&csr).transpose());
+            prop_assert_eq!(
+                csr.same_pattern(&transposed),
+                csr.shape() == transposed.shape() && csr.pattern() == transposed.pattern()
+            );
+        }
+
+        #[test]
+        fn mutable_entries_agree_with_entries(csr in csr_strategy()) {
+            let (nrows, ncols) = csr.shape();
+            let mut csc = crate::convert::serial::convert_csr_csc(&csr);
+            let mut csr = csr;
+            let mut expected = DMatrix::from(&csr);
+
+            for i in 0..nrows + 1 {
+                for j in 0..ncols + 1 {
+                    let value = csr.get_entry(i, j).map(SparseEntry::into_value);
+                    prop_assert_eq!(csr.get_entry_mut(i, j).map(SparseEntryMut::into_value), value);
+                    prop_assert_eq!(csc.get_entry_mut(i, j).map(SparseEntryMut::into_value), value);
+
+                    if let Some(SparseEntry::NonZero(_)) = csr.get_entry(i, j) {
+                        *csr.entry(i, j) += 1;
+                        *csc.entry(i, j) += 1;
+                        expected[(i, j)] += 1;
+                    }
+                }
+            }
+
+            prop_assert_eq!(DMatrix::from(&csr), expected.clone());
+            prop_assert_eq!(DMatrix::from(&csc), expected);
+        }
     }
 }
@@ -0,0 +1,426 @@
+//! Cross-validation utilities for sparse design matrices.
+//!
+//! Fitting e.g. a ridge or lasso regression `y ≈ X β` with k-fold cross-validation repeatedly
+//! splits the rows (samples) of the design matrix `X` into a training and a test set. This module
+//! provides the building blocks to do so without densifying `X`:
+//!
+//! - [`Folds`] assigns every row to one of `k` folds, either in contiguous blocks, interleaved,
+//!   or from a user-provided assignment (e.g. a shuffled or stratified one).
+//! - [`split_rows`] splits a CSR matrix into the rows in a test set and the remaining rows, in a
+//!   single pass over the matrix.
+//! - [`FoldGrams`] computes the Gram matrix `Xᵀ X` and the vector `Xᵀ y` of every training set.
+//!   The products of each fold are computed once, and the training products are obtained by
+//!   subtracting them from the products of the whole matrix, so the total cost is about that of
+//!   a single `Xᵀ X` instead of `k - 1` of them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra::{DMatrix, DVector};
+//! use nalgebra_sparse::{
+//!     cs::CsrMatrix,
+//!     cv::{split_rows, FoldGrams, Folds},
+//! };
+//!
+//! let dense = DMatrix::from_row_slice(4, 2, &[1.0, 0.0, 0.0, 2.0, 3.0, 0.0, 1.0, 1.0]);
+//! let x = CsrMatrix::from(&dense);
+//! let y = DVector::from_vec(vec![1.0, 2.0, 3.0, 4.0]);
+//!
+//! let folds = Folds::contiguous(4, 2);
+//! assert_eq!(folds.test_rows(1), vec![2, 3]);
+//!
+//! let (train, test) = split_rows(&x, &folds.test_rows(1));
+//! assert_eq!((train.nrows(), test.nrows()), (2, 2));
+//!
+//! // The Gram matrix of the training set of fold 1 is that of its first two rows
+//! let grams = FoldGrams::new(&x, &y, &folds).unwrap();
+//! let rows = dense.rows(0, 2);
+//! assert_eq!(DMatrix::from(&grams.train_gram(1)), rows.transpose() * rows);
+//! ```
+
+use crate::{
+    convert::serial::convert_csr_csc,
+    cs::{CompressedRowStorage, CsMatrix, CsrMatrix},
+    error::{OperationError, OperationErrorKind},
+    lp::aat,
+    partition::block_ranges,
+};
+use nalgebra::{DVector, Scalar};
+use num_traits::Zero;
+use std::{
+    borrow::Borrow,
+    ops::{AddAssign, Mul, SubAssign},
+};
+
+/// An assignment of the rows of a matrix to `k` folds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Folds {
+    assignment: Vec<usize>,
+    nfolds: usize,
+}
+
+impl Folds {
+    /// Assigns `nrows` rows to `nfolds` contiguous blocks of rows, whose sizes differ by at most
+    /// one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nfolds` is zero.
+    #[must_use]
+    pub fn contiguous(nrows: usize, nfolds: usize) -> Self {
+        let mut assignment = vec![0; nrows];
+
+        for (fold, range) in block_ranges(nrows, nfolds).into_iter().enumerate() {
+            assignment[range].fill(fold);
+        }
+
+        Self { assignment, nfolds }
+    }
+
+    /// Assigns row `i` of `nrows` rows to fold `i % nfolds`.
+    ///
+    /// This spreads rows that are sorted by some property, e.g. by time, evenly across the folds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nfolds` is zero.
+    #[must_use]
+    pub fn interleaved(nrows: usize, nfolds: usize) -> Self {
+        assert!(nfolds > 0, "Cannot split rows into zero folds.");
+
+        Self {
+            assignment: (0..nrows).map(|i| i % nfolds).collect(),
+            nfolds,
+        }
+    }
+
+    /// Uses `assignment[i]` as the fold of row `i`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if any fold is
+    /// not smaller than `nfolds`.
+    pub fn from_assignment(assignment: Vec<usize>, nfolds: usize) -> Result<Self, OperationError> {
+        if let Some(fold) = assignment.iter().find(|&&fold| fold >= nfolds) {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!("Fold {} is out of bounds for {} folds.", fold, nfolds),
+            ));
+        }
+
+        Ok(Self { assignment, nfolds })
+    }
+
+    /// The number of folds.
+    #[must_use]
+    pub fn nfolds(&self) -> usize {
+        self.nfolds
+    }
+
+    /// The number of assigned rows.
+    #[must_use]
+    pub fn nrows(&self) -> usize {
+        self.assignment.len()
+    }
+
+    /// The fold of every row.
+    #[must_use]
+    pub fn assignment(&self) -> &[usize] {
+        &self.assignment
+    }
+
+    /// The rows in the test set of `fold`, i.e. the rows assigned to it, in increasing order.
+    #[must_use]
+    pub fn test_rows(&self, fold: usize) -> Vec<usize> {
+        self.rows_where(|f| f == fold)
+    }
+
+    /// The rows in the training set of `fold`, i.e. the rows assigned to any other fold, in
+    /// increasing order.
+    #[must_use]
+    pub fn train_rows(&self, fold: usize) -> Vec<usize> {
+        self.rows_where(|f| f != fold)
+    }
+
+    fn rows_where<F: Fn(usize) -> bool>(&self, predicate: F) -> Vec<usize> {
+        self.assignment
+            .iter()
+            .enumerate()
+            .filter_map(|(row, &fold)| predicate(fold).then_some(row))
+            .collect()
+    }
+}
+
+/// Splits `matrix` into the rows that are not in `test_rows` and the rows that are, returning
+/// `(train, test)`.
+///
+/// Both matrices keep the rows in the order in which they appear in `matrix`, regardless of the
+/// order of `test_rows`, and are built in a single pass over the matrix. Repeated test rows are
+/// only taken once.
+///
+/// # Panics
+///
+/// Panics if any of the test rows is out of bounds.
+#[must_use]
+pub fn split_rows<T, MO, MI, D>(
+    matrix: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    test_rows: &[usize],
+) -> (CsrMatrix<T>, CsrMatrix<T>)
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    let (nrows, ncols) = matrix.shape();
+    let mut is_test = vec![false; nrows];
+
+    for &row in test_rows {
+        assert!(
+            row < nrows,
+            "Row index {} is out of bounds for a matrix with {} rows.",
+            row,
+            nrows
+        );
+        is_test[row] = true;
+    }
+
+    let mut parts = [
+        (Vec::new(), Vec::new(), Vec::new()),
+        (Vec::new(), Vec::new(), Vec::new()),
+    ];
+
+    for (row, lane) in matrix.iter().enumerate() {
+        let (offsets, indices, data) = &mut parts[usize::from(is_test[row])];
+        offsets.push(indices.len());
+
+        for (j, value) in lane {
+            indices.push(j);
+            data.push(value.clone());
+        }
+    }
+
+    let [train, test] = parts;
+    let build = |(offsets, indices, data): (Vec<usize>, Vec<usize>, Vec<T>)| unsafe {
+        CsrMatrix::from_parts_unchecked(offsets.len(), ncols, offsets, indices, data)
+    };
+
+    (build(train), build(test))
+}
+
+/// The Gram matrices `Xᵀ X` and vectors `Xᵀ y` of the training sets of every fold of a design
+/// matrix `X` and a target vector `y`.
+///
+/// Every training Gram matrix is stored with the sparsity pattern of the Gram matrix of the
+/// whole design matrix, so a single symbolic Cholesky analysis (see
+/// [`CholeskySymbolic`](crate::factorization::CholeskySymbolic)) can be reused for all folds.
+#[derive(Debug, Clone)]
+pub struct FoldGrams<T: Scalar> {
+    gram: CsrMatrix<T>,
+    xty: DVector<T>,
+    fold_grams: Vec<CsrMatrix<T>>,
+    fold_xty: Vec<DVector<T>>,
+}
+
+impl<T> FoldGrams<T>
+where
+    T: Scalar + Zero + AddAssign + SubAssign + Mul<Output = T>,
+{
+    /// Computes the products of every fold of `x` and `y`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `y` or
+    /// `folds` do not have as many rows as `x`.
+    pub fn new<MO, MI, D>(
+        x: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+        y: &DVector<T>,
+        folds: &Folds,
+    ) -> Result<Self, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let (nrows, ncols) = x.shape();
+
+        if y.len() != nrows || folds.nrows() != nrows {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!(
+                    "The design matrix has {} rows, but y has {} and the folds assign {}.",
+                    nrows,
+                    y.len(),
+                    folds.nrows()
+                ),
+            ));
+        }
+
+        let mut fold_grams = Vec::with_capacity(folds.nfolds());
+        let mut fold_xty = Vec::with_capacity(folds.nfolds());
+
+        for fold in 0..folds.nfolds() {
+            let rows = folds.test_rows(fold);
+            let (_, x_fold) = split_rows(x, &rows);
+
+            // Xᵀ X is A Aᵀ for A = Xᵀ, whose CSR representation is the CSC representation of X
+            fold_grams.push(aat(&convert_csr_csc(&x_fold).transpose_owned()));
+
+            let mut xty = DVector::<T>::zeros(ncols);
+            for (lane, &row) in x_fold.iter().zip(&rows) {
+                for (j, value) in lane {
+                    xty[j] += value.clone() * y[row].clone();
+                }
+            }
+            fold_xty.push(xty);
+        }
+
+        let gram = aat(&convert_csr_csc(x).transpose_owned());
+
+        let mut xty = DVector::<T>::zeros(ncols);
+        for fold in &fold_xty {
+            for (total, value) in xty.iter_mut().zip(fold.iter()) {
+                *total += value.clone();
+            }
+        }
+
+        Ok(Self {
+            gram,
+            xty,
+            fold_grams,
+            fold_xty,
+        })
+    }
+
+    /// The number of folds.
+    #[must_use]
+    pub fn nfolds(&self) -> usize {
+        self.fold_grams.len()
+    }
+
+    /// The Gram matrix `Xᵀ X` of the whole design matrix.
+    #[must_use]
+    pub fn gram(&self) -> &CsrMatrix<T> {
+        &self.gram
+    }
+
+    /// The vector `Xᵀ y` of the whole design matrix.
+    #[must_use]
+    pub fn xty(&self) -> &DVector<T> {
+        &self.xty
+    }
+
+    /// The Gram matrix of the test set of `fold`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fold` is out of bounds.
+    #[must_use]
+    pub fn test_gram(&self, fold: usize) -> &CsrMatrix<T> {
+        &self.fold_grams[fold]
+    }
+
+    /// The vector `Xᵀ y` of the test set of `fold`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fold` is out of bounds.
+    #[must_use]
+    pub fn test_xty(&self, fold: usize) -> &DVector<T> {
+        &self.fold_xty[fold]
+    }
+
+    /// The Gram matrix of the training set of `fold`, with the sparsity pattern of
+    /// [`gram`](Self::gram).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fold` is out of bounds.
+    #[must_use]
+    pub fn train_gram(&self, fold: usize) -> CsrMatrix<T> {
+        let mut train = self.gram.clone();
+        let (offsets, indices, data) = train.cs_data_mut();
+
+        // The pattern of every fold is contained in the pattern of the whole matrix, with the
+        // indices of both sorted in every row
+        for (row, lane) in self.fold_grams[fold].iter().enumerate() {
+            let end = offsets.get(row + 1).copied().unwrap_or(indices.len());
+            let mut position = offsets[row];
+
+            for (j, value) in lane {
+                while indices[position] != j {
+                    position += 1;
+                }
+                debug_assert!(position < end);
+
+                data[position] -= value.clone();
+            }
+        }
+
+        train
+    }
+
+    /// The vector `Xᵀ y` of the training set of `fold`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fold` is out of bounds.
+    #[must_use]
+    pub fn train_xty(&self, fold: usize) -> DVector<T> {
+        let mut xty = self.xty.clone();
+        for (total, value) in xty.iter_mut().zip(self.fold_xty[fold].iter()) {
+            *total -= value.clone();
+        }
+        xty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest::*;
+    use nalgebra::DMatrix;
+    use proptest::prelude::*;
+
+    #[test]
+    fn folds_assign_every_row_once() {
+        let contiguous = Folds::contiguous(7, 3);
+        assert_eq!(contiguous.assignment(), &[0, 0, 0, 1, 1, 2, 2]);
+        assert_eq!(contiguous.train_rows(1), vec![0, 1, 2, 5, 6]);
+
+        let interleaved = Folds::interleaved(7, 3);
+        assert_eq!(interleaved.test_rows(0), vec![0, 3, 6]);
+
+        assert!(Folds::from_assignment(vec![0, 2, 1], 3).is_ok());
+        assert!(matches!(
+            Folds::from_assignment(vec![0, 3], 3).unwrap_err().kind(),
+            OperationErrorKind::InvalidPattern
+        ));
+    }
+
+    proptest! {
+        #[test]
+        fn fold_products_agree_with_dense(csr in csr_strategy(), nfolds in 1usize..4) {
+            let x = DMatrix::from(&csr);
+            let y = DVector::from_fn(x.nrows(), |i, _| i as i32 - 2);
+            let folds = Folds::interleaved(x.nrows(), nfolds);
+            let grams = FoldGrams::new(&csr, &y, &folds).unwrap();
+
+            prop_assert_eq!(DMatrix::from(grams.gram()), x.transpose() * &x);
+
+            for fold in 0..nfolds {
+                let rows = folds.train_rows(fold);
+                let (train, test) = split_rows(&csr, &folds.test_rows(fold));
+                let x_train = x.select_rows(&rows);
+                let y_train = y.select_rows(&rows);
+
+                prop_assert_eq!(DMatrix::from(&train), x_train.clone());
+                prop_assert_eq!(train.nrows() + test.nrows(), x.nrows());
+                let train_gram = grams.train_gram(fold);
+                prop_assert_eq!(train_gram.cs_data().0, grams.gram().cs_data().0);
+                prop_assert_eq!(DMatrix::from(&train_gram), x_train.transpose() * &x_train);
+                prop_assert_eq!(grams.train_xty(fold), x_train.transpose() * y_train);
+            }
+        }
+    }
+}
@@ -0,0 +1,578 @@
+//! Transport-agnostic building blocks for distributed sparse matrix-vector products.
+//!
+//! In a distributed SpMV `y = A x`, each rank owns a block of rows of `A` (see
+//! [`partition_rows`](crate::partition::partition_rows)) along with the matching entries of `x`
+//! and `y`. Before a rank can compute its rows of `y`, it has to receive the values of its ghost
+//! columns from the ranks that own them, and send the values of its own entries that other ranks
+//! need in turn.
+//!
+//! This module provides everything needed for that except for the communication itself:
+//!
+//! - [`DistributedMatrix`] holds a rank's local rows along with a description of what it has to
+//!   send to, and receive from, every neighbouring rank ([`GhostExchange`]).
+//! - [`DistributedMatrix::pack`] gathers the owned entries of `x` that each neighbour needs into
+//!   one contiguous buffer per neighbour.
+//! - [`DistributedMatrix::unpack`] scatters the buffers received from each neighbour into the
+//!   ghost values of this rank.
+//! - [`DistributedMatrix::spmv`] computes the local rows of `y` from the owned and ghost values.
+//!
+//! The buffers are plain slices and vectors, so they can be handed to any transport (e.g. MPI,
+//! UCX, or channels between threads).
+//!
+//! # Example
+//!
+//! The example below simulates every rank within a single process, with the "transport" being a
+//! plain copy of the buffers.
+//!
+//! ```rust
+//! use nalgebra::{DMatrix, DVector};
+//! use nalgebra_sparse::{cs::CsrMatrix, distributed::DistributedMatrix, partition::partition_rows};
+//!
+//! let dense = DMatrix::from_fn(6, 6, |i, j| if i == j { 2.0 } else if i.max(j) - i.min(j) == 1 { -1.0 } else { 0.0 });
+//! let matrix = CsrMatrix::from(&dense);
+//! let x = DVector::from_fn(6, |i, _| i as f64);
+//!
+//! let ranks = DistributedMatrix::from_row_blocks(partition_rows(&matrix, 3).unwrap());
+//!
+//! // Every rank packs the entries its neighbours need.
+//! let outboxes = ranks
+//!     .iter()
+//!     .map(|rank| rank.pack(x.rows_range(rank.owned_columns()).as_slice()))
+//!     .collect::<Vec<_>>();
+//!
+//! for rank in &ranks {
+//!     // Receive one buffer from every neighbour, in the order of `rank.receives()`.
+//!     let inbox = rank
+//!         .receives()
+//!         .iter()
+//!         .map(|receive| {
+//!             let sender = &ranks[receive.rank];
+//!             let send = sender.sends().iter().position(|send| send.rank == rank.rank()).unwrap();
+//!             outboxes[receive.rank][send].clone()
+//!         })
+//!         .collect::<Vec<_>>();
+//!
+//!     let ghosts = rank.unpack(&inbox);
+//!     let y = rank.spmv(x.rows_range(rank.owned_columns()).as_slice(), &ghosts).unwrap();
+//!
+//!     assert_eq!(y, (&dense * &x).rows_range(rank.rows()).into_owned());
+//! }
+//! ```
+
+use crate::{
+    cs::CsrMatrix,
+    error::{OperationError, OperationErrorKind},
+    partition::RowBlock,
+};
+use nalgebra::{ClosedAdd, ClosedMul, DVector, Scalar};
+use num_traits::Zero;
+use std::ops::Range;
+
+/// A description of the values exchanged with a single neighbouring rank.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhostExchange {
+    /// The neighbouring rank.
+    pub rank: usize,
+
+    /// The positions of the exchanged values, in the order in which they appear in the buffer.
+    ///
+    /// For sends, these are indices into the owned entries of `x`. For receives, these are indices
+    /// into the ghost values (i.e. into [`RowBlock::ghost_columns`]).
+    pub indices: Vec<usize>,
+}
+
+/// The local part of a distributed sparse matrix, along with the ghost exchanges it requires.
+///
+/// See the [module-level documentation](self) for an overview.
+#[derive(Debug, Clone)]
+pub struct DistributedMatrix<T>
+where
+    T: Scalar,
+{
+    block: RowBlock<T>,
+    sends: Vec<GhostExchange>,
+    receives: Vec<GhostExchange>,
+    boundary_rows: Vec<usize>,
+}
+
+impl<T> DistributedMatrix<T>
+where
+    T: Scalar,
+{
+    /// Creates the local part of a distributed matrix from the block of rows owned by this rank,
+    /// and the values it has to send to other ranks.
+    ///
+    /// The receives are derived from the ghost columns of the block. The sends cannot be, as they
+    /// depend on the blocks owned by other ranks, so they have to be computed by the caller (e.g.
+    /// by exchanging the ghost columns of every rank once up-front). If every block is known to a
+    /// single process, [`DistributedMatrix::from_row_blocks`] computes them instead.
+    #[must_use]
+    pub fn new(block: RowBlock<T>, sends: Vec<GhostExchange>) -> Self {
+        let mut receives: Vec<GhostExchange> = Vec::new();
+
+        // Ghost columns are sorted and ranks own contiguous columns, so the ghosts owned by any
+        // one rank are consecutive.
+        for (ghost, &owner) in block.ghost_owners.iter().enumerate() {
+            match receives.last_mut() {
+                Some(receive) if receive.rank == owner => receive.indices.push(ghost),
+                _ => receives.push(GhostExchange {
+                    rank: owner,
+                    indices: vec![ghost],
+                }),
+            }
+        }
+
+        // Lanes are sorted by local column, and ghosts come after the owned columns, so a row
+        // references a ghost iff its last entry does.
+        let owned = block.owned_columns.len();
+        let boundary_rows = block
+            .local
+            .iter()
+            .enumerate()
+            .filter(|(_, lane)| matches!(lane.clone().last(), Some((j, _)) if j >= owned))
+            .map(|(row, _)| row)
+            .collect();
+
+        Self {
+            block,
+            sends,
+            receives,
+            boundary_rows,
+        }
+    }
+
+    /// Creates the local part of a distributed matrix for every rank, from the blocks of all of
+    /// the ranks.
+    ///
+    /// This is convenient when a single process partitions the matrix before distributing it.
+    /// The blocks must be indexed by their rank, as produced by
+    /// [`partition_rows`](crate::partition::partition_rows).
+    #[must_use]
+    pub fn from_row_blocks(blocks: Vec<RowBlock<T>>) -> Vec<Self> {
+        let mut sends = vec![Vec::<GhostExchange>::new(); blocks.len()];
+
+        for block in &blocks {
+            for (&column, &owner) in block.ghost_columns.iter().zip(&block.ghost_owners) {
+                let owned_index = column - blocks[owner].owned_columns.start;
+
+                match sends[owner].last_mut() {
+                    Some(send) if send.rank == block.rank => send.indices.push(owned_index),
+                    _ => sends[owner].push(GhostExchange {
+                        rank: block.rank,
+                        indices: vec![owned_index],
+                    }),
+                }
+            }
+        }
+
+        blocks
+            .into_iter()
+            .zip(sends)
+            .map(|(block, sends)| Self::new(block, sends))
+            .collect()
+    }
+
+    /// The rank that owns this part of the matrix.
+    #[must_use]
+    pub fn rank(&self) -> usize {
+        self.block.rank
+    }
+
+    /// The global rows owned by this rank, i.e. the entries of `y` computed by this rank.
+    #[must_use]
+    pub fn rows(&self) -> Range<usize> {
+        self.block.rows.clone()
+    }
+
+    /// The global columns owned by this rank, i.e. the entries of `x` owned by this rank.
+    #[must_use]
+    pub fn owned_columns(&self) -> Range<usize> {
+        self.block.owned_columns.clone()
+    }
+
+    /// The block of rows owned by this rank.
+    #[must_use]
+    pub fn block(&self) -> &RowBlock<T> {
+        &self.block
+    }
+
+    /// The local rows of the matrix, in the local column numbering of [`RowBlock::local`].
+    #[must_use]
+    pub fn local(&self) -> &CsrMatrix<T> {
+        &self.block.local
+    }
+
+    /// The values this rank has to send, one exchange per neighbouring rank.
+    #[must_use]
+    pub fn sends(&self) -> &[GhostExchange] {
+        &self.sends
+    }
+
+    /// The values this rank has to receive, one exchange per neighbouring rank, ordered by rank.
+    #[must_use]
+    pub fn receives(&self) -> &[GhostExchange] {
+        &self.receives
+    }
+
+    /// The local rows that reference at least one ghost column, in increasing order.
+    ///
+    /// These are the only rows that [`PendingSpmv::finish`] has to update once the ghost values
+    /// have been received. Every other row is an interior row, and is computed in full by
+    /// [`DistributedMatrix::begin_spmv`].
+    #[must_use]
+    pub fn boundary_rows(&self) -> &[usize] {
+        &self.boundary_rows
+    }
+
+    /// The number of ghost values this rank needs in order to compute its rows.
+    #[must_use]
+    pub fn nghosts(&self) -> usize {
+        self.block.ghost_columns.len()
+    }
+
+    /// Packs the owned entries of `x` needed by each neighbouring rank into one buffer per
+    /// neighbour, in the same order as [`DistributedMatrix::sends`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x_owned` does not contain exactly one value per owned column.
+    #[must_use]
+    pub fn pack(&self, x_owned: &[T]) -> Vec<Vec<T>> {
+        assert_eq!(
+            x_owned.len(),
+            self.block.owned_columns.len(),
+            "There must be exactly one value per owned column."
+        );
+
+        self.sends
+            .iter()
+            .map(|send| {
+                send.indices
+                    .iter()
+                    .map(|&index| x_owned[index].clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Unpacks the buffers received from each neighbouring rank into the ghost values of this
+    /// rank.
+    ///
+    /// `buffers` must contain one buffer per neighbour, in the same order as
+    /// [`DistributedMatrix::receives`]. The returned ghost values are in the same order as
+    /// [`RowBlock::ghost_columns`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of buffers, or the length of any buffer, does not match the
+    /// corresponding receive.
+    #[must_use]
+    pub fn unpack(&self, buffers: &[Vec<T>]) -> Vec<T> {
+        assert_eq!(
+            buffers.len(),
+            self.receives.len(),
+            "There must be exactly one buffer per receive."
+        );
+
+        let mut ghosts = vec![None; self.nghosts()];
+
+        for (receive, buffer) in self.receives.iter().zip(buffers) {
+            assert_eq!(
+                buffer.len(),
+                receive.indices.len(),
+                "The buffer received from rank {} has the wrong length.",
+                receive.rank
+            );
+
+            for (&index, value) in receive.indices.iter().zip(buffer) {
+                ghosts[index] = Some(value.clone());
+            }
+        }
+
+        ghosts
+            .into_iter()
+            .map(|ghost| ghost.expect("Every ghost is received from exactly one rank."))
+            .collect()
+    }
+}
+
+impl<T> DistributedMatrix<T>
+where
+    T: Scalar + Zero + ClosedAdd + ClosedMul,
+{
+    /// Computes the rows of `y = A x` owned by this rank, from the owned entries of `x` and the
+    /// ghost values of this rank (see [`DistributedMatrix::unpack`]).
+    ///
+    /// This is equivalent to calling [`DistributedMatrix::begin_spmv`] immediately followed by
+    /// [`PendingSpmv::finish`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if
+    /// `x_owned` does not contain one value per owned column, or `ghosts` does not contain one
+    /// value per ghost column.
+    pub fn spmv(&self, x_owned: &[T], ghosts: &[T]) -> Result<DVector<T>, OperationError> {
+        self.begin_spmv(x_owned)?.finish(ghosts)
+    }
+
+    /// Starts computing the rows of `y = A x` owned by this rank, using only the owned entries of
+    /// `x`.
+    ///
+    /// This computes every interior row in full, and the contributions of the owned columns to
+    /// every boundary row (see [`DistributedMatrix::boundary_rows`]). The ghost values are only
+    /// needed to [finish](PendingSpmv::finish) the product, so the exchange of ghost values can be
+    /// started before calling this, and waited upon afterwards, to overlap communication with
+    /// computation:
+    ///
+    /// ```rust,ignore
+    /// let requests = transport.start_exchange(rank.pack(x_owned));
+    /// let pending = rank.begin_spmv(x_owned)?;
+    /// let ghosts = rank.unpack(&transport.wait(requests));
+    /// let y = pending.finish(&ghosts)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if
+    /// `x_owned` does not contain one value per owned column.
+    pub fn begin_spmv(&self, x_owned: &[T]) -> Result<PendingSpmv<'_, T>, OperationError> {
+        if x_owned.len() != self.block.owned_columns.len() {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from("The owned values do not match the local matrix."),
+            ));
+        }
+
+        let _span = span!(
+            "distributed_spmv_begin",
+            rank = self.block.rank,
+            nnz = self.block.local.nnz()
+        );
+
+        let owned = x_owned.len();
+
+        let y = self.block.local.iter().map(|lane| {
+            lane.take_while(|&(j, _)| j < owned)
+                .fold(T::zero(), |total, (j, v)| {
+                    total + v.clone() * x_owned[j].clone()
+                })
+        });
+
+        Ok(PendingSpmv {
+            matrix: self,
+            y: DVector::from_iterator(self.block.rows.len(), y),
+        })
+    }
+}
+
+/// A distributed SpMV whose interior rows have been computed, but which is still waiting on the
+/// ghost values of its rank.
+///
+/// This is produced by [`DistributedMatrix::begin_spmv`].
+#[derive(Debug, Clone)]
+pub struct PendingSpmv<'a, T>
+where
+    T: Scalar,
+{
+    matrix: &'a DistributedMatrix<T>,
+    y: DVector<T>,
+}
+
+impl<'a, T> PendingSpmv<'a, T>
+where
+    T: Scalar + Zero + ClosedAdd + ClosedMul,
+{
+    /// Finishes the product by adding the contributions of the ghost columns to every boundary
+    /// row, and returns the rows of `y` owned by this rank.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if
+    /// `ghosts` does not contain one value per ghost column.
+    pub fn finish(self, ghosts: &[T]) -> Result<DVector<T>, OperationError> {
+        let PendingSpmv { matrix, mut y } = self;
+
+        if ghosts.len() != matrix.nghosts() {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from("The ghost values do not match the local matrix."),
+            ));
+        }
+
+        let _span = span!(
+            "distributed_spmv_finish",
+            rank = matrix.block.rank,
+            boundary_rows = matrix.boundary_rows.len()
+        );
+
+        let owned = matrix.block.owned_columns.len();
+
+        for &row in &matrix.boundary_rows {
+            let lane = matrix.block.local.get_lane(row).unwrap();
+
+            y[row] = lane
+                .skip_while(|&(j, _)| j < owned)
+                .fold(y[row].clone(), |total, (j, v)| {
+                    total + v.clone() * ghosts[j - owned].clone()
+                });
+        }
+
+        Ok(y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{partition::partition_rows, proptest::*};
+    use nalgebra::DMatrix;
+    use proptest::prelude::*;
+
+    /// Runs a full distributed SpMV with every rank in this process, returning the global `y`.
+    fn simulate_spmv(ranks: &[DistributedMatrix<i32>], x: &DVector<i32>) -> DVector<i32> {
+        let outboxes = ranks
+            .iter()
+            .map(|rank| rank.pack(x.rows_range(rank.owned_columns()).as_slice()))
+            .collect::<Vec<_>>();
+
+        let mut y = Vec::new();
+
+        for rank in ranks {
+            let inbox = rank
+                .receives()
+                .iter()
+                .map(|receive| {
+                    let sender = &ranks[receive.rank];
+                    let send = sender
+                        .sends()
+                        .iter()
+                        .position(|send| send.rank == rank.rank())
+                        .unwrap();
+
+                    outboxes[receive.rank][send].clone()
+                })
+                .collect::<Vec<_>>();
+
+            let ghosts = rank.unpack(&inbox);
+            let owned = x.rows_range(rank.owned_columns());
+
+            y.extend(
+                rank.spmv(owned.as_slice(), &ghosts)
+                    .unwrap()
+                    .iter()
+                    .copied(),
+            );
+        }
+
+        DVector::from_vec(y)
+    }
+
+    #[test]
+    fn exchanges_of_tridiagonal_matrix() {
+        let dense = DMatrix::from_fn(6, 6, |i, j| (i.max(j) - i.min(j) <= 1) as i32);
+        let ranks = DistributedMatrix::from_row_blocks(
+            partition_rows(&CsrMatrix::from(&dense), 3).unwrap(),
+        );
+
+        let middle = &ranks[1];
+
+        assert_eq!(
+            middle.receives(),
+            &[
+                GhostExchange {
+                    rank: 0,
+                    indices: vec![0]
+                },
+                GhostExchange {
+                    rank: 2,
+                    indices: vec![1]
+                },
+            ]
+        );
+        assert_eq!(
+            middle.sends(),
+            &[
+                GhostExchange {
+                    rank: 0,
+                    indices: vec![0]
+                },
+                GhostExchange {
+                    rank: 2,
+                    indices: vec![1]
+                },
+            ]
+        );
+
+        assert_eq!(middle.pack(&[10, 20]), vec![vec![10], vec![20]]);
+        assert_eq!(middle.unpack(&[vec![1], vec![4]]), vec![1, 4]);
+    }
+
+    #[test]
+    fn spmv_rejects_mismatched_values() {
+        let ranks = DistributedMatrix::from_row_blocks(
+            partition_rows(&CsrMatrix::<i32>::identity(4), 2).unwrap(),
+        );
+
+        let error = ranks[0].spmv(&[1], &[]).unwrap_err();
+
+        assert!(matches!(error.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn boundary_rows_of_tridiagonal_matrix() {
+        let dense = DMatrix::from_fn(9, 9, |i, j| (i.max(j) - i.min(j) <= 1) as i32);
+        let ranks = DistributedMatrix::from_row_blocks(
+            partition_rows(&CsrMatrix::from(&dense), 3).unwrap(),
+        );
+
+        assert_eq!(ranks[0].boundary_rows(), &[2]);
+        assert_eq!(ranks[1].boundary_rows(), &[0, 2]);
+        assert_eq!(ranks[2].boundary_rows(), &[0]);
+    }
+
+    #[test]
+    fn begin_computes_interior_rows_in_full() {
+        let dense = DMatrix::from_fn(9, 9, |i, j| (i.max(j) - i.min(j) <= 1) as i32);
+        let ranks = DistributedMatrix::from_row_blocks(
+            partition_rows(&CsrMatrix::from(&dense), 3).unwrap(),
+        );
+
+        let x = DVector::from_fn(9, |i, _| i as i32 + 1);
+        let expected = &dense * &x;
+
+        let middle = &ranks[1];
+        let x_owned = x.rows_range(middle.owned_columns());
+        let pending = middle.begin_spmv(x_owned.as_slice()).unwrap();
+
+        // Row 4 only references owned columns, so it is complete before the ghosts arrive.
+        assert_eq!(pending.y[1], expected[4]);
+
+        let ghosts = [x[2], x[6]];
+        let y = pending.finish(&ghosts).unwrap();
+
+        assert_eq!(y, expected.rows_range(3..6).into_owned());
+    }
+
+    #[test]
+    fn finish_rejects_mismatched_ghosts() {
+        let dense = DMatrix::from_fn(4, 4, |i, j| (i.max(j) - i.min(j) <= 1) as i32);
+        let ranks = DistributedMatrix::from_row_blocks(
+            partition_rows(&CsrMatrix::from(&dense), 2).unwrap(),
+        );
+
+        let pending = ranks[0].begin_spmv(&[1, 2]).unwrap();
+        let error = pending.finish(&[]).unwrap_err();
+
+        assert!(matches!(error.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    proptest! {
+        #[test]
+        fn distributed_spmv_agrees_with_serial(matrix in csr_strategy(), parts in 1usize..5) {
+            let x = DVector::from_fn(matrix.ncols(), |i, _| 3 - i as i32);
+            let ranks = DistributedMatrix::from_row_blocks(partition_rows(&matrix, parts).unwrap());
+
+            prop_assert_eq!(simulate_spmv(&ranks, &x), DMatrix::from(&matrix) * x);
+        }
+    }
+}
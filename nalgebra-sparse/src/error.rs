@@ -138,6 +138,11 @@ pub enum OperationErrorKind {
     /// Indicates that a matrix is singular when it is expected to be invertible.
     #[error("Singular")]
     Singular,
+
+    /// Indicates that the operation was stopped before completion, because a stop was requested
+    /// through a [`Control`](crate::control::Control).
+    #[error("Cancelled")]
+    Cancelled,
 }
 
 impl OperationError {
@@ -0,0 +1,723 @@
+use super::{CholeskyError, CsCholesky, CsLu, LuError};
+use crate::cs::{Compression, CsMatrix};
+use nalgebra::{allocator::Allocator, DefaultAllocator, Dim, Matrix, OMatrix, RealField, Storage};
+use std::borrow::Borrow;
+
+/// [`AutoLu`] and [`AutoCholesky`] use the banded factorizations if their storage is at most this
+/// many times the number of explicit entries of the matrix.
+const BANDED_STORAGE_RATIO: usize = 4;
+
+/// An LU factorization `P A = L U` of a square banded matrix, with partial pivoting.
+///
+/// The factors are stored densely within the band, like the `gbtrf` routine of LAPACK: a matrix
+/// with `kl` sub-diagonals and `ku` super-diagonals takes `n (2 kl + ku + 1)` entries, since row
+/// exchanges can widen the upper band of `U` to `kl + ku`. Factoring costs `O(n kl (kl + ku))`
+/// and solving `O(n (2 kl + ku))` per right-hand side, without any of the index manipulation of
+/// the general sparse LU factorization [`CsLu`]. This makes it the method of choice for e.g. 1D
+/// discretizations, whose matrices have a handful of diagonals.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{cs::CsrMatrix, factorization::BandedLu};
+///
+/// // A tridiagonal matrix, whose first diagonal entry requires a row exchange.
+/// let dense = DMatrix::from_row_slice(4, 4, &[
+///     0.0, 1.0, 0.0, 0.0,
+///     2.0, 1.0, 1.0, 0.0,
+///     0.0, 1.0, 3.0, 1.0,
+///     0.0, 0.0, 1.0, 4.0,
+/// ]);
+/// let lu = BandedLu::factor(&CsrMatrix::from(&dense)).unwrap();
+/// assert_eq!((lu.lower_bandwidth(), lu.upper_bandwidth()), (1, 1));
+///
+/// let b = DVector::from_vec(vec![1.0, 2.0, 3.0, 4.0]);
+/// assert!((&dense * lu.solve(&b) - &b).norm() < 1e-12);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BandedLu<T> {
+    n: usize,
+    kl: usize,
+    ku: usize,
+    /// The factors, row by row: row `i` holds columns `i - kl` through `i + kl + ku`.
+    band: Vec<T>,
+    /// The row exchanged with row `k` before eliminating column `k`.
+    pivots: Vec<usize>,
+}
+
+impl<T: RealField> BandedLu<T> {
+    /// Computes the banded LU factorization of the provided matrix, whose bandwidths are
+    /// determined from its explicit entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LuError::NotSquare`] if the matrix is not square, and [`LuError::ZeroPivot`] if
+    /// it is singular.
+    pub fn factor<MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> Result<Self, LuError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        let (nrows, ncols) = matrix.shape();
+
+        if nrows != ncols {
+            return Err(LuError::NotSquare);
+        }
+
+        let (kl, ku) = bandwidths(matrix);
+        let n = nrows;
+        let width = 2 * kl + ku + 1;
+
+        let _span = span!("banded_lu_factor", n = n, kl = kl, ku = ku);
+
+        let mut lu = Self {
+            n,
+            kl,
+            ku,
+            band: vec![T::zero(); n * width],
+            pivots: Vec::with_capacity(n),
+        };
+
+        for (row, col, value) in row_col_triplets(matrix) {
+            let index = lu.index(row, col);
+            lu.band[index] = value.clone();
+        }
+
+        for k in 0..n {
+            let last_row = (k + kl).min(n - 1);
+            let last_col = (k + kl + ku).min(n - 1);
+
+            let mut pivot = k;
+            let mut largest = lu.at(k, k).abs();
+
+            for i in k + 1..=last_row {
+                let magnitude = lu.at(i, k).abs();
+
+                if magnitude > largest {
+                    pivot = i;
+                    largest = magnitude;
+                }
+            }
+
+            if largest.is_zero() {
+                return Err(LuError::ZeroPivot { column: k });
+            }
+
+            lu.pivots.push(pivot);
+
+            if pivot != k {
+                for j in k..=last_col {
+                    let (a, b) = (lu.index(k, j), lu.index(pivot, j));
+                    lu.band.swap(a, b);
+                }
+            }
+
+            let diagonal = lu.at(k, k);
+
+            for i in k + 1..=last_row {
+                let multiplier = lu.at(i, k) / diagonal.clone();
+                let index = lu.index(i, k);
+                lu.band[index] = multiplier.clone();
+
+                if !multiplier.is_zero() {
+                    for j in k + 1..=last_col {
+                        let update = multiplier.clone() * lu.at(k, j);
+                        let index = lu.index(i, j);
+                        lu.band[index] -= update;
+                    }
+                }
+            }
+        }
+
+        Ok(lu)
+    }
+
+    /// The number of sub-diagonals of the factored matrix.
+    #[must_use]
+    pub fn lower_bandwidth(&self) -> usize {
+        self.kl
+    }
+
+    /// The number of super-diagonals of the factored matrix. The factor `U` may have up to
+    /// `lower_bandwidth() + upper_bandwidth()` super-diagonals.
+    #[must_use]
+    pub fn upper_bandwidth(&self) -> usize {
+        self.ku
+    }
+
+    /// Solves the system `A X = B`, where `X` and `B` are dense matrices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `B` is the wrong size i.e. for an N×N matrix `A`, `B` must be some N×M matrix.
+    #[must_use]
+    pub fn solve<R, C, S>(&self, b: &Matrix<T, R, C, S>) -> OMatrix<T, R, C>
+    where
+        R: Dim,
+        C: Dim,
+        S: Storage<T, R, C>,
+        DefaultAllocator: Allocator<T, R, C>,
+    {
+        assert_eq!(
+            b.nrows(),
+            self.n,
+            "The right hand side must have as many rows as the matrix."
+        );
+
+        let _span = span!("banded_lu_solve", nrhs = b.ncols());
+
+        let n = self.n;
+        let mut x = b.clone_owned();
+
+        // Solve L Y = P B, applying the row exchanges in the order of the elimination.
+        for (k, &pivot) in self.pivots.iter().enumerate() {
+            if pivot != k {
+                x.swap_rows(k, pivot);
+            }
+
+            for i in k + 1..=(k + self.kl).min(n - 1) {
+                let multiplier = self.at(i, k);
+
+                for c in 0..x.ncols() {
+                    let update = multiplier.clone() * x[(k, c)].clone();
+                    x[(i, c)] -= update;
+                }
+            }
+        }
+
+        // Solve U X = Y
+        for k in (0..n).rev() {
+            for j in k + 1..=(k + self.kl + self.ku).min(n - 1) {
+                let u = self.at(k, j);
+
+                for c in 0..x.ncols() {
+                    let update = u.clone() * x[(j, c)].clone();
+                    x[(k, c)] -= update;
+                }
+            }
+
+            let diagonal = self.at(k, k);
+
+            for c in 0..x.ncols() {
+                x[(k, c)] /= diagonal.clone();
+            }
+        }
+
+        x
+    }
+
+    fn index(&self, row: usize, col: usize) -> usize {
+        row * (2 * self.kl + self.ku + 1) + (col + self.kl - row)
+    }
+
+    fn at(&self, row: usize, col: usize) -> T {
+        self.band[self.index(row, col)].clone()
+    }
+}
+
+/// A Cholesky factorization `A = L Lᵀ` of a symmetric positive definite banded matrix.
+///
+/// The factor `L` has the same bandwidth `b` as `A`, and is stored densely within the band in
+/// `n (b + 1)` entries. Factoring costs `O(n b²)` and solving `O(n b)` per right-hand side. Only
+/// the lower triangle of the matrix is read, and symmetry is not checked.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{cs::CscMatrix, factorization::BandedCholesky};
+///
+/// // The 1D Laplacian.
+/// let dense = DMatrix::from_fn(5, 5, |i, j| match i as isize - j as isize {
+///     0 => 2.0,
+///     -1 | 1 => -1.0,
+///     _ => 0.0,
+/// });
+/// let cholesky = BandedCholesky::factor(&CscMatrix::from(&dense)).unwrap();
+/// assert_eq!(cholesky.bandwidth(), 1);
+///
+/// let b = DVector::from_element(5, 1.0);
+/// assert!((&dense * cholesky.solve(&b) - &b).norm() < 1e-12);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BandedCholesky<T> {
+    n: usize,
+    bandwidth: usize,
+    /// The factor, row by row: row `i` holds columns `i - bandwidth` through `i`.
+    band: Vec<T>,
+}
+
+impl<T: RealField> BandedCholesky<T> {
+    /// Computes the banded Cholesky factorization of the provided matrix, whose bandwidth is
+    /// determined from the explicit entries of its lower triangle.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CholeskyError::NotSquare`] if the matrix is not square, and
+    /// [`CholeskyError::NotPositiveDefinite`] if it is not positive definite.
+    pub fn factor<MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> Result<Self, CholeskyError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        let (nrows, ncols) = matrix.shape();
+
+        if nrows != ncols {
+            return Err(CholeskyError::NotSquare);
+        }
+
+        let (bandwidth, _) = bandwidths(matrix);
+        let n = nrows;
+
+        let _span = span!("banded_cholesky_factor", n = n, bandwidth = bandwidth);
+
+        let mut cholesky = Self {
+            n,
+            bandwidth,
+            band: vec![T::zero(); n * (bandwidth + 1)],
+        };
+
+        for (row, col, value) in row_col_triplets(matrix).filter(|(row, col, _)| col <= row) {
+            let index = cholesky.index(row, col);
+            cholesky.band[index] = value.clone();
+        }
+
+        for i in 0..n {
+            let first = i.saturating_sub(bandwidth);
+
+            for j in first..=i {
+                let mut sum = cholesky.at(i, j);
+
+                // Both rows `i` and `j` are stored from column `first` on, since `j <= i`.
+                for k in first.max(j.saturating_sub(bandwidth))..j {
+                    sum -= cholesky.at(i, k) * cholesky.at(j, k);
+                }
+
+                let value = if j == i {
+                    if sum <= T::zero() {
+                        return Err(CholeskyError::NotPositiveDefinite);
+                    }
+
+                    sum.sqrt()
+                } else {
+                    sum / cholesky.at(j, j)
+                };
+
+                let index = cholesky.index(i, j);
+                cholesky.band[index] = value;
+            }
+        }
+
+        Ok(cholesky)
+    }
+
+    /// The number of sub-diagonals of the factored matrix, which is also that of `L`.
+    #[must_use]
+    pub fn bandwidth(&self) -> usize {
+        self.bandwidth
+    }
+
+    /// Solves the system `A X = B`, where `X` and `B` are dense matrices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `B` is the wrong size i.e. for an N×N matrix `A`, `B` must be some N×M matrix.
+    #[must_use]
+    pub fn solve<R, C, S>(&self, b: &Matrix<T, R, C, S>) -> OMatrix<T, R, C>
+    where
+        R: Dim,
+        C: Dim,
+        S: Storage<T, R, C>,
+        DefaultAllocator: Allocator<T, R, C>,
+    {
+        assert_eq!(
+            b.nrows(),
+            self.n,
+            "The right hand side must have as many rows as the matrix."
+        );
+
+        let _span = span!("banded_cholesky_solve", nrhs = b.ncols());
+
+        let n = self.n;
+        let mut x = b.clone_owned();
+
+        // Solve L Y = B
+        for i in 0..n {
+            for k in i.saturating_sub(self.bandwidth)..i {
+                let l = self.at(i, k);
+
+                for c in 0..x.ncols() {
+                    let update = l.clone() * x[(k, c)].clone();
+                    x[(i, c)] -= update;
+                }
+            }
+
+            let diagonal = self.at(i, i);
+
+            for c in 0..x.ncols() {
+                x[(i, c)] /= diagonal.clone();
+            }
+        }
+
+        // Solve Lᵀ X = Y
+        for i in (0..n).rev() {
+            for k in i + 1..=(i + self.bandwidth).min(n.saturating_sub(1)) {
+                let l = self.at(k, i);
+
+                for c in 0..x.ncols() {
+                    let update = l.clone() * x[(k, c)].clone();
+                    x[(i, c)] -= update;
+                }
+            }
+
+            let diagonal = self.at(i, i);
+
+            for c in 0..x.ncols() {
+                x[(i, c)] /= diagonal.clone();
+            }
+        }
+
+        x
+    }
+
+    fn index(&self, row: usize, col: usize) -> usize {
+        row * (self.bandwidth + 1) + (col + self.bandwidth - row)
+    }
+
+    fn at(&self, row: usize, col: usize) -> T {
+        self.band[self.index(row, col)].clone()
+    }
+}
+
+/// An LU factorization that uses [`BandedLu`] for matrices with a narrow band, and [`CsLu`] for
+/// all others.
+///
+/// The banded factorization is used if its storage of `n (2 kl + ku + 1)` entries is at most four
+/// times the number of explicit entries of the matrix (or of its dimension, if that is larger).
+/// This holds e.g. for every tridiagonal matrix, while the matrices of 2D and 3D discretizations
+/// are left to the general sparse factorization.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra::DVector;
+/// use nalgebra_sparse::{cs::CsrMatrix, factorization::AutoLu};
+///
+/// let a = CsrMatrix::from_fn_banded(100, 100, 1, |i, j| Some(if i == j { 4.0 } else { -1.0 }));
+/// let lu = AutoLu::factor(&a).unwrap();
+/// assert!(matches!(lu, AutoLu::Banded(_)));
+///
+/// let x = lu.solve(&DVector::from_element(100, 1.0));
+/// assert!(x.iter().all(|x_i| *x_i > 0.0));
+/// ```
+#[derive(Debug, Clone)]
+pub enum AutoLu<T: RealField> {
+    /// The factorization of a matrix with a narrow band.
+    Banded(BandedLu<T>),
+
+    /// The factorization of any other matrix, with partial pivoting.
+    Sparse(CsLu<T>),
+}
+
+impl<T: RealField> AutoLu<T> {
+    /// Computes the LU factorization of the provided matrix, with the banded or the general
+    /// sparse factorization as described above.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LuError::NotSquare`] if the matrix is not square, and [`LuError::ZeroPivot`] if
+    /// it is singular.
+    pub fn factor<MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> Result<Self, LuError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        let (kl, ku) = bandwidths(matrix);
+
+        if prefers_banded(matrix, 2 * kl + ku + 1) {
+            BandedLu::factor(matrix).map(AutoLu::Banded)
+        } else {
+            CsLu::factor(matrix).map(AutoLu::Sparse)
+        }
+    }
+
+    /// Solves the system `A X = B`, where `X` and `B` are dense matrices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `B` is the wrong size i.e. for an N×N matrix `A`, `B` must be some N×M matrix.
+    #[must_use]
+    pub fn solve<R, C, S>(&self, b: &Matrix<T, R, C, S>) -> OMatrix<T, R, C>
+    where
+        R: Dim,
+        C: Dim,
+        S: Storage<T, R, C>,
+        DefaultAllocator: Allocator<T, R, C>,
+    {
+        match self {
+            AutoLu::Banded(lu) => lu.solve(b),
+            AutoLu::Sparse(lu) => lu.solve(b),
+        }
+    }
+}
+
+/// A Cholesky factorization that uses [`BandedCholesky`] for matrices with a narrow band, and
+/// [`CsCholesky`] for all others.
+///
+/// The banded factorization is used if its storage of `n (b + 1)` entries is at most four times
+/// the number of explicit entries of the matrix (or of its dimension, if that is larger).
+#[derive(Debug, Clone)]
+pub enum AutoCholesky<T: RealField> {
+    /// The factorization of a matrix with a narrow band.
+    Banded(BandedCholesky<T>),
+
+    /// The factorization of any other matrix.
+    Sparse(CsCholesky<T>),
+}
+
+impl<T: RealField> AutoCholesky<T> {
+    /// Computes the Cholesky factorization of the provided symmetric positive definite matrix,
+    /// with the banded or the general sparse factorization as described above.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CholeskyError::NotSquare`] if the matrix is not square, and
+    /// [`CholeskyError::NotPositiveDefinite`] if it is not positive definite.
+    pub fn factor<MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> Result<Self, CholeskyError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        let (nrows, ncols) = matrix.shape();
+
+        if nrows != ncols {
+            return Err(CholeskyError::NotSquare);
+        }
+
+        let (bandwidth, _) = bandwidths(matrix);
+
+        if prefers_banded(matrix, bandwidth + 1) {
+            BandedCholesky::factor(matrix).map(AutoCholesky::Banded)
+        } else {
+            CsCholesky::factor(matrix).map(AutoCholesky::Sparse)
+        }
+    }
+
+    /// Solves the system `A X = B`, where `X` and `B` are dense matrices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `B` is the wrong size i.e. for an N×N matrix `A`, `B` must be some N×M matrix.
+    #[must_use]
+    pub fn solve<R, C, S>(&self, b: &Matrix<T, R, C, S>) -> OMatrix<T, R, C>
+    where
+        R: Dim,
+        C: Dim,
+        S: Storage<T, R, C>,
+        DefaultAllocator: Allocator<T, R, C>,
+    {
+        match self {
+            AutoCholesky::Banded(cholesky) => cholesky.solve(b),
+            AutoCholesky::Sparse(cholesky) => cholesky.solve(b),
+        }
+    }
+}
+
+/// An iterator over the explicit entries of `matrix` as `(row, col, value)` triplets.
+fn row_col_triplets<T, MO, MI, D, C>(
+    matrix: &CsMatrix<T, MO, MI, D, C>,
+) -> impl Iterator<Item = (usize, usize, &T)>
+where
+    T: RealField,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    matrix
+        .triplet_iter()
+        .map(|(major, minor, value)| (C::nmajor(major, minor), C::nminor(major, minor), value))
+}
+
+/// The number of sub- and super-diagonals of `matrix` that hold explicit entries.
+fn bandwidths<T, MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> (usize, usize)
+where
+    T: RealField,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    row_col_triplets(matrix).fold((0, 0), |(kl, ku), (row, col, _)| {
+        (
+            kl.max(row.saturating_sub(col)),
+            ku.max(col.saturating_sub(row)),
+        )
+    })
+}
+
+/// Whether a band storage of `width` entries per row is small enough to prefer over the general
+/// sparse factorizations.
+fn prefers_banded<T, MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>, width: usize) -> bool
+where
+    T: RealField,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let n = matrix.nrows();
+    n * width <= BANDED_STORAGE_RATIO * matrix.nnz().max(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cs::{CscMatrix, CsrMatrix};
+    use nalgebra::{DMatrix, DVector};
+    use proptest::prelude::*;
+
+    /// A square matrix with small integer entries within the given bandwidths.
+    fn banded_strategy() -> impl Strategy<Value = DMatrix<f64>> {
+        (0..9usize, 0..3usize, 0..3usize).prop_flat_map(|(n, kl, ku)| {
+            proptest::collection::vec(-3..=3i32, n * n).prop_map(move |values| {
+                DMatrix::from_fn(n, n, |i, j| {
+                    if i <= j + kl && j <= i + ku {
+                        f64::from(values[i * n + j])
+                    } else {
+                        0.0
+                    }
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn banded_factorizations_report_errors() {
+        let rectangular = CsrMatrix::<f64>::zeros(2, 3);
+        assert_eq!(
+            BandedLu::factor(&rectangular).unwrap_err(),
+            LuError::NotSquare
+        );
+        assert_eq!(
+            BandedCholesky::factor(&rectangular).unwrap_err(),
+            CholeskyError::NotSquare
+        );
+
+        // The first two rows are linearly dependent, which shows once the third row is eliminated.
+        let singular = CsrMatrix::from(&DMatrix::from_row_slice(
+            3,
+            3,
+            &[1.0, 2.0, 0.0, 2.0, 4.0, 0.0, 0.0, 1.0, 1.0],
+        ));
+        assert_eq!(
+            BandedLu::factor(&singular).unwrap_err(),
+            LuError::ZeroPivot { column: 2 }
+        );
+
+        let indefinite = CscMatrix::from(&DMatrix::from_row_slice(2, 2, &[1.0, 2.0, 2.0, 1.0]));
+        assert_eq!(
+            BandedCholesky::factor(&indefinite).unwrap_err(),
+            CholeskyError::NotPositiveDefinite
+        );
+
+        let empty = CsrMatrix::<f64>::zeros(0, 0);
+        assert_eq!(
+            BandedLu::factor(&empty)
+                .unwrap()
+                .solve(&DVector::zeros(0))
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn auto_factorizations_route_by_bandwidth() {
+        let n = 16;
+        let tridiagonal = DMatrix::from_fn(n, n, |i, j| match i as isize - j as isize {
+            0 => 4.0,
+            -1 | 1 => -1.0,
+            _ => 0.0,
+        });
+
+        // The same matrix with two far off-diagonal entries, which widen the band.
+        let mut wide = tridiagonal.clone();
+        wide[(0, n - 1)] = -1.0;
+        wide[(n - 1, 0)] = -1.0;
+
+        let b = DVector::from_fn(n, |i, _| i as f64);
+
+        for (dense, banded) in [(tridiagonal, true), (wide, false)] {
+            let csr = CsrMatrix::from(&dense);
+            let expected = dense.clone().lu().solve(&b).unwrap();
+
+            let lu = AutoLu::factor(&csr).unwrap();
+            assert_eq!(matches!(lu, AutoLu::Banded(_)), banded);
+            assert!((lu.solve(&b) - &expected).amax() < 1e-12);
+
+            let cholesky = AutoCholesky::factor(&CscMatrix::from(&dense)).unwrap();
+            assert_eq!(matches!(cholesky, AutoCholesky::Banded(_)), banded);
+            assert!((cholesky.solve(&b) - &expected).amax() < 1e-12);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn banded_lu_solves_systems_and_detects_singular_matrices(
+            dense in banded_strategy(),
+            nrhs in 0..3usize,
+        ) {
+            let n = dense.nrows();
+            let b = DMatrix::from_fn(n, nrhs, |i, j| (i + 2 * j) as f64 - 3.0);
+            let result = BandedLu::factor(&CsrMatrix::from(&dense));
+
+            // The determinant of an integer matrix is an integer, so it is either zero or at
+            // least one in magnitude.
+            if dense.determinant().abs() >= 0.5 {
+                let lu = result.unwrap();
+                prop_assert!((&dense * lu.solve(&b) - &b).amax() < 1e-8);
+            } else if let Ok(lu) = result {
+                // Rounding may leave a tiny pivot instead of an exact zero, but no larger than
+                // the rounding error of the elimination.
+                let smallest = (0..n).map(|k| lu.at(k, k).abs()).fold(f64::INFINITY, f64::min);
+                let tolerance = f64::EPSILON * n as f64 * dense.amax();
+                prop_assert!(
+                    smallest <= tolerance,
+                    "singular matrix with smallest pivot {} > {}",
+                    smallest,
+                    tolerance
+                );
+            } else {
+                prop_assert!(
+                    matches!(result, Err(LuError::ZeroPivot { .. })),
+                    "expected a zero pivot"
+                );
+            }
+        }
+
+        #[test]
+        fn banded_cholesky_solves_positive_definite_systems(
+            lower in banded_strategy(),
+            nrhs in 0..3usize,
+        ) {
+            // `B Bᵀ + I` is positive definite with the lower bandwidth of `B` in both triangles.
+            let factor = lower.lower_triangle();
+            let n = factor.nrows();
+            let dense = &factor * factor.transpose() + DMatrix::identity(n, n);
+            let b = DMatrix::from_fn(n, nrhs, |i, j| (i + 2 * j) as f64 - 3.0);
+
+            let cholesky = BandedCholesky::factor(&CscMatrix::from(&dense)).unwrap();
+            prop_assert!(cholesky.bandwidth() <= 2);
+            prop_assert!((&dense * cholesky.solve(&b) - &b).amax() < 1e-8);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Batched factorization and solves of many independent, small sparse systems.
+//!
+//! Some applications (e.g. contact mechanics or per-cell chemistry in a simulation) need to solve
+//! thousands of small, independent systems at every step. Factoring each of these on its own
+//! works, but leaves the parallelism across the batch on the table. The functions in this module
+//! operate on a whole batch at once, and when the `rayon` feature is enabled, process the systems
+//! of the batch in parallel. Each individual factorization and solve remains single-threaded.
+//!
+//! The systems in a batch may all have different sparsity patterns. When every system shares the
+//! same pattern, [`factor_batch_with_pattern`] skips the symbolic factorization for all of them.
+//!
+//! Results are always returned in the same order as the inputs, and a failure to factor one
+//! system of the batch does not affect any of the others.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra::DMatrix;
+//! use nalgebra_sparse::{cs::CscMatrix, factorization::batch::factor_and_solve_batch};
+//!
+//! let matrices = (1..=4)
+//!     .map(|i| CscMatrix::<f64>::identity(3) * (i as f64))
+//!     .collect::<Vec<_>>();
+//!
+//! let rhs = vec![DMatrix::from_element(3, 1, 2.0); 4];
+//!
+//! let solutions = factor_and_solve_batch(&matrices, &rhs);
+//!
+//! for (i, solution) in solutions.into_iter().enumerate() {
+//!     let solution = solution.unwrap();
+//!     assert!((solution[(0, 0)] - 2.0 / (i + 1) as f64).abs() < 1e-12);
+//! }
+//! ```
+
+use super::{CholeskyError, CholeskyPattern, CsCholesky};
+use crate::cs::{Compression, CsMatrix};
+use nalgebra::{DMatrix, RealField, Scalar};
+use std::borrow::Borrow;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Applies `f` to every element of `items`, in parallel if the `rayon` feature is enabled.
+#[cfg(feature = "rayon")]
+fn map_batch<I, O, F>(items: &[I], f: F) -> Vec<O>
+where
+    I: Sync,
+    O: Send,
+    F: Fn(&I) -> O + Sync + Send,
+{
+    items.par_iter().map(f).collect()
+}
+
+/// Applies `f` to every element of `items`, in parallel if the `rayon` feature is enabled.
+#[cfg(not(feature = "rayon"))]
+fn map_batch<I, O, F>(items: &[I], f: F) -> Vec<O>
+where
+    I: Sync,
+    O: Send,
+    F: Fn(&I) -> O + Sync + Send,
+{
+    items.iter().map(f).collect()
+}
+
+/// Computes the Cholesky factorization of every matrix in the batch.
+///
+/// See [`CsCholesky::factor`] for the requirements on each matrix.
+///
+/// # Panics
+///
+/// Panics if any of the matrices is not square.
+pub fn factor_batch<T, MO, MI, D, C>(
+    matrices: &[CsMatrix<T, MO, MI, D, C>],
+) -> Vec<Result<CsCholesky<T>, CholeskyError>>
+where
+    T: Scalar + RealField + Send + Sync,
+    MO: Borrow<[usize]> + Sync,
+    MI: Borrow<[usize]> + Sync,
+    D: Borrow<[T]> + Sync,
+    C: Compression + Sync,
+{
+    let _span = span!("cholesky_factor_batch", batch_size = matrices.len());
+
+    map_batch(matrices, |matrix| CsCholesky::factor(matrix))
+}
+
+/// Computes the Cholesky factorization of every matrix in a batch that shares a single sparsity
+/// pattern.
+///
+/// `l_pattern` is the pattern of the Cholesky factor of any one of the matrices, e.g. as obtained
+/// from [`CsCholesky::into_pattern`]. Reusing it skips the symbolic factorization of every
+/// matrix in the batch.
+///
+/// # Errors
+///
+/// Each matrix fails in the same way as in [`CsCholesky::factor_with_pattern`].
+pub fn factor_batch_with_pattern<T, MO, MI, D, C>(
+    l_pattern: &CholeskyPattern,
+    matrices: &[CsMatrix<T, MO, MI, D, C>],
+) -> Vec<Result<CsCholesky<T>, CholeskyError>>
+where
+    T: Scalar + RealField + Send + Sync,
+    MO: Borrow<[usize]> + Sync,
+    MI: Borrow<[usize]> + Sync,
+    D: Borrow<[T]> + Sync,
+    C: Compression + Sync,
+{
+    let _span = span!(
+        "cholesky_factor_batch_with_pattern",
+        batch_size = matrices.len()
+    );
+
+    map_batch(matrices, |matrix| {
+        CsCholesky::factor_with_pattern(l_pattern.clone(), matrix)
+    })
+}
+
+/// Solves the system `A_i X_i = B_i` for every factorization `A_i` and right-hand side `B_i` in
+/// the batch.
+///
+/// # Panics
+///
+/// Panics if `factors` and `rhs` have different lengths, or if any `B_i` is the wrong size for
+/// its system (see [`CsCholesky::solve`]).
+pub fn solve_batch<T>(factors: &[CsCholesky<T>], rhs: &[DMatrix<T>]) -> Vec<DMatrix<T>>
+where
+    T: Scalar + RealField + Send + Sync,
+{
+    assert_eq!(
+        factors.len(),
+        rhs.len(),
+        "The batch must have one right-hand side per factorization."
+    );
+
+    let _span = span!("cholesky_solve_batch", batch_size = factors.len());
+
+    let systems = factors.iter().zip(rhs).collect::<Vec<_>>();
+
+    map_batch(&systems, |(factor, b)| factor.solve(*b))
+}
+
+/// Factors each matrix `A_i` in the batch and solves `A_i X_i = B_i` with it.
+///
+/// This is equivalent to calling [`factor_batch`] followed by [`solve_batch`], except that systems
+/// that fail to factor do not prevent the rest of the batch from being solved, and no
+/// factorizations are kept around once their system has been solved.
+///
+/// # Errors
+///
+/// Each system fails in the same way as in [`CsCholesky::factor`].
+///
+/// # Panics
+///
+/// Panics if `matrices` and `rhs` have different lengths, if any of the matrices is not square, or
+/// if any `B_i` is the wrong size for its system.
+pub fn factor_and_solve_batch<T, MO, MI, D, C>(
+    matrices: &[CsMatrix<T, MO, MI, D, C>],
+    rhs: &[DMatrix<T>],
+) -> Vec<Result<DMatrix<T>, CholeskyError>>
+where
+    T: Scalar + RealField + Send + Sync,
+    MO: Borrow<[usize]> + Sync,
+    MI: Borrow<[usize]> + Sync,
+    D: Borrow<[T]> + Sync,
+    C: Compression + Sync,
+{
+    assert_eq!(
+        matrices.len(),
+        rhs.len(),
+        "The batch must have one right-hand side per matrix."
+    );
+
+    let _span = span!(
+        "cholesky_factor_and_solve_batch",
+        batch_size = matrices.len()
+    );
+
+    let systems = matrices.iter().zip(rhs).collect::<Vec<_>>();
+
+    map_batch(&systems, |(matrix, b)| {
+        CsCholesky::factor(*matrix).map(|factor| factor.solve(*b))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cs::CscMatrix;
+    use matrixcompare::assert_matrix_eq;
+
+    /// Builds an SPD tridiagonal matrix with `diagonal` on its diagonal and `-1` off of it.
+    fn tridiagonal(n: usize, diagonal: f64) -> CscMatrix<f64> {
+        let dense = DMatrix::from_fn(n, n, |i, j| {
+            if i == j {
+                diagonal
+            } else if i.max(j) - i.min(j) == 1 {
+                -1.0
+            } else {
+                0.0
+            }
+        });
+
+        CscMatrix::from(&dense)
+    }
+
+    #[test]
+    fn batch_factorizations_agree_with_individual_factorizations() {
+        let matrices = (0..16)
+            .map(|i| tridiagonal(2 + i % 5, 4.0 + i as f64))
+            .collect::<Vec<_>>();
+
+        let batch = factor_batch(&matrices);
+
+        assert_eq!(batch.len(), matrices.len());
+
+        for (matrix, factor) in matrices.iter().zip(batch) {
+            let expected = CsCholesky::factor(matrix).unwrap();
+            assert_matrix_eq!(factor.unwrap().l(), expected.l());
+        }
+    }
+
+    #[test]
+    fn batch_with_pattern_reuses_shared_pattern() {
+        let matrices = (0..8)
+            .map(|i| tridiagonal(6, 3.0 + i as f64))
+            .collect::<Vec<_>>();
+
+        let pattern = CsCholesky::factor(&matrices[0]).unwrap().into_pattern();
+        let batch = factor_batch_with_pattern(&pattern, &matrices);
+
+        for (matrix, factor) in matrices.iter().zip(batch) {
+            let expected = CsCholesky::factor(matrix).unwrap();
+            assert_matrix_eq!(factor.unwrap().l(), expected.l(), comp = float);
+        }
+    }
+
+    #[test]
+    fn batch_solutions_satisfy_their_systems() {
+        let matrices = (0..10).map(|i| tridiagonal(1 + i, 5.0)).collect::<Vec<_>>();
+
+        let rhs = matrices
+            .iter()
+            .map(|matrix| DMatrix::from_fn(matrix.nrows(), 2, |i, j| (i + j) as f64))
+            .collect::<Vec<_>>();
+
+        let factors = factor_batch(&matrices)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let solutions = solve_batch(&factors, &rhs);
+
+        for ((matrix, b), x) in matrices.iter().zip(&rhs).zip(&solutions) {
+            let residual = DMatrix::from(matrix) * x - b;
+            assert!(residual.norm() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn failed_system_does_not_affect_rest_of_batch() {
+        let matrices = vec![
+            tridiagonal(3, 4.0),
+            tridiagonal(3, -4.0),
+            tridiagonal(3, 4.0),
+        ];
+
+        let rhs = vec![DMatrix::from_element(3, 1, 1.0); 3];
+
+        let solutions = factor_and_solve_batch(&matrices, &rhs);
+
+        assert!(solutions[0].is_ok());
+        assert_eq!(solutions[1], Err(CholeskyError::NotPositiveDefinite));
+        assert!(solutions[2].is_ok());
+    }
+}
@@ -0,0 +1,325 @@
+use super::{CholeskyError, CholeskySymbolic, IluError, IluSymbolic};
+use crate::{
+    cs::{CompressedRowStorage, Compression, CsMatrix},
+    frozen::{pattern_hash, FrozenCsrMatrix},
+};
+use nalgebra::Scalar;
+use std::{
+    any::TypeId,
+    borrow::Borrow,
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+/// A cache of symbolic analyses, keyed by sparsity pattern.
+///
+/// Applications that repeatedly assemble matrices with the same structure, e.g. the same mesh
+/// with different coefficients, often analyze that structure again in every module that
+/// factors one of them. Requesting the analysis through a `SymbolicCache` instead runs it only
+/// for the first matrix with a given pattern, and hands out shared references to that analysis
+/// for every later matrix.
+///
+/// Patterns are looked up by a hash of the shape, offsets and indices of the matrix, and then
+/// compared in full, so a hash collision can never return the analysis of a different pattern.
+/// Passing a [`FrozenCsrMatrix`] skips the hashing, since it was already done when freezing.
+///
+/// A cache can either be owned by the application, or be the process-wide one returned by
+/// [`SymbolicCache::global`]. Nothing is cached unless it is requested through a cache.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra::DMatrix;
+/// use nalgebra_sparse::{
+///     cs::CscMatrix,
+///     factorization::{CsCholesky, SymbolicCache},
+/// };
+///
+/// let dense = DMatrix::from_row_slice(3, 3, &[4.0, 1.0, 0.0, 1.0, 4.0, 1.0, 0.0, 1.0, 4.0]);
+/// let a = CscMatrix::from(&dense);
+/// let b = CscMatrix::from(&(dense * 2.0));
+///
+/// let mut cache = SymbolicCache::new();
+/// let first = cache.cholesky(&a).unwrap();
+/// let second = cache.cholesky(&b).unwrap();
+///
+/// assert_eq!(cache.hits(), 1);
+/// assert_eq!(cache.misses(), 1);
+///
+/// let cholesky = CsCholesky::factor_symbolic(&second, &b).unwrap();
+/// assert_eq!(cholesky.l().nnz(), first.nnz());
+/// ```
+#[derive(Debug, Default)]
+pub struct SymbolicCache {
+    cholesky: HashMap<u64, Vec<CacheEntry<CholeskySymbolic>>>,
+    ilu: HashMap<u64, Vec<CacheEntry<IluSymbolic>>>,
+    hits: usize,
+    misses: usize,
+}
+
+#[derive(Debug)]
+struct CacheEntry<S> {
+    compression: TypeId,
+    shape: (usize, usize),
+    offsets: Vec<usize>,
+    indices: Vec<usize>,
+    level: usize,
+    symbolic: Arc<S>,
+}
+
+impl<S> CacheEntry<S> {
+    fn matches<T, MO, MI, D, C>(&self, matrix: &CsMatrix<T, MO, MI, D, C>, level: usize) -> bool
+    where
+        T: Scalar,
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression + 'static,
+    {
+        let (offsets, indices, _) = matrix.cs_data();
+
+        self.compression == TypeId::of::<C>()
+            && self.level == level
+            && self.shape == matrix.shape()
+            && self.offsets == offsets
+            && self.indices == indices
+    }
+}
+
+impl SymbolicCache {
+    /// Creates an empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide cache.
+    ///
+    /// The global cache is created empty the first time it is requested, and only holds the
+    /// analyses requested through it. It lives for the rest of the process, so long-running
+    /// applications should [`clear`](SymbolicCache::clear) it when the patterns they work with
+    /// change.
+    pub fn global() -> &'static Mutex<SymbolicCache> {
+        static GLOBAL: OnceLock<Mutex<SymbolicCache>> = OnceLock::new();
+
+        GLOBAL.get_or_init(|| Mutex::new(SymbolicCache::new()))
+    }
+
+    /// Returns the Cholesky analysis of the pattern of `matrix`, computing it with
+    /// [`CholeskySymbolic::analyze`] if it is not cached yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`CholeskySymbolic::analyze`]. Failed analyses are not cached.
+    pub fn cholesky<T, MO, MI, D, C>(
+        &mut self,
+        matrix: &CsMatrix<T, MO, MI, D, C>,
+    ) -> Result<Arc<CholeskySymbolic>, CholeskyError>
+    where
+        T: Scalar,
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression + 'static,
+    {
+        self.cholesky_with_hash(pattern_hash(matrix), matrix)
+    }
+
+    /// Behaves like [`SymbolicCache::cholesky`], but reuses the pattern hash computed when the
+    /// matrix was frozen.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`CholeskySymbolic::analyze`]. Failed analyses are not cached.
+    pub fn cholesky_frozen<T: Scalar>(
+        &mut self,
+        matrix: &FrozenCsrMatrix<T>,
+    ) -> Result<Arc<CholeskySymbolic>, CholeskyError> {
+        self.cholesky_with_hash(matrix.pattern_hash(), matrix.matrix())
+    }
+
+    /// Returns the incomplete LU analysis of the pattern of `matrix` with the given level of
+    /// fill, computing it with [`IluSymbolic::analyze`] if it is not cached yet.
+    ///
+    /// Analyses of the same pattern with different levels of fill are cached separately.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`IluSymbolic::analyze`]. Failed analyses are not cached.
+    pub fn ilu<T, MO, MI, D>(
+        &mut self,
+        matrix: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+        level: usize,
+    ) -> Result<Arc<IluSymbolic>, IluError>
+    where
+        T: Scalar,
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        self.ilu_with_hash(pattern_hash(matrix), matrix, level)
+    }
+
+    /// Behaves like [`SymbolicCache::ilu`], but reuses the pattern hash computed when the matrix
+    /// was frozen.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`IluSymbolic::analyze`]. Failed analyses are not cached.
+    pub fn ilu_frozen<T: Scalar>(
+        &mut self,
+        matrix: &FrozenCsrMatrix<T>,
+        level: usize,
+    ) -> Result<Arc<IluSymbolic>, IluError> {
+        self.ilu_with_hash(matrix.pattern_hash(), matrix.matrix(), level)
+    }
+
+    /// The number of requests that were answered from the cache.
+    #[must_use]
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// The number of requests that required a new analysis.
+    #[must_use]
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// The number of cached analyses.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cholesky.values().map(Vec::len).sum::<usize>()
+            + self.ilu.values().map(Vec::len).sum::<usize>()
+    }
+
+    /// Returns `true` if no analyses are cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every cached analysis and resets the hit and miss counts.
+    ///
+    /// Analyses that were handed out before remain valid.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn cholesky_with_hash<T, MO, MI, D, C>(
+        &mut self,
+        hash: u64,
+        matrix: &CsMatrix<T, MO, MI, D, C>,
+    ) -> Result<Arc<CholeskySymbolic>, CholeskyError>
+    where
+        T: Scalar,
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression + 'static,
+    {
+        let entries = self.cholesky.entry(hash).or_default();
+
+        if let Some(entry) = entries.iter().find(|entry| entry.matches(matrix, 0)) {
+            self.hits += 1;
+            return Ok(Arc::clone(&entry.symbolic));
+        }
+
+        let symbolic = Arc::new(CholeskySymbolic::analyze(matrix)?);
+        self.misses += 1;
+        entries.push(new_entry(matrix, 0, Arc::clone(&symbolic)));
+
+        Ok(symbolic)
+    }
+
+    fn ilu_with_hash<T, MO, MI, D>(
+        &mut self,
+        hash: u64,
+        matrix: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+        level: usize,
+    ) -> Result<Arc<IluSymbolic>, IluError>
+    where
+        T: Scalar,
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let entries = self.ilu.entry(hash).or_default();
+
+        if let Some(entry) = entries.iter().find(|entry| entry.matches(matrix, level)) {
+            self.hits += 1;
+            return Ok(Arc::clone(&entry.symbolic));
+        }
+
+        let symbolic = Arc::new(IluSymbolic::analyze(matrix, level)?);
+        self.misses += 1;
+        entries.push(new_entry(matrix, level, Arc::clone(&symbolic)));
+
+        Ok(symbolic)
+    }
+}
+
+fn new_entry<S, T, MO, MI, D, C>(
+    matrix: &CsMatrix<T, MO, MI, D, C>,
+    level: usize,
+    symbolic: Arc<S>,
+) -> CacheEntry<S>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression + 'static,
+{
+    let (offsets, indices, _) = matrix.cs_data();
+
+    CacheEntry {
+        compression: TypeId::of::<C>(),
+        shape: matrix.shape(),
+        offsets: offsets.to_vec(),
+        indices: indices.to_vec(),
+        level,
+        symbolic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cs::{CscMatrix, CsrMatrix};
+    use nalgebra::DMatrix;
+
+    #[test]
+    fn cache_reuses_analyses_of_equal_patterns() {
+        let dense = DMatrix::from_row_slice(3, 3, &[4.0, 1.0, 0.0, 1.0, 4.0, 1.0, 0.0, 1.0, 4.0]);
+        let csr = CsrMatrix::from(&dense);
+        let frozen = FrozenCsrMatrix::new(CsrMatrix::from(&(dense.clone() * 3.0)));
+
+        let mut cache = SymbolicCache::new();
+        let first = cache.cholesky(&csr).unwrap();
+        let second = cache.cholesky_frozen(&frozen).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // The same pattern in another format, or with another level of fill, is a new analysis.
+        cache.cholesky(&CscMatrix::from(&dense)).unwrap();
+        let ilu0 = cache.ilu(&csr, 0).unwrap();
+        let ilu1 = cache.ilu_frozen(&frozen, 1).unwrap();
+        assert!(!Arc::ptr_eq(&ilu0, &ilu1));
+        assert!(Arc::ptr_eq(&ilu1, &cache.ilu(&csr, 1).unwrap()));
+
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (2, 4, 4));
+
+        let identity = CsrMatrix::<f64>::identity(3);
+        let other = cache.cholesky(&identity).unwrap();
+        assert_eq!(other.nnz(), 3);
+        assert!(matches!(
+            cache.cholesky(&CsrMatrix::<f64>::zeros(2, 3)),
+            Err(CholeskyError::NotSquare)
+        ));
+        assert_eq!(cache.len(), 5);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(first.nnz(), 5);
+    }
+}
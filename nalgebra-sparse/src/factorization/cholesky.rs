@@ -1,4 +1,5 @@
 use crate::{
+    control::{Control, Stage},
     convert::utils::CountToOffsetIter,
     cs::{Compression, CsMatrix, CscMatrix},
     ops::serial::spsolve::*,
@@ -56,6 +57,162 @@ impl CholeskyPattern {
     }
 }
 
+/// The symbolic analysis of a sparse Cholesky factorization.
+///
+/// The analysis only depends on the sparsity pattern of the factored matrix: it consists of the
+/// elimination tree of the matrix, the number of non-zeros in every column of the factor `L` and
+/// the pattern of `L` itself. It can therefore be computed once and reused for every matrix that
+/// shares the same pattern, which skips the symbolic phase of every subsequent factorization (see
+/// [`CsCholesky::factor_symbolic`] and [`CsCholesky::refactor`]).
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra::DMatrix;
+/// use nalgebra_sparse::{cs::CscMatrix, factorization::{CholeskySymbolic, CsCholesky}};
+///
+/// let dense = DMatrix::from_row_slice(3, 3, &[4.0, 1.0, 0.0, 1.0, 4.0, 1.0, 0.0, 1.0, 4.0]);
+/// let a = CscMatrix::from(&dense);
+///
+/// let symbolic = CholeskySymbolic::analyze(&a).unwrap();
+/// assert_eq!(symbolic.elimination_tree(), &[Some(1), Some(2), None]);
+///
+/// let mut cholesky = CsCholesky::factor_symbolic(&symbolic, &a).unwrap();
+///
+/// // Same pattern, new values: only the numeric phase runs again.
+/// let b = CscMatrix::from(&(dense * 2.0));
+/// cholesky.refactor(&symbolic, &b).unwrap();
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CholeskySymbolic {
+    l_pattern: CholeskyPattern,
+    lt_pattern: CholeskyPattern,
+    elimination_tree: Vec<Option<usize>>,
+}
+
+impl CholeskySymbolic {
+    /// Performs the symbolic analysis of the provided matrix.
+    ///
+    /// Only the sparsity pattern of the matrix is used; the values are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CholeskyError::NotSquare`] if the matrix is not square.
+    pub fn analyze<T, MO, MI, D, C>(
+        matrix: &CsMatrix<T, MO, MI, D, C>,
+    ) -> Result<Self, CholeskyError>
+    where
+        T: Scalar,
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        Self::analyze_with_control(matrix, &Control::default())
+    }
+
+    /// Behaves like [`CholeskySymbolic::analyze`], but reports progress to `control` and stops if
+    /// it requests it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CholeskyError::Cancelled`] if `control` requested a stop, in addition to the
+    /// errors returned by [`CholeskySymbolic::analyze`].
+    pub fn analyze_with_control<T, MO, MI, D, C>(
+        matrix: &CsMatrix<T, MO, MI, D, C>,
+        control: &Control<'_>,
+    ) -> Result<Self, CholeskyError>
+    where
+        T: Scalar,
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        let (nrows, ncols) = matrix.shape();
+
+        if nrows != ncols {
+            return Err(CholeskyError::NotSquare);
+        }
+
+        let span = span!("cholesky_symbolic", l_nnz = tracing::field::Empty);
+
+        let elimination_tree = elimination_tree(matrix);
+        let lt_pattern = nonzero_pattern(matrix, &elimination_tree, control);
+        record!(span, l_nnz = lt_pattern.indices.len());
+
+        if control.should_stop() {
+            return Err(CholeskyError::Cancelled);
+        }
+
+        Ok(Self {
+            l_pattern: lt_pattern.transpose(),
+            lt_pattern,
+            elimination_tree,
+        })
+    }
+
+    /// The shape of the analyzed matrix.
+    #[must_use]
+    pub fn shape(&self) -> (usize, usize) {
+        self.l_pattern.shape
+    }
+
+    /// The elimination tree of the analyzed matrix.
+    ///
+    /// Entry `j` holds the parent of column `j` in the tree, which is the row index of the first
+    /// off-diagonal non-zero in column `j` of `L`, or `None` if `j` is a root.
+    #[must_use]
+    pub fn elimination_tree(&self) -> &[Option<usize>] {
+        &self.elimination_tree
+    }
+
+    /// The number of non-zeros in every column of the factor `L`, including the diagonal.
+    pub fn column_counts(&self) -> impl Iterator<Item = usize> + '_ {
+        let offsets = &self.l_pattern.offsets;
+        let nnz = self.l_pattern.indices.len();
+
+        offsets
+            .iter()
+            .enumerate()
+            .map(move |(j, &offset)| offsets.get(j + 1).copied().unwrap_or(nnz) - offset)
+    }
+
+    /// The number of non-zeros in the factor `L`.
+    #[must_use]
+    pub fn nnz(&self) -> usize {
+        self.l_pattern.indices.len()
+    }
+
+    /// The sparsity pattern of the factor `L`.
+    #[must_use]
+    pub fn l_pattern(&self) -> &CholeskyPattern {
+        &self.l_pattern
+    }
+
+    /// Checks that every entry on or below the diagonal of `matrix` is part of the pattern of
+    /// `L`, i.e. that `matrix` can be factored with this analysis.
+    fn admits<T, MO, MI, D, C>(&self, matrix: &CsMatrix<T, MO, MI, D, C>) -> bool
+    where
+        T: Scalar,
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        let offsets = &self.l_pattern.offsets;
+        let indices = &self.l_pattern.indices;
+
+        matrix.iter().enumerate().all(|(i, lane)| {
+            let upper = offsets.get(i + 1).copied().unwrap_or(indices.len());
+            let l_lane = &indices[offsets[i]..upper];
+
+            lane.filter(|(j, _)| *j >= i)
+                .all(|(j, _)| l_lane.binary_search(&j).is_ok())
+        })
+    }
+}
+
 /// A sparse Cholesky factorization `A = L L^T` of a [`CscMatrix`].
 ///
 /// The factor `L` is a sparse, lower-triangular matrix. See the article on [Wikipedia] for
@@ -93,6 +250,14 @@ pub enum CholeskyError {
     /// The matrix and cholesky pattern have different shapes.
     #[error("The matrix and cholesky pattern have different shapes.")]
     ShapeMismatch,
+
+    /// The matrix has non-zeros outside of the pattern of a previous symbolic analysis.
+    #[error("The matrix has non-zeros outside of the analyzed sparsity pattern.")]
+    PatternMismatch,
+
+    /// The factorization was stopped before completion.
+    #[error("The factorization was cancelled before completion.")]
+    Cancelled,
 }
 
 impl<T: Scalar + RealField> CsCholesky<T> {
@@ -116,14 +281,122 @@ impl<T: Scalar + RealField> CsCholesky<T> {
         D: Borrow<[T]>,
         C: Compression,
     {
-        let (nrows, ncols) = matrix.shape();
+        Self::factor_with_control(matrix, &Control::default())
+    }
 
-        if nrows == ncols {
-            let lt_pattern = nonzero_pattern(matrix);
-            Self::decompose_left_looking(lt_pattern.transpose(), lt_pattern, matrix)
-        } else {
-            Err(CholeskyError::NotSquare)
+    /// Computes the Cholesky factorization of the provided matrix, stopping early if `control`
+    /// requests it.
+    ///
+    /// Behaves like [`CsCholesky::factor`], but checks `control` once per column of the factor, and
+    /// reports the progress of both the symbolic and numeric stages of the factorization.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CholeskyError::Cancelled`] if `control` requested a stop before the
+    /// factorization completed, in addition to the errors returned by [`CsCholesky::factor`].
+    pub fn factor_with_control<MO, MI, D, C>(
+        matrix: &CsMatrix<T, MO, MI, D, C>,
+        control: &Control<'_>,
+    ) -> Result<Self, CholeskyError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        let _span = span!("cholesky_factor", n = matrix.nrows(), nnz = matrix.nnz());
+
+        let CholeskySymbolic {
+            l_pattern,
+            lt_pattern,
+            ..
+        } = CholeskySymbolic::analyze_with_control(matrix, control)?;
+
+        Self::decompose_left_looking(l_pattern, &lt_pattern, matrix, control)
+    }
+
+    /// Computes the Cholesky factorization of the provided matrix, reusing a previous symbolic
+    /// analysis.
+    ///
+    /// The matrix must have the same sparsity pattern as the matrix that `symbolic` was computed
+    /// from (or a subset of it), and must be symmetric positive definite.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CholeskyError::ShapeMismatch`] if the shapes of the matrix and the analysis
+    /// differ, [`CholeskyError::PatternMismatch`] if the matrix has non-zeros outside of the
+    /// analyzed pattern, and [`CholeskyError::NotPositiveDefinite`] if the numeric factorization
+    /// fails.
+    pub fn factor_symbolic<MO, MI, D, C>(
+        symbolic: &CholeskySymbolic,
+        matrix: &CsMatrix<T, MO, MI, D, C>,
+    ) -> Result<Self, CholeskyError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        check_symbolic(symbolic, matrix)?;
+
+        Self::decompose_left_looking(
+            symbolic.l_pattern.clone(),
+            &symbolic.lt_pattern,
+            matrix,
+            &Control::default(),
+        )
+    }
+
+    /// Recomputes the factorization in place for a new matrix, reusing both the symbolic analysis
+    /// and the storage of the current factor.
+    ///
+    /// `symbolic` must be the analysis the current factor was computed with, and the matrix must
+    /// satisfy the same requirements as in [`CsCholesky::factor_symbolic`]. This is the cheapest
+    /// way to factor a sequence of matrices that share a sparsity pattern, e.g. the Jacobians
+    /// of a Newton iteration.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same way as [`CsCholesky::factor_symbolic`], and additionally returns
+    /// [`CholeskyError::PatternMismatch`] if the current factor does not have the pattern of
+    /// `symbolic`. If an error is returned, the current factor is left unchanged.
+    pub fn refactor<MO, MI, D, C>(
+        &mut self,
+        symbolic: &CholeskySymbolic,
+        matrix: &CsMatrix<T, MO, MI, D, C>,
+    ) -> Result<(), CholeskyError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        check_symbolic(symbolic, matrix)?;
+
+        let (offsets, indices, data) = self.l_matrix.cs_data();
+
+        if offsets != symbolic.l_pattern.offsets || indices != symbolic.l_pattern.indices {
+            return Err(CholeskyError::PatternMismatch);
         }
+
+        let mut new_data = data.to_vec();
+
+        Self::numeric(
+            &symbolic.l_pattern,
+            &symbolic.lt_pattern,
+            matrix,
+            &Control::default(),
+            &mut new_data,
+        )?;
+
+        let (nrows, ncols) = self.l_matrix.shape();
+        let l_matrix = std::mem::replace(&mut self.l_matrix, CscMatrix::zeros(0, 0));
+        let (offsets, indices, _) = l_matrix.disassemble();
+
+        self.l_matrix =
+            unsafe { CscMatrix::from_parts_unchecked(nrows, ncols, offsets, indices, new_data) };
+
+        Ok(())
     }
 
     /// Takes in an existing Cholesky pattern and a matrix, and recomputes the factors.
@@ -158,7 +431,7 @@ impl<T: Scalar + RealField> CsCholesky<T> {
 
         if nrows == ncols {
             let lt_pattern = l_pattern.transpose();
-            Self::decompose_left_looking(l_pattern, lt_pattern, matrix)
+            Self::decompose_left_looking(l_pattern, &lt_pattern, matrix, &Control::default())
         } else {
             Err(CholeskyError::NotSquare)
         }
@@ -168,8 +441,9 @@ impl<T: Scalar + RealField> CsCholesky<T> {
     /// one used to initialize `self`, but with different non-zero values provided by `values`.
     fn decompose_left_looking<MO, MI, D, C>(
         l_pattern: CholeskyPattern,
-        u_pattern: CholeskyPattern,
+        u_pattern: &CholeskyPattern,
         matrix: &CsMatrix<T, MO, MI, D, C>,
+        control: &Control<'_>,
     ) -> Result<Self, CholeskyError>
     where
         MO: Borrow<[usize]>,
@@ -177,12 +451,54 @@ impl<T: Scalar + RealField> CsCholesky<T> {
         D: Borrow<[T]>,
         C: Compression,
     {
+        let mut data = vec![T::zero(); l_pattern.indices.len()];
+
+        Self::numeric(&l_pattern, u_pattern, matrix, control, &mut data)?;
+
+        let CholeskyPattern {
+            shape: (nrows, ncols),
+            offsets,
+            indices,
+        } = l_pattern;
+
+        Ok(Self {
+            l_matrix: unsafe {
+                CscMatrix::from_parts_unchecked(nrows, ncols, offsets, indices, data)
+            },
+        })
+    }
+
+    /// The numeric phase of the factorization, which overwrites every entry of `data` with the
+    /// values of `L` in the layout of `l_pattern`.
+    fn numeric<MO, MI, D, C>(
+        l_pattern: &CholeskyPattern,
+        u_pattern: &CholeskyPattern,
+        matrix: &CsMatrix<T, MO, MI, D, C>,
+        control: &Control<'_>,
+        data: &mut [T],
+    ) -> Result<(), CholeskyError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        let _span = span!(
+            "cholesky_numeric",
+            n = matrix.nmajor(),
+            l_nnz = l_pattern.indices.len()
+        );
+
         let mut work_c = l_pattern.offsets.clone();
         let mut work_x = vec![T::zero(); matrix.nmajor()];
 
-        let mut data = vec![T::zero(); l_pattern.indices.len()];
+        let n = matrix.nmajor();
 
         for (i, lane) in matrix.iter().enumerate() {
+            if control.checkpoint(Stage::NumericFactorization, i, n) {
+                return Err(CholeskyError::Cancelled);
+            }
+
             work_x[i] = T::zero();
 
             for (j, val) in lane {
@@ -260,17 +576,9 @@ impl<T: Scalar + RealField> CsCholesky<T> {
             }
         }
 
-        let CholeskyPattern {
-            shape: (nrows, ncols),
-            offsets,
-            indices,
-        } = l_pattern;
+        control.report(Stage::NumericFactorization, n, n);
 
-        Ok(Self {
-            l_matrix: unsafe {
-                CscMatrix::from_parts_unchecked(nrows, ncols, offsets, indices, data)
-            },
-        })
+        Ok(())
     }
 
     /// Returns a reference to the Cholesky factor `L`.
@@ -334,6 +642,8 @@ impl<T: Scalar + RealField> CsCholesky<T> {
         C: Dim,
         S: Storage<T, R, C> + StorageMut<T, R, C>,
     {
+        let _span = span!("cholesky_solve", nrhs = b.ncols());
+
         // If the factorization succeeded, then the solve should only fail if the input matrix is
         // of the wrong size. Therefore, we merely unwrap here.
 
@@ -345,8 +655,35 @@ impl<T: Scalar + RealField> CsCholesky<T> {
     }
 }
 
+/// Checks that `matrix` can be factored with the analysis `symbolic`.
+fn check_symbolic<T, MO, MI, D, C>(
+    symbolic: &CholeskySymbolic,
+    matrix: &CsMatrix<T, MO, MI, D, C>,
+) -> Result<(), CholeskyError>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    if symbolic.shape() != matrix.shape() {
+        Err(CholeskyError::ShapeMismatch)
+    } else if !symbolic.admits(matrix) {
+        Err(CholeskyError::PatternMismatch)
+    } else {
+        Ok(())
+    }
+}
+
 /// Computes the pattern of non-zeros for the Cholesky decomposition of the input matrix.
-fn nonzero_pattern<T, MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> CholeskyPattern
+///
+/// Progress is reported through `control`, but this never stops early.
+pub(crate) fn nonzero_pattern<T, MO, MI, D, C>(
+    matrix: &CsMatrix<T, MO, MI, D, C>,
+    etree: &[Option<usize>],
+    control: &Control<'_>,
+) -> CholeskyPattern
 where
     T: Scalar,
     MO: Borrow<[usize]>,
@@ -354,7 +691,6 @@ where
     D: Borrow<[T]>,
     C: Compression,
 {
-    let etree = elimination_tree(matrix);
     let nmajor = matrix.nmajor();
 
     let mut counts = vec![0usize; nmajor];
@@ -362,6 +698,7 @@ where
     let mut marks = vec![false; etree.len()];
 
     for (i, lane) in matrix.iter().enumerate() {
+        control.report(Stage::SymbolicFactorization, i, nmajor);
         marks.fill(false);
 
         let mut indices = lane
@@ -392,6 +729,8 @@ where
         counts[i] += count;
     }
 
+    control.report(Stage::SymbolicFactorization, nmajor, nmajor);
+
     let new_offsets = CountToOffsetIter::new(counts).collect();
 
     CholeskyPattern {
@@ -402,7 +741,9 @@ where
 }
 
 /// Computes the elimination tree of the input matrix.
-fn elimination_tree<T, MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> Vec<Option<usize>>
+pub(crate) fn elimination_tree<T, MO, MI, D, C>(
+    matrix: &CsMatrix<T, MO, MI, D, C>,
+) -> Vec<Option<usize>>
 where
     T: Scalar,
     MO: Borrow<[usize]>,
@@ -539,11 +880,143 @@ mod tests {
         assert_matrix_eq!(l, cs_l, comp = abs, tol = TOLERANCE);
     }
 
+    #[test]
+    fn cholesky_stops_when_control_requests_it() {
+        let a = Matrix5::from_diagonal(&Vector5::new(40.0, 60.0, 11.0, 50.0, 10.0));
+        let csc = CscMatrix::from(&a);
+
+        // Stop part-way through the numeric factorization.
+        let checks = std::cell::Cell::new(0);
+        let should_stop = || {
+            checks.set(checks.get() + 1);
+            checks.get() > 3
+        };
+
+        let control = Control::new().with_should_stop(&should_stop);
+
+        assert_eq!(
+            CsCholesky::factor_with_control(&csc, &control).unwrap_err(),
+            CholeskyError::Cancelled
+        );
+
+        let never_stop = || false;
+        let control = Control::new().with_should_stop(&never_stop);
+
+        assert!(CsCholesky::factor_with_control(&csc, &control).is_ok());
+    }
+
+    #[test]
+    fn symbolic_analysis_of_arrow_matrix() {
+        // An "arrow" matrix: a diagonal plus a dense last row and column. Every column of L has
+        // the last row as its only off-diagonal entry, so the elimination tree is a star.
+        #[rustfmt::skip]
+        let a = Matrix5::new(
+            4.0, 0.0, 0.0, 0.0, 1.0,
+            0.0, 4.0, 0.0, 0.0, 1.0,
+            0.0, 0.0, 4.0, 0.0, 1.0,
+            0.0, 0.0, 0.0, 4.0, 1.0,
+            1.0, 1.0, 1.0, 1.0, 4.0
+        );
+        let symbolic = CholeskySymbolic::analyze(&CscMatrix::from(&a)).unwrap();
+
+        assert_eq!(symbolic.shape(), (5, 5));
+        assert_eq!(
+            symbolic.elimination_tree(),
+            &[Some(4), Some(4), Some(4), Some(4), None]
+        );
+        assert_eq!(
+            symbolic.column_counts().collect::<Vec<_>>(),
+            vec![2, 2, 2, 2, 1]
+        );
+        assert_eq!(symbolic.nnz(), 9);
+    }
+
+    #[test]
+    fn symbolic_factorization_rejects_mismatched_matrices() {
+        let diagonal = CscMatrix::from(&Matrix5::from_diagonal_element(2.0));
+        let symbolic = CholeskySymbolic::analyze(&diagonal).unwrap();
+
+        let mut dense = Matrix5::from_diagonal_element(2.0);
+        dense[(3, 1)] = 0.5;
+        dense[(1, 3)] = 0.5;
+
+        assert_eq!(
+            CsCholesky::factor_symbolic(&symbolic, &CscMatrix::from(&dense)).unwrap_err(),
+            CholeskyError::PatternMismatch
+        );
+        assert_eq!(
+            CsCholesky::factor_symbolic(&symbolic, &CscMatrix::<f64>::identity(4)).unwrap_err(),
+            CholeskyError::ShapeMismatch
+        );
+        assert_eq!(
+            CholeskySymbolic::analyze(&CscMatrix::<f64>::zeros(2, 3)).unwrap_err(),
+            CholeskyError::NotSquare
+        );
+
+        // A factor computed with a different analysis cannot be refactored in place.
+        let mut cholesky = CsCholesky::factor(&CscMatrix::from(&dense)).unwrap();
+        assert_eq!(
+            cholesky.refactor(&symbolic, &diagonal).unwrap_err(),
+            CholeskyError::PatternMismatch
+        );
+    }
+
+    #[test]
+    fn refactor_leaves_factor_unchanged_on_failure() {
+        let a = CscMatrix::from(&Matrix5::from_diagonal_element(4.0));
+        let symbolic = CholeskySymbolic::analyze(&a).unwrap();
+        let mut cholesky = CsCholesky::factor_symbolic(&symbolic, &a).unwrap();
+
+        let not_positive_definite = CscMatrix::from(&Matrix5::from_diagonal_element(-1.0));
+        assert_eq!(
+            cholesky
+                .refactor(&symbolic, &not_positive_definite)
+                .unwrap_err(),
+            CholeskyError::NotPositiveDefinite
+        );
+        assert!(cholesky.l().triplet_iter().all(|(_, _, &v)| v == 2.0));
+    }
+
     proptest! {
+        #[test]
+        fn symbolic_analysis_agrees_with_factor(matrix in csc_positive_definite()) {
+            let symbolic = CholeskySymbolic::analyze(&matrix).unwrap();
+            let l = CsCholesky::factor(&matrix).unwrap().take_l();
+            let (offsets, indices, _) = l.cs_data();
+
+            prop_assert_eq!(&symbolic.l_pattern().offsets[..], offsets);
+            prop_assert_eq!(&symbolic.l_pattern().indices[..], indices);
+            prop_assert_eq!(symbolic.column_counts().sum::<usize>(), l.nnz());
+
+            // The parent of every column is its first off-diagonal entry.
+            for (j, lane) in l.iter().enumerate() {
+                let parent = lane.map(|(i, _)| i).find(|&i| i > j);
+                prop_assert_eq!(symbolic.elimination_tree()[j], parent);
+            }
+        }
+
+        #[test]
+        fn refactor_agrees_with_fresh_factorization(
+            (matrix, scale) in (csc_positive_definite(), 0.5..4.0f64)
+        ) {
+            let symbolic = CholeskySymbolic::analyze(&matrix).unwrap();
+            let mut cholesky = CsCholesky::factor_symbolic(&symbolic, &matrix).unwrap();
+
+            let scaled = CscMatrix::from(&(DMatrix::from(&matrix) * scale));
+            cholesky.refactor(&symbolic, &scaled).unwrap();
+
+            let expected = CsCholesky::factor(&scaled).unwrap();
+            prop_assert_matrix_eq!(cholesky.l(), expected.l(), comp = abs, tol = TOLERANCE);
+
+            let rhs = DMatrix::from_fn(matrix.nrows(), 2, |i, j| (i + j) as f64);
+            let x = cholesky.solve(&rhs);
+            prop_assert_matrix_eq!(scaled.to_view() * x, rhs, comp = abs, tol = 1e-9);
+        }
+
         #[test]
         fn nonzero_cholesky_pattern_of_identity_matrix_is_same_as_identity(n in 0..100usize) {
             let eye = CsrMatrix::<f32>::identity(n);
-            let pattern = nonzero_pattern(&eye);
+            let pattern = nonzero_pattern(&eye, &elimination_tree(&eye), &Control::default());
 
             let (offsets, indices, _) = eye.cs_data();
 
@@ -565,7 +1038,7 @@ mod tests {
             let (lt_offsets, lt_indices, _) = lt_as_csc.disassemble();
 
             // nonzero_pattern computes L^T
-            let lt_pattern = nonzero_pattern(&matrix);
+            let lt_pattern = nonzero_pattern(&matrix, &elimination_tree(&matrix), &Control::default());
             let l_pattern = lt_pattern.transpose();
 
             prop_assert_eq!(l_pattern.offsets, l_offsets);
@@ -0,0 +1,572 @@
+use crate::{
+    cs::{CompressedRowStorage, CsMatrix, CsrMatrix},
+    ops::serial::spsolve::{
+        spsolve_lower_triangular_csr_dense_mut, spsolve_upper_triangular_csr_dense_mut, Diagonal,
+    },
+};
+use nalgebra::{
+    allocator::Allocator, DefaultAllocator, Dim, Matrix, RealField, Scalar, Storage, StorageMut,
+};
+use std::{borrow::Borrow, collections::BTreeMap};
+use thiserror::Error;
+
+/// Possible errors produced by the incomplete LU factorization.
+#[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
+#[non_exhaustive]
+pub enum IluError {
+    /// The matrix doesn't have nrows == ncols
+    #[error("The matrix is not square.")]
+    NotSquare,
+
+    /// The matrix and the symbolic analysis have different shapes.
+    #[error("The matrix and the symbolic analysis have different shapes.")]
+    ShapeMismatch,
+
+    /// The matrix has non-zeros outside of the pattern of the symbolic analysis.
+    #[error("The matrix has non-zeros outside of the analyzed sparsity pattern.")]
+    PatternMismatch,
+
+    /// A pivot of the factorization is zero, so the incomplete factors are singular.
+    #[error("The pivot in row {row} of the incomplete factorization is zero.")]
+    ZeroPivot {
+        /// The row of the zero pivot.
+        row: usize,
+    },
+}
+
+/// The symbolic analysis of a level-of-fill incomplete LU factorization, ILU(k).
+///
+/// Every entry of the factors is assigned a level of fill: the entries of the matrix (and its
+/// diagonal) have level zero, and eliminating with an entry of level `a` and an entry of level `b`
+/// creates fill of level `a + b + 1`. ILU(k) keeps exactly the entries whose level is at most `k`,
+/// so `k = 0` gives ILU(0), whose factors have the pattern of the matrix, and increasing `k`
+/// trades memory and setup time for a more accurate preconditioner. For `k >= n - 1`, the
+/// factorization is the complete LU factorization without pivoting.
+///
+/// The analysis only depends on the sparsity pattern of the factored matrix, and can be reused
+/// for every matrix that shares it (see [`CsIlu::factor_symbolic`] and [`CsIlu::refactor`]).
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra::DMatrix;
+/// use nalgebra_sparse::{cs::CsrMatrix, factorization::IluSymbolic};
+///
+/// // An arrow matrix: eliminating the first row fills in everything.
+/// let dense = DMatrix::from_row_slice(3, 3, &[4.0, 1.0, 1.0, 1.0, 4.0, 0.0, 1.0, 0.0, 4.0]);
+/// let a = CsrMatrix::from(&dense);
+///
+/// assert_eq!(IluSymbolic::analyze(&a, 0).unwrap().nnz(), 7);
+/// assert_eq!(IluSymbolic::analyze(&a, 1).unwrap().nnz(), 9);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IluSymbolic {
+    level: usize,
+    offsets: Vec<usize>,
+    indices: Vec<usize>,
+    diagonal: Vec<usize>,
+}
+
+impl IluSymbolic {
+    /// Computes the pattern of the ILU(`level`) factors of the provided CSR matrix.
+    ///
+    /// Only the sparsity pattern of the matrix is used; the values are ignored. The diagonal is
+    /// always part of the pattern, even where the matrix does not store it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IluError::NotSquare`] if the matrix is not square.
+    pub fn analyze<T, MO, MI, D>(
+        matrix: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+        level: usize,
+    ) -> Result<Self, IluError>
+    where
+        T: Scalar,
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let (nrows, ncols) = matrix.shape();
+
+        if nrows != ncols {
+            return Err(IluError::NotSquare);
+        }
+
+        let _span = span!("ilu_analyze", n = nrows, level = level);
+
+        let mut offsets = Vec::with_capacity(nrows);
+        let mut indices = Vec::with_capacity(matrix.nnz());
+        let mut levels: Vec<usize> = Vec::with_capacity(matrix.nnz());
+        let mut diagonal = Vec::with_capacity(nrows);
+        let mut row_levels = BTreeMap::<usize, usize>::new();
+
+        for (i, lane) in matrix.iter().enumerate() {
+            row_levels.clear();
+            row_levels.extend(lane.map(|(j, _)| (j, 0)));
+            row_levels.insert(i, 0);
+
+            // Eliminate with every row k < i in increasing order. Fill only ever appears to the
+            // right of k, so the next row to eliminate with is always found after k.
+            let mut next = row_levels.range(..i).next().map(|(&k, _)| k);
+
+            while let Some(k) = next {
+                let level_ik = row_levels[&k];
+                let row_end = offsets.get(k + 1).copied().unwrap_or(indices.len());
+
+                for position in diagonal[k] + 1..row_end {
+                    let fill_level = level_ik + levels[position] + 1;
+
+                    if fill_level <= level {
+                        row_levels
+                            .entry(indices[position])
+                            .and_modify(|existing| *existing = fill_level.min(*existing))
+                            .or_insert(fill_level);
+                    }
+                }
+
+                next = row_levels.range(k + 1..i).next().map(|(&k, _)| k);
+            }
+
+            offsets.push(indices.len());
+
+            for (&j, &fill_level) in &row_levels {
+                if j == i {
+                    diagonal.push(indices.len());
+                }
+
+                indices.push(j);
+                levels.push(fill_level);
+            }
+        }
+
+        Ok(Self {
+            level,
+            offsets,
+            indices,
+            diagonal,
+        })
+    }
+
+    /// The level of fill `k` of the analysis.
+    #[must_use]
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// The shape of the analyzed matrix.
+    #[must_use]
+    pub fn shape(&self) -> (usize, usize) {
+        (self.offsets.len(), self.offsets.len())
+    }
+
+    /// The number of non-zeros of the factors `L` and `U` combined, counting the diagonal once.
+    #[must_use]
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// The offsets and column indices of the combined pattern of `L` and `U`.
+    #[must_use]
+    pub fn pattern(&self) -> (&[usize], &[usize]) {
+        (&self.offsets, &self.indices)
+    }
+}
+
+/// An incomplete LU factorization `A ≈ L U` of a square [`CsrMatrix`], with level-of-fill
+/// control (ILU(k), see [`IluSymbolic`]).
+///
+/// `L` is unit lower triangular and `U` is upper triangular, and both are stored in a single CSR
+/// matrix (the diagonal of `L` is implicit). No pivoting is performed, so the factorization may
+/// fail with a zero pivot even if `A` is non-singular. It is mostly useful as a preconditioner for
+/// iterative solvers (it implements [`Preconditioner`](crate::iterative::Preconditioner)), where
+/// the level of fill is the usual dial between the memory used by the factors and the number of
+/// iterations of the solver.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{cs::CsrMatrix, factorization::CsIlu};
+///
+/// let dense = DMatrix::from_row_slice(3, 3, &[4.0, -1.0, -1.0, -2.0, 4.0, 0.0, -1.0, 0.0, 4.0]);
+/// let a = CsrMatrix::from(&dense);
+/// let b = DVector::from_vec(vec![1.0, 2.0, 3.0]);
+///
+/// // ILU(0) drops the fill at (1, 2) and (2, 1), so it only solves approximately...
+/// let ilu = CsIlu::factor(&a, 0).unwrap();
+/// assert!((&dense * ilu.solve(&b) - &b).norm() > 1e-3);
+///
+/// // ...while ILU(1) keeps it, and is exact for this matrix.
+/// let ilu = CsIlu::factor(&a, 1).unwrap();
+/// assert!((&dense * ilu.solve(&b) - &b).norm() < 1e-12);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CsIlu<T>
+where
+    T: Scalar + RealField,
+{
+    factors: CsrMatrix<T>,
+    diagonal: Vec<usize>,
+}
+
+impl<T: Scalar + RealField> CsIlu<T> {
+    /// Computes the ILU(`level`) factorization of the provided matrix.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IluError::NotSquare`] if the matrix is not square, and [`IluError::ZeroPivot`]
+    /// if a pivot of the factorization is zero.
+    pub fn factor<MO, MI, D>(
+        matrix: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+        level: usize,
+    ) -> Result<Self, IluError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let symbolic = IluSymbolic::analyze(matrix, level)?;
+        Self::factor_symbolic(&symbolic, matrix)
+    }
+
+    /// Computes the numeric factorization of the provided matrix, reusing a symbolic analysis of
+    /// a matrix with the same pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IluError::ShapeMismatch`] if the matrix does not have the shape of the analyzed
+    /// one, [`IluError::PatternMismatch`] if it has non-zeros outside of the analyzed pattern, and
+    /// [`IluError::ZeroPivot`] if a pivot of the factorization is zero.
+    pub fn factor_symbolic<MO, MI, D>(
+        symbolic: &IluSymbolic,
+        matrix: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    ) -> Result<Self, IluError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let mut data = vec![T::zero(); symbolic.nnz()];
+        Self::numeric(symbolic, matrix, &mut data)?;
+
+        let (n, _) = symbolic.shape();
+        let factors = unsafe {
+            CsrMatrix::from_parts_unchecked(
+                n,
+                n,
+                symbolic.offsets.clone(),
+                symbolic.indices.clone(),
+                data,
+            )
+        };
+
+        Ok(Self {
+            factors,
+            diagonal: symbolic.diagonal.clone(),
+        })
+    }
+
+    /// Recomputes the factorization in place for a new matrix, reusing both the symbolic analysis
+    /// and the storage of the current factors.
+    ///
+    /// `symbolic` must be the analysis the current factors were computed with.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same way as [`CsIlu::factor_symbolic`], and additionally returns
+    /// [`IluError::PatternMismatch`] if the current factors do not have the pattern of
+    /// `symbolic`. If an error is returned, the current factors are left unchanged.
+    pub fn refactor<MO, MI, D>(
+        &mut self,
+        symbolic: &IluSymbolic,
+        matrix: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    ) -> Result<(), IluError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let (offsets, indices, data) = self.factors.cs_data();
+
+        if offsets != symbolic.offsets.as_slice() || indices != symbolic.indices.as_slice() {
+            return Err(IluError::PatternMismatch);
+        }
+
+        let mut new_data = data.to_vec();
+        Self::numeric(symbolic, matrix, &mut new_data)?;
+        self.factors.cs_data_mut().2.clone_from_slice(&new_data);
+
+        Ok(())
+    }
+
+    /// Computes the values of the combined factors on the pattern of `symbolic` into `data`, with
+    /// the row-by-row (IKJ) variant of Gaussian elimination.
+    fn numeric<MO, MI, D>(
+        symbolic: &IluSymbolic,
+        matrix: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+        data: &mut [T],
+    ) -> Result<(), IluError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        if symbolic.shape() != matrix.shape() {
+            return Err(IluError::ShapeMismatch);
+        }
+
+        let _span = span!("ilu_numeric", n = matrix.nrows(), nnz = symbolic.nnz());
+
+        let IluSymbolic {
+            offsets,
+            indices,
+            diagonal,
+            ..
+        } = symbolic;
+        let row_end = |i: usize| offsets.get(i + 1).copied().unwrap_or(indices.len());
+
+        // The position of every column of the current row in `data`, if it is in the pattern.
+        let mut positions = vec![None; matrix.ncols()];
+
+        for (i, lane) in matrix.iter().enumerate() {
+            let row = offsets[i]..row_end(i);
+
+            for position in row.clone() {
+                positions[indices[position]] = Some(position);
+                data[position] = T::zero();
+            }
+
+            for (j, value) in lane {
+                match positions[j] {
+                    Some(position) => data[position] = value.clone(),
+                    None => return Err(IluError::PatternMismatch),
+                }
+            }
+
+            for position in offsets[i]..diagonal[i] {
+                let k = indices[position];
+                let l_ik = data[position].clone() / data[diagonal[k]].clone();
+                data[position] = l_ik.clone();
+
+                for u_position in diagonal[k] + 1..row_end(k) {
+                    if let Some(target) = positions[indices[u_position]] {
+                        data[target] -= l_ik.clone() * data[u_position].clone();
+                    }
+                }
+            }
+
+            if data[diagonal[i]].is_zero() {
+                return Err(IluError::ZeroPivot { row: i });
+            }
+
+            for position in row {
+                positions[indices[position]] = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a reference to the combined factors, i.e. the strictly lower triangular part of `L`
+    /// and all of `U` in a single matrix.
+    #[must_use]
+    pub fn factors(&self) -> &CsrMatrix<T> {
+        &self.factors
+    }
+
+    /// Returns the unit lower triangular factor `L`, with its diagonal stored explicitly.
+    #[must_use]
+    pub fn l(&self) -> CsrMatrix<T> {
+        let (n, _) = self.factors.shape();
+        let (offsets, indices, data) = self.factors.cs_data();
+
+        let mut l_offsets = Vec::with_capacity(n);
+        let mut l_indices = Vec::new();
+        let mut l_data = Vec::new();
+
+        for (i, &diagonal) in self.diagonal.iter().enumerate() {
+            l_offsets.push(l_indices.len());
+            l_indices.extend_from_slice(&indices[offsets[i]..diagonal]);
+            l_indices.push(i);
+            l_data.extend_from_slice(&data[offsets[i]..diagonal]);
+            l_data.push(T::one());
+        }
+
+        unsafe { CsrMatrix::from_parts_unchecked(n, n, l_offsets, l_indices, l_data) }
+    }
+
+    /// Returns the upper triangular factor `U`.
+    #[must_use]
+    pub fn u(&self) -> CsrMatrix<T> {
+        let (n, _) = self.factors.shape();
+        let (offsets, indices, data) = self.factors.cs_data();
+
+        let mut u_offsets = Vec::with_capacity(n);
+        let mut u_indices = Vec::new();
+        let mut u_data = Vec::new();
+
+        for (i, &diagonal) in self.diagonal.iter().enumerate() {
+            let row_end = offsets.get(i + 1).copied().unwrap_or(indices.len());
+
+            u_offsets.push(u_indices.len());
+            u_indices.extend_from_slice(&indices[diagonal..row_end]);
+            u_data.extend_from_slice(&data[diagonal..row_end]);
+        }
+
+        unsafe { CsrMatrix::from_parts_unchecked(n, n, u_offsets, u_indices, u_data) }
+    }
+
+    /// Solves the system `L U X = B`, where `X` and `B` are dense matrices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `B` is the wrong size i.e. for an N×N matrix `A`, `B` must be some N×M matrix.
+    #[must_use]
+    pub fn solve<R, C, S>(
+        &self,
+        b: &Matrix<T, R, C, S>,
+    ) -> Matrix<T, R, C, <DefaultAllocator as Allocator<T, R, C>>::Buffer>
+    where
+        R: Dim,
+        C: Dim,
+        S: Storage<T, R, C>,
+        DefaultAllocator: Allocator<T, R, C>,
+    {
+        let b_clone = b.clone_owned();
+        self.solve_mut(b_clone)
+    }
+
+    /// Solves the system `L U X = B`, where `X` and `B` are dense matrices.
+    ///
+    /// The result is stored in-place in `b`. We take ownership of `b`, mutate it directly, and
+    /// then return the same matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `B` is the wrong size i.e. for an N×N matrix `A`, `B` must be some N×M matrix.
+    #[must_use]
+    pub fn solve_mut<R, C, S>(&self, mut b: Matrix<T, R, C, S>) -> Matrix<T, R, C, S>
+    where
+        R: Dim,
+        C: Dim,
+        S: Storage<T, R, C> + StorageMut<T, R, C>,
+    {
+        let _span = span!("ilu_solve", nrhs = b.ncols());
+
+        // The pivots were checked during the factorization, so the solves can only fail if `b`
+        // has the wrong size.
+        spsolve_lower_triangular_csr_dense_mut(self.factors.to_view(), &mut b, Diagonal::Unit)
+            .unwrap();
+        spsolve_upper_triangular_csr_dense_mut(self.factors.to_view(), &mut b, Diagonal::NonUnit)
+            .unwrap();
+
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::{DMatrix, DVector};
+
+    /// The upwind discretization of a convection-diffusion operator on an `m × m` grid, which is
+    /// non-symmetric and fills in when factored.
+    fn convection_diffusion(m: usize) -> DMatrix<f64> {
+        let n = m * m;
+        let mut dense = DMatrix::zeros(n, n);
+
+        for i in 0..m {
+            for j in 0..m {
+                let row = i * m + j;
+                dense[(row, row)] = 4.5;
+
+                if j > 0 {
+                    dense[(row, row - 1)] = -1.5;
+                }
+                if j + 1 < m {
+                    dense[(row, row + 1)] = -1.0;
+                }
+                if i > 0 {
+                    dense[(row, row - m)] = -1.25;
+                }
+                if i + 1 < m {
+                    dense[(row, row + m)] = -0.75;
+                }
+            }
+        }
+
+        dense
+    }
+
+    #[test]
+    fn ilu0_reproduces_the_matrix_on_its_pattern() {
+        let dense = convection_diffusion(4);
+        let a = CsrMatrix::from(&dense);
+
+        let ilu = CsIlu::factor(&a, 0).unwrap();
+        assert_eq!(ilu.factors().pattern(), a.pattern());
+
+        let product = DMatrix::from(&ilu.l()) * DMatrix::from(&ilu.u());
+
+        for (i, j, value) in a.triplet_iter() {
+            assert!((product[(i, j)] - value).abs() < 1e-12);
+        }
+
+        assert!((&product - &dense).norm() > 1e-6);
+    }
+
+    #[test]
+    fn ilu_with_enough_fill_is_the_complete_lu_factorization() {
+        let dense = convection_diffusion(4);
+        let a = CsrMatrix::from(&dense);
+
+        let nnz: Vec<_> = (0..5)
+            .map(|level| IluSymbolic::analyze(&a, level).unwrap().nnz())
+            .collect();
+        assert!(nnz.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(nnz[0] < nnz[4]);
+
+        let ilu = CsIlu::factor(&a, 15).unwrap();
+        let product = DMatrix::from(&ilu.l()) * DMatrix::from(&ilu.u());
+        assert!((&product - &dense).norm() < 1e-12);
+
+        let b = DVector::from_fn(16, |i, _| i as f64);
+        assert!((&dense * ilu.solve(&b) - &b).norm() < 1e-12);
+    }
+
+    #[test]
+    fn refactor_agrees_with_factor() {
+        let dense = convection_diffusion(3);
+        let a = CsrMatrix::from(&dense);
+        let symbolic = IluSymbolic::analyze(&a, 1).unwrap();
+        let mut ilu = CsIlu::factor_symbolic(&symbolic, &a).unwrap();
+
+        let scaled = CsrMatrix::from(&(&dense * 3.0));
+        ilu.refactor(&symbolic, &scaled).unwrap();
+        let expected = CsIlu::factor(&scaled, 1).unwrap();
+        assert_eq!(ilu.factors().cs_data().2, expected.factors().cs_data().2);
+
+        let other = CsrMatrix::from(&DMatrix::<f64>::from_element(9, 9, 1.0));
+        assert_eq!(
+            ilu.refactor(&symbolic, &other),
+            Err(IluError::PatternMismatch)
+        );
+        assert_eq!(ilu.factors().cs_data().2, expected.factors().cs_data().2);
+    }
+
+    #[test]
+    fn ilu_reports_zero_pivots() {
+        let dense = DMatrix::from_row_slice(2, 2, &[1.0, 2.0, 2.0, 4.0]);
+        let a = CsrMatrix::from(&dense);
+        assert_eq!(
+            CsIlu::factor(&a, 0).unwrap_err(),
+            IluError::ZeroPivot { row: 1 }
+        );
+
+        let rectangular = CsrMatrix::<f64>::zeros(2, 3);
+        assert_eq!(
+            CsIlu::factor(&rectangular, 0).unwrap_err(),
+            IluError::NotSquare
+        );
+    }
+}
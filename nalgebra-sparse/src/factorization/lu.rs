@@ -0,0 +1,471 @@
+use crate::{
+    cs::{Compression, CsMatrix, CsrMatrix},
+    ops::serial::spsolve::{
+        spsolve_lower_triangular_csr_dense_mut, spsolve_upper_triangular_csr_dense_mut, Diagonal,
+    },
+};
+use nalgebra::{allocator::Allocator, DefaultAllocator, Dim, Matrix, OMatrix, RealField, Storage};
+use std::{borrow::Borrow, cmp::Ordering};
+use thiserror::Error;
+
+/// How the sparse LU factorization chooses the pivot row of every column.
+///
+/// Every strategy eliminates the columns in their natural order, and only differs in which of the
+/// rows with an entry in the current column becomes the pivot row. Stability is measured by the
+/// growth of the entries during the elimination, which every factorization reports (see
+/// [`CsLu::pivot_growth`]).
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum LuPivoting<T> {
+    /// Partial pivoting: the row with the entry of largest magnitude becomes the pivot row. This is
+    /// the most stable strategy, but it ignores the sparsity of the rows.
+    #[default]
+    Partial,
+
+    /// Threshold pivoting with tolerance `0 < τ <= 1`: among the rows whose entry is at least `τ`
+    /// times the largest entry in magnitude, the row with the fewest non-zeros becomes the pivot
+    /// row, which usually creates less fill. Smaller tolerances preserve sparsity better, at the
+    /// cost of allowing more growth. `τ = 1` is equivalent to partial pivoting.
+    Threshold(T),
+
+    /// Static pivoting: the rows are never exchanged, so the diagonal entries are the pivots. This
+    /// preserves the most sparsity and is stable for diagonally dominant matrices, but fails on
+    /// zero diagonal entries and can be arbitrarily unstable otherwise.
+    Static,
+}
+
+/// Possible errors produced by the sparse LU factorization.
+#[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
+#[non_exhaustive]
+pub enum LuError {
+    /// The matrix doesn't have nrows == ncols
+    #[error("The matrix is not square.")]
+    NotSquare,
+
+    /// No admissible pivot is non-zero, so the matrix is singular (or, with static pivoting, has
+    /// a zero pivot on the diagonal).
+    #[error("The pivot of column {column} is zero.")]
+    ZeroPivot {
+        /// The column without a non-zero pivot.
+        column: usize,
+    },
+}
+
+/// A sparse LU factorization `P A = L U` of a square matrix, with a configurable pivoting
+/// strategy (see [`LuPivoting`]).
+///
+/// `P` is a row permutation, `L` is unit lower triangular and `U` is upper triangular. The
+/// factorization is computed by right-looking Gaussian elimination on the rows of the matrix, so
+/// fill only appears where the elimination creates it, and no fill-reducing ordering of the
+/// columns is performed.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{
+///     cs::CsrMatrix,
+///     factorization::{CsLu, LuError, LuPivoting},
+/// };
+///
+/// let dense = DMatrix::from_row_slice(3, 3, &[0.0, 2.0, 1.0, 1.0, 1.0, 0.0, 3.0, 0.0, 1.0]);
+/// let a = CsrMatrix::from(&dense);
+/// let b = DVector::from_vec(vec![1.0, 2.0, 3.0]);
+///
+/// let lu = CsLu::factor(&a).unwrap();
+/// assert!((&dense * lu.solve(&b) - &b).norm() < 1e-12);
+/// assert!(lu.pivot_growth() >= 1.0);
+///
+/// // The first diagonal entry is zero, so static pivoting fails.
+/// let error = CsLu::factor_with_pivoting(&a, LuPivoting::Static).unwrap_err();
+/// assert_eq!(error, LuError::ZeroPivot { column: 0 });
+/// ```
+#[derive(Debug, Clone)]
+pub struct CsLu<T>
+where
+    T: RealField,
+{
+    l: CsrMatrix<T>,
+    u: CsrMatrix<T>,
+    permutation: Vec<usize>,
+    pivot_growth: T,
+}
+
+impl<T: RealField> CsLu<T> {
+    /// Computes the LU factorization of the provided matrix with partial pivoting.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LuError::NotSquare`] if the matrix is not square, and [`LuError::ZeroPivot`] if
+    /// it is singular.
+    pub fn factor<MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> Result<Self, LuError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        Self::factor_with_pivoting(matrix, LuPivoting::Partial)
+    }
+
+    /// Computes the LU factorization of the provided matrix with the given pivoting strategy.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LuError::NotSquare`] if the matrix is not square, and [`LuError::ZeroPivot`] if
+    /// every admissible pivot of a column is zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tolerance of [`LuPivoting::Threshold`] is not in `(0, 1]`.
+    pub fn factor_with_pivoting<MO, MI, D, C>(
+        matrix: &CsMatrix<T, MO, MI, D, C>,
+        pivoting: LuPivoting<T>,
+    ) -> Result<Self, LuError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        let (n, ncols) = matrix.shape();
+
+        if n != ncols {
+            return Err(LuError::NotSquare);
+        }
+
+        if let LuPivoting::Threshold(tolerance) = &pivoting {
+            assert!(
+                *tolerance > T::zero() && *tolerance <= T::one(),
+                "The threshold pivoting tolerance must be in (0, 1]."
+            );
+        }
+
+        let _span = span!("lu_factor", n = n, nnz = matrix.nnz());
+
+        // The active part of every row that has not been chosen as a pivot row yet, sorted by
+        // column, and the rows that (may) have an entry in every column.
+        let mut rows = vec![Vec::new(); n];
+        let mut column_rows = vec![Vec::new(); n];
+        let mut max_entry = T::zero();
+
+        for (major, minor, value) in matrix.triplet_iter() {
+            let (row, col) = (C::nmajor(major, minor), C::nminor(major, minor));
+            rows[row].push((col, value.clone()));
+            column_rows[col].push(row);
+            max_entry = max_entry.max(value.clone().abs());
+        }
+
+        for row in &mut rows {
+            row.sort_unstable_by_key(|&(col, _)| col);
+        }
+
+        let mut pivoted = vec![false; n];
+        let mut lower = vec![Vec::new(); n];
+        let mut permutation = Vec::with_capacity(n);
+        let mut u_offsets = Vec::with_capacity(n);
+        let mut u_indices = Vec::new();
+        let mut u_data = Vec::new();
+        let mut max_growth = max_entry.clone();
+
+        for k in 0..n {
+            let mut candidates = std::mem::take(&mut column_rows[k]);
+            candidates.sort_unstable();
+            candidates.dedup();
+            // Every column before `k` has been eliminated from the active rows, so a row has an
+            // entry in column `k` if and only if its first active entry is in column `k`.
+            candidates.retain(|&r| !pivoted[r] && rows[r].first().map(|e| e.0) == Some(k));
+
+            let pivot = choose_pivot(&pivoting, k, &candidates, &rows)
+                .ok_or(LuError::ZeroPivot { column: k })?;
+
+            pivoted[pivot] = true;
+            permutation.push(pivot);
+            let pivot_row = std::mem::take(&mut rows[pivot]);
+            let pivot_value = pivot_row[0].1.clone();
+
+            for &r in candidates.iter().filter(|&&r| r != pivot) {
+                let l_rk = rows[r][0].1.clone() / pivot_value.clone();
+                let row = std::mem::take(&mut rows[r]);
+                let updated = subtract_scaled(&row[1..], &l_rk, &pivot_row[1..], |col| {
+                    column_rows[col].push(r)
+                });
+
+                for (_, value) in &updated {
+                    max_growth = max_growth.max(value.clone().abs());
+                }
+
+                rows[r] = updated;
+                lower[r].push((k, l_rk));
+            }
+
+            u_offsets.push(u_indices.len());
+            for (col, value) in pivot_row {
+                u_indices.push(col);
+                u_data.push(value);
+            }
+        }
+
+        let mut l_offsets = Vec::with_capacity(n);
+        let mut l_indices = Vec::new();
+        let mut l_data = Vec::new();
+
+        for &row in &permutation {
+            l_offsets.push(l_indices.len());
+            for (col, value) in std::mem::take(&mut lower[row]) {
+                l_indices.push(col);
+                l_data.push(value);
+            }
+        }
+
+        let pivot_growth = if max_entry.is_zero() {
+            T::one()
+        } else {
+            max_growth / max_entry
+        };
+
+        Ok(Self {
+            l: unsafe { CsrMatrix::from_parts_unchecked(n, n, l_offsets, l_indices, l_data) },
+            u: unsafe { CsrMatrix::from_parts_unchecked(n, n, u_offsets, u_indices, u_data) },
+            permutation,
+            pivot_growth,
+        })
+    }
+
+    /// Returns the strictly lower triangular part of the unit lower triangular factor `L`.
+    #[must_use]
+    pub fn l(&self) -> &CsrMatrix<T> {
+        &self.l
+    }
+
+    /// Returns the upper triangular factor `U`.
+    #[must_use]
+    pub fn u(&self) -> &CsrMatrix<T> {
+        &self.u
+    }
+
+    /// The row permutation `P`: row `i` of `P A` is row `row_permutation()[i]` of `A`.
+    #[must_use]
+    pub fn row_permutation(&self) -> &[usize] {
+        &self.permutation
+    }
+
+    /// The growth factor of the elimination, i.e. the largest magnitude of any entry computed
+    /// during the factorization, divided by the largest magnitude of an entry of `A`.
+    ///
+    /// The growth factor is at least one, and bounds the backward error of the factorization
+    /// relative to the machine precision. Large values indicate that the pivoting strategy traded
+    /// too much stability for sparsity.
+    #[must_use]
+    pub fn pivot_growth(&self) -> T {
+        self.pivot_growth.clone()
+    }
+
+    /// Solves the system `A X = B`, where `X` and `B` are dense matrices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `B` is the wrong size i.e. for an N×N matrix `A`, `B` must be some N×M matrix.
+    #[must_use]
+    pub fn solve<R, C, S>(&self, b: &Matrix<T, R, C, S>) -> OMatrix<T, R, C>
+    where
+        R: Dim,
+        C: Dim,
+        S: Storage<T, R, C>,
+        DefaultAllocator: Allocator<T, R, C>,
+    {
+        assert_eq!(
+            b.nrows(),
+            self.permutation.len(),
+            "The right hand side must have as many rows as the matrix."
+        );
+
+        let _span = span!("lu_solve", nrhs = b.ncols());
+
+        let mut x = b.clone_owned();
+
+        for (i, &row) in self.permutation.iter().enumerate() {
+            x.row_mut(i).copy_from(&b.row(row));
+        }
+
+        // The pivots were checked during the factorization, so the solves cannot fail.
+        spsolve_lower_triangular_csr_dense_mut(self.l.to_view(), &mut x, Diagonal::Unit).unwrap();
+        spsolve_upper_triangular_csr_dense_mut(self.u.to_view(), &mut x, Diagonal::NonUnit)
+            .unwrap();
+
+        x
+    }
+}
+
+/// Chooses the pivot row of column `k` among the `candidates` according to `pivoting`.
+///
+/// Returns `None` if no admissible pivot is non-zero.
+fn choose_pivot<T: RealField>(
+    pivoting: &LuPivoting<T>,
+    k: usize,
+    candidates: &[usize],
+    rows: &[Vec<(usize, T)>],
+) -> Option<usize> {
+    let magnitude = |r: usize| rows[r][0].1.clone().abs();
+    let largest = candidates
+        .iter()
+        .map(|&r| magnitude(r))
+        .fold(T::zero(), |max, value| max.max(value));
+
+    if largest.is_zero() {
+        return None;
+    }
+
+    match pivoting {
+        LuPivoting::Static => candidates
+            .contains(&k)
+            .then_some(k)
+            .filter(|&k| !magnitude(k).is_zero()),
+        LuPivoting::Partial => candidates
+            .iter()
+            .copied()
+            .find(|&r| magnitude(r) == largest),
+        LuPivoting::Threshold(tolerance) => {
+            let threshold = tolerance.clone() * largest;
+
+            candidates
+                .iter()
+                .copied()
+                .filter(|&r| magnitude(r) >= threshold)
+                .min_by(|&a, &b| {
+                    rows[a].len().cmp(&rows[b].len()).then_with(|| {
+                        magnitude(b)
+                            .partial_cmp(&magnitude(a))
+                            .unwrap_or(Ordering::Equal)
+                    })
+                })
+        }
+    }
+}
+
+/// Computes `row - factor * pivot_row` for two rows sorted by column, calling `on_fill` for every
+/// column that only appears in `pivot_row`.
+fn subtract_scaled<T, F>(
+    row: &[(usize, T)],
+    factor: &T,
+    pivot_row: &[(usize, T)],
+    mut on_fill: F,
+) -> Vec<(usize, T)>
+where
+    T: RealField,
+    F: FnMut(usize),
+{
+    let mut result = Vec::with_capacity(row.len() + pivot_row.len());
+    let (mut i, mut j) = (0, 0);
+
+    while i < row.len() || j < pivot_row.len() {
+        let ordering = match (row.get(i), pivot_row.get(j)) {
+            (Some(a), Some(b)) => a.0.cmp(&b.0),
+            (Some(_), None) => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+
+        match ordering {
+            Ordering::Less => {
+                result.push(row[i].clone());
+                i += 1;
+            }
+            Ordering::Greater => {
+                let (col, value) = &pivot_row[j];
+                on_fill(*col);
+                result.push((*col, -(factor.clone() * value.clone())));
+                j += 1;
+            }
+            Ordering::Equal => {
+                let (col, value) = &row[i];
+                let update = factor.clone() * pivot_row[j].1.clone();
+                result.push((*col, value.clone() - update));
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cs::CscMatrix;
+    use nalgebra::{DMatrix, DVector};
+
+    /// Reconstructs `P A` and `L U` of a factorization as dense matrices.
+    fn reconstruct(lu: &CsLu<f64>, dense: &DMatrix<f64>) -> (DMatrix<f64>, DMatrix<f64>) {
+        let n = dense.nrows();
+        let pa = DMatrix::from_fn(n, n, |i, j| dense[(lu.row_permutation()[i], j)]);
+        let l = DMatrix::from(lu.l()) + DMatrix::identity(n, n);
+
+        (pa, l * DMatrix::from(lu.u()))
+    }
+
+    #[test]
+    fn every_pivoting_strategy_factors_a_diagonally_dominant_matrix() {
+        let dense = DMatrix::from_row_slice(
+            4,
+            4,
+            &[
+                5.0, 1.0, 0.0, 2.0, 1.0, 6.0, -2.0, 0.0, 0.0, 3.0, 7.0, 1.0, -2.0, 0.0, 1.0, 4.0,
+            ],
+        );
+        let b = DVector::from_vec(vec![1.0, -2.0, 3.0, 4.0]);
+
+        for pivoting in [
+            LuPivoting::Partial,
+            LuPivoting::Threshold(0.1),
+            LuPivoting::Static,
+        ] {
+            let lu = CsLu::factor_with_pivoting(&CscMatrix::from(&dense), pivoting).unwrap();
+            let (pa, product) = reconstruct(&lu, &dense);
+
+            assert!((pa - product).norm() < 1e-12);
+            assert!((&dense * lu.solve(&b) - &b).norm() < 1e-12);
+            assert!(lu.pivot_growth() >= 1.0);
+        }
+
+        let lu = CsLu::factor_with_pivoting(&CsrMatrix::from(&dense), LuPivoting::Static).unwrap();
+        assert_eq!(lu.row_permutation(), &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn threshold_pivoting_trades_growth_for_sparsity() {
+        // Partial pivoting picks the dense first row, which fills in the second row.
+        let dense = DMatrix::from_row_slice(
+            4,
+            4,
+            &[
+                4.0, 1.0, 1.0, 1.0, 3.0, 5.0, 0.0, 0.0, 0.0, 0.0, 5.0, 0.0, 0.0, 0.0, 0.0, 5.0,
+            ],
+        );
+        let a = CsrMatrix::from(&dense);
+
+        let partial = CsLu::factor(&a).unwrap();
+        let threshold = CsLu::factor_with_pivoting(&a, LuPivoting::Threshold(0.5)).unwrap();
+
+        assert_eq!(partial.row_permutation(), &[0, 1, 2, 3]);
+        assert_eq!(threshold.row_permutation(), &[1, 0, 2, 3]);
+        assert!(threshold.u().nnz() < partial.u().nnz());
+        assert!(threshold.pivot_growth() > partial.pivot_growth());
+
+        for lu in [partial, threshold] {
+            let (pa, product) = reconstruct(&lu, &dense);
+            assert!((pa - product).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn lu_reports_zero_pivots() {
+        let singular = DMatrix::from_row_slice(2, 2, &[1.0, 2.0, 2.0, 4.0]);
+        assert_eq!(
+            CsLu::factor(&CsrMatrix::from(&singular)).unwrap_err(),
+            LuError::ZeroPivot { column: 1 }
+        );
+
+        let rectangular = CsrMatrix::<f64>::zeros(2, 3);
+        assert_eq!(CsLu::factor(&rectangular).unwrap_err(), LuError::NotSquare);
+    }
+}
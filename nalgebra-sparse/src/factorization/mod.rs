@@ -1,6 +1,33 @@
 //! Matrix factorization for sparse matrices.
 //!
-//! Currently, the only factorization provided here is the [`CscCholesky`] factorization.
+//! The factorizations provided here are the [`CscCholesky`] factorization, and the incomplete LU
+//! factorization [`CsIlu`] with level-of-fill control, which is mostly useful as a preconditioner.
+//! The symbolic phase of both can be computed once (with [`CholeskySymbolic`] and [`IluSymbolic`])
+//! and reused across every matrix that shares a sparsity pattern. A [`SymbolicCache`] does this
+//! automatically, by handing out the analysis of every pattern it has seen before.
+//!
+//! General square systems can be solved with the sparse LU factorization [`CsLu`], whose pivoting
+//! strategy ([`LuPivoting`]) trades stability for the preservation of sparsity. Matrices with a
+//! narrow band, e.g. from 1D discretizations, are factored more cheaply by [`BandedLu`] and
+//! [`BandedCholesky`], and [`AutoLu`] and [`AutoCholesky`] choose between the banded and the
+//! general factorizations based on the band of the matrix.
+//!
+//! Matrices whose Cholesky factor does not fit in memory can be factored with
+//! [`OutOfCoreCholesky`], which spills the factor to disk in panels of columns.
+//!
+//! Many independent small systems can be factored and solved at once with the functions in the
+//! [`batch`] module.
+mod banded;
+pub mod batch;
+mod cache;
 mod cholesky;
+mod ilu;
+mod lu;
+mod out_of_core;
 
+pub use banded::*;
+pub use cache::*;
 pub use cholesky::*;
+pub use ilu::*;
+pub use lu::*;
+pub use out_of_core::*;
@@ -0,0 +1,515 @@
+use super::CholeskyError;
+use crate::cs::{Compression, CsMatrix};
+use nalgebra::{DVector, RealField};
+use std::{
+    borrow::Borrow,
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// A scalar type whose values can be spilled to disk by [`OutOfCoreCholesky`].
+pub trait SpillScalar: RealField + Copy {
+    /// The number of bytes of the little-endian representation of a value.
+    const SIZE: usize;
+
+    /// Appends the little-endian representation of `self` to `bytes`.
+    fn write_le(&self, bytes: &mut Vec<u8>);
+
+    /// Reads a value from the first [`SIZE`](Self::SIZE) bytes of `bytes`.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_spill_scalar {
+    ($($t:ty),*) => {
+        $(
+            impl SpillScalar for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                fn write_le(&self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read_le(bytes: &[u8]) -> Self {
+                    let mut array = [0; std::mem::size_of::<$t>()];
+                    array.copy_from_slice(&bytes[..Self::SIZE]);
+                    <$t>::from_le_bytes(array)
+                }
+            }
+        )*
+    };
+}
+
+impl_spill_scalar!(f32, f64);
+
+/// Possible errors produced by the out-of-core Cholesky factorization.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum OutOfCoreError {
+    /// The factorization itself failed.
+    #[error(transparent)]
+    Cholesky(#[from] CholeskyError),
+
+    /// Writing the factor to disk or reading it back failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// A sparse Cholesky factorization `A = L Lᵀ` whose factor is kept on disk instead of in memory.
+///
+/// The columns of `L` are computed in panels of a fixed number of columns with a left-looking
+/// algorithm: the previous panels are streamed back from disk to update the current panel, which
+/// is then appended to the spill file. Solves stream the panels forwards for `L` and backwards for
+/// `Lᵀ`. Only the matrix, one panel and a few vectors of length `n` are ever held in memory, so
+/// this can factor matrices whose factors exceed the available memory, at the cost of reading
+/// the factor from disk once per panel during the factorization, and once per triangular solve.
+///
+/// The spill file must not exist before the factorization, which creates it, owns it and removes
+/// it when it is dropped.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{cs::CscMatrix, factorization::OutOfCoreCholesky};
+///
+/// let dense = DMatrix::from_row_slice(3, 3, &[4.0, 1.0, 0.0, 1.0, 4.0, 1.0, 0.0, 1.0, 4.0]);
+/// let a = CscMatrix::from(&dense);
+/// let path = std::env::temp_dir().join("nalgebra-sparse-out-of-core-doctest.bin");
+///
+/// let cholesky = OutOfCoreCholesky::factor(&a, 2, &path).unwrap();
+/// assert_eq!(cholesky.panels(), 2);
+///
+/// let b = DVector::from_vec(vec![1.0, 2.0, 3.0]);
+/// let x = cholesky.solve(&b).unwrap();
+/// assert!((&dense * x - b).norm() < 1e-12);
+/// ```
+#[derive(Debug)]
+pub struct OutOfCoreCholesky<T> {
+    n: usize,
+    nnz: usize,
+    path: PathBuf,
+    /// The byte offset of every panel in the spill file.
+    panel_offsets: Vec<u64>,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+/// A column of `L`, as its row indices and values. The diagonal entry comes first.
+type Column<T> = (Vec<usize>, Vec<T>);
+
+impl<T: SpillScalar> OutOfCoreCholesky<T> {
+    /// Computes the Cholesky factorization of the provided matrix in panels of `panel_columns`
+    /// columns, spilling the factor to a new file at `path`. Existing files are never
+    /// overwritten.
+    ///
+    /// Only the lower triangle of the matrix is read, and it is up to the user to ensure that the
+    /// matrix is symmetric positive definite.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CholeskyError::NotSquare`] if the matrix is not square,
+    /// [`CholeskyError::NotPositiveDefinite`] if the factorization encounters a non-positive
+    /// pivot, and [`OutOfCoreError::Io`] if the spill file could not be created, written or read.
+    /// In particular, creating the spill file fails if a file already exists at `path`, which is
+    /// left untouched. Otherwise, the spill file is removed if the factorization fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `panel_columns` is zero.
+    pub fn factor<MO, MI, D, C, P>(
+        matrix: &CsMatrix<T, MO, MI, D, C>,
+        panel_columns: usize,
+        path: P,
+    ) -> Result<Self, OutOfCoreError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+        P: AsRef<Path>,
+    {
+        assert!(panel_columns > 0, "A panel must have at least one column.");
+
+        let (n, ncols) = matrix.shape();
+
+        if n != ncols {
+            return Err(CholeskyError::NotSquare.into());
+        }
+
+        // The factorization only owns the spill file once it has created it, so that a failure
+        // never removes a file that existed before.
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        let mut factor = Self {
+            n,
+            nnz: 0,
+            path: path.as_ref().to_path_buf(),
+            panel_offsets: Vec::new(),
+            _phantom: std::marker::PhantomData,
+        };
+
+        // On failure, dropping `factor` removes the spill file.
+        factor.factor_panels(matrix, panel_columns, file)?;
+
+        Ok(factor)
+    }
+
+    fn factor_panels<MO, MI, D, C>(
+        &mut self,
+        matrix: &CsMatrix<T, MO, MI, D, C>,
+        panel_columns: usize,
+        file: File,
+    ) -> Result<(), OutOfCoreError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        let _span = span!(
+            "out_of_core_cholesky_factor",
+            n = self.n,
+            nnz = matrix.nnz()
+        );
+
+        let mut lower = vec![Vec::new(); self.n];
+
+        for (major, minor, value) in matrix.triplet_iter() {
+            let (row, col) = (C::nmajor(major, minor), C::nminor(major, minor));
+
+            if row >= col {
+                lower[col].push((row, *value));
+            }
+        }
+
+        let mut writer = BufWriter::new(file);
+        let mut position = 0;
+
+        for start in (0..self.n).step_by(panel_columns) {
+            let end = (start + panel_columns).min(self.n);
+
+            let mut accumulators: Vec<BTreeMap<usize, T>> = (start..end)
+                .map(|j| std::mem::take(&mut lower[j]).into_iter().collect())
+                .collect();
+
+            writer.flush()?;
+            self.stream_columns(0..self.panel_offsets.len(), |column| {
+                update_panel(&mut accumulators, start, &column);
+            })?;
+
+            let mut panel = Vec::with_capacity(end - start);
+
+            for j in start..end {
+                let accumulator = std::mem::take(&mut accumulators[j - start]);
+                let column = finish_column(j, accumulator)?;
+                update_panel(&mut accumulators, start, &column);
+                panel.push(column);
+            }
+
+            let bytes = encode_panel(&panel);
+            writer.write_all(&bytes)?;
+            self.panel_offsets.push(position);
+            self.nnz += panel.iter().map(|(rows, _)| rows.len()).sum::<usize>();
+            position += bytes.len() as u64;
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// The number of rows (and columns) of the factored matrix.
+    #[must_use]
+    pub fn dim(&self) -> usize {
+        self.n
+    }
+
+    /// The number of non-zeros of the factor `L`.
+    #[must_use]
+    pub fn nnz(&self) -> usize {
+        self.nnz
+    }
+
+    /// The number of panels the factor was spilled in.
+    #[must_use]
+    pub fn panels(&self) -> usize {
+        self.panel_offsets.len()
+    }
+
+    /// The path of the spill file holding the factor.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Solves the system `A x = b`, streaming the factor from disk twice.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the spill file could not be read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b` does not have as many rows as the factored matrix.
+    pub fn solve(&self, b: &DVector<T>) -> Result<DVector<T>, OutOfCoreError> {
+        assert_eq!(
+            b.nrows(),
+            self.n,
+            "The right hand side must have as many rows as the matrix."
+        );
+
+        let _span = span!("out_of_core_cholesky_solve", n = self.n);
+
+        let mut x = b.clone();
+
+        // Solve L y = b, column by column.
+        self.stream_columns(0..self.panels(), |(rows, values)| {
+            let k = rows[0];
+            x[k] /= values[0];
+            let x_k = x[k];
+
+            for (&r, &l_rk) in rows.iter().zip(&values).skip(1) {
+                x[r] -= l_rk * x_k;
+            }
+        })?;
+
+        // Solve Lᵀ x = y, with the columns in reverse order.
+        for panel in (0..self.panels()).rev() {
+            let columns = self.read_panel(panel)?;
+
+            for (rows, values) in columns.into_iter().rev() {
+                let k = rows[0];
+                let mut x_k = x[k];
+
+                for (&r, &l_rk) in rows.iter().zip(&values).skip(1) {
+                    x_k -= l_rk * x[r];
+                }
+
+                x[k] = x_k / values[0];
+            }
+        }
+
+        Ok(x)
+    }
+
+    /// Calls `f` with every column of the panels in `panels`, in order.
+    fn stream_columns<F>(&self, panels: std::ops::Range<usize>, mut f: F) -> io::Result<()>
+    where
+        F: FnMut(Column<T>),
+    {
+        if panels.is_empty() {
+            return Ok(());
+        }
+
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        reader.seek(SeekFrom::Start(self.panel_offsets[panels.start]))?;
+
+        for _ in panels {
+            for column in decode_panel(&mut reader)? {
+                f(column);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_panel(&self, panel: usize) -> io::Result<Vec<Column<T>>> {
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        reader.seek(SeekFrom::Start(self.panel_offsets[panel]))?;
+        decode_panel(&mut reader)
+    }
+}
+
+impl<T> Drop for OutOfCoreCholesky<T> {
+    fn drop(&mut self) {
+        // The spill file was created by `factor`, so it is ours to remove.
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Applies the updates of the finished column `k` of `L` to the columns of the panel starting at
+/// column `start`: `a_rj -= l_rk * l_jk` for every `r >= j`.
+fn update_panel<T: SpillScalar>(
+    accumulators: &mut [BTreeMap<usize, T>],
+    start: usize,
+    (rows, values): &Column<T>,
+) {
+    let end = start + accumulators.len();
+    let first = rows.partition_point(|&r| r < start);
+
+    for q in first..rows.len() {
+        let j = rows[q];
+
+        if j >= end {
+            break;
+        }
+
+        let l_jk = values[q];
+        let accumulator = &mut accumulators[j - start];
+
+        for (&r, &l_rk) in rows[q..].iter().zip(&values[q..]) {
+            *accumulator.entry(r).or_insert_with(T::zero) -= l_rk * l_jk;
+        }
+    }
+}
+
+/// Computes column `j` of `L` from its fully updated entries.
+fn finish_column<T: SpillScalar>(
+    j: usize,
+    mut accumulator: BTreeMap<usize, T>,
+) -> Result<Column<T>, CholeskyError> {
+    let diagonal = accumulator.remove(&j).unwrap_or_else(T::zero);
+
+    if diagonal <= T::zero() {
+        return Err(CholeskyError::NotPositiveDefinite);
+    }
+
+    let l_jj = diagonal.sqrt();
+    let mut rows = Vec::with_capacity(accumulator.len() + 1);
+    let mut values = Vec::with_capacity(accumulator.len() + 1);
+    rows.push(j);
+    values.push(l_jj);
+
+    for (r, value) in accumulator {
+        rows.push(r);
+        values.push(value / l_jj);
+    }
+
+    Ok((rows, values))
+}
+
+/// Encodes a panel as its number of columns, followed by the number of entries of every column
+/// and its `(row, value)` pairs, all in little-endian.
+fn encode_panel<T: SpillScalar>(panel: &[Column<T>]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(panel.len() as u64).to_le_bytes());
+
+    for (rows, values) in panel {
+        bytes.extend_from_slice(&(rows.len() as u64).to_le_bytes());
+
+        for (&r, value) in rows.iter().zip(values) {
+            bytes.extend_from_slice(&(r as u64).to_le_bytes());
+            value.write_le(&mut bytes);
+        }
+    }
+
+    bytes
+}
+
+fn decode_panel<T: SpillScalar, R: Read>(reader: &mut R) -> io::Result<Vec<Column<T>>> {
+    let read_u64 = |reader: &mut R| -> io::Result<u64> {
+        let mut bytes = [0; 8];
+        reader.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    };
+
+    let ncols = read_u64(reader)? as usize;
+    let mut panel = Vec::with_capacity(ncols);
+    let mut value_bytes = vec![0; T::SIZE];
+
+    for _ in 0..ncols {
+        let len = read_u64(reader)? as usize;
+        let mut rows = Vec::with_capacity(len);
+        let mut values = Vec::with_capacity(len);
+
+        for _ in 0..len {
+            rows.push(read_u64(reader)? as usize);
+            reader.read_exact(&mut value_bytes)?;
+            values.push(T::read_le(&value_bytes));
+        }
+
+        panel.push((rows, values));
+    }
+
+    Ok(panel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cs::CsrMatrix, factorization::CsCholesky};
+    use nalgebra::DMatrix;
+
+    fn spill_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "nalgebra-sparse-{}-{}.bin",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn out_of_core_cholesky_agrees_with_in_core_cholesky() {
+        // The 2D Laplacian on a 4 × 4 grid, shifted to be positive definite.
+        let m = 4;
+        let dense = DMatrix::from_fn(m * m, m * m, |a, b| {
+            let (ai, aj, bi, bj) = (a / m, a % m, b / m, b % m);
+            let distance = ai.abs_diff(bi) + aj.abs_diff(bj);
+
+            match distance {
+                0 => 4.5,
+                1 => -1.0,
+                _ => 0.0,
+            }
+        });
+        let a = CsrMatrix::from(&dense);
+        let in_core = CsCholesky::factor(&a).unwrap();
+        let b = DVector::from_fn(m * m, |i, _| i as f64 - 3.0);
+        let expected = in_core.solve(&b);
+
+        for &panel_columns in &[1, 3, m * m] {
+            let path = spill_path(&format!("panels-{}", panel_columns));
+            let cholesky = OutOfCoreCholesky::factor(&a, panel_columns, &path).unwrap();
+
+            assert_eq!(cholesky.dim(), m * m);
+            assert_eq!(cholesky.nnz(), in_core.l().nnz());
+            assert_eq!(cholesky.panels(), (m * m).div_ceil(panel_columns));
+            assert!(path.exists());
+            assert!((cholesky.solve(&b).unwrap() - &expected).norm() < 1e-12);
+
+            drop(cholesky);
+            assert!(!path.exists());
+        }
+    }
+
+    #[test]
+    fn out_of_core_cholesky_reports_errors_and_cleans_up() {
+        let path = spill_path("indefinite");
+        let indefinite = CsrMatrix::from(&DMatrix::from_row_slice(2, 2, &[1.0, 2.0, 2.0, 1.0]));
+
+        assert!(matches!(
+            OutOfCoreCholesky::factor(&indefinite, 1, &path),
+            Err(OutOfCoreError::Cholesky(CholeskyError::NotPositiveDefinite))
+        ));
+        assert!(!path.exists());
+
+        let rectangular = CsrMatrix::<f64>::zeros(2, 3);
+        assert!(matches!(
+            OutOfCoreCholesky::factor(&rectangular, 1, &path),
+            Err(OutOfCoreError::Cholesky(CholeskyError::NotSquare))
+        ));
+    }
+
+    #[test]
+    fn out_of_core_cholesky_never_touches_existing_files() {
+        let path = spill_path("existing");
+        fs::write(&path, b"precious").unwrap();
+
+        let a = CsrMatrix::<f64>::identity(3);
+        let result = OutOfCoreCholesky::factor(&a, 1, &path);
+
+        assert!(matches!(
+            result,
+            Err(OutOfCoreError::Io(ref err)) if err.kind() == io::ErrorKind::AlreadyExists
+        ));
+        drop(result);
+        assert_eq!(fs::read(&path).unwrap(), b"precious");
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+//! Immutable, cheaply clonable handles to CSR matrices.
+//!
+//! Solvers and preconditioners that cache work derived from a matrix, e.g. a symbolic
+//! factorization, need a guarantee that the matrix does not change under them. A
+//! [`FrozenCsrMatrix`] gives that guarantee: it owns its matrix behind an [`Arc`] and only ever
+//! hands out shared references to it, so every clone is a cheap pointer copy of the same matrix.
+//!
+//! Every frozen matrix carries two keys for caches:
+//!
+//! - Its [`id`](FrozenCsrMatrix::id), which identifies this particular frozen matrix and is
+//!   shared by all of its clones. Two frozen matrices with equal ids have identical values.
+//! - Its [`pattern_hash`](FrozenCsrMatrix::pattern_hash), a hash of the shape and sparsity pattern
+//!   computed once when freezing, so that structurally identical matrices (e.g. the same mesh
+//!   assembled with different coefficients) can share work that only depends on the pattern.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra_sparse::{cs::CsrMatrix, frozen::FrozenCsrMatrix};
+//!
+//! let a = FrozenCsrMatrix::new(CsrMatrix::<f64>::identity(3));
+//! let b = a.clone();
+//! let c = FrozenCsrMatrix::new(CsrMatrix::<f64>::identity(3) * 2.0);
+//!
+//! assert_eq!(a.id(), b.id());
+//! assert_ne!(a.id(), c.id());
+//! assert!(a.same_pattern(&c));
+//! assert_eq!(a.nnz(), 3);
+//! ```
+
+use crate::cs::{Compression, CsMatrix, CsrMatrix};
+use nalgebra::Scalar;
+use std::{
+    borrow::Borrow,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// The identity of a [`FrozenCsrMatrix`], shared by all of its clones.
+///
+/// Ids are never reused within a process, even after the frozen matrix has been dropped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FrozenId(u64);
+
+/// An immutable, reference-counted CSR matrix. See the [module-level documentation](self).
+#[derive(Debug)]
+pub struct FrozenCsrMatrix<T: Scalar> {
+    inner: Arc<FrozenInner<T>>,
+}
+
+#[derive(Debug)]
+struct FrozenInner<T: Scalar> {
+    matrix: CsrMatrix<T>,
+    id: FrozenId,
+    pattern_hash: u64,
+}
+
+impl<T: Scalar> Clone for FrozenCsrMatrix<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T: Scalar> FrozenCsrMatrix<T> {
+    /// Freezes `matrix`, assigning it a new id and hashing its sparsity pattern.
+    pub fn new(matrix: CsrMatrix<T>) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        let pattern_hash = pattern_hash(&matrix);
+
+        Self {
+            inner: Arc::new(FrozenInner {
+                matrix,
+                id: FrozenId(NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+                pattern_hash,
+            }),
+        }
+    }
+
+    /// The frozen matrix.
+    #[must_use]
+    pub fn matrix(&self) -> &CsrMatrix<T> {
+        &self.inner.matrix
+    }
+
+    /// The id of this frozen matrix, shared by all of its clones.
+    #[must_use]
+    pub fn id(&self) -> FrozenId {
+        self.inner.id
+    }
+
+    /// A hash of the shape and sparsity pattern of the matrix, ignoring its values.
+    #[must_use]
+    pub fn pattern_hash(&self) -> u64 {
+        self.inner.pattern_hash
+    }
+
+    /// Returns `true` if both frozen matrices have the same shape and sparsity pattern.
+    ///
+    /// The pattern hashes are compared first, so this only compares the patterns themselves when
+    /// they are very likely to be equal.
+    #[must_use]
+    pub fn same_pattern(&self, other: &Self) -> bool {
+        if Self::ptr_eq(self, other) {
+            return true;
+        }
+
+        let (offsets, indices, _) = self.matrix().cs_data();
+        let (other_offsets, other_indices, _) = other.matrix().cs_data();
+
+        self.pattern_hash() == other.pattern_hash()
+            && self.matrix().shape() == other.matrix().shape()
+            && offsets == other_offsets
+            && indices == other_indices
+    }
+
+    /// Returns `true` if both handles refer to the same frozen matrix.
+    #[must_use]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.inner, &other.inner)
+    }
+
+    /// Returns the matrix, without copying it if this is its only handle.
+    #[must_use]
+    pub fn into_inner(self) -> CsrMatrix<T> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => inner.matrix,
+            Err(inner) => inner.matrix.clone(),
+        }
+    }
+}
+
+impl<T: Scalar> Deref for FrozenCsrMatrix<T> {
+    type Target = CsrMatrix<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner.matrix
+    }
+}
+
+impl<T: Scalar> From<CsrMatrix<T>> for FrozenCsrMatrix<T> {
+    fn from(matrix: CsrMatrix<T>) -> Self {
+        Self::new(matrix)
+    }
+}
+
+/// Hashes the shape, offsets and indices of `matrix`.
+///
+/// This is the hash returned by [`FrozenCsrMatrix::pattern_hash`], so that caches can mix frozen
+/// and plain matrices.
+pub(crate) fn pattern_hash<T, MO, MI, D, C>(matrix: &CsMatrix<T, MO, MI, D, C>) -> u64
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let (offsets, indices, _) = matrix.cs_data();
+    let mut hasher = DefaultHasher::new();
+
+    matrix.shape().hash(&mut hasher);
+    offsets.hash(&mut hasher);
+    indices.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iterative::LinearOperator, proptest::*};
+    use nalgebra::DVector;
+    use proptest::prelude::*;
+
+    #[test]
+    fn frozen_matrices_are_shared_and_keep_their_identity() {
+        let matrix =
+            CsrMatrix::try_from_parts(2, 3, vec![0, 2], vec![0, 2, 1], vec![1, 2, 3]).unwrap();
+        let frozen = FrozenCsrMatrix::new(matrix.clone());
+        let clone = frozen.clone();
+
+        assert!(FrozenCsrMatrix::ptr_eq(&frozen, &clone));
+        assert_eq!(frozen.id(), clone.id());
+
+        let refrozen = FrozenCsrMatrix::new(matrix.clone());
+        assert_ne!(frozen.id(), refrozen.id());
+        assert_eq!(frozen.pattern_hash(), refrozen.pattern_hash());
+        assert!(frozen.same_pattern(&refrozen));
+
+        let transposed = FrozenCsrMatrix::new(matrix.transpose().into());
+        assert!(!frozen.same_pattern(&transposed));
+
+        let mut y = DVector::zeros(2);
+        clone.apply_to(&DVector::from_vec(vec![1, 1, 1]), &mut y);
+        assert_eq!(y, DVector::from_vec(vec![3, 3]));
+
+        drop(frozen);
+        assert_eq!(clone.into_inner().cs_data(), matrix.cs_data());
+    }
+
+    proptest! {
+        #[test]
+        fn equal_patterns_have_equal_hashes(csr in csr_strategy()) {
+            let frozen = FrozenCsrMatrix::new(csr.clone());
+            let values = FrozenCsrMatrix::new(csr * 2);
+
+            prop_assert_eq!(frozen.pattern_hash(), values.pattern_hash());
+            prop_assert!(frozen.same_pattern(&values));
+        }
+    }
+}
@@ -0,0 +1,442 @@
+//! Golden-file regression testing for kernel outputs.
+//!
+//! A golden file records the exact output (pattern and values) of a kernel for a given input. Once
+//! recorded, the output of the kernel can be compared against the golden file, so that a refactor
+//! of the internals of e.g. [`spadd_csr_csr`](crate::ops::serial::spadd::spadd_csr_csr) can be
+//! checked against the output of the previous implementation across many inputs, rather than only
+//! against a handful of hand-written expectations.
+//!
+//! Golden files are plain text, with one line for the header and one line for each of the
+//! offsets, minor indices and values of the matrix:
+//!
+//! ```text
+//! csr 3 4 5
+//! 0 2 3
+//! 0 3 1 2 3
+//! 1 2.5 -1 0 4
+//! ```
+//!
+//! Values are written with their `Display` implementation and read back with `FromStr`, which for
+//! the primitive floating-point types round-trips exactly.
+//!
+//! # Recording and checking
+//!
+//! [`assert_golden`] compares a matrix against a golden file, and panics with a
+//! [`ComparisonReport`] of the differences if they do not match. When the environment variable
+//! `NALGEBRA_SPARSE_UPDATE_GOLDEN` is set, it instead (re-)records the golden file from the given
+//! matrix. Golden files are never recorded implicitly, so that a missing golden file cannot make a
+//! test pass by accident.
+//!
+//! [`structured_inputs`] provides a set of small matrices with structures that commonly trip up
+//! kernels (empty lanes, explicit zeros, dense and banded lanes, ...), to drive golden tests.
+//!
+//! **This module is only available if the `proptest-support` feature is enabled**.
+
+use crate::{
+    analysis::{compare_report, ComparisonReport},
+    cs::{Compression, CsMatrix, CsrMatrix},
+    error::{OperationError, SparseFormatError},
+};
+use nalgebra::Scalar;
+use num_traits::Signed;
+use std::{
+    borrow::Borrow,
+    fmt::Display,
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+    str::FromStr,
+};
+use thiserror::Error;
+
+/// The environment variable that makes [`assert_golden`] record golden files instead of checking
+/// them.
+pub const UPDATE_GOLDEN_VAR: &str = "NALGEBRA_SPARSE_UPDATE_GOLDEN";
+
+/// Errors produced when reading or checking golden files.
+#[derive(Debug, Error)]
+pub enum GoldenFileError {
+    /// The golden file could not be read or written.
+    #[error("I/O error while accessing golden file: {0}")]
+    Io(#[from] io::Error),
+
+    /// The golden file does not follow the expected format.
+    #[error("Malformed golden file: {0}")]
+    Malformed(String),
+
+    /// The golden file describes an invalid sparse matrix.
+    #[error("Invalid matrix in golden file: {0}")]
+    Format(#[from] SparseFormatError),
+
+    /// The golden matrix could not be compared to the given matrix, e.g. because their shapes
+    /// differ.
+    #[error("{0}")]
+    Operation(#[from] OperationError),
+}
+
+/// The name of the compression written to the header of a golden file.
+fn compression_name<C: Compression>() -> &'static str {
+    if C::nmajor(1, 0) == 1 {
+        "csr"
+    } else {
+        "csc"
+    }
+}
+
+/// Writes `items` as a single space-separated line.
+fn write_line<W, I>(writer: &mut W, items: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator,
+    I::Item: Display,
+{
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            write!(writer, " ")?;
+        }
+
+        write!(writer, "{}", item)?;
+    }
+
+    writeln!(writer)
+}
+
+/// Parses a single space-separated line of `items`.
+fn parse_line<T: FromStr>(line: Option<&str>, what: &str) -> Result<Vec<T>, GoldenFileError> {
+    let line = line.ok_or_else(|| GoldenFileError::Malformed(format!("missing {} line", what)))?;
+
+    line.split_whitespace()
+        .map(|item| {
+            item.parse().map_err(|_| {
+                GoldenFileError::Malformed(format!("could not parse {:?} in {} line", item, what))
+            })
+        })
+        .collect()
+}
+
+/// Writes a sparse matrix in the golden file format to `writer`.
+pub fn write_golden<W, T, MO, MI, D, C>(
+    mut writer: W,
+    matrix: &CsMatrix<T, MO, MI, D, C>,
+) -> io::Result<()>
+where
+    W: Write,
+    T: Scalar + Display,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let (offsets, indices, values) = matrix.cs_data();
+
+    writeln!(
+        writer,
+        "{} {} {} {}",
+        compression_name::<C>(),
+        matrix.nrows(),
+        matrix.ncols(),
+        matrix.nnz()
+    )?;
+    write_line(&mut writer, offsets)?;
+    write_line(&mut writer, indices)?;
+    write_line(&mut writer, values)
+}
+
+/// Reads a sparse matrix in the golden file format from `reader`.
+///
+/// # Errors
+///
+/// Returns an error if the contents of `reader` cannot be read, are not in the golden file format,
+/// were written for a different compression than `C`, or do not describe a valid matrix.
+pub fn read_golden<R, T, C>(
+    mut reader: R,
+) -> Result<CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C>, GoldenFileError>
+where
+    R: Read,
+    T: Scalar + FromStr,
+    C: Compression,
+{
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents)?;
+
+    let mut lines = contents.lines();
+
+    let header = lines.next().unwrap_or_default();
+    let mut fields = header.split_whitespace();
+
+    if fields.next() != Some(compression_name::<C>()) {
+        return Err(GoldenFileError::Malformed(format!(
+            "expected a {} header, found {:?}",
+            compression_name::<C>(),
+            header
+        )));
+    }
+
+    let shape = parse_line::<usize>(Some(&header[3..]), "header")?;
+    let (nrows, ncols, nnz) = match shape[..] {
+        [nrows, ncols, nnz] => (nrows, ncols, nnz),
+        _ => {
+            return Err(GoldenFileError::Malformed(format!(
+                "expected a shape and nnz in header, found {:?}",
+                header
+            )))
+        }
+    };
+
+    let offsets = parse_line(lines.next(), "offsets")?;
+    let indices = parse_line(lines.next(), "indices")?;
+    let values = parse_line(lines.next(), "values")?;
+
+    if values.len() != nnz {
+        return Err(GoldenFileError::Malformed(format!(
+            "expected {} values, found {}",
+            nnz,
+            values.len()
+        )));
+    }
+
+    Ok(CsMatrix::try_from_parts(
+        nrows, ncols, offsets, indices, values,
+    )?)
+}
+
+/// Compares a sparse matrix against the golden file at `path`.
+///
+/// # Errors
+///
+/// Returns an error if the golden file cannot be read (e.g. because it does not exist), or does
+/// not describe a matrix with the same compression and shape as `matrix`.
+pub fn check_golden<P, T, MO, MI, D, C>(
+    path: P,
+    matrix: &CsMatrix<T, MO, MI, D, C>,
+    tolerance: T,
+) -> Result<ComparisonReport<T>, GoldenFileError>
+where
+    P: AsRef<Path>,
+    T: Scalar + Signed + PartialOrd + FromStr,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let golden = read_golden::<_, T, C>(fs::File::open(path)?)?;
+
+    Ok(compare_report(&golden, matrix, tolerance)?)
+}
+
+/// Asserts that a sparse matrix matches the golden file at `path`, within `tolerance`.
+///
+/// If the environment variable [`UPDATE_GOLDEN_VAR`] is set, this records `matrix` to `path`
+/// instead, creating any missing parent directories.
+///
+/// # Panics
+///
+/// Panics if the golden file cannot be read or written, or if the matrix does not match the golden
+/// file. In the latter case, the panic message lists the differences, where `A` is the golden
+/// matrix and `B` is `matrix`.
+pub fn assert_golden<P, T, MO, MI, D, C>(path: P, matrix: &CsMatrix<T, MO, MI, D, C>, tolerance: T)
+where
+    P: AsRef<Path>,
+    T: Scalar + Signed + PartialOrd + FromStr + Display,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let path = path.as_ref();
+
+    if std::env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+        let record = || -> io::Result<()> {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            write_golden(io::BufWriter::new(fs::File::create(path)?), matrix)
+        };
+
+        if let Err(err) = record() {
+            panic!("could not record golden file {}: {}", path.display(), err);
+        }
+
+        return;
+    }
+
+    match check_golden(path, matrix, tolerance) {
+        Ok(report) if report.is_match() => {}
+        Ok(report) => panic!(
+            "matrix does not match golden file {}: {}",
+            path.display(),
+            report
+        ),
+        Err(GoldenFileError::Io(err)) if err.kind() == io::ErrorKind::NotFound => panic!(
+            "missing golden file {}; set {} to record it",
+            path.display(),
+            UPDATE_GOLDEN_VAR
+        ),
+        Err(err) => panic!("could not check golden file {}: {}", path.display(), err),
+    }
+}
+
+/// Returns a set of named, small matrices with structures that are useful to drive golden tests.
+///
+/// The set covers empty matrices, matrices with empty rows and columns, explicit zeros, banded,
+/// triangular, dense and rectangular matrices. All values are small integers or halves, so that
+/// they are exactly representable and kernels built from additions and multiplications produce
+/// exact results.
+#[must_use]
+pub fn structured_inputs() -> Vec<(&'static str, CsrMatrix<f64>)> {
+    let from_fn = |nrows, ncols, f: &dyn Fn(usize, usize) -> Option<f64>| {
+        let mut offsets = Vec::with_capacity(nrows);
+        let mut indices = Vec::new();
+        let mut values = Vec::new();
+
+        for i in 0..nrows {
+            offsets.push(indices.len());
+
+            for j in 0..ncols {
+                if let Some(value) = f(i, j) {
+                    indices.push(j);
+                    values.push(value);
+                }
+            }
+        }
+
+        CsrMatrix::try_from_parts(nrows, ncols, offsets, indices, values).unwrap()
+    };
+
+    vec![
+        ("empty", CsrMatrix::zeros(0, 0)),
+        ("zeros", CsrMatrix::zeros(3, 4)),
+        ("identity", CsrMatrix::identity(5)),
+        (
+            "tridiagonal",
+            from_fn(6, 6, &|i, j| match i as isize - j as isize {
+                0 => Some(2.0),
+                -1 | 1 => Some(-1.0),
+                _ => None,
+            }),
+        ),
+        (
+            "arrow",
+            from_fn(5, 5, &|i, j| {
+                (i == 0 || j == 0 || i == j).then_some(1.0 + (i + j) as f64 * 0.5)
+            }),
+        ),
+        (
+            "upper_triangular",
+            from_fn(4, 4, &|i, j| (i <= j).then(|| (j - i) as f64 + 1.0)),
+        ),
+        (
+            "dense",
+            from_fn(3, 3, &|i, j| Some((3 * i + j) as f64 - 4.0)),
+        ),
+        (
+            "empty_lanes",
+            from_fn(5, 5, &|i, j| {
+                (i % 2 == 0 && j % 2 == 1).then_some((i + j) as f64)
+            }),
+        ),
+        (
+            "explicit_zeros",
+            from_fn(4, 4, &|i, j| {
+                ((i + j) % 3 == 0).then_some((i as f64 - j as f64) * 0.5)
+            }),
+        ),
+        (
+            "wide",
+            from_fn(3, 7, &|i, j| {
+                ((j + i) % 2 == 0).then_some(j as f64 - i as f64)
+            }),
+        ),
+        (
+            "tall",
+            from_fn(7, 2, &|i, j| (i % 3 != j).then_some(1.0 - i as f64 * 0.5)),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cs::CscMatrix, proptest::*};
+    use proptest::prelude::*;
+
+    #[test]
+    fn read_golden_rejects_mismatched_compression() {
+        let mut buffer = Vec::new();
+        write_golden(&mut buffer, &CsrMatrix::<i32>::identity(2)).unwrap();
+
+        let err = read_golden::<_, i32, _>(buffer.as_slice())
+            .map(|csc: CscMatrix<i32>| csc.nnz())
+            .unwrap_err();
+
+        assert!(matches!(err, GoldenFileError::Malformed(_)));
+    }
+
+    #[test]
+    fn read_golden_rejects_invalid_matrices() {
+        let truncated = "csr 2 2 2\n0 1\n0 1\n";
+        let unparseable = "csr 2 2 2\n0 1\n0 x\n1 2\n";
+        let out_of_bounds = "csr 2 2 2\n0 1\n0 2\n1 2\n";
+
+        assert!(matches!(
+            read_golden::<_, i32, crate::cs::CompressedRowStorage>(truncated.as_bytes()),
+            Err(GoldenFileError::Malformed(_))
+        ));
+        assert!(matches!(
+            read_golden::<_, i32, crate::cs::CompressedRowStorage>(unparseable.as_bytes()),
+            Err(GoldenFileError::Malformed(_))
+        ));
+        assert!(matches!(
+            read_golden::<_, i32, crate::cs::CompressedRowStorage>(out_of_bounds.as_bytes()),
+            Err(GoldenFileError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn check_golden_reports_differences() {
+        let path = std::env::temp_dir().join(format!(
+            "nalgebra-sparse-check-golden-{}.golden",
+            std::process::id()
+        ));
+
+        let matrix = CsrMatrix::<i32>::identity(3);
+        write_golden(fs::File::create(&path).unwrap(), &matrix).unwrap();
+
+        assert!(check_golden(&path, &matrix, 0).unwrap().is_match());
+
+        let report = check_golden(&path, &(matrix * 2), 0).unwrap();
+        assert_eq!(report.differing.len(), 3);
+
+        let err = check_golden(&path, &CsrMatrix::<i32>::identity(2), 0).unwrap_err();
+        assert!(matches!(err, GoldenFileError::Operation(_)));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn structured_inputs_round_trip_exactly() {
+        for (name, matrix) in structured_inputs() {
+            let mut buffer = Vec::new();
+            write_golden(&mut buffer, &matrix).unwrap();
+
+            let read: CsrMatrix<f64> = read_golden(buffer.as_slice()).unwrap();
+
+            assert_eq!(read.shape(), matrix.shape(), "{}", name);
+            assert_eq!(read.cs_data(), matrix.cs_data(), "{}", name);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn golden_csc_round_trips(csc in csc_strategy()) {
+            let mut buffer = Vec::new();
+            write_golden(&mut buffer, &csc).unwrap();
+
+            let read: CscMatrix<i32> = read_golden(buffer.as_slice()).unwrap();
+
+            prop_assert_eq!(read.shape(), csc.shape());
+            prop_assert_eq!(read.cs_data(), csc.cs_data());
+        }
+    }
+}
@@ -0,0 +1,599 @@
+//! Construction of sparse neighborhood graphs from dense point sets.
+//!
+//! Spectral clustering, manifold learning and graph-based semi-supervised learning all start
+//! from a sparse graph on a set of points, whose edges connect every point to its nearest
+//! neighbours. This module builds such graphs directly as [`CsrMatrix`] adjacency matrices, so
+//! that they can be used with the rest of the crate right away.
+//!
+//! The points are given as the rows of a dense matrix, and distances are Euclidean.
+//!
+//! - [`KnnGraph`] connects every point to its `k` nearest neighbours, optionally symmetrized
+//!   with a [`Symmetrization`] policy.
+//! - [`RadiusGraph`] connects every pair of points within a given distance of each other.
+//!
+//! The values stored for the edges, e.g. distances or Gaussian affinities, are chosen with
+//! [`EdgeWeights`]. Neither graph has self-loops unless they are requested.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra::DMatrix;
+//! use nalgebra_sparse::graph::{EdgeWeights, KnnGraph, RadiusGraph};
+//!
+//! // Four points on a line.
+//! let points = DMatrix::from_column_slice(4, 1, &[0.0, 1.0, 3.0, 7.0]);
+//!
+//! let graph = KnnGraph::new(1).build(&points);
+//! let (_, neighbours, distances) = graph.cs_data();
+//! assert_eq!(neighbours, &[1, 0, 1, 2]);
+//! assert_eq!(distances, &[1.0, 1.0, 2.0, 4.0]);
+//!
+//! let connectivity = KnnGraph::new(2)
+//!     .with_weights(EdgeWeights::Connectivity)
+//!     .build(&points);
+//! assert_eq!(connectivity.nnz(), 8);
+//!
+//! // Points within a distance of 2 of each other, with self-loops.
+//! let radius = RadiusGraph::new(2.0).with_self_loops(true).build(&points);
+//! assert_eq!(radius.cs_data().1, &[0, 1, 0, 1, 2, 1, 2, 3]);
+//! ```
+
+use crate::cs::CsrMatrix;
+use nalgebra::{Dim, Matrix, RawStorage, RealField};
+
+/// The values stored for the edges of a neighborhood graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeWeights<T> {
+    /// Stores the Euclidean distance between the endpoints.
+    ///
+    /// Edges between coincident points, including self-loops, are stored as explicit zeros.
+    Distance,
+
+    /// Stores `1` for every edge.
+    Connectivity,
+
+    /// Stores the Gaussian affinity `exp(-d² / (2 * bandwidth²))` of the endpoints at distance
+    /// `d`.
+    ///
+    /// This is also known as the heat kernel `exp(-d² / t)`, with `t = 2 * bandwidth²`. The
+    /// bandwidth must be positive.
+    Gaussian {
+        /// The standard deviation of the kernel.
+        bandwidth: T,
+    },
+}
+
+impl<T: RealField> EdgeWeights<T> {
+    fn weight(&self, squared_distance: T) -> T {
+        match self {
+            EdgeWeights::Distance => squared_distance.sqrt(),
+            EdgeWeights::Connectivity => T::one(),
+            EdgeWeights::Gaussian { bandwidth } => {
+                let variance = bandwidth.clone() * bandwidth.clone();
+                (-squared_distance / (variance.clone() + variance)).exp()
+            }
+        }
+    }
+}
+
+/// How the directed edges of a k-nearest-neighbour graph are turned into a symmetric graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetrization {
+    /// Keeps the directed graph, in which `j` may be a neighbour of `i` but not the converse.
+    None,
+
+    /// Connects `i` and `j` if either is among the nearest neighbours of the other.
+    ///
+    /// Points may end up with more than `k` neighbours.
+    Union,
+
+    /// Connects `i` and `j` only if both are among the nearest neighbours of the other.
+    ///
+    /// Points may end up with fewer than `k` neighbours, or none at all.
+    Mutual,
+}
+
+/// A builder for k-nearest-neighbour graphs.
+///
+/// Row `i` of the built adjacency matrix holds an edge to each of the `k` points closest to
+/// point `i`, excluding point `i` itself. The graph is therefore not symmetric in general: `j`
+/// may be among the nearest neighbours of `i` without the converse being true, unless a
+/// [`Symmetrization`] is requested. Ties in distance are broken in favour of the point with the
+/// smallest index, so the graph is deterministic. If there are no more than `k` other points,
+/// every other point is a neighbour.
+///
+/// Neighbours are found by brute force, comparing every pair of points. The comparisons are
+/// done in square blocks of points, so that both blocks stay in cache, which keeps the cost at
+/// `O(n² d)` for `n` points in `d` dimensions with a small constant. Distances involving
+/// non-finite coordinates are unspecified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnnGraph<T> {
+    k: usize,
+    weights: EdgeWeights<T>,
+    symmetrization: Symmetrization,
+    self_loops: bool,
+    block_size: usize,
+}
+
+impl<T> KnnGraph<T> {
+    /// Creates a builder for directed graphs connecting every point to its `k` nearest
+    /// neighbours, with distances as edge weights and without self-loops.
+    #[must_use]
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            weights: EdgeWeights::Distance,
+            symmetrization: Symmetrization::None,
+            self_loops: false,
+            block_size: 64,
+        }
+    }
+
+    /// Sets the values stored for the edges.
+    #[must_use]
+    pub fn with_weights(self, weights: EdgeWeights<T>) -> Self {
+        Self { weights, ..self }
+    }
+
+    /// Sets how the directed nearest-neighbour relation is symmetrized.
+    #[must_use]
+    pub fn with_symmetrization(self, symmetrization: Symmetrization) -> Self {
+        Self {
+            symmetrization,
+            ..self
+        }
+    }
+
+    /// Sets whether every point is connected to itself, in addition to its `k` neighbours.
+    #[must_use]
+    pub fn with_self_loops(self, self_loops: bool) -> Self {
+        Self { self_loops, ..self }
+    }
+
+    /// Sets the number of points in each block of the brute-force search.
+    ///
+    /// The default of 64 keeps two blocks of points in low-dimensional spaces comfortably in
+    /// cache. A block size of zero is treated as one.
+    #[must_use]
+    pub fn with_block_size(self, block_size: usize) -> Self {
+        Self { block_size, ..self }
+    }
+}
+
+impl<T: RealField> KnnGraph<T> {
+    /// Builds the graph of the points given by the rows of `points`.
+    ///
+    /// The result is a square matrix with one row and column per point. Without symmetrization
+    /// and self-loops, every row has at most `k` explicit entries.
+    #[must_use]
+    pub fn build<R, C, S>(&self, points: &Matrix<T, R, C, S>) -> CsrMatrix<T>
+    where
+        R: Dim,
+        C: Dim,
+        S: RawStorage<T, R, C>,
+    {
+        let npoints = points.nrows();
+
+        let _span = span!(
+            "knn_graph",
+            npoints = npoints,
+            dimension = points.ncols(),
+            k = self.k
+        );
+
+        let k = self.k.min(npoints.saturating_sub(1));
+        let mut nearest = vec![Vec::with_capacity(k + 1); npoints];
+
+        blocked_search(points, self.block_size, |query, candidate, distance| {
+            offer(&mut nearest[query], k, distance, candidate);
+        });
+
+        let mut neighbourhoods: Vec<_> = nearest
+            .into_iter()
+            .map(|neighbours| {
+                let mut neighbourhood: Vec<_> =
+                    neighbours.into_iter().map(|(d, j)| (j, d)).collect();
+                neighbourhood.sort_unstable_by_key(|(j, _)| *j);
+                neighbourhood
+            })
+            .collect();
+
+        if self.symmetrization != Symmetrization::None {
+            neighbourhoods = symmetrize(neighbourhoods, self.symmetrization);
+        }
+
+        assemble(neighbourhoods, &self.weights, self.self_loops)
+    }
+}
+
+/// A builder for radius graphs, also known as epsilon-neighborhood graphs.
+///
+/// Points `i` and `j` are connected if their distance is at most the radius, so the graph is
+/// always symmetric. Like [`KnnGraph`], the graph is built by a blocked brute-force search over
+/// all pairs of points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RadiusGraph<T> {
+    radius: T,
+    weights: EdgeWeights<T>,
+    self_loops: bool,
+    block_size: usize,
+}
+
+impl<T> RadiusGraph<T> {
+    /// Creates a builder for graphs connecting all points within `radius` of each other, with
+    /// distances as edge weights and without self-loops.
+    #[must_use]
+    pub fn new(radius: T) -> Self {
+        Self {
+            radius,
+            weights: EdgeWeights::Distance,
+            self_loops: false,
+            block_size: 64,
+        }
+    }
+
+    /// Sets the values stored for the edges.
+    #[must_use]
+    pub fn with_weights(self, weights: EdgeWeights<T>) -> Self {
+        Self { weights, ..self }
+    }
+
+    /// Sets whether every point is connected to itself.
+    #[must_use]
+    pub fn with_self_loops(self, self_loops: bool) -> Self {
+        Self { self_loops, ..self }
+    }
+
+    /// Sets the number of points in each block of the brute-force search.
+    ///
+    /// See [`KnnGraph::with_block_size`].
+    #[must_use]
+    pub fn with_block_size(self, block_size: usize) -> Self {
+        Self { block_size, ..self }
+    }
+}
+
+impl<T: RealField> RadiusGraph<T> {
+    /// Builds the graph of the points given by the rows of `points`.
+    ///
+    /// The result is a symmetric matrix with one row and column per point. A negative radius
+    /// gives a graph without edges, apart from any self-loops.
+    #[must_use]
+    pub fn build<R, C, S>(&self, points: &Matrix<T, R, C, S>) -> CsrMatrix<T>
+    where
+        R: Dim,
+        C: Dim,
+        S: RawStorage<T, R, C>,
+    {
+        let npoints = points.nrows();
+
+        let _span = span!(
+            "radius_graph",
+            npoints = npoints,
+            dimension = points.ncols()
+        );
+
+        let mut neighbourhoods = vec![Vec::new(); npoints];
+
+        if self.radius >= T::zero() {
+            let squared_radius = self.radius.clone() * self.radius.clone();
+
+            // Candidates are visited in increasing order for every query, so every neighbourhood
+            // is sorted by index.
+            blocked_search(points, self.block_size, |query, candidate, distance| {
+                if distance <= squared_radius {
+                    neighbourhoods[query].push((candidate, distance));
+                }
+            });
+        }
+
+        assemble(neighbourhoods, &self.weights, self.self_loops)
+    }
+}
+
+/// Calls `visit(query, candidate, squared_distance)` for every pair of distinct points given by
+/// the rows of `points`.
+///
+/// Pairs are visited in square blocks of `block_size` points. For every query, the candidates
+/// are visited in increasing order.
+fn blocked_search<T, R, C, S, F>(points: &Matrix<T, R, C, S>, block_size: usize, mut visit: F)
+where
+    T: RealField,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C>,
+    F: FnMut(usize, usize, T),
+{
+    let (npoints, dimension) = points.shape();
+    let block_size = block_size.max(1);
+
+    let coordinates = row_major_coordinates(points);
+    let point = |i: usize| &coordinates[i * dimension..(i + 1) * dimension];
+
+    for query_start in (0..npoints).step_by(block_size) {
+        let queries = query_start..(query_start + block_size).min(npoints);
+
+        for candidate_start in (0..npoints).step_by(block_size) {
+            let candidates = candidate_start..(candidate_start + block_size).min(npoints);
+
+            for query in queries.clone() {
+                for candidate in candidates.clone() {
+                    if candidate != query {
+                        visit(
+                            query,
+                            candidate,
+                            squared_distance(point(query), point(candidate)),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Copies the rows of `points` into a contiguous row-major buffer.
+fn row_major_coordinates<T, R, C, S>(points: &Matrix<T, R, C, S>) -> Vec<T>
+where
+    T: RealField,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C>,
+{
+    (0..points.nrows())
+        .flat_map(|i| (0..points.ncols()).map(move |d| points[(i, d)].clone()))
+        .collect()
+}
+
+fn squared_distance<T: RealField>(a: &[T], b: &[T]) -> T {
+    a.iter().zip(b).fold(T::zero(), |total, (a, b)| {
+        let difference = a.clone() - b.clone();
+        total + difference.clone() * difference
+    })
+}
+
+/// Inserts `candidate` into the sorted list of the `k` nearest neighbours found so far, if it is
+/// closer than the farthest of them.
+///
+/// Candidates are offered in increasing order of their index, so candidates at the same distance
+/// as an existing neighbour are inserted after it, and never displace it.
+fn offer<T: RealField>(neighbours: &mut Vec<(T, usize)>, k: usize, distance: T, candidate: usize) {
+    if neighbours.len() == k
+        && neighbours
+            .last()
+            .is_none_or(|(farthest, _)| distance >= *farthest)
+    {
+        return;
+    }
+
+    let position = neighbours.partition_point(|(d, _)| *d <= distance);
+    neighbours.insert(position, (distance, candidate));
+    neighbours.truncate(k);
+}
+
+/// Symmetrizes directed neighbourhoods, given as `(neighbour, squared distance)` pairs sorted by
+/// neighbour.
+fn symmetrize<T: RealField>(
+    neighbourhoods: Vec<Vec<(usize, T)>>,
+    symmetrization: Symmetrization,
+) -> Vec<Vec<(usize, T)>> {
+    // The reversed edges, which are sorted because the sources are visited in increasing order.
+    let mut reversed = vec![Vec::new(); neighbourhoods.len()];
+
+    for (i, neighbourhood) in neighbourhoods.iter().enumerate() {
+        for (j, distance) in neighbourhood {
+            reversed[*j].push((i, distance.clone()));
+        }
+    }
+
+    neighbourhoods
+        .into_iter()
+        .zip(reversed)
+        .map(|(forward, backward)| {
+            let mut merged = Vec::with_capacity(forward.len() + backward.len());
+            let mut forward = forward.into_iter().peekable();
+            let mut backward = backward.into_iter().peekable();
+
+            loop {
+                let (edge, in_both) = match (forward.peek(), backward.peek()) {
+                    (Some((f, _)), Some((b, _))) if f == b => {
+                        backward.next();
+                        (forward.next(), true)
+                    }
+                    (Some((f, _)), Some((b, _))) if f < b => (forward.next(), false),
+                    (_, Some(_)) => (backward.next(), false),
+                    (Some(_), None) => (forward.next(), false),
+                    (None, None) => break,
+                };
+
+                if in_both || symmetrization == Symmetrization::Union {
+                    merged.extend(edge);
+                }
+            }
+
+            merged
+        })
+        .collect()
+}
+
+/// Assembles neighbourhoods, given as `(neighbour, squared distance)` pairs sorted by neighbour,
+/// into a weighted adjacency matrix.
+fn assemble<T: RealField>(
+    neighbourhoods: Vec<Vec<(usize, T)>>,
+    weights: &EdgeWeights<T>,
+    self_loops: bool,
+) -> CsrMatrix<T> {
+    let npoints = neighbourhoods.len();
+    let nnz = neighbourhoods.iter().map(Vec::len).sum::<usize>() + self_loops as usize * npoints;
+
+    let mut offsets = Vec::with_capacity(npoints);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+
+    for (i, neighbourhood) in neighbourhoods.into_iter().enumerate() {
+        offsets.push(indices.len());
+
+        let split = neighbourhood.partition_point(|(j, _)| *j < i);
+        let mut edges = neighbourhood.into_iter();
+
+        for (j, distance) in edges.by_ref().take(split) {
+            indices.push(j);
+            data.push(weights.weight(distance));
+        }
+
+        if self_loops {
+            indices.push(i);
+            data.push(weights.weight(T::zero()));
+        }
+
+        for (j, distance) in edges {
+            indices.push(j);
+            data.push(weights.weight(distance));
+        }
+    }
+
+    // SAFETY: Every neighbourhood holds distinct indices below `npoints`, sorted, and excludes
+    // the point itself, so adding the self-loop in sorted position keeps them distinct.
+    unsafe { CsrMatrix::from_parts_unchecked(npoints, npoints, offsets, indices, data) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::DMatrix;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    /// The `k` nearest neighbours of every point, sorted by index, found by sorting all others.
+    fn naive_knn(points: &DMatrix<f64>, k: usize) -> Vec<Vec<(usize, f64)>> {
+        (0..points.nrows())
+            .map(|i| {
+                let mut others: Vec<_> = (0..points.nrows())
+                    .filter(|&j| j != i)
+                    .map(|j| (j, (points.row(i) - points.row(j)).norm()))
+                    .collect();
+                others.sort_by(|(i, a), (j, b)| a.partial_cmp(b).unwrap().then(i.cmp(j)));
+                others.truncate(k);
+                others.sort_by_key(|(j, _)| *j);
+                others
+            })
+            .collect()
+    }
+
+    /// Up to a dozen points with small integer coordinates, so that there are many ties.
+    fn points_strategy() -> impl Strategy<Value = DMatrix<f64>> {
+        (0..12usize, 0..3usize).prop_flat_map(|(n, d)| {
+            proptest::collection::vec(-3..=3i32, n * d).prop_map(move |coordinates| {
+                DMatrix::from_fn(n, d, |i, k| f64::from(coordinates[i * d + k]))
+            })
+        })
+    }
+
+    #[test]
+    fn knn_graph_handles_degenerate_inputs() {
+        let empty = KnnGraph::new(3).build(&DMatrix::<f64>::zeros(0, 2));
+        assert_eq!((empty.nrows(), empty.ncols(), empty.nnz()), (0, 0, 0));
+
+        // With fewer than k other points, every other point is a neighbour.
+        let points = DMatrix::from_row_slice(3, 2, &[0.0, 0.0, 0.0, 0.0, 3.0, 4.0]);
+        let graph = KnnGraph::new(5).build(&points);
+        let (offsets, indices, data) = graph.cs_data();
+        assert_eq!(offsets, &[0, 2, 4]);
+        assert_eq!(indices, &[1, 2, 0, 2, 0, 1]);
+        assert_eq!(data, &[0.0, 5.0, 0.0, 5.0, 5.0, 5.0]);
+
+        assert_eq!(KnnGraph::new(0).build(&points).nnz(), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn knn_graph_agrees_with_naive_search(
+            points in points_strategy(),
+            k in 0..5usize,
+            block_size in 0..5usize,
+        ) {
+            let npoints = points.nrows();
+            let graph = KnnGraph::new(k).with_block_size(block_size).build(&points);
+
+            prop_assert_eq!(graph.shape(), (npoints, npoints));
+
+            for (lane, expected) in graph.iter().zip(naive_knn(&points, k)) {
+                let actual: Vec<_> = lane.map(|(j, v)| (j, *v)).collect();
+                prop_assert_eq!(actual, expected);
+            }
+
+            let connectivity = KnnGraph::new(k)
+                .with_weights(EdgeWeights::Connectivity)
+                .build(&points);
+            prop_assert_eq!(connectivity.pattern(), graph.pattern());
+            prop_assert!(connectivity.triplet_iter().all(|(_, _, &v)| v == 1.0));
+        }
+
+        #[test]
+        fn knn_graph_symmetrizations_agree_with_naive_search(
+            points in points_strategy(),
+            k in 0..5usize,
+            self_loops in any::<bool>(),
+        ) {
+            let npoints = points.nrows();
+            let directed: BTreeSet<_> = naive_knn(&points, k)
+                .into_iter()
+                .enumerate()
+                .flat_map(|(i, neighbours)| neighbours.into_iter().map(move |(j, _)| (i, j)))
+                .collect();
+            let loops: BTreeSet<_> = (0..npoints).filter(|_| self_loops).map(|i| (i, i)).collect();
+            let is_edge = |i: usize, j: usize| directed.contains(&(i, j));
+
+            let builder = KnnGraph::new(k).with_self_loops(self_loops);
+
+            for (symmetrization, keep) in [
+                (Symmetrization::None, (|a, _| a) as fn(bool, bool) -> bool),
+                (Symmetrization::Union, |a, b| a || b),
+                (Symmetrization::Mutual, |a, b| a && b),
+            ] {
+                let graph = builder.with_symmetrization(symmetrization).build(&points);
+
+                let expected: Vec<_> = (0..npoints)
+                    .flat_map(|i| (0..npoints).map(move |j| (i, j)))
+                    .filter(|&(i, j)| loops.contains(&(i, j)) || keep(is_edge(i, j), is_edge(j, i)))
+                    .map(|(i, j)| (i, j, (points.row(i) - points.row(j)).norm()))
+                    .collect();
+                let actual: Vec<_> = graph.triplet_iter().map(|(i, j, v)| (i, j, *v)).collect();
+
+                prop_assert_eq!(actual, expected);
+            }
+        }
+
+        #[test]
+        fn radius_graph_agrees_with_naive_search(
+            points in points_strategy(),
+            radius in -1.0..4.0f64,
+            block_size in 0..5usize,
+            self_loops in any::<bool>(),
+        ) {
+            let npoints = points.nrows();
+            let bandwidth = 1.5;
+            let graph = RadiusGraph::new(radius)
+                .with_weights(EdgeWeights::Gaussian { bandwidth })
+                .with_self_loops(self_loops)
+                .with_block_size(block_size)
+                .build(&points);
+
+            let expected: Vec<_> = (0..npoints)
+                .flat_map(|i| (0..npoints).map(move |j| (i, j)))
+                .filter_map(|(i, j)| {
+                    let distance = (points.row(i) - points.row(j)).norm();
+                    let is_edge = if i == j { self_loops } else { distance <= radius };
+                    is_edge.then_some((i, j, distance))
+                })
+                .collect();
+
+            prop_assert_eq!(graph.nnz(), expected.len());
+
+            for ((i, j, weight), (ei, ej, distance)) in graph.triplet_iter().zip(expected) {
+                prop_assert_eq!((i, j), (ei, ej));
+                let affinity = (-distance * distance / (2.0 * bandwidth * bandwidth)).exp();
+                prop_assert!((weight - affinity).abs() <= 1e-12);
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! Crate-internal helpers for instrumenting expensive operations with `tracing` spans.
+//!
+//! When the `tracing` feature is enabled, the [`span!`] macro creates and enters a `DEBUG` level
+//! span with the given name and fields, and [`record!`] records a field on that span after the
+//! fact (e.g. the number of non-zeros in a result). When the feature is disabled, both macros
+//! expand to (almost) nothing, and none of the field expressions are evaluated.
+//!
+//! Fields that are only known once the operation completes should be declared up-front with
+//! `tracing::field::Empty`, as `tracing` otherwise ignores values recorded for unknown fields.
+
+/// Creates and enters a span, returning a guard that exits the span when dropped.
+#[cfg(feature = "tracing")]
+macro_rules! span {
+    ($name:literal $(, $field:ident = $value:expr)* $(,)?) => {
+        ::tracing::debug_span!($name $(, $field = $value)*).entered()
+    };
+}
+
+/// Creates and enters a span, returning a guard that exits the span when dropped.
+#[cfg(not(feature = "tracing"))]
+macro_rules! span {
+    ($name:literal $(, $field:ident = $value:expr)* $(,)?) => {
+        $crate::instrument::DisabledSpan
+    };
+}
+
+/// Records the value of a field on a span previously created by [`span!`].
+#[cfg(feature = "tracing")]
+macro_rules! record {
+    ($span:expr, $field:ident = $value:expr) => {
+        $span.record(stringify!($field), $value);
+    };
+}
+
+/// Records the value of a field on a span previously created by [`span!`].
+#[cfg(not(feature = "tracing"))]
+macro_rules! record {
+    ($span:expr, $field:ident = $value:expr) => {
+        let _ = &$span;
+    };
+}
+
+/// Stand-in for an entered span when the `tracing` feature is disabled.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct DisabledSpan;
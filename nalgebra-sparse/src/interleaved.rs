@@ -0,0 +1,340 @@
+//! A compressed sparse matrix type that stores indices and values interleaved.
+//!
+//! [`CsMatrix`] stores the minor indices and the values of its explicit entries in two separate
+//! arrays (a "struct of arrays" layout). [`InterleavedCsMatrix`] instead stores `(index, value)`
+//! pairs in a single array (an "array of structs" layout). For matrices with very short lanes, this
+//! can improve cache behaviour, since reading an entry only touches a single cache line rather
+//! than two. For long lanes, or for operations that only touch the sparsity pattern, the separate
+//! arrays of `CsMatrix` are usually the better choice.
+//!
+//! Which layout wins depends heavily on the matrix and the operation, so both can be converted to
+//! one another with `From`, and the sparse-dense products and matrix-vector products of
+//! [`ops::serial::spmm`](crate::ops::serial::spmm) have interleaved counterparts (e.g.
+//! [`spmv_interleaved_csr`](crate::ops::serial::spmm::spmv_interleaved_csr) for
+//! [`spmv_csr`](crate::ops::serial::spmm::spmv_csr)) that compute the same results, so that the
+//! trade-off can be measured.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra_sparse::{cs::CsrMatrix, interleaved::InterleavedCsrMatrix};
+//!
+//! let csr = CsrMatrix::<f64>::identity(3);
+//! let interleaved = InterleavedCsrMatrix::from(&csr);
+//!
+//! assert_eq!(interleaved.entries(), &[(0, 1.0), (1, 1.0), (2, 1.0)]);
+//!
+//! let round_trip = CsrMatrix::from(interleaved);
+//! assert_eq!(round_trip.cs_data(), csr.cs_data());
+//! ```
+
+use crate::{
+    cs::{validate_pattern, CompressedColumnStorage, CompressedRowStorage, Compression},
+    error::SparseFormatError,
+    SparseEntry,
+};
+use nalgebra::Scalar;
+use std::marker::PhantomData;
+
+/// A compressed sparse matrix that stores the minor index and value of each explicit entry
+/// side-by-side.
+///
+/// Apart from the layout of the entries, this type upholds the same invariants as
+/// [`CsMatrix`](crate::cs::CsMatrix): the offsets have one entry per major lane, and the entries
+/// within each lane are sorted by their minor index without duplicates.
+#[derive(Debug, Clone)]
+pub struct InterleavedCsMatrix<T, CompressionKind>
+where
+    T: Scalar,
+    CompressionKind: Compression,
+{
+    shape: (usize, usize),
+    offsets: Vec<usize>,
+    entries: Vec<(usize, T)>,
+    _phantom: PhantomData<CompressionKind>,
+}
+
+/// An alias for an interleaved, row-major compressed sparse matrix.
+pub type InterleavedCsrMatrix<T> = InterleavedCsMatrix<T, CompressedRowStorage>;
+
+/// An alias for an interleaved, column-major compressed sparse matrix.
+pub type InterleavedCscMatrix<T> = InterleavedCsMatrix<T, CompressedColumnStorage>;
+
+impl<T, CompressionKind> InterleavedCsMatrix<T, CompressionKind>
+where
+    T: Scalar,
+    CompressionKind: Compression,
+{
+    /// The shape of the matrix, as (nrows, ncols).
+    #[inline]
+    #[must_use]
+    pub fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    /// The number of rows in this matrix.
+    #[inline]
+    #[must_use]
+    pub fn nrows(&self) -> usize {
+        self.shape.0
+    }
+
+    /// The number of columns in this matrix.
+    #[inline]
+    #[must_use]
+    pub fn ncols(&self) -> usize {
+        self.shape.1
+    }
+
+    /// The number of lanes along the major dimension of this matrix.
+    #[inline]
+    pub fn nmajor(&self) -> usize {
+        let (rows, cols) = self.shape;
+        CompressionKind::nmajor(rows, cols)
+    }
+
+    /// The number of lanes along the minor dimension of this matrix.
+    #[inline]
+    pub fn nminor(&self) -> usize {
+        let (rows, cols) = self.shape;
+        CompressionKind::nminor(rows, cols)
+    }
+
+    /// Returns the number of non-zero entries in the sparse matrix.
+    #[inline]
+    #[must_use]
+    pub fn nnz(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) unsafe fn from_parts_unchecked(
+        nrows: usize,
+        ncols: usize,
+        offsets: Vec<usize>,
+        entries: Vec<(usize, T)>,
+    ) -> Self {
+        Self {
+            shape: (nrows, ncols),
+            offsets,
+            entries,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Constructor for the `InterleavedCsMatrix` type that checks for shape / size / compression
+    /// consistency.
+    ///
+    /// # Errors
+    ///
+    /// This function errors out in the same scenarios as
+    /// [`CsMatrix::try_from_parts`](crate::cs::CsMatrix::try_from_parts), where the indices are
+    /// the first element of each entry.
+    pub fn try_from_parts(
+        nrows: usize,
+        ncols: usize,
+        offsets: Vec<usize>,
+        entries: Vec<(usize, T)>,
+    ) -> Result<Self, SparseFormatError> {
+        let indices = entries.iter().map(|(index, _)| *index).collect::<Vec<_>>();
+
+        validate_pattern(
+            CompressionKind::nmajor(nrows, ncols),
+            CompressionKind::nminor(nrows, ncols),
+            &offsets,
+            &indices,
+        )?;
+
+        Ok(unsafe { Self::from_parts_unchecked(nrows, ncols, offsets, entries) })
+    }
+
+    /// Consumes self and returns the underlying major offsets and interleaved `(minor_index,
+    /// value)` entries.
+    pub fn disassemble(self) -> (Vec<usize>, Vec<(usize, T)>) {
+        (self.offsets, self.entries)
+    }
+
+    /// The major offsets of each lane.
+    #[must_use]
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// The `(minor_index, value)` pairs of every explicit entry, sorted in major -> minor order.
+    #[must_use]
+    pub fn entries(&self) -> &[(usize, T)] {
+        &self.entries
+    }
+
+    /// Gets a major-axis lane of the data given a major index.
+    ///
+    /// Returns `None` iff the major index does not correspond to a lane in the matrix.
+    pub fn get_lane(&self, major_index: usize) -> Option<InterleavedLaneIter<'_, T>> {
+        if major_index >= self.nmajor() {
+            return None;
+        }
+
+        Some(InterleavedLaneIter {
+            entries: lane_entries(&self.offsets, &self.entries, major_index).iter(),
+        })
+    }
+
+    /// An iterator that iterates across every major lane of the matrix, in order.
+    pub fn iter(&self) -> InterleavedMatrixIter<'_, T> {
+        InterleavedMatrixIter {
+            current_major_index: 0,
+            offsets: &self.offsets,
+            entries: &self.entries,
+        }
+    }
+
+    /// An iterator that iterates through every explicit non-zero triplet `(major_index,
+    /// minor_index, value)` in the matrix, in major -> minor (i.e. sorted) order.
+    pub fn triplet_iter(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        self.iter().enumerate().flat_map(|(major_index, lane)| {
+            lane.map(move |(minor_index, value)| (major_index, minor_index, value))
+        })
+    }
+
+    fn get_entry_major_minor(
+        &self,
+        major_index: usize,
+        minor_index: usize,
+    ) -> Option<SparseEntry<'_, T>> {
+        if major_index >= self.nmajor() || minor_index >= self.nminor() {
+            return None;
+        }
+
+        let lane = lane_entries(&self.offsets, &self.entries, major_index);
+
+        let entry = match lane.binary_search_by(|(index, _)| index.cmp(&minor_index)) {
+            Ok(local_index) => SparseEntry::NonZero(&lane[local_index].1),
+            Err(_) => SparseEntry::Zero,
+        };
+
+        Some(entry)
+    }
+}
+
+impl<T> InterleavedCsMatrix<T, CompressedRowStorage>
+where
+    T: Scalar,
+{
+    /// Gets a value in the sparse matrix from a `(row, column)` index pair.
+    ///
+    /// This function will return `None` if and only if the requested entry is out-of-bounds of the
+    /// underlying matrix.
+    #[inline]
+    pub fn get_entry(&self, row: usize, column: usize) -> Option<SparseEntry<'_, T>> {
+        self.get_entry_major_minor(row, column)
+    }
+}
+
+impl<T> InterleavedCsMatrix<T, CompressedColumnStorage>
+where
+    T: Scalar,
+{
+    /// Gets a value in the sparse matrix from a `(row, column)` index pair.
+    ///
+    /// This function will return `None` if and only if the requested entry is out-of-bounds of the
+    /// underlying matrix.
+    #[inline]
+    pub fn get_entry(&self, row: usize, column: usize) -> Option<SparseEntry<'_, T>> {
+        self.get_entry_major_minor(column, row)
+    }
+}
+
+impl<T, CompressionKind> PartialEq for InterleavedCsMatrix<T, CompressionKind>
+where
+    T: Scalar,
+    CompressionKind: Compression,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.shape == other.shape && self.offsets == other.offsets && self.entries == other.entries
+    }
+}
+
+/// Returns the entries of the lane with the given major index.
+fn lane_entries<'a, T>(
+    offsets: &[usize],
+    entries: &'a [(usize, T)],
+    major_index: usize,
+) -> &'a [(usize, T)] {
+    let offset = offsets[major_index];
+
+    if major_index + 1 < offsets.len() {
+        &entries[offset..offsets[major_index + 1]]
+    } else {
+        &entries[offset..]
+    }
+}
+
+/// An iterator through each of the major lanes of an [`InterleavedCsMatrix`].
+///
+/// This yields an [`InterleavedLaneIter`] for every lane.
+#[derive(Debug, Clone)]
+pub struct InterleavedMatrixIter<'a, T> {
+    current_major_index: usize,
+    offsets: &'a [usize],
+    entries: &'a [(usize, T)],
+}
+
+impl<'a, T> Iterator for InterleavedMatrixIter<'a, T> {
+    type Item = InterleavedLaneIter<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_major_index >= self.offsets.len() {
+            return None;
+        }
+
+        let entries = lane_entries(self.offsets, self.entries, self.current_major_index);
+        self.current_major_index += 1;
+
+        Some(InterleavedLaneIter {
+            entries: entries.iter(),
+        })
+    }
+}
+
+impl<'a, T> ExactSizeIterator for InterleavedMatrixIter<'a, T> {
+    fn len(&self) -> usize {
+        self.offsets.len() - self.current_major_index
+    }
+}
+
+/// An iterator representing a single lane in an [`InterleavedCsMatrix`].
+///
+/// As an iterator yields `(usize, &T)` pairs for every element in the lane, in the same way as
+/// [`CsLaneIter`](crate::cs::CsLaneIter).
+#[derive(Debug, Clone)]
+pub struct InterleavedLaneIter<'a, T> {
+    entries: std::slice::Iter<'a, (usize, T)>,
+}
+
+impl<'a, T> Iterator for InterleavedLaneIter<'a, T> {
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(|(index, value)| (*index, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for InterleavedLaneIter<'a, T> {}
+
+impl<T, CompressionKind> Default for InterleavedCsMatrix<T, CompressionKind>
+where
+    T: Scalar,
+    CompressionKind: Compression,
+{
+    fn default() -> Self {
+        Self {
+            shape: (0, 0),
+            offsets: Vec::new(),
+            entries: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,1360 @@
+//! Implementation of the Matrix Market exchange format.
+
+use crate::{
+    control::{Control, Stage},
+    coo::CooMatrix,
+    cs::{Compression, CsMatrix},
+};
+use nalgebra::{Complex, Scalar};
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    convert::TryFrom,
+    fs,
+    io::{BufWriter, Write},
+    path::Path,
+};
+use thiserror::Error;
+
+/// Errors produced when reading (or writing) Matrix Market files.
+#[derive(Debug, Error)]
+#[error("Matrix Market error - Kind: {error_kind:?}; Message: {message}")]
+pub struct MatrixMarketError {
+    error_kind: MatrixMarketErrorKind,
+    message: String,
+}
+
+/// The different kinds of errors that may occur when reading (or writing) Matrix Market files.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MatrixMarketErrorKind {
+    /// The file could not be read or written.
+    IoError,
+
+    /// The header (i.e. the `%%MatrixMarket` banner) is missing, or describes an unsupported
+    /// combination of format, field and symmetry.
+    InvalidHeader,
+
+    /// A size line or an entry could not be parsed.
+    ParsingError,
+
+    /// The field of the file (e.g. `real`) cannot be represented by the requested scalar type
+    /// (e.g. `i32`).
+    TypeMismatch,
+
+    /// The number of entries in the file does not match the size line.
+    EntryMismatch,
+
+    /// An entry is outside of the shape given by the size line.
+    IndexOutOfBounds,
+
+    /// A file with a symmetric, skew-symmetric or hermitian qualifier describes a matrix that is
+    /// not square.
+    NonSquare,
+
+    /// A file with a symmetric, skew-symmetric or hermitian qualifier contains an entry above
+    /// the diagonal.
+    NotLowerTriangle,
+
+    /// A file with a skew-symmetric qualifier contains an entry on the diagonal.
+    DiagonalError,
+
+    /// The [`Control`] passed to the loader requested a stop before the whole file was read.
+    Cancelled,
+}
+
+impl MatrixMarketError {
+    pub(crate) fn from_kind_and_message(
+        error_kind: MatrixMarketErrorKind,
+        message: String,
+    ) -> Self {
+        Self {
+            error_kind,
+            message,
+        }
+    }
+
+    /// The Matrix Market error kind.
+    #[must_use]
+    pub fn kind(&self) -> &MatrixMarketErrorKind {
+        &self.error_kind
+    }
+
+    /// The underlying error message.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+}
+
+/// A scalar type that can be read from (and written to) Matrix Market files.
+///
+/// Each of the conversions returns `None` if the value cannot be represented by the scalar type,
+/// e.g. a `real` value for an integer type, or a `complex` value for a real type.
+pub trait MatrixMarketScalar: Scalar {
+    /// Converts a value from an `integer` field.
+    fn from_integer(value: i64) -> Option<Self>;
+
+    /// Converts a value from a `real` field.
+    fn from_real(value: f64) -> Option<Self>;
+
+    /// Converts a value from a `complex` field, given its real and imaginary parts.
+    fn from_complex(re: f64, im: f64) -> Option<Self>;
+
+    /// The value of every entry of a `pattern` file.
+    fn from_pattern() -> Self;
+
+    /// The negation of `self`, used to mirror entries of skew-symmetric matrices.
+    fn negated(&self) -> Self;
+
+    /// The complex conjugate of `self`, used to mirror entries of hermitian matrices.
+    fn conjugated(&self) -> Self;
+
+    /// The field that values of this type are written with.
+    fn field() -> MatrixMarketField;
+
+    /// Formats `self` as the value tokens of an entry, i.e. everything after its indices.
+    fn to_tokens(&self) -> String;
+}
+
+macro_rules! impl_matrix_market_scalar_integer {
+    ($($t:ty),*) => {
+        $(
+            impl MatrixMarketScalar for $t {
+                fn from_integer(value: i64) -> Option<Self> {
+                    <$t>::try_from(value).ok()
+                }
+
+                fn from_real(_value: f64) -> Option<Self> {
+                    None
+                }
+
+                fn from_complex(_re: f64, _im: f64) -> Option<Self> {
+                    None
+                }
+
+                fn from_pattern() -> Self {
+                    1
+                }
+
+                fn negated(&self) -> Self {
+                    -*self
+                }
+
+                fn conjugated(&self) -> Self {
+                    *self
+                }
+
+                fn field() -> MatrixMarketField {
+                    MatrixMarketField::Integer
+                }
+
+                fn to_tokens(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_matrix_market_scalar_real {
+    ($($t:ty),*) => {
+        $(
+            impl MatrixMarketScalar for $t {
+                fn from_integer(value: i64) -> Option<Self> {
+                    Some(value as $t)
+                }
+
+                fn from_real(value: f64) -> Option<Self> {
+                    Some(value as $t)
+                }
+
+                fn from_complex(_re: f64, _im: f64) -> Option<Self> {
+                    None
+                }
+
+                fn from_pattern() -> Self {
+                    1.0
+                }
+
+                fn negated(&self) -> Self {
+                    -*self
+                }
+
+                fn conjugated(&self) -> Self {
+                    *self
+                }
+
+                fn field() -> MatrixMarketField {
+                    MatrixMarketField::Real
+                }
+
+                fn to_tokens(&self) -> String {
+                    format!("{:e}", self)
+                }
+            }
+
+            impl MatrixMarketScalar for Complex<$t> {
+                fn from_integer(value: i64) -> Option<Self> {
+                    Some(Complex::new(value as $t, 0.0))
+                }
+
+                fn from_real(value: f64) -> Option<Self> {
+                    Some(Complex::new(value as $t, 0.0))
+                }
+
+                fn from_complex(re: f64, im: f64) -> Option<Self> {
+                    Some(Complex::new(re as $t, im as $t))
+                }
+
+                fn from_pattern() -> Self {
+                    Complex::new(1.0, 0.0)
+                }
+
+                fn negated(&self) -> Self {
+                    -*self
+                }
+
+                fn conjugated(&self) -> Self {
+                    self.conj()
+                }
+
+                fn field() -> MatrixMarketField {
+                    MatrixMarketField::Complex
+                }
+
+                fn to_tokens(&self) -> String {
+                    format!("{:e} {:e}", self.re, self.im)
+                }
+            }
+        )*
+    };
+}
+
+impl_matrix_market_scalar_integer!(i8, i16, i32, i64, isize);
+impl_matrix_market_scalar_real!(f32, f64);
+
+/// The storage format of a Matrix Market file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Format {
+    Coordinate,
+    Array,
+}
+
+/// The type of the values stored in a Matrix Market file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MatrixMarketField {
+    /// Every entry has a single integer value.
+    Integer,
+
+    /// Every entry has a single real value.
+    Real,
+
+    /// Every entry has a real and an imaginary part.
+    Complex,
+
+    /// Entries have no value, only the sparsity pattern is stored.
+    Pattern,
+}
+
+impl MatrixMarketField {
+    /// The name of the field in the header of a Matrix Market file.
+    fn name(self) -> &'static str {
+        match self {
+            MatrixMarketField::Integer => "integer",
+            MatrixMarketField::Real => "real",
+            MatrixMarketField::Complex => "complex",
+            MatrixMarketField::Pattern => "pattern",
+        }
+    }
+}
+
+/// The symmetry of the matrix stored in a Matrix Market file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Symmetry {
+    General,
+    Symmetric,
+    SkewSymmetric,
+    Hermitian,
+}
+
+impl Symmetry {
+    /// The name of the symmetry in the header of a Matrix Market file.
+    fn name(self) -> &'static str {
+        match self {
+            Symmetry::General => "general",
+            Symmetry::Symmetric => "symmetric",
+            Symmetry::SkewSymmetric => "skew-symmetric",
+            Symmetry::Hermitian => "hermitian",
+        }
+    }
+}
+
+/// The banner of a Matrix Market file.
+#[derive(Copy, Clone, Debug)]
+struct Header {
+    format: Format,
+    field: MatrixMarketField,
+    symmetry: Symmetry,
+}
+
+fn error(kind: MatrixMarketErrorKind, message: String) -> MatrixMarketError {
+    MatrixMarketError::from_kind_and_message(kind, message)
+}
+
+fn parse_header(line: &str) -> Result<Header, MatrixMarketError> {
+    let invalid = |message: &str| {
+        error(
+            MatrixMarketErrorKind::InvalidHeader,
+            format!("{} (in header {:?})", message, line),
+        )
+    };
+
+    let tokens = line
+        .split_whitespace()
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>();
+
+    let tokens = match &tokens[..] {
+        [banner, object, format, field, symmetry]
+            if banner == "%%matrixmarket" && object == "matrix" =>
+        {
+            (format.as_str(), field.as_str(), symmetry.as_str())
+        }
+        _ => {
+            return Err(invalid(
+                "Expected `%%MatrixMarket matrix <format> <field> <symmetry>`",
+            ))
+        }
+    };
+
+    let format = match tokens.0 {
+        "coordinate" => Format::Coordinate,
+        "array" => Format::Array,
+        _ => return Err(invalid("Unsupported format")),
+    };
+
+    let field = match tokens.1 {
+        "integer" => MatrixMarketField::Integer,
+        "real" | "double" => MatrixMarketField::Real,
+        "complex" => MatrixMarketField::Complex,
+        "pattern" => MatrixMarketField::Pattern,
+        _ => return Err(invalid("Unsupported field")),
+    };
+
+    let symmetry = match tokens.2 {
+        "general" => Symmetry::General,
+        "symmetric" => Symmetry::Symmetric,
+        "skew-symmetric" => Symmetry::SkewSymmetric,
+        "hermitian" => Symmetry::Hermitian,
+        _ => return Err(invalid("Unsupported symmetry")),
+    };
+
+    if format == Format::Array && field == MatrixMarketField::Pattern {
+        return Err(invalid("The array format cannot have a pattern field"));
+    }
+
+    if symmetry == Symmetry::Hermitian && field != MatrixMarketField::Complex {
+        return Err(invalid("Only complex matrices can be hermitian"));
+    }
+
+    Ok(Header {
+        format,
+        field,
+        symmetry,
+    })
+}
+
+fn parse_token<T: std::str::FromStr>(
+    token: Option<&str>,
+    what: &str,
+    line: usize,
+) -> Result<T, MatrixMarketError> {
+    let token = token.ok_or_else(|| {
+        error(
+            MatrixMarketErrorKind::ParsingError,
+            format!("Missing {} on line {}", what, line),
+        )
+    })?;
+
+    token.parse().map_err(|_| {
+        error(
+            MatrixMarketErrorKind::ParsingError,
+            format!("Could not parse {} {:?} on line {}", what, token, line),
+        )
+    })
+}
+
+/// Parses the value of an entry from `tokens`, according to the field of the file.
+fn parse_value<'a, T, I>(
+    tokens: &mut I,
+    field: MatrixMarketField,
+    line: usize,
+) -> Result<T, MatrixMarketError>
+where
+    T: MatrixMarketScalar,
+    I: Iterator<Item = &'a str>,
+{
+    let value = match field {
+        MatrixMarketField::Integer => {
+            T::from_integer(parse_token(tokens.next(), "integer value", line)?)
+        }
+        MatrixMarketField::Real => T::from_real(parse_token(tokens.next(), "real value", line)?),
+        MatrixMarketField::Complex => T::from_complex(
+            parse_token(tokens.next(), "real part", line)?,
+            parse_token(tokens.next(), "imaginary part", line)?,
+        ),
+        MatrixMarketField::Pattern => Some(T::from_pattern()),
+    };
+
+    value.ok_or_else(|| {
+        error(
+            MatrixMarketErrorKind::TypeMismatch,
+            format!(
+                "The value on line {} cannot be represented by the scalar type {}",
+                line,
+                std::any::type_name::<T>()
+            ),
+        )
+    })
+}
+
+/// Pushes an entry at a 0-based `(i, j)` into `coo`, along with its mirrored entry if the matrix
+/// has a symmetry.
+fn push_entry<T>(
+    coo: &mut CooMatrix<T>,
+    symmetry: Symmetry,
+    i: usize,
+    j: usize,
+    value: T,
+    line: usize,
+) -> Result<(), MatrixMarketError>
+where
+    T: MatrixMarketScalar,
+{
+    if i >= coo.nrows() || j >= coo.ncols() {
+        return Err(error(
+            MatrixMarketErrorKind::IndexOutOfBounds,
+            format!(
+                "The entry ({}, {}) on line {} is out of bounds for a {}×{} matrix",
+                i + 1,
+                j + 1,
+                line,
+                coo.nrows(),
+                coo.ncols()
+            ),
+        ));
+    }
+
+    if symmetry != Symmetry::General && i < j {
+        return Err(error(
+            MatrixMarketErrorKind::NotLowerTriangle,
+            format!(
+                "The entry ({}, {}) on line {} is above the diagonal",
+                i + 1,
+                j + 1,
+                line
+            ),
+        ));
+    }
+
+    if symmetry == Symmetry::SkewSymmetric && i == j {
+        return Err(error(
+            MatrixMarketErrorKind::DiagonalError,
+            format!(
+                "The entry ({}, {}) on line {} is on the diagonal of a skew-symmetric matrix",
+                i + 1,
+                j + 1,
+                line
+            ),
+        ));
+    }
+
+    if i != j {
+        match symmetry {
+            Symmetry::General => {}
+            Symmetry::Symmetric => coo.push(j, i, value.clone()),
+            Symmetry::SkewSymmetric => coo.push(j, i, value.negated()),
+            Symmetry::Hermitian => coo.push(j, i, value.conjugated()),
+        }
+    }
+
+    coo.push(i, j, value);
+
+    Ok(())
+}
+
+/// Loads a sparse matrix from a Matrix Market file.
+///
+/// Both the `coordinate` and the `array` formats are supported, with `integer`, `real`,
+/// `complex` and `pattern` fields, and `general`, `symmetric`, `skew-symmetric` and `hermitian`
+/// symmetries. The requested scalar type must be able to represent the field of the file (see
+/// [`MatrixMarketScalar`]), and the entries of a `pattern` file are all set to one.
+///
+/// Files with a symmetry qualifier only store the lower triangle of the matrix. The returned
+/// matrix contains both triangles, where every mirrored entry is transposed, negated or conjugated
+/// respectively. Duplicate entries in `coordinate` files are kept as duplicates in the returned
+/// [`CooMatrix`], and summed when it is converted to a compressed format. Only the non-zero entries
+/// of `array` files are kept.
+///
+/// # Errors
+///
+/// Returns a [`MatrixMarketError`] if the file cannot be read, or does not describe a valid matrix
+/// in the Matrix Market format. See [`MatrixMarketErrorKind`] for the possible causes.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra_sparse::io::load_matrix_market_from_str;
+/// # use nalgebra::DMatrix;
+///
+/// let input = "%%MatrixMarket matrix coordinate real symmetric
+/// % A comment
+/// 3 3 3
+/// 1 1 4.0
+/// 2 1 -1.0
+/// 3 3 2.5
+/// ";
+///
+/// let coo = load_matrix_market_from_str::<f64>(input).unwrap();
+///
+/// let expected = DMatrix::from_row_slice(3, 3, &[4.0, -1.0, 0.0, -1.0, 0.0, 0.0, 0.0, 0.0, 2.5]);
+/// assert_eq!(DMatrix::from(&coo), expected);
+/// ```
+pub fn load_matrix_market<T, P>(path: P) -> Result<CooMatrix<T>, MatrixMarketError>
+where
+    T: MatrixMarketScalar,
+    P: AsRef<Path>,
+{
+    load_matrix_market_with_control(path, &Control::default())
+}
+
+/// Loads a sparse matrix from a Matrix Market file, stopping early if `control` requests it.
+///
+/// `control` is checked, and progress is reported, once per entry of the file, i.e. once per line
+/// of a `coordinate` file and once per value of an `array` file. See [`load_matrix_market`] for
+/// details on the supported files.
+///
+/// # Errors
+///
+/// Returns a [`MatrixMarketError`] with kind [`MatrixMarketErrorKind::Cancelled`] if `control`
+/// requested a stop before the whole file was read, in addition to the errors of
+/// [`load_matrix_market`].
+pub fn load_matrix_market_with_control<T, P>(
+    path: P,
+    control: &Control<'_>,
+) -> Result<CooMatrix<T>, MatrixMarketError>
+where
+    T: MatrixMarketScalar,
+    P: AsRef<Path>,
+{
+    let input = fs::read_to_string(path.as_ref()).map_err(|err| {
+        error(
+            MatrixMarketErrorKind::IoError,
+            format!("Could not read {}: {}", path.as_ref().display(), err),
+        )
+    })?;
+
+    load_matrix_market_from_str_with_control(&input, control)
+}
+
+/// Parses a Matrix Market file from a string.
+///
+/// See [`load_matrix_market`] for details.
+///
+/// # Errors
+///
+/// See [`load_matrix_market`].
+pub fn load_matrix_market_from_str<T>(input: &str) -> Result<CooMatrix<T>, MatrixMarketError>
+where
+    T: MatrixMarketScalar,
+{
+    load_matrix_market_from_str_with_control(input, &Control::default())
+}
+
+/// Parses a Matrix Market file from a string, stopping early if `control` requests it.
+///
+/// See [`load_matrix_market_with_control`] for details.
+///
+/// # Errors
+///
+/// See [`load_matrix_market_with_control`].
+pub fn load_matrix_market_from_str_with_control<T>(
+    input: &str,
+    control: &Control<'_>,
+) -> Result<CooMatrix<T>, MatrixMarketError>
+where
+    T: MatrixMarketScalar,
+{
+    let checkpoint = |completed, total| {
+        if control.checkpoint(Stage::Reading, completed, total) {
+            Err(error(
+                MatrixMarketErrorKind::Cancelled,
+                String::from("The file was not read completely, because a stop was requested"),
+            ))
+        } else {
+            Ok(())
+        }
+    };
+
+    let mut lines = input.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    let header = match lines.next() {
+        Some((_, line)) => parse_header(line)?,
+        None => {
+            return Err(error(
+                MatrixMarketErrorKind::InvalidHeader,
+                String::from("The input is empty"),
+            ))
+        }
+    };
+
+    let mut lines = lines.filter(|(_, line)| {
+        let line = line.trim_start();
+        !line.is_empty() && !line.starts_with('%')
+    });
+
+    let (size_line, size) = lines.next().ok_or_else(|| {
+        error(
+            MatrixMarketErrorKind::ParsingError,
+            String::from("Missing size line"),
+        )
+    })?;
+
+    let mut tokens = size.split_whitespace();
+    let nrows = parse_token(tokens.next(), "number of rows", size_line)?;
+    let ncols = parse_token(tokens.next(), "number of columns", size_line)?;
+
+    if header.symmetry != Symmetry::General && nrows != ncols {
+        return Err(error(
+            MatrixMarketErrorKind::NonSquare,
+            format!(
+                "A {:?} matrix must be square, but has shape {}×{}",
+                header.symmetry, nrows, ncols
+            ),
+        ));
+    }
+
+    let _span = span!(
+        "load_matrix_market",
+        nrows = nrows,
+        ncols = ncols,
+        nnz = tracing::field::Empty
+    );
+
+    let mut coo = CooMatrix::new(nrows, ncols);
+
+    match header.format {
+        Format::Coordinate => {
+            let nnz: usize = parse_token(tokens.next(), "number of entries", size_line)?;
+            let mut count = 0;
+
+            for (line, entry) in lines {
+                checkpoint(count.min(nnz), nnz)?;
+
+                let mut tokens = entry.split_whitespace();
+                let i: usize = parse_token(tokens.next(), "row index", line)?;
+                let j: usize = parse_token(tokens.next(), "column index", line)?;
+
+                if i == 0 || j == 0 {
+                    return Err(error(
+                        MatrixMarketErrorKind::IndexOutOfBounds,
+                        format!("Indices on line {} must be 1-based", line),
+                    ));
+                }
+
+                let value = parse_value(&mut tokens, header.field, line)?;
+
+                push_entry(&mut coo, header.symmetry, i - 1, j - 1, value, line)?;
+                count += 1;
+            }
+
+            if count != nnz {
+                return Err(error(
+                    MatrixMarketErrorKind::EntryMismatch,
+                    format!("Expected {} entries, but found {}", nnz, count),
+                ));
+            }
+
+            control.report(Stage::Reading, nnz, nnz);
+        }
+        Format::Array => {
+            // Array files list the (lower triangle of the) matrix in column-major order.
+            let skip_diagonal = usize::from(header.symmetry == Symmetry::SkewSymmetric);
+            let total = if header.symmetry == Symmetry::General {
+                nrows * ncols
+            } else {
+                nrows * (nrows + 1) / 2 - skip_diagonal * nrows
+            };
+            let positions = (0..ncols).flat_map(|j| {
+                let first = if header.symmetry == Symmetry::General {
+                    0
+                } else {
+                    j + skip_diagonal
+                };
+
+                (first..nrows).map(move |i| (i, j))
+            });
+
+            let mut values = lines
+                .flat_map(|(line, entry)| entry.split_whitespace().map(move |token| (line, token)));
+
+            for (k, (i, j)) in positions.enumerate() {
+                checkpoint(k, total)?;
+
+                let (line, first) = values.next().ok_or_else(|| {
+                    error(
+                        MatrixMarketErrorKind::EntryMismatch,
+                        format!("Missing the entry ({}, {})", i + 1, j + 1),
+                    )
+                })?;
+
+                let value: T = if header.field == MatrixMarketField::Complex {
+                    let second = values.next().map(|(_, token)| token);
+                    parse_value(
+                        &mut std::iter::once(first).chain(second),
+                        header.field,
+                        line,
+                    )?
+                } else {
+                    parse_value(&mut std::iter::once(first), header.field, line)?
+                };
+
+                // Array files are dense, so only their non-zero entries are kept.
+                if !matches!(T::from_integer(0), Some(zero) if value == zero) {
+                    push_entry(&mut coo, header.symmetry, i, j, value, line)?;
+                }
+            }
+
+            if let Some((line, _)) = values.next() {
+                return Err(error(
+                    MatrixMarketErrorKind::EntryMismatch,
+                    format!("Found more entries than expected on line {}", line),
+                ));
+            }
+
+            control.report(Stage::Reading, total, total);
+        }
+    }
+
+    record!(_span, nnz = coo.nnz());
+
+    Ok(coo)
+}
+
+/// A sparse matrix type that can be written to a Matrix Market file.
+///
+/// This is implemented for [`CooMatrix`] and for every [`CsMatrix`], i.e. for CSR and CSC
+/// matrices.
+pub trait MatrixMarketExport<T: MatrixMarketScalar> {
+    /// The shape of the matrix, as (nrows, ncols).
+    fn export_shape(&self) -> (usize, usize);
+
+    /// An iterator over the `(row, column, value)` triplets of every explicit entry.
+    fn export_triplets(&self) -> Box<dyn Iterator<Item = (usize, usize, &T)> + '_>;
+}
+
+impl<T> MatrixMarketExport<T> for CooMatrix<T>
+where
+    T: MatrixMarketScalar,
+{
+    fn export_shape(&self) -> (usize, usize) {
+        (self.nrows(), self.ncols())
+    }
+
+    fn export_triplets(&self) -> Box<dyn Iterator<Item = (usize, usize, &T)> + '_> {
+        Box::new(self.triplet_iter())
+    }
+}
+
+impl<T, MO, MI, D, C> MatrixMarketExport<T> for CsMatrix<T, MO, MI, D, C>
+where
+    T: MatrixMarketScalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    fn export_shape(&self) -> (usize, usize) {
+        self.shape()
+    }
+
+    fn export_triplets(&self) -> Box<dyn Iterator<Item = (usize, usize, &T)> + '_> {
+        Box::new(
+            self.triplet_iter().map(|(major, minor, value)| {
+                (C::nmajor(major, minor), C::nminor(major, minor), value)
+            }),
+        )
+    }
+}
+
+/// Options for writing Matrix Market files.
+///
+/// The default options write every entry with the field of the scalar type (see
+/// [`MatrixMarketScalar::field`]) and a `general` symmetry.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MatrixMarketWriteOptions {
+    /// Whether to only write the sparsity pattern of the matrix, with a `pattern` field.
+    pub pattern: bool,
+
+    /// Whether to check if the matrix is symmetric, skew-symmetric or hermitian (in that order of
+    /// preference), and if so only write its lower triangle along with the matching qualifier.
+    ///
+    /// For `pattern` files, only a symmetric pattern is detected. Detecting the symmetry needs
+    /// a lookup of the mirrored entry of every explicit entry. Matrices with duplicate entries are
+    /// always written as `general`.
+    pub detect_symmetry: bool,
+}
+
+/// Detects the strongest symmetry of the matrix with the given entries.
+fn detect_symmetry<T>(
+    shape: (usize, usize),
+    triplets: &[(usize, usize, &T)],
+    field: MatrixMarketField,
+) -> Symmetry
+where
+    T: MatrixMarketScalar,
+{
+    if shape.0 != shape.1 {
+        return Symmetry::General;
+    }
+
+    let mut entries = HashMap::with_capacity(triplets.len());
+
+    for &(i, j, value) in triplets {
+        if entries.insert((i, j), value).is_some() {
+            return Symmetry::General;
+        }
+    }
+
+    let holds = |mirror: &dyn Fn(&T) -> T, diagonal: &dyn Fn(&T) -> bool| {
+        triplets.iter().all(|&(i, j, value)| {
+            if i == j {
+                diagonal(value)
+            } else {
+                matches!(entries.get(&(j, i)), Some(&other) if field == MatrixMarketField::Pattern || *other == mirror(value))
+            }
+        })
+    };
+
+    if holds(&T::clone, &|_| true) {
+        Symmetry::Symmetric
+    } else if field == MatrixMarketField::Pattern {
+        Symmetry::General
+    } else if holds(&T::negated, &|_| false) {
+        Symmetry::SkewSymmetric
+    } else if field == MatrixMarketField::Complex
+        && holds(&T::conjugated, &|value| *value == value.conjugated())
+    {
+        Symmetry::Hermitian
+    } else {
+        Symmetry::General
+    }
+}
+
+/// Writes a sparse matrix to `writer` in the Matrix Market `coordinate` format.
+///
+/// See [`save_matrix_market`] for details.
+///
+/// # Errors
+///
+/// Returns a [`MatrixMarketError`] with kind [`MatrixMarketErrorKind::IoError`] if writing to
+/// `writer` fails.
+pub fn write_matrix_market<T, M, W>(
+    mut writer: W,
+    matrix: &M,
+    options: MatrixMarketWriteOptions,
+) -> Result<(), MatrixMarketError>
+where
+    T: MatrixMarketScalar,
+    M: MatrixMarketExport<T> + ?Sized,
+    W: Write,
+{
+    let shape = matrix.export_shape();
+    let field = if options.pattern {
+        MatrixMarketField::Pattern
+    } else {
+        T::field()
+    };
+
+    let _span = span!(
+        "write_matrix_market",
+        nrows = shape.0,
+        ncols = shape.1,
+        nnz = tracing::field::Empty
+    );
+
+    let triplets = matrix.export_triplets().collect::<Vec<_>>();
+
+    let symmetry = if options.detect_symmetry {
+        detect_symmetry(shape, &triplets, field)
+    } else {
+        Symmetry::General
+    };
+
+    let written = triplets
+        .iter()
+        .filter(|(i, j, _)| symmetry == Symmetry::General || i >= j)
+        .collect::<Vec<_>>();
+
+    record!(_span, nnz = written.len());
+
+    let io_error = |err: std::io::Error| {
+        error(
+            MatrixMarketErrorKind::IoError,
+            format!("Could not write Matrix Market data: {}", err),
+        )
+    };
+
+    writeln!(
+        writer,
+        "%%MatrixMarket matrix coordinate {} {}",
+        field.name(),
+        symmetry.name()
+    )
+    .map_err(io_error)?;
+    writeln!(writer, "{} {} {}", shape.0, shape.1, written.len()).map_err(io_error)?;
+
+    for (i, j, value) in written {
+        if field == MatrixMarketField::Pattern {
+            writeln!(writer, "{} {}", i + 1, j + 1)
+        } else {
+            writeln!(writer, "{} {} {}", i + 1, j + 1, value.to_tokens())
+        }
+        .map_err(io_error)?;
+    }
+
+    writer.flush().map_err(io_error)
+}
+
+/// Writes a sparse matrix to a string in the Matrix Market `coordinate` format.
+///
+/// See [`save_matrix_market`] for details.
+pub fn save_matrix_market_to_string<T, M>(matrix: &M, options: MatrixMarketWriteOptions) -> String
+where
+    T: MatrixMarketScalar,
+    M: MatrixMarketExport<T> + ?Sized,
+{
+    let mut buffer = Vec::new();
+
+    // Writing to a `Vec` cannot fail, and every token is valid UTF-8.
+    write_matrix_market(&mut buffer, matrix, options).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+/// Saves a sparse matrix to a Matrix Market file, in the `coordinate` format.
+///
+/// Every explicit entry of the matrix is written, including explicit zeros and duplicate entries
+/// of a [`CooMatrix`]. Values are written with the field of the scalar type, or as a `pattern`
+/// if requested. Floating-point values are written in scientific notation, with enough digits to
+/// read them back exactly. See [`MatrixMarketWriteOptions`] for how symmetric matrices can be
+/// detected and written more compactly.
+///
+/// Files written by this function can be read back with [`load_matrix_market`], as well as by
+/// e.g. `scipy.io.mmread` and MATLAB's `mmread`.
+///
+/// # Errors
+///
+/// Returns a [`MatrixMarketError`] with kind [`MatrixMarketErrorKind::IoError`] if the file cannot
+/// be written.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra_sparse::{
+///     cs::CsrMatrix,
+///     io::{save_matrix_market_to_string, MatrixMarketWriteOptions},
+/// };
+///
+/// let csr = CsrMatrix::try_from_parts(2, 2, vec![0, 2], vec![0, 1, 0], vec![4, -1, -1]).unwrap();
+/// let options = MatrixMarketWriteOptions {
+///     detect_symmetry: true,
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(
+///     save_matrix_market_to_string(&csr, options),
+///     "%%MatrixMarket matrix coordinate integer symmetric\n2 2 2\n1 1 4\n2 1 -1\n"
+/// );
+/// ```
+pub fn save_matrix_market<T, M, P>(
+    path: P,
+    matrix: &M,
+    options: MatrixMarketWriteOptions,
+) -> Result<(), MatrixMarketError>
+where
+    T: MatrixMarketScalar,
+    M: MatrixMarketExport<T> + ?Sized,
+    P: AsRef<Path>,
+{
+    let file = fs::File::create(path.as_ref()).map_err(|err| {
+        error(
+            MatrixMarketErrorKind::IoError,
+            format!("Could not create {}: {}", path.as_ref().display(), err),
+        )
+    })?;
+
+    write_matrix_market(BufWriter::new(file), matrix, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        control::Progress,
+        cs::{CscMatrix, CsrMatrix},
+        proptest::*,
+    };
+    use nalgebra::DMatrix;
+    use proptest::prelude::*;
+    use std::cell::RefCell;
+
+    fn kind_of<T: MatrixMarketScalar>(input: &str) -> MatrixMarketErrorKind {
+        *load_matrix_market_from_str::<T>(input).unwrap_err().kind()
+    }
+
+    #[test]
+    fn load_coordinate_general() {
+        let input = "%%MatrixMarket matrix coordinate integer general
+%
+2 3 3
+1 3 -2
+2 1 5
+
+2 3 1
+";
+        let coo = load_matrix_market_from_str::<i32>(input).unwrap();
+
+        assert_eq!(
+            DMatrix::from(&coo),
+            DMatrix::from_row_slice(2, 3, &[0, 0, -2, 5, 0, 1])
+        );
+    }
+
+    #[test]
+    fn load_coordinate_pattern_and_skew_symmetric() {
+        let pattern = "%%MatrixMarket matrix coordinate pattern symmetric
+2 2 2
+1 1
+2 1
+";
+        let skew = "%%MATRIXMARKET Matrix Coordinate Real Skew-Symmetric
+2 2 1
+2 1 1.5
+";
+
+        assert_eq!(
+            DMatrix::from(&load_matrix_market_from_str::<f64>(pattern).unwrap()),
+            DMatrix::from_row_slice(2, 2, &[1.0, 1.0, 1.0, 0.0])
+        );
+        assert_eq!(
+            DMatrix::from(&load_matrix_market_from_str::<f64>(skew).unwrap()),
+            DMatrix::from_row_slice(2, 2, &[0.0, -1.5, 1.5, 0.0])
+        );
+    }
+
+    #[test]
+    fn load_coordinate_complex_hermitian() {
+        let input = "%%MatrixMarket matrix coordinate complex hermitian
+2 2 2
+1 1 2.0 0.0
+2 1 1.0 -3.0
+";
+        let coo = load_matrix_market_from_str::<Complex<f64>>(input).unwrap();
+
+        assert_eq!(
+            DMatrix::from(&coo),
+            DMatrix::from_row_slice(
+                2,
+                2,
+                &[
+                    Complex::new(2.0, 0.0),
+                    Complex::new(1.0, 3.0),
+                    Complex::new(1.0, -3.0),
+                    Complex::new(0.0, 0.0),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn load_array_general_and_symmetric() {
+        // Array files are column-major.
+        let general = "%%MatrixMarket matrix array real general
+2 2
+1.0
+0.0
+3.0
+4.0
+";
+        let symmetric = "%%MatrixMarket matrix array integer symmetric
+3 3
+1 2 0
+4 5
+6
+";
+        let general = load_matrix_market_from_str::<f64>(general).unwrap();
+
+        assert_eq!(general.nnz(), 3);
+        assert_eq!(
+            DMatrix::from(&general),
+            DMatrix::from_row_slice(2, 2, &[1.0, 3.0, 0.0, 4.0])
+        );
+        assert_eq!(
+            DMatrix::from(&load_matrix_market_from_str::<i64>(symmetric).unwrap()),
+            DMatrix::from_row_slice(3, 3, &[1, 2, 0, 2, 4, 5, 0, 5, 6])
+        );
+    }
+
+    #[test]
+    fn load_array_skew_symmetric_skips_diagonal() {
+        let input = "%%MatrixMarket matrix array real skew-symmetric
+3 3
+1.0 2.0
+3.0
+";
+        assert_eq!(
+            DMatrix::from(&load_matrix_market_from_str::<f32>(input).unwrap()),
+            DMatrix::from_row_slice(3, 3, &[0.0, -1.0, -2.0, 1.0, 0.0, -3.0, 2.0, 3.0, 0.0])
+        );
+    }
+
+    #[test]
+    fn load_with_control_reports_progress_for_every_entry() {
+        let coordinate = "%%MatrixMarket matrix coordinate integer general
+2 3 3
+1 3 -2
+2 1 5
+2 3 1
+";
+        let skew = "%%MatrixMarket matrix array integer skew-symmetric
+3 3
+1 2
+3
+";
+
+        for (input, total) in [(coordinate, 3), (skew, 3)] {
+            let reports = RefCell::new(Vec::new());
+            let progress = |progress: Progress| {
+                assert_eq!((progress.stage, progress.total), (Stage::Reading, total));
+                reports.borrow_mut().push(progress.completed);
+            };
+            let control = Control::new().with_progress(&progress);
+
+            let coo = load_matrix_market_from_str_with_control::<i32>(input, &control).unwrap();
+
+            assert_eq!(
+                DMatrix::from(&coo),
+                DMatrix::from(&load_matrix_market_from_str::<i32>(input).unwrap())
+            );
+            assert_eq!(reports.into_inner(), vec![0, 1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn load_with_cancelled_control_fails_with_cancelled() {
+        let input = "%%MatrixMarket matrix coordinate real general
+1 1 1
+1 1 2.0
+";
+        let should_stop = || true;
+        let control = Control::new().with_should_stop(&should_stop);
+
+        let error = load_matrix_market_from_str_with_control::<f64>(input, &control).unwrap_err();
+
+        assert_eq!(*error.kind(), MatrixMarketErrorKind::Cancelled);
+    }
+
+    #[test]
+    fn load_matrix_market_reports_errors() {
+        use MatrixMarketErrorKind::*;
+
+        let header = "%%MatrixMarket matrix coordinate real general\n";
+
+        assert_eq!(kind_of::<f64>(""), InvalidHeader);
+        assert_eq!(
+            kind_of::<f64>("%%MatrixMarket matrix coordinate real\n1 1 0\n"),
+            InvalidHeader
+        );
+        assert_eq!(
+            kind_of::<f64>("%%MatrixMarket matrix array pattern general\n1 1\n"),
+            InvalidHeader
+        );
+        assert_eq!(
+            kind_of::<f64>("%%MatrixMarket matrix coordinate real hermitian\n1 1 0\n"),
+            InvalidHeader
+        );
+        assert_eq!(kind_of::<f64>(header), ParsingError);
+        assert_eq!(
+            kind_of::<f64>(&format!("{}2 2 1\n1 x 1.0\n", header)),
+            ParsingError
+        );
+        assert_eq!(
+            kind_of::<i32>(&format!("{}2 2 1\n1 1 1.0\n", header)),
+            TypeMismatch
+        );
+        assert_eq!(
+            kind_of::<f64>(&format!("{}2 2 2\n1 1 1.0\n", header)),
+            EntryMismatch
+        );
+        assert_eq!(
+            kind_of::<f64>(&format!("{}2 2 1\n3 1 1.0\n", header)),
+            IndexOutOfBounds
+        );
+        assert_eq!(
+            kind_of::<f64>(&format!("{}2 2 1\n0 1 1.0\n", header)),
+            IndexOutOfBounds
+        );
+        assert_eq!(
+            kind_of::<f64>("%%MatrixMarket matrix coordinate real symmetric\n2 3 0\n"),
+            NonSquare
+        );
+        assert_eq!(
+            kind_of::<f64>("%%MatrixMarket matrix coordinate real symmetric\n2 2 1\n1 2 1.0\n"),
+            NotLowerTriangle
+        );
+        assert_eq!(
+            kind_of::<f64>(
+                "%%MatrixMarket matrix coordinate real skew-symmetric\n2 2 1\n1 1 1.0\n"
+            ),
+            DiagonalError
+        );
+        assert_eq!(
+            kind_of::<f64>("%%MatrixMarket matrix array real general\n1 2\n1.0\n"),
+            EntryMismatch
+        );
+        assert_eq!(
+            kind_of::<f64>("%%MatrixMarket matrix array real general\n1 1\n1.0 2.0\n"),
+            EntryMismatch
+        );
+        assert_eq!(
+            *load_matrix_market::<f64, _>("/nonexistent/matrix.mtx")
+                .unwrap_err()
+                .kind(),
+            IoError
+        );
+    }
+
+    fn round_trip<T, M>(matrix: &M, options: MatrixMarketWriteOptions) -> CooMatrix<T>
+    where
+        T: MatrixMarketScalar,
+        M: MatrixMarketExport<T>,
+    {
+        load_matrix_market_from_str(&save_matrix_market_to_string(matrix, options)).unwrap()
+    }
+
+    #[test]
+    fn save_detects_symmetries() {
+        let detect = MatrixMarketWriteOptions {
+            detect_symmetry: true,
+            ..Default::default()
+        };
+
+        let symmetric = CsrMatrix::from(&DMatrix::from_row_slice(2, 2, &[1.5, 2.0, 2.0, 0.0]));
+        let skew = CscMatrix::from(&DMatrix::from_row_slice(2, 2, &[0, 3, -3, 0]));
+        let general = CsrMatrix::from(&DMatrix::from_row_slice(2, 2, &[1, 3, -3, 0]));
+        let hermitian = CsrMatrix::from(&DMatrix::from_row_slice(
+            2,
+            2,
+            &[
+                Complex::new(1.0, 0.0),
+                Complex::new(2.0, 1.0),
+                Complex::new(2.0, -1.0),
+                Complex::new(0.0, 0.0),
+            ],
+        ));
+
+        assert_eq!(
+            save_matrix_market_to_string(&symmetric, detect),
+            "%%MatrixMarket matrix coordinate real symmetric\n2 2 2\n1 1 1.5e0\n2 1 2e0\n"
+        );
+        assert_eq!(
+            save_matrix_market_to_string(&skew, detect),
+            "%%MatrixMarket matrix coordinate integer skew-symmetric\n2 2 1\n2 1 -3\n"
+        );
+        assert_eq!(
+            save_matrix_market_to_string(&general, detect),
+            "%%MatrixMarket matrix coordinate integer general\n2 2 3\n1 1 1\n1 2 3\n2 1 -3\n"
+        );
+        assert_eq!(
+            save_matrix_market_to_string(&hermitian, detect),
+            "%%MatrixMarket matrix coordinate complex hermitian\n2 2 2\n1 1 1e0 0e0\n2 1 2e0 -1e0\n"
+        );
+
+        for matrix in [&symmetric] {
+            assert_eq!(
+                DMatrix::from(&round_trip(matrix, detect)),
+                DMatrix::from(matrix)
+            );
+        }
+        assert_eq!(
+            DMatrix::from(&round_trip(&skew, detect)),
+            DMatrix::from(&skew)
+        );
+        assert_eq!(
+            DMatrix::from(&round_trip(&hermitian, detect)),
+            DMatrix::from(&hermitian)
+        );
+    }
+
+    #[test]
+    fn save_pattern_and_duplicates() {
+        let mut coo = CooMatrix::new(2, 2);
+        coo.push(0, 1, 2.0);
+        coo.push(1, 0, 5.0);
+
+        let pattern = MatrixMarketWriteOptions {
+            pattern: true,
+            detect_symmetry: true,
+        };
+
+        assert_eq!(
+            save_matrix_market_to_string(&coo, pattern),
+            "%%MatrixMarket matrix coordinate pattern symmetric\n2 2 1\n2 1\n"
+        );
+
+        // Duplicates are written as-is, and prevent detecting a symmetry.
+        coo.push(1, 0, -3.0);
+        coo.push(0, 1, 0.0);
+
+        let written = save_matrix_market_to_string(&coo, pattern);
+        assert!(written.starts_with("%%MatrixMarket matrix coordinate pattern general\n2 2 4\n"));
+
+        let read = round_trip(&coo, MatrixMarketWriteOptions::default());
+        assert_eq!(DMatrix::from(&read), DMatrix::from(&coo));
+    }
+
+    #[test]
+    fn save_matrix_market_reports_io_errors() {
+        let err = save_matrix_market(
+            "/nonexistent/matrix.mtx",
+            &CsrMatrix::<f64>::identity(2),
+            MatrixMarketWriteOptions::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(*err.kind(), MatrixMarketErrorKind::IoError);
+    }
+
+    proptest! {
+        #[test]
+        fn save_then_load_is_reflective(csr in csr_strategy(), detect_symmetry in any::<bool>()) {
+            let options = MatrixMarketWriteOptions { pattern: false, detect_symmetry };
+            let read = round_trip(&csr, options);
+
+            prop_assert_eq!(DMatrix::from(&read), DMatrix::from(&csr));
+            let read = CsrMatrix::from(read);
+            prop_assert_eq!(read.cs_data(), csr.cs_data());
+        }
+
+        #[test]
+        fn save_then_load_floats_is_exact(
+            csc in csc(-1e10..1e10, PROPTEST_MATRIX_DIM, PROPTEST_MATRIX_DIM, PROPTEST_MAX_NNZ)
+        ) {
+            let read = round_trip(&csc, MatrixMarketWriteOptions::default());
+
+            let read = CscMatrix::from(read);
+            prop_assert_eq!(read.cs_data(), csc.cs_data());
+        }
+    }
+}
@@ -0,0 +1,14 @@
+//! Reading and writing sparse matrices in external file formats.
+//!
+//! Currently only the [Matrix Market](https://math.nist.gov/MatrixMarket/formats.html) exchange
+//! format is supported. It is the de facto standard format for exchanging sparse matrices, and is
+//! used e.g. by the [SuiteSparse Matrix Collection](https://sparse.tamu.edu/), SciPy and MATLAB.
+
+mod matrix_market;
+
+pub use self::matrix_market::{
+    load_matrix_market, load_matrix_market_from_str, load_matrix_market_from_str_with_control,
+    load_matrix_market_with_control, save_matrix_market, save_matrix_market_to_string,
+    write_matrix_market, MatrixMarketError, MatrixMarketErrorKind, MatrixMarketExport,
+    MatrixMarketField, MatrixMarketScalar, MatrixMarketWriteOptions,
+};
@@ -0,0 +1,250 @@
+use nalgebra::{DMatrix, DVector, RealField};
+use std::collections::VecDeque;
+
+/// Anderson acceleration (also known as Anderson mixing) of a fixed-point iteration
+/// `xₖ₊₁ = g(xₖ)`.
+///
+/// Rather than taking `g(xₖ)` as the next iterate, Anderson acceleration combines the last
+/// `depth + 1` evaluations of `g` so that the combined fixed-point residual `g(x) - x` is as small
+/// as possible (in the least squares sense). This often turns a slowly converging linear
+/// iteration (e.g. Jacobi or Gauss-Seidel sweeps with a sparse matrix) into a rapidly converging
+/// one, at the cost of storing `2 depth` vectors and solving a small dense least squares problem
+/// per iteration.
+///
+/// The accelerator only keeps the window of past residuals and evaluations; the caller owns the
+/// iteration, and hands every iterate and its image under `g` to
+/// [`AndersonAcceleration::next_iterate`].
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{cs::CsrMatrix, iterative::AndersonAcceleration};
+///
+/// // Jacobi iteration x ← D⁻¹ (b - (A - D) x) for a diagonally dominant system A x = b
+/// let a = CsrMatrix::from(&DMatrix::from_fn(20, 20, |i, j| match (i, j) {
+///     _ if i == j => 3.0,
+///     _ if i == j + 1 || j == i + 1 => -1.4,
+///     _ => 0.0,
+/// }));
+/// let b = DVector::from_element(20, 1.0);
+/// let dense = DMatrix::from(&a);
+/// let jacobi = |x: &DVector<f64>| x + (&b - &dense * x) / 3.0;
+///
+/// let mut anderson = AndersonAcceleration::new(10);
+/// let mut x = DVector::zeros(20);
+///
+/// // Plain Jacobi iteration needs about 300 iterations to reach this tolerance
+/// for _ in 0..50 {
+///     let g = jacobi(&x);
+///     x = anderson.next_iterate(&x, &g);
+/// }
+///
+/// assert!((&dense * &x - &b).norm() < 1e-10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AndersonAcceleration<T> {
+    depth: usize,
+    mixing: T,
+    residual_differences: VecDeque<DVector<T>>,
+    evaluation_differences: VecDeque<DVector<T>>,
+    previous: Option<(DVector<T>, DVector<T>)>,
+}
+
+impl<T> AndersonAcceleration<T>
+where
+    T: RealField,
+{
+    /// Creates an accelerator that combines (at most) the last `depth + 1` evaluations, without
+    /// damping.
+    ///
+    /// A depth of zero performs the plain (possibly damped) fixed-point iteration.
+    #[must_use]
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            mixing: T::one(),
+            residual_differences: VecDeque::with_capacity(depth + 1),
+            evaluation_differences: VecDeque::with_capacity(depth + 1),
+            previous: None,
+        }
+    }
+
+    /// Sets the mixing (or damping) parameter `β` in `(0, 1]`.
+    ///
+    /// Without acceleration, the next iterate is `xₖ + β (g(xₖ) - xₖ)`, so values below one damp
+    /// every step. This can stabilize iterations whose map `g` is not contractive.
+    #[must_use]
+    pub fn with_mixing(self, mixing: T) -> Self {
+        Self { mixing, ..self }
+    }
+
+    /// The maximum number of past differences that are combined.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The number of past differences currently stored, which is at most [`Self::depth`].
+    pub fn history_len(&self) -> usize {
+        self.residual_differences.len()
+    }
+
+    /// Forgets all previous iterates, e.g. after the fixed-point map changed.
+    pub fn reset(&mut self) {
+        self.residual_differences.clear();
+        self.evaluation_differences.clear();
+        self.previous = None;
+    }
+
+    /// Computes the next iterate from the current iterate `x` and its image `g = g(x)`, and
+    /// records both for the following iterations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` and `g` do not have the same number of rows, or if they do not have as many
+    /// rows as the iterates previously passed to the accelerator.
+    pub fn next_iterate(&mut self, x: &DVector<T>, g: &DVector<T>) -> DVector<T> {
+        assert_eq!(
+            x.nrows(),
+            g.nrows(),
+            "x and g(x) must have the same number of rows."
+        );
+
+        let f = g - x;
+
+        if let Some((f_previous, g_previous)) = self.previous.take() {
+            assert_eq!(
+                f_previous.nrows(),
+                f.nrows(),
+                "The iterates must keep the same number of rows."
+            );
+
+            if self.depth > 0 {
+                if self.residual_differences.len() == self.depth {
+                    self.residual_differences.pop_front();
+                    self.evaluation_differences.pop_front();
+                }
+
+                self.residual_differences.push_back(&f - f_previous);
+                self.evaluation_differences.push_back(g - g_previous);
+            }
+        }
+
+        // xₖ₊₁ = xₖ + β fₖ - (ΔX + β ΔF) γ, where ΔX = ΔG - ΔF and γ minimizes ‖fₖ - ΔF γ‖
+        let mut next = x + &f * self.mixing.clone();
+
+        if !self.residual_differences.is_empty() {
+            let delta_f = DMatrix::from_columns(self.residual_differences.make_contiguous());
+            let svd = delta_f.svd(true, true);
+
+            // Drop the directions in which the window is (nearly) linearly dependent
+            let cutoff = svd.singular_values.max() * T::default_epsilon().sqrt();
+            let gamma = svd
+                .solve(&f, cutoff)
+                .expect("The SVD was computed with both U and V.");
+
+            let unmixed = T::one() - self.mixing.clone();
+            for ((df, dg), gamma_i) in self
+                .residual_differences
+                .iter()
+                .zip(&self.evaluation_differences)
+                .zip(gamma.iter())
+            {
+                next.axpy(-gamma_i.clone(), dg, T::one());
+                next.axpy(gamma_i.clone() * unmixed.clone(), df, T::one());
+            }
+        }
+
+        self.previous = Some((f, g.clone()));
+
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A slowly converging Jacobi iteration for a 1D diffusion problem, alongside its matrix and
+    /// right hand side.
+    fn jacobi(
+        n: usize,
+    ) -> (
+        impl Fn(&DVector<f64>) -> DVector<f64>,
+        DMatrix<f64>,
+        DVector<f64>,
+    ) {
+        let a = DMatrix::from_fn(n, n, |i, j| match (i, j) {
+            _ if i == j => 2.2,
+            _ if i == j + 1 || j == i + 1 => -1.0,
+            _ => 0.0,
+        });
+        let b = DVector::from_fn(n, |i, _| (i % 3) as f64);
+        let (a_map, b_map) = (a.clone(), b.clone());
+
+        (
+            move |x: &DVector<f64>| x + (&b_map - &a_map * x) / 2.2,
+            a,
+            b,
+        )
+    }
+
+    fn iterate(anderson: &mut AndersonAcceleration<f64>, n: usize, iterations: usize) -> f64 {
+        let (g, a, b) = jacobi(n);
+        let mut x = DVector::zeros(n);
+
+        for _ in 0..iterations {
+            x = anderson.next_iterate(&x, &g(&x));
+        }
+
+        (a * x - b).norm()
+    }
+
+    #[test]
+    fn depth_zero_is_the_plain_iteration() {
+        let (g, _, _) = jacobi(8);
+        let mut anderson = AndersonAcceleration::new(0).with_mixing(0.5);
+        let mut x = DVector::zeros(8);
+        let mut plain = DVector::zeros(8);
+
+        for _ in 0..5 {
+            x = anderson.next_iterate(&x, &g(&x));
+            plain = &plain + (g(&plain) - &plain) * 0.5;
+        }
+
+        assert_eq!(anderson.history_len(), 0);
+        assert!((x - plain).norm() < 1e-14);
+    }
+
+    #[test]
+    fn acceleration_outperforms_the_plain_iteration() {
+        let plain = iterate(&mut AndersonAcceleration::new(0), 30, 40);
+        let accelerated = iterate(&mut AndersonAcceleration::new(5), 30, 40);
+        let damped = iterate(&mut AndersonAcceleration::new(5).with_mixing(0.7), 30, 40);
+
+        assert!(accelerated < 1e-3 * plain);
+        assert!(damped < 1e-3 * plain);
+    }
+
+    #[test]
+    fn history_is_bounded_by_the_depth_and_can_be_reset() {
+        let (g, _, _) = jacobi(4);
+        let mut anderson = AndersonAcceleration::new(2);
+        let mut x = DVector::zeros(4);
+
+        for expected in [0, 1, 2, 2] {
+            x = anderson.next_iterate(&x, &g(&x));
+            assert_eq!(anderson.history_len(), expected);
+        }
+
+        anderson.reset();
+        assert_eq!(anderson.history_len(), 0);
+        assert_eq!(anderson.depth(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "same number of rows")]
+    fn rejects_mismatched_iterates() {
+        AndersonAcceleration::new(1).next_iterate(&DVector::<f64>::zeros(2), &DVector::zeros(3));
+    }
+}
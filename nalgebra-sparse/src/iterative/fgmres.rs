@@ -0,0 +1,316 @@
+use super::{
+    check_dimensions, matvec, Gmres, GmresReport, GmresSolution, LinearOperator, Orthogonalization,
+    Preconditioner,
+};
+use crate::{control::Control, error::OperationError};
+use nalgebra::{DVector, Dynamic, RealField, Storage, StorageMut, Vector};
+
+/// Flexible restarted GMRES(m) solver, FGMRES(m), for square sparse systems `A x = b`.
+///
+/// Standard right-preconditioned GMRES forms its update as `M⁻¹ (V y)` from the Krylov basis `V`,
+/// which is only correct if the same `M⁻¹` was applied to every basis vector. FGMRES instead
+/// stores every preconditioned basis vector `zₖ = Mₖ⁻¹ vₖ` and forms the update as `Z y`, so that
+/// the preconditioner may change from one iteration to the next. This makes it possible to
+/// precondition with e.g. a few iterations of an inner iterative solver, whose result depends
+/// non-linearly on its input. In return, FGMRES stores twice as many vectors as GMRES.
+///
+/// Only right preconditioning is supported, as a changing left preconditioner would change the
+/// residual being minimized. Otherwise, the solver behaves (and is configured) like [`Gmres`], and
+/// reports its outcome in the same types.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{
+///     cs::CsrMatrix,
+///     iterative::{Fgmres, Gmres, GmresStatus},
+/// };
+///
+/// let a = CsrMatrix::from(&DMatrix::from_fn(10, 10, |i, j| match (i, j) {
+///     _ if i == j => 4.0,
+///     _ if j == i + 1 => -1.0,
+///     _ if i == j + 1 => -2.0,
+///     _ => 0.0,
+/// }));
+/// let b = DVector::from_element(10, 1.0);
+///
+/// // Precondition with a few iterations of unpreconditioned GMRES on the same system
+/// let inner = Gmres::new().with_restart(3).with_max_iterations(3);
+/// let preconditioner = |r: &DVector<f64>| inner.solve(&a, r).unwrap().solution;
+///
+/// let result = Fgmres::new()
+///     .with_tolerance(1e-12)
+///     .with_preconditioner(&preconditioner)
+///     .solve(&a, &b)
+///     .unwrap();
+///
+/// assert_eq!(result.status, GmresStatus::Converged);
+/// assert!((DMatrix::from(&a) * &result.solution - &b).norm() < 1e-10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Fgmres<'a, T> {
+    gmres: Gmres<'a, T>,
+}
+
+impl<'a, T> Default for Fgmres<'a, T>
+where
+    T: RealField,
+{
+    fn default() -> Self {
+        Self {
+            gmres: Gmres::default(),
+        }
+    }
+}
+
+impl<'a, T> Fgmres<'a, T>
+where
+    T: RealField,
+{
+    /// Creates a new solver with the same defaults as [`Gmres::new`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the restart length `m`. See [`Gmres::with_restart`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `restart` is zero.
+    #[must_use]
+    pub fn with_restart(self, restart: usize) -> Self {
+        Self {
+            gmres: self.gmres.with_restart(restart),
+        }
+    }
+
+    /// Sets the maximum number of iterations across all restart cycles.
+    #[must_use]
+    pub fn with_max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            gmres: self.gmres.with_max_iterations(max_iterations),
+        }
+    }
+
+    /// Sets the tolerance on the residual norm, relative to the norm of the right hand side.
+    #[must_use]
+    pub fn with_tolerance(self, tolerance: T) -> Self {
+        Self {
+            gmres: self.gmres.with_tolerance(tolerance),
+        }
+    }
+
+    /// Sets the orthogonalization scheme of the Arnoldi iteration.
+    #[must_use]
+    pub fn with_orthogonalization(self, orthogonalization: Orthogonalization) -> Self {
+        Self {
+            gmres: self.gmres.with_orthogonalization(orthogonalization),
+        }
+    }
+
+    /// Sets the tolerance used to detect a breakdown of the Arnoldi iteration. See
+    /// [`Gmres::with_breakdown_tolerance`].
+    #[must_use]
+    pub fn with_breakdown_tolerance(self, breakdown_tolerance: T) -> Self {
+        Self {
+            gmres: self.gmres.with_breakdown_tolerance(breakdown_tolerance),
+        }
+    }
+
+    /// Applies `preconditioner` from the right. It may return different results for the same
+    /// input over the course of the solve.
+    #[must_use]
+    pub fn with_preconditioner(self, preconditioner: &'a dyn Preconditioner<T>) -> Self {
+        Self {
+            gmres: self.gmres.with_right_preconditioner(preconditioner),
+        }
+    }
+
+    /// Checks `control`, and reports progress to it, once per Arnoldi step. See
+    /// [`Gmres::with_control`].
+    #[must_use]
+    pub fn with_control(self, control: &'a Control<'a>) -> Self {
+        Self {
+            gmres: self.gmres.with_control(control),
+        }
+    }
+
+    /// Solves `A x = b`, starting from an initial guess of zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` is not
+    /// square, or if `b` does not have as many rows as `A`, and with kind
+    /// `OperationErrorKind::Cancelled` if the [control](Fgmres::with_control) requested a stop.
+    pub fn solve<A, S>(
+        &self,
+        a: &A,
+        b: &Vector<T, Dynamic, S>,
+    ) -> Result<GmresSolution<T>, OperationError>
+    where
+        A: LinearOperator<T> + ?Sized,
+        S: Storage<T, Dynamic>,
+    {
+        self.solve_with_initial_guess(a, b, DVector::zeros(b.nrows()))
+    }
+
+    /// Solves `A x = b`, starting from the initial guess `x0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` is not
+    /// square, or if `b` or `x0` do not have as many rows as `A`, and with kind
+    /// `OperationErrorKind::Cancelled` if the [control](Fgmres::with_control) requested a stop.
+    pub fn solve_with_initial_guess<A, S>(
+        &self,
+        a: &A,
+        b: &Vector<T, Dynamic, S>,
+        mut x0: DVector<T>,
+    ) -> Result<GmresSolution<T>, OperationError>
+    where
+        A: LinearOperator<T> + ?Sized,
+        S: Storage<T, Dynamic>,
+    {
+        let report = self.solve_in_place(a, b, &mut x0)?;
+
+        Ok(GmresSolution {
+            solution: x0,
+            status: report.status,
+            iterations: report.iterations,
+            restarts: report.restarts,
+            residual_norm: report.residual_norm,
+        })
+    }
+
+    /// Solves `A x = b` in place, using the current contents of `x` as the initial guess and
+    /// overwriting them with the solution. See [`Gmres::solve_in_place`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` is not
+    /// square, or if `b` or `x` do not have as many rows as `A`. `x` is left untouched in that
+    /// case.
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::Cancelled` if the
+    /// [control](Fgmres::with_control) requested a stop. `x` then holds the iterate of the last
+    /// completed restart cycle.
+    pub fn solve_in_place<A, S1, S2>(
+        &self,
+        a: &A,
+        b: &Vector<T, Dynamic, S1>,
+        x: &mut Vector<T, Dynamic, S2>,
+    ) -> Result<GmresReport<T>, OperationError>
+    where
+        A: LinearOperator<T> + ?Sized,
+        S1: Storage<T, Dynamic>,
+        S2: StorageMut<T, Dynamic>,
+    {
+        check_dimensions(a.shape(), b.nrows(), x.nrows())?;
+
+        let span = span!("fgmres", n = a.nrows(), iterations = tracing::field::Empty);
+
+        let report = self
+            .gmres
+            .run(|x| matvec(a, x), &b.clone_owned(), x, true)?;
+
+        record!(span, iterations = report.iterations);
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        control::CancellationToken, cs::CsrMatrix, error::OperationErrorKind,
+        iterative::GmresStatus,
+    };
+    use nalgebra::DMatrix;
+    use std::cell::Cell;
+
+    fn system() -> (CsrMatrix<f64>, DVector<f64>) {
+        let a = CsrMatrix::from(&DMatrix::from_fn(8, 8, |i, j| match (i, j) {
+            _ if i == j => 3.0 + i as f64,
+            _ if j == i + 1 => -1.0,
+            _ if i == j + 2 => 2.0,
+            _ => 0.0,
+        }));
+        let b = DVector::from_fn(8, |i, _| i as f64 - 3.0);
+
+        (a, b)
+    }
+
+    #[test]
+    fn rejects_mismatched_dimensions() {
+        let err = Fgmres::new()
+            .solve(&CsrMatrix::<f64>::identity(3), &DVector::zeros(2))
+            .unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn cancelled_solves_fail_with_cancelled() {
+        let (a, b) = system();
+        let token = CancellationToken::new();
+        token.cancel();
+        let control = Control::new().with_cancellation_token(&token);
+
+        let err = Fgmres::new()
+            .with_control(&control)
+            .solve(&a, &b)
+            .unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::Cancelled));
+    }
+
+    #[test]
+    fn agrees_with_gmres_for_a_fixed_preconditioner() {
+        let (a, b) = system();
+        let diagonal = DMatrix::from(&a).diagonal();
+        let jacobi = |r: &DVector<f64>| r.component_div(&diagonal);
+
+        let flexible = Fgmres::new()
+            .with_restart(3)
+            .with_tolerance(1e-12)
+            .with_preconditioner(&jacobi)
+            .solve(&a, &b)
+            .unwrap();
+        let standard = Gmres::new()
+            .with_restart(3)
+            .with_tolerance(1e-12)
+            .with_right_preconditioner(&jacobi)
+            .solve(&a, &b)
+            .unwrap();
+
+        assert_eq!(flexible.status, GmresStatus::Converged);
+        assert_eq!(flexible.iterations, standard.iterations);
+        assert!((&flexible.solution - &standard.solution).norm() < 1e-10);
+    }
+
+    #[test]
+    fn converges_with_a_changing_preconditioner() {
+        let (a, b) = system();
+        let diagonal = DMatrix::from(&a).diagonal();
+
+        // Alternates between Jacobi and (badly) scaled Jacobi preconditioning
+        let calls = Cell::new(0);
+        let alternating = |r: &DVector<f64>| {
+            calls.set(calls.get() + 1);
+            let scale = if calls.get() % 2 == 0 { 1.0 } else { 10.0 };
+            r.component_div(&diagonal) * scale
+        };
+
+        let result = Fgmres::new()
+            .with_tolerance(1e-12)
+            .with_preconditioner(&alternating)
+            .solve(&a, &b)
+            .unwrap();
+
+        assert_eq!(result.status, GmresStatus::Converged);
+        assert!((DMatrix::from(&a) * &result.solution - &b).norm() < 1e-10);
+    }
+}
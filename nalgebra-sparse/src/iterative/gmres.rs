@@ -0,0 +1,1085 @@
+use super::{check_dimensions, matvec, LinearOperator, Preconditioner};
+use crate::{
+    control::{Control, Stage},
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::{DMatrix, DVector, Dynamic, RealField, Storage, StorageMut, Vector};
+use std::fmt;
+
+/// Why a [`Gmres`] solve stopped.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GmresStatus {
+    /// The residual norm dropped below the requested tolerance.
+    Converged,
+
+    /// The maximum number of iterations was performed without reaching the tolerance.
+    MaxIterationsReached,
+
+    /// The Arnoldi process produced a singular Hessenberg matrix, i.e. the Krylov subspace
+    /// contains no better approximation of the solution. This typically means that the system
+    /// matrix (or preconditioned system matrix) is singular.
+    Breakdown,
+
+    /// A full restart cycle did not reduce the residual norm. Restarted GMRES can stagnate on
+    /// some non-normal matrices, in which case a longer restart length or a better preconditioner
+    /// is needed.
+    Stagnated,
+}
+
+/// How [`Gmres`] orthogonalizes every new Krylov vector against the basis built so far.
+///
+/// The schemes trade robustness against cost: the more accurately the basis is kept orthogonal,
+/// the more reliable the residual norms computed by the solver are on ill-conditioned systems,
+/// but the more work and memory every iteration takes.
+#[non_exhaustive]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Orthogonalization {
+    /// Modified Gram-Schmidt, which projects out the basis vectors one at a time.
+    ///
+    /// This is the cheapest scheme and is accurate enough for most problems, but the basis can
+    /// lose orthogonality when the Krylov vectors are nearly linearly dependent.
+    #[default]
+    ModifiedGramSchmidt,
+
+    /// Classical Gram-Schmidt, applied twice.
+    ///
+    /// Computes all projections of a pass at once (i.e. as a sequence of dot products that do not
+    /// depend on each other), and repeats the pass to restore the orthogonality lost by the
+    /// first. This costs twice as much arithmetic as modified Gram-Schmidt, but keeps the basis
+    /// orthogonal to working precision.
+    ClassicalGramSchmidtTwice,
+
+    /// Householder reflections.
+    ///
+    /// The most robust scheme, which produces a basis that is orthogonal to working precision
+    /// regardless of the conditioning of the Krylov vectors. It stores a reflector in addition to
+    /// every basis vector and costs roughly twice as much as modified Gram-Schmidt.
+    Householder,
+}
+
+/// The outcome of a [`Gmres`] solve.
+#[derive(Debug, Clone)]
+pub struct GmresSolution<T> {
+    /// The best approximate solution that was found.
+    pub solution: DVector<T>,
+
+    /// Why the solver stopped.
+    pub status: GmresStatus,
+
+    /// The total number of iterations (i.e. matrix-vector products with the system matrix) that
+    /// were performed, across all restart cycles.
+    pub iterations: usize,
+
+    /// The number of restart cycles that were started.
+    pub restarts: usize,
+
+    /// The norm of the (left-preconditioned) residual `M⁻¹ (b - A x)` of `solution`.
+    pub residual_norm: T,
+}
+
+/// Statistics about a [`Gmres`] solve whose solution was written in place.
+///
+/// See [`Gmres::solve_in_place`].
+#[derive(Debug, Clone)]
+pub struct GmresReport<T> {
+    /// Why the solver stopped.
+    pub status: GmresStatus,
+
+    /// The total number of iterations (i.e. matrix-vector products with the system matrix) that
+    /// were performed, across all restart cycles.
+    pub iterations: usize,
+
+    /// The number of restart cycles that were started.
+    pub restarts: usize,
+
+    /// The norm of the (left-preconditioned) residual `M⁻¹ (b - A x)` of the solution.
+    pub residual_norm: T,
+}
+
+/// Restarted GMRES(m) solver for square, possibly non-symmetric, sparse systems `A x = b`.
+///
+/// Every restart cycle builds an orthonormal basis of (at most) `m` vectors for the Krylov
+/// subspace of the current residual using Arnoldi iteration, and then picks the update from that
+/// subspace which minimizes the residual norm. The solver stops once the residual norm `‖r‖`
+/// satisfies `‖r‖ <= tolerance * ‖b‖`, where both norms are measured after left
+/// preconditioning. The orthogonalization scheme used by the Arnoldi iteration can be chosen with
+/// [`Gmres::with_orthogonalization`].
+///
+/// Preconditioners are given as implementations of [`Preconditioner`], which apply `M⁻¹` to a
+/// vector (this includes closures):
+///
+/// - A left preconditioner solves `M⁻¹ A x = M⁻¹ b` instead, and therefore changes the residual
+///   that is measured.
+/// - A right preconditioner solves `A M⁻¹ y = b` with `x = M⁻¹ y`, and therefore leaves the
+///   residual unchanged.
+///
+/// Both may be used at the same time. The preconditioners must not change between iterations.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{
+///     cs::CsrMatrix,
+///     iterative::{Gmres, GmresStatus},
+/// };
+///
+/// // A non-symmetric, diagonally dominant matrix
+/// let a = CsrMatrix::try_from_parts(
+///     3,
+///     3,
+///     vec![0, 2, 5],
+///     vec![0, 1, 0, 1, 2, 1, 2],
+///     vec![4.0, 1.0, -1.0, 4.0, 1.0, -1.0, 4.0],
+/// )
+/// .unwrap();
+/// let b = DVector::from_vec(vec![1.0, 2.0, 3.0]);
+///
+/// // Jacobi (i.e. diagonal) preconditioning
+/// let jacobi = |r: &DVector<f64>| r / 4.0;
+///
+/// let result = Gmres::new()
+///     .with_restart(2)
+///     .with_tolerance(1e-12)
+///     .with_right_preconditioner(&jacobi)
+///     .solve(&a, &b)
+///     .unwrap();
+///
+/// assert_eq!(result.status, GmresStatus::Converged);
+/// assert!((DMatrix::from(&a) * &result.solution - &b).norm() < 1e-10);
+/// ```
+#[derive(Clone)]
+pub struct Gmres<'a, T> {
+    restart: usize,
+    max_iterations: usize,
+    tolerance: T,
+    orthogonalization: Orthogonalization,
+    breakdown_tolerance: T,
+    left_preconditioner: Option<&'a dyn Preconditioner<T>>,
+    right_preconditioner: Option<&'a dyn Preconditioner<T>>,
+    control: Option<&'a Control<'a>>,
+}
+
+impl<'a, T> Default for Gmres<'a, T>
+where
+    T: RealField,
+{
+    fn default() -> Self {
+        Self {
+            restart: 30,
+            max_iterations: 1000,
+            tolerance: nalgebra::convert(1e-8),
+            orthogonalization: Orthogonalization::default(),
+            breakdown_tolerance: T::default_epsilon(),
+            left_preconditioner: None,
+            right_preconditioner: None,
+            control: None,
+        }
+    }
+}
+
+impl<'a, T> Gmres<'a, T>
+where
+    T: RealField,
+{
+    /// Creates a new solver with a restart length of 30, at most 1000 iterations, a relative
+    /// tolerance of `1e-8`, modified Gram-Schmidt orthogonalization, a breakdown tolerance of
+    /// machine epsilon and no preconditioning.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the restart length `m`, i.e. the maximum dimension of the Krylov subspace built in
+    /// every restart cycle.
+    ///
+    /// Longer restart lengths typically converge in fewer iterations, but need to store `m + 1`
+    /// vectors of the size of the system and perform more orthogonalization work per iteration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `restart` is zero.
+    #[must_use]
+    pub fn with_restart(self, restart: usize) -> Self {
+        assert!(restart > 0, "The GMRES restart length must be positive.");
+
+        Self { restart, ..self }
+    }
+
+    /// Sets the maximum number of iterations across all restart cycles.
+    #[must_use]
+    pub fn with_max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    /// Sets the tolerance on the residual norm, relative to the norm of the right hand side.
+    #[must_use]
+    pub fn with_tolerance(self, tolerance: T) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Sets the orthogonalization scheme of the Arnoldi iteration.
+    #[must_use]
+    pub fn with_orthogonalization(self, orthogonalization: Orthogonalization) -> Self {
+        Self {
+            orthogonalization,
+            ..self
+        }
+    }
+
+    /// Sets the tolerance used to detect a (happy) breakdown of the Arnoldi iteration.
+    ///
+    /// A new Krylov vector `w` is considered to lie in the span of the current basis once the
+    /// norm of its orthogonalized remainder drops to `breakdown_tolerance * ‖w‖` or below, at
+    /// which point the restart cycle ends. Larger tolerances end cycles earlier on problems whose
+    /// Krylov vectors become nearly dependent, rather than orthogonalizing against noise.
+    #[must_use]
+    pub fn with_breakdown_tolerance(self, breakdown_tolerance: T) -> Self {
+        Self {
+            breakdown_tolerance,
+            ..self
+        }
+    }
+
+    /// Applies `preconditioner` (i.e. `M⁻¹`) from the left.
+    #[must_use]
+    pub fn with_left_preconditioner(self, preconditioner: &'a dyn Preconditioner<T>) -> Self {
+        Self {
+            left_preconditioner: Some(preconditioner),
+            ..self
+        }
+    }
+
+    /// Applies `preconditioner` (i.e. `M⁻¹`) from the right.
+    #[must_use]
+    pub fn with_right_preconditioner(self, preconditioner: &'a dyn Preconditioner<T>) -> Self {
+        Self {
+            right_preconditioner: Some(preconditioner),
+            ..self
+        }
+    }
+
+    /// Checks `control`, and reports progress to it, once per Arnoldi step, i.e. once per
+    /// iteration. Progress is measured in iterations out of the maximum number of iterations.
+    ///
+    /// A solve that `control` stops fails with an [`OperationError`] with kind
+    /// `OperationErrorKind::Cancelled`.
+    #[must_use]
+    pub fn with_control(self, control: &'a Control<'a>) -> Self {
+        Self {
+            control: Some(control),
+            ..self
+        }
+    }
+
+    /// Solves `A x = b`, starting from an initial guess of zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` is not
+    /// square, or if `b` does not have as many rows as `A`, and with kind
+    /// `OperationErrorKind::Cancelled` if the [control](Gmres::with_control) requested a stop.
+    pub fn solve<A, S>(
+        &self,
+        a: &A,
+        b: &Vector<T, Dynamic, S>,
+    ) -> Result<GmresSolution<T>, OperationError>
+    where
+        A: LinearOperator<T> + ?Sized,
+        S: Storage<T, Dynamic>,
+    {
+        self.solve_with_initial_guess(a, b, DVector::zeros(b.nrows()))
+    }
+
+    /// Solves `A x = b`, starting from the initial guess `x0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` is not
+    /// square, or if `b` or `x0` do not have as many rows as `A`, and with kind
+    /// `OperationErrorKind::Cancelled` if the [control](Gmres::with_control) requested a stop.
+    pub fn solve_with_initial_guess<A, S>(
+        &self,
+        a: &A,
+        b: &Vector<T, Dynamic, S>,
+        mut x0: DVector<T>,
+    ) -> Result<GmresSolution<T>, OperationError>
+    where
+        A: LinearOperator<T> + ?Sized,
+        S: Storage<T, Dynamic>,
+    {
+        let report = self.solve_in_place(a, b, &mut x0)?;
+
+        Ok(GmresSolution {
+            solution: x0,
+            status: report.status,
+            iterations: report.iterations,
+            restarts: report.restarts,
+            residual_norm: report.residual_norm,
+        })
+    }
+
+    /// Solves `A x = b` in place, using the current contents of `x` as the initial guess and
+    /// overwriting them with the solution.
+    ///
+    /// Both `b` and `x` may be views, e.g. columns of larger dense matrices, so that a sequence of
+    /// systems can be solved without copying every right hand side and solution into a separate
+    /// vector first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` is not
+    /// square, or if `b` or `x` do not have as many rows as `A`. `x` is left untouched in that
+    /// case.
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::Cancelled` if the
+    /// [control](Gmres::with_control) requested a stop. `x` then holds the iterate of the last
+    /// completed restart cycle.
+    pub fn solve_in_place<A, S1, S2>(
+        &self,
+        a: &A,
+        b: &Vector<T, Dynamic, S1>,
+        x: &mut Vector<T, Dynamic, S2>,
+    ) -> Result<GmresReport<T>, OperationError>
+    where
+        A: LinearOperator<T> + ?Sized,
+        S1: Storage<T, Dynamic>,
+        S2: StorageMut<T, Dynamic>,
+    {
+        check_dimensions(a.shape(), b.nrows(), x.nrows())?;
+
+        let span = span!(
+            "gmres",
+            n = a.nrows(),
+            restart = self.restart,
+            iterations = tracing::field::Empty
+        );
+
+        let report = self.run(|x| matvec(a, x), &b.clone_owned(), x, false)?;
+
+        record!(span, iterations = report.iterations);
+
+        Ok(report)
+    }
+
+    /// Applies the left preconditioner to `v`, if any.
+    fn precondition_left(&self, mut v: DVector<T>) -> DVector<T> {
+        if let Some(m) = self.left_preconditioner {
+            m.apply_mut(&mut v);
+        }
+        v
+    }
+
+    /// Applies the right preconditioner to `v`, if any.
+    fn precondition_right(&self, mut v: DVector<T>) -> DVector<T> {
+        if let Some(m) = self.right_preconditioner {
+            m.apply_mut(&mut v);
+        }
+        v
+    }
+
+    /// Runs restarted GMRES on the operator `apply`, which computes `A x`.
+    ///
+    /// If `flexible` is set, the right-preconditioned basis vectors are stored and used to form
+    /// the update, so that the right preconditioner may change between iterations (FGMRES).
+    pub(super) fn run<F, S>(
+        &self,
+        apply: F,
+        b: &DVector<T>,
+        x: &mut Vector<T, Dynamic, S>,
+        flexible: bool,
+    ) -> Result<GmresReport<T>, OperationError>
+    where
+        F: Fn(&DVector<T>) -> DVector<T>,
+        S: StorageMut<T, Dynamic>,
+    {
+        let m = self.restart;
+        let eps = T::default_epsilon();
+        let residual =
+            |x: &Vector<T, Dynamic, S>| self.precondition_left(b - apply(&x.clone_owned()));
+
+        let threshold = self.precondition_left(b.clone()).norm() * self.tolerance.clone();
+
+        let mut r = residual(x);
+        let mut beta = r.norm();
+        let mut iterations = 0;
+        let mut restarts = 0;
+
+        let status = loop {
+            if beta <= threshold {
+                break GmresStatus::Converged;
+            }
+
+            if iterations >= self.max_iterations {
+                break GmresStatus::MaxIterationsReached;
+            }
+
+            restarts += 1;
+
+            // The Hessenberg matrix is reduced to upper-triangular form with Givens rotations as
+            // it is built, so that the residual norm of every iterate is available as `g[j + 1]`
+            // without forming the iterate itself.
+            let mut h = DMatrix::zeros(m + 1, m);
+            let mut rotations: Vec<(T, T)> = Vec::with_capacity(m);
+            let mut g = DVector::zeros(m + 1);
+
+            let (mut basis, gamma) = KrylovBasis::new(self.orthogonalization, r, beta.clone(), m);
+            g[0] = gamma;
+
+            // The right-preconditioned basis vectors, only kept if the preconditioner may change
+            let mut preconditioned = Vec::new();
+
+            let mut k = 0;
+            let mut happy_breakdown = false;
+
+            while k < m && iterations < self.max_iterations {
+                if self.control.is_some_and(|control| {
+                    control.checkpoint(Stage::IterativeSolve, iterations, self.max_iterations)
+                }) {
+                    return Err(OperationError::from_kind_and_message(
+                        OperationErrorKind::Cancelled,
+                        String::from("The solve was cancelled before it stopped on its own."),
+                    ));
+                }
+
+                let z = self.precondition_right(basis.vectors[k].clone());
+                let w = self.precondition_left(apply(&z));
+                let w_norm = w.norm();
+
+                if flexible {
+                    preconditioned.push(z);
+                }
+
+                let (next, h_next) = basis.orthogonalize(w, h.column_mut(k));
+                h[(k + 1, k)] = h_next.clone();
+
+                for (i, (c, s)) in rotations.iter().enumerate() {
+                    let upper = h[(i, k)].clone();
+                    let lower = h[(i + 1, k)].clone();
+
+                    h[(i, k)] = c.clone() * upper.clone() + s.clone() * lower.clone();
+                    h[(i + 1, k)] = c.clone() * lower - s.clone() * upper;
+                }
+
+                let (c, s) = givens(h[(k, k)].clone(), h[(k + 1, k)].clone());
+                h[(k, k)] = c.clone() * h[(k, k)].clone() + s.clone() * h[(k + 1, k)].clone();
+                h[(k + 1, k)] = T::zero();
+                g[k + 1] = -s.clone() * g[k].clone();
+                g[k] = c.clone() * g[k].clone();
+                rotations.push((c, s));
+
+                iterations += 1;
+                k += 1;
+
+                // If the new direction is (numerically) contained in the current subspace, the
+                // subspace is invariant under the operator and no further progress is possible.
+                if h_next.abs() <= self.breakdown_tolerance.clone() * w_norm {
+                    happy_breakdown = true;
+                    break;
+                }
+
+                if g[k].clone().abs() <= threshold {
+                    break;
+                }
+
+                basis.push(next);
+            }
+
+            // Solve the triangular least squares problem, dropping any trailing columns that
+            // are (numerically) linearly dependent on the previous ones.
+            let scale = (0..k).fold(T::zero(), |max, i| max.max(h[(i, i)].clone().abs()));
+            let rank = (0..k)
+                .position(|i| h[(i, i)].clone().abs() <= eps.clone() * scale.clone())
+                .unwrap_or(k);
+
+            let mut y = g.rows(0, rank).into_owned();
+            for i in (0..rank).rev() {
+                let mut y_i = y[i].clone();
+                for j in i + 1..rank {
+                    y_i -= h[(i, j)].clone() * y[j].clone();
+                }
+                y[i] = y_i / h[(i, i)].clone();
+            }
+
+            if flexible {
+                for (z, y_i) in preconditioned.iter().zip(y.iter()) {
+                    x.axpy(y_i.clone(), z, T::one());
+                }
+            } else {
+                let mut update = DVector::zeros(x.nrows());
+                for (v, y_i) in basis.vectors.iter().zip(y.iter()) {
+                    update.axpy(y_i.clone(), v, T::one());
+                }
+                *x += self.precondition_right(update);
+            }
+
+            let previous_beta = beta;
+            r = residual(x);
+            beta = r.norm();
+
+            if rank < k {
+                break if beta <= threshold {
+                    GmresStatus::Converged
+                } else {
+                    GmresStatus::Breakdown
+                };
+            }
+
+            // Only a full cycle is expected to make progress; a cycle cut short by the iteration
+            // limit is reported as such on the next pass through the loop.
+            if beta > threshold && !happy_breakdown && k == m {
+                let required = previous_beta * (T::one() - eps.clone().sqrt());
+                if beta > required {
+                    break GmresStatus::Stagnated;
+                }
+            }
+        };
+
+        if let Some(control) = self.control {
+            control.report(Stage::IterativeSolve, iterations, iterations);
+        }
+
+        Ok(GmresReport {
+            status,
+            iterations,
+            restarts,
+            residual_norm: beta,
+        })
+    }
+}
+
+impl<'a, T> fmt::Debug for Gmres<'a, T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let preconditioner = "dyn Preconditioner<T>";
+
+        f.debug_struct("Gmres")
+            .field("restart", &self.restart)
+            .field("max_iterations", &self.max_iterations)
+            .field("tolerance", &self.tolerance)
+            .field("orthogonalization", &self.orthogonalization)
+            .field("breakdown_tolerance", &self.breakdown_tolerance)
+            .field(
+                "left_preconditioner",
+                &self.left_preconditioner.map(|_| preconditioner),
+            )
+            .field(
+                "right_preconditioner",
+                &self.right_preconditioner.map(|_| preconditioner),
+            )
+            .field("control", &self.control)
+            .finish()
+    }
+}
+
+/// The orthonormal basis of the Krylov subspace built during a restart cycle.
+struct KrylovBasis<T> {
+    orthogonalization: Orthogonalization,
+    vectors: Vec<DVector<T>>,
+    /// The unit vectors `u` of the Householder reflectors `P = I - 2 u uᵀ`, one per basis vector.
+    /// Empty unless orthogonalizing with Householder reflections.
+    reflectors: Vec<DVector<T>>,
+}
+
+impl<T> KrylovBasis<T>
+where
+    T: RealField,
+{
+    /// Starts a basis from the residual `r` of norm `beta`, returning the basis and the
+    /// coordinate `gamma` of `r` along the first basis vector (i.e. `r = gamma v₀`).
+    fn new(orthogonalization: Orthogonalization, r: DVector<T>, beta: T, m: usize) -> (Self, T) {
+        let mut basis = Self {
+            orthogonalization,
+            vectors: Vec::with_capacity(m + 1),
+            reflectors: Vec::new(),
+        };
+
+        match orthogonalization {
+            Orthogonalization::ModifiedGramSchmidt
+            | Orthogonalization::ClassicalGramSchmidtTwice => {
+                basis.vectors.push(r / beta.clone());
+                (basis, beta)
+            }
+
+            Orthogonalization::Householder => {
+                // `P₀ r = alpha e₀`, hence `r = alpha P₀ e₀`
+                let (u, alpha) = householder(&r, 0);
+                basis.reflectors.reserve(m + 1);
+                basis.push_reflector(u);
+                (basis, alpha)
+            }
+        }
+    }
+
+    /// Orthogonalizes `w` against the basis, writing its coordinates along the basis vectors into
+    /// the first entries of `coefficients`. Returns the direction of the remainder and its
+    /// coordinate along that direction.
+    ///
+    /// The direction is only meaningful (and should only be passed to [`Self::push`]) if the
+    /// coordinate is not (numerically) zero.
+    fn orthogonalize<S>(
+        &self,
+        mut w: DVector<T>,
+        mut coefficients: Vector<T, Dynamic, S>,
+    ) -> (DVector<T>, T)
+    where
+        S: StorageMut<T, Dynamic>,
+    {
+        let k = self.vectors.len();
+
+        match self.orthogonalization {
+            Orthogonalization::ModifiedGramSchmidt => {
+                for (i, v) in self.vectors.iter().enumerate() {
+                    let h_ik = w.dot(v);
+                    w.axpy(-h_ik.clone(), v, T::one());
+                    coefficients[i] = h_ik;
+                }
+            }
+
+            Orthogonalization::ClassicalGramSchmidtTwice => {
+                for i in 0..k {
+                    coefficients[i] = T::zero();
+                }
+
+                for _ in 0..2 {
+                    let projections: Vec<T> = self.vectors.iter().map(|v| w.dot(v)).collect();
+
+                    for (i, (v, h_ik)) in self.vectors.iter().zip(projections).enumerate() {
+                        w.axpy(-h_ik.clone(), v, T::one());
+                        coefficients[i] += h_ik;
+                    }
+                }
+            }
+
+            Orthogonalization::Householder => {
+                for u in &self.reflectors {
+                    reflect(u, &mut w);
+                }
+
+                for i in 0..k {
+                    coefficients[i] = w[i].clone();
+                }
+
+                // Once the basis spans the whole space, the remainder is necessarily zero
+                if k >= w.nrows() {
+                    return (w, T::zero());
+                }
+
+                return householder(&w, k);
+            }
+        }
+
+        let norm = w.norm();
+        (w / norm.clone(), norm)
+    }
+
+    /// Appends the direction returned by [`Self::orthogonalize`] to the basis.
+    fn push(&mut self, direction: DVector<T>) {
+        match self.orthogonalization {
+            Orthogonalization::ModifiedGramSchmidt
+            | Orthogonalization::ClassicalGramSchmidtTwice => self.vectors.push(direction),
+
+            Orthogonalization::Householder => self.push_reflector(direction),
+        }
+    }
+
+    /// Appends the reflector `P_k = I - 2 u uᵀ` and the basis vector `P₀ ⋯ P_k e_k`.
+    fn push_reflector(&mut self, u: DVector<T>) {
+        let k = self.reflectors.len();
+        self.reflectors.push(u);
+
+        let mut v = DVector::zeros(self.reflectors[0].nrows());
+        v[k] = T::one();
+
+        for u in self.reflectors.iter().rev() {
+            reflect(u, &mut v);
+        }
+
+        self.vectors.push(v);
+    }
+}
+
+/// Computes the Householder reflector `P = I - 2 u uᵀ` that maps `x[j..]` onto a multiple
+/// `alpha` of the unit vector `e_j`, leaving `x[..j]` untouched. Returns `u` and `alpha`.
+fn householder<T>(x: &DVector<T>, j: usize) -> (DVector<T>, T)
+where
+    T: RealField,
+{
+    let sigma = x.rows_range(j..).norm();
+    let mut u = DVector::zeros(x.nrows());
+
+    if sigma.is_zero() {
+        // The identity, with `u = 0`
+        return (u, T::zero());
+    }
+
+    // Choose the sign of `alpha` to avoid cancellation when forming `u`
+    let alpha = if x[j] >= T::zero() { -sigma } else { sigma };
+
+    u.rows_range_mut(j..).copy_from(&x.rows_range(j..));
+    u[j] -= alpha.clone();
+    u.normalize_mut();
+
+    (u, alpha)
+}
+
+/// Applies the Householder reflector `I - 2 u uᵀ` to `v`.
+fn reflect<T>(u: &DVector<T>, v: &mut DVector<T>)
+where
+    T: RealField,
+{
+    let projection = u.dot(v);
+    v.axpy(-(projection.clone() + projection), u, T::one());
+}
+
+/// Computes the Givens rotation `(c, s)` that maps `(a, b)` onto `(r, 0)`.
+fn givens<T>(a: T, b: T) -> (T, T)
+where
+    T: RealField,
+{
+    let r = a.clone().hypot(b.clone());
+
+    if r.is_zero() {
+        (T::one(), T::zero())
+    } else {
+        (a / r.clone(), b / r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        control::Progress,
+        cs::{CscMatrix, CsrMatrix},
+        error::OperationErrorKind,
+        factorization::CsCholesky,
+        iterative::IdentityPreconditioner,
+        proptest::*,
+    };
+    use proptest::prelude::*;
+    use std::cell::{Cell, RefCell};
+
+    /// Non-symmetric, strictly diagonally dominant matrices alongside a right hand side.
+    fn nonsymmetric_system() -> impl Strategy<Value = (CsrMatrix<f64>, DVector<f64>)> {
+        (1usize..=8)
+            .prop_flat_map(|n| {
+                (
+                    csr(-1.0..=1.0, n..=n, n..=n, PROPTEST_MAX_NNZ),
+                    proptest::collection::vec(-5.0..=5.0, n),
+                )
+            })
+            .prop_map(|(csr, b)| {
+                let n = csr.nrows();
+                let mut dense = DMatrix::from(&csr);
+                dense.fill_diagonal(f64::from(n as u32) + 1.0);
+
+                (CsrMatrix::from(&dense), DVector::from_vec(b))
+            })
+    }
+
+    #[test]
+    fn rejects_mismatched_dimensions() {
+        let a = CsrMatrix::<f64>::identity(3);
+
+        let err = Gmres::new()
+            .solve(&CsrMatrix::<f64>::zeros(2, 3), &DVector::zeros(2))
+            .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+
+        let err = Gmres::new().solve(&a, &DVector::zeros(2)).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+
+        let err = Gmres::new()
+            .solve_with_initial_guess(&a, &DVector::zeros(3), DVector::zeros(4))
+            .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn zero_right_hand_side_converges_immediately() {
+        let a = CsrMatrix::<f64>::identity(3);
+        let result = Gmres::new().solve(&a, &DVector::zeros(3)).unwrap();
+
+        assert_eq!(result.status, GmresStatus::Converged);
+        assert_eq!(result.iterations, 0);
+        assert_eq!(result.solution, DVector::zeros(3));
+    }
+
+    #[test]
+    fn singular_system_reports_breakdown() {
+        // [ 1 0 ]
+        // [ 0 0 ]
+        let a = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![0], vec![1.0f64]).unwrap();
+        let b = DVector::from_vec(vec![1.0, 1.0]);
+
+        let result = Gmres::new().solve(&a, &b).unwrap();
+
+        assert_eq!(result.status, GmresStatus::Breakdown);
+        assert!((result.solution[0] - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cyclic_shift_stagnates_with_short_restarts() {
+        // The cyclic shift matrix is the classic example of restarted GMRES making no progress:
+        // the first n - 1 Krylov vectors are orthogonal to the residual.
+        let n = 4;
+        let a = CsrMatrix::try_from_parts(
+            n,
+            n,
+            (0..n).collect(),
+            (0..n).map(|i| (i + n - 1) % n).collect(),
+            vec![1.0; n],
+        )
+        .unwrap();
+        let b = DVector::from_fn(n, |i, _| if i == 0 { 1.0 } else { 0.0 });
+
+        let result = Gmres::new().with_restart(2).solve(&a, &b).unwrap();
+        assert_eq!(result.status, GmresStatus::Stagnated);
+
+        let result = Gmres::new().with_restart(n).solve(&a, &b).unwrap();
+        assert_eq!(result.status, GmresStatus::Converged);
+    }
+
+    #[test]
+    fn solves_into_columns_of_a_dense_matrix() {
+        let a = CsrMatrix::from(&DMatrix::from_fn(4, 4, |i, j| match (i, j) {
+            _ if i == j => 4.0,
+            _ if j == i + 1 => 1.0,
+            _ if i == j + 1 => -2.0,
+            _ => 0.0,
+        }));
+        let rhs = DMatrix::from_fn(4, 3, |i, j| (i * 3 + j) as f64 - 5.0);
+        let mut solutions = DMatrix::zeros(4, 3);
+        let gmres = Gmres::new().with_tolerance(1e-12);
+
+        for j in 0..3 {
+            let report = gmres
+                .solve_in_place(&a, &rhs.column(j), &mut solutions.column_mut(j))
+                .unwrap();
+            assert_eq!(report.status, GmresStatus::Converged);
+        }
+
+        assert!((DMatrix::from(&a) * &solutions - &rhs).amax() < 1e-10);
+
+        // The initial guess is taken from the output, so an exact guess needs no iterations.
+        let report = gmres
+            .solve_in_place(&a, &rhs.column(0), &mut solutions.column_mut(0))
+            .unwrap();
+        assert_eq!(report.iterations, 0);
+    }
+
+    #[test]
+    fn max_iterations_are_respected() {
+        let a = CscMatrix::from(&DMatrix::from_fn(5, 5, |i, j| {
+            if i == j {
+                2.0
+            } else if j == i + 1 {
+                -1.0
+            } else {
+                0.0
+            }
+        }));
+        let b = DVector::from_element(5, 1.0);
+
+        let result = Gmres::new()
+            .with_max_iterations(2)
+            .with_tolerance(1e-14)
+            .solve(&a, &b)
+            .unwrap();
+
+        assert_eq!(result.status, GmresStatus::MaxIterationsReached);
+        assert_eq!(result.iterations, 2);
+    }
+
+    #[test]
+    fn control_is_checked_once_per_arnoldi_step() {
+        let a = CsrMatrix::from(&DMatrix::from_fn(30, 30, |i, j| match (i, j) {
+            _ if i == j => 3.0,
+            _ if j == i + 1 => -1.0,
+            _ if i == j + 1 => -1.5,
+            _ => 0.0,
+        }));
+        let b = DVector::from_fn(30, |i, _| (i % 7) as f64 - 3.0);
+        let solver = Gmres::new().with_restart(3).with_tolerance(1e-14);
+
+        let reports = RefCell::new(Vec::new());
+        let progress = |progress: Progress| reports.borrow_mut().push(progress.completed);
+        let control = Control::new().with_progress(&progress);
+        let result = solver.clone().with_control(&control).solve(&a, &b).unwrap();
+
+        let mut expected = (0..result.iterations).collect::<Vec<_>>();
+        expected.push(result.iterations);
+        assert_eq!(reports.into_inner(), expected);
+
+        // Stop at the start of the third restart cycle, i.e. on the seventh check
+        let checks = Cell::new(0);
+        let should_stop = || {
+            checks.set(checks.get() + 1);
+            checks.get() == 7
+        };
+        let control = Control::new().with_should_stop(&should_stop);
+        let mut x = DVector::zeros(30);
+
+        let err = solver
+            .clone()
+            .with_control(&control)
+            .solve_in_place(&a, &b, &mut x)
+            .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::Cancelled));
+
+        let two_cycles = solver.with_max_iterations(6).solve(&a, &b).unwrap();
+        assert_eq!(x, two_cycles.solution);
+    }
+
+    #[test]
+    fn exact_preconditioner_converges_in_one_iteration() {
+        let a = CsrMatrix::from(&DMatrix::from_row_slice(
+            3,
+            3,
+            &[4.0, 1.0, 0.0, 1.0, 3.0, -1.0, 0.0, -1.0, 2.0],
+        ));
+        let b = DVector::from_vec(vec![1.0, 2.0, 3.0]);
+        let cholesky = CsCholesky::factor(&a).unwrap();
+
+        for gmres in [
+            Gmres::new().with_left_preconditioner(&cholesky),
+            Gmres::new().with_right_preconditioner(&cholesky),
+        ] {
+            let result = gmres.with_tolerance(1e-12).solve(&a, &b).unwrap();
+
+            assert_eq!(result.status, GmresStatus::Converged);
+            assert_eq!(result.iterations, 1);
+        }
+
+        let result = Gmres::new()
+            .with_left_preconditioner(&IdentityPreconditioner)
+            .with_tolerance(1e-12)
+            .solve(&a, &b)
+            .unwrap();
+        assert_eq!(result.status, GmresStatus::Converged);
+    }
+
+    const ORTHOGONALIZATIONS: [Orthogonalization; 3] = [
+        Orthogonalization::ModifiedGramSchmidt,
+        Orthogonalization::ClassicalGramSchmidtTwice,
+        Orthogonalization::Householder,
+    ];
+
+    #[test]
+    fn every_orthogonalization_detects_breakdown_and_stagnation() {
+        let singular = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![0], vec![1.0f64]).unwrap();
+        let n = 4;
+        let shift = CsrMatrix::try_from_parts(
+            n,
+            n,
+            (0..n).collect(),
+            (0..n).map(|i| (i + n - 1) % n).collect(),
+            vec![1.0; n],
+        )
+        .unwrap();
+        let e0 = DVector::from_fn(n, |i, _| if i == 0 { 1.0 } else { 0.0 });
+
+        for orthogonalization in ORTHOGONALIZATIONS {
+            let gmres = Gmres::new().with_orthogonalization(orthogonalization);
+
+            let result = gmres
+                .solve(&singular, &DVector::from_element(2, 1.0))
+                .unwrap();
+            assert_eq!(result.status, GmresStatus::Breakdown);
+
+            let result = gmres.clone().with_restart(2).solve(&shift, &e0).unwrap();
+            assert_eq!(result.status, GmresStatus::Stagnated);
+
+            let result = gmres.with_restart(n).solve(&shift, &e0).unwrap();
+            assert_eq!(result.status, GmresStatus::Converged);
+        }
+    }
+
+    #[test]
+    fn breakdown_tolerance_ends_restart_cycles_early() {
+        // With a breakdown tolerance of one, every new Krylov vector is considered dependent, so
+        // every cycle performs a single iteration.
+        let a = CsrMatrix::from(&DMatrix::from_fn(4, 4, |i, j| match (i, j) {
+            _ if i == j => 4.0,
+            _ if j == i + 1 => 1.0,
+            _ => 0.0,
+        }));
+        let b = DVector::from_element(4, 1.0);
+
+        for orthogonalization in ORTHOGONALIZATIONS {
+            let result = Gmres::new()
+                .with_orthogonalization(orthogonalization)
+                .with_breakdown_tolerance(1.0)
+                .with_tolerance(1e-10)
+                .solve(&a, &b)
+                .unwrap();
+
+            assert_eq!(result.status, GmresStatus::Converged);
+            assert_eq!(result.iterations, result.restarts);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn orthogonalizations_agree((a, b) in nonsymmetric_system(), restart in 1usize..=8) {
+            let solutions: Vec<_> = ORTHOGONALIZATIONS
+                .iter()
+                .map(|&orthogonalization| {
+                    Gmres::new()
+                        .with_orthogonalization(orthogonalization)
+                        .with_restart(restart)
+                        .with_tolerance(1e-12)
+                        .solve(&a, &b)
+                        .unwrap()
+                })
+                .collect();
+
+            for result in &solutions {
+                prop_assert_eq!(result.status, GmresStatus::Converged);
+                prop_assert!(
+                    (&result.solution - &solutions[0].solution).norm() <= 1e-8 * b.norm().max(1.0)
+                );
+            }
+        }
+
+        #[test]
+        fn converges_on_diagonally_dominant_systems((a, b) in nonsymmetric_system(), restart in 1usize..=8) {
+            let result = Gmres::new()
+                .with_restart(restart)
+                .with_tolerance(1e-10)
+                .solve(&a, &b)
+                .unwrap();
+
+            prop_assert_eq!(result.status, GmresStatus::Converged);
+            prop_assert!((DMatrix::from(&a) * &result.solution - &b).norm() <= 1e-9 * b.norm().max(1.0));
+        }
+
+        #[test]
+        fn preconditioning_preserves_the_solution((a, b) in nonsymmetric_system()) {
+            let diagonal = DMatrix::from(&a).diagonal();
+            let jacobi = |r: &DVector<f64>| r.component_div(&diagonal);
+
+            let left = Gmres::new()
+                .with_tolerance(1e-12)
+                .with_left_preconditioner(&jacobi)
+                .solve(&a, &b)
+                .unwrap();
+            let right = Gmres::new()
+                .with_tolerance(1e-12)
+                .with_right_preconditioner(&jacobi)
+                .solve(&CscMatrix::from(a.clone()), &b)
+                .unwrap();
+
+            prop_assert_eq!(left.status, GmresStatus::Converged);
+            prop_assert_eq!(right.status, GmresStatus::Converged);
+            prop_assert!((&left.solution - &right.solution).norm() <= 1e-8 * b.norm().max(1.0));
+        }
+    }
+}
@@ -0,0 +1,549 @@
+use super::{matvec, FnOperator, Gmres, LinearOperator};
+use crate::error::{OperationError, OperationErrorKind};
+use nalgebra::{DMatrix, DVector, Dynamic, Matrix, RealField, Storage};
+
+/// Why a [`LowRankAdi`] solve stopped.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AdiStatus {
+    /// The residual norm dropped below the requested tolerance.
+    Converged,
+
+    /// The maximum number of iterations was performed without reaching the tolerance.
+    MaxIterationsReached,
+}
+
+/// The outcome of a [`LowRankAdi`] solve.
+#[derive(Debug, Clone)]
+pub struct LowRankAdiSolution<T> {
+    /// The low-rank factor `Z` of the approximate solution `X ≈ Z Zᵀ`. Every iteration appends
+    /// as many columns as `B` has.
+    pub factor: DMatrix<T>,
+
+    /// Why the solver stopped.
+    pub status: AdiStatus,
+
+    /// The number of ADI iterations that were performed.
+    pub iterations: usize,
+
+    /// The total number of GMRES iterations across all shifted solves, including those spent on
+    /// computing heuristic shifts.
+    pub linear_iterations: usize,
+
+    /// The spectral norm of the residual `A Z Zᵀ + Z Zᵀ Aᵀ + B Bᵀ` of `factor`, assuming that the
+    /// shifted systems were solved exactly.
+    pub residual_norm: T,
+}
+
+/// The shift parameters used by [`LowRankAdi`].
+///
+/// The convergence of the ADI iteration depends strongly on its (negative, real) shifts `pₖ`,
+/// which should cover the spectrum of `A`: every shift damps the residual components along the
+/// eigenvalues `λ` of `A` by a factor `|(pₖ - λ) / (pₖ + λ)|`.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdiShifts<T> {
+    /// Selects `count` shifts with the heuristic of Penzl (1999) from the real parts of Ritz
+    /// values of `A` and `A⁻¹`, which are computed with a few Arnoldi iterations before the ADI
+    /// iteration starts.
+    Heuristic {
+        /// The number of shifts to select.
+        count: usize,
+    },
+
+    /// Uses the given shifts, which must all be negative, cyclically.
+    Fixed(Vec<T>),
+}
+
+impl<T> Default for AdiShifts<T> {
+    /// Eight heuristic shifts.
+    fn default() -> Self {
+        Self::Heuristic { count: 8 }
+    }
+}
+
+/// Low-rank ADI solver for large sparse Lyapunov equations `A X + X Aᵀ = -B Bᵀ`.
+///
+/// For a stable matrix `A` (i.e. all its eigenvalues have negative real parts) and a right hand
+/// side `B` with few columns, the solution `X` is symmetric positive semi-definite and usually has
+/// a low numerical rank. The low-rank alternating direction implicit (LR-ADI) iteration computes a
+/// factor `Z` with `X ≈ Z Zᵀ` without ever forming the dense `n × n` matrix `X`, so that it can be
+/// applied to the Gramians of large sparse control systems.
+///
+/// Every iteration solves the shifted systems `(A + pₖ I) V = W` for the current residual factor
+/// `W`, one column at a time, with the configured [`Gmres`] solver. The residual of the iterate is
+/// then `W Wᵀ`, so its norm is available at the cost of a small dense eigenvalue problem. The
+/// solver stops once the residual norm satisfies `‖A Z Zᵀ + Z Zᵀ Aᵀ + B Bᵀ‖ <= tolerance ‖B Bᵀ‖`.
+///
+/// Only real shifts are used. These are optimal for symmetric `A`, and still converge (more
+/// slowly) for matrices with complex eigenvalues.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{
+///     cs::CsrMatrix,
+///     iterative::{AdiStatus, LowRankAdi},
+/// };
+///
+/// // The controllability Gramian of a discretized 1D heat equation, controlled at one end
+/// let n = 50;
+/// let a = CsrMatrix::from(&DMatrix::from_fn(n, n, |i, j| match (i, j) {
+///     _ if i == j => -2.0,
+///     _ if i == j + 1 || j == i + 1 => 1.0,
+///     _ => 0.0,
+/// }));
+/// let b = DMatrix::from_fn(n, 1, |i, _| if i == 0 { 1.0 } else { 0.0 });
+///
+/// let result = LowRankAdi::new().with_tolerance(1e-8).solve(&a, &b).unwrap();
+/// assert_eq!(result.status, AdiStatus::Converged);
+///
+/// let dense = DMatrix::from(&a);
+/// let x = &result.factor * result.factor.transpose();
+/// let residual = &dense * &x + &x * dense.transpose() + &b * b.transpose();
+/// assert!(residual.norm() < 1e-6);
+/// assert!(result.factor.ncols() < n);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LowRankAdi<'a, T> {
+    shifts: AdiShifts<T>,
+    max_iterations: usize,
+    tolerance: T,
+    linear_solver: Gmres<'a, T>,
+}
+
+impl<'a, T> Default for LowRankAdi<'a, T>
+where
+    T: RealField,
+{
+    fn default() -> Self {
+        Self {
+            shifts: AdiShifts::default(),
+            max_iterations: 100,
+            tolerance: nalgebra::convert(1e-10),
+            linear_solver: Gmres::new().with_tolerance(nalgebra::convert(1e-12)),
+        }
+    }
+}
+
+impl<'a, T> LowRankAdi<'a, T>
+where
+    T: RealField,
+{
+    /// Creates a new solver with the default [`AdiShifts`], at most 100 iterations, a relative
+    /// tolerance of `1e-10`, and a [`Gmres`] solver with a relative tolerance of `1e-12` for the
+    /// shifted systems.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the shift parameters.
+    #[must_use]
+    pub fn with_shifts(self, shifts: AdiShifts<T>) -> Self {
+        Self { shifts, ..self }
+    }
+
+    /// Sets the maximum number of ADI iterations.
+    #[must_use]
+    pub fn with_max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    /// Sets the tolerance on the residual norm, relative to `‖B Bᵀ‖`.
+    #[must_use]
+    pub fn with_tolerance(self, tolerance: T) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Sets the GMRES solver used for the shifted systems `(A + pₖ I) V = W`.
+    ///
+    /// The residual norm reported by the ADI iteration assumes exact solves, so the tolerance of
+    /// this solver should be well below the tolerance of the ADI iteration. A preconditioner of
+    /// the solver is applied to the shifted systems as is.
+    #[must_use]
+    pub fn with_linear_solver(self, linear_solver: Gmres<'a, T>) -> Self {
+        Self {
+            linear_solver,
+            ..self
+        }
+    }
+
+    /// Solves `A X + X Aᵀ = -B Bᵀ` for a low-rank factor `Z` of `X ≈ Z Zᵀ`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` is not
+    /// square, if `B` does not have as many rows as `A`, if fixed shifts are empty or not all
+    /// negative, or if no heuristic shifts could be found because `A` is not stable.
+    pub fn solve<A, S>(
+        &self,
+        a: &A,
+        b: &Matrix<T, Dynamic, Dynamic, S>,
+    ) -> Result<LowRankAdiSolution<T>, OperationError>
+    where
+        A: LinearOperator<T> + ?Sized,
+        S: Storage<T, Dynamic, Dynamic>,
+    {
+        let (nrows, ncols) = a.shape();
+
+        if nrows != ncols {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from("Lefthand matrix is not square."),
+            ));
+        }
+
+        if b.nrows() != nrows {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!(
+                    "B has {} rows, but {} rows are needed for this equation.",
+                    b.nrows(),
+                    nrows
+                ),
+            ));
+        }
+
+        let span = span!(
+            "lyapunov_adi",
+            n = nrows,
+            iterations = tracing::field::Empty
+        );
+
+        let mut linear_iterations = 0;
+        let shifts = match &self.shifts {
+            AdiShifts::Fixed(shifts) => {
+                if shifts.is_empty() || shifts.iter().any(|p| *p >= T::zero()) {
+                    return Err(OperationError::from_kind_and_message(
+                        OperationErrorKind::InvalidPattern,
+                        String::from("The ADI shifts must be non-empty and all negative."),
+                    ));
+                }
+
+                shifts.clone()
+            }
+            AdiShifts::Heuristic { count } => {
+                self.heuristic_shifts(a, b, *count, &mut linear_iterations)?
+            }
+        };
+
+        let mut w = b.clone_owned();
+        let mut columns = Vec::new();
+        let mut norm = gram_norm(&w);
+        let threshold = norm.clone() * self.tolerance.clone();
+        let mut iterations = 0;
+
+        let status = loop {
+            if norm <= threshold {
+                break AdiStatus::Converged;
+            }
+
+            if iterations >= self.max_iterations {
+                break AdiStatus::MaxIterationsReached;
+            }
+
+            let p = shifts[iterations % shifts.len()].clone();
+            let shifted = FnOperator::new(nrows, ncols, |x: &DVector<T>, y: &mut DVector<T>| {
+                a.apply_to(x, y);
+                y.axpy(p.clone(), x, T::one());
+            });
+
+            // Vₖ = (A + pₖ I)⁻¹ Wₖ₋₁, Wₖ = Wₖ₋₁ - 2 pₖ Vₖ, Zₖ = [Zₖ₋₁, √(-2 pₖ) Vₖ]
+            let two: T = nalgebra::convert(2.0);
+            let scale = (-two.clone() * p.clone()).sqrt();
+
+            for mut w_j in w.column_iter_mut() {
+                let v = self.linear_solver.solve(&shifted, &w_j.clone_owned())?;
+                linear_iterations += v.iterations;
+
+                w_j.axpy(-two.clone() * p.clone(), &v.solution, T::one());
+                columns.push(v.solution * scale.clone());
+            }
+
+            iterations += 1;
+            norm = gram_norm(&w);
+        };
+
+        record!(span, iterations = iterations);
+
+        let factor = if columns.is_empty() {
+            DMatrix::zeros(nrows, 0)
+        } else {
+            DMatrix::from_columns(&columns)
+        };
+
+        Ok(LowRankAdiSolution {
+            factor,
+            status,
+            iterations,
+            linear_iterations,
+            residual_norm: norm,
+        })
+    }
+
+    /// Selects `count` shifts with Penzl's heuristic from the Ritz values of `A` and `A⁻¹`.
+    fn heuristic_shifts<A, S>(
+        &self,
+        a: &A,
+        b: &Matrix<T, Dynamic, Dynamic, S>,
+        count: usize,
+        linear_iterations: &mut usize,
+    ) -> Result<Vec<T>, OperationError>
+    where
+        A: LinearOperator<T> + ?Sized,
+        S: Storage<T, Dynamic, Dynamic>,
+    {
+        let n = a.nrows();
+        let sum = b.column_sum();
+        let start = if sum.iter().any(|b_i| !b_i.is_zero()) {
+            sum
+        } else {
+            DVector::from_element(n, T::one())
+        };
+
+        let mut candidates = ritz_values(|x| Ok(matvec(a, x)), &start, n.min(2 * count))?;
+        let inverse = ritz_values(
+            |x| {
+                let solution = self.linear_solver.solve(a, x)?;
+                *linear_iterations += solution.iterations;
+                Ok(solution.solution)
+            },
+            &start,
+            n.min(count),
+        )?;
+
+        candidates.extend(
+            inverse
+                .into_iter()
+                .filter(|theta| !theta.is_zero())
+                .map(|theta| T::one() / theta),
+        );
+        candidates.retain(|lambda| *lambda < T::zero());
+
+        if candidates.is_empty() {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from(
+                    "No Ritz value of A has a negative real part, so A does not appear to be \
+                     stable.",
+                ),
+            ));
+        }
+
+        // The largest damping factor max_λ ∏ |(pⱼ - λ) / (pⱼ + λ)| over all candidates λ
+        let damping = |shifts: &[T], lambda: &T| {
+            shifts.iter().fold(T::one(), |product, p| {
+                product * ((p.clone() - lambda.clone()) / (p.clone() + lambda.clone())).abs()
+            })
+        };
+        let worst = |shifts: &[T]| {
+            candidates
+                .iter()
+                .map(|lambda| (damping(shifts, lambda), lambda.clone()))
+                .fold(None, |worst: Option<(T, T)>, candidate| match worst {
+                    Some(worst) if worst.0 >= candidate.0 => Some(worst),
+                    _ => Some(candidate),
+                })
+                .expect("There is at least one candidate.")
+        };
+
+        // Start with the single best shift, then repeatedly add the candidate that is damped least
+        let mut shifts = vec![
+            candidates
+                .iter()
+                .map(|p| (worst(std::slice::from_ref(p)).0, p.clone()))
+                .fold(None, |best: Option<(T, T)>, candidate| match best {
+                    Some(best) if best.0 <= candidate.0 => Some(best),
+                    _ => Some(candidate),
+                })
+                .expect("There is at least one candidate.")
+                .1,
+        ];
+
+        while shifts.len() < count {
+            let (factor, lambda) = worst(&shifts);
+
+            if factor.is_zero() {
+                break;
+            }
+
+            shifts.push(lambda);
+        }
+
+        Ok(shifts)
+    }
+}
+
+/// Computes the spectral norm `‖W Wᵀ‖ = λₘₐₓ(Wᵀ W)`.
+pub(super) fn gram_norm<T: RealField>(w: &DMatrix<T>) -> T {
+    if w.ncols() == 0 {
+        return T::zero();
+    }
+
+    w.tr_mul(w).symmetric_eigenvalues().max()
+}
+
+/// Computes the real parts of the Ritz values of `steps` Arnoldi iterations with the operator
+/// `apply`, starting from `start`.
+fn ritz_values<T, F>(
+    mut apply: F,
+    start: &DVector<T>,
+    steps: usize,
+) -> Result<Vec<T>, OperationError>
+where
+    T: RealField,
+    F: FnMut(&DVector<T>) -> Result<DVector<T>, OperationError>,
+{
+    let start_norm = start.norm();
+
+    if steps == 0 || start_norm.is_zero() {
+        return Ok(Vec::new());
+    }
+
+    let mut basis = vec![start / start_norm];
+    let mut hessenberg = DMatrix::zeros(steps + 1, steps);
+    let mut k = 0;
+
+    while k < steps {
+        let mut w = apply(&basis[k])?;
+        let w_norm = w.norm();
+
+        for (i, v) in basis.iter().enumerate() {
+            let h = v.dot(&w);
+            w.axpy(-h.clone(), v, T::one());
+            hessenberg[(i, k)] = h;
+        }
+
+        let h_next = w.norm();
+        k += 1;
+
+        // The basis spans an invariant subspace, whose Ritz values are exact eigenvalues
+        if h_next <= T::default_epsilon().sqrt() * w_norm {
+            break;
+        }
+
+        hessenberg[(k, k - 1)] = h_next.clone();
+        basis.push(w / h_next);
+    }
+
+    let square = DMatrix::from_fn(k, k, |i, j| hessenberg[(i, j)].clone());
+
+    Ok(square
+        .complex_eigenvalues()
+        .iter()
+        .map(|lambda| lambda.re.clone())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cs::CsrMatrix;
+
+    /// Solves the Lyapunov equation densely through its Kronecker form
+    /// `(I ⊗ A + A ⊗ I) vec(X) = -vec(B Bᵀ)`.
+    fn dense_lyapunov(a: &DMatrix<f64>, b: &DMatrix<f64>) -> DMatrix<f64> {
+        let n = a.nrows();
+        let identity = DMatrix::<f64>::identity(n, n);
+        let kronecker = identity.kronecker(a) + a.kronecker(&identity);
+        let rhs = -(b * b.transpose());
+        let x = kronecker
+            .lu()
+            .solve(&DVector::from_column_slice(rhs.as_slice()))
+            .unwrap();
+
+        DMatrix::from_column_slice(n, n, x.as_slice())
+    }
+
+    fn system() -> (DMatrix<f64>, DMatrix<f64>) {
+        let a = DMatrix::from_fn(12, 12, |i, j| match (i, j) {
+            _ if i == j => -4.0 - i as f64,
+            _ if j == i + 1 => 1.5,
+            _ if i == j + 1 => -0.5,
+            _ if i == j + 3 => 1.0,
+            _ => 0.0,
+        });
+        let b = DMatrix::from_fn(12, 2, |i, j| ((i + 2 * j) % 5) as f64 - 1.0);
+
+        (a, b)
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let a = CsrMatrix::from(&-DMatrix::<f64>::identity(3, 3));
+
+        for (b, shifts) in [
+            (DMatrix::zeros(2, 1), AdiShifts::default()),
+            (DMatrix::zeros(3, 1), AdiShifts::Fixed(vec![])),
+            (DMatrix::zeros(3, 1), AdiShifts::Fixed(vec![-1.0, 0.5])),
+        ] {
+            let err = LowRankAdi::new()
+                .with_shifts(shifts)
+                .solve(&a, &b)
+                .unwrap_err();
+            assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+        }
+
+        let unstable = CsrMatrix::<f64>::identity(3);
+        let err = LowRankAdi::new()
+            .solve(&unstable, &DMatrix::from_element(3, 1, 1.0))
+            .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn zero_right_hand_side_converges_immediately() {
+        let a = CsrMatrix::from(&-DMatrix::<f64>::identity(4, 4));
+        let result = LowRankAdi::new().solve(&a, &DMatrix::zeros(4, 2)).unwrap();
+
+        assert_eq!(result.status, AdiStatus::Converged);
+        assert_eq!(result.iterations, 0);
+        assert_eq!(result.factor.shape(), (4, 0));
+    }
+
+    #[test]
+    fn agrees_with_the_dense_solution() {
+        let (a, b) = system();
+        let expected = dense_lyapunov(&a, &b);
+
+        for shifts in [
+            AdiShifts::default(),
+            AdiShifts::Heuristic { count: 3 },
+            AdiShifts::Fixed(vec![-4.0, -8.0, -16.0]),
+        ] {
+            let result = LowRankAdi::new()
+                .with_shifts(shifts.clone())
+                .solve(&CsrMatrix::from(&a), &b)
+                .unwrap();
+            let x = &result.factor * result.factor.transpose();
+            let residual = &a * &x + &x * a.transpose() + &b * b.transpose();
+
+            assert_eq!(result.status, AdiStatus::Converged, "{:?}", shifts);
+            assert_eq!(result.factor.ncols(), 2 * result.iterations);
+            assert!((x - &expected).norm() < 1e-8 * expected.norm());
+            assert!((residual.singular_values().max() - result.residual_norm).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn reports_the_residual_when_not_converged() {
+        let (a, b) = system();
+        let result = LowRankAdi::new()
+            .with_shifts(AdiShifts::Fixed(vec![-1.0]))
+            .with_max_iterations(2)
+            .solve(&CsrMatrix::from(&a), &b)
+            .unwrap();
+        let x = &result.factor * result.factor.transpose();
+        let residual = &a * &x + &x * a.transpose() + &b * b.transpose();
+
+        assert_eq!(result.status, AdiStatus::MaxIterationsReached);
+        assert_eq!(result.iterations, 2);
+        let spectral = residual.singular_values().max();
+        assert!((spectral - result.residual_norm).abs() < 1e-8 * spectral);
+    }
+}
@@ -0,0 +1,85 @@
+//! Iterative solvers for sparse linear systems.
+//!
+//! Iterative solvers only ever access the system matrix through matrix-vector products (see
+//! [`LinearOperator`]), which makes them suitable for systems that are too large to factorize, or whose factors would fill
+//! in too much. In return, they only produce an approximate solution, and how quickly (or
+//! whether) they converge depends strongly on the properties of the matrix and on the quality of
+//! the preconditioner.
+//!
+//! The solvers are configured through builder-style structs (e.g. [`Gmres`]), and return the
+//! best solution they found alongside a status describing why they stopped. Not converging is
+//! therefore not considered to be an error: the caller decides whether the final residual is good
+//! enough. Errors are only returned for invalid inputs, e.g. mismatched dimensions.
+//!
+//! Preconditioners implement the [`Preconditioner`] trait, so that any solver accepts both the
+//! preconditioners provided by this crate and user-defined ones.
+//!
+//! Currently available solvers:
+//!
+//! - [`Gmres`]: restarted GMRES(m) for general (non-symmetric) square systems.
+//! - [`Fgmres`]: flexible GMRES(m), for preconditioners that change between iterations.
+//!
+//! Systems of non-linear equations can be solved with [`NewtonKrylov`], which uses GMRES for the
+//! linear system of every Newton iteration, and fixed-point iterations can be sped up with
+//! [`AndersonAcceleration`]. Large sparse Lyapunov equations can be solved for low-rank factors of
+//! their solution with [`LowRankAdi`], which uses GMRES for its shifted linear systems. Algebraic
+//! Riccati equations (e.g. for LQR design) are solved on top of it with [`NewtonAdi`].
+
+mod anderson;
+mod fgmres;
+mod gmres;
+mod lyapunov;
+mod newton_krylov;
+mod operator;
+mod preconditioner;
+mod riccati;
+
+pub use anderson::*;
+pub use fgmres::*;
+pub use gmres::*;
+pub use lyapunov::*;
+pub use newton_krylov::*;
+pub use operator::*;
+pub use preconditioner::*;
+pub use riccati::*;
+
+use crate::error::{OperationError, OperationErrorKind};
+use nalgebra::{DVector, RealField};
+
+/// Computes `A x`.
+fn matvec<T, A>(a: &A, x: &DVector<T>) -> DVector<T>
+where
+    T: RealField,
+    A: LinearOperator<T> + ?Sized,
+{
+    let mut y = DVector::zeros(a.nrows());
+    a.apply_to(x, &mut y);
+    y
+}
+
+/// Checks that the system `A x = b` is square, and that `b` and `x` have as many rows as `A`.
+fn check_dimensions(
+    (nrows, ncols): (usize, usize),
+    b_rows: usize,
+    x_rows: usize,
+) -> Result<(), OperationError> {
+    if nrows != ncols {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("Lefthand matrix is not square."),
+        ));
+    }
+
+    if b_rows != nrows || x_rows != nrows {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!(
+                "The right hand side has {} rows and the initial guess has {} rows, but {} rows \
+                 are needed to solve this system.",
+                b_rows, x_rows, nrows
+            ),
+        ));
+    }
+
+    Ok(())
+}
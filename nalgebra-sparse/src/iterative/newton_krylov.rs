@@ -0,0 +1,497 @@
+use super::{Gmres, LinearOperator};
+use crate::error::{OperationError, OperationErrorKind};
+use nalgebra::{DVector, RealField};
+
+/// Why a [`NewtonKrylov`] solve stopped.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NewtonKrylovStatus {
+    /// The residual norm dropped below the requested tolerance.
+    Converged,
+
+    /// The maximum number of Newton iterations was performed without reaching the tolerance.
+    MaxIterationsReached,
+
+    /// The line search could not find a step that sufficiently decreases the residual norm. This
+    /// typically means that the Newton direction is not a descent direction, e.g. because the
+    /// Jacobian is inaccurate or (nearly) singular, or because the linear solves were too loose.
+    LineSearchFailed,
+}
+
+/// The outcome of a [`NewtonKrylov`] solve.
+#[derive(Debug, Clone)]
+pub struct NewtonKrylovSolution<T> {
+    /// The best approximate solution that was found.
+    pub solution: DVector<T>,
+
+    /// Why the solver stopped.
+    pub status: NewtonKrylovStatus,
+
+    /// The number of Newton iterations (i.e. Jacobian evaluations) that were performed.
+    pub iterations: usize,
+
+    /// The total number of GMRES iterations across all Newton iterations.
+    pub linear_iterations: usize,
+
+    /// The norm of the residual `F(x)` of `solution`.
+    pub residual_norm: T,
+}
+
+/// How accurately [`NewtonKrylov`] solves the linear system of every Newton iteration.
+///
+/// The linear system `J(x) s = -F(x)` is solved to a relative tolerance `η`, the forcing term.
+/// Solving it accurately while far away from the solution is wasted work, as the Newton model is
+/// inaccurate there anyway, whereas loose solves close to the solution destroy the fast local
+/// convergence of Newton's method.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ForcingTerm<T> {
+    /// Uses the same forcing term in every iteration, which gives linear convergence at a rate
+    /// of roughly `η`.
+    Constant(T),
+
+    /// Chooses the forcing term adaptively from the decrease of the residual norm, following
+    /// "Choice 2" of Eisenstat and Walker (1996):
+    /// `ηₖ = gamma (‖F(xₖ)‖ / ‖F(xₖ₋₁)‖)^alpha`, safeguarded against decreasing too quickly and
+    /// capped at `max`.
+    ///
+    /// With `alpha = 2`, this recovers the quadratic local convergence of Newton's method.
+    EisenstatWalker {
+        /// The initial (and maximum) forcing term, in `[0, 1)`.
+        max: T,
+
+        /// The scaling factor, in `(0, 1]`.
+        gamma: T,
+
+        /// The exponent, in `(1, 2]`.
+        alpha: T,
+    },
+}
+
+impl<T> Default for ForcingTerm<T>
+where
+    T: RealField,
+{
+    /// Eisenstat–Walker forcing terms with `max = 0.9`, `gamma = 0.9` and `alpha = 2`.
+    fn default() -> Self {
+        Self::EisenstatWalker {
+            max: nalgebra::convert(0.9),
+            gamma: nalgebra::convert(0.9),
+            alpha: nalgebra::convert(2.0),
+        }
+    }
+}
+
+/// How [`NewtonKrylov`] globalizes the Newton iteration.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineSearch {
+    /// Always takes the full Newton step. This converges quickly from a good initial guess, but
+    /// may diverge from a poor one.
+    None,
+
+    /// Halves the step until the residual norm decreases sufficiently, giving up after
+    /// `max_steps` halvings.
+    Backtracking {
+        /// The maximum number of times the step is halved.
+        max_steps: usize,
+    },
+}
+
+impl Default for LineSearch {
+    /// Backtracking with at most 10 halvings of the step.
+    fn default() -> Self {
+        Self::Backtracking { max_steps: 10 }
+    }
+}
+
+/// Inexact Newton–Krylov solver for square systems of non-linear equations `F(x) = 0`.
+///
+/// Every Newton iteration solves the linear system `J(x) s = -F(x)` for the Jacobian `J(x)` of
+/// `F` approximately with [`Gmres`], to the relative tolerance given by the [`ForcingTerm`], and
+/// then moves along `s` as far as the [`LineSearch`] allows. The solver stops once the residual
+/// norm satisfies `‖F(x)‖ <= tolerance * ‖F(x₀)‖`.
+///
+/// The Jacobian is given as a function of `x` that returns any [`LinearOperator`]: an assembled
+/// sparse matrix, or a Jacobian-vector product in an [`FnOperator`](super::FnOperator) for
+/// matrix-free solves. The inner GMRES solver (e.g. its restart length or its preconditioner)
+/// can be configured with [`NewtonKrylov::with_linear_solver`]; its tolerance is overridden by the
+/// forcing term.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::DVector;
+/// use nalgebra_sparse::{
+///     coo::CooMatrix,
+///     cs::CsrMatrix,
+///     iterative::{NewtonKrylov, NewtonKrylovStatus},
+/// };
+///
+/// // F(x) = [x₀² + x₁ - 3, x₀ - x₁² + 3], with a solution at (1, 2)
+/// let residual = |x: &DVector<f64>| {
+///     DVector::from_vec(vec![x[0] * x[0] + x[1] - 3.0, x[0] - x[1] * x[1] + 3.0])
+/// };
+/// let jacobian = |x: &DVector<f64>| {
+///     let mut coo = CooMatrix::new(2, 2);
+///     coo.push(0, 0, 2.0 * x[0]);
+///     coo.push(0, 1, 1.0);
+///     coo.push(1, 0, 1.0);
+///     coo.push(1, 1, -2.0 * x[1]);
+///     CsrMatrix::from(coo)
+/// };
+///
+/// let result = NewtonKrylov::new()
+///     .with_tolerance(1e-12)
+///     .solve(residual, jacobian, DVector::from_vec(vec![1.5, 2.5]))
+///     .unwrap();
+///
+/// assert_eq!(result.status, NewtonKrylovStatus::Converged);
+/// assert!((result.solution - DVector::from_vec(vec![1.0, 2.0])).norm() < 1e-10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct NewtonKrylov<'a, T> {
+    max_iterations: usize,
+    tolerance: T,
+    forcing_term: ForcingTerm<T>,
+    line_search: LineSearch,
+    linear_solver: Gmres<'a, T>,
+}
+
+impl<'a, T> Default for NewtonKrylov<'a, T>
+where
+    T: RealField,
+{
+    fn default() -> Self {
+        Self {
+            max_iterations: 50,
+            tolerance: nalgebra::convert(1e-8),
+            forcing_term: ForcingTerm::default(),
+            line_search: LineSearch::default(),
+            linear_solver: Gmres::default(),
+        }
+    }
+}
+
+impl<'a, T> NewtonKrylov<'a, T>
+where
+    T: RealField,
+{
+    /// Creates a new solver with at most 50 Newton iterations, a relative tolerance of `1e-8`,
+    /// the default [`ForcingTerm`] and [`LineSearch`], and a default [`Gmres`] solver.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of Newton iterations.
+    #[must_use]
+    pub fn with_max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    /// Sets the tolerance on the residual norm, relative to the residual norm of the initial
+    /// guess.
+    #[must_use]
+    pub fn with_tolerance(self, tolerance: T) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Sets how accurately the linear system of every Newton iteration is solved.
+    #[must_use]
+    pub fn with_forcing_term(self, forcing_term: ForcingTerm<T>) -> Self {
+        Self {
+            forcing_term,
+            ..self
+        }
+    }
+
+    /// Sets the line search used to globalize the Newton iteration.
+    #[must_use]
+    pub fn with_line_search(self, line_search: LineSearch) -> Self {
+        Self {
+            line_search,
+            ..self
+        }
+    }
+
+    /// Sets the GMRES solver used for the linear systems. Its tolerance is ignored.
+    #[must_use]
+    pub fn with_linear_solver(self, linear_solver: Gmres<'a, T>) -> Self {
+        Self {
+            linear_solver,
+            ..self
+        }
+    }
+
+    /// Solves `F(x) = 0`, starting from the initial guess `x0`.
+    ///
+    /// `residual` computes `F(x)`, and `jacobian` returns the Jacobian of `F` at `x`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `residual`
+    /// does not return a vector with as many rows as `x0`, or if `jacobian` does not return a
+    /// square operator of matching size.
+    pub fn solve<F, J, A>(
+        &self,
+        residual: F,
+        jacobian: J,
+        x0: DVector<T>,
+    ) -> Result<NewtonKrylovSolution<T>, OperationError>
+    where
+        F: Fn(&DVector<T>) -> DVector<T>,
+        J: Fn(&DVector<T>) -> A,
+        A: LinearOperator<T>,
+    {
+        let n = x0.nrows();
+        let evaluate = |x: &DVector<T>| {
+            let f = residual(x);
+
+            if f.nrows() == n {
+                Ok(f)
+            } else {
+                Err(OperationError::from_kind_and_message(
+                    OperationErrorKind::InvalidPattern,
+                    format!(
+                        "The residual has {} rows, but the unknowns have {} rows.",
+                        f.nrows(),
+                        n
+                    ),
+                ))
+            }
+        };
+
+        let span = span!("newton_krylov", n = n, iterations = tracing::field::Empty);
+
+        let mut x = x0;
+        let mut f = evaluate(&x)?;
+        let mut norm = f.norm();
+        let threshold = norm.clone() * self.tolerance.clone();
+
+        let mut eta = match &self.forcing_term {
+            ForcingTerm::Constant(eta) => eta.clone(),
+            ForcingTerm::EisenstatWalker { max, .. } => max.clone(),
+        };
+        let mut iterations = 0;
+        let mut linear_iterations = 0;
+
+        let status = loop {
+            if norm <= threshold {
+                break NewtonKrylovStatus::Converged;
+            }
+
+            if iterations >= self.max_iterations {
+                break NewtonKrylovStatus::MaxIterationsReached;
+            }
+
+            iterations += 1;
+
+            let linear = self
+                .linear_solver
+                .clone()
+                .with_tolerance(eta.clone())
+                .solve(&jacobian(&x), &-&f)?;
+            linear_iterations += linear.iterations;
+            let step = linear.solution;
+
+            // Backtrack until the residual norm decreases sufficiently, relaxing the forcing term
+            // to match the shortened step (Eisenstat and Walker, 1994).
+            let sufficient_decrease: T = nalgebra::convert(1e-4);
+            let max_steps = match self.line_search {
+                LineSearch::None => 0,
+                LineSearch::Backtracking { max_steps } => max_steps,
+            };
+
+            let mut lambda = T::one();
+            let mut accepted = None;
+
+            for backtracks in 0..=max_steps {
+                let candidate = &x + &step * lambda.clone();
+                let f_candidate = evaluate(&candidate)?;
+                let norm_candidate = f_candidate.norm();
+                let required = (T::one() - sufficient_decrease.clone() * (T::one() - eta.clone()))
+                    * norm.clone();
+
+                if self.line_search == LineSearch::None || norm_candidate <= required {
+                    accepted = Some((candidate, f_candidate, norm_candidate));
+                    break;
+                }
+
+                if backtracks < max_steps {
+                    let half: T = nalgebra::convert(0.5);
+                    lambda *= half.clone();
+                    eta = T::one() - half * (T::one() - eta);
+                }
+            }
+
+            let (candidate, f_candidate, norm_candidate) = match accepted {
+                Some(accepted) => accepted,
+                None => break NewtonKrylovStatus::LineSearchFailed,
+            };
+
+            match &self.forcing_term {
+                ForcingTerm::Constant(constant) => eta = constant.clone(),
+                ForcingTerm::EisenstatWalker { max, gamma, alpha } => {
+                    let ratio = norm_candidate.clone() / norm.clone();
+                    let mut next = gamma.clone() * ratio.powf(alpha.clone());
+
+                    // Keep the forcing term from dropping too quickly while far from the solution,
+                    // and from oversolving the final iterations.
+                    let previous = gamma.clone() * eta.clone().powf(alpha.clone());
+                    if previous > nalgebra::convert(0.1) {
+                        next = next.max(previous);
+                    }
+
+                    let oversolve = norm_candidate.clone() * nalgebra::convert(2.0);
+                    if oversolve > T::zero() {
+                        next = next.max(threshold.clone() / oversolve);
+                    }
+
+                    eta = next.min(max.clone());
+                }
+            }
+
+            x = candidate;
+            f = f_candidate;
+            norm = norm_candidate;
+        };
+
+        record!(span, iterations = iterations);
+
+        Ok(NewtonKrylovSolution {
+            solution: x,
+            status,
+            iterations,
+            linear_iterations,
+            residual_norm: norm,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cs::CsrMatrix, iterative::FnOperator};
+    use nalgebra::DMatrix;
+
+    /// `F(x) = A x + x³ - b` for the 1D Laplacian `A`, alongside its sparse Jacobian.
+    fn cubic_laplacian(
+        n: usize,
+    ) -> (
+        impl Fn(&DVector<f64>) -> DVector<f64>,
+        impl Fn(&DVector<f64>) -> CsrMatrix<f64>,
+    ) {
+        let laplacian = DMatrix::from_fn(n, n, |i, j| match (i, j) {
+            _ if i == j => 2.0,
+            _ if i == j + 1 || j == i + 1 => -1.0,
+            _ => 0.0,
+        });
+        let b = DVector::from_fn(n, |i, _| 1.0 + i as f64);
+
+        let a = laplacian.clone();
+        let residual = move |x: &DVector<f64>| &a * x + x.map(|x_i| x_i.powi(3)) - &b;
+        let jacobian = move |x: &DVector<f64>| {
+            let mut j = laplacian.clone();
+            j.set_diagonal(&(laplacian.diagonal() + x.map(|x_i| 3.0 * x_i * x_i)));
+            CsrMatrix::from(&j)
+        };
+
+        (residual, jacobian)
+    }
+
+    #[test]
+    fn rejects_mismatched_residuals() {
+        let err = NewtonKrylov::new()
+            .solve(
+                |_: &DVector<f64>| DVector::zeros(3),
+                |_: &DVector<f64>| CsrMatrix::<f64>::identity(2),
+                DVector::from_element(2, 1.0),
+            )
+            .unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn forcing_terms_converge_on_a_cubic_laplacian() {
+        let (residual, jacobian) = cubic_laplacian(10);
+
+        for forcing_term in [ForcingTerm::Constant(1e-2), ForcingTerm::default()] {
+            let result = NewtonKrylov::new()
+                .with_tolerance(1e-12)
+                .with_forcing_term(forcing_term)
+                .solve(&residual, &jacobian, DVector::zeros(10))
+                .unwrap();
+            assert_eq!(result.status, NewtonKrylovStatus::Converged);
+            assert_eq!(result.status, NewtonKrylovStatus::Converged);
+            assert!(residual(&result.solution).norm() <= 1e-10);
+        }
+
+        // Constant loose forcing terms need many more Newton iterations
+        let loose = NewtonKrylov::new()
+            .with_tolerance(1e-12)
+            .with_forcing_term(ForcingTerm::Constant(0.5))
+            .solve(&residual, &jacobian, DVector::zeros(10))
+            .unwrap();
+        let adaptive = NewtonKrylov::new()
+            .with_tolerance(1e-12)
+            .solve(&residual, &jacobian, DVector::zeros(10))
+            .unwrap();
+
+        assert!(adaptive.iterations < loose.iterations);
+    }
+
+    #[test]
+    fn accepts_jacobian_vector_products() {
+        let (residual, jacobian) = cubic_laplacian(6);
+
+        // A matrix-free Jacobian, built from the assembled one for comparison
+        let jvp = |x: &DVector<f64>| {
+            let j = jacobian(x);
+            FnOperator::new(6, 6, move |v: &DVector<f64>, y: &mut DVector<f64>| {
+                j.apply_to(v, y)
+            })
+        };
+
+        let assembled = NewtonKrylov::new()
+            .with_tolerance(1e-12)
+            .solve(&residual, &jacobian, DVector::zeros(6))
+            .unwrap();
+        let matrix_free = NewtonKrylov::new()
+            .with_tolerance(1e-12)
+            .solve(&residual, jvp, DVector::zeros(6))
+            .unwrap();
+
+        assert_eq!(matrix_free.status, NewtonKrylovStatus::Converged);
+        assert_eq!(matrix_free.iterations, assembled.iterations);
+        assert!((matrix_free.solution - assembled.solution).norm() < 1e-10);
+    }
+
+    #[test]
+    fn line_search_globalizes_arctangent() {
+        // Newton's method on atan(x) = 0 diverges from |x₀| > 1.39
+        let residual = |x: &DVector<f64>| x.map(f64::atan);
+        let jacobian = |x: &DVector<f64>| {
+            CsrMatrix::from(&DMatrix::from_element(1, 1, 1.0 / (1.0 + x[0] * x[0])))
+        };
+        let x0 = DVector::from_element(1, 10.0);
+
+        let full = NewtonKrylov::new()
+            .with_line_search(LineSearch::None)
+            .with_max_iterations(5)
+            .solve(residual, jacobian, x0.clone())
+            .unwrap();
+        assert_ne!(full.status, NewtonKrylovStatus::Converged);
+
+        let result = NewtonKrylov::new()
+            .with_tolerance(1e-12)
+            .solve(residual, jacobian, x0)
+            .unwrap();
+        assert_eq!(result.status, NewtonKrylovStatus::Converged);
+        assert!(result.solution[0].abs() < 1e-10);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{
+    cs::{Compression, CsMatrix},
+    frozen::FrozenCsrMatrix,
+};
+use nalgebra::{DVector, Scalar};
+use num_traits::Zero;
+use std::{
+    borrow::Borrow,
+    fmt,
+    ops::{AddAssign, Mul},
+};
+
+/// A linear operator `A`, i.e. anything that can compute matrix-vector products `A x` and
+/// `Aᵀ x`.
+///
+/// The iterative solvers only ever access the system matrix through this trait, so they accept
+/// operators that are never assembled into a sparse matrix, e.g. compositions such as
+/// `Aᵀ A + λ I` or matrix-free discretizations. The trait is implemented by:
+///
+/// - Every [`CsMatrix`], i.e. both [`CsrMatrix`](crate::cs::CsrMatrix) and
+///   [`CscMatrix`](crate::cs::CscMatrix), as well as their views.
+/// - [`FrozenCsrMatrix`], which forwards to the matrix it wraps.
+/// - [`FnOperator`], which wraps closures computing the products.
+pub trait LinearOperator<T>
+where
+    T: Scalar,
+{
+    /// The shape of the operator, as `(nrows, ncols)`.
+    fn shape(&self) -> (usize, usize);
+
+    /// The number of rows of the operator.
+    fn nrows(&self) -> usize {
+        self.shape().0
+    }
+
+    /// The number of columns of the operator.
+    fn ncols(&self) -> usize {
+        self.shape().1
+    }
+
+    /// Overwrites `y` with `A x`.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `x` does not have `ncols` rows or `y` does not have `nrows` rows.
+    fn apply_to(&self, x: &DVector<T>, y: &mut DVector<T>);
+
+    /// Overwrites `y` with `Aᵀ x`.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `x` does not have `nrows` rows or `y` does not have `ncols` rows, or if the
+    /// operator does not support transposed products.
+    fn apply_transpose_to(&self, x: &DVector<T>, y: &mut DVector<T>);
+}
+
+impl<T, MO, MI, D, C> LinearOperator<T> for CsMatrix<T, MO, MI, D, C>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    fn shape(&self) -> (usize, usize) {
+        CsMatrix::shape(self)
+    }
+
+    fn apply_to(&self, x: &DVector<T>, y: &mut DVector<T>) {
+        assert_eq!(
+            x.nrows(),
+            self.ncols(),
+            "x must have as many rows as A has columns."
+        );
+        assert_eq!(y.nrows(), self.nrows(), "y must have as many rows as A.");
+
+        y.fill(T::zero());
+
+        for (major, minor, value) in self.triplet_iter() {
+            let row = C::nmajor(major, minor);
+            let col = C::nminor(major, minor);
+
+            y[row] += value.clone() * x[col].clone();
+        }
+    }
+
+    fn apply_transpose_to(&self, x: &DVector<T>, y: &mut DVector<T>) {
+        assert_eq!(x.nrows(), self.nrows(), "x must have as many rows as A.");
+        assert_eq!(
+            y.nrows(),
+            self.ncols(),
+            "y must have as many rows as A has columns."
+        );
+
+        y.fill(T::zero());
+
+        for (major, minor, value) in self.triplet_iter() {
+            let row = C::nmajor(major, minor);
+            let col = C::nminor(major, minor);
+
+            y[col] += value.clone() * x[row].clone();
+        }
+    }
+}
+
+impl<T> LinearOperator<T> for FrozenCsrMatrix<T>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+{
+    fn shape(&self) -> (usize, usize) {
+        self.matrix().shape()
+    }
+
+    fn apply_to(&self, x: &DVector<T>, y: &mut DVector<T>) {
+        self.matrix().apply_to(x, y)
+    }
+
+    fn apply_transpose_to(&self, x: &DVector<T>, y: &mut DVector<T>) {
+        self.matrix().apply_transpose_to(x, y)
+    }
+}
+
+/// A [`LinearOperator`] defined by closures that compute its products.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{
+///     cs::CsrMatrix,
+///     iterative::{FnOperator, Gmres, GmresStatus, LinearOperator},
+/// };
+///
+/// // Solve the regularized normal equations (Aᵀ A + λ I) x = Aᵀ b without forming Aᵀ A
+/// let a = CsrMatrix::from(&DMatrix::from_row_slice(3, 2, &[1.0, 0.0, 1.0, 1.0, 0.0, 2.0]));
+/// let lambda = 0.1;
+///
+/// let normal = FnOperator::new(2, 2, |x: &DVector<f64>, y: &mut DVector<f64>| {
+///     let mut ax = DVector::zeros(3);
+///     a.apply_to(x, &mut ax);
+///     a.apply_transpose_to(&ax, y);
+///     y.axpy(lambda, x, 1.0);
+/// });
+///
+/// let b = DVector::from_vec(vec![1.0, 2.0, 3.0]);
+/// let mut rhs = DVector::zeros(2);
+/// a.apply_transpose_to(&b, &mut rhs);
+///
+/// let result = Gmres::new().with_tolerance(1e-12).solve(&normal, &rhs).unwrap();
+/// assert_eq!(result.status, GmresStatus::Converged);
+/// ```
+#[derive(Clone)]
+pub struct FnOperator<F, G> {
+    shape: (usize, usize),
+    apply: F,
+    apply_transpose: Option<G>,
+}
+
+impl<F, T> FnOperator<F, fn(&DVector<T>, &mut DVector<T>)> {
+    /// Creates an operator of shape `(nrows, ncols)` whose products `A x` are computed by
+    /// `apply(x, y)`, which must overwrite `y` with `A x`.
+    ///
+    /// The operator does not support transposed products, and panics if one is requested. Use
+    /// [`FnOperator::with_transpose`] to provide them.
+    pub fn new(nrows: usize, ncols: usize, apply: F) -> Self {
+        Self {
+            shape: (nrows, ncols),
+            apply,
+            apply_transpose: None,
+        }
+    }
+}
+
+impl<F, G> FnOperator<F, G> {
+    /// Creates an operator of shape `(nrows, ncols)` whose products `A x` and `Aᵀ x` are computed
+    /// by `apply(x, y)` and `apply_transpose(x, y)`, which must overwrite `y` with the product.
+    pub fn with_transpose(nrows: usize, ncols: usize, apply: F, apply_transpose: G) -> Self {
+        Self {
+            shape: (nrows, ncols),
+            apply,
+            apply_transpose: Some(apply_transpose),
+        }
+    }
+}
+
+impl<T, F, G> LinearOperator<T> for FnOperator<F, G>
+where
+    T: Scalar,
+    F: Fn(&DVector<T>, &mut DVector<T>),
+    G: Fn(&DVector<T>, &mut DVector<T>),
+{
+    fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    fn apply_to(&self, x: &DVector<T>, y: &mut DVector<T>) {
+        (self.apply)(x, y)
+    }
+
+    fn apply_transpose_to(&self, x: &DVector<T>, y: &mut DVector<T>) {
+        match &self.apply_transpose {
+            Some(apply_transpose) => apply_transpose(x, y),
+            None => panic!("This operator was created without a transposed product."),
+        }
+    }
+}
+
+impl<F, G> fmt::Debug for FnOperator<F, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let product = "Fn(&DVector<T>, &mut DVector<T>)";
+
+        f.debug_struct("FnOperator")
+            .field("shape", &self.shape)
+            .field("apply", &product)
+            .field(
+                "apply_transpose",
+                &self.apply_transpose.as_ref().map(|_| product),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cs::{CscMatrix, CsrMatrix},
+        proptest::*,
+    };
+    use nalgebra::DMatrix;
+    use proptest::prelude::*;
+
+    #[test]
+    #[should_panic(expected = "without a transposed product")]
+    fn fn_operator_without_transpose_panics() {
+        let identity = FnOperator::new(2, 2, |x: &DVector<f64>, y: &mut DVector<f64>| {
+            y.copy_from(x)
+        });
+        let mut y = DVector::zeros(2);
+
+        identity.apply_to(&DVector::from_element(2, 1.0), &mut y);
+        assert_eq!(y, DVector::from_element(2, 1.0));
+
+        identity.apply_transpose_to(&DVector::zeros(2), &mut y);
+    }
+
+    #[test]
+    fn fn_operator_with_transpose_applies_both_products() {
+        let dense = DMatrix::from_row_slice(2, 3, &[1.0, 2.0, 0.0, 0.0, -1.0, 3.0]);
+        let operator = FnOperator::with_transpose(
+            2,
+            3,
+            |x: &DVector<f64>, y: &mut DVector<f64>| y.copy_from(&(&dense * x)),
+            |x: &DVector<f64>, y: &mut DVector<f64>| y.copy_from(&(dense.transpose() * x)),
+        );
+        let x = DVector::from_vec(vec![1.0, 2.0, 3.0]);
+        let mut y = DVector::zeros(2);
+        let mut z = DVector::zeros(3);
+
+        operator.apply_to(&x, &mut y);
+        operator.apply_transpose_to(&y, &mut z);
+
+        assert_eq!(operator.shape(), (2, 3));
+        assert_eq!(y, &dense * &x);
+        assert_eq!(z, dense.transpose() * &y);
+    }
+
+    proptest! {
+        #[test]
+        fn sparse_products_agree_with_dense(csr in csr_strategy()) {
+            let dense = DMatrix::from(&csr);
+            let csc = CscMatrix::from(csr.clone());
+            let x = DVector::from_fn(csr.ncols(), |i, _| i as i32 - 2);
+            let x_t = DVector::from_fn(csr.nrows(), |i, _| 3 - i as i32);
+
+            for operator in [&csr as &dyn LinearOperator<i32>, &CsrMatrix::from(&dense), &csc] {
+                let mut y = DVector::from_element(csr.nrows(), 7);
+                let mut y_t = DVector::from_element(csr.ncols(), 7);
+
+                operator.apply_to(&x, &mut y);
+                operator.apply_transpose_to(&x_t, &mut y_t);
+
+                prop_assert_eq!(y, &dense * &x);
+                prop_assert_eq!(y_t, dense.transpose() * &x_t);
+            }
+        }
+    }
+}
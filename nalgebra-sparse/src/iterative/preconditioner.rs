@@ -0,0 +1,615 @@
+use super::LinearOperator;
+use crate::{
+    cs::{CompressedRowStorage, Compression, CsMatrix, CsrMatrix},
+    error::{OperationError, OperationErrorKind},
+    factorization::{CsCholesky, CsIlu},
+};
+use nalgebra::{DVector, RealField, Scalar};
+use std::borrow::Borrow;
+
+/// A preconditioner, i.e. an (approximate) inverse `M⁻¹` of a system matrix that is cheap to
+/// apply.
+///
+/// The iterative solvers accept any type implementing this trait, so that domain-specific
+/// preconditioners can be plugged in alongside the ones provided here:
+///
+/// - [`IdentityPreconditioner`], which performs no preconditioning.
+/// - [`JacobiPreconditioner`], which scales by the inverse of the diagonal of the system matrix.
+/// - [`SsorPreconditioner`], symmetric successive over-relaxation, for (mostly) symmetric systems.
+/// - [`CsCholesky`], which applies an exact inverse through a Cholesky factorization.
+/// - [`CsIlu`], which applies an approximate inverse through an incomplete LU factorization.
+/// - Any function or closure `Fn(&DVector<T>) -> DVector<T>`.
+///
+/// Only [`Preconditioner::apply_mut`] needs to be implemented; implement
+/// [`Preconditioner::apply`] as well if the preconditioner naturally produces a new vector.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::DVector;
+/// use nalgebra_sparse::iterative::Preconditioner;
+///
+/// /// Scales every entry of the residual by a fixed factor.
+/// struct Scaling(f64);
+///
+/// impl Preconditioner<f64> for Scaling {
+///     fn apply_mut(&self, r: &mut DVector<f64>) {
+///         *r *= self.0;
+///     }
+/// }
+///
+/// let r = DVector::from_vec(vec![1.0, 2.0]);
+/// assert_eq!(Scaling(0.5).apply(&r), DVector::from_vec(vec![0.5, 1.0]));
+/// ```
+pub trait Preconditioner<T>
+where
+    T: Scalar,
+{
+    /// Computes `M⁻¹ r`.
+    fn apply(&self, r: &DVector<T>) -> DVector<T> {
+        let mut z = r.clone();
+        self.apply_mut(&mut z);
+        z
+    }
+
+    /// Overwrites `r` with `M⁻¹ r`.
+    fn apply_mut(&self, r: &mut DVector<T>);
+}
+
+/// The identity preconditioner `M = I`, which leaves every vector unchanged.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct IdentityPreconditioner;
+
+impl<T> Preconditioner<T> for IdentityPreconditioner
+where
+    T: Scalar,
+{
+    fn apply(&self, r: &DVector<T>) -> DVector<T> {
+        r.clone()
+    }
+
+    fn apply_mut(&self, _r: &mut DVector<T>) {}
+}
+
+/// The Jacobi (or diagonal) preconditioner `M = D / ω`, where `D` is the diagonal of the system
+/// matrix and `ω` is an optional damping factor.
+///
+/// Applying it divides every entry of the residual by the corresponding diagonal entry, which is
+/// cheap and often effective for diagonally dominant systems, or systems whose rows are badly
+/// scaled relative to each other. Since it is a plain [`Preconditioner`], it can also be used on
+/// its own to scale residuals explicitly.
+///
+/// Diagonal entries that are zero (including ones that are not stored), or whose magnitude is not
+/// larger than the threshold set with [`with_threshold`](Self::with_threshold), cannot be
+/// inverted safely. The corresponding entries of the residual are only scaled by the damping
+/// factor instead.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::DVector;
+/// use nalgebra_sparse::{cs::CsrMatrix, iterative::{JacobiPreconditioner, Preconditioner}};
+///
+/// // The second diagonal entry is not stored, and is therefore left alone.
+/// let a = CsrMatrix::try_from_parts(3, 3, vec![0, 2, 3], vec![0, 2, 0, 2], vec![4.0, 1.0, 1.0, -2.0])
+///     .unwrap();
+/// let jacobi = JacobiPreconditioner::new(&a).unwrap().with_damping(0.5);
+///
+/// let r = DVector::from_vec(vec![2.0, 2.0, 2.0]);
+/// assert_eq!(jacobi.apply(&r), DVector::from_vec(vec![0.25, 1.0, -0.5]));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JacobiPreconditioner<T: Scalar> {
+    diagonal: DVector<T>,
+    threshold: T,
+    damping: T,
+}
+
+impl<T> JacobiPreconditioner<T>
+where
+    T: RealField,
+{
+    /// Creates a Jacobi preconditioner from the diagonal of the square matrix `a`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`OperationErrorKind::InvalidPattern`] if `a` is not square.
+    pub fn new<MO, MI, D, C>(a: &CsMatrix<T, MO, MI, D, C>) -> Result<Self, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+        C: Compression,
+    {
+        if a.nrows() != a.ncols() {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from("The Jacobi preconditioner requires a square matrix."),
+            ));
+        }
+
+        let mut diagonal = DVector::zeros(a.nrows());
+
+        for (major, minor, value) in a.triplet_iter() {
+            if major == minor {
+                diagonal[major] = value.clone();
+            }
+        }
+
+        Ok(Self::from_diagonal(diagonal))
+    }
+
+    /// Creates a Jacobi preconditioner from an explicitly given diagonal.
+    pub fn from_diagonal(diagonal: DVector<T>) -> Self {
+        Self {
+            diagonal,
+            threshold: T::zero(),
+            damping: T::one(),
+        }
+    }
+
+    /// Sets the damping factor `ω`, so that applying the preconditioner computes `ω D⁻¹ r`.
+    ///
+    /// Defaults to one, i.e. no damping.
+    #[must_use]
+    pub fn with_damping(self, damping: T) -> Self {
+        Self { damping, ..self }
+    }
+
+    /// Sets the magnitude up to which diagonal entries are considered too small to be inverted.
+    ///
+    /// Defaults to zero, so that only diagonal entries that are exactly zero are skipped.
+    #[must_use]
+    pub fn with_threshold(self, threshold: T) -> Self {
+        Self { threshold, ..self }
+    }
+
+    /// The diagonal `D` the preconditioner was built from.
+    #[must_use]
+    pub fn diagonal(&self) -> &DVector<T> {
+        &self.diagonal
+    }
+
+    /// The damping factor `ω`.
+    #[must_use]
+    pub fn damping(&self) -> T {
+        self.damping.clone()
+    }
+
+    /// The magnitude up to which diagonal entries are skipped.
+    #[must_use]
+    pub fn threshold(&self) -> T {
+        self.threshold.clone()
+    }
+}
+
+impl<T> Preconditioner<T> for JacobiPreconditioner<T>
+where
+    T: RealField,
+{
+    /// Overwrites `r` with `ω D⁻¹ r`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `r` does not have as many rows as the diagonal.
+    fn apply_mut(&self, r: &mut DVector<T>) {
+        assert_eq!(
+            r.nrows(),
+            self.diagonal.nrows(),
+            "The residual must have as many rows as the diagonal."
+        );
+
+        for (r_i, d_i) in r.iter_mut().zip(self.diagonal.iter()) {
+            if d_i.clone().abs() > self.threshold {
+                *r_i = self.damping.clone() * r_i.clone() / d_i.clone();
+            } else {
+                *r_i = self.damping.clone() * r_i.clone();
+            }
+        }
+    }
+}
+
+/// The symmetric successive over-relaxation (SSOR) preconditioner.
+///
+/// With the split `A = D + L + U` of the system matrix into its diagonal and its strictly lower
+/// and upper triangular parts (see [`CsMatrix::split_dlu`]), and a relaxation factor `0 < ω < 2`,
+/// the preconditioner is
+///
+/// ```text
+/// M = ω / (2 - ω) · (D / ω + L) (D / ω)⁻¹ (D / ω + U),
+/// ```
+///
+/// so applying `M⁻¹` costs one forward and one backward substitution with the triangular factors,
+/// i.e. roughly one product with `A`. It is symmetric positive definite whenever `A` is, which
+/// makes it a cheap but effective default for SPD systems when incomplete factorizations or
+/// multigrid are overkill. `ω = 1` gives the symmetric Gauss-Seidel preconditioner.
+///
+/// Instead of applying `M⁻¹` to the residuals of a solver for `A x = b`, the system can also be
+/// preconditioned from both sides with the factors of `M`, and solved through
+/// [`eisenstat`](Self::eisenstat). This uses the Eisenstat trick to compute the products of the
+/// preconditioned operator without ever multiplying with `A`, which roughly halves the work per
+/// iteration.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{
+///     cs::CsrMatrix,
+///     iterative::{Gmres, GmresStatus, SsorPreconditioner},
+/// };
+///
+/// let dense = DMatrix::from_row_slice(3, 3, &[4.0, -1.0, 0.0, -1.0, 4.0, -1.0, 0.0, -1.0, 4.0]);
+/// let a = CsrMatrix::from(&dense);
+/// let b = DVector::from_vec(vec![1.0, 2.0, 3.0]);
+///
+/// let ssor = SsorPreconditioner::new(&a, 1.2).unwrap();
+///
+/// // Precondition the residuals...
+/// let result = Gmres::new()
+///     .with_tolerance(1e-12)
+///     .with_left_preconditioner(&ssor)
+///     .solve(&a, &b)
+///     .unwrap();
+/// assert_eq!(result.status, GmresStatus::Converged);
+///
+/// // ...or solve the symmetrically preconditioned system with the Eisenstat trick.
+/// let system = ssor.eisenstat();
+/// let result = Gmres::new()
+///     .with_tolerance(1e-12)
+///     .solve(&system, &system.transform_rhs(&b))
+///     .unwrap();
+/// let x = system.recover_solution(&result.solution);
+///
+/// assert!((&dense * x - b).norm() < 1e-10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SsorPreconditioner<T: Scalar> {
+    scaled_diagonal: DVector<T>,
+    lower: CsrMatrix<T>,
+    upper: CsrMatrix<T>,
+    omega: T,
+}
+
+impl<T> SsorPreconditioner<T>
+where
+    T: RealField,
+{
+    /// Creates an SSOR preconditioner for the square CSR matrix `a` with relaxation factor `omega`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`OperationErrorKind::InvalidPattern`] if `a` is not square, and with
+    /// [`OperationErrorKind::Singular`] if a diagonal entry of `a` is zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `omega` is not in the open interval `(0, 2)`.
+    pub fn new<MO, MI, D>(
+        a: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+        omega: T,
+    ) -> Result<Self, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        assert!(
+            omega > T::zero() && omega < T::one() + T::one(),
+            "The relaxation factor must be in the open interval (0, 2)."
+        );
+
+        let (diagonal, lower, upper) = a.split_dlu()?;
+
+        if let Some(i) = diagonal.iter().position(|d_i| d_i.is_zero()) {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::Singular,
+                format!(
+                    "The diagonal entry {} is zero, so the SSOR preconditioner is singular.",
+                    i
+                ),
+            ));
+        }
+
+        Ok(Self {
+            scaled_diagonal: diagonal / omega.clone(),
+            lower,
+            upper,
+            omega,
+        })
+    }
+
+    /// The relaxation factor `ω`.
+    #[must_use]
+    pub fn omega(&self) -> T {
+        self.omega.clone()
+    }
+
+    /// The symmetrically preconditioned system `(D / ω + L)⁻¹ A (D / ω + U)⁻¹`, whose products are
+    /// computed with the Eisenstat trick.
+    #[must_use]
+    pub fn eisenstat(&self) -> EisenstatOperator<'_, T> {
+        EisenstatOperator { ssor: self }
+    }
+
+    /// Overwrites `x` with `(D / ω + L)⁻¹ x`.
+    fn solve_lower(&self, x: &mut DVector<T>) {
+        for (i, row) in self.lower.iter().enumerate() {
+            let mut x_i = x[i].clone();
+
+            for (j, l_ij) in row {
+                x_i -= l_ij.clone() * x[j].clone();
+            }
+
+            x[i] = x_i / self.scaled_diagonal[i].clone();
+        }
+    }
+
+    /// Overwrites `x` with `(D / ω + U)⁻¹ x`.
+    fn solve_upper(&self, x: &mut DVector<T>) {
+        for (i, row) in self.upper.iter().enumerate().rev() {
+            let mut x_i = x[i].clone();
+
+            for (j, u_ij) in row {
+                x_i -= u_ij.clone() * x[j].clone();
+            }
+
+            x[i] = x_i / self.scaled_diagonal[i].clone();
+        }
+    }
+
+    /// Overwrites `x` with `(D / ω + L)⁻ᵀ x`.
+    fn solve_lower_transpose(&self, x: &mut DVector<T>) {
+        for (i, row) in self.lower.iter().enumerate().rev() {
+            x[i] /= self.scaled_diagonal[i].clone();
+            let x_i = x[i].clone();
+
+            for (j, l_ij) in row {
+                x[j] -= l_ij.clone() * x_i.clone();
+            }
+        }
+    }
+
+    /// Overwrites `x` with `(D / ω + U)⁻ᵀ x`.
+    fn solve_upper_transpose(&self, x: &mut DVector<T>) {
+        for (i, row) in self.upper.iter().enumerate() {
+            x[i] /= self.scaled_diagonal[i].clone();
+            let x_i = x[i].clone();
+
+            for (j, u_ij) in row {
+                x[j] -= u_ij.clone() * x_i.clone();
+            }
+        }
+    }
+
+    /// Overwrites `y` with `x + (ω - 2) (D / ω) t`, i.e. `x + (1 - 2 / ω) D t`.
+    fn eisenstat_correction(&self, x: &DVector<T>, t: &DVector<T>, y: &mut DVector<T>) {
+        let factor = self.omega.clone() - (T::one() + T::one());
+
+        for (i, y_i) in y.iter_mut().enumerate() {
+            *y_i = x[i].clone() + factor.clone() * self.scaled_diagonal[i].clone() * t[i].clone();
+        }
+    }
+}
+
+impl<T> Preconditioner<T> for SsorPreconditioner<T>
+where
+    T: RealField,
+{
+    /// Overwrites `r` with `M⁻¹ r = (2 - ω) / ω · (D / ω + U)⁻¹ (D / ω) (D / ω + L)⁻¹ r`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `r` does not have as many rows as the system matrix.
+    fn apply_mut(&self, r: &mut DVector<T>) {
+        assert_eq!(
+            r.nrows(),
+            self.scaled_diagonal.nrows(),
+            "The residual must have as many rows as the system matrix."
+        );
+
+        self.solve_lower(r);
+        r.component_mul_assign(&self.scaled_diagonal);
+        self.solve_upper(r);
+        *r *= (T::one() + T::one() - self.omega.clone()) / self.omega.clone();
+    }
+}
+
+/// The system `A` preconditioned from both sides with the factors of an [`SsorPreconditioner`],
+/// i.e. `Â = (D / ω + L)⁻¹ A (D / ω + U)⁻¹`.
+///
+/// Since `A = (D / ω + L) + (D / ω + U) + (1 - 2 / ω) D`, the products of `Â` can be computed as
+///
+/// ```text
+/// Â x = t + (D / ω + L)⁻¹ (x + (1 - 2 / ω) D t),   where t = (D / ω + U)⁻¹ x,
+/// ```
+///
+/// which only takes the two triangular solves that applying the preconditioner takes anyway, and
+/// no product with `A` (the Eisenstat trick). To solve `A x = b`, solve `Â x̂ = b̂` with the right
+/// hand side from [`transform_rhs`](Self::transform_rhs), and map `x̂` back with
+/// [`recover_solution`](Self::recover_solution). If `A` is symmetric positive definite, then so
+/// is `Â`.
+///
+/// See [`SsorPreconditioner`] for an example.
+#[derive(Debug, Clone, Copy)]
+pub struct EisenstatOperator<'a, T: Scalar> {
+    ssor: &'a SsorPreconditioner<T>,
+}
+
+impl<'a, T> EisenstatOperator<'a, T>
+where
+    T: RealField,
+{
+    /// Computes the right hand side `b̂ = (D / ω + L)⁻¹ b` of the preconditioned system.
+    #[must_use]
+    pub fn transform_rhs(&self, b: &DVector<T>) -> DVector<T> {
+        let mut rhs = b.clone();
+        self.ssor.solve_lower(&mut rhs);
+        rhs
+    }
+
+    /// Computes the solution `x = (D / ω + U)⁻¹ x̂` of the original system from the solution `x̂`
+    /// of the preconditioned system.
+    #[must_use]
+    pub fn recover_solution(&self, solution: &DVector<T>) -> DVector<T> {
+        let mut x = solution.clone();
+        self.ssor.solve_upper(&mut x);
+        x
+    }
+}
+
+impl<'a, T> LinearOperator<T> for EisenstatOperator<'a, T>
+where
+    T: RealField,
+{
+    fn shape(&self) -> (usize, usize) {
+        let n = self.ssor.scaled_diagonal.nrows();
+        (n, n)
+    }
+
+    fn apply_to(&self, x: &DVector<T>, y: &mut DVector<T>) {
+        let mut t = x.clone();
+        self.ssor.solve_upper(&mut t);
+        self.ssor.eisenstat_correction(x, &t, y);
+        self.ssor.solve_lower(y);
+        *y += t;
+    }
+
+    fn apply_transpose_to(&self, x: &DVector<T>, y: &mut DVector<T>) {
+        let mut s = x.clone();
+        self.ssor.solve_lower_transpose(&mut s);
+        self.ssor.eisenstat_correction(x, &s, y);
+        self.ssor.solve_upper_transpose(y);
+        *y += s;
+    }
+}
+
+impl<T, F> Preconditioner<T> for F
+where
+    T: Scalar,
+    F: Fn(&DVector<T>) -> DVector<T>,
+{
+    fn apply(&self, r: &DVector<T>) -> DVector<T> {
+        self(r)
+    }
+
+    fn apply_mut(&self, r: &mut DVector<T>) {
+        *r = self(r);
+    }
+}
+
+impl<T> Preconditioner<T> for CsCholesky<T>
+where
+    T: RealField,
+{
+    fn apply(&self, r: &DVector<T>) -> DVector<T> {
+        self.solve(r)
+    }
+
+    fn apply_mut(&self, r: &mut DVector<T>) {
+        let rhs = std::mem::replace(r, DVector::zeros(0));
+        *r = self.solve_mut(rhs);
+    }
+}
+
+impl<T> Preconditioner<T> for CsIlu<T>
+where
+    T: RealField,
+{
+    fn apply(&self, r: &DVector<T>) -> DVector<T> {
+        self.solve(r)
+    }
+
+    fn apply_mut(&self, r: &mut DVector<T>) {
+        let rhs = std::mem::replace(r, DVector::zeros(0));
+        *r = self.solve_mut(rhs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cs::{CscMatrix, CsrMatrix};
+    use nalgebra::DMatrix;
+
+    #[test]
+    fn implementations_agree_with_their_inverse() {
+        let dense = DMatrix::from_row_slice(3, 3, &[4.0, 1.0, 0.0, 1.0, 3.0, -1.0, 0.0, -1.0, 2.0]);
+        let r = DVector::from_vec(vec![1.0, -2.0, 3.0]);
+
+        assert_eq!(IdentityPreconditioner.apply(&r), r);
+
+        let cholesky = CsCholesky::factor(&CsrMatrix::from(&dense)).unwrap();
+        let expected = dense.clone().lu().solve(&r).unwrap();
+        assert!((cholesky.apply(&r) - &expected).norm() < 1e-12);
+
+        let mut z = r.clone();
+        cholesky.apply_mut(&mut z);
+        assert!((z - &expected).norm() < 1e-12);
+
+        let scaling = |v: &DVector<f64>| v * 2.0;
+        let mut z = r.clone();
+        scaling.apply_mut(&mut z);
+        assert_eq!(z, &r * 2.0);
+        assert_eq!(Preconditioner::apply(&scaling, &r), &r * 2.0);
+    }
+
+    #[test]
+    fn jacobi_preconditioner_skips_small_diagonal_entries() {
+        let dense =
+            DMatrix::from_row_slice(3, 3, &[4.0, 1.0, 0.0, 1.0, 0.25, -1.0, 0.0, -1.0, 0.0]);
+        let a = CscMatrix::from(&dense);
+        let r = DVector::from_vec(vec![2.0, 3.0, 4.0]);
+
+        let jacobi = JacobiPreconditioner::new(&a).unwrap();
+        assert_eq!(jacobi.diagonal(), &DVector::from_vec(vec![4.0, 0.25, 0.0]));
+        assert_eq!(jacobi.apply(&r), DVector::from_vec(vec![0.5, 12.0, 4.0]));
+
+        let jacobi = jacobi.with_threshold(0.5).with_damping(2.0);
+        let mut z = r.clone();
+        jacobi.apply_mut(&mut z);
+        assert_eq!(z, DVector::from_vec(vec![1.0, 6.0, 8.0]));
+
+        let rectangular = CsrMatrix::<f64>::zeros(2, 3);
+        let err = JacobiPreconditioner::new(&rectangular).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn ssor_agrees_with_its_dense_definition() {
+        let dense = DMatrix::from_row_slice(
+            4,
+            4,
+            &[
+                4.0, -1.0, 0.0, 0.5, -1.0, 5.0, -2.0, 0.0, 0.0, -1.0, 3.0, 1.0, 2.0, 0.0, 1.0, 6.0,
+            ],
+        );
+        let a = CsrMatrix::from(&dense);
+        let omega = 1.3;
+        let ssor = SsorPreconditioner::new(&a, omega).unwrap();
+
+        let d = DMatrix::from_diagonal(&dense.diagonal());
+        let lower = &d / omega + dense.lower_triangle() - &d;
+        let upper = &d / omega + dense.upper_triangle() - &d;
+        let m = (&lower * (&d / omega).try_inverse().unwrap() * &upper) * (omega / (2.0 - omega));
+        let a_hat = lower.clone().try_inverse().unwrap() * &dense * upper.try_inverse().unwrap();
+
+        let r = DVector::from_vec(vec![1.0, -2.0, 3.0, 0.5]);
+        assert!((ssor.apply(&r) - m.lu().solve(&r).unwrap()).norm() < 1e-12);
+
+        let system = ssor.eisenstat();
+        let mut y = DVector::zeros(4);
+        system.apply_to(&r, &mut y);
+        assert!((&y - &a_hat * &r).norm() < 1e-12);
+        system.apply_transpose_to(&r, &mut y);
+        assert!((&y - a_hat.transpose() * &r).norm() < 1e-12);
+
+        let b_hat = system.transform_rhs(&r);
+        assert!((&lower * b_hat - &r).norm() < 1e-12);
+
+        let mut singular = dense.clone();
+        singular[(2, 2)] = 0.0;
+        let err = SsorPreconditioner::new(&CsrMatrix::from(&singular), omega).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::Singular));
+    }
+}
@@ -0,0 +1,457 @@
+use super::{lyapunov::gram_norm, AdiStatus, FnOperator, LinearOperator, LowRankAdi};
+use crate::error::{OperationError, OperationErrorKind};
+use nalgebra::{DMatrix, DVector, RealField};
+
+/// Why a [`NewtonAdi`] solve stopped.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NewtonAdiStatus {
+    /// The residual norm dropped below the requested tolerance.
+    Converged,
+
+    /// The maximum number of Newton iterations was performed without reaching the tolerance.
+    MaxIterationsReached,
+}
+
+/// The outcome of a [`NewtonAdi`] solve.
+#[derive(Debug, Clone)]
+pub struct NewtonAdiSolution<T> {
+    /// The low-rank factor `Z` of the approximate solution `X ≈ Z Zᵀ`.
+    pub factor: DMatrix<T>,
+
+    /// The feedback matrix `K = X B`, so that the optimal control of the LQR problem is
+    /// `u = -Kᵀ x`.
+    pub feedback: DMatrix<T>,
+
+    /// Why the solver stopped.
+    pub status: NewtonAdiStatus,
+
+    /// The number of Newton iterations (i.e. Lyapunov solves) that were performed.
+    pub iterations: usize,
+
+    /// The total number of ADI iterations across all Lyapunov solves.
+    pub adi_iterations: usize,
+
+    /// Whether every Lyapunov solve reached the tolerance of the [`LowRankAdi`] solver.
+    pub lyapunov_converged: bool,
+
+    /// The spectral norm of the residual `Aᵀ X + X A - X B Bᵀ X + Cᵀ C` of `factor`.
+    pub residual_norm: T,
+}
+
+/// Low-rank Newton–ADI solver for large algebraic Riccati equations
+/// `Aᵀ X + X A - X B Bᵀ X + Cᵀ C = 0`.
+///
+/// This is the continuous-time algebraic Riccati equation of the linear-quadratic regulator (LQR)
+/// for the system `ẋ = A x + B u` with cost `∫ (‖C x‖² + ‖u‖²) dt`, for a large sparse `A` and
+/// inputs `B` and outputs `C` with few columns and rows, respectively. Its stabilizing solution `X`
+/// then usually has a low numerical rank, and is computed as a factor `Z` with `X ≈ Z Zᵀ`.
+///
+/// Every Newton (Kleinman) iteration solves the Lyapunov equation of the closed-loop system
+/// `(A - B Kₖᵀ)ᵀ Xₖ₊₁ + Xₖ₊₁ (A - B Kₖᵀ) = -Cᵀ C - Kₖ Kₖᵀ` with [`LowRankAdi`], and updates the
+/// feedback to `Kₖ₊₁ = Xₖ₊₁ B`. The closed-loop matrix is never formed: it is applied through
+/// transposed products with `A` and the low-rank correction. The factor is compressed after every
+/// iteration, and the solver stops once `‖R(X)‖ <= tolerance ‖Cᵀ C‖` for the Riccati residual
+/// `R(X)`, which is computed in low-rank form as well.
+///
+/// The iteration has to start from a stabilizing feedback `K₀`, i.e. `A - B K₀ᵀ` has to be stable.
+/// [`NewtonAdi::solve`] starts from `K₀ = 0`, which requires `A` itself to be stable.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::DMatrix;
+/// use nalgebra_sparse::{
+///     cs::CsrMatrix,
+///     iterative::{NewtonAdi, NewtonAdiStatus},
+/// };
+///
+/// // LQR design for a discretized 1D heat equation, controlled at one end and observed on average
+/// let n = 40;
+/// let a = CsrMatrix::from(&DMatrix::from_fn(n, n, |i, j| match (i, j) {
+///     _ if i == j => -2.0,
+///     _ if i == j + 1 || j == i + 1 => 1.0,
+///     _ => 0.0,
+/// }));
+/// let b = DMatrix::from_fn(n, 1, |i, _| if i == 0 { 1.0 } else { 0.0 });
+/// let c = DMatrix::from_element(1, n, 1.0 / n as f64);
+///
+/// let result = NewtonAdi::new().solve(&a, &b, &c).unwrap();
+/// assert_eq!(result.status, NewtonAdiStatus::Converged);
+///
+/// let dense = DMatrix::from(&a);
+/// let x = &result.factor * result.factor.transpose();
+/// let residual = dense.transpose() * &x + &x * &dense - &x * &b * b.transpose() * &x
+///     + c.transpose() * &c;
+/// assert!(residual.norm() < 1e-8);
+/// ```
+#[derive(Debug, Clone)]
+pub struct NewtonAdi<'a, T> {
+    max_iterations: usize,
+    tolerance: T,
+    lyapunov_solver: LowRankAdi<'a, T>,
+}
+
+impl<'a, T> Default for NewtonAdi<'a, T>
+where
+    T: RealField,
+{
+    fn default() -> Self {
+        Self {
+            max_iterations: 20,
+            tolerance: nalgebra::convert(1e-8),
+            lyapunov_solver: LowRankAdi::default(),
+        }
+    }
+}
+
+impl<'a, T> NewtonAdi<'a, T>
+where
+    T: RealField,
+{
+    /// Creates a new solver with at most 20 Newton iterations, a relative tolerance of `1e-8`,
+    /// and a default [`LowRankAdi`] solver.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of Newton iterations.
+    #[must_use]
+    pub fn with_max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    /// Sets the tolerance on the norm of the Riccati residual, relative to `‖Cᵀ C‖`.
+    #[must_use]
+    pub fn with_tolerance(self, tolerance: T) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Sets the solver used for the Lyapunov equation of every Newton iteration.
+    ///
+    /// Its tolerance should be well below the tolerance of the Newton iteration, as the Newton
+    /// iteration cannot converge beyond the accuracy of its Lyapunov solves.
+    #[must_use]
+    pub fn with_lyapunov_solver(self, lyapunov_solver: LowRankAdi<'a, T>) -> Self {
+        Self {
+            lyapunov_solver,
+            ..self
+        }
+    }
+
+    /// Solves `Aᵀ X + X A - X B Bᵀ X + Cᵀ C = 0` for a low-rank factor of its stabilizing
+    /// solution, starting from a feedback of zero.
+    ///
+    /// `A` has to be stable, and its operator has to support transposed products.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` is not
+    /// square, or if `B` does not have as many rows as `A` or `C` as many columns. Errors of the
+    /// Lyapunov solves (e.g. because a closed-loop matrix is not stable) are passed on.
+    pub fn solve<A>(
+        &self,
+        a: &A,
+        b: &DMatrix<T>,
+        c: &DMatrix<T>,
+    ) -> Result<NewtonAdiSolution<T>, OperationError>
+    where
+        A: LinearOperator<T> + ?Sized,
+    {
+        self.solve_with_initial_feedback(a, b, c, DMatrix::zeros(b.nrows(), b.ncols()))
+    }
+
+    /// Solves `Aᵀ X + X A - X B Bᵀ X + Cᵀ C = 0` for a low-rank factor of its stabilizing
+    /// solution, starting from the feedback `k0`.
+    ///
+    /// `A - B K₀ᵀ` has to be stable, and the operator `A` has to support transposed products.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` is not
+    /// square, if `B` or `k0` do not have as many rows as `A` or `C` as many columns, or if `k0`
+    /// does not have as many columns as `B`. Errors of the Lyapunov solves (e.g. because a
+    /// closed-loop matrix is not stable) are passed on.
+    pub fn solve_with_initial_feedback<A>(
+        &self,
+        a: &A,
+        b: &DMatrix<T>,
+        c: &DMatrix<T>,
+        k0: DMatrix<T>,
+    ) -> Result<NewtonAdiSolution<T>, OperationError>
+    where
+        A: LinearOperator<T> + ?Sized,
+    {
+        let (nrows, ncols) = a.shape();
+
+        if nrows != ncols {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from("Lefthand matrix is not square."),
+            ));
+        }
+
+        if b.nrows() != nrows || c.ncols() != nrows || k0.shape() != b.shape() {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!(
+                    "B has shape {:?}, C has shape {:?} and K₀ has shape {:?}, which does not \
+                     match A with shape {:?}.",
+                    b.shape(),
+                    c.shape(),
+                    k0.shape(),
+                    (nrows, ncols)
+                ),
+            ));
+        }
+
+        let span = span!("newton_adi", n = nrows, iterations = tracing::field::Empty);
+
+        let c_t = c.transpose();
+        let threshold = gram_norm(&c_t) * self.tolerance.clone();
+
+        let mut feedback = k0;
+        let mut iterations = 0;
+        let mut adi_iterations = 0;
+        let mut lyapunov_converged = true;
+
+        let (factor, norm, status) = loop {
+            // Aₖᵀ x = Aᵀ x - Kₖ (Bᵀ x)
+            let closed_loop =
+                FnOperator::new(nrows, ncols, |x: &DVector<T>, y: &mut DVector<T>| {
+                    a.apply_transpose_to(x, y);
+                    y.gemv(-T::one(), &feedback, &b.tr_mul(x), T::one());
+                });
+            let rhs = DMatrix::from_fn(nrows, c_t.ncols() + feedback.ncols(), |i, j| {
+                if j < c_t.ncols() {
+                    c_t[(i, j)].clone()
+                } else {
+                    feedback[(i, j - c_t.ncols())].clone()
+                }
+            });
+
+            let lyapunov = self.lyapunov_solver.solve(&closed_loop, &rhs)?;
+            adi_iterations += lyapunov.iterations;
+            lyapunov_converged &= lyapunov.status == AdiStatus::Converged;
+            iterations += 1;
+
+            let factor = compress(lyapunov.factor);
+            feedback = &factor * factor.tr_mul(b);
+
+            let norm = residual_norm(a, &factor, &feedback, &c_t);
+
+            if norm <= threshold {
+                break (factor, norm, NewtonAdiStatus::Converged);
+            }
+
+            if iterations >= self.max_iterations {
+                break (factor, norm, NewtonAdiStatus::MaxIterationsReached);
+            }
+        };
+
+        record!(span, iterations = iterations);
+
+        Ok(NewtonAdiSolution {
+            factor,
+            feedback,
+            status,
+            iterations,
+            adi_iterations,
+            lyapunov_converged,
+            residual_norm: norm,
+        })
+    }
+}
+
+/// Computes a factor with (numerically) linearly independent columns and the same product `Z Zᵀ`
+/// as `z`, dropping the directions whose singular values are negligible.
+fn compress<T: RealField>(z: DMatrix<T>) -> DMatrix<T> {
+    if z.ncols() == 0 {
+        return z;
+    }
+
+    // Z = Q R = Q U Σ Vᵀ, so Z Zᵀ = (Q U Σ) (Q U Σ)ᵀ
+    let qr = z.qr();
+    let q = qr.q();
+    let svd = qr.r().svd(true, true);
+    let u = svd.u.expect("The SVD was computed with U.");
+
+    // The singular values are not necessarily sorted
+    let cutoff = svd.singular_values.max() * T::default_epsilon().sqrt();
+    let columns = svd
+        .singular_values
+        .iter()
+        .zip(u.column_iter())
+        .filter(|(sigma, _)| **sigma > cutoff)
+        .map(|(sigma, u_j)| &q * u_j * sigma.clone())
+        .collect::<Vec<_>>();
+
+    if columns.is_empty() {
+        DMatrix::zeros(q.nrows(), 0)
+    } else {
+        DMatrix::from_columns(&columns)
+    }
+}
+
+/// Computes the spectral norm of the Riccati residual `Aᵀ X + X A - K Kᵀ + Cᵀ C` for `X = Z Zᵀ`
+/// and `K = X B`.
+///
+/// The residual is the symmetric low-rank product `U D Uᵀ` with `U = [Aᵀ Z, Z, K, Cᵀ]` and a block
+/// matrix `D` of identities, so its norm follows from the small matrix `R D Rᵀ` of the
+/// QR decomposition `U = Q R`.
+fn residual_norm<T, A>(a: &A, z: &DMatrix<T>, k: &DMatrix<T>, c_t: &DMatrix<T>) -> T
+where
+    T: RealField,
+    A: LinearOperator<T> + ?Sized,
+{
+    let (n, r) = z.shape();
+    let (m, p) = (k.ncols(), c_t.ncols());
+
+    let mut y = DVector::zeros(n);
+    let mut u = DMatrix::zeros(n, 2 * r + m + p);
+    for (j, z_j) in z.column_iter().enumerate() {
+        a.apply_transpose_to(&z_j.clone_owned(), &mut y);
+        u.set_column(j, &y);
+    }
+    u.columns_mut(r, r).copy_from(z);
+    u.columns_mut(2 * r, m).copy_from(k);
+    u.columns_mut(2 * r + m, p).copy_from(c_t);
+
+    let mut d = DMatrix::zeros(u.ncols(), u.ncols());
+    for i in 0..r {
+        d[(i, r + i)] = T::one();
+        d[(r + i, i)] = T::one();
+    }
+    for i in 2 * r..2 * r + m {
+        d[(i, i)] = -T::one();
+    }
+    for i in 2 * r + m..u.ncols() {
+        d[(i, i)] = T::one();
+    }
+
+    let r_factor = u.qr().r();
+    let core = &r_factor * d * r_factor.transpose();
+
+    core.symmetric_eigenvalues().amax()
+}
+
+#[cfg(test)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cs::CsrMatrix, iterative::AdiShifts};
+
+    fn system() -> (DMatrix<f64>, DMatrix<f64>, DMatrix<f64>) {
+        let a = DMatrix::from_fn(12, 12, |i, j| match (i, j) {
+            _ if i == j => -3.0 - 0.5 * i as f64,
+            _ if j == i + 1 => 1.5,
+            _ if i == j + 1 => -0.5,
+            _ if i == j + 4 => 1.0,
+            _ => 0.0,
+        });
+        let b = DMatrix::from_fn(12, 2, |i, j| ((i + 3 * j) % 4) as f64 - 1.0);
+        let c = DMatrix::from_fn(1, 12, |_, j| (j % 3) as f64);
+
+        (a, b, c)
+    }
+
+    fn dense_residual(
+        a: &DMatrix<f64>,
+        b: &DMatrix<f64>,
+        c: &DMatrix<f64>,
+        x: &DMatrix<f64>,
+    ) -> DMatrix<f64> {
+        a.transpose() * x + x * a - x * b * b.transpose() * x + c.transpose() * c
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let a = CsrMatrix::from(&-DMatrix::<f64>::identity(3, 3));
+
+        for (b, c) in [
+            (DMatrix::zeros(2, 1), DMatrix::zeros(1, 3)),
+            (DMatrix::zeros(3, 1), DMatrix::zeros(1, 2)),
+        ] {
+            let err = NewtonAdi::new().solve(&a, &b, &c).unwrap_err();
+            assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+        }
+
+        let err = NewtonAdi::new()
+            .solve_with_initial_feedback(
+                &a,
+                &DMatrix::zeros(3, 1),
+                &DMatrix::zeros(1, 3),
+                DMatrix::zeros(3, 2),
+            )
+            .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn agrees_with_the_scalar_solution() {
+        // 2 a x - b² x² + c² = 0 has the stabilizing solution x = (a + √(a² + b² c²)) / b²
+        let (a, b, c) = (-1.5, 2.0, 3.0);
+        let expected = (a + f64::sqrt(a * a + b * b * c * c)) / (b * b);
+
+        let result = NewtonAdi::new()
+            .solve(
+                &CsrMatrix::from(&DMatrix::from_element(1, 1, a)),
+                &DMatrix::from_element(1, 1, b),
+                &DMatrix::from_element(1, 1, c),
+            )
+            .unwrap();
+        let x = (&result.factor * result.factor.transpose())[(0, 0)];
+
+        assert_eq!(result.status, NewtonAdiStatus::Converged);
+        assert!((x - expected).abs() < 1e-8 * expected);
+        assert!((result.feedback[(0, 0)] - x * b).abs() < 1e-12);
+    }
+
+    #[test]
+    fn solves_a_sparse_riccati_equation() {
+        let (a, b, c) = system();
+        let result = NewtonAdi::new()
+            .with_tolerance(1e-10)
+            .solve(&CsrMatrix::from(&a), &b, &c)
+            .unwrap();
+
+        let x = &result.factor * result.factor.transpose();
+        let residual = dense_residual(&a, &b, &c, &x);
+
+        assert_eq!(result.status, NewtonAdiStatus::Converged);
+        assert!(result.lyapunov_converged);
+        assert!(residual.norm() < 1e-8);
+        assert!((residual.singular_values().max() - result.residual_norm).abs() < 1e-8);
+        assert!(((&x * &b) - &result.feedback).norm() < 1e-10);
+
+        // The optimal feedback stabilizes the system
+        let closed_loop = &a - &b * result.feedback.transpose();
+        assert!(closed_loop
+            .complex_eigenvalues()
+            .iter()
+            .all(|lambda| lambda.re < 0.0));
+    }
+
+    #[test]
+    fn starts_from_a_stabilizing_feedback() {
+        // A + B Bᵀ is unstable, but (A + B Bᵀ) - B K₀ᵀ with K₀ = B is stable
+        let (a, b, c) = system();
+        let a = a + &b * b.transpose();
+        let k0 = b.clone();
+        assert!(a.complex_eigenvalues().iter().any(|lambda| lambda.re > 0.0));
+
+        let lyapunov_solver = LowRankAdi::new().with_shifts(AdiShifts::Heuristic { count: 10 });
+        let result = NewtonAdi::new()
+            .with_lyapunov_solver(lyapunov_solver)
+            .solve_with_initial_feedback(&CsrMatrix::from(&a), &b, &c, k0)
+            .unwrap();
+        let x = &result.factor * result.factor.transpose();
+
+        assert_eq!(result.status, NewtonAdiStatus::Converged);
+        assert!(dense_residual(&a, &b, &c, &x).norm() < 1e-6);
+    }
+}
@@ -16,13 +16,54 @@
 //! - [CSR](cs::CsrMatrix), [CSC](cs::CscMatrix) and [COO](coo::CooMatrix) formats, and
 //!   [conversions](`convert`) between them.
 //! - Common arithmetic operations are implemented. See the [`ops`] module.
+//! - [Sparse vectors](vector::CsVector), including borrowed views of the rows of a CSR matrix and
+//!   the columns of a CSC matrix, with dot products against dense and sparse vectors.
 //! - Sparsity patterns in CSR and CSC matrices are explicitly represented by the
 //!   [SparsityPattern](pattern::SparsityPattern) type, which encodes the invariants of the
 //!   associated index data structures.
+//! - Reading and writing [Matrix Market](io) files, the standard interchange format for sparse
+//!   matrices.
 //! - [proptest strategies](`proptest`) for sparse matrices when the feature
 //!   `proptest-support` is enabled.
 //! - [matrixcompare support](https://crates.io/crates/matrixcompare) for effortless
 //!   (approximate) comparison of matrices in test code (requires the `compare` feature).
+//! - [tracing](https://crates.io/crates/tracing) spans around factorizations, solvers and large
+//!   conversions when the feature `tracing` is enabled.
+//! - Heap-allocation-free `SmallCsrMatrix` / `SmallCscMatrix` aliases for tiny matrices when the
+//!   feature `smallvec` is enabled.
+//! - A minimal [sparse third-order tensor](tensor::CooTensor) type with mode-n unfoldings and
+//!   products.
+//! - Transport-agnostic [distributed SpMV](distributed) building blocks when the feature
+//!   `distributed` is enabled.
+//! - [int8-quantized](quantized::QuantizedCsrMatrix) CSR matrices with per-row scales for
+//!   memory-bound inference workloads.
+//! - [Run-length compressed](runlength::RunLengthCsMatrix) patterns for structured-grid
+//!   matrices, whose lanes are mostly made up of consecutive indices.
+//! - `Serialize` / `Deserialize` impls for the COO, CSR and CSC formats and for sparsity
+//!   patterns when the feature `serde` is enabled. Deserialization checks every invariant of the
+//!   format rather than trusting the input.
+//! - Naive [reference implementations](reference) of the sparse operations for differential
+//!   testing and debugging when the feature `proptest-support` is enabled.
+//! - [Batched factorizations](factorization::batch) that run in parallel across the batch when
+//!   the feature `rayon` is enabled.
+//! - Parallel sparse matrix-vector and sparse-sparse products in `ops::parallel`, and parallel
+//!   assembly of CSR matrices from thread-local COO matrices in `convert::parallel`, when the
+//!   feature `rayon` is enabled.
+//! - Stationary distributions, absorption probabilities and hitting times of
+//!   [Markov chains](markov) with sparse transition matrices.
+//! - [Building blocks](lp) for linear programming solvers: standard-form constraint matrices,
+//!   `A Aᵀ` and `A D Aᵀ`, basis extraction and basis factorizations with column replacement
+//!   updates.
+//! - [Frozen](frozen::FrozenCsrMatrix) CSR matrices: immutable, cheaply clonable handles with
+//!   identity and pattern keys for caching work such as symbolic factorizations.
+//! - [Cross-validation](cv) utilities for sparse design matrices: k-fold row splits and
+//!   fold-wise Gram matrices `Xᵀ X` for ridge and lasso regressions.
+//! - A coordinate descent solver for [L1/L2-regularized least squares](regression) that works on
+//!   the columns of a CSC design matrix.
+//! - [k-nearest-neighbour and radius graphs](graph) of dense point sets, with distance or
+//!   Gaussian weights, built directly as CSR adjacency matrices.
+//! - [Stencil operators](stencil) on the active cells of masked 2D and 3D grids, with an index
+//!   map between grid coordinates and matrix rows.
 //!
 //! ## Current state
 //!
@@ -132,15 +173,45 @@
 )]
 
 pub extern crate nalgebra as na;
+#[macro_use]
+mod instrument;
+
+pub mod analysis;
+pub mod control;
 pub mod convert;
 pub mod coo;
 pub mod cs;
+pub mod cv;
+#[cfg(feature = "distributed")]
+pub mod distributed;
 pub mod error;
 pub mod factorization;
+pub mod frozen;
+pub mod graph;
+pub mod interleaved;
+pub mod io;
+pub mod iterative;
+pub mod lp;
+pub mod markov;
 pub mod ops;
+pub mod partition;
+pub mod pattern;
+pub mod quantized;
+pub mod regression;
+pub mod runlength;
+#[cfg(feature = "serde")]
+mod serde;
+pub mod shaped;
+pub mod stencil;
+pub mod tensor;
+pub mod vector;
 
+#[cfg(feature = "proptest-support")]
+pub mod golden;
 #[cfg(feature = "proptest-support")]
 pub mod proptest;
+#[cfg(feature = "proptest-support")]
+pub mod reference;
 
 #[cfg(feature = "compare")]
 mod matrixcompare;
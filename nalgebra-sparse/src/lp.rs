@@ -0,0 +1,1112 @@
+//! Building blocks for linear and quadratic programming solvers.
+//!
+//! Solvers for linear programs (LPs) such as `min cᵀx subject to A x = b, x >= 0` spend most of
+//! their time on a few sparse linear algebra kernels. This module provides them for solver
+//! authors:
+//!
+//! - [`block_matrix`] and [`standard_form`] assemble constraint matrices from sparse blocks, e.g.
+//!   to add slack variables for inequality constraints.
+//! - [`aat`] and [`aat_upper`] compute the matrix `A Aᵀ` of the normal equations of interior point
+//!   methods, computing every entry of the symmetric result only once.
+//! - [`NormalMatrix`] recomputes `A D Aᵀ` in place for the changing diagonal scaling `D` of an
+//!   interior point method, reusing the sparsity pattern of the product.
+//! - [`basis_matrix`] extracts the basis matrix `B = A[:, basis]` of a simplex iteration.
+//! - [`BasisFactorization`] factorizes a basis matrix, and updates the factorization when a
+//!   single column of the basis is replaced, as happens in every simplex iteration.
+//!
+//! # Example
+//!
+//! ```
+//! use nalgebra::{DMatrix, DVector};
+//! use nalgebra_sparse::{
+//!     cs::CscMatrix,
+//!     lp::{basis_matrix, standard_form, BasisFactorization},
+//! };
+//!
+//! // The constraints x0 + x1 = 2 and x0 - x1 <= 1, with a slack variable for the inequality
+//! let a_eq = CscMatrix::from(&DMatrix::from_row_slice(1, 2, &[1.0, 1.0]));
+//! let a_ub = CscMatrix::from(&DMatrix::from_row_slice(1, 2, &[1.0, -1.0]));
+//! let a = standard_form(&a_eq, &a_ub).unwrap();
+//! assert_eq!(a.shape(), (2, 3));
+//!
+//! // The basis of x0 and the slack variable gives the vertex x = (2, 0)
+//! let mut basis = BasisFactorization::new(&basis_matrix(&a, &[0, 2]).unwrap()).unwrap();
+//! let b = DVector::from_vec(vec![2.0, 1.0]);
+//! assert_eq!(basis.solve(&b), DVector::from_vec(vec![2.0, -1.0]));
+//!
+//! // The slack variable is negative, so x1 enters the basis in its place, giving x = (1.5, 0.5)
+//! basis.replace_column(1, &DVector::from_vec(vec![1.0, -1.0])).unwrap();
+//! assert!((basis.solve(&b) - DVector::from_vec(vec![1.5, 0.5])).norm() < 1e-12);
+//! ```
+
+use crate::{
+    cs::{CompressedColumnStorage, CompressedRowStorage, CsMatrix, CscMatrix, CsrMatrix},
+    error::{OperationError, OperationErrorKind},
+    factorization::CsLu,
+};
+use nalgebra::{DVector, RealField, Scalar};
+use num_traits::{One, Zero};
+use std::{
+    borrow::Borrow,
+    ops::{AddAssign, Mul},
+};
+
+/// Assembles a CSC matrix from a grid of blocks.
+///
+/// `blocks[i][j]` is the block in block row `i` and block column `j`, where `None` stands for a
+/// block of zeros. Every block row must have the same number of blocks, and the height of every
+/// block row and the width of every block column are taken from the blocks that are present.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if the block rows
+/// have differing numbers of blocks, if two blocks in the same block row (column) have differing
+/// numbers of rows (columns), or if a block row or column only holds `None`, so that its size is
+/// unknown.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::DMatrix;
+/// use nalgebra_sparse::{cs::CscMatrix, lp::block_matrix};
+///
+/// let a = CscMatrix::from(&DMatrix::from_row_slice(1, 2, &[1, 2]));
+/// let i = CscMatrix::<i32>::identity(1);
+///
+/// let block = block_matrix(&[&[Some(&a), None], &[Some(&a), Some(&i)]]).unwrap();
+///
+/// assert_eq!(DMatrix::from(&block), DMatrix::from_row_slice(2, 3, &[1, 2, 0, 1, 2, 1]));
+/// ```
+pub fn block_matrix<T>(blocks: &[&[Option<&CscMatrix<T>>]]) -> Result<CscMatrix<T>, OperationError>
+where
+    T: Scalar,
+{
+    let nblock_cols = blocks.first().map_or(0, |row| row.len());
+
+    if blocks.iter().any(|row| row.len() != nblock_cols) {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("Every block row must have the same number of blocks."),
+        ));
+    }
+
+    let mut heights = vec![None; blocks.len()];
+    let mut widths = vec![None; nblock_cols];
+
+    for (i, row) in blocks.iter().enumerate() {
+        for (j, block) in row.iter().enumerate() {
+            if let Some(block) = block {
+                let (nrows, ncols) = block.shape();
+
+                if *heights[i].get_or_insert(nrows) != nrows
+                    || *widths[j].get_or_insert(ncols) != ncols
+                {
+                    return Err(OperationError::from_kind_and_message(
+                        OperationErrorKind::InvalidPattern,
+                        format!(
+                            "The block ({}, {}) of shape ({}, {}) does not fit the other blocks \
+                             in its block row or column.",
+                            i, j, nrows, ncols
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    let sizes = |sizes: Vec<Option<usize>>| -> Result<Vec<usize>, OperationError> {
+        sizes
+            .into_iter()
+            .map(|size| {
+                size.ok_or_else(|| {
+                    OperationError::from_kind_and_message(
+                        OperationErrorKind::InvalidPattern,
+                        String::from("Every block row and column needs at least one block."),
+                    )
+                })
+            })
+            .collect()
+    };
+    let heights = sizes(heights)?;
+    let widths = sizes(widths)?;
+
+    let nrows = heights.iter().sum();
+    let ncols = widths.iter().sum();
+    let nnz = blocks
+        .iter()
+        .flat_map(|row| row.iter().flatten())
+        .map(|block| block.nnz())
+        .sum();
+
+    let mut offsets = Vec::with_capacity(ncols);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+
+    for (j, &width) in widths.iter().enumerate() {
+        for column in 0..width {
+            offsets.push(indices.len());
+            let mut row_offset = 0;
+
+            for (row, &height) in blocks.iter().zip(&heights) {
+                if let Some(lane) = row[j].and_then(|block| block.get_lane(column)) {
+                    for (i, value) in lane {
+                        indices.push(row_offset + i);
+                        data.push(value.clone());
+                    }
+                }
+
+                row_offset += height;
+            }
+        }
+    }
+
+    Ok(unsafe { CsMatrix::from_parts_unchecked(nrows, ncols, offsets, indices, data) })
+}
+
+/// Assembles the constraint matrix of a linear program in standard form.
+///
+/// The constraints `A_eq x = b_eq` and `A_ub x <= b_ub` are turned into equality constraints on
+/// non-negative variables by adding a slack variable `s >= 0` for every inequality, so that
+/// `A_ub x + s = b_ub`. The resulting constraint matrix is
+///
+/// ```text
+/// [ A_eq  0 ]
+/// [ A_ub  I ]
+/// ```
+///
+/// with the slack variables following the original variables.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A_eq` and
+/// `A_ub` do not have the same number of columns.
+pub fn standard_form<T>(
+    a_eq: &CscMatrix<T>,
+    a_ub: &CscMatrix<T>,
+) -> Result<CscMatrix<T>, OperationError>
+where
+    T: Scalar + Zero + One,
+{
+    let slack = CscMatrix::identity(a_ub.nrows());
+    let zeros = CscMatrix::zeros(a_eq.nrows(), a_ub.nrows());
+
+    block_matrix(&[&[Some(a_eq), Some(&zeros)], &[Some(a_ub), Some(&slack)]])
+}
+
+/// Extracts the basis matrix `A[:, basis]`, whose `k`-th column is the column `basis[k]` of `A`.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if an index in
+/// `basis` is not a column of `A`.
+pub fn basis_matrix<T, MO, MI, D>(
+    a: &CsMatrix<T, MO, MI, D, CompressedColumnStorage>,
+    basis: &[usize],
+) -> Result<CscMatrix<T>, OperationError>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    let mut offsets = Vec::with_capacity(basis.len());
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+
+    for &column in basis {
+        let lane = a.get_lane(column).ok_or_else(|| {
+            OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!(
+                    "The basis column {} is not a column of a matrix with {} columns.",
+                    column,
+                    a.ncols()
+                ),
+            )
+        })?;
+
+        offsets.push(indices.len());
+
+        for (i, value) in lane {
+            indices.push(i);
+            data.push(value.clone());
+        }
+    }
+
+    Ok(unsafe { CsMatrix::from_parts_unchecked(a.nrows(), basis.len(), offsets, indices, data) })
+}
+
+/// Computes the upper triangle (including the diagonal) of the symmetric matrix `A Aᵀ`.
+///
+/// Entry `(i, j)` of `A Aᵀ` is the dot product of the rows `i` and `j` of `A`, so only the
+/// products with `i <= j` are ever computed. This halves the work compared to a general sparse
+/// product, and the upper triangle is all that e.g. a Cholesky factorization of the normal
+/// equations needs.
+pub fn aat_upper<T, MO, MI, D>(a: &CsMatrix<T, MO, MI, D, CompressedRowStorage>) -> CsrMatrix<T>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    let (m, n) = a.shape();
+    let (a_offsets, a_indices, a_data) = a.cs_data();
+    let lane = |i: usize| a_offsets[i]..a_offsets.get(i + 1).copied().unwrap_or(a_data.len());
+    let columns = ColumnIndex::new(n, a_offsets, a_indices);
+
+    let mut offsets = Vec::with_capacity(m);
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+
+    let mut marker = vec![usize::MAX; m];
+    let mut accumulator = vec![T::zero(); m];
+    let mut touched = Vec::new();
+
+    for i in 0..m {
+        offsets.push(indices.len());
+
+        for (&k, a_ik) in a_indices[lane(i)].iter().zip(&a_data[lane(i)]) {
+            for &(j, position) in columns.lane_from(k, i) {
+                let product = a_ik.clone() * a_data[position].clone();
+
+                if marker[j] == i {
+                    accumulator[j] += product;
+                } else {
+                    marker[j] = i;
+                    accumulator[j] = product;
+                    touched.push(j);
+                }
+            }
+        }
+
+        touched.sort_unstable();
+        for j in touched.drain(..) {
+            indices.push(j);
+            data.push(accumulator[j].clone());
+        }
+    }
+
+    unsafe { CsMatrix::from_parts_unchecked(m, m, offsets, indices, data) }
+}
+
+/// Computes the symmetric matrix `A Aᵀ`.
+///
+/// The upper triangle is computed with [`aat_upper`], and then mirrored into the lower triangle.
+pub fn aat<T, MO, MI, D>(a: &CsMatrix<T, MO, MI, D, CompressedRowStorage>) -> CsrMatrix<T>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    let upper = aat_upper(a);
+    let m = upper.nrows();
+
+    // Row j of the result holds the entries (i, j) of the upper triangle with i < j, followed by
+    // row j of the upper triangle
+    let mut lower: Vec<Vec<(usize, T)>> = vec![Vec::new(); m];
+    for (i, j, value) in upper.triplet_iter() {
+        if i < j {
+            lower[j].push((i, value.clone()));
+        }
+    }
+
+    let nnz = 2 * upper.nnz() - upper.triplet_iter().filter(|(i, j, _)| i == j).count();
+    let mut offsets = Vec::with_capacity(m);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+
+    for (row_lower, row_upper) in lower.into_iter().zip(upper.iter()) {
+        offsets.push(indices.len());
+
+        for (i, value) in row_lower {
+            indices.push(i);
+            data.push(value);
+        }
+
+        for (k, value) in row_upper {
+            indices.push(k);
+            data.push(value.clone());
+        }
+    }
+
+    unsafe { CsMatrix::from_parts_unchecked(m, m, offsets, indices, data) }
+}
+
+/// The normal-equations matrix `A D Aᵀ + δ I` of an interior point method, for a fixed sparse
+/// matrix `A` and a changing diagonal matrix `D`.
+///
+/// Interior point methods solve a system with `A D Aᵀ` in every iteration, where only the
+/// positive diagonal scaling `D` (and possibly the regularization `δ`) changes between
+/// iterations. The sparsity pattern of the product is therefore computed once when the
+/// `NormalMatrix` is created, and [`NormalMatrix::update`] only recomputes the values in place,
+/// without allocating. Since the pattern never changes, the symbolic analysis of a Cholesky
+/// factorization of [`NormalMatrix::matrix`] can be reused across iterations as well.
+///
+/// The pattern always contains the full diagonal, so that the regularization can be added even
+/// for empty rows of `A`. Both triangles of the symmetric matrix are stored, but only the upper
+/// one is computed; the lower one is copied from it.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{cs::CsrMatrix, lp::NormalMatrix};
+///
+/// let a = CsrMatrix::from(&DMatrix::from_row_slice(2, 3, &[1.0, 0.0, 2.0, 0.0, 3.0, 1.0]));
+/// let mut normal = NormalMatrix::new(&a);
+///
+/// let d = DVector::from_vec(vec![1.0, 2.0, 0.5]);
+/// normal.update_with_regularization(&d, 1e-3);
+///
+/// let dense_a = DMatrix::from(&a);
+/// let expected = &dense_a * DMatrix::from_diagonal(&d) * dense_a.transpose()
+///     + DMatrix::identity(2, 2) * 1e-3;
+/// assert!((DMatrix::from(&normal.matrix()) - expected).norm() < 1e-12);
+/// ```
+#[derive(Debug, Clone)]
+pub struct NormalMatrix<T> {
+    a_offsets: Vec<usize>,
+    a_indices: Vec<usize>,
+    a_data: Vec<T>,
+    columns: ColumnIndex,
+    offsets: Vec<usize>,
+    indices: Vec<usize>,
+    data: Vec<T>,
+    /// The position of the entry `(j, i)` in the upper triangle for every entry `(i, j)` of the
+    /// lower triangle.
+    mirror: Vec<(usize, usize)>,
+    /// The position of every entry of the current row in `data`, indexed by its column.
+    slots: Vec<usize>,
+}
+
+impl<T> NormalMatrix<T>
+where
+    T: Scalar + Zero + One + AddAssign + Mul<Output = T>,
+{
+    /// Computes the sparsity pattern of `A D Aᵀ`, and initializes its values to `A Aᵀ`, i.e. with
+    /// `D = I` and without regularization.
+    pub fn new<MO, MI, D>(a: &CsMatrix<T, MO, MI, D, CompressedRowStorage>) -> Self
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let (m, n) = a.shape();
+        let (a_offsets, a_indices, a_data) = a.cs_data();
+        let upper = aat_upper(a).pattern();
+
+        // Row j holds the entries (i, j) of the upper triangle with i < j, the diagonal and the
+        // entries of row j of the upper triangle with k > j
+        let mut lower = vec![Vec::new(); m];
+        for (i, j) in upper.entries() {
+            if i < j {
+                lower[j].push(i);
+            }
+        }
+
+        let mut offsets = Vec::with_capacity(m);
+        let mut indices = Vec::with_capacity(2 * upper.nnz() + m);
+
+        for (j, lower) in lower.into_iter().enumerate() {
+            offsets.push(indices.len());
+            indices.extend(lower);
+            indices.push(j);
+            indices.extend(upper.lane(j).unwrap().iter().filter(|&&k| k > j));
+        }
+
+        let lane = |i: usize| offsets[i]..offsets.get(i + 1).copied().unwrap_or(indices.len());
+        let mut mirror = Vec::new();
+
+        for i in 0..m {
+            for position in lane(i) {
+                let j = indices[position];
+
+                if j < i {
+                    let transposed = lane(j).start
+                        + indices[lane(j)]
+                            .binary_search(&i)
+                            .expect("The pattern is symmetric.");
+                    mirror.push((position, transposed));
+                }
+            }
+        }
+
+        let mut normal = Self {
+            a_offsets: a_offsets.to_vec(),
+            a_indices: a_indices.to_vec(),
+            a_data: a_data.to_vec(),
+            columns: ColumnIndex::new(n, a_offsets, a_indices),
+            data: vec![T::zero(); indices.len()],
+            offsets,
+            indices,
+            mirror,
+            slots: vec![0; m],
+        };
+
+        normal.update(&DVector::from_element(n, T::one()));
+        normal
+    }
+
+    /// The current value of `A D Aᵀ + δ I`.
+    #[must_use]
+    pub fn matrix(&self) -> CsMatrix<T, &[usize], &[usize], &[T], CompressedRowStorage> {
+        let m = self.offsets.len();
+
+        unsafe {
+            CsMatrix::from_parts_unchecked(
+                m,
+                m,
+                self.offsets.as_slice(),
+                self.indices.as_slice(),
+                self.data.as_slice(),
+            )
+        }
+    }
+
+    /// Recomputes the values for the diagonal `D = diag(d)`, without regularization.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `d` does not have an entry for every column of `A`.
+    pub fn update(&mut self, d: &DVector<T>) {
+        self.update_with_regularization(d, T::zero());
+    }
+
+    /// Recomputes the values for the diagonal `D = diag(d)`, and adds `regularization` to the
+    /// diagonal of the product.
+    ///
+    /// A small regularization `δ > 0` keeps the matrix positive definite when `A` does not have
+    /// full row rank, or when entries of `d` approach zero towards the end of an interior point
+    /// method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `d` does not have an entry for every column of `A`.
+    pub fn update_with_regularization(&mut self, d: &DVector<T>, regularization: T) {
+        assert_eq!(
+            d.nrows(),
+            self.columns.ncols(),
+            "d must have an entry for every column of A."
+        );
+
+        let m = self.offsets.len();
+
+        for i in 0..m {
+            let lane = self.offsets[i]
+                ..self
+                    .offsets
+                    .get(i + 1)
+                    .copied()
+                    .unwrap_or(self.indices.len());
+            for position in lane {
+                let j = self.indices[position];
+
+                if j >= i {
+                    self.slots[j] = position;
+                    self.data[position] = T::zero();
+                }
+            }
+
+            let a_lane = self.a_offsets[i]
+                ..self
+                    .a_offsets
+                    .get(i + 1)
+                    .copied()
+                    .unwrap_or(self.a_indices.len());
+
+            for (&k, a_ik) in self.a_indices[a_lane.clone()]
+                .iter()
+                .zip(&self.a_data[a_lane])
+            {
+                let scaled = a_ik.clone() * d[k].clone();
+
+                for &(j, position) in self.columns.lane_from(k, i) {
+                    self.data[self.slots[j]] += scaled.clone() * self.a_data[position].clone();
+                }
+            }
+
+            self.data[self.slots[i]] += regularization.clone();
+        }
+
+        for &(lower, upper) in &self.mirror {
+            self.data[lower] = self.data[upper].clone();
+        }
+    }
+}
+
+/// The entries of every column of a row-major matrix, as pairs of their row and their position in
+/// the data of the matrix, sorted by row.
+#[derive(Debug, Clone)]
+struct ColumnIndex {
+    offsets: Vec<usize>,
+    entries: Vec<(usize, usize)>,
+}
+
+impl ColumnIndex {
+    fn new(ncols: usize, offsets: &[usize], indices: &[usize]) -> Self {
+        let mut column_offsets = vec![0; ncols + 1];
+        for &j in indices {
+            column_offsets[j + 1] += 1;
+        }
+        for j in 0..ncols {
+            column_offsets[j + 1] += column_offsets[j];
+        }
+
+        let mut next = column_offsets.clone();
+        let mut entries = vec![(0, 0); indices.len()];
+
+        for (i, &start) in offsets.iter().enumerate() {
+            let end = offsets.get(i + 1).copied().unwrap_or(indices.len());
+
+            for (position, &j) in indices.iter().enumerate().take(end).skip(start) {
+                entries[next[j]] = (i, position);
+                next[j] += 1;
+            }
+        }
+
+        Self {
+            offsets: column_offsets,
+            entries,
+        }
+    }
+
+    fn ncols(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// The entries of column `k` in the rows `first_row..`.
+    fn lane_from(&self, k: usize, first_row: usize) -> &[(usize, usize)] {
+        let column = &self.entries[self.offsets[k]..self.offsets[k + 1]];
+        let start = column.partition_point(|&(i, _)| i < first_row);
+
+        &column[start..]
+    }
+}
+
+/// An elementary row operation `row[target] -= multiplier * row[source]` of a
+/// [`BasisFactorization`] update.
+#[derive(Debug, Clone)]
+struct RowEta<T> {
+    target: usize,
+    source: usize,
+    multiplier: T,
+}
+
+/// An LU factorization of a square basis matrix `B` that can be updated when a column of `B` is
+/// replaced, in the style of Forrest and Tomlin.
+///
+/// The factorization starts out as `P B = L U`, computed with the sparse LU factorization
+/// [`CsLu`] with partial pivoting, so `L` and `U` only hold the fill created by the elimination
+/// rather than `O(m²)` dense entries. Replacing column `r` of `B`
+/// replaces column `r` of `U` with a "spike" `L⁻¹ P a`, which is moved to the end of `U` together
+/// with row `r`, so that only row `r` breaks the triangular structure. That row is then
+/// eliminated with the rows below it, and the elimination is recorded as a sequence of row
+/// operations that are replayed in every subsequent solve. `U` stays sparse, and every update
+/// costs about as much as a solve with `U`.
+///
+/// The row operations accumulate with every update, and so do rounding errors. Simplex
+/// implementations typically factorize the basis from scratch every few dozen updates (see
+/// [`BasisFactorization::updates`]).
+#[derive(Debug, Clone)]
+pub struct BasisFactorization<T: Scalar> {
+    /// The row permutation `P`: row `i` of `P B` is row `permutation[i]` of `B`.
+    permutation: Vec<usize>,
+    /// The strictly lower triangular part of the unit lower triangular `L`.
+    lower: CsrMatrix<T>,
+    /// The off-diagonal entries of every row of `U`.
+    upper: Vec<Vec<(usize, T)>>,
+    diagonal: Vec<T>,
+    /// The order of the rows (and columns) of `U` in which it is upper triangular.
+    order: Vec<usize>,
+    position: Vec<usize>,
+    etas: Vec<RowEta<T>>,
+    updates: usize,
+    pivot_tolerance: T,
+}
+
+impl<T> BasisFactorization<T>
+where
+    T: RealField,
+{
+    /// Factorizes the basis matrix `basis`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `basis` is
+    /// not square, and with kind `OperationErrorKind::Singular` if it is numerically singular.
+    pub fn new<MO, MI, D>(
+        basis: &CsMatrix<T, MO, MI, D, CompressedColumnStorage>,
+    ) -> Result<Self, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let (m, ncols) = basis.shape();
+
+        if m != ncols {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from("The basis matrix is not square."),
+            ));
+        }
+
+        let scale = basis
+            .triplet_iter()
+            .fold(T::zero(), |scale, (_, _, value)| {
+                scale.max(value.clone().abs())
+            });
+        let pivot_tolerance = T::default_epsilon() * nalgebra::convert(m as f64) * scale;
+
+        let singular = || {
+            OperationError::from_kind_and_message(
+                OperationErrorKind::Singular,
+                String::from("The basis matrix is numerically singular."),
+            )
+        };
+
+        // The sparse LU factorization eliminates the rows of the basis with partial pivoting, so
+        // `L` and `U` only hold the fill that the elimination creates.
+        let lu = CsLu::factor(basis).map_err(|_| singular())?;
+        let (lower, u) = (lu.l().clone(), lu.u());
+        let mut upper = Vec::with_capacity(m);
+        let mut diagonal = Vec::with_capacity(m);
+
+        // Every row of `U` starts with its diagonal entry.
+        for (i, mut lane) in u.iter().enumerate() {
+            let u_ii = match lane.next() {
+                Some((j, u_ii)) if j == i && u_ii.clone().abs() > pivot_tolerance => u_ii.clone(),
+                _ => return Err(singular()),
+            };
+
+            diagonal.push(u_ii);
+            upper.push(
+                lane.filter(|(_, u_ij)| !u_ij.is_zero())
+                    .map(|(j, u_ij)| (j, u_ij.clone()))
+                    .collect(),
+            );
+        }
+
+        Ok(Self {
+            permutation: lu.row_permutation().to_vec(),
+            lower,
+            upper,
+            diagonal,
+            order: (0..m).collect(),
+            position: (0..m).collect(),
+            etas: Vec::new(),
+            updates: 0,
+            pivot_tolerance,
+        })
+    }
+
+    /// The number of rows (and columns) of the basis matrix.
+    #[must_use]
+    pub fn dim(&self) -> usize {
+        self.diagonal.len()
+    }
+
+    /// The number of column replacements since the basis was factorized.
+    #[must_use]
+    pub fn updates(&self) -> usize {
+        self.updates
+    }
+
+    /// Solves `B x = b` (known as FTRAN in simplex implementations).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b` does not have as many rows as `B`.
+    #[must_use]
+    pub fn solve(&self, b: &DVector<T>) -> DVector<T> {
+        assert_eq!(
+            b.nrows(),
+            self.dim(),
+            "b must have as many rows as the basis."
+        );
+
+        let y = self.spike(b.clone());
+        let mut x = y.clone();
+
+        for &i in self.order.iter().rev() {
+            let mut x_i = y[i].clone();
+            for (j, u_ij) in &self.upper[i] {
+                x_i -= u_ij.clone() * x[*j].clone();
+            }
+            x[i] = x_i / self.diagonal[i].clone();
+        }
+
+        x
+    }
+
+    /// Solves `Bᵀ y = c` (known as BTRAN in simplex implementations).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `c` does not have as many rows as `B`.
+    #[must_use]
+    pub fn solve_transpose(&self, c: &DVector<T>) -> DVector<T> {
+        assert_eq!(
+            c.nrows(),
+            self.dim(),
+            "c must have as many rows as the basis."
+        );
+
+        let mut z = c.clone();
+
+        for &i in &self.order {
+            z[i] /= self.diagonal[i].clone();
+            let z_i = z[i].clone();
+
+            for (j, u_ij) in &self.upper[i] {
+                z[*j] -= u_ij.clone() * z_i.clone();
+            }
+        }
+
+        for eta in self.etas.iter().rev() {
+            let update = eta.multiplier.clone() * z[eta.target].clone();
+            z[eta.source] -= update;
+        }
+
+        for (i, lane) in self.lower.iter().enumerate().rev() {
+            let z_i = z[i].clone();
+            for (j, l_ij) in lane {
+                z[j] -= l_ij.clone() * z_i.clone();
+            }
+        }
+
+        let mut y = z.clone();
+        for (i, &row) in self.permutation.iter().enumerate() {
+            y[row] = z[i].clone();
+        }
+
+        y
+    }
+
+    /// Replaces column `r` of the basis matrix with `column`, and updates the factorization.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::Singular` if the basis matrix
+    /// would become numerically singular, in which case the factorization is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `r` is not a column of the basis, or if `column` does not have as many rows as the
+    /// basis.
+    pub fn replace_column(&mut self, r: usize, column: &DVector<T>) -> Result<(), OperationError> {
+        let m = self.dim();
+        assert!(r < m, "The replaced column must be a column of the basis.");
+        assert_eq!(
+            column.nrows(),
+            m,
+            "The new column must have as many rows as the basis."
+        );
+
+        let spike = self.spike(column.clone());
+        let p = self.position[r];
+
+        // Eliminate the entries of row r in the columns that come after it, which all come before
+        // it once r is moved to the end. The entries of column r are those of the spike.
+        let mut row = DVector::zeros(m);
+        for (j, u_rj) in &self.upper[r] {
+            row[*j] = u_rj.clone();
+        }
+        let mut pivot = spike[r].clone();
+        let mut etas = Vec::new();
+
+        for &source in &self.order[p + 1..] {
+            if row[source].is_zero() {
+                continue;
+            }
+
+            let multiplier = row[source].clone() / self.diagonal[source].clone();
+            row[source] = T::zero();
+
+            for (j, u_sj) in &self.upper[source] {
+                if *j != r {
+                    row[*j] -= multiplier.clone() * u_sj.clone();
+                }
+            }
+
+            pivot -= multiplier.clone() * spike[source].clone();
+            etas.push(RowEta {
+                target: r,
+                source,
+                multiplier,
+            });
+        }
+
+        if pivot.clone().abs() <= self.pivot_tolerance {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::Singular,
+                String::from("Replacing the column would make the basis matrix singular."),
+            ));
+        }
+
+        for (i, lane) in self.upper.iter_mut().enumerate() {
+            lane.retain(|(j, _)| *j != r);
+
+            if i != r && !spike[i].is_zero() {
+                lane.push((r, spike[i].clone()));
+            }
+        }
+
+        self.upper[r].clear();
+        self.diagonal[r] = pivot;
+
+        self.order.remove(p);
+        self.order.push(r);
+        for (q, &i) in self.order.iter().enumerate().skip(p) {
+            self.position[i] = q;
+        }
+
+        self.etas.extend(etas);
+        self.updates += 1;
+
+        Ok(())
+    }
+
+    /// Computes the spike `R L⁻¹ P a`, where `R` holds the row operations of all updates so far.
+    fn spike(&self, b: DVector<T>) -> DVector<T> {
+        let mut a = b.clone();
+        for (i, &row) in self.permutation.iter().enumerate() {
+            a[i] = b[row].clone();
+        }
+
+        for (i, lane) in self.lower.iter().enumerate() {
+            for (j, l_ij) in lane {
+                let update = l_ij.clone() * a[j].clone();
+                a[i] -= update;
+            }
+        }
+
+        for eta in &self.etas {
+            let update = eta.multiplier.clone() * a[eta.source].clone();
+            a[eta.target] -= update;
+        }
+
+        a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest::*;
+    use matrixcompare::prop_assert_matrix_eq;
+    use nalgebra::DMatrix;
+    use proptest::prelude::*;
+
+    /// A sparse, diagonally dominant test matrix.
+    fn basis(m: usize) -> DMatrix<f64> {
+        DMatrix::from_fn(m, m, |i, j| match (i, j) {
+            _ if i == j => 4.0 + (i % 3) as f64,
+            _ if (i * 7 + j * 3) % 5 == 0 => 1.0 - (j % 2) as f64 * 2.0,
+            _ => 0.0,
+        })
+    }
+
+    fn assert_solves(factorization: &BasisFactorization<f64>, dense: &DMatrix<f64>) {
+        let m = dense.nrows();
+        let b = DVector::from_fn(m, |i, _| (i % 4) as f64 - 1.5);
+
+        let x = factorization.solve(&b);
+        assert!((dense * &x - &b).norm() < 1e-10 * b.norm());
+
+        let y = factorization.solve_transpose(&b);
+        assert!((dense.tr_mul(&y) - &b).norm() < 1e-10 * b.norm());
+    }
+
+    #[test]
+    fn block_matrix_rejects_inconsistent_blocks() {
+        let a = CscMatrix::<i32>::zeros(2, 3);
+        let b = CscMatrix::<i32>::zeros(3, 3);
+
+        for blocks in [
+            &[&[Some(&a), Some(&b)][..]][..],
+            &[&[Some(&a)][..], &[Some(&a), None][..]][..],
+            &[&[Some(&a), None][..], &[Some(&b), None][..]][..],
+        ] {
+            let err = block_matrix(blocks).unwrap_err();
+            assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+        }
+
+        let empty = block_matrix::<i32>(&[]).unwrap();
+        assert_eq!(empty.shape(), (0, 0));
+    }
+
+    #[test]
+    fn standard_form_adds_slack_variables() {
+        let a_eq = CscMatrix::from(&DMatrix::from_row_slice(1, 2, &[1, 2]));
+        let a_ub = CscMatrix::from(&DMatrix::from_row_slice(2, 2, &[3, 0, 0, 4]));
+
+        let a = standard_form(&a_eq, &a_ub).unwrap();
+
+        assert_eq!(
+            DMatrix::from(&a),
+            DMatrix::from_row_slice(3, 4, &[1, 2, 0, 0, 3, 0, 1, 0, 0, 4, 0, 1])
+        );
+        assert!(standard_form(&a_eq, &CscMatrix::zeros(1, 3)).is_err());
+    }
+
+    #[test]
+    fn basis_matrix_selects_columns_in_order() {
+        let a = CscMatrix::from(&DMatrix::from_row_slice(2, 3, &[1, 0, 2, 0, 3, 4]));
+
+        let b = basis_matrix(&a, &[2, 0]).unwrap();
+        assert_eq!(
+            DMatrix::from(&b),
+            DMatrix::from_row_slice(2, 2, &[2, 1, 4, 0])
+        );
+
+        let err = basis_matrix(&a, &[3]).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn basis_factorization_follows_column_replacements() {
+        let m = 12;
+        let mut dense = basis(m);
+        let mut factorization = BasisFactorization::new(&CscMatrix::from(&dense)).unwrap();
+        assert_solves(&factorization, &dense);
+
+        for (k, &r) in [3, 0, 11, 3, 7, 5, 3, 10].iter().enumerate() {
+            let column = DVector::from_fn(m, |i, _| match i {
+                _ if i == r => 3.0 + k as f64,
+                _ if (i + k) % 4 == 0 => -1.0,
+                _ => 0.0,
+            });
+
+            factorization.replace_column(r, &column).unwrap();
+            dense.set_column(r, &column);
+
+            assert_eq!(factorization.updates(), k + 1);
+            assert_solves(&factorization, &dense);
+        }
+    }
+
+    #[test]
+    fn basis_factorization_of_large_sparse_basis_stays_sparse() {
+        // A dense factorization of this basis would need 8 · 10⁸ bytes and 10¹³ flops.
+        let m = 10_000;
+        let mut coo = crate::coo::CooMatrix::new(m, m);
+
+        for i in 0..m {
+            coo.push(i, i, 1.0 + (i % 3) as f64);
+
+            if i > 0 {
+                coo.push(i, i - 1, 2.0 + (i % 2) as f64);
+            }
+
+            if i + 1 < m {
+                coo.push(i, i + 1, -1.0);
+            }
+        }
+
+        let basis = CscMatrix::from(coo.clone());
+        let mut factorization = BasisFactorization::new(&basis).unwrap();
+
+        // Partial pivoting keeps `L` and `U` within a few diagonals of the tridiagonal basis.
+        assert!(factorization.lower.nnz() <= m);
+        assert!(factorization.upper.iter().map(Vec::len).sum::<usize>() <= 2 * m);
+
+        let b = DVector::from_fn(m, |i, _| (i % 4) as f64 - 1.5);
+        let residual = |basis: &CscMatrix<f64>, x: &DVector<f64>| {
+            let mut r = -b.clone();
+            for (j, i, value) in basis.triplet_iter() {
+                r[i] += value * x[j];
+            }
+            r.norm()
+        };
+
+        assert!(residual(&basis, &factorization.solve(&b)) < 1e-8 * b.norm());
+
+        let column = DVector::from_fn(m, |i, _| if i % 1000 == 0 { 5.0 } else { 0.0 });
+        factorization.replace_column(4000, &column).unwrap();
+
+        let mut replaced = coo;
+        replaced.retain(|_, j, _| j != 4000);
+        for i in (0..m).step_by(1000) {
+            replaced.push(i, 4000, 5.0);
+        }
+
+        assert!(residual(&CscMatrix::from(replaced), &factorization.solve(&b)) < 1e-8 * b.norm());
+    }
+
+    #[test]
+    fn basis_factorization_rejects_singular_bases() {
+        let singular = DMatrix::from_row_slice(2, 2, &[1.0, 2.0, 2.0, 4.0]);
+        let err = BasisFactorization::new(&CscMatrix::from(&singular)).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::Singular));
+
+        let err = BasisFactorization::new(&CscMatrix::<f64>::zeros(2, 3)).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+
+        // Replacing a column with a copy of another one is rejected without changing the basis
+        let dense = basis(5);
+        let mut factorization = BasisFactorization::new(&CscMatrix::from(&dense)).unwrap();
+        let err = factorization
+            .replace_column(1, &dense.column(3).clone_owned())
+            .unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::Singular));
+        assert_eq!(factorization.updates(), 0);
+        assert_solves(&factorization, &dense);
+    }
+
+    #[test]
+    fn normal_matrix_regularizes_empty_rows() {
+        let a =
+            CsrMatrix::try_from_parts(3, 2, vec![0, 2, 2], vec![0, 1, 1], vec![1, 2, 3]).unwrap();
+        let mut normal = NormalMatrix::new(&a);
+        let d = DVector::from_vec(vec![2, 3]);
+
+        normal.update_with_regularization(&d, 1);
+
+        assert_eq!(
+            DMatrix::from(&normal.matrix()),
+            DMatrix::from_row_slice(3, 3, &[15, 0, 18, 0, 1, 0, 18, 0, 28])
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn normal_matrix_agrees_with_dense(
+            (a, d) in csr_strategy().prop_flat_map(|a| {
+                let d = proptest::collection::vec(-5..=5, a.ncols());
+                (Just(a), d)
+            })
+        ) {
+            let dense = DMatrix::from(&a);
+            let d = DVector::from_vec(d);
+            let m = a.nrows();
+
+            let mut normal = NormalMatrix::new(&a);
+            let pattern = normal.matrix().pattern();
+            prop_assert_matrix_eq!(normal.matrix(), &dense * dense.transpose());
+            prop_assert!((0..m).all(|i| pattern.lane(i).unwrap().contains(&i)));
+
+            normal.update_with_regularization(&d, 2);
+            let expected = &dense * DMatrix::from_diagonal(&d) * dense.transpose()
+                + DMatrix::identity(m, m) * 2;
+
+            prop_assert_matrix_eq!(normal.matrix(), expected);
+            prop_assert_eq!(normal.matrix().pattern(), pattern);
+        }
+
+        #[test]
+        fn aat_agrees_with_dense(a in csr_strategy()) {
+            let dense = DMatrix::from(&a);
+            let expected = &dense * dense.transpose();
+
+            let full = aat(&a);
+            let upper = aat_upper(&a);
+
+            prop_assert_matrix_eq!(full, expected);
+            prop_assert_matrix_eq!(upper, expected.upper_triangle());
+            prop_assert!(upper.triplet_iter().all(|(i, j, _)| i <= j));
+        }
+    }
+}
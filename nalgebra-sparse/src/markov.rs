@@ -0,0 +1,1004 @@
+//! Analysis of discrete-time Markov chains given by sparse transition matrices.
+//!
+//! A Markov chain on `n` states is described by its row-stochastic transition matrix `P`, whose
+//! entry `(i, j)` is the probability of moving from state `i` to state `j` in one step. Every row
+//! of `P` therefore holds non-negative entries that sum to one. The routines in this module take
+//! `P` as a [`CsrMatrix`](crate::cs::CsrMatrix), so that every row is one lane.
+//!
+//! The structure of the chain is derived from the graph of the non-zero entries of `P` with
+//! [`SparsityPattern::strongly_connected_components`]: its strongly connected components are the
+//! communicating classes of the chain, and classes that cannot be left are its recurrent classes.
+//! All other states are transient.
+//!
+//! - [`StationarySolver`] computes the stationary distribution of every recurrent class.
+//! - [`AbsorptionSolver`] computes the probabilities of ending up in every recurrent class, the
+//!   expected number of steps until then, and products with the fundamental matrix `(I - Q)⁻¹`
+//!   of the transient states.
+
+use crate::{
+    coo::CooMatrix,
+    cs::{CompressedRowStorage, CsMatrix, CsrMatrix},
+    error::{OperationError, OperationErrorKind},
+    iterative::{Gmres, GmresStatus},
+    pattern::SparsityPattern,
+};
+use nalgebra::{DMatrix, DVector, RealField};
+use std::borrow::Borrow;
+
+/// The stationary distributions of a Markov chain, as computed by [`StationarySolver::solve`].
+///
+/// A chain has exactly one stationary distribution per recurrent class, which is supported on the
+/// states of that class. Every stationary distribution of the whole chain is a convex combination
+/// of these, so the chain has a unique stationary distribution iff it has a single recurrent
+/// class.
+#[derive(Debug, Clone)]
+pub struct StationaryDistribution<T> {
+    /// The states of every recurrent class, in ascending order.
+    pub classes: Vec<Vec<usize>>,
+
+    /// The stationary distribution of every recurrent class, as a vector over all states of the
+    /// chain that is zero outside of the class.
+    pub distributions: Vec<DVector<T>>,
+
+    /// The transient states of the chain, which have zero probability in every stationary
+    /// distribution.
+    pub transient_states: Vec<usize>,
+
+    /// Whether the power iteration reached the tolerance for every class that was not solved
+    /// directly.
+    pub converged: bool,
+}
+
+impl<T> StationaryDistribution<T> {
+    /// The unique stationary distribution of the chain, if it has a single recurrent class.
+    #[must_use]
+    pub fn unique(&self) -> Option<&DVector<T>> {
+        match self.distributions.as_slice() {
+            [distribution] => Some(distribution),
+            _ => None,
+        }
+    }
+}
+
+/// Computes the stationary distributions `π = π P` of a Markov chain with a sparse transition
+/// matrix `P`.
+///
+/// The chain is first split into its recurrent classes (see the [module documentation](self)), so
+/// that reducible chains are handled as well. The stationary distribution of every class is then
+/// computed on the (irreducible) restriction of `P` to the class:
+///
+/// - Classes with at most [`StationarySolver::with_dense_threshold`] states are solved directly
+///   with the GTH (Grassmann–Taksar–Heyman) variant of Gaussian elimination. GTH only ever adds
+///   non-negative numbers, so it is accurate even for nearly decoupled chains.
+/// - Larger classes are solved with the power method `πₖ₊₁ = πₖ P`, starting from the uniform
+///   distribution on the class. Every step is a sparse product with `P`. The running (Cesàro)
+///   average of the iterates is tracked as well, so that periodic classes, on which the iterates
+///   themselves oscillate, converge too. The iteration stops once the iterate or the average
+///   satisfies `‖π P - π‖₁ <= tolerance`.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{cs::CsrMatrix, markov::StationarySolver};
+///
+/// // A random walk on a path with three states, which is periodic
+/// let p = CsrMatrix::from(&DMatrix::from_row_slice(3, 3, &[
+///     0.0, 1.0, 0.0,
+///     0.5, 0.0, 0.5,
+///     0.0, 1.0, 0.0,
+/// ]));
+///
+/// for threshold in [0, 10] {
+///     let result = StationarySolver::new().with_dense_threshold(threshold).solve(&p).unwrap();
+///     let pi = result.unique().unwrap();
+///
+///     assert!((pi - DVector::from_vec(vec![0.25, 0.5, 0.25])).norm() < 1e-12);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StationarySolver<T> {
+    max_iterations: usize,
+    tolerance: T,
+    dense_threshold: usize,
+}
+
+impl<T> Default for StationarySolver<T>
+where
+    T: RealField,
+{
+    fn default() -> Self {
+        Self {
+            max_iterations: 10_000,
+            tolerance: nalgebra::convert(1e-12),
+            dense_threshold: 200,
+        }
+    }
+}
+
+impl<T> StationarySolver<T>
+where
+    T: RealField,
+{
+    /// Creates a new solver that solves classes with up to 200 states directly, and larger ones
+    /// with at most 10000 power iterations to a tolerance of `1e-12`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of power iterations per class.
+    #[must_use]
+    pub fn with_max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    /// Sets the tolerance on the residual `‖π P - π‖₁` of the power iteration.
+    #[must_use]
+    pub fn with_tolerance(self, tolerance: T) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Sets the largest number of states of a class that is solved directly with GTH elimination.
+    ///
+    /// GTH elimination works on the dense restriction of the transition matrix to the class, so it
+    /// takes cubic time and quadratic memory in the number of states of the class. Larger classes
+    /// are solved with the power method on the sparse transition matrix, which takes memory linear
+    /// in the number of states of the class, and time linear in its number of transitions per
+    /// iteration.
+    #[must_use]
+    pub fn with_dense_threshold(self, dense_threshold: usize) -> Self {
+        Self {
+            dense_threshold,
+            ..self
+        }
+    }
+
+    /// Computes the stationary distribution of every recurrent class of the chain with the
+    /// transition matrix `p`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `p` is not
+    /// square, has negative entries, or has a row whose entries do not sum to one.
+    pub fn solve<MO, MI, D>(
+        &self,
+        p: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    ) -> Result<StationaryDistribution<T>, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let n = p.nrows();
+        let span = span!(
+            "stationary_distribution",
+            n = n,
+            classes = tracing::field::Empty
+        );
+
+        let chain = Chain::new(p)?;
+        let mut converged = true;
+        let mut distributions = Vec::with_capacity(chain.recurrent.len());
+
+        for class in &chain.recurrent {
+            let pi = if class.len() <= self.dense_threshold {
+                gth(chain.restrict(class, class))
+            } else {
+                let (pi, class_converged) = self.power_iteration(&chain, class);
+                converged &= class_converged;
+                pi
+            };
+
+            let mut distribution = DVector::zeros(n);
+            for (&state, pi_state) in class.iter().zip(pi.iter()) {
+                distribution[state] = pi_state.clone();
+            }
+
+            distributions.push(distribution);
+        }
+
+        record!(span, classes = chain.recurrent.len());
+
+        Ok(StationaryDistribution {
+            classes: chain.recurrent,
+            distributions,
+            transient_states: chain.transient,
+            converged,
+        })
+    }
+
+    /// Runs the power method with Cesàro averaging on the recurrent class `class` of `chain`, and
+    /// returns the better of the final iterate and the final average, alongside whether it
+    /// converged.
+    ///
+    /// Every step is a sparse product `π P` over the transitions out of the states of the class,
+    /// so the restriction of `P` to the class is never formed.
+    fn power_iteration(&self, chain: &Chain<'_, T>, class: &[usize]) -> (DVector<T>, bool) {
+        let m = class.len();
+        let step = |pi: &DVector<T>| {
+            let mut next = DVector::zeros(m);
+
+            for (i, &state) in class.iter().enumerate() {
+                for (column, p_ij) in chain.lane(state) {
+                    // No non-zero transition leaves a recurrent class, but explicit zeros may
+                    if let Ok(j) = class.binary_search(&column) {
+                        next[j] += pi[i].clone() * p_ij.clone();
+                    }
+                }
+            }
+
+            next
+        };
+        let residual = |pi: &DVector<T>| (step(pi) - pi).lp_norm(1);
+
+        let mut pi = DVector::from_element(m, T::one() / nalgebra::convert(m as f64));
+        let mut sum = pi.clone();
+        let mut best = (residual(&pi), pi.clone());
+
+        for k in 1..=self.max_iterations {
+            if best.0 <= self.tolerance {
+                return (best.1, true);
+            }
+
+            pi = step(&pi);
+            // Renormalize to counter the drift of the total probability due to rounding
+            let total = pi.sum();
+            pi /= total;
+            sum += &pi;
+
+            let average = &sum / nalgebra::convert::<f64, T>((k + 1) as f64);
+
+            for candidate in [pi.clone(), average] {
+                let r = residual(&candidate);
+
+                if r < best.0 {
+                    best = (r, candidate);
+                }
+            }
+        }
+
+        let converged = best.0 <= self.tolerance;
+        (best.1, converged)
+    }
+}
+
+/// The absorption behaviour of a Markov chain, as computed by [`AbsorptionSolver::solve`].
+///
+/// Every recurrent class acts as one absorbing target: once the chain enters it, it never leaves.
+/// For an absorbing chain in the classic sense, every absorbing state is a class of its own.
+#[derive(Debug, Clone)]
+pub struct Absorption<T> {
+    /// The states of every recurrent class, in ascending order.
+    pub classes: Vec<Vec<usize>>,
+
+    /// The transient states of the chain.
+    pub transient_states: Vec<usize>,
+
+    /// The probability that the chain started in state `i` ends up in class `c`, at `(i, c)`.
+    ///
+    /// Every row sums to one. The rows of recurrent states are one in the column of their own
+    /// class.
+    pub probabilities: DMatrix<T>,
+
+    /// The expected number of steps until the chain started in a state enters a recurrent class,
+    /// which is zero for recurrent states.
+    pub expected_steps: DVector<T>,
+
+    /// Whether the linear solver converged for every right hand side, which is always the case
+    /// when the transient states are solved directly.
+    pub converged: bool,
+}
+
+/// The product of the fundamental matrix of a Markov chain with a matrix, as computed by
+/// [`AbsorptionSolver::fundamental_matrix_product`].
+#[derive(Debug, Clone)]
+pub struct FundamentalMatrixProduct<T> {
+    /// The product `N V`, which is zero in the rows of recurrent states.
+    pub product: DMatrix<T>,
+
+    /// Whether the linear solver converged for every column of `V`.
+    pub converged: bool,
+}
+
+/// Computes absorption probabilities, expected hitting times and products with the fundamental
+/// matrix of a Markov chain with a sparse transition matrix `P`.
+///
+/// Order the states so that the transient states come first. Then `P` is of the form
+///
+/// ```text
+/// P = [ Q  R ]
+///     [ 0  P' ]
+/// ```
+///
+/// where `Q` holds the transitions between transient states, and `R` those from transient into
+/// recurrent states. The fundamental matrix `N = (I - Q)⁻¹` holds the expected number of visits
+/// of every transient state before absorption, so that the absorption probabilities are `N R`
+/// (summed over every recurrent class) and the expected numbers of steps until absorption are
+/// `N 1`.
+///
+/// `N` is dense even if `P` is sparse, so it is never formed. Instead, `(I - Q) X = B` is solved
+/// for the required right hand sides `B`:
+///
+/// - If there are at most [`AbsorptionSolver::with_dense_threshold`] transient states, `I - Q` is
+///   factorized with a dense LU decomposition.
+/// - Otherwise, `I - Q` is assembled as a sparse matrix and every column of `B` is solved with
+///   [`Gmres`], which can be configured with [`AbsorptionSolver::with_linear_solver`].
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::DMatrix;
+/// use nalgebra_sparse::{cs::CsrMatrix, markov::AbsorptionSolver};
+///
+/// // The gambler's ruin with a fair coin: states 0 and 3 are absorbing
+/// let p = CsrMatrix::from(&DMatrix::<f64>::from_row_slice(4, 4, &[
+///     1.0, 0.0, 0.0, 0.0,
+///     0.5, 0.0, 0.5, 0.0,
+///     0.0, 0.5, 0.0, 0.5,
+///     0.0, 0.0, 0.0, 1.0,
+/// ]));
+///
+/// for threshold in [0, 10] {
+///     let absorption = AbsorptionSolver::new().with_dense_threshold(threshold).solve(&p).unwrap();
+///
+///     assert_eq!(absorption.classes, vec![vec![0], vec![3]]);
+///     assert!((absorption.probabilities[(1, 0)] - 2.0 / 3.0).abs() < 1e-10);
+///     assert!((absorption.expected_steps[1] - 2.0).abs() < 1e-10);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AbsorptionSolver<'a, T> {
+    dense_threshold: usize,
+    linear_solver: Gmres<'a, T>,
+}
+
+impl<'a, T> Default for AbsorptionSolver<'a, T>
+where
+    T: RealField,
+{
+    fn default() -> Self {
+        Self {
+            dense_threshold: 200,
+            linear_solver: Gmres::new().with_tolerance(nalgebra::convert(1e-12)),
+        }
+    }
+}
+
+impl<'a, T> AbsorptionSolver<'a, T>
+where
+    T: RealField,
+{
+    /// Creates a new solver that solves for up to 200 transient states directly, and for more
+    /// with GMRES to a relative tolerance of `1e-12`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the largest number of transient states for which `I - Q` is factorized densely.
+    #[must_use]
+    pub fn with_dense_threshold(self, dense_threshold: usize) -> Self {
+        Self {
+            dense_threshold,
+            ..self
+        }
+    }
+
+    /// Sets the solver for the sparse systems with `I - Q`.
+    #[must_use]
+    pub fn with_linear_solver(self, linear_solver: Gmres<'a, T>) -> Self {
+        Self {
+            linear_solver,
+            ..self
+        }
+    }
+
+    /// Computes the absorption probabilities and expected numbers of steps until absorption of
+    /// the chain with the transition matrix `p`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `p` is not
+    /// a valid transition matrix (see [`StationarySolver::solve`]).
+    pub fn solve<MO, MI, D>(
+        &self,
+        p: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    ) -> Result<Absorption<T>, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let n = p.nrows();
+        let span = span!("absorption", n = n, transient = tracing::field::Empty);
+
+        let chain = Chain::new(p)?;
+        let transient = &chain.transient;
+        let nclasses = chain.recurrent.len();
+        record!(span, transient = transient.len());
+
+        let mut class_of = vec![None; n];
+        for (c, class) in chain.recurrent.iter().enumerate() {
+            for &state in class {
+                class_of[state] = Some(c);
+            }
+        }
+
+        // The right hand sides are the columns of R, summed per class, followed by all ones
+        let mut rhs = DMatrix::zeros(transient.len(), nclasses + 1);
+        for (i, &state) in transient.iter().enumerate() {
+            for (j, p_ij) in chain.lane(state) {
+                if let Some(c) = class_of[j] {
+                    rhs[(i, c)] += p_ij.clone();
+                }
+            }
+
+            rhs[(i, nclasses)] = T::one();
+        }
+
+        let (solution, converged) = self.solve_transient(&chain, rhs)?;
+
+        let mut probabilities = DMatrix::zeros(n, nclasses);
+        let mut expected_steps = DVector::zeros(n);
+
+        for (i, &state) in transient.iter().enumerate() {
+            for c in 0..nclasses {
+                probabilities[(state, c)] = solution[(i, c)].clone();
+            }
+
+            expected_steps[state] = solution[(i, nclasses)].clone();
+        }
+
+        for (state, c) in class_of.into_iter().enumerate() {
+            if let Some(c) = c {
+                probabilities[(state, c)] = T::one();
+            }
+        }
+
+        Ok(Absorption {
+            classes: chain.recurrent,
+            transient_states: chain.transient,
+            probabilities,
+            expected_steps,
+            converged,
+        })
+    }
+
+    /// Computes the product `N V` of the fundamental matrix `N = (I - Q)⁻¹` of the chain with the
+    /// transition matrix `p` and the matrix `V`.
+    ///
+    /// `V` has a row for every state of the chain, of which only those of transient states are
+    /// used. For example, the column `(N V)[:, k]` for the indicator vector `V[:, k]` of a
+    /// transient state `k` holds the expected number of visits of `k` before absorption, from
+    /// every starting state.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `p` is not
+    /// a valid transition matrix (see [`StationarySolver::solve`]), or if `V` does not have a row
+    /// for every state.
+    pub fn fundamental_matrix_product<MO, MI, D>(
+        &self,
+        p: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+        v: &DMatrix<T>,
+    ) -> Result<FundamentalMatrixProduct<T>, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let n = p.nrows();
+
+        if v.nrows() != n {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!(
+                    "The matrix to multiply has {} rows, but the chain has {} states.",
+                    v.nrows(),
+                    n
+                ),
+            ));
+        }
+
+        let chain = Chain::new(p)?;
+        let rhs = v.select_rows(&chain.transient);
+        let (solution, converged) = self.solve_transient(&chain, rhs)?;
+
+        let mut product = DMatrix::zeros(n, v.ncols());
+        for (i, &state) in chain.transient.iter().enumerate() {
+            product.row_mut(state).copy_from(&solution.row(i));
+        }
+
+        Ok(FundamentalMatrixProduct { product, converged })
+    }
+
+    /// Solves `(I - Q) X = B` on the transient states of `chain`, and returns whether every
+    /// column converged.
+    fn solve_transient(
+        &self,
+        chain: &Chain<'_, T>,
+        mut rhs: DMatrix<T>,
+    ) -> Result<(DMatrix<T>, bool), OperationError> {
+        let transient = &chain.transient;
+        let m = transient.len();
+
+        if m <= self.dense_threshold {
+            let mut i_minus_q = -chain.restrict(transient, transient);
+            for i in 0..m {
+                i_minus_q[(i, i)] += T::one();
+            }
+
+            // I - Q is non-singular for the transient states of a chain, up to rounding
+            if !i_minus_q.lu().solve_mut(&mut rhs) {
+                return Err(OperationError::from_kind_and_message(
+                    OperationErrorKind::Singular,
+                    String::from("I - Q is numerically singular."),
+                ));
+            }
+
+            return Ok((rhs, true));
+        }
+
+        let mut position = vec![None; chain.nstates()];
+        for (i, &state) in transient.iter().enumerate() {
+            position[state] = Some(i);
+        }
+
+        let mut coo = CooMatrix::new(m, m);
+        for (i, &state) in transient.iter().enumerate() {
+            coo.push(i, i, T::one());
+
+            for (j, p_ij) in chain.lane(state) {
+                if let Some(j) = position[j] {
+                    coo.push(i, j, -p_ij.clone());
+                }
+            }
+        }
+        let i_minus_q = CsrMatrix::from(coo);
+
+        let mut converged = true;
+        for mut column in rhs.column_iter_mut() {
+            let result = self
+                .linear_solver
+                .solve(&i_minus_q, &column.clone_owned())?;
+            converged &= result.status == GmresStatus::Converged;
+            column.copy_from(&result.solution);
+        }
+
+        Ok((rhs, converged))
+    }
+}
+
+/// The class structure of a Markov chain, alongside its validated transition matrix.
+pub(crate) struct Chain<'a, T> {
+    offsets: &'a [usize],
+    indices: &'a [usize],
+    data: &'a [T],
+
+    /// The states of every recurrent class, in ascending order.
+    pub(crate) recurrent: Vec<Vec<usize>>,
+
+    /// The transient states, in ascending order.
+    pub(crate) transient: Vec<usize>,
+}
+
+impl<'a, T> Chain<'a, T>
+where
+    T: RealField,
+{
+    /// Validates the transition matrix `p` and determines the classes of its chain.
+    pub(crate) fn new<MO, MI, D>(
+        p: &'a CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    ) -> Result<Self, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let (n, ncols) = p.shape();
+
+        if n != ncols {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from("The transition matrix is not square."),
+            ));
+        }
+
+        let (offsets, indices, data) = p.cs_data();
+        let lane = |row: usize| offsets[row]..offsets.get(row + 1).copied().unwrap_or(data.len());
+        let tolerance = T::default_epsilon().sqrt();
+
+        // The graph of the non-zero transitions, ignoring explicitly stored zeros
+        let mut graph_offsets = Vec::with_capacity(n);
+        let mut graph_indices = Vec::with_capacity(indices.len());
+
+        for row in 0..n {
+            graph_offsets.push(graph_indices.len());
+            let mut total = T::zero();
+
+            for k in lane(row) {
+                if data[k] < T::zero() {
+                    return Err(OperationError::from_kind_and_message(
+                        OperationErrorKind::InvalidPattern,
+                        format!("The transition matrix has a negative entry in row {}.", row),
+                    ));
+                }
+
+                if !data[k].is_zero() {
+                    graph_indices.push(indices[k]);
+                    total += data[k].clone();
+                }
+            }
+
+            if (total - T::one()).abs() > tolerance {
+                return Err(OperationError::from_kind_and_message(
+                    OperationErrorKind::InvalidPattern,
+                    format!("Row {} of the transition matrix does not sum to one.", row),
+                ));
+            }
+        }
+
+        let graph = unsafe {
+            SparsityPattern::from_offsets_and_indices_unchecked(n, graph_offsets, graph_indices)
+        };
+        let components = graph.strongly_connected_components();
+        let labels = components.labels();
+
+        // A class is recurrent iff no transition leaves it
+        let mut closed = vec![true; components.count()];
+        for (from, to) in graph.entries() {
+            if labels[from] != labels[to] {
+                closed[labels[from]] = false;
+            }
+        }
+
+        let mut recurrent = Vec::new();
+        let mut transient = Vec::new();
+
+        for members in components.members() {
+            if closed[labels[members[0]]] {
+                recurrent.push(members);
+            } else {
+                transient.extend(members);
+            }
+        }
+
+        recurrent.sort_unstable_by_key(|members| members[0]);
+        transient.sort_unstable();
+
+        Ok(Self {
+            offsets,
+            indices,
+            data,
+            recurrent,
+            transient,
+        })
+    }
+
+    /// The number of states of the chain.
+    pub(crate) fn nstates(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// The transitions `(j, P[row, j])` out of the state `row`, including explicit zeros.
+    pub(crate) fn lane(&self, row: usize) -> impl Iterator<Item = (usize, &T)> {
+        let end = self
+            .offsets
+            .get(row + 1)
+            .copied()
+            .unwrap_or(self.data.len());
+        let range = self.offsets[row]..end;
+
+        self.indices[range.clone()]
+            .iter()
+            .copied()
+            .zip(&self.data[range])
+    }
+
+    /// Extracts the transition probabilities from the states `rows` to the states `columns` into
+    /// a dense matrix. Both lists must be sorted.
+    pub(crate) fn restrict(&self, rows: &[usize], columns: &[usize]) -> DMatrix<T> {
+        let mut restricted = DMatrix::zeros(rows.len(), columns.len());
+
+        for (i, &row) in rows.iter().enumerate() {
+            for (column, p_ij) in self.lane(row) {
+                if let Ok(j) = columns.binary_search(&column) {
+                    restricted[(i, j)] = p_ij.clone();
+                }
+            }
+        }
+
+        restricted
+    }
+}
+
+/// Computes the stationary distribution of an irreducible chain with the transition matrix `p`
+/// with GTH elimination.
+fn gth<T: RealField>(mut p: DMatrix<T>) -> DVector<T> {
+    let m = p.nrows();
+
+    // Eliminate the states from the last to the first, computing the probability of leaving
+    // every state as the sum of its remaining off-diagonal transitions rather than as one minus
+    // the diagonal, which avoids any subtraction
+    for k in (1..m).rev() {
+        let mut leaving = T::zero();
+        for j in 0..k {
+            leaving += p[(k, j)].clone();
+        }
+
+        for i in 0..k {
+            p[(i, k)] /= leaving.clone();
+        }
+
+        for j in 0..k {
+            let p_kj = p[(k, j)].clone();
+
+            for i in 0..k {
+                let p_ik = p[(i, k)].clone();
+                p[(i, j)] += p_ik * p_kj.clone();
+            }
+        }
+    }
+
+    let mut pi = DVector::zeros(m);
+
+    if m > 0 {
+        pi[0] = T::one();
+    }
+
+    for k in 1..m {
+        let mut pi_k = T::zero();
+        for i in 0..k {
+            pi_k += pi[i].clone() * p[(i, k)].clone();
+        }
+        pi[k] = pi_k;
+    }
+
+    let total = pi.sum();
+    pi / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cs::CsrMatrix;
+
+    /// A random walk on a weighted ring with `n` states, with a lazy step at every state.
+    fn ring(n: usize) -> DMatrix<f64> {
+        DMatrix::from_fn(n, n, |i, j| {
+            let weight = 1.0 + (i % 3) as f64;
+            if i == j {
+                0.1
+            } else if j == (i + 1) % n {
+                0.9 * weight / (weight + 1.0)
+            } else if i == (j + 1) % n {
+                0.9 / (weight + 1.0)
+            } else {
+                0.0
+            }
+        })
+    }
+
+    fn assert_stationary(p: &DMatrix<f64>, pi: &DVector<f64>) {
+        assert!((p.tr_mul(pi) - pi).lp_norm(1) < 1e-10);
+        assert!((pi.sum() - 1.0).abs() < 1e-12);
+        assert!(pi.iter().all(|&pi_i| pi_i >= 0.0));
+    }
+
+    #[test]
+    fn rejects_invalid_transition_matrices() {
+        let solver = StationarySolver::new();
+
+        for p in [
+            DMatrix::from_row_slice(1, 2, &[0.5, 0.5]),
+            DMatrix::from_row_slice(2, 2, &[0.5, 0.5, 0.5, 0.4]),
+            DMatrix::from_row_slice(2, 2, &[1.5, -0.5, 0.5, 0.5]),
+        ] {
+            let err = solver.solve(&CsrMatrix::from(&p)).unwrap_err();
+            assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+        }
+    }
+
+    #[test]
+    fn direct_and_iterative_solutions_agree() {
+        let p = ring(30);
+        let csr = CsrMatrix::from(&p);
+
+        let direct = StationarySolver::new().solve(&csr).unwrap();
+        let iterative = StationarySolver::new()
+            .with_dense_threshold(0)
+            .solve(&csr)
+            .unwrap();
+
+        assert!(direct.converged && iterative.converged);
+        assert_stationary(&p, direct.unique().unwrap());
+        assert_stationary(&p, iterative.unique().unwrap());
+        assert!((direct.unique().unwrap() - iterative.unique().unwrap()).norm() < 1e-10);
+    }
+
+    #[test]
+    fn power_iteration_on_large_classes_stays_sparse() {
+        // A random walk on a cycle of 10⁵ states, whose dense restriction would take 80 GB
+        let n = 100_000;
+        let mut coo = CooMatrix::new(n, n);
+
+        for i in 0..n {
+            coo.push(i, (i + 1) % n, 0.5);
+            coo.push(i, (i + n - 1) % n, 0.5);
+        }
+
+        let result = StationarySolver::new()
+            .solve(&CsrMatrix::from(coo))
+            .unwrap();
+        let pi = result.unique().unwrap();
+
+        assert!(result.converged);
+        assert!(pi.iter().all(|&pi_i| (pi_i - 1.0 / n as f64).abs() < 1e-15));
+    }
+
+    #[test]
+    fn gth_is_accurate_for_nearly_decoupled_chains() {
+        // Two states that almost never switch, with very different switching probabilities
+        let p = DMatrix::from_row_slice(2, 2, &[1.0 - 1e-14, 1e-14, 3e-14, 1.0 - 3e-14]);
+        let pi: DVector<f64> = gth(p);
+
+        assert!((pi[0] - 0.75).abs() < 1e-15);
+        assert!((pi[1] - 0.25).abs() < 1e-15);
+    }
+
+    #[test]
+    fn reducible_chains_have_one_distribution_per_recurrent_class() {
+        // States 0 and 1 are transient, {2, 3} and {4} are recurrent, explicit zeros are ignored
+        let p = DMatrix::from_row_slice(
+            5,
+            5,
+            &[
+                0.2, 0.3, 0.5, 0.0, 0.0, //
+                0.5, 0.0, 0.0, 0.0, 0.5, //
+                0.0, 0.0, 0.4, 0.6, 0.0, //
+                0.0, 0.0, 0.9, 0.1, 0.0, //
+                0.0, 0.0, 0.0, 0.0, 1.0, //
+            ],
+        );
+        let mut csr = CsrMatrix::from(&p);
+        let (offsets, mut indices, mut data) = csr.disassemble();
+        // Store an explicit zero transition from state 4 to state 0
+        indices.insert(offsets[4], 0);
+        data.insert(offsets[4], 0.0);
+        csr = CsrMatrix::try_from_parts(5, 5, offsets, indices, data).unwrap();
+
+        for threshold in [0, 10] {
+            let result = StationarySolver::new()
+                .with_dense_threshold(threshold)
+                .solve(&csr)
+                .unwrap();
+
+            assert_eq!(result.classes, vec![vec![2, 3], vec![4]]);
+            assert_eq!(result.transient_states, vec![0, 1]);
+            assert!(result.unique().is_none());
+            assert!(result.converged);
+
+            for (class, distribution) in result.classes.iter().zip(&result.distributions) {
+                assert_stationary(&p, distribution);
+                assert!((0..5)
+                    .filter(|state| !class.contains(state))
+                    .all(|state| distribution[state] == 0.0));
+            }
+
+            assert!((result.distributions[0][2] - 0.6).abs() < 1e-10);
+        }
+    }
+
+    /// The gambler's ruin with a fair coin on the states `0..=n`, of which `0` and `n` are
+    /// absorbing.
+    fn gamblers_ruin(n: usize) -> DMatrix<f64> {
+        DMatrix::from_fn(n + 1, n + 1, |i, j| {
+            if i == 0 || i == n {
+                (i == j) as u8 as f64
+            } else if j + 1 == i || j == i + 1 {
+                0.5
+            } else {
+                0.0
+            }
+        })
+    }
+
+    #[test]
+    fn absorption_agrees_with_the_gamblers_ruin() {
+        let n = 40;
+        let p = CsrMatrix::from(&gamblers_ruin(n));
+
+        for threshold in [0, 100] {
+            let absorption = AbsorptionSolver::new()
+                .with_dense_threshold(threshold)
+                .solve(&p)
+                .unwrap();
+
+            assert!(absorption.converged);
+            assert_eq!(absorption.classes, vec![vec![0], vec![n]]);
+            assert_eq!(absorption.transient_states, (1..n).collect::<Vec<_>>());
+
+            for i in 0..=n {
+                let ruin = 1.0 - i as f64 / n as f64;
+                let steps = (i * (n - i)) as f64;
+
+                assert!((absorption.probabilities[(i, 0)] - ruin).abs() < 1e-8);
+                assert!((absorption.probabilities[(i, 1)] - (1.0 - ruin)).abs() < 1e-8);
+                assert!((absorption.expected_steps[i] - steps).abs() < 1e-6 * steps.max(1.0));
+            }
+        }
+    }
+
+    #[test]
+    fn absorption_into_recurrent_classes() {
+        // State 0 is transient and leaves into the classes {1, 2} and {3}
+        let p = DMatrix::<f64>::from_row_slice(
+            4,
+            4,
+            &[
+                0.5, 0.1, 0.2, 0.2, //
+                0.0, 0.3, 0.7, 0.0, //
+                0.0, 1.0, 0.0, 0.0, //
+                0.0, 0.0, 0.0, 1.0, //
+            ],
+        );
+        let absorption = AbsorptionSolver::new().solve(&CsrMatrix::from(&p)).unwrap();
+
+        assert_eq!(absorption.classes, vec![vec![1, 2], vec![3]]);
+        assert!((absorption.probabilities[(0, 0)] - 0.6).abs() < 1e-12);
+        assert!((absorption.probabilities[(0, 1)] - 0.4).abs() < 1e-12);
+        assert!((absorption.expected_steps[0] - 2.0).abs() < 1e-12);
+        assert_eq!(
+            absorption.probabilities.row(2),
+            DMatrix::from_row_slice(1, 2, &[1.0, 0.0])
+        );
+        assert_eq!(absorption.expected_steps[2], 0.0);
+    }
+
+    #[test]
+    fn fundamental_matrix_product_agrees_with_the_dense_inverse() {
+        let n = 12;
+        let dense = gamblers_ruin(n);
+        let p = CsrMatrix::from(&dense);
+        let v = DMatrix::from_fn(n + 1, 3, |i, j| (i * (j + 1) % 5) as f64);
+
+        let q = dense.slice((1, 1), (n - 1, n - 1)).clone_owned();
+        let fundamental = (DMatrix::identity(n - 1, n - 1) - q).try_inverse().unwrap();
+        let mut expected = DMatrix::zeros(n + 1, 3);
+        expected
+            .rows_mut(1, n - 1)
+            .copy_from(&(fundamental * v.rows(1, n - 1)));
+
+        for threshold in [0, 100] {
+            let result = AbsorptionSolver::new()
+                .with_dense_threshold(threshold)
+                .fundamental_matrix_product(&p, &v)
+                .unwrap();
+
+            assert!(result.converged);
+            assert!((result.product - &expected).norm() < 1e-8 * expected.norm());
+        }
+
+        let err = AbsorptionSolver::new()
+            .fundamental_matrix_product(&p, &DMatrix::zeros(n, 1))
+            .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn reports_non_convergence() {
+        let p = ring(30);
+        let result = StationarySolver::new()
+            .with_dense_threshold(0)
+            .with_max_iterations(3)
+            .solve(&CsrMatrix::from(&p))
+            .unwrap();
+
+        assert!(!result.converged);
+        assert!((result.unique().unwrap().sum() - 1.0).abs() < 1e-12);
+    }
+}
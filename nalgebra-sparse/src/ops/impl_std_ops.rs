@@ -4,11 +4,11 @@ use super::serial::{scalar::*, spadd::*, spmm::*, spsub::*};
 use crate::cs::{
     CompressedColumnStorage, CompressedRowStorage, Compression, CsMatrix, CscMatrix, CsrMatrix,
 };
-use nalgebra::{Dim, Matrix, RawStorage, RawStorageMut, Scalar};
+use nalgebra::{Dim, Matrix, RawStorage, RawStorageMut, Scalar, SliceStorage};
 use num_traits::Zero;
 use std::{
-    borrow::Borrow,
-    ops::{Add, AddAssign, Div, Mul, Neg, Sub},
+    borrow::{Borrow, BorrowMut},
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
 };
 
 // Addition
@@ -251,6 +251,44 @@ where
     }
 }
 
+// In-place addition and subtraction
+
+impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2, C> AddAssign<CsMatrix<T2, MO2, MI2, D2, C>>
+    for CsMatrix<T1, MO1, MI1, D1, C>
+where
+    T1: Scalar + AddAssign<T2>,
+    T2: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: BorrowMut<[T1]>,
+    D2: Borrow<[T2]>,
+    C: Compression,
+{
+    fn add_assign(&mut self, rhs: CsMatrix<T2, MO2, MI2, D2, C>) {
+        spadd_assign(self, rhs).unwrap()
+    }
+}
+
+impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2, C> SubAssign<CsMatrix<T2, MO2, MI2, D2, C>>
+    for CsMatrix<T1, MO1, MI1, D1, C>
+where
+    T1: Scalar + SubAssign<T2>,
+    T2: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: BorrowMut<[T1]>,
+    D2: Borrow<[T2]>,
+    C: Compression,
+{
+    fn sub_assign(&mut self, rhs: CsMatrix<T2, MO2, MI2, D2, C>) {
+        spsub_assign(self, rhs).unwrap()
+    }
+}
+
 // Multiplication
 
 impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2> Mul<CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>>
@@ -413,6 +451,69 @@ where
     }
 }
 
+// Borrowed operands
+//
+// Every operation on borrowed matrices is forwarded to the same operation on views of the
+// operands, so that `&a + &b` neither consumes nor copies `a` and `b`.
+
+/// A borrowed view of a compressed matrix, as produced by [`CsMatrix::to_view`].
+type View<'a, T, C> = CsMatrix<T, &'a [usize], &'a [usize], &'a [T], C>;
+
+macro_rules! impl_borrowed_sparse_binop {
+    ($($trait:ident, $method:ident;)*) => ($(
+        impl<'a, 'b, T1, T2, MO1, MO2, MI1, MI2, D1, D2, C1, C2>
+            $trait<&'b CsMatrix<T2, MO2, MI2, D2, C2>> for &'a CsMatrix<T1, MO1, MI1, D1, C1>
+        where
+            T1: Scalar,
+            T2: Scalar,
+            MO1: Borrow<[usize]>,
+            MO2: Borrow<[usize]>,
+            MI1: Borrow<[usize]>,
+            MI2: Borrow<[usize]>,
+            D1: Borrow<[T1]>,
+            D2: Borrow<[T2]>,
+            C1: Compression,
+            C2: Compression,
+            View<'a, T1, C1>: $trait<View<'b, T2, C2>>,
+        {
+            type Output = <View<'a, T1, C1> as $trait<View<'b, T2, C2>>>::Output;
+
+            fn $method(self, rhs: &'b CsMatrix<T2, MO2, MI2, D2, C2>) -> Self::Output {
+                self.to_view().$method(rhs.to_view())
+            }
+        }
+    )*)
+}
+
+impl_borrowed_sparse_binop! {
+    Add, add;
+    Sub, sub;
+    Mul, mul;
+}
+
+impl<'a, 'b, T1, T2, R, C, S, MO, MI, D, Cs> Mul<&'b Matrix<T2, R, C, S>>
+    for &'a CsMatrix<T1, MO, MI, D, Cs>
+where
+    T1: Scalar,
+    T2: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T2, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+    Cs: Compression,
+    View<'a, T1, Cs>: Mul<Matrix<T2, R, C, SliceStorage<'b, T2, R, C, S::RStride, S::CStride>>>,
+{
+    type Output = <View<'a, T1, Cs> as Mul<
+        Matrix<T2, R, C, SliceStorage<'b, T2, R, C, S::RStride, S::CStride>>,
+    >>::Output;
+
+    fn mul(self, rhs: &'b Matrix<T2, R, C, S>) -> Self::Output {
+        self.to_view() * rhs.generic_slice((0, 0), rhs.shape_generic())
+    }
+}
+
 // Scalars
 
 macro_rules! impl_sparse_scalar_product_and_div {
@@ -467,7 +568,214 @@ macro_rules! impl_sparse_scalar_product_and_div {
                 sp_cs_scalar_div(self, rhs)
             }
         }
+
+        impl<'a, T1, MO, MI, D, C> Mul<$t> for &'a CsMatrix<T1, MO, MI, D, C>
+        where
+            T1: Scalar + Mul<$t>,
+            <T1 as Mul<$t>>::Output: Scalar,
+            MO: Borrow<[usize]>,
+            MI: Borrow<[usize]>,
+            D: Borrow<[T1]>,
+            C: Compression,
+        {
+            type Output = CsMatrix<
+                <T1 as Mul<$t>>::Output,
+                &'a [usize],
+                &'a [usize],
+                Vec<<T1 as Mul<$t>>::Output>,
+                C,
+            >;
+
+            fn mul(self, rhs: $t) -> Self::Output {
+                sp_cs_scalar_prod(self.to_view(), rhs)
+            }
+        }
+
+        impl<'a, T1, MO, MI, D, C> Mul<&'a CsMatrix<T1, MO, MI, D, C>> for $t
+        where
+            T1: Scalar + Mul<$t>,
+            <T1 as Mul<$t>>::Output: Scalar,
+            MO: Borrow<[usize]>,
+            MI: Borrow<[usize]>,
+            D: Borrow<[T1]>,
+            C: Compression,
+        {
+            type Output = CsMatrix<
+                <T1 as Mul<$t>>::Output,
+                &'a [usize],
+                &'a [usize],
+                Vec<<T1 as Mul<$t>>::Output>,
+                C,
+            >;
+
+            fn mul(self, rhs: &'a CsMatrix<T1, MO, MI, D, C>) -> Self::Output {
+                sp_cs_scalar_prod(rhs.to_view(), self)
+            }
+        }
+
+        impl<'a, T1, MO, MI, D, C> Div<$t> for &'a CsMatrix<T1, MO, MI, D, C>
+        where
+            T1: Scalar + Div<$t>,
+            <T1 as Div<$t>>::Output: Scalar,
+            MO: Borrow<[usize]>,
+            MI: Borrow<[usize]>,
+            D: Borrow<[T1]>,
+            C: Compression,
+        {
+            type Output = CsMatrix<
+                <T1 as Div<$t>>::Output,
+                &'a [usize],
+                &'a [usize],
+                Vec<<T1 as Div<$t>>::Output>,
+                C,
+            >;
+
+            fn div(self, rhs: $t) -> Self::Output {
+                sp_cs_scalar_div(self.to_view(), rhs)
+            }
+        }
     )*)
 }
 
 impl_sparse_scalar_product_and_div!(isize usize u8 i8 u16 i16 u32 i32 u64 i64 f32 f64);
+
+// In-place scaling
+
+impl<T, MO, MI, D, C> MulAssign<T> for CsMatrix<T, MO, MI, D, C>
+where
+    T: Scalar + MulAssign,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: BorrowMut<[T]>,
+    C: Compression,
+{
+    fn mul_assign(&mut self, rhs: T) {
+        self.scale_mut(rhs);
+    }
+}
+
+impl<T, MO, MI, D, C> DivAssign<T> for CsMatrix<T, MO, MI, D, C>
+where
+    T: Scalar + DivAssign,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: BorrowMut<[T]>,
+    C: Compression,
+{
+    fn div_assign(&mut self, rhs: T) {
+        self.unscale_mut(rhs);
+    }
+}
+
+// Negation
+
+impl<T, MO, MI, D, C> Neg for CsMatrix<T, MO, MI, D, C>
+where
+    T: Scalar + Neg,
+    <T as Neg>::Output: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    type Output = CsMatrix<<T as Neg>::Output, MO, MI, Vec<<T as Neg>::Output>, C>;
+
+    fn neg(self) -> Self::Output {
+        sp_cs_neg(self)
+    }
+}
+
+impl<'a, T, MO, MI, D, C> Neg for &'a CsMatrix<T, MO, MI, D, C>
+where
+    T: Scalar + Neg,
+    <T as Neg>::Output: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    type Output =
+        CsMatrix<<T as Neg>::Output, &'a [usize], &'a [usize], Vec<<T as Neg>::Output>, C>;
+
+    fn neg(self) -> Self::Output {
+        sp_cs_neg(self.to_view())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cs::{CscMatrix, CsrMatrix},
+        proptest::*,
+    };
+    use matrixcompare::prop_assert_matrix_eq;
+    use nalgebra::DMatrix;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn borrowed_operators_agree_with_dense(
+            (a, b) in csr_strategy().prop_flat_map(|a| {
+                let (nrows, ncols) = a.shape();
+                (Just(a), csr(PROPTEST_I32_VALUE_STRATEGY, nrows, ncols, PROPTEST_MAX_NNZ))
+            })
+        ) {
+            let dense_a = DMatrix::from(&a);
+            let dense_b = DMatrix::from(&b);
+
+            prop_assert_matrix_eq!(&a + &b, &dense_a + &dense_b);
+            prop_assert_matrix_eq!(&a - &b, &dense_a - &dense_b);
+
+            let b_t = CscMatrix::from(b.transpose_owned());
+            prop_assert_matrix_eq!(&a * &b_t, &dense_a * dense_b.transpose());
+            prop_assert_matrix_eq!(&a * &dense_b.transpose(), &dense_a * dense_b.transpose());
+
+            // The operands are still usable after the operations.
+            prop_assert_matrix_eq!(a, dense_a);
+        }
+
+        #[test]
+        fn borrowed_operators_accept_views(a in csr_strategy()) {
+            let view = a.to_view();
+            let identity = CsrMatrix::<i32>::identity(a.ncols());
+
+            prop_assert_matrix_eq!(&view * &identity, DMatrix::from(&a));
+        }
+
+        #[test]
+        fn scalar_operators_agree_with_dense(a in csc_strategy()) {
+            let dense = DMatrix::from(&a);
+
+            prop_assert_matrix_eq!(&a * 3, &dense * 3);
+            prop_assert_matrix_eq!(3 * &a, &dense * 3);
+            prop_assert_matrix_eq!(&a / 2, dense.map(|x| x / 2));
+
+            let mut scaled = a.clone();
+            scaled *= 3;
+            prop_assert_matrix_eq!(scaled, &dense * 3);
+
+            let mut unscaled = a.clone();
+            unscaled /= 2;
+            prop_assert_matrix_eq!(unscaled, dense.map(|x| x / 2));
+        }
+
+        #[test]
+        fn negation_agrees_with_dense(
+            (a, b) in csr_strategy().prop_flat_map(|a| {
+                let (nrows, ncols) = a.shape();
+                (Just(a), csr(PROPTEST_I32_VALUE_STRATEGY, nrows, ncols, PROPTEST_MAX_NNZ))
+            })
+        ) {
+            let dense_a = DMatrix::from(&a);
+            let dense_b = DMatrix::from(&b);
+
+            prop_assert_matrix_eq!(-&a, -&dense_a);
+            prop_assert_matrix_eq!(-&a + b.to_view(), &dense_b - &dense_a);
+            prop_assert_matrix_eq!(-a.clone() + b.clone(), &dense_b - &dense_a);
+
+            let mut negated = a.clone();
+            negated.negate_mut();
+            prop_assert_matrix_eq!(negated, -&dense_a);
+        }
+    }
+}
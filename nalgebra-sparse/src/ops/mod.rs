@@ -6,10 +6,10 @@
 //! offer more control over allocation, and allow fusing some low-level operations for higher
 //! performance.
 //!
-//! The available operations are organized by backend. Currently, only the [`serial`] backend
-//! is available. In the future, backends that expose parallel operations may become available.
-//! All `std::ops` implementations will remain single-threaded and powered by the
-//! `serial` backend.
+//! The available operations are organized by backend. The [`serial`] backend is always available,
+//! and the `parallel` backend, which runs some of the same operations on the `rayon` thread pool,
+//! is available when the `rayon` feature is enabled. All `std::ops` implementations will remain
+//! single-threaded and powered by the `serial` backend.
 //!
 //! Many routines are able to implicitly transpose matrices involved in the operation.
 //! For example, the routine [`spadd_csr_prealloc`](serial::spadd_csr_prealloc) performs the
@@ -23,8 +23,11 @@
 //!
 //! The below table summarizes the currently supported binary operators between matrices.
 //! In general, binary operators between sparse matrices are only supported if both matrices
-//! are stored in the same format. All supported binary operators are implemented for
-//! all four combinations of values and references.
+//! are stored in the same format. All supported binary operators between sparse matrices are
+//! implemented both for values (including views obtained with
+//! [`to_view`](crate::cs::CsMatrix::to_view)) and for pairs of references, so `&a + &b` and
+//! `&a * &b` work without consuming or cloning either operand. Sparse-dense products are also
+//! implemented for a pair of references.
 //!
 //! <table>
 //!     <tr>
@@ -67,8 +70,12 @@
 //! As can be seen from the table, only `CSR * Dense` and `CSC * Dense` are supported.
 //! The other way around, i.e. `Dense * CSR` and `Dense * CSC` are not implemented.
 //!
-//! Additionally, [CsrMatrix](`crate::cs::CsrMatrix`) and [CooMatrix](`crate::coo::CooMatrix`)
-//! support multiplication with scalars, in addition to division by a scalar.
+//! Additionally, [CsrMatrix](`crate::cs::CsrMatrix`) and [CscMatrix](`crate::cs::CscMatrix`)
+//! support multiplication with scalars, in addition to division by a scalar, both by value and by
+//! reference. Scaling a reference (e.g. `&a * 2.0`) allocates new values but shares the sparsity
+//! pattern of `a`. To scale the values without allocating at all, use `a *= 2.0` and `a /= 2.0`,
+//! or equivalently [`scale_mut`](crate::cs::CsMatrix::scale_mut) and
+//! [`unscale_mut`](crate::cs::CsMatrix::unscale_mut).
 //! Note that only `Matrix * Scalar` works in a generic context, although `Scalar * Matrix`
 //! has been implemented for many of the built-in arithmetic types. This is due to a fundamental
 //! restriction of the Rust type system. Therefore, in generic code you will need to always place
@@ -78,12 +85,16 @@
 //!
 //! The following table lists currently supported unary operators.
 //!
-//! | Format   | AddAssign\<Matrix\> | MulAssign\<Matrix\> | MulAssign\<Scalar\> | Neg    |
-//! | -------- | -----------------   | -----------------   | ------------------- | ------ |
-//! | COO      |                     |                     |                     |        |
-//! | CSR      |                     |                     | x                   | x      |
-//! | CSC      |                     |                     | x                   | x      |
-//! |
+//! | Format   | AddAssign\<Matrix\> | MulAssign\<Matrix\> | MulAssign\<Scalar\> | DivAssign\<Scalar\> | Neg    |
+//! | -------- | -----------------   | -----------------   | ------------------- | ------------------- | ------ |
+//! | COO      |                     |                     |                     |                     |        |
+//! | CSR      | x                   |                     | x                   | x                   | x      |
+//! | CSC      | x                   |                     | x                   | x                   | x      |
+//!
+//! Negation is also implemented for references, so that expressions such as `-&a + b` can be
+//! written without consuming `a`. To negate the values of a matrix without allocating, use
+//! [`negate_mut`](crate::cs::CsMatrix::negate_mut).
+//!
 //! # Example usage
 //!
 //! For example, consider the case where you want to compute the expression
@@ -126,4 +137,105 @@
 //! always be verified by performance profiling!
 
 mod impl_std_ops;
+#[cfg(feature = "rayon")]
+pub mod parallel;
 pub mod serial;
+
+/// Determines whether a matrix should be transposed in a given operation.
+///
+/// See the [module-level documentation](crate::ops) for the purpose of this enum.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Op<T> {
+    /// Indicates that the matrix should be used as-is.
+    NoOp(T),
+
+    /// Indicates that the matrix should be transposed.
+    Transpose(T),
+}
+
+impl<T> Op<T> {
+    /// Returns a reference to the inner value that the operation applies to.
+    pub fn inner_ref(&self) -> &T {
+        self.as_ref().into_inner()
+    }
+
+    /// Returns an `Op` applied to a reference of the inner value.
+    pub fn as_ref(&self) -> Op<&T> {
+        match self {
+            Op::NoOp(obj) => Op::NoOp(obj),
+            Op::Transpose(obj) => Op::Transpose(obj),
+        }
+    }
+
+    /// Converts the underlying data type.
+    pub fn convert<U>(self) -> Op<U>
+    where
+        T: Into<U>,
+    {
+        self.map_same_op(T::into)
+    }
+
+    /// Transforms the inner value with the provided function, but preserves the operation.
+    pub fn map_same_op<U, F: FnOnce(T) -> U>(self, f: F) -> Op<U> {
+        match self {
+            Op::NoOp(obj) => Op::NoOp(f(obj)),
+            Op::Transpose(obj) => Op::Transpose(f(obj)),
+        }
+    }
+
+    /// Consumes the `Op` and returns the inner value.
+    pub fn into_inner(self) -> T {
+        match self {
+            Op::NoOp(obj) | Op::Transpose(obj) => obj,
+        }
+    }
+
+    /// Applies the transpose operation.
+    ///
+    /// This operation follows the usual semantics of transposition. In particular, double
+    /// transposition is equivalent to no transposition.
+    #[must_use]
+    pub fn transposed(self) -> Self {
+        match self {
+            Op::NoOp(obj) => Op::Transpose(obj),
+            Op::Transpose(obj) => Op::NoOp(obj),
+        }
+    }
+}
+
+/// How the products along a lane are summed up by the kernels that accept [`SpmmOptions`].
+///
+/// The default, sequential summation accumulates the products one after the other, in the order of
+/// the lane. Its rounding error grows linearly with the length of the lane, which can become
+/// noticeable for very long rows of floating-point values. The other strategies trade some speed
+/// for a smaller error. For integer types, all strategies produce the same result.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Summation {
+    /// Accumulates the products one after the other.
+    #[default]
+    Sequential,
+
+    /// Recursively sums up the two halves of the products of a lane. The rounding error grows
+    /// logarithmically with the length of the lane, at the cost of buffering the products.
+    Pairwise,
+
+    /// Accumulates the products with Kahan's compensated summation, whose rounding error does not
+    /// grow with the length of the lane, at the cost of a few more operations per product.
+    Kahan,
+}
+
+/// Options for the sparse-dense products that accept them, such as
+/// [`spmv_csr_with_options`](serial::spmm::spmv_csr_with_options).
+///
+/// The default options give the same results as the variants of the products without options.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SpmmOptions {
+    /// How the products along every lane are summed up.
+    pub summation: Summation,
+}
+
+impl<T> From<T> for Op<T> {
+    fn from(obj: T) -> Self {
+        Self::NoOp(obj)
+    }
+}
@@ -0,0 +1,8 @@
+//! Parallel sparse matrix arithmetic routines, powered by `rayon`.
+//!
+//! This module is only available when the `rayon` feature is enabled. The routines mirror their
+//! counterparts in the [`serial`](super::serial) backend, and run on the global `rayon` thread
+//! pool. For small matrices, the overhead of distributing the work usually outweighs the gains,
+//! so the serial routines should be preferred there.
+
+pub mod spmm;
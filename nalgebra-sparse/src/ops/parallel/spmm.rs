@@ -0,0 +1,706 @@
+//! Parallel sparse matrix multiplication.
+//!
+//! Products are parallelized along the major dimension of the sparse matrix, which is split into
+//! contiguous ranges of lanes that require roughly the same amount of work (e.g. hold roughly the
+//! same number of entries). This keeps the threads busy even if a few lanes are much denser than
+//! the others.
+//!
+//! Sparse-sparse products ([`spmm_csr_csr`]) use Gustavson's algorithm: every row of the product
+//! is accumulated from the rows of the right hand side that are selected by the corresponding row
+//! of the left hand side. Every thread keeps its own accumulator, computes the rows of its ranges
+//! into buffers of its own, and the buffers are concatenated into the final CSR matrix in order.
+//!
+//! Products whose output is indexed by the major dimension (e.g. `A x` for a CSR matrix `A`) write
+//! disjoint parts of the output from every thread, and need no synchronization. Products whose
+//! output is indexed by the minor dimension (e.g. `A x` for a CSC matrix `A`) would have every
+//! thread write to the whole output instead, so every thread accumulates into a buffer of its own,
+//! and the buffers are summed up at the end.
+
+use crate::{
+    control::{Control, Stage},
+    convert::utils::CountToOffsetIter,
+    cs::{CompressedColumnStorage, CompressedRowStorage, Compression, CsMatrix, CsrMatrix},
+    error::{OperationError, OperationErrorKind},
+    ops::Op,
+};
+use nalgebra::{Dim, IsContiguous, RawStorage, Scalar, StorageMut, Vector};
+use num_traits::Zero;
+use rayon::prelude::*;
+use std::{
+    borrow::Borrow,
+    ops::{AddAssign, Mul, Range},
+};
+
+/// Parallel sparse matrix product of two CSR matrices, computing `A * B` as a CSR matrix.
+///
+/// The rows of the product are computed in parallel with Gustavson's algorithm, in ranges of rows
+/// that require roughly the same number of scalar multiplications. Like the serial products, the
+/// pattern of the result holds every entry that is structurally non-zero, even if its value
+/// cancels out to zero.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices have incompatible shapes for a
+/// matrix product.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::DMatrix;
+/// use nalgebra_sparse::{cs::CsrMatrix, ops::parallel::spmm::spmm_csr_csr};
+///
+/// let a = DMatrix::from_fn(60, 40, |i, j| if (i + 2 * j) % 5 == 0 { 1.0 } else { 0.0 });
+/// let b = DMatrix::from_fn(40, 30, |i, j| if (3 * i + j) % 7 == 0 { 2.0 } else { 0.0 });
+///
+/// let product = spmm_csr_csr(CsrMatrix::from(&a), CsrMatrix::from(&b)).unwrap();
+///
+/// assert_eq!(DMatrix::from(&product), a * b);
+/// ```
+pub fn spmm_csr_csr<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Mul<T2> + Sync,
+    <T1 as Mul<T2>>::Output: Scalar + AddAssign + Send,
+    T2: Scalar + Sync,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    spmm_csr_csr_with_control(lhs, rhs, &Control::default())
+}
+
+/// Behaves like [`spmm_csr_csr`], but stops early if `control` requests it.
+///
+/// The callbacks of a [`Control`] are not shared with the worker threads. Instead, the ranges of
+/// rows are processed in a few waves of one range per thread, and `control` is checked, and
+/// progress is reported in rows of the output matrix, on the calling thread before every wave.
+///
+/// This is the parallel counterpart of
+/// [`serial::spmm::spmm_csr_csr_with_control`](crate::ops::serial::spmm::spmm_csr_csr_with_control).
+///
+/// # Errors
+///
+/// In addition to the errors produced by [`spmm_csr_csr`], this function fails and produces an
+/// [`OperationError`] with kind [`OperationErrorKind::Cancelled`] if `control` requested a stop
+/// before the product was complete.
+pub fn spmm_csr_csr_with_control<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+    control: &Control<'_>,
+) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Mul<T2> + Sync,
+    <T1 as Mul<T2>>::Output: Scalar + AddAssign + Send,
+    T2: Scalar + Sync,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    let (rows, lc) = lhs.shape();
+    let (rr, columns) = rhs.shape();
+
+    if lc != rr {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from(
+                "The two matrices have incompatible shapes (M × K1 and K2 × N where K1 ≠ K2)",
+            ),
+        ));
+    }
+
+    let span = span!(
+        "parallel_spmm_csr_csr",
+        nrows = rows,
+        ncols = columns,
+        lhs_nnz = lhs.nnz(),
+        rhs_nnz = rhs.nnz(),
+        nnz = tracing::field::Empty,
+    );
+
+    let (a_offsets, a_indices, a_data) = lhs.cs_data();
+    let (b_offsets, b_indices, b_data) = rhs.cs_data();
+    let a_lane =
+        |row: usize| a_offsets[row]..a_offsets.get(row + 1).copied().unwrap_or(a_data.len());
+    let b_lane =
+        |row: usize| b_offsets[row]..b_offsets.get(row + 1).copied().unwrap_or(b_data.len());
+
+    // The number of scalar multiplications before every row, to balance the ranges of rows
+    let mut work = Vec::with_capacity(rows);
+    let mut total = 0;
+
+    for row in 0..rows {
+        work.push(total);
+        total += a_lane(row)
+            .map(|k| b_lane(a_indices[k]).len())
+            .sum::<usize>();
+    }
+
+    let threads = rayon::current_num_threads();
+    let ranges = balanced_lanes(&work, total, 4 * threads);
+    let mut chunks = Vec::with_capacity(ranges.len());
+
+    for wave in ranges.chunks(threads) {
+        if control.checkpoint(Stage::MatrixProduct, wave[0].start, rows) {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::Cancelled,
+                String::from("The matrix product was cancelled before completion."),
+            ));
+        }
+
+        chunks.par_extend(wave.par_iter().cloned().map_init(
+            || Accumulator::new(columns),
+            |accumulator, range| {
+                let mut counts = Vec::with_capacity(range.len());
+                let mut indices = Vec::new();
+                let mut data = Vec::new();
+
+                for row in range {
+                    for k in a_lane(row) {
+                        for l in b_lane(a_indices[k]) {
+                            accumulator.add(b_indices[l], a_data[k].clone() * b_data[l].clone());
+                        }
+                    }
+
+                    counts.push(accumulator.drain_into(&mut indices, &mut data));
+                }
+
+                (counts, indices, data)
+            },
+        ));
+    }
+
+    control.report(Stage::MatrixProduct, rows, rows);
+
+    let nnz = chunks.iter().map(|(_, indices, _)| indices.len()).sum();
+    let mut counts = Vec::with_capacity(rows);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+
+    for (chunk_counts, chunk_indices, chunk_data) in chunks {
+        counts.extend(chunk_counts);
+        indices.extend(chunk_indices);
+        data.extend(chunk_data);
+    }
+
+    record!(span, nnz = nnz);
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+
+    Ok(unsafe { CsMatrix::from_parts_unchecked(rows, columns, offsets, indices, data) })
+}
+
+/// Parallel sparse matrix-vector multiplication into a pre-allocated vector, computing
+/// `y <- beta * y + alpha * op(A) * x` for a CSR matrix `A`.
+///
+/// This is the parallel counterpart of [`serial::spmm::spmv_csr`](crate::ops::serial::spmm::spmv_csr).
+/// Products with `A` are partitioned by rows and do not allocate beyond the partition itself,
+/// while products with `Aᵀ` are partitioned by columns of `Aᵀ` and allocate one accumulation
+/// buffer of the size of `y` per thread. `y` is not read if `beta` is zero.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `x` does not have as many entries as `op(A)` has
+/// columns, or `y` does not have as many entries as `op(A)` has rows. `y` is left untouched in
+/// that case.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{cs::CsrMatrix, ops::{parallel::spmm::spmv_csr, Op}};
+///
+/// let dense = DMatrix::from_fn(100, 80, |i, j| if (i + j) % 7 == 0 { 1.0 } else { 0.0 });
+/// let a = CsrMatrix::from(&dense);
+/// let x = DVector::from_fn(80, |i, _| i as f64);
+/// let mut y = DVector::from_element(100, 1.0);
+///
+/// spmv_csr(2.0, &mut y, 0.5, Op::NoOp(&a), &x).unwrap();
+///
+/// assert_eq!(y, DVector::from_element(100, 2.0) + &dense * &x * 0.5);
+/// ```
+pub fn spmv_csr<T, MO, MI, D, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedRowStorage>>,
+    x: &Vector<T, R2, S2>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T> + Send + Sync,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    S1: StorageMut<T, R1> + IsContiguous,
+    R2: Dim,
+    S2: RawStorage<T, R2> + Sync,
+{
+    match a {
+        Op::NoOp(a) => spmv_cs(beta, y, alpha, a.to_view(), x),
+        Op::Transpose(a) => spmv_cs(beta, y, alpha, a.transpose(), x),
+    }
+}
+
+/// Parallel sparse matrix-vector multiplication into a pre-allocated vector, computing
+/// `y <- beta * y + alpha * op(A) * x` for a CSC matrix `A`.
+///
+/// This is the parallel counterpart of [`serial::spmm::spmv_csc`](crate::ops::serial::spmm::spmv_csc).
+/// Products with `A` are partitioned by columns and allocate one accumulation buffer of the size
+/// of `y` per thread, while products with `Aᵀ` are partitioned by rows of `Aᵀ` and do not allocate
+/// beyond the partition itself. `y` is not read if `beta` is zero.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `x` does not have as many entries as `op(A)` has
+/// columns, or `y` does not have as many entries as `op(A)` has rows. `y` is left untouched in
+/// that case.
+pub fn spmv_csc<T, MO, MI, D, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedColumnStorage>>,
+    x: &Vector<T, R2, S2>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T> + Send + Sync,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    S1: StorageMut<T, R1> + IsContiguous,
+    R2: Dim,
+    S2: RawStorage<T, R2> + Sync,
+{
+    match a {
+        Op::NoOp(a) => spmv_cs(beta, y, alpha, a.to_view(), x),
+        Op::Transpose(a) => spmv_cs(beta, y, alpha, a.transpose(), x),
+    }
+}
+
+/// Computes `y <- beta * y + alpha * A * x` for a sparse matrix `A` of either compression.
+fn spmv_cs<T, CS, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: CsMatrix<T, &[usize], &[usize], &[T], CS>,
+    x: &Vector<T, R2, S2>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T> + Send + Sync,
+    CS: Compression,
+    R1: Dim,
+    S1: StorageMut<T, R1> + IsContiguous,
+    R2: Dim,
+    S2: RawStorage<T, R2> + Sync,
+{
+    let (rows, columns) = a.shape();
+
+    if x.nrows() != columns {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from(
+                "The two matrices have incompatible shapes (M × K1 and K2 × N where K1 ≠ K2)",
+            ),
+        ));
+    }
+
+    if y.nrows() != rows {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!(
+                "The output matrix has shape {:?}, but the product has shape {:?}.",
+                y.shape(),
+                (rows, 1)
+            ),
+        ));
+    }
+
+    let _span = span!("parallel_spmv", rows = rows, nnz = a.nnz());
+
+    let (offsets, indices, data) = a.cs_data();
+    let lane = |major: usize| offsets[major]..offsets.get(major + 1).copied().unwrap_or(data.len());
+    let threads = rayon::current_num_threads();
+    let y = y.as_mut_slice();
+
+    // Combines the product with the previous contents of the output, without reading them if
+    // beta is zero
+    let update = |y_i: &mut T, product: T| {
+        *y_i = if beta.is_zero() {
+            alpha.clone() * product
+        } else {
+            beta.clone() * y_i.clone() + alpha.clone() * product
+        };
+    };
+
+    // Whether the lanes of A are its rows
+    let row_major = CS::nmajor(1, 0) == 1;
+
+    if row_major {
+        // Every lane computes one entry of y, so every range of lanes owns its part of y. More
+        // ranges than threads let rayon even out the remaining imbalance.
+        let mut chunks = Vec::new();
+        let mut rest = y;
+
+        for lanes in balanced_lanes(offsets, data.len(), 4 * threads) {
+            let (chunk, tail) = rest.split_at_mut(lanes.len());
+            chunks.push((lanes, chunk));
+            rest = tail;
+        }
+
+        chunks.into_par_iter().for_each(|(lanes, chunk)| {
+            for (major, y_i) in lanes.zip(chunk) {
+                let mut product = T::zero();
+
+                for k in lane(major) {
+                    product += data[k].clone() * x[indices[k]].clone();
+                }
+
+                update(y_i, product);
+            }
+        });
+    } else {
+        // Every lane scatters into all of y, so every range of lanes gets its own buffer
+        let buffers = balanced_lanes(offsets, data.len(), threads)
+            .into_par_iter()
+            .map(|lanes| {
+                let mut buffer = vec![T::zero(); rows];
+
+                for major in lanes {
+                    for k in lane(major) {
+                        buffer[indices[k]] += data[k].clone() * x[major].clone();
+                    }
+                }
+
+                buffer
+            })
+            .collect::<Vec<_>>();
+
+        y.par_iter_mut().enumerate().for_each(|(i, y_i)| {
+            let mut product = T::zero();
+
+            for buffer in &buffers {
+                product += buffer[i].clone();
+            }
+
+            update(y_i, product);
+        });
+    }
+
+    Ok(())
+}
+
+/// A dense accumulator for one row of a sparse product, alongside the sparse list of the columns
+/// it currently occupies.
+///
+/// Clearing the accumulator only touches the occupied columns, so that every row costs time
+/// proportional to its number of entries rather than to the number of columns.
+struct Accumulator<T> {
+    values: Vec<Option<T>>,
+    occupied: Vec<usize>,
+}
+
+impl<T> Accumulator<T>
+where
+    T: AddAssign,
+{
+    fn new(columns: usize) -> Self {
+        Self {
+            values: std::iter::repeat_with(|| None).take(columns).collect(),
+            occupied: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, column: usize, value: T) {
+        match &mut self.values[column] {
+            Some(sum) => *sum += value,
+            slot => {
+                *slot = Some(value);
+                self.occupied.push(column);
+            }
+        }
+    }
+
+    /// Appends the accumulated row to `indices` and `data` in ascending column order, clears the
+    /// accumulator, and returns the number of entries of the row.
+    fn drain_into(&mut self, indices: &mut Vec<usize>, data: &mut Vec<T>) -> usize {
+        let count = self.occupied.len();
+        self.occupied.sort_unstable();
+
+        for column in self.occupied.drain(..) {
+            indices.push(column);
+            data.extend(self.values[column].take());
+        }
+
+        count
+    }
+}
+
+/// Splits the lanes that start at `offsets` (and hold `nnz` entries in total) into at most `parts`
+/// contiguous, non-empty ranges that require roughly the same amount of work, counting one unit
+/// per entry and one per lane.
+pub(crate) fn balanced_lanes(offsets: &[usize], nnz: usize, parts: usize) -> Vec<Range<usize>> {
+    let nmajor = offsets.len();
+    let work = |major: usize| offsets.get(major).copied().unwrap_or(nnz) + major;
+    let total = work(nmajor);
+    let parts = parts.clamp(1, nmajor.max(1));
+
+    let mut ranges = Vec::with_capacity(parts);
+    let mut start = 0;
+
+    for part in 1..=parts {
+        // The first lane at which the accumulated work reaches this part's share
+        let target = total * part / parts;
+        let (mut low, mut high) = (start, nmajor);
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+
+            if work(mid) < target {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        if low > start {
+            ranges.push(start..low);
+            start = low;
+        }
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        control::{CancellationToken, Progress},
+        cs::CscMatrix,
+        ops::serial,
+        proptest::*,
+    };
+    use nalgebra::{proptest::matrix, DMatrix, DVector};
+    use proptest::prelude::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn balanced_lanes_cover_all_lanes() {
+        // One dense lane among many empty ones
+        let offsets = [0, 0, 0, 100, 100, 100, 100];
+
+        for parts in 1..10 {
+            let ranges = balanced_lanes(&offsets, 101, parts);
+
+            assert!(ranges.len() <= parts);
+            assert_eq!(ranges.first().unwrap().start, 0);
+            assert_eq!(ranges.last().unwrap().end, 7);
+            assert!(ranges.windows(2).all(|w| w[0].end == w[1].start));
+            assert!(ranges.iter().all(|range| !range.is_empty()));
+        }
+
+        assert_eq!(balanced_lanes(&[0, 2, 4, 6], 8, 2), vec![0..2, 2..4]);
+        assert!(balanced_lanes(&[], 0, 4).is_empty());
+    }
+
+    #[test]
+    fn spmm_csr_csr_rejects_incompatible_shapes() {
+        let err =
+            spmm_csr_csr(CsrMatrix::<i32>::identity(3), CsrMatrix::<i32>::identity(2)).unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn spmm_csr_csr_keeps_cancelled_entries() {
+        let a = CsrMatrix::from(&DMatrix::from_row_slice(1, 2, &[1, 1]));
+        let b = CsrMatrix::from(&DMatrix::from_row_slice(2, 1, &[1, -1]));
+        let product = spmm_csr_csr(a, b).unwrap();
+
+        assert_eq!(product.nnz(), 1);
+        assert_eq!(DMatrix::from(&product), DMatrix::zeros(1, 1));
+    }
+
+    #[test]
+    fn spmm_csr_csr_with_control_reports_progress_and_can_be_cancelled() {
+        let a = CsrMatrix::from(&DMatrix::from_fn(200, 50, |i, j| ((i + j) % 3 == 0) as i32));
+        let b = CsrMatrix::from(&DMatrix::from_fn(50, 40, |i, j| ((i * j) % 5 == 1) as i32));
+
+        let reports = RefCell::new(Vec::new());
+        let progress = |progress: Progress| reports.borrow_mut().push(progress.completed);
+        let control = Control::new().with_progress(&progress);
+
+        let product = spmm_csr_csr_with_control(a.to_view(), b.to_view(), &control).unwrap();
+        let reports = reports.into_inner();
+
+        assert_eq!(
+            DMatrix::from(&product),
+            DMatrix::from(&a) * DMatrix::from(&b)
+        );
+        assert_eq!(reports.first(), Some(&0));
+        assert_eq!(reports.last(), Some(&200));
+        assert!(reports.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let control = Control::new().with_cancellation_token(&token);
+        let err = spmm_csr_csr_with_control(a, b, &control).unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::Cancelled));
+    }
+
+    #[test]
+    fn spmv_rejects_mismatched_shapes() {
+        let a = CsrMatrix::<f64>::identity(3);
+        let csc = CscMatrix::<f64>::identity(3);
+        let mut y = DVector::from_element(3, 1.0);
+
+        assert!(spmv_csr(0.0, &mut y, 1.0, Op::NoOp(&a), &DVector::zeros(2)).is_err());
+        assert!(spmv_csc(0.0, &mut y, 1.0, Op::NoOp(&csc), &DVector::zeros(4)).is_err());
+        assert!(spmv_csr(
+            0.0,
+            &mut DVector::zeros(2),
+            1.0,
+            Op::Transpose(&a),
+            &y.clone()
+        )
+        .is_err());
+        assert_eq!(y, DVector::from_element(3, 1.0));
+    }
+
+    #[test]
+    fn spmv_ignores_nan_output_if_beta_is_zero() {
+        let a = CsrMatrix::<f64>::identity(50);
+        let csc = CscMatrix::<f64>::identity(50);
+        let x = DVector::from_fn(50, |i, _| i as f64);
+
+        let mut y = DVector::from_element(50, f64::NAN);
+        spmv_csr(0.0, &mut y, 2.0, Op::NoOp(&a), &x).unwrap();
+        assert_eq!(y, &x * 2.0);
+
+        let mut y = DVector::from_element(50, f64::NAN);
+        spmv_csc(0.0, &mut y, 2.0, Op::NoOp(&csc), &x).unwrap();
+        assert_eq!(y, &x * 2.0);
+    }
+
+    #[test]
+    fn spmv_handles_empty_matrices() {
+        let a = CsrMatrix::<i32>::zeros(0, 0);
+        let csc = CscMatrix::<i32>::zeros(0, 0);
+        let x = DVector::zeros(0);
+        let mut y = DVector::zeros(0);
+
+        spmv_csr(0, &mut y, 1, Op::NoOp(&a), &x).unwrap();
+        spmv_csr(0, &mut y, 1, Op::Transpose(&a), &x).unwrap();
+        spmv_csc(0, &mut y, 1, Op::NoOp(&csc), &x).unwrap();
+        spmv_csc(0, &mut y, 1, Op::Transpose(&csc), &x).unwrap();
+        assert!(y.is_empty());
+    }
+
+    #[test]
+    fn spmv_of_square_matrices_agrees_with_dense() {
+        // The shape of a square matrix does not tell whether its lanes are rows or columns.
+        let dense =
+            DMatrix::from_row_slice(4, 4, &[1, 2, 0, 0, 0, 0, 3, 0, 0, 0, 0, 4, 5, 0, 0, 0]);
+        let a = CsrMatrix::from(&dense);
+        let csc = CscMatrix::from(&dense);
+        let x = DVector::from_column_slice(&[1, 2, 3, 4]);
+
+        let expected = &dense * &x;
+        let expected_t = dense.transpose() * &x;
+
+        let mut y = DVector::zeros(4);
+        spmv_csr(0, &mut y, 1, Op::NoOp(&a), &x).unwrap();
+        assert_eq!(y, expected);
+
+        let mut y = DVector::zeros(4);
+        spmv_csc(0, &mut y, 1, Op::NoOp(&csc), &x).unwrap();
+        assert_eq!(y, expected);
+
+        let mut y = DVector::zeros(4);
+        spmv_csr(0, &mut y, 1, Op::Transpose(&a), &x).unwrap();
+        assert_eq!(y, expected_t);
+
+        let mut y = DVector::zeros(4);
+        spmv_csc(0, &mut y, 1, Op::Transpose(&csc), &x).unwrap();
+        assert_eq!(y, expected_t);
+    }
+
+    proptest! {
+        #[test]
+        fn spmm_csr_csr_agrees_with_dense(
+            (a, b) in (PROPTEST_MATRIX_DIM, PROPTEST_MATRIX_DIM, PROPTEST_MATRIX_DIM)
+                .prop_flat_map(|(m, k, n)| {
+                    (
+                        csr(PROPTEST_I32_VALUE_STRATEGY, m, k, PROPTEST_MAX_NNZ),
+                        csr(PROPTEST_I32_VALUE_STRATEGY, k, n, PROPTEST_MAX_NNZ),
+                    )
+                })
+        ) {
+            let expected = DMatrix::from(&a) * DMatrix::from(&b);
+            let serial = CsrMatrix::from(serial::spmm::spmm_csr_csr(a.to_view(), b.to_view()).unwrap());
+            let product = spmm_csr_csr(a, b).unwrap();
+
+            prop_assert_eq!(DMatrix::from(&product), expected);
+            prop_assert_eq!(product.pattern(), serial.pattern());
+
+            // The pattern is sorted and free of duplicates
+            let (nrows, ncols) = product.shape();
+            let (offsets, indices, data) = product.disassemble();
+            prop_assert!(CsrMatrix::try_from_parts(nrows, ncols, offsets, indices, data).is_ok());
+        }
+
+        #[test]
+        fn spmv_agrees_with_dense(
+            (a, x, x_t, y, y_t) in csr_strategy().prop_flat_map(|a| {
+                let (m, n) = a.shape();
+                (
+                    Just(a),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, n, 1),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, m, 1),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, m, 1),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, n, 1),
+                )
+            }),
+            alpha in -3i32..=3,
+            beta in -3i32..=3,
+        ) {
+            let dense = DMatrix::from(&a);
+            let csc = CscMatrix::from(a.clone());
+            let x = x.column(0).into_owned();
+            let x_t = x_t.column(0).into_owned();
+            let y = y.column(0).into_owned();
+            let y_t = y_t.column(0).into_owned();
+
+            let expected = &y * beta + &dense * &x * alpha;
+            let expected_t = &y_t * beta + dense.transpose() * &x_t * alpha;
+
+            let mut result = y.clone();
+            spmv_csr(beta, &mut result, alpha, Op::NoOp(&a), &x).unwrap();
+            prop_assert_eq!(&result, &expected);
+
+            let mut result = y.clone();
+            spmv_csc(beta, &mut result, alpha, Op::NoOp(&csc), &x).unwrap();
+            prop_assert_eq!(&result, &expected);
+
+            let mut result = y_t.clone();
+            spmv_csr(beta, &mut result, alpha, Op::Transpose(&a), &x_t).unwrap();
+            prop_assert_eq!(&result, &expected_t);
+
+            let mut result = y_t.clone();
+            spmv_csc(beta, &mut result, alpha, Op::Transpose(&csc), &x_t).unwrap();
+            prop_assert_eq!(&result, &expected_t);
+        }
+    }
+}
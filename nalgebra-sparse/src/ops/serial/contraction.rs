@@ -0,0 +1,281 @@
+//! Module holding scalar contractions of sparse matrices.
+//!
+//! A contraction reduces one or two matrices to a single scalar, e.g. the trace of a matrix or
+//! the trace of a product. These show up frequently in objective and gradient computations (e.g.
+//! `tr(A B)` or `<A, B>_F`), where forming the full product first would be wasteful: only a
+//! fraction of the entries of the product contribute to the result.
+//!
+//! Every contraction here only ever visits the explicit entries of its operands, and the
+//! contractions of two matrices only multiply entries whose positions coincide.
+
+use crate::{
+    cs::{Compression, CsMatrix},
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::Scalar;
+use num_traits::Zero;
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    ops::{AddAssign, Mul},
+};
+
+/// Computes the trace of a square sparse matrix, i.e. the sum of the entries on its diagonal.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if the matrix is
+/// not square.
+pub fn sp_cs_trace<T, MO, MI, D, C>(cs: &CsMatrix<T, MO, MI, D, C>) -> Result<T, OperationError>
+where
+    T: Scalar + AddAssign + Zero,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let (nrows, ncols) = cs.shape();
+
+    if nrows != ncols {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!(
+                "Cannot compute the trace of a non-square matrix of shape ({}, {}).",
+                nrows, ncols
+            ),
+        ));
+    }
+
+    let mut trace = T::zero();
+
+    for (k, lane) in cs.iter().enumerate() {
+        for (i, value) in lane {
+            match i.cmp(&k) {
+                Ordering::Less => continue,
+                Ordering::Equal => trace += value.clone(),
+                Ordering::Greater => break,
+            }
+        }
+    }
+
+    Ok(trace)
+}
+
+/// Computes `tr(A B) = Σᵢ A[i, :] · B[:, i]` without forming the product `A B`.
+///
+/// `B` is required to have the transposed compression of `A` (e.g. a CSC matrix if `A` is a CSR
+/// matrix), so that the rows of `A` and columns of `B` (or vice versa) can be walked side by side.
+/// Convert `B` first if it has the same compression as `A`.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if the shape of
+/// `B` is not the transposed shape of `A`.
+pub fn sp_cs_diag_product<T1, T2, MO1, MO2, MI1, MI2, D1, D2, C>(
+    a: &CsMatrix<T1, MO1, MI1, D1, C>,
+    b: &CsMatrix<T2, MO2, MI2, D2, C::Transpose>,
+) -> Result<<T1 as Mul<T2>>::Output, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    <T1 as Mul<T2>>::Output: Scalar + AddAssign + Zero,
+    T2: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+    C: Compression,
+{
+    let (a_rows, a_cols) = a.shape();
+    let (b_rows, b_cols) = b.shape();
+
+    if a_rows != b_cols || a_cols != b_rows {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!(
+                "Cannot compute the trace of the product of a matrix of shape ({}, {}) and a \
+                 matrix of shape ({}, {}).",
+                a_rows, a_cols, b_rows, b_cols
+            ),
+        ));
+    }
+
+    // Lane `i` of `A` and lane `i` of `B` are row `i` of `A` and column `i` of `B` (or the other
+    // way around), both indexed by the same inner dimension.
+    Ok(contract_lanes(a, b))
+}
+
+/// Computes the Frobenius inner product `<A, B>_F = Σᵢⱼ A[i, j] B[i, j]`.
+///
+/// Only positions where both `A` and `B` have an explicit entry contribute to the sum, so this
+/// runs in time proportional to the number of explicit entries of both matrices.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` and `B` do
+/// not have the same shape.
+pub fn sp_cs_frobenius_inner_product<T1, T2, MO1, MO2, MI1, MI2, D1, D2, C>(
+    a: &CsMatrix<T1, MO1, MI1, D1, C>,
+    b: &CsMatrix<T2, MO2, MI2, D2, C>,
+) -> Result<<T1 as Mul<T2>>::Output, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    <T1 as Mul<T2>>::Output: Scalar + AddAssign + Zero,
+    T2: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+    C: Compression,
+{
+    if a.shape() != b.shape() {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!(
+                "Cannot compute the inner product of matrices of shape {:?} and {:?}.",
+                a.shape(),
+                b.shape()
+            ),
+        ));
+    }
+
+    Ok(contract_lanes(a, b))
+}
+
+/// Sums the products of all entries that share a major and a minor index in the compressed
+/// representations of `a` and `b`.
+///
+/// Both matrices must have the same number of major lanes.
+fn contract_lanes<T1, T2, MO1, MO2, MI1, MI2, D1, D2, C1, C2>(
+    a: &CsMatrix<T1, MO1, MI1, D1, C1>,
+    b: &CsMatrix<T2, MO2, MI2, D2, C2>,
+) -> <T1 as Mul<T2>>::Output
+where
+    T1: Scalar + Mul<T2>,
+    <T1 as Mul<T2>>::Output: Scalar + AddAssign + Zero,
+    T2: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+    C1: Compression,
+    C2: Compression,
+{
+    let mut sum = <T1 as Mul<T2>>::Output::zero();
+
+    for (a_lane, b_lane) in a.iter().zip(b.iter()) {
+        let mut a_lane = a_lane.peekable();
+        let mut b_lane = b_lane.peekable();
+
+        while let (Some(&(i, a_val)), Some(&(j, b_val))) = (a_lane.peek(), b_lane.peek()) {
+            match i.cmp(&j) {
+                Ordering::Less => {
+                    a_lane.next();
+                }
+
+                Ordering::Greater => {
+                    b_lane.next();
+                }
+
+                Ordering::Equal => {
+                    sum += a_val.clone() * b_val.clone();
+                    a_lane.next();
+                    b_lane.next();
+                }
+            }
+        }
+    }
+
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cs::{CscMatrix, CsrMatrix},
+        proptest::*,
+    };
+    use nalgebra::DMatrix;
+    use proptest::prelude::*;
+
+    /// Square matrices.
+    fn square() -> impl Strategy<Value = CsrMatrix<i32>> {
+        PROPTEST_MATRIX_DIM
+            .prop_flat_map(|n| csr(PROPTEST_I32_VALUE_STRATEGY, n..=n, n..=n, PROPTEST_MAX_NNZ))
+    }
+
+    /// Pairs of matrices where the second has the shape of the first, or its transposed shape.
+    fn pair(transposed: bool) -> impl Strategy<Value = (CsrMatrix<i32>, CsrMatrix<i32>)> {
+        csr_strategy().prop_flat_map(move |a| {
+            let (nrows, ncols) = a.shape();
+            let (nrows, ncols) = if transposed {
+                (ncols, nrows)
+            } else {
+                (nrows, ncols)
+            };
+            let b = csr(
+                PROPTEST_I32_VALUE_STRATEGY,
+                nrows..=nrows,
+                ncols..=ncols,
+                PROPTEST_MAX_NNZ,
+            );
+
+            (Just(a), b)
+        })
+    }
+
+    #[test]
+    fn trace_rejects_non_square_matrices() {
+        let err = CsrMatrix::<f64>::zeros(2, 3).trace().unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn contractions_reject_mismatched_shapes() {
+        let a = CsrMatrix::<f64>::zeros(2, 3);
+
+        let err = a.diag_product(&CscMatrix::zeros(2, 3)).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+
+        let err = a
+            .frobenius_inner_product(&CsrMatrix::zeros(3, 2))
+            .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    proptest! {
+        #[test]
+        fn trace_agrees_with_dense(csr in square()) {
+            let expected = DMatrix::from(&csr).trace();
+
+            prop_assert_eq!(sp_cs_trace(&csr).unwrap(), expected);
+            prop_assert_eq!(sp_cs_trace(&CscMatrix::from(csr)).unwrap(), expected);
+        }
+
+        #[test]
+        fn diag_product_agrees_with_trace_of_dense_product((a, b) in pair(true)) {
+            let expected = (DMatrix::from(&a) * DMatrix::from(&b)).trace();
+
+            prop_assert_eq!(sp_cs_diag_product(&a, &CscMatrix::from(b.clone())).unwrap(), expected);
+            prop_assert_eq!(sp_cs_diag_product(&CscMatrix::from(a), &b).unwrap(), expected);
+        }
+
+        #[test]
+        fn frobenius_inner_product_agrees_with_dense((a, b) in pair(false)) {
+            let expected = DMatrix::from(&a).dot(&DMatrix::from(&b));
+
+            prop_assert_eq!(sp_cs_frobenius_inner_product(&a, &b).unwrap(), expected);
+            prop_assert_eq!(
+                sp_cs_frobenius_inner_product(&CscMatrix::from(a), &CscMatrix::from(b)).unwrap(),
+                expected
+            );
+        }
+    }
+}
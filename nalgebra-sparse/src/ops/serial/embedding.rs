@@ -0,0 +1,217 @@
+//! Module holding sparse-dense kernels for embedding lookups.
+//!
+//! An embedding lookup gathers rows out of a dense embedding table `E` (one row per item), and
+//! combines them according to a sparse selection or weight matrix `S`:
+//!
+//! ```text
+//! E_out := S * E
+//! ```
+//!
+//! Each row of `S` typically only has a handful of non-zero entries (e.g. the items a user
+//! interacted with, along with some weights), while `E` can have many rows. Unlike
+//! [`spmm_csr_dense`](super::spmm::spmm_csr_dense), the kernels here therefore produce a dense
+//! output and only ever touch the rows of `E` that are actually selected.
+//!
+//! The embedding table is typically reused across many lookups, so it is taken by reference.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra::DMatrix;
+//! use nalgebra_sparse::{cs::CsrMatrix, ops::serial::embedding::embedding_gather_csr};
+//!
+//! // Three items with two-dimensional embeddings.
+//! let table = DMatrix::from_row_slice(3, 2, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+//!
+//! // The first output is the mean of items 0 and 2, the second output is item 1.
+//! let selection =
+//!     CsrMatrix::try_from_parts(2, 3, vec![0, 2], vec![0, 2, 1], vec![0.5, 0.5, 1.0]).unwrap();
+//!
+//! let output = embedding_gather_csr(&selection, &table).unwrap();
+//!
+//! assert_eq!(output, DMatrix::from_row_slice(2, 2, &[3.0, 4.0, 3.0, 4.0]));
+//! ```
+
+use crate::{
+    cs::{CompressedRowStorage, CsMatrix},
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::{DMatrix, Dim, Matrix, RawStorage, Scalar};
+use num_traits::Zero;
+use std::{
+    borrow::Borrow,
+    ops::{AddAssign, Mul},
+};
+
+/// Computes the embedding lookup `S * E` for a CSR selection matrix `S` and a dense embedding
+/// table `E`.
+///
+/// Row `i` of the output is the weighted sum of the rows of `table` that are selected by the
+/// explicit entries in row `i` of `csr`. Rows of `csr` without any explicit entries produce a row
+/// of zeros.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the number of columns in `csr` does not match the
+/// number of rows in `table`.
+pub fn embedding_gather_csr<T, MO, MI, D, R, C, S>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    table: &Matrix<T, R, C, S>,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C>,
+{
+    let (nrows, nitems) = csr.shape();
+    let (table_rows, dimension) = table.shape();
+
+    if nitems != table_rows {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from(
+                "The selection matrix must have as many columns as the embedding table has rows",
+            ),
+        ));
+    }
+
+    let _span = span!(
+        "embedding_gather_csr",
+        nrows = nrows,
+        dimension = dimension,
+        nnz = csr.nnz()
+    );
+
+    let mut output = DMatrix::zeros(nrows, dimension);
+
+    for (i, lane) in csr.iter().enumerate() {
+        for (item, weight) in lane {
+            for k in 0..dimension {
+                output[(i, k)] += weight.clone() * table[(item, k)].clone();
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Computes the transposed embedding product `Sᵀ * G` for a CSR selection matrix `S` and a dense
+/// matrix `G` with one row per row of `S`.
+///
+/// This is the gradient of the loss with respect to the embedding table in
+/// [`embedding_gather_csr`], given the gradient `G` with respect to its output. Every explicit
+/// entry `(i, item)` of `csr` adds the weighted row `i` of `gradient` into row `item` of the
+/// output, so rows of the embedding table that are never selected receive a gradient of zero.
+///
+/// The output has as many rows as `csr` has columns. Transposing `csr` is never necessary.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the number of rows in `csr` does not match the
+/// number of rows in `gradient`.
+pub fn embedding_scatter_csr<T, MO, MI, D, R, C, S>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    gradient: &Matrix<T, R, C, S>,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C>,
+{
+    let (nrows, nitems) = csr.shape();
+    let (gradient_rows, dimension) = gradient.shape();
+
+    if nrows != gradient_rows {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from(
+                "The selection matrix must have as many rows as the output gradient has rows",
+            ),
+        ));
+    }
+
+    let _span = span!(
+        "embedding_scatter_csr",
+        nitems = nitems,
+        dimension = dimension,
+        nnz = csr.nnz()
+    );
+
+    let mut output = DMatrix::zeros(nitems, dimension);
+
+    for (i, lane) in csr.iter().enumerate() {
+        for (item, weight) in lane {
+            for k in 0..dimension {
+                output[(item, k)] += weight.clone() * gradient[(i, k)].clone();
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cs::CsrMatrix, proptest::*};
+    use proptest::prelude::*;
+
+    fn table(nrows: usize) -> DMatrix<i32> {
+        DMatrix::from_fn(nrows, 3, |i, j| (2 * i + j) as i32 - 3)
+    }
+
+    #[test]
+    fn embedding_gather_rejects_mismatched_table() {
+        let csr = CsrMatrix::<i32>::identity(3);
+        let err = embedding_gather_csr(&csr, &table(4)).unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn embedding_scatter_rejects_mismatched_gradient() {
+        let csr = CsrMatrix::<i32>::identity(3);
+        let err = embedding_scatter_csr(&csr, &table(2)).unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn embedding_scatter_accumulates_repeated_items() {
+        // Both rows select item 1, so their gradients have to be summed.
+        let csr = CsrMatrix::try_from_parts(2, 3, vec![0, 1], vec![1, 1], vec![2, 3]).unwrap();
+        let gradient = DMatrix::from_row_slice(2, 2, &[1, 1, 10, 20]);
+
+        let output = embedding_scatter_csr(&csr, &gradient).unwrap();
+
+        assert_eq!(output, DMatrix::from_row_slice(3, 2, &[0, 0, 32, 62, 0, 0]));
+    }
+
+    proptest! {
+        #[test]
+        fn embedding_gather_agrees_with_dense_product(csr in csr_strategy()) {
+            let table = table(csr.ncols());
+            let expected = DMatrix::from(&csr) * &table;
+
+            prop_assert_eq!(embedding_gather_csr(&csr, &table).unwrap(), expected);
+        }
+
+        #[test]
+        fn embedding_scatter_agrees_with_dense_transpose_product(csr in csr_strategy()) {
+            let gradient = table(csr.nrows());
+            let expected = DMatrix::from(&csr).transpose() * &gradient;
+
+            prop_assert_eq!(embedding_scatter_csr(&csr, &gradient).unwrap(), expected);
+        }
+    }
+}
@@ -0,0 +1,427 @@
+//! Module holding building blocks for computing gradients with respect to the values of sparse
+//! matrices.
+//!
+//! Frameworks that differentiate through sparse operations typically keep the sparsity pattern of
+//! a sparse matrix fixed, and only learn its values. The gradient of a loss with respect to those
+//! values is then itself a sparse matrix with the exact same pattern: entries outside of the
+//! pattern are not parameters, and so have no gradient. Such a "same-pattern" gradient matrix can
+//! be obtained with [`CsMatrix::zeros_like`], and the kernels in this module produce their value
+//! gradients in that layout, so that the gradient data can be used directly alongside the data of
+//! the original matrix.
+//!
+//! For the product `Y := S * X` of a sparse matrix `S` and a dense matrix `X`, with the gradient
+//! `G` of the loss with respect to `Y`, the gradients are:
+//!
+//! ```text
+//! dS := (G * Xᵀ) masked by the pattern of S
+//! dX := Sᵀ * G
+//! ```
+//!
+//! The masked product in the first line is often called a sampled dense-dense matrix product
+//! (SDDMM), and is provided separately as [`sddmm_csr`].
+
+use crate::{
+    cs::{CompressedRowStorage, CsMatrix, CsrMatrix},
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::{DMatrix, Dim, Matrix, RawStorage, Scalar};
+use num_traits::Zero;
+use std::{
+    borrow::Borrow,
+    ops::{AddAssign, Mul},
+};
+
+/// The gradients of a sparse-dense product `Y := S * X` with respect to both of its inputs.
+#[derive(Debug, Clone)]
+pub struct SpmmGradients<T>
+where
+    T: Scalar,
+{
+    /// The gradient with respect to the values of `S`, which has the same pattern as `S`.
+    pub values: CsrMatrix<T>,
+
+    /// The gradient with respect to the dense input `X`.
+    pub input: DMatrix<T>,
+}
+
+/// Computes the sampled dense-dense matrix product `(A * Bᵀ)` for every explicit entry in the
+/// pattern of a CSR matrix.
+///
+/// The output has the exact same pattern as `pattern`, where entry `(i, j)` is the dot product of
+/// row `i` of `a` and row `j` of `b`. The values of `pattern` are ignored, which means that any
+/// matrix with the right pattern can be used, regardless of its value type.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `a` does not have as many rows as `pattern`, `b` does
+/// not have as many rows as `pattern` has columns, or `a` and `b` have a different number of
+/// columns.
+pub fn sddmm_csr<T, P, MO, MI, D, R1, C1, S1, R2, C2, S2>(
+    pattern: &CsMatrix<P, MO, MI, D, CompressedRowStorage>,
+    a: &Matrix<T, R1, C1, S1>,
+    b: &Matrix<T, R2, C2, S2>,
+) -> Result<CsrMatrix<T>, OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    P: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[P]>,
+    R1: Dim,
+    C1: Dim,
+    S1: RawStorage<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    let (nrows, ncols) = pattern.shape();
+
+    if a.nrows() != nrows || b.nrows() != ncols || a.ncols() != b.ncols() {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from(
+                "The dense matrices must have shapes M × K and N × K for a pattern of shape M × N",
+            ),
+        ));
+    }
+
+    let _span = span!("sddmm_csr", nrows = nrows, nnz = pattern.nnz());
+
+    let (offsets, indices, _) = pattern.cs_data();
+
+    let data = pattern
+        .iter()
+        .enumerate()
+        .flat_map(|(i, lane)| {
+            lane.map(move |(j, _)| {
+                let mut total = T::zero();
+
+                for k in 0..a.ncols() {
+                    total += a[(i, k)].clone() * b[(j, k)].clone();
+                }
+
+                total
+            })
+        })
+        .collect();
+
+    Ok(unsafe {
+        CsrMatrix::from_parts_unchecked(nrows, ncols, offsets.to_vec(), indices.to_vec(), data)
+    })
+}
+
+/// Computes the gradients of the sparse-dense product `Y := S * X` with respect to the values of
+/// `S` and with respect to `X`, given the gradient of the loss with respect to `Y`.
+///
+/// See the [module-level documentation](self) for the definition of both gradients.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the shapes of `csr`, `input` and `output_gradient`
+/// are not those of `S`, `X` and `Y` in a valid product.
+pub fn spmm_csr_dense_backward<T, MO, MI, D, R1, C1, S1, R2, C2, S2>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    input: &Matrix<T, R1, C1, S1>,
+    output_gradient: &Matrix<T, R2, C2, S2>,
+) -> Result<SpmmGradients<T>, OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    C1: Dim,
+    S1: RawStorage<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    check_shapes(csr, input, Some(output_gradient))?;
+
+    let _span = span!(
+        "spmm_csr_dense_backward",
+        nrows = csr.nrows(),
+        nnz = csr.nnz()
+    );
+
+    let (_, gradients) = fused_pass(csr, input, Some(output_gradient), false);
+
+    Ok(gradients.unwrap())
+}
+
+/// Computes the sparse-dense product `Y := S * X` together with the gradients with respect to the
+/// values of `S` and with respect to `X`, in a single pass over the pattern of `S`.
+///
+/// This returns the same results as computing the product and calling
+/// [`spmm_csr_dense_backward`], but only reads every row of `X` once per explicit entry of `S`.
+/// This is useful when the gradient of the output is known upfront, e.g. when recomputing the
+/// forward pass during gradient checkpointing.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the shapes of `csr`, `input` and `output_gradient`
+/// are not those of `S`, `X` and `Y` in a valid product.
+pub fn spmm_csr_dense_forward_backward<T, MO, MI, D, R1, C1, S1, R2, C2, S2>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    input: &Matrix<T, R1, C1, S1>,
+    output_gradient: &Matrix<T, R2, C2, S2>,
+) -> Result<(DMatrix<T>, SpmmGradients<T>), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    C1: Dim,
+    S1: RawStorage<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    check_shapes(csr, input, Some(output_gradient))?;
+
+    let _span = span!(
+        "spmm_csr_dense_forward_backward",
+        nrows = csr.nrows(),
+        nnz = csr.nnz()
+    );
+
+    let (output, gradients) = fused_pass(csr, input, Some(output_gradient), true);
+
+    Ok((output.unwrap(), gradients.unwrap()))
+}
+
+/// Computes the sparse-dense product `Y := S * X` as a dense matrix.
+///
+/// Unlike [`spmm_csr_dense`](super::spmm::spmm_csr_dense), this produces a dense output, which is
+/// what the gradient kernels in this module expect.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices have incompatible shapes for a
+/// matrix product.
+pub fn spmm_csr_dense_forward<T, MO, MI, D, R, C, S>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    input: &Matrix<T, R, C, S>,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C>,
+{
+    check_shapes::<_, _, _, _, _, _, _, R, C, S>(csr, input, None)?;
+
+    let _span = span!(
+        "spmm_csr_dense_forward",
+        nrows = csr.nrows(),
+        nnz = csr.nnz()
+    );
+
+    let (output, _) = fused_pass::<_, _, _, _, _, _, _, R, C, S>(csr, input, None, true);
+
+    Ok(output.unwrap())
+}
+
+/// Checks that `csr`, `input` and (optionally) `output_gradient` have the shapes of `S`, `X` and
+/// `Y` in the product `Y := S * X`.
+fn check_shapes<T, MO, MI, D, R1, C1, S1, R2, C2, S2>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    input: &Matrix<T, R1, C1, S1>,
+    output_gradient: Option<&Matrix<T, R2, C2, S2>>,
+) -> Result<(), OperationError>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    C1: Dim,
+    S1: RawStorage<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    let (nrows, ncols) = csr.shape();
+
+    if input.nrows() != ncols {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from(
+                "The two matrices have incompatible shapes (M × K1 and K2 × N where K1 ≠ K2)",
+            ),
+        ));
+    }
+
+    if let Some(gradient) = output_gradient {
+        if gradient.shape() != (nrows, input.ncols()) {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from("The output gradient must have the same shape as the product"),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Makes a single pass over the pattern of `csr`, computing the product `S * X` if `forward` is
+/// set and the gradients if `output_gradient` is provided.
+///
+/// The shapes must already have been checked with [`check_shapes`].
+fn fused_pass<T, MO, MI, D, R1, C1, S1, R2, C2, S2>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    input: &Matrix<T, R1, C1, S1>,
+    output_gradient: Option<&Matrix<T, R2, C2, S2>>,
+    forward: bool,
+) -> (Option<DMatrix<T>>, Option<SpmmGradients<T>>)
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    C1: Dim,
+    S1: RawStorage<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    let (nrows, ncols) = csr.shape();
+    let dimension = input.ncols();
+
+    let mut output = forward.then(|| DMatrix::zeros(nrows, dimension));
+    let mut values = output_gradient.map(|_| Vec::with_capacity(csr.nnz()));
+    let mut input_gradient = output_gradient.map(|_| DMatrix::zeros(ncols, dimension));
+
+    for (i, lane) in csr.iter().enumerate() {
+        for (j, s) in lane {
+            let mut value_gradient = T::zero();
+
+            for k in 0..dimension {
+                let x = input[(j, k)].clone();
+
+                if let Some(output) = &mut output {
+                    output[(i, k)] += s.clone() * x.clone();
+                }
+
+                if let (Some(gradient), Some(input_gradient)) =
+                    (output_gradient, &mut input_gradient)
+                {
+                    let g = gradient[(i, k)].clone();
+
+                    value_gradient += g.clone() * x;
+                    input_gradient[(j, k)] += s.clone() * g;
+                }
+            }
+
+            if let Some(values) = &mut values {
+                values.push(value_gradient);
+            }
+        }
+    }
+
+    let gradients = values.zip(input_gradient).map(|(values, input)| {
+        let (offsets, indices, _) = csr.cs_data();
+
+        let values = unsafe {
+            CsrMatrix::from_parts_unchecked(
+                nrows,
+                ncols,
+                offsets.to_vec(),
+                indices.to_vec(),
+                values,
+            )
+        };
+
+        SpmmGradients { values, input }
+    });
+
+    (output, gradients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest::*;
+    use proptest::prelude::*;
+
+    fn dense(nrows: usize, ncols: usize, shift: i32) -> DMatrix<i32> {
+        DMatrix::from_fn(nrows, ncols, |i, j| (3 * i + j) as i32 % 5 - shift)
+    }
+
+    #[test]
+    fn zeros_like_keeps_pattern() {
+        let csr =
+            CsrMatrix::try_from_parts(2, 3, vec![0, 2], vec![0, 2, 1], vec![1, 2, 3]).unwrap();
+        let zeros = csr.zeros_like();
+
+        assert_eq!(
+            zeros.cs_data(),
+            (csr.cs_data().0, csr.cs_data().1, &[0, 0, 0][..])
+        );
+    }
+
+    #[test]
+    fn backward_rejects_mismatched_gradient() {
+        let csr = CsrMatrix::<i32>::identity(3);
+        let err = spmm_csr_dense_backward(&csr, &dense(3, 2, 0), &dense(3, 3, 0)).unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn sddmm_rejects_mismatched_inner_dimension() {
+        let csr = CsrMatrix::<i32>::identity(3);
+        let err = sddmm_csr(&csr, &dense(3, 2, 0), &dense(3, 3, 0)).unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    proptest! {
+        #[test]
+        fn sddmm_agrees_with_masked_dense_product(csr in csr_strategy()) {
+            let a = dense(csr.nrows(), 3, 2);
+            let b = dense(csr.ncols(), 3, 1);
+            let product = &a * b.transpose();
+
+            let sampled = sddmm_csr(&csr, &a, &b).unwrap();
+
+            prop_assert_eq!(sampled.cs_data().0, csr.cs_data().0);
+            prop_assert_eq!(sampled.cs_data().1, csr.cs_data().1);
+
+            for (i, j, v) in sampled.triplet_iter() {
+                prop_assert_eq!(*v, product[(i, j)]);
+            }
+        }
+
+        #[test]
+        fn fused_pass_agrees_with_separate_kernels(csr in csr_strategy()) {
+            let input = dense(csr.ncols(), 2, 2);
+            let output_gradient = dense(csr.nrows(), 2, 1);
+
+            let (output, gradients) =
+                spmm_csr_dense_forward_backward(&csr, &input, &output_gradient).unwrap();
+
+            prop_assert_eq!(&output, &(DMatrix::from(&csr) * &input));
+            prop_assert_eq!(&output, &spmm_csr_dense_forward(&csr, &input).unwrap());
+
+            let expected_values = sddmm_csr(&csr, &output_gradient, &input).unwrap();
+            prop_assert_eq!(gradients.values.cs_data(), expected_values.cs_data());
+
+            let expected_input = DMatrix::from(&csr).transpose() * &output_gradient;
+            prop_assert_eq!(&gradients.input, &expected_input);
+
+            let backward = spmm_csr_dense_backward(&csr, &input, &output_gradient).unwrap();
+            prop_assert_eq!(backward.values.cs_data(), gradients.values.cs_data());
+            prop_assert_eq!(backward.input, gradients.input);
+        }
+    }
+}
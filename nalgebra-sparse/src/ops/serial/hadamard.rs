@@ -0,0 +1,528 @@
+//! Module holding the element-wise (Hadamard) products of sparse matrices.
+//!
+//! The Hadamard product `A ∘ B` of two matrices of the same shape multiplies their entries
+//! position by position, i.e. `(A ∘ B)[i, j] = A[i, j] B[i, j]`. Since a product with an implicit
+//! zero is zero, the sparsity pattern of the result is the intersection of the patterns of the
+//! two operands: it holds exactly the positions where both `A` and `B` have an explicit entry.
+//! Products that happen to evaluate to zero are kept as explicit entries.
+//!
+//! # Performance
+//!
+//! As with subtraction, `sp_hadamard_csr_csr` and `sp_hadamard_csc_csc` are the fastest
+//! combinations, since they merge the lanes of both operands directly. Mixing formats, such as in
+//! `sp_hadamard_csr_csc`, needs to iterate along the minor lanes of the second matrix. The
+//! products of a sparse and a dense matrix only visit the explicit entries of the sparse one.
+
+use crate::{
+    convert::utils::CountToOffsetIter,
+    cs::{CompressedColumnStorage, CompressedRowStorage, Compression, CsMatrix},
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::{Dim, Matrix, RawStorage, Scalar};
+use std::{borrow::Borrow, cmp::Ordering, ops::Mul};
+
+/// The result of a Hadamard product, which has the compression `C` of its first operand.
+type Product<T1, T2, C> =
+    CsMatrix<<T1 as Mul<T2>>::Output, Vec<usize>, Vec<usize>, Vec<<T1 as Mul<T2>>::Output>, C>;
+
+/// Sparse-sparse Hadamard product.
+///
+/// This function takes two CSR matrices, and computes their element-wise product.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn sp_hadamard_csr_csr<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+) -> Result<Product<T1, T2, CompressedRowStorage>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    check_shapes(lhs.shape(), rhs.shape())?;
+
+    Ok(intersect(&lhs, rhs.triplet_iter()))
+}
+
+/// Sparse-sparse Hadamard product.
+///
+/// This function takes two CSC matrices, and computes their element-wise product.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn sp_hadamard_csc_csc<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+) -> Result<Product<T1, T2, CompressedColumnStorage>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    check_shapes(lhs.shape(), rhs.shape())?;
+
+    Ok(intersect(&lhs, rhs.triplet_iter()))
+}
+
+/// Sparse-sparse Hadamard product.
+///
+/// This function takes two arguments, a CSR matrix and CSC matrix, and computes their
+/// element-wise product as a CSR matrix.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn sp_hadamard_csr_csc<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    csr: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
+    csc: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+) -> Result<Product<T1, T2, CompressedRowStorage>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    check_shapes(csr.shape(), csc.shape())?;
+
+    let right_iter = csc
+        .minor_lane_iter()
+        .enumerate()
+        .flat_map(|(i, lane)| lane.map(move |(j, value)| (i, j, value)));
+
+    Ok(intersect(&csr, right_iter))
+}
+
+/// Sparse-sparse Hadamard product.
+///
+/// This function takes two arguments, a CSC matrix and CSR matrix, and computes their
+/// element-wise product as a CSC matrix.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn sp_hadamard_csc_csr<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    csc: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
+    csr: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+) -> Result<Product<T1, T2, CompressedColumnStorage>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    check_shapes(csc.shape(), csr.shape())?;
+
+    let right_iter = csr
+        .minor_lane_iter()
+        .enumerate()
+        .flat_map(|(i, lane)| lane.map(move |(j, value)| (i, j, value)));
+
+    Ok(intersect(&csc, right_iter))
+}
+
+/// Sparse-dense Hadamard product.
+///
+/// This function takes in two matrices, one CSR matrix and one dense matrix, and computes their
+/// element-wise product. The result has the sparsity pattern of the CSR matrix.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn sp_hadamard_csr_dense<T1, T2, R, C, S, MO, MI, D>(
+    csr: CsMatrix<T1, MO, MI, D, CompressedRowStorage>,
+    dense: &Matrix<T2, R, C, S>,
+) -> Result<Product<T1, T2, CompressedRowStorage>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T2, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+{
+    check_shapes(csr.shape(), dense.shape())?;
+
+    Ok(scale_by_dense(csr, |row, col| dense.index((row, col))))
+}
+
+/// Sparse-dense Hadamard product.
+///
+/// This function takes in two matrices, one CSC matrix and one dense matrix, and computes their
+/// element-wise product. The result has the sparsity pattern of the CSC matrix.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn sp_hadamard_csc_dense<T1, T2, R, C, S, MO, MI, D>(
+    csc: CsMatrix<T1, MO, MI, D, CompressedColumnStorage>,
+    dense: &Matrix<T2, R, C, S>,
+) -> Result<Product<T1, T2, CompressedColumnStorage>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T2, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+{
+    check_shapes(csc.shape(), dense.shape())?;
+
+    Ok(scale_by_dense(csc, |col, row| dense.index((row, col))))
+}
+
+fn check_shapes(lhs: (usize, usize), rhs: (usize, usize)) -> Result<(), OperationError> {
+    if lhs != rhs {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("The two matrices have differing shapes (both should be M × N)"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Multiplies the entries of `lhs` with the entries of the triplet iterator `rhs` that have the
+/// same major and minor index.
+///
+/// `rhs` must yield `(major, minor, value)` triplets in the major -> minor order of `lhs`.
+fn intersect<'a, T1, T2, MO, MI, D, C, I>(
+    lhs: &CsMatrix<T1, MO, MI, D, C>,
+    rhs: I,
+) -> Product<T1, T2, C>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+    C: Compression,
+    I: Iterator<Item = (usize, usize, &'a T2)>,
+{
+    let (nrows, ncols) = lhs.shape();
+
+    let mut left_iter = lhs.triplet_iter().peekable();
+    let mut right_iter = rhs.peekable();
+
+    let mut counts = vec![0; lhs.nmajor()];
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+
+    while let (Some(&(il, jl, vl)), Some(&(ir, jr, vr))) = (left_iter.peek(), right_iter.peek()) {
+        match (il, jl).cmp(&(ir, jr)) {
+            Ordering::Less => {
+                left_iter.next();
+            }
+
+            Ordering::Greater => {
+                right_iter.next();
+            }
+
+            Ordering::Equal => {
+                counts[il] += 1;
+                indices.push(jl);
+                data.push(vl.clone() * vr.clone());
+
+                left_iter.next();
+                right_iter.next();
+            }
+        }
+    }
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+
+    unsafe { CsMatrix::from_parts_unchecked(nrows, ncols, offsets, indices, data) }
+}
+
+/// Multiplies every explicit entry of `cs` with the dense value returned by `dense` for its major
+/// and minor index, keeping the pattern of `cs`.
+fn scale_by_dense<'a, T1, T2, MO, MI, D, C, F>(
+    cs: CsMatrix<T1, MO, MI, D, C>,
+    dense: F,
+) -> Product<T1, T2, C>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar + 'a,
+    <T1 as Mul<T2>>::Output: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+    C: Compression,
+    F: Fn(usize, usize) -> &'a T2,
+{
+    let (nrows, ncols) = cs.shape();
+    let data = cs
+        .triplet_iter()
+        .map(|(major, minor, value)| value.clone() * dense(major, minor).clone())
+        .collect();
+    let (offsets, indices, _) = cs.disassemble();
+
+    unsafe {
+        CsMatrix::from_parts_unchecked(
+            nrows,
+            ncols,
+            offsets.borrow().to_vec(),
+            indices.borrow().to_vec(),
+            data,
+        )
+    }
+}
+
+/// Sparse Hadamard products for any combination of storage formats.
+///
+/// This trait is implemented for every pair of operands supported by the `sp_hadamard_x_y`
+/// functions in this module, so that [`sp_hadamard`] can pick the right one from the types of its
+/// arguments. The output format is the same as that of the function it dispatches to.
+pub trait SpHadamard<Rhs> {
+    /// The type of the result of the product.
+    type Output;
+
+    /// Computes the element-wise product of `self` and `rhs`.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same way as the `sp_hadamard_x_y` function for the formats of `self` and
+    /// `rhs`.
+    fn sp_hadamard(self, rhs: Rhs) -> Result<Self::Output, OperationError>;
+}
+
+/// Computes the element-wise product of `lhs` and `rhs` for any supported combination of CSR, CSC
+/// and dense operands.
+///
+/// This dispatches on the types of `lhs` and `rhs` to the matching `sp_hadamard_x_y` function in
+/// this module, e.g. [`sp_hadamard_csr_csc`] for a CSR and a CSC matrix. Dense operands are taken
+/// by reference and may only appear on the right.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra::DMatrix;
+/// use nalgebra_sparse::{
+///     cs::{CscMatrix, CsrMatrix},
+///     ops::serial::hadamard::sp_hadamard,
+/// };
+///
+/// let csr = CsrMatrix::from(&DMatrix::from_row_slice(2, 2, &[1, 2, 0, 3]));
+/// let csc = CscMatrix::from(&DMatrix::from_row_slice(2, 2, &[4, 0, 5, 6]));
+///
+/// // Only the positions (0, 0) and (1, 1) are explicit in both matrices
+/// let product = sp_hadamard(csr.to_view(), csc.to_view()).unwrap();
+/// assert_eq!(product.nnz(), 2);
+/// assert_eq!(DMatrix::from(&product), DMatrix::from_row_slice(2, 2, &[4, 0, 0, 18]));
+///
+/// let dense = DMatrix::from_element(2, 2, 2);
+/// let scaled = sp_hadamard(csr.to_view(), &dense).unwrap();
+/// assert_eq!(scaled.nnz(), 3);
+/// ```
+pub fn sp_hadamard<L, R>(lhs: L, rhs: R) -> Result<L::Output, OperationError>
+where
+    L: SpHadamard<R>,
+{
+    lhs.sp_hadamard(rhs)
+}
+
+macro_rules! impl_sp_hadamard_sparse {
+    ($lhs:ty, $rhs:ty, $function:ident) => {
+        impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2> SpHadamard<CsMatrix<T2, MO2, MI2, D2, $rhs>>
+            for CsMatrix<T1, MO1, MI1, D1, $lhs>
+        where
+            T1: Scalar + Mul<T2>,
+            T2: Scalar,
+            <T1 as Mul<T2>>::Output: Scalar,
+            MO1: Borrow<[usize]>,
+            MO2: Borrow<[usize]>,
+            MI1: Borrow<[usize]>,
+            MI2: Borrow<[usize]>,
+            D1: Borrow<[T1]>,
+            D2: Borrow<[T2]>,
+        {
+            type Output = Product<T1, T2, $lhs>;
+
+            fn sp_hadamard(
+                self,
+                rhs: CsMatrix<T2, MO2, MI2, D2, $rhs>,
+            ) -> Result<Self::Output, OperationError> {
+                $function(self, rhs)
+            }
+        }
+    };
+}
+
+impl_sp_hadamard_sparse!(
+    CompressedRowStorage,
+    CompressedRowStorage,
+    sp_hadamard_csr_csr
+);
+impl_sp_hadamard_sparse!(
+    CompressedRowStorage,
+    CompressedColumnStorage,
+    sp_hadamard_csr_csc
+);
+impl_sp_hadamard_sparse!(
+    CompressedColumnStorage,
+    CompressedRowStorage,
+    sp_hadamard_csc_csr
+);
+impl_sp_hadamard_sparse!(
+    CompressedColumnStorage,
+    CompressedColumnStorage,
+    sp_hadamard_csc_csc
+);
+
+macro_rules! impl_sp_hadamard_dense {
+    ($lhs:ty, $function:ident) => {
+        impl<'a, T1, T2, R, C, S, MO, MI, D> SpHadamard<&'a Matrix<T2, R, C, S>>
+            for CsMatrix<T1, MO, MI, D, $lhs>
+        where
+            T1: Scalar + Mul<T2>,
+            T2: Scalar,
+            <T1 as Mul<T2>>::Output: Scalar,
+            R: Dim,
+            C: Dim,
+            S: RawStorage<T2, R, C>,
+            MO: Borrow<[usize]>,
+            MI: Borrow<[usize]>,
+            D: Borrow<[T1]>,
+        {
+            type Output = Product<T1, T2, $lhs>;
+
+            fn sp_hadamard(
+                self,
+                rhs: &'a Matrix<T2, R, C, S>,
+            ) -> Result<Self::Output, OperationError> {
+                $function(self, rhs)
+            }
+        }
+    };
+}
+
+impl_sp_hadamard_dense!(CompressedRowStorage, sp_hadamard_csr_dense);
+impl_sp_hadamard_dense!(CompressedColumnStorage, sp_hadamard_csc_dense);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cs::{CscMatrix, CsrMatrix},
+        proptest::*,
+        SparseEntry,
+    };
+    use matrixcompare::prop_assert_matrix_eq;
+    use nalgebra::DMatrix;
+    use proptest::prelude::*;
+
+    /// Pairs of matrices of the same shape.
+    fn pair() -> impl Strategy<Value = (CsrMatrix<i32>, CsrMatrix<i32>)> {
+        csr_strategy().prop_flat_map(|a| {
+            let (nrows, ncols) = a.shape();
+            let b = csr(
+                PROPTEST_I32_VALUE_STRATEGY,
+                nrows..=nrows,
+                ncols..=ncols,
+                PROPTEST_MAX_NNZ,
+            );
+
+            (Just(a), b)
+        })
+    }
+
+    #[test]
+    fn sp_hadamard_keeps_explicit_zero_products() {
+        let a = CsrMatrix::try_from_parts(2, 3, vec![0, 2], vec![0, 2, 1], vec![0, 2, 3]).unwrap();
+        let b = CsrMatrix::try_from_parts(2, 3, vec![0, 1], vec![0, 0, 1], vec![5, 7, 4]).unwrap();
+
+        let product = sp_hadamard_csr_csr(a.to_view(), b.to_view()).unwrap();
+
+        assert_eq!(product.cs_data(), (&[0, 1][..], &[0, 1][..], &[0, 12][..]));
+    }
+
+    #[test]
+    fn sp_hadamard_rejects_mismatched_shapes() {
+        let a = CsrMatrix::<i32>::zeros(2, 3);
+
+        for err in [
+            sp_hadamard(a.to_view(), CsrMatrix::<i32>::zeros(3, 2)).unwrap_err(),
+            sp_hadamard(a.to_view(), CscMatrix::<i32>::zeros(2, 2)).unwrap_err(),
+            sp_hadamard(a.to_view(), &DMatrix::<i32>::zeros(3, 3)).unwrap_err(),
+        ] {
+            assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn sp_hadamard_agrees_with_dense((a, b) in pair()) {
+            let dense_a = DMatrix::from(&a);
+            let dense_b = DMatrix::from(&b);
+            let expected = dense_a.component_mul(&dense_b);
+
+            let a_csc = CscMatrix::from(a.clone());
+            let b_csc = CscMatrix::from(b.clone());
+
+            prop_assert_matrix_eq!(sp_hadamard(a.to_view(), b.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(sp_hadamard(a.to_view(), b_csc.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(sp_hadamard(a_csc.to_view(), b.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(sp_hadamard(a_csc.to_view(), b_csc.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(sp_hadamard(a.to_view(), &dense_b).unwrap(), expected);
+            prop_assert_matrix_eq!(sp_hadamard(a_csc.to_view(), &dense_b).unwrap(), expected);
+        }
+
+        #[test]
+        fn sp_hadamard_pattern_is_the_intersection((a, b) in pair()) {
+            let product = sp_hadamard(a.to_view(), CscMatrix::from(b.clone())).unwrap();
+            let expected: Vec<_> = a
+                .pattern()
+                .entries()
+                .filter(|&(i, j)| matches!(b.get_entry(i, j), Some(SparseEntry::NonZero(_))))
+                .collect();
+
+            prop_assert_eq!(product.pattern().entries().collect::<Vec<_>>(), expected);
+
+            // Every explicit entry is kept when multiplying with a dense matrix
+            let scaled = sp_hadamard(a.to_view(), &DMatrix::from(&b)).unwrap();
+            prop_assert_eq!(scaled.pattern(), a.pattern());
+        }
+    }
+}
@@ -0,0 +1,493 @@
+//! Module holding the Khatri-Rao and face-splitting products.
+//!
+//! The Khatri-Rao product `A ⊙ B` of an `I × K` matrix `A` and a `J × K` matrix `B` is the
+//! `IJ × K` matrix whose `k`-th column is the Kronecker product of the `k`-th columns of `A` and
+//! `B`, i.e. entry `(i * J + j, k)` of the product is `A[(i, k)] * B[(j, k)]`. It appears most
+//! prominently in the matricized-tensor times Khatri-Rao product (MTTKRP) at the heart of CP
+//! tensor decompositions, e.g. on top of the unfoldings of a [`CooTensor`](crate::tensor::CooTensor).
+//!
+//! The face-splitting product of an `I × K` matrix `A` and an `I × L` matrix `B` is the row-wise
+//! analogue, i.e. the `I × KL` matrix whose `i`-th row is the Kronecker product of the `i`-th rows
+//! of `A` and `B`. Entry `(i, k * L + l)` of the product is `A[(i, k)] * B[(i, l)]`.
+//!
+//! Since the Khatri-Rao product is built from columns, its functions take and produce CSC
+//! matrices, while the face-splitting product is built from rows and uses CSR matrices instead.
+//!
+//! # Sparse-Dense products
+//!
+//! When one of the operands is dense, the product is still sparse, as every entry of the product
+//! is scaled by an entry of the sparse operand. The products with a dense operand store every
+//! product of an explicit entry of the sparse operand with an entry of the dense operand, so any
+//! zeros in the dense operand end up as explicit zeros in the product.
+
+use crate::{
+    convert::utils::CountToOffsetIter,
+    cs::{CompressedColumnStorage, CompressedRowStorage, CsMatrix, CscMatrix, CsrMatrix},
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::{Dim, Matrix, RawStorage, Scalar};
+use std::{borrow::Borrow, ops::Mul};
+
+/// Khatri-Rao product of two sparse matrices.
+///
+/// This computes the `IJ × K` product `A ⊙ B` of the `I × K` matrix `A` and `J × K` matrix `B`.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices have a different number of columns.
+pub fn khatri_rao_csc_csc<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+) -> Result<CscMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    let (lrows, lcols) = lhs.shape();
+    let (rrows, rcols) = rhs.shape();
+
+    if lcols != rcols {
+        return Err(mismatched_lanes(
+            "The two matrices have incompatible shapes (I × K1 and J × K2 where K1 ≠ K2)",
+        ));
+    }
+
+    let mut counts = vec![0usize; lcols];
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+
+    for ((count, left), right) in counts.iter_mut().zip(lhs.iter()).zip(rhs.iter()) {
+        for (i, a) in left {
+            for (j, b) in right.clone() {
+                *count += 1;
+                indices.push(i * rrows + j);
+                data.push(a.clone() * b.clone());
+            }
+        }
+    }
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+
+    Ok(unsafe { CscMatrix::from_parts_unchecked(lrows * rrows, lcols, offsets, indices, data) })
+}
+
+/// Khatri-Rao product of a sparse and a dense matrix.
+///
+/// This computes the `IJ × K` product `A ⊙ B` of the sparse `I × K` matrix `A` and the dense
+/// `J × K` matrix `B`. See the [module-level documentation](self) for how zeros in `B` are
+/// treated.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices have a different number of columns.
+pub fn khatri_rao_csc_dense<T1, T2, R, C, S, MO, MI, D>(
+    csc: CsMatrix<T1, MO, MI, D, CompressedColumnStorage>,
+    dense: Matrix<T2, R, C, S>,
+) -> Result<CscMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T2, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+{
+    let (lrows, lcols) = csc.shape();
+    let (rrows, rcols) = dense.shape();
+
+    if lcols != rcols {
+        return Err(mismatched_lanes(
+            "The two matrices have incompatible shapes (I × K1 and J × K2 where K1 ≠ K2)",
+        ));
+    }
+
+    let mut counts = vec![0usize; lcols];
+    let mut indices = Vec::with_capacity(csc.nnz() * rrows);
+    let mut data = Vec::with_capacity(csc.nnz() * rrows);
+
+    for (k, (count, lane)) in counts.iter_mut().zip(csc.iter()).enumerate() {
+        for (i, a) in lane {
+            for j in 0..rrows {
+                *count += 1;
+                indices.push(i * rrows + j);
+                data.push(a.clone() * dense[(j, k)].clone());
+            }
+        }
+    }
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+
+    Ok(unsafe { CscMatrix::from_parts_unchecked(lrows * rrows, lcols, offsets, indices, data) })
+}
+
+/// Khatri-Rao product of a dense and a sparse matrix.
+///
+/// This computes the `IJ × K` product `A ⊙ B` of the dense `I × K` matrix `A` and the sparse
+/// `J × K` matrix `B`. See the [module-level documentation](self) for how zeros in `A` are
+/// treated.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices have a different number of columns.
+pub fn khatri_rao_dense_csc<T1, T2, R, C, S, MO, MI, D>(
+    dense: Matrix<T1, R, C, S>,
+    csc: CsMatrix<T2, MO, MI, D, CompressedColumnStorage>,
+) -> Result<CscMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T1, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T2]>,
+{
+    let (lrows, lcols) = dense.shape();
+    let (rrows, rcols) = csc.shape();
+
+    if lcols != rcols {
+        return Err(mismatched_lanes(
+            "The two matrices have incompatible shapes (I × K1 and J × K2 where K1 ≠ K2)",
+        ));
+    }
+
+    let mut counts = vec![0usize; lcols];
+    let mut indices = Vec::with_capacity(csc.nnz() * lrows);
+    let mut data = Vec::with_capacity(csc.nnz() * lrows);
+
+    for (k, (count, lane)) in counts.iter_mut().zip(csc.iter()).enumerate() {
+        for i in 0..lrows {
+            for (j, b) in lane.clone() {
+                *count += 1;
+                indices.push(i * rrows + j);
+                data.push(dense[(i, k)].clone() * b.clone());
+            }
+        }
+    }
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+
+    Ok(unsafe { CscMatrix::from_parts_unchecked(lrows * rrows, lcols, offsets, indices, data) })
+}
+
+/// Face-splitting (row-wise Khatri-Rao) product of two sparse matrices.
+///
+/// This computes the `I × KL` face-splitting product of the `I × K` matrix `A` and the `I × L`
+/// matrix `B`.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices have a different number of rows.
+pub fn face_splitting_csr_csr<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    let (lrows, lcols) = lhs.shape();
+    let (rrows, rcols) = rhs.shape();
+
+    if lrows != rrows {
+        return Err(mismatched_lanes(
+            "The two matrices have incompatible shapes (I1 × K and I2 × L where I1 ≠ I2)",
+        ));
+    }
+
+    let mut counts = vec![0usize; lrows];
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+
+    for ((count, left), right) in counts.iter_mut().zip(lhs.iter()).zip(rhs.iter()) {
+        for (k, a) in left {
+            for (l, b) in right.clone() {
+                *count += 1;
+                indices.push(k * rcols + l);
+                data.push(a.clone() * b.clone());
+            }
+        }
+    }
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+
+    Ok(unsafe { CsrMatrix::from_parts_unchecked(lrows, lcols * rcols, offsets, indices, data) })
+}
+
+/// Face-splitting (row-wise Khatri-Rao) product of a sparse and a dense matrix.
+///
+/// This computes the `I × KL` face-splitting product of the sparse `I × K` matrix `A` and the
+/// dense `I × L` matrix `B`. See the [module-level documentation](self) for how zeros in `B` are
+/// treated.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices have a different number of rows.
+pub fn face_splitting_csr_dense<T1, T2, R, C, S, MO, MI, D>(
+    csr: CsMatrix<T1, MO, MI, D, CompressedRowStorage>,
+    dense: Matrix<T2, R, C, S>,
+) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T2, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+{
+    let (lrows, lcols) = csr.shape();
+    let (rrows, rcols) = dense.shape();
+
+    if lrows != rrows {
+        return Err(mismatched_lanes(
+            "The two matrices have incompatible shapes (I1 × K and I2 × L where I1 ≠ I2)",
+        ));
+    }
+
+    let mut counts = vec![0usize; lrows];
+    let mut indices = Vec::with_capacity(csr.nnz() * rcols);
+    let mut data = Vec::with_capacity(csr.nnz() * rcols);
+
+    for (i, (count, lane)) in counts.iter_mut().zip(csr.iter()).enumerate() {
+        for (k, a) in lane {
+            for l in 0..rcols {
+                *count += 1;
+                indices.push(k * rcols + l);
+                data.push(a.clone() * dense[(i, l)].clone());
+            }
+        }
+    }
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+
+    Ok(unsafe { CsrMatrix::from_parts_unchecked(lrows, lcols * rcols, offsets, indices, data) })
+}
+
+/// Face-splitting (row-wise Khatri-Rao) product of a dense and a sparse matrix.
+///
+/// This computes the `I × KL` face-splitting product of the dense `I × K` matrix `A` and the
+/// sparse `I × L` matrix `B`. See the [module-level documentation](self) for how zeros in `A` are
+/// treated.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices have a different number of rows.
+pub fn face_splitting_dense_csr<T1, T2, R, C, S, MO, MI, D>(
+    dense: Matrix<T1, R, C, S>,
+    csr: CsMatrix<T2, MO, MI, D, CompressedRowStorage>,
+) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    T2: Scalar,
+    <T1 as Mul<T2>>::Output: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T1, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T2]>,
+{
+    let (lrows, lcols) = dense.shape();
+    let (rrows, rcols) = csr.shape();
+
+    if lrows != rrows {
+        return Err(mismatched_lanes(
+            "The two matrices have incompatible shapes (I1 × K and I2 × L where I1 ≠ I2)",
+        ));
+    }
+
+    let mut counts = vec![0usize; lrows];
+    let mut indices = Vec::with_capacity(csr.nnz() * lcols);
+    let mut data = Vec::with_capacity(csr.nnz() * lcols);
+
+    for (i, (count, lane)) in counts.iter_mut().zip(csr.iter()).enumerate() {
+        for k in 0..lcols {
+            for (l, b) in lane.clone() {
+                *count += 1;
+                indices.push(k * rcols + l);
+                data.push(dense[(i, k)].clone() * b.clone());
+            }
+        }
+    }
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+
+    Ok(unsafe { CsrMatrix::from_parts_unchecked(lrows, lcols * rcols, offsets, indices, data) })
+}
+
+fn mismatched_lanes(message: &str) -> OperationError {
+    OperationError::from_kind_and_message(OperationErrorKind::InvalidPattern, String::from(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest::*;
+    use matrixcompare::{assert_matrix_eq, prop_assert_matrix_eq};
+    use nalgebra::{DMatrix, SMatrix};
+    use proptest::prelude::*;
+
+    /// Two CSC matrices with the same number of columns.
+    fn same_columns_strategy() -> impl Strategy<Value = (CscMatrix<i32>, CscMatrix<i32>)> {
+        PROPTEST_MATRIX_DIM.prop_flat_map(|ncols| {
+            let matrix = || {
+                csc(
+                    PROPTEST_I32_VALUE_STRATEGY,
+                    PROPTEST_MATRIX_DIM,
+                    ncols,
+                    PROPTEST_MAX_NNZ,
+                )
+            };
+
+            (matrix(), matrix())
+        })
+    }
+
+    /// Two CSR matrices with the same number of rows.
+    fn same_rows_strategy() -> impl Strategy<Value = (CsrMatrix<i32>, CsrMatrix<i32>)> {
+        PROPTEST_MATRIX_DIM.prop_flat_map(|nrows| {
+            let matrix = || {
+                csr(
+                    PROPTEST_I32_VALUE_STRATEGY,
+                    nrows,
+                    PROPTEST_MATRIX_DIM,
+                    PROPTEST_MAX_NNZ,
+                )
+            };
+
+            (matrix(), matrix())
+        })
+    }
+
+    /// The Khatri-Rao product computed directly from its definition on dense matrices.
+    fn dense_khatri_rao(a: &DMatrix<i32>, b: &DMatrix<i32>) -> DMatrix<i32> {
+        DMatrix::from_fn(a.nrows() * b.nrows(), a.ncols(), |r, k| {
+            a[(r / b.nrows(), k)] * b[(r % b.nrows(), k)]
+        })
+    }
+
+    /// The face-splitting product computed directly from its definition on dense matrices.
+    fn dense_face_splitting(a: &DMatrix<i32>, b: &DMatrix<i32>) -> DMatrix<i32> {
+        DMatrix::from_fn(a.nrows(), a.ncols() * b.ncols(), |i, c| {
+            a[(i, c / b.ncols())] * b[(i, c % b.ncols())]
+        })
+    }
+
+    #[test]
+    fn khatri_rao_of_known_matrices() {
+        #[rustfmt::skip]
+        let a = SMatrix::<i32, 2, 2>::from_row_slice(&[
+            1, 0,
+            2, 3,
+        ]);
+
+        #[rustfmt::skip]
+        let b = SMatrix::<i32, 2, 2>::from_row_slice(&[
+            4, 5,
+            0, 6,
+        ]);
+
+        #[rustfmt::skip]
+        let expected = SMatrix::<i32, 4, 2>::from_row_slice(&[
+            4, 0,
+            0, 0,
+            8, 15,
+            0, 18,
+        ]);
+
+        let product = khatri_rao_csc_csc(CscMatrix::from(&a), CscMatrix::from(&b)).unwrap();
+
+        assert_eq!(product.shape(), (4, 2));
+        assert_eq!(product.nnz(), 4);
+        assert_matrix_eq!(product, expected);
+    }
+
+    #[test]
+    fn khatri_rao_rejects_mismatched_columns() {
+        let a = CscMatrix::<i32>::identity(2);
+        let b = CscMatrix::<i32>::zeros(2, 3);
+
+        let error = khatri_rao_csc_csc(a, b).unwrap_err();
+
+        assert!(matches!(error.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn face_splitting_rejects_mismatched_rows() {
+        let a = CsrMatrix::<i32>::identity(2);
+        let b = DMatrix::<i32>::zeros(3, 2);
+
+        let error = face_splitting_csr_dense(a, b).unwrap_err();
+
+        assert!(matches!(error.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    proptest! {
+        #[test]
+        fn khatri_rao_agrees_with_dense((a, b) in same_columns_strategy()) {
+            let dense_a = DMatrix::from(&a);
+            let dense_b = DMatrix::from(&b);
+            let expected = dense_khatri_rao(&dense_a, &dense_b);
+
+            let sparse = khatri_rao_csc_csc(a.to_view(), b.to_view()).unwrap();
+            let sparse_dense = khatri_rao_csc_dense(a.to_view(), dense_b.clone()).unwrap();
+            let dense_sparse = khatri_rao_dense_csc(dense_a.clone(), b.to_view()).unwrap();
+
+            prop_assert_eq!(sparse.nnz(), a.iter().zip(b.iter()).map(|(l, r)| l.len() * r.len()).sum::<usize>());
+            prop_assert_matrix_eq!(sparse, expected);
+            prop_assert_matrix_eq!(sparse_dense, expected);
+            prop_assert_matrix_eq!(dense_sparse, expected);
+        }
+
+        #[test]
+        fn face_splitting_agrees_with_dense((a, b) in same_rows_strategy()) {
+            let dense_a = DMatrix::from(&a);
+            let dense_b = DMatrix::from(&b);
+            let expected = dense_face_splitting(&dense_a, &dense_b);
+
+            let sparse = face_splitting_csr_csr(a.to_view(), b.to_view()).unwrap();
+            let sparse_dense = face_splitting_csr_dense(a.to_view(), dense_b.clone()).unwrap();
+            let dense_sparse = face_splitting_dense_csr(dense_a.clone(), b.to_view()).unwrap();
+
+            prop_assert_matrix_eq!(sparse, expected);
+            prop_assert_matrix_eq!(sparse_dense, expected);
+            prop_assert_matrix_eq!(dense_sparse, expected);
+        }
+
+        #[test]
+        fn face_splitting_is_transposed_khatri_rao((a, b) in same_rows_strategy()) {
+            let face_splitting = face_splitting_csr_csr(a.to_view(), b.to_view()).unwrap();
+            let khatri_rao = khatri_rao_csc_csc(a.transpose(), b.transpose()).unwrap();
+
+            prop_assert_matrix_eq!(face_splitting, khatri_rao.transpose());
+        }
+    }
+}
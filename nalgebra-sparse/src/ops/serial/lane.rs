@@ -0,0 +1,329 @@
+//! Module holding transforms that operate on each major lane of a sparse matrix independently.
+//!
+//! These are mostly useful for machine learning workloads such as sparse attention or graph
+//! neural networks, where e.g. a softmax is taken over the neighbours of every node (i.e. over
+//! every row of an adjacency matrix).
+//!
+//! The transforms only ever consider the explicit entries of a lane. Implicit zeros are treated as
+//! if they were masked out entirely, rather than as entries with a value of zero. For a softmax,
+//! this means that the implicit zeros of a row get a probability of zero, and the probabilities
+//! of the explicit entries of every non-empty row sum up to one.
+//!
+//! Every transform that produces a matrix returns a matrix with the exact same sparsity pattern as
+//! its input.
+
+use crate::cs::{CompressedRowStorage, CsMatrix, CsrMatrix};
+use nalgebra::{RealField, Scalar};
+use std::borrow::Borrow;
+
+/// Builds a CSR matrix with the same pattern as `csr`, computing the values of every lane with
+/// `f`.
+fn map_lanes<T1, T2, MO, MI, D, F>(
+    csr: &CsMatrix<T1, MO, MI, D, CompressedRowStorage>,
+    mut f: F,
+) -> CsrMatrix<T2>
+where
+    T1: Scalar,
+    T2: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+    F: FnMut(usize, &[usize], &[T1], &mut Vec<T2>),
+{
+    let (nrows, ncols) = csr.shape();
+    let (offsets, indices, values) = csr.cs_data();
+
+    let mut data = Vec::with_capacity(values.len());
+
+    for (row, &offset) in offsets.iter().enumerate() {
+        let upper = offsets.get(row + 1).copied().unwrap_or(indices.len());
+        f(
+            row,
+            &indices[offset..upper],
+            &values[offset..upper],
+            &mut data,
+        );
+    }
+
+    unsafe {
+        CsrMatrix::from_parts_unchecked(nrows, ncols, offsets.to_vec(), indices.to_vec(), data)
+    }
+}
+
+/// Returns the largest value in `values`, or `None` if it is empty.
+fn lane_max<T>(values: &[T]) -> Option<T>
+where
+    T: RealField,
+{
+    values
+        .iter()
+        .cloned()
+        .reduce(|max, value| if value > max { value } else { max })
+}
+
+/// Computes a numerically-stable softmax over the explicit entries of each row of a CSR matrix.
+///
+/// Every value `v` in a row is replaced with `exp(v - m) / Σ exp(v_i - m)`, where `m` is the
+/// largest value in that row. Subtracting `m` does not change the result, but prevents `exp` from
+/// overflowing for large values.
+///
+/// See the [module-level documentation](self) for how implicit zeros are treated.
+#[must_use]
+pub fn softmax_csr<T, MO, MI, D>(csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>) -> CsrMatrix<T>
+where
+    T: RealField,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    let _span = span!("softmax_csr", nrows = csr.nrows(), nnz = csr.nnz());
+
+    map_lanes(csr, |_, _, values, data| {
+        if let Some(max) = lane_max(values) {
+            let start = data.len();
+
+            data.extend(
+                values
+                    .iter()
+                    .map(|value| (value.clone() - max.clone()).exp()),
+            );
+
+            let sum = data[start..]
+                .iter()
+                .fold(T::zero(), |sum, value| sum + value.clone());
+
+            for value in &mut data[start..] {
+                *value /= sum.clone();
+            }
+        }
+    })
+}
+
+/// Computes a numerically-stable log-sum-exp over the explicit entries of each row of a CSR
+/// matrix.
+///
+/// For every row, this computes `log(Σ exp(v_i))` as `m + log(Σ exp(v_i - m))`, where `m` is the
+/// largest value in the row. Rows without any explicit entries have no well-defined log-sum-exp,
+/// and produce `None`.
+///
+/// This is the normalization term of [`softmax_csr`], i.e. `softmax(v) = exp(v - lse)`, and is
+/// mostly useful to compute log-probabilities without first computing the probabilities.
+#[must_use]
+pub fn log_sum_exp_csr<T, MO, MI, D>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+) -> Vec<Option<T>>
+where
+    T: RealField,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    let _span = span!("log_sum_exp_csr", nrows = csr.nrows(), nnz = csr.nnz());
+
+    let (offsets, indices, values) = csr.cs_data();
+
+    offsets
+        .iter()
+        .enumerate()
+        .map(|(row, &offset)| {
+            let upper = offsets.get(row + 1).copied().unwrap_or(indices.len());
+            let values = &values[offset..upper];
+
+            lane_max(values).map(|max| {
+                let sum = values.iter().fold(T::zero(), |sum, value| {
+                    sum + (value.clone() - max.clone()).exp()
+                });
+
+                max + sum.ln()
+            })
+        })
+        .collect()
+}
+
+/// Computes a log-softmax over the explicit entries of each row of a CSR matrix.
+///
+/// Every value `v` in a row is replaced with `v - lse`, where `lse` is the log-sum-exp of the row
+/// (see [`log_sum_exp_csr`]). This is more accurate than taking the logarithm of
+/// [`softmax_csr`] for very negative values.
+#[must_use]
+pub fn log_softmax_csr<T, MO, MI, D>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+) -> CsrMatrix<T>
+where
+    T: RealField,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    let lse = log_sum_exp_csr(csr);
+
+    map_lanes(csr, |row, _, values, data| {
+        if let Some(lse) = &lse[row] {
+            data.extend(values.iter().map(|value| value.clone() - lse.clone()));
+        }
+    })
+}
+
+/// Applies dropout-style random masking to the explicit entries of a CSR matrix.
+///
+/// Every explicit entry is dropped (i.e. set to an explicit zero) with probability `1 - keep`,
+/// and every entry that is kept is scaled by `1 / keep`, so that the expected value of every entry
+/// is unchanged. The pattern of the matrix is left as-is, so that e.g. gradients can be computed
+/// with the same pattern.
+///
+/// This crate does not depend on a random number generator, so `uniform` has to be provided by
+/// the caller. It is called once per explicit entry, in row-major order, and must return a sample
+/// from the uniform distribution on `[0, 1)`, e.g. `|| rng.gen::<f64>()` with the `rand` crate.
+///
+/// # Panics
+///
+/// Panics if `keep` is not in `(0, 1]`.
+#[must_use]
+pub fn dropout_csr<T, MO, MI, D, F>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    keep: T,
+    mut uniform: F,
+) -> CsrMatrix<T>
+where
+    T: RealField,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    F: FnMut() -> T,
+{
+    assert!(
+        keep > T::zero() && keep <= T::one(),
+        "The probability of keeping an entry must be in (0, 1]."
+    );
+
+    let scale = T::one() / keep.clone();
+
+    map_lanes(csr, |_, _, values, data| {
+        data.extend(values.iter().map(|value| {
+            if uniform() < keep {
+                value.clone() * scale.clone()
+            } else {
+                T::zero()
+            }
+        }));
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest::*;
+    use proptest::prelude::*;
+
+    fn float_csr_strategy() -> impl Strategy<Value = CsrMatrix<f64>> {
+        csr(
+            -50.0..50.0,
+            PROPTEST_MATRIX_DIM,
+            PROPTEST_MATRIX_DIM,
+            PROPTEST_MAX_NNZ,
+        )
+    }
+
+    /// A tiny deterministic linear congruential generator for uniform samples on `[0, 1)`.
+    fn lcg(seed: u64) -> impl FnMut() -> f64 {
+        let mut state = seed;
+
+        move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        }
+    }
+
+    #[test]
+    fn softmax_of_known_matrix() {
+        let csr = CsrMatrix::try_from_parts(
+            3,
+            3,
+            vec![0, 2, 2],
+            vec![0, 2, 1],
+            vec![0.0, 2.0f64.ln(), 1000.0],
+        )
+        .unwrap();
+
+        let softmax = softmax_csr(&csr);
+        let (offsets, indices, data) = softmax.cs_data();
+
+        assert_eq!(offsets, csr.cs_data().0);
+        assert_eq!(indices, csr.cs_data().1);
+        assert!((data[0] - 1.0 / 3.0).abs() < 1e-12);
+        assert!((data[1] - 2.0 / 3.0).abs() < 1e-12);
+
+        // A single large entry would overflow without subtracting the maximum first.
+        assert_eq!(data[2], 1.0);
+
+        let lse = log_sum_exp_csr(&csr);
+
+        assert!((lse[0].unwrap() - 3.0f64.ln()).abs() < 1e-12);
+        assert_eq!(lse[1], None);
+        assert_eq!(lse[2], Some(1000.0));
+    }
+
+    #[test]
+    fn dropout_keeps_pattern_and_scales_kept_entries() {
+        let csr = CsrMatrix::<f64>::identity(1000);
+        let dropped = dropout_csr(&csr, 0.25, lcg(42));
+
+        assert_eq!(dropped.cs_data().0, csr.cs_data().0);
+        assert_eq!(dropped.cs_data().1, csr.cs_data().1);
+
+        let values = dropped.cs_data().2;
+        let kept = values.iter().filter(|&&v| v != 0.0).count();
+
+        assert!(values.iter().all(|&v| v == 0.0 || v == 4.0));
+        assert!(kept > 150 && kept < 350);
+    }
+
+    #[test]
+    fn dropout_with_keep_one_is_identity() {
+        let csr = CsrMatrix::<f64>::identity(5) * 3.0;
+        let dropped = dropout_csr(&csr, 1.0, lcg(7));
+
+        assert_eq!(dropped.cs_data(), csr.cs_data());
+    }
+
+    #[test]
+    #[should_panic]
+    fn dropout_rejects_zero_keep() {
+        let _ = dropout_csr(&CsrMatrix::<f64>::identity(2), 0.0, lcg(1));
+    }
+
+    proptest! {
+        #[test]
+        fn softmax_rows_sum_to_one(csr in float_csr_strategy()) {
+            let softmax = softmax_csr(&csr);
+
+            prop_assert_eq!(softmax.cs_data().0, csr.cs_data().0);
+            prop_assert_eq!(softmax.cs_data().1, csr.cs_data().1);
+
+            for lane in softmax.iter() {
+                if lane.len() > 0 {
+                    let sum = lane.map(|(_, v)| *v).sum::<f64>();
+                    prop_assert!((sum - 1.0).abs() < 1e-12);
+                }
+            }
+        }
+
+        #[test]
+        fn log_softmax_agrees_with_softmax(csr in float_csr_strategy()) {
+            let softmax = softmax_csr(&csr);
+            let log_softmax = log_softmax_csr(&csr);
+
+            let lse = log_sum_exp_csr(&csr);
+
+            for (row, lane) in csr.iter().enumerate() {
+                prop_assert_eq!(lse[row].is_some(), lane.len() > 0);
+            }
+
+            for (p, log_p) in softmax.cs_data().2.iter().zip(log_softmax.cs_data().2) {
+                prop_assert!((p - log_p.exp()).abs() < 1e-12);
+            }
+        }
+    }
+}
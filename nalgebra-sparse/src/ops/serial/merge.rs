@@ -0,0 +1,342 @@
+//! Module holding the sorted-merge machinery behind the sparse-sparse elementwise routines.
+//!
+//! Addition and subtraction produce a matrix whose pattern is the union of the patterns of their
+//! operands. Computing that union amounts to walking the entries of both operands in lockstep,
+//! which is only correct if both are traversed in the same major -> minor order. [`merge_lanes`]
+//! implements this walk once, so that custom elementwise kernels (e.g. an elementwise maximum) can
+//! be written by only providing the function that combines the values.
+//!
+//! Rather than allocating for the worst case (`nnz(A) + nnz(B)` entries), the union is first
+//! counted symbolically, so that the output buffers can be sized exactly before any value is
+//! computed. When both operands already share the same pattern, the in-place variants skip the
+//! merge entirely and combine the values entry by entry.
+
+use crate::{
+    convert::utils::CountToOffsetIter,
+    cs::{Compression, CsMatrix},
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::Scalar;
+use std::{
+    borrow::{Borrow, BorrowMut},
+    cmp::Ordering,
+    iter::{FusedIterator, Peekable},
+};
+
+/// An entry of the union of two sparsity patterns, as passed to the `combine` function of
+/// [`merge_lanes`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Merged<L, R> {
+    /// The entry is only present in the left operand.
+    Left(L),
+    /// The entry is only present in the right operand.
+    Right(R),
+    /// The entry is present in both operands.
+    Both(L, R),
+}
+
+/// Merges two triplet iterators into a single iterator over the union of their entries.
+///
+/// Both `left` and `right` must yield `(major, minor, value)` triplets sorted in the same
+/// lexicographic (major -> minor) order, without duplicates. This is the order in which
+/// [`CsMatrix::triplet_iter`] yields the entries of a matrix, so two matrices of the same
+/// compression kind can be merged directly. To merge a CSR matrix with a CSC matrix, traverse the
+/// latter along its minor lanes instead:
+///
+/// ```ignore
+/// let left = csr.triplet_iter();
+/// let right = csc
+///     .minor_lane_iter()
+///     .enumerate()
+///     .flat_map(|(i, lane)| lane.map(move |(j, value)| (i, j, value)));
+/// ```
+///
+/// For every `(major, minor)` position present in either iterator, the returned iterator yields
+/// `(major, minor, combine(entry))`, where `entry` tells whether the value came from the left, the
+/// right, or both iterators. The output is in the same order as the inputs, so it can be collected
+/// straight into the offsets, indices and data of a compressed matrix.
+///
+/// If either input is not sorted, the output is unspecified (but the iterator does not panic).
+///
+/// # Example
+///
+/// ```
+/// use nalgebra_sparse::{
+///     cs::CsrMatrix,
+///     ops::serial::merge::{merge_lanes, Merged},
+/// };
+///
+/// let a = CsrMatrix::try_from_parts(2, 3, vec![0, 2], vec![0, 2, 1], vec![1, -4, 2]).unwrap();
+/// let b = CsrMatrix::try_from_parts(2, 3, vec![0, 1], vec![2, 1], vec![3, 5]).unwrap();
+///
+/// // The elementwise maximum of `a` and `b`, where missing entries are zero.
+/// let maximum = merge_lanes(a.triplet_iter(), b.triplet_iter(), |entry| match entry {
+///     Merged::Left(l) => (*l).max(0),
+///     Merged::Right(r) => (*r).max(0),
+///     Merged::Both(l, r) => *l.max(r),
+/// })
+/// .collect::<Vec<_>>();
+///
+/// assert_eq!(maximum, vec![(0, 0, 1), (0, 2, 3), (1, 1, 5)]);
+/// ```
+pub fn merge_lanes<IL, IR, L, R, T, F>(
+    left: IL,
+    right: IR,
+    combine: F,
+) -> MergeLanes<IL::IntoIter, IR::IntoIter, F>
+where
+    IL: IntoIterator<Item = (usize, usize, L)>,
+    IR: IntoIterator<Item = (usize, usize, R)>,
+    F: FnMut(Merged<L, R>) -> T,
+{
+    MergeLanes {
+        left: left.into_iter().peekable(),
+        right: right.into_iter().peekable(),
+        combine,
+    }
+}
+
+/// The iterator returned by [`merge_lanes`].
+pub struct MergeLanes<IL, IR, F>
+where
+    IL: Iterator,
+    IR: Iterator,
+{
+    left: Peekable<IL>,
+    right: Peekable<IR>,
+    combine: F,
+}
+
+impl<IL, IR, L, R, T, F> Iterator for MergeLanes<IL, IR, F>
+where
+    IL: Iterator<Item = (usize, usize, L)>,
+    IR: Iterator<Item = (usize, usize, R)>,
+    F: FnMut(Merged<L, R>) -> T,
+{
+    type Item = (usize, usize, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = match (self.left.peek(), self.right.peek()) {
+            (Some((il, jl, _)), Some((ir, jr, _))) => (il, jl).cmp(&(ir, jr)),
+            // Only right is exhausted
+            (Some(_), None) => Ordering::Less,
+            // Only left is exhausted
+            (None, Some(_)) => Ordering::Greater,
+            // Both are exhausted
+            (None, None) => return None,
+        };
+
+        let (i, j, entry) = match order {
+            Ordering::Less => {
+                let (i, j, l) = self.left.next()?;
+                (i, j, Merged::Left(l))
+            }
+            Ordering::Greater => {
+                let (i, j, r) = self.right.next()?;
+                (i, j, Merged::Right(r))
+            }
+            Ordering::Equal => {
+                let (i, j, l) = self.left.next()?;
+                let (_, _, r) = self.right.next()?;
+                (i, j, Merged::Both(l, r))
+            }
+        };
+
+        Some((i, j, (self.combine)(entry)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (left_lower, left_upper) = self.left.size_hint();
+        let (right_lower, right_upper) = self.right.size_hint();
+
+        let upper = match (left_upper, right_upper) {
+            (Some(l), Some(r)) => l.checked_add(r),
+            _ => None,
+        };
+
+        (left_lower.max(right_lower), upper)
+    }
+}
+
+impl<IL, IR, L, R, T, F> FusedIterator for MergeLanes<IL, IR, F>
+where
+    IL: FusedIterator<Item = (usize, usize, L)>,
+    IR: FusedIterator<Item = (usize, usize, R)>,
+    F: FnMut(Merged<L, R>) -> T,
+{
+}
+
+/// Counts the number of entries in every major lane of the union of two patterns.
+///
+/// Both iterators must yield `(major, minor)` pairs in the same (lexicographic) order, with no
+/// duplicates, i.e. in the order in which a compressed matrix stores its entries.
+pub(super) fn union_lane_counts<IL, IR>(nmajor: usize, left: IL, right: IR) -> Vec<usize>
+where
+    IL: Iterator<Item = (usize, usize)>,
+    IR: Iterator<Item = (usize, usize)>,
+{
+    let mut counts = vec![0; nmajor];
+
+    let merged = merge_lanes(
+        left.map(|(i, j)| (i, j, ())),
+        right.map(|(i, j)| (i, j, ())),
+        |_| (),
+    );
+
+    for (major, _, ()) in merged {
+        counts[major] += 1;
+    }
+
+    counts
+}
+
+/// Fills `buffers` with the compressed representation of `triplets`, whose lane sizes are given by
+/// `counts`.
+///
+/// The buffers are cleared first, but their allocations are kept: `indices` and `data` only grow
+/// if they cannot already hold exactly `counts.iter().sum()` entries.
+pub(super) fn collect_into_buffers<T, I>(
+    counts: Vec<usize>,
+    triplets: I,
+    buffers: (Vec<usize>, Vec<usize>, Vec<T>),
+) -> (Vec<usize>, Vec<usize>, Vec<T>)
+where
+    I: Iterator<Item = (usize, usize, T)>,
+{
+    let (mut offsets, mut indices, mut data) = buffers;
+    let nnz = counts.iter().sum();
+
+    offsets.clear();
+    offsets.extend(CountToOffsetIter::new(counts));
+
+    indices.clear();
+    indices.reserve_exact(nnz);
+    data.clear();
+    data.reserve_exact(nnz);
+
+    for (_, j, v) in triplets {
+        indices.push(j);
+        data.push(v);
+    }
+
+    debug_assert_eq!(indices.len(), nnz);
+
+    (offsets, indices, data)
+}
+
+/// Combines the values of `rhs` into those of `lhs` entry by entry with `combine`, provided that
+/// both matrices have the same shape and the same sparsity pattern.
+///
+/// # Errors
+///
+/// Fails with [`OperationErrorKind::InvalidPattern`] if the shapes or the patterns differ, in
+/// which case `lhs` is left untouched.
+pub(super) fn combine_identical_patterns<T1, T2, MO1, MO2, MI1, MI2, D1, D2, C, F>(
+    lhs: &mut CsMatrix<T1, MO1, MI1, D1, C>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, C>,
+    mut combine: F,
+) -> Result<(), OperationError>
+where
+    T1: Scalar,
+    T2: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: BorrowMut<[T1]>,
+    D2: Borrow<[T2]>,
+    C: Compression,
+    F: FnMut(&mut T1, &T2),
+{
+    if lhs.shape() != rhs.shape() {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("The two matrices have differing shapes (both should be M × N)"),
+        ));
+    }
+
+    if !lhs.same_pattern(&rhs) {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("The two matrices do not share the same sparsity pattern"),
+        ));
+    }
+
+    let (_, _, rhs_data) = rhs.cs_data();
+    let (_, _, lhs_data) = lhs.cs_data_mut();
+
+    for (l, r) in lhs_data.iter_mut().zip(rhs_data) {
+        combine(l, r);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn merge_lanes_handles_exhausted_operands() {
+        let left = vec![(0, 1, 'a'), (2, 0, 'b')];
+        let empty: Vec<(usize, usize, char)> = Vec::new();
+
+        let merged = merge_lanes(left.clone(), empty.clone(), |entry| entry).collect::<Vec<_>>();
+        assert_eq!(
+            merged,
+            vec![(0, 1, Merged::Left('a')), (2, 0, Merged::Left('b'))]
+        );
+
+        let merged = merge_lanes(empty, left, |entry| entry).collect::<Vec<_>>();
+        assert_eq!(
+            merged,
+            vec![(0, 1, Merged::Right('a')), (2, 0, Merged::Right('b'))]
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn merge_lanes_of_csr_and_csc_visits_the_union_in_order(
+            (a, b) in csr_strategy().prop_flat_map(|a| {
+                let (nrows, ncols) = a.shape();
+                let b = csc(
+                    PROPTEST_I32_VALUE_STRATEGY,
+                    nrows..=nrows,
+                    ncols..=ncols,
+                    PROPTEST_MAX_NNZ,
+                );
+
+                (Just(a), b)
+            })
+        ) {
+            let right = b
+                .minor_lane_iter()
+                .enumerate()
+                .flat_map(|(i, lane)| lane.map(move |(j, value)| (i, j, value)));
+
+            let merged = merge_lanes(a.triplet_iter(), right, |entry| match entry {
+                Merged::Left(l) => (Some(*l), None),
+                Merged::Right(r) => (None, Some(*r)),
+                Merged::Both(l, r) => (Some(*l), Some(*r)),
+            })
+            .collect::<Vec<_>>();
+
+            let mut expected = BTreeMap::new();
+            for (i, j, v) in a.triplet_iter() {
+                expected.entry((i, j)).or_insert((None, None)).0 = Some(*v);
+            }
+            for (j, i, v) in b.triplet_iter() {
+                expected.entry((i, j)).or_insert((None, None)).1 = Some(*v);
+            }
+            let expected = expected
+                .into_iter()
+                .map(|((i, j), entry)| (i, j, entry))
+                .collect::<Vec<_>>();
+
+            prop_assert_eq!(merged, expected);
+        }
+    }
+}
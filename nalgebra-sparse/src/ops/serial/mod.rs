@@ -8,8 +8,17 @@
 //! some operations which will be able to dynamically adapt the output pattern to fit the
 //! result, but these have yet to be implemented.
 
+pub mod contraction;
+pub mod diffusion;
+pub mod embedding;
+pub mod gradient;
+pub mod hadamard;
+pub mod khatri_rao;
+pub mod lane;
+pub mod merge;
+pub mod row_major;
 pub mod scalar;
 pub mod spadd;
 pub mod spmm;
-pub(crate) mod spsolve;
+pub mod spsolve;
 pub mod spsub;
@@ -0,0 +1,486 @@
+//! Module holding sparse-dense products for dense operands stored in row-major order.
+//!
+//! nalgebra's dense matrices are stored in column-major order, so data coming from row-major
+//! sources (e.g. C or Python libraries, images, or interleaved per-node data) would normally have
+//! to be transposed into a fresh column-major matrix before every product. The products in this
+//! module instead take borrowed row-major views ([`RowMajor`] and [`RowMajorMut`]) directly, and
+//! order their loops so that the dense operands are always traversed along their rows, i.e. along
+//! contiguous memory.
+//!
+//! A row-major view can also have a row stride larger than its number of columns, which makes it
+//! possible to operate on a block of columns of a larger row-major array without copying it. A
+//! sparse-matrix-vector product is the special case of a dense operand with a single column.
+
+use crate::{
+    cs::{CompressedColumnStorage, CompressedRowStorage, CsMatrix},
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::{ClosedAdd, ClosedMul, Scalar};
+use num_traits::Zero;
+use std::borrow::Borrow;
+
+/// A borrowed dense matrix stored in row-major order.
+///
+/// Element `(i, j)` is stored at `data[i * row_stride + j]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowMajor<'a, T> {
+    data: &'a [T],
+    nrows: usize,
+    ncols: usize,
+    row_stride: usize,
+}
+
+/// A mutably borrowed dense matrix stored in row-major order.
+///
+/// Element `(i, j)` is stored at `data[i * row_stride + j]`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RowMajorMut<'a, T> {
+    data: &'a mut [T],
+    nrows: usize,
+    ncols: usize,
+    row_stride: usize,
+}
+
+/// Panics if `len` elements are not enough to hold a row-major matrix of the given layout.
+fn check_layout(len: usize, nrows: usize, ncols: usize, row_stride: usize) {
+    assert!(
+        row_stride >= ncols,
+        "The row stride ({}) must be at least the number of columns ({}).",
+        row_stride,
+        ncols
+    );
+
+    let required = match nrows {
+        0 => 0,
+        _ => (nrows - 1) * row_stride + ncols,
+    };
+
+    assert!(
+        len >= required,
+        "A row-major matrix of shape ({}, {}) with a row stride of {} needs at least {} elements, \
+         but only {} were given.",
+        nrows,
+        ncols,
+        row_stride,
+        required,
+        len
+    );
+}
+
+impl<'a, T> RowMajor<'a, T> {
+    /// Creates a view of `data` as a contiguous row-major matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` has fewer than `nrows * ncols` elements.
+    #[must_use]
+    pub fn from_slice(data: &'a [T], nrows: usize, ncols: usize) -> Self {
+        Self::from_slice_with_stride(data, nrows, ncols, ncols)
+    }
+
+    /// Creates a view of `data` as a row-major matrix whose consecutive rows start `row_stride`
+    /// elements apart.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row_stride` is smaller than `ncols`, or if `data` is too short to hold every
+    /// row.
+    #[must_use]
+    pub fn from_slice_with_stride(
+        data: &'a [T],
+        nrows: usize,
+        ncols: usize,
+        row_stride: usize,
+    ) -> Self {
+        check_layout(data.len(), nrows, ncols, row_stride);
+
+        Self {
+            data,
+            nrows,
+            ncols,
+            row_stride,
+        }
+    }
+
+    /// The shape of the matrix, as `(nrows, ncols)`.
+    #[must_use]
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// Borrows row `i` of the matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    #[must_use]
+    pub fn row(&self, i: usize) -> &'a [T] {
+        assert!(i < self.nrows, "Row index out of bounds.");
+
+        let start = i * self.row_stride;
+        &self.data[start..start + self.ncols]
+    }
+}
+
+impl<'a, T> RowMajorMut<'a, T> {
+    /// Creates a mutable view of `data` as a contiguous row-major matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` has fewer than `nrows * ncols` elements.
+    #[must_use]
+    pub fn from_slice(data: &'a mut [T], nrows: usize, ncols: usize) -> Self {
+        Self::from_slice_with_stride(data, nrows, ncols, ncols)
+    }
+
+    /// Creates a mutable view of `data` as a row-major matrix whose consecutive rows start
+    /// `row_stride` elements apart.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row_stride` is smaller than `ncols`, or if `data` is too short to hold every
+    /// row.
+    #[must_use]
+    pub fn from_slice_with_stride(
+        data: &'a mut [T],
+        nrows: usize,
+        ncols: usize,
+        row_stride: usize,
+    ) -> Self {
+        check_layout(data.len(), nrows, ncols, row_stride);
+
+        Self {
+            data,
+            nrows,
+            ncols,
+            row_stride,
+        }
+    }
+
+    /// The shape of the matrix, as `(nrows, ncols)`.
+    #[must_use]
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// Mutably borrows row `i` of the matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn row_mut(&mut self, i: usize) -> &mut [T] {
+        assert!(i < self.nrows, "Row index out of bounds.");
+
+        let start = i * self.row_stride;
+        &mut self.data[start..start + self.ncols]
+    }
+
+    /// Reborrows the mutable view as an immutable view.
+    #[must_use]
+    pub fn as_row_major(&self) -> RowMajor<'_, T> {
+        RowMajor {
+            data: self.data,
+            nrows: self.nrows,
+            ncols: self.ncols,
+            row_stride: self.row_stride,
+        }
+    }
+}
+
+/// Checks that `lhs_shape * rhs_shape` is a valid product whose result fits in `output_shape`.
+fn check_product_shapes(
+    lhs_shape: (usize, usize),
+    rhs_shape: (usize, usize),
+    output_shape: (usize, usize),
+) -> Result<(), OperationError> {
+    if lhs_shape.1 != rhs_shape.0 || output_shape != (lhs_shape.0, rhs_shape.1) {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!(
+                "Cannot multiply a matrix of shape {:?} by a matrix of shape {:?} into a matrix \
+                 of shape {:?}.",
+                lhs_shape, rhs_shape, output_shape
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Computes `output_row += scale * row` elementwise.
+fn axpy_row<T>(output_row: &mut [T], scale: &T, row: &[T])
+where
+    T: Scalar + ClosedAdd + ClosedMul,
+{
+    for (output, value) in output_row.iter_mut().zip(row) {
+        *output += scale.clone() * value.clone();
+    }
+}
+
+/// Sets every element of `output` to zero.
+fn zero_fill<T>(output: &mut RowMajorMut<'_, T>)
+where
+    T: Scalar + Zero,
+{
+    for i in 0..output.nrows {
+        output.row_mut(i).fill(T::zero());
+    }
+}
+
+/// Computes `C = A B` for a CSR matrix `A` and row-major dense matrices `B` and `C`.
+///
+/// Every row of `C` is accumulated from the rows of `B` selected by the corresponding row of `A`,
+/// so both dense operands are only ever traversed along their rows.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if the number of
+/// columns of `A` does not match the number of rows of `B`, or if `C` does not have the shape of
+/// the product. `output` is left untouched in that case.
+pub fn spmm_csr_row_major<T, MO, MI, D>(
+    csr: CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    dense: RowMajor<'_, T>,
+    mut output: RowMajorMut<'_, T>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + ClosedAdd + ClosedMul,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    check_product_shapes(csr.shape(), dense.shape(), output.shape())?;
+    let _span = span!("spmm_csr_row_major", nnz = csr.nnz(), ncols = dense.ncols);
+
+    for (i, lane) in csr.iter().enumerate() {
+        let output_row = output.row_mut(i);
+        output_row.fill(T::zero());
+
+        for (j, a_ij) in lane {
+            axpy_row(output_row, a_ij, dense.row(j));
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes `C = A B` for a CSC matrix `A` and row-major dense matrices `B` and `C`.
+///
+/// Every column of `A` scatters the corresponding row of `B` into the rows of `C`, so both dense
+/// operands are only ever traversed along their rows.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if the number of
+/// columns of `A` does not match the number of rows of `B`, or if `C` does not have the shape of
+/// the product. `output` is left untouched in that case.
+pub fn spmm_csc_row_major<T, MO, MI, D>(
+    csc: CsMatrix<T, MO, MI, D, CompressedColumnStorage>,
+    dense: RowMajor<'_, T>,
+    mut output: RowMajorMut<'_, T>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + ClosedAdd + ClosedMul,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    check_product_shapes(csc.shape(), dense.shape(), output.shape())?;
+    let _span = span!("spmm_csc_row_major", nnz = csc.nnz(), ncols = dense.ncols);
+
+    zero_fill(&mut output);
+
+    for (j, lane) in csc.iter().enumerate() {
+        let dense_row = dense.row(j);
+
+        for (i, a_ij) in lane {
+            axpy_row(output.row_mut(i), a_ij, dense_row);
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes `C = B A` for row-major dense matrices `B` and `C`, and a CSR matrix `A`.
+///
+/// Every row of `C` is accumulated from the rows of `A`, scaled by the corresponding row of `B`,
+/// which is read contiguously.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if the number of
+/// columns of `B` does not match the number of rows of `A`, or if `C` does not have the shape of
+/// the product. `output` is left untouched in that case.
+pub fn spmm_row_major_csr<T, MO, MI, D>(
+    dense: RowMajor<'_, T>,
+    csr: CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    mut output: RowMajorMut<'_, T>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + ClosedAdd + ClosedMul,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    check_product_shapes(dense.shape(), csr.shape(), output.shape())?;
+    let _span = span!("spmm_row_major_csr", nnz = csr.nnz(), nrows = dense.nrows);
+
+    for i in 0..dense.nrows {
+        let output_row = output.row_mut(i);
+        output_row.fill(T::zero());
+
+        for (b_ij, lane) in dense.row(i).iter().zip(csr.iter()) {
+            for (k, a_jk) in lane {
+                output_row[k] += b_ij.clone() * a_jk.clone();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes `C = B A` for row-major dense matrices `B` and `C`, and a CSC matrix `A`.
+///
+/// Every entry of `C` is the dot product of a row of `B` with a column of `A`.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if the number of
+/// columns of `B` does not match the number of rows of `A`, or if `C` does not have the shape of
+/// the product. `output` is left untouched in that case.
+pub fn spmm_row_major_csc<T, MO, MI, D>(
+    dense: RowMajor<'_, T>,
+    csc: CsMatrix<T, MO, MI, D, CompressedColumnStorage>,
+    mut output: RowMajorMut<'_, T>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + ClosedAdd + ClosedMul,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    check_product_shapes(dense.shape(), csc.shape(), output.shape())?;
+    let _span = span!("spmm_row_major_csc", nnz = csc.nnz(), nrows = dense.nrows);
+
+    for i in 0..dense.nrows {
+        let dense_row = dense.row(i);
+        let output_row = output.row_mut(i);
+
+        for (c_ik, lane) in output_row.iter_mut().zip(csc.iter()) {
+            let mut sum = T::zero();
+
+            for (j, a_jk) in lane {
+                sum += dense_row[j].clone() * a_jk.clone();
+            }
+
+            *c_ik = sum;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cs::{CscMatrix, CsrMatrix},
+        proptest::*,
+    };
+    use nalgebra::DMatrix;
+    use proptest::prelude::*;
+
+    /// A sparse matrix, together with a dense matrix stored in row-major order with some padding
+    /// after every row, such that their product is defined.
+    fn product_with_padded_row_major(
+    ) -> impl Strategy<Value = (CsrMatrix<i32>, DMatrix<i32>, usize)> {
+        (csr_strategy(), PROPTEST_MATRIX_DIM, 0usize..=2).prop_flat_map(|(a, ncols, padding)| {
+            let nrows = a.ncols();
+            let dense = proptest::collection::vec(PROPTEST_I32_VALUE_STRATEGY, nrows * ncols)
+                .prop_map(move |values| DMatrix::from_row_slice(nrows, ncols, &values));
+
+            (Just(a), dense, Just(padding))
+        })
+    }
+
+    /// Lays out `dense` in row-major order, with `padding` unused elements after every row.
+    fn to_row_major(dense: &DMatrix<i32>, padding: usize) -> Vec<i32> {
+        let stride = dense.ncols() + padding;
+        let mut data = vec![-1; dense.nrows() * stride];
+
+        for i in 0..dense.nrows() {
+            for j in 0..dense.ncols() {
+                data[i * stride + j] = dense[(i, j)];
+            }
+        }
+
+        data
+    }
+
+    /// Reads back a contiguous row-major matrix.
+    fn from_row_major(data: &[i32], nrows: usize, ncols: usize) -> DMatrix<i32> {
+        DMatrix::from_row_slice(nrows, ncols, data)
+    }
+
+    #[test]
+    #[should_panic]
+    fn views_reject_short_slices() {
+        let data = [1, 2, 3, 4, 5];
+        let _ = RowMajor::from_slice_with_stride(&data, 2, 2, 4);
+    }
+
+    #[test]
+    fn mismatched_shapes_leave_output_untouched() {
+        let csr = CsrMatrix::<i32>::identity(2);
+        let dense = [1, 2, 3];
+        let mut output = [7; 4];
+
+        let err = spmm_csr_row_major(
+            csr.to_view(),
+            RowMajor::from_slice(&dense, 3, 1),
+            RowMajorMut::from_slice(&mut output, 2, 2),
+        )
+        .unwrap_err();
+
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+        assert_eq!(output, [7; 4]);
+    }
+
+    proptest! {
+        #[test]
+        fn sparse_times_row_major_agrees_with_dense((a, b, padding) in product_with_padded_row_major()) {
+            let expected = DMatrix::from(&a) * &b;
+            let (nrows, ncols) = expected.shape();
+            let b_data = to_row_major(&b, padding);
+            let b_view = RowMajor::from_slice_with_stride(&b_data, b.nrows(), b.ncols(), b.ncols() + padding);
+
+            let mut output = vec![-1; nrows * ncols];
+            spmm_csr_row_major(a.to_view(), b_view, RowMajorMut::from_slice(&mut output, nrows, ncols)).unwrap();
+            prop_assert_eq!(from_row_major(&output, nrows, ncols), expected.clone());
+
+            let mut output = vec![-1; nrows * ncols];
+            let a_csc = CscMatrix::from(a.clone());
+            spmm_csc_row_major(a_csc.to_view(), b_view, RowMajorMut::from_slice(&mut output, nrows, ncols)).unwrap();
+            prop_assert_eq!(from_row_major(&output, nrows, ncols), expected);
+        }
+
+        #[test]
+        fn row_major_times_sparse_agrees_with_dense((a, b, padding) in product_with_padded_row_major()) {
+            // Multiply from the other side: (B^T A^T) = (A B)^T
+            let a_t = CsrMatrix::from(&DMatrix::from(&a).transpose());
+            let b_t = b.transpose();
+            let expected = &b_t * DMatrix::from(&a_t);
+            let (nrows, ncols) = expected.shape();
+            let b_data = to_row_major(&b_t, padding);
+            let b_view = RowMajor::from_slice_with_stride(&b_data, b_t.nrows(), b_t.ncols(), b_t.ncols() + padding);
+
+            let mut output = vec![-1; nrows * ncols];
+            spmm_row_major_csr(b_view, a_t.to_view(), RowMajorMut::from_slice(&mut output, nrows, ncols)).unwrap();
+            prop_assert_eq!(from_row_major(&output, nrows, ncols), expected.clone());
+
+            let mut output = vec![-1; nrows * ncols];
+            let a_t_csc = CscMatrix::from(a_t.clone());
+            spmm_row_major_csc(b_view, a_t_csc.to_view(), RowMajorMut::from_slice(&mut output, nrows, ncols)).unwrap();
+            prop_assert_eq!(from_row_major(&output, nrows, ncols), expected);
+        }
+    }
+}
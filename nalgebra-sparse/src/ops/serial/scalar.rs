@@ -4,7 +4,7 @@ use crate::cs::{Compression, CsMatrix};
 use nalgebra::Scalar;
 use std::{
     borrow::Borrow,
-    ops::{Div, Mul},
+    ops::{Div, Mul, Neg},
 };
 
 /// Scalar product for sparse matrices.
@@ -66,3 +66,26 @@ where
 
     unsafe { CsMatrix::from_parts_unchecked(rows, columns, offsets, indices, data) }
 }
+
+/// Negation for sparse matrices.
+///
+/// Every stored value is negated, including any explicit zeros, so the sparsity pattern of the
+/// output is identical to that of the input.
+pub fn sp_cs_neg<T, MO, MI, D, C>(
+    cs: CsMatrix<T, MO, MI, D, C>,
+) -> CsMatrix<<T as Neg>::Output, MO, MI, Vec<<T as Neg>::Output>, C>
+where
+    T: Scalar + Neg,
+    <T as Neg>::Output: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let (rows, columns) = cs.shape();
+    let (offsets, indices, data) = cs.disassemble();
+
+    let data = data.borrow().iter().map(|x| -x.clone()).collect();
+
+    unsafe { CsMatrix::from_parts_unchecked(rows, columns, offsets, indices, data) }
+}
@@ -14,14 +14,26 @@
 //!
 //! One should prefer to pose their problems as a combination of CSX <-> CSX additions, or
 //! dense-sparse additions.
+//!
+//! The sparse-sparse functions first count the entries of the result, so that its buffers are
+//! allocated with exactly the required size. Each of them also has a `_with_buffers` variant
+//! (e.g. [`spadd_csr_csr_with_buffers`]) which writes the result into caller-provided buffers
+//! instead, so that repeated additions can reuse the same allocations.
 
 use crate::{
-    convert::utils::CountToOffsetIter,
-    cs::{CompressedColumnStorage, CompressedRowStorage, CsMatrix, CscMatrix, CsrMatrix},
+    cs::{
+        CompressedColumnStorage, CompressedRowStorage, Compression, CsMatrix, CscMatrix, CsrMatrix,
+    },
     error::{OperationError, OperationErrorKind},
+    ops::serial::merge::{
+        collect_into_buffers, combine_identical_patterns, merge_lanes, union_lane_counts, Merged,
+    },
 };
 use nalgebra::{Dim, Matrix, RawStorage, RawStorageMut, Scalar};
-use std::{borrow::Borrow, cmp::Ordering, ops::Add};
+use std::{
+    borrow::{Borrow, BorrowMut},
+    ops::{Add, AddAssign},
+};
 
 /// Sparse-sparse matrix addition.
 ///
@@ -36,6 +48,36 @@ pub fn spadd_csr_csc<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
     csr: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
     csc: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
 ) -> Result<CsrMatrix<<T1 as Add<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Into<<T1 as Add<T2>>::Output> + Add<T2>,
+    T2: Scalar + Into<<T1 as Add<T2>>::Output>,
+    <T1 as Add<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    spadd_csr_csc_with_buffers(csr, csc, Default::default())
+}
+
+/// Sparse-sparse matrix addition into caller-provided buffers.
+///
+/// This is the same as [`spadd_csr_csc`], except that the offsets, indices and data of the
+/// result are written into `buffers`, e.g. the [`disassemble`](CsMatrix::disassemble)d parts of a
+/// matrix that is no longer needed. The buffers are cleared, but their allocations are reused, so
+/// repeatedly computing additions of the same size does not allocate.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn spadd_csr_csc_with_buffers<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    csr: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
+    csc: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+    buffers: (Vec<usize>, Vec<usize>, Vec<<T1 as Add<T2>>::Output>),
+) -> Result<CsrMatrix<<T1 as Add<T2>>::Output>, OperationError>
 where
     T1: Scalar + Into<<T1 as Add<T2>>::Output> + Add<T2>,
     T2: Scalar + Into<<T1 as Add<T2>>::Output>,
@@ -57,34 +99,27 @@ where
         ));
     }
 
-    let mut left_iter = csr.triplet_iter();
-    let mut right_iter = csc
+    let counts = union_lane_counts(
+        lrows,
+        csr.triplet_iter().map(|(i, j, _)| (i, j)),
+        csc.minor_lane_iter()
+            .enumerate()
+            .flat_map(|(i, lane)| lane.map(move |(j, _)| (i, j))),
+    );
+
+    let left_iter = csr.triplet_iter();
+    let right_iter = csc
         .minor_lane_iter()
         .enumerate()
         .flat_map(|(i, lane)| lane.map(move |(j, value)| (i, j, value)));
 
-    let left_val = left_iter.next();
-    let right_val = right_iter.next();
-
-    let added_triplets = TripletAdditionIter {
-        left_val,
-        right_val,
-        left_iter,
-        right_iter,
-    };
-
-    let max_nnz = csr.nnz() + csc.nnz();
-    let mut counts = vec![0; lrows];
-    let mut indices = Vec::with_capacity(max_nnz);
-    let mut data = Vec::with_capacity(max_nnz);
-
-    for (i, j, v) in added_triplets {
-        counts[i] += 1;
-        indices.push(j);
-        data.push(v);
-    }
+    let added_triplets = merge_lanes(left_iter, right_iter, |entry| match entry {
+        Merged::Left(l) => l.clone().into(),
+        Merged::Right(r) => r.clone().into(),
+        Merged::Both(l, r) => l.clone() + r.clone(),
+    });
 
-    let offsets = CountToOffsetIter::new(counts).collect();
+    let (offsets, indices, data) = collect_into_buffers(counts, added_triplets, buffers);
 
     Ok(unsafe { CsMatrix::from_parts_unchecked(lrows, lcols, offsets, indices, data) })
 }
@@ -113,7 +148,35 @@ where
     D1: Borrow<[T1]>,
     D2: Borrow<[T2]>,
 {
-    spadd_csr_csc(csr, csc)
+    spadd_csc_csr_with_buffers(csc, csr, Default::default())
+}
+
+/// Sparse-sparse matrix addition into caller-provided buffers.
+///
+/// This is the same as [`spadd_csc_csr`], except that the offsets, indices and data of the
+/// result are written into `buffers`. See [`spadd_csr_csc_with_buffers`].
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn spadd_csc_csr_with_buffers<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    csc: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
+    csr: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+    buffers: (Vec<usize>, Vec<usize>, Vec<<T2 as Add<T1>>::Output>),
+) -> Result<CsrMatrix<<T2 as Add<T1>>::Output>, OperationError>
+where
+    T1: Scalar + Into<<T2 as Add<T1>>::Output>,
+    T2: Scalar + Into<<T2 as Add<T1>>::Output> + Add<T1>,
+    <T2 as Add<T1>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    spadd_csr_csc_with_buffers(csr, csc, buffers)
 }
 
 /// Sparse-sparse matrix addition.
@@ -128,6 +191,36 @@ pub fn spadd_csc_csc<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
     lhs: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
     rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
 ) -> Result<CscMatrix<<T1 as Add<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Into<<T1 as Add<T2>>::Output> + Add<T2>,
+    T2: Scalar + Into<<T1 as Add<T2>>::Output>,
+    <T1 as Add<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    spadd_csc_csc_with_buffers(lhs, rhs, Default::default())
+}
+
+/// Sparse-sparse matrix addition into caller-provided buffers.
+///
+/// This is the same as [`spadd_csc_csc`], except that the offsets, indices and data of the
+/// result are written into `buffers`, e.g. the [`disassemble`](CsMatrix::disassemble)d parts of a
+/// matrix that is no longer needed. The buffers are cleared, but their allocations are reused, so
+/// repeatedly computing additions of the same size does not allocate.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn spadd_csc_csc_with_buffers<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+    buffers: (Vec<usize>, Vec<usize>, Vec<<T1 as Add<T2>>::Output>),
+) -> Result<CscMatrix<<T1 as Add<T2>>::Output>, OperationError>
 where
     T1: Scalar + Into<<T1 as Add<T2>>::Output> + Add<T2>,
     T2: Scalar + Into<<T1 as Add<T2>>::Output>,
@@ -149,31 +242,22 @@ where
         ));
     }
 
-    let mut left_iter = lhs.triplet_iter();
-    let mut right_iter = rhs.triplet_iter();
-
-    let left_val = left_iter.next();
-    let right_val = right_iter.next();
+    let counts = union_lane_counts(
+        lcols,
+        lhs.triplet_iter().map(|(i, j, _)| (i, j)),
+        rhs.triplet_iter().map(|(i, j, _)| (i, j)),
+    );
 
-    let added_triplets = TripletAdditionIter {
-        left_val,
-        right_val,
-        left_iter,
-        right_iter,
-    };
+    let left_iter = lhs.triplet_iter();
+    let right_iter = rhs.triplet_iter();
 
-    let max_nnz = lhs.nnz() + rhs.nnz();
-    let mut counts = vec![0; lcols];
-    let mut indices = Vec::with_capacity(max_nnz);
-    let mut data = Vec::with_capacity(max_nnz);
-
-    for (i, j, v) in added_triplets {
-        counts[i] += 1;
-        indices.push(j);
-        data.push(v);
-    }
+    let added_triplets = merge_lanes(left_iter, right_iter, |entry| match entry {
+        Merged::Left(l) => l.clone().into(),
+        Merged::Right(r) => r.clone().into(),
+        Merged::Both(l, r) => l.clone() + r.clone(),
+    });
 
-    let offsets = CountToOffsetIter::new(counts).collect();
+    let (offsets, indices, data) = collect_into_buffers(counts, added_triplets, buffers);
 
     Ok(unsafe { CsMatrix::from_parts_unchecked(lrows, lcols, offsets, indices, data) })
 }
@@ -201,7 +285,65 @@ where
     D1: Borrow<[T1]>,
     D2: Borrow<[T2]>,
 {
-    Ok(spadd_csc_csc(lhs.transpose(), rhs.transpose())?.transpose_owned())
+    spadd_csr_csr_with_buffers(lhs, rhs, Default::default())
+}
+
+/// Sparse-sparse matrix addition into caller-provided buffers.
+///
+/// This is the same as [`spadd_csr_csr`], except that the offsets, indices and data of the
+/// result are written into `buffers`. See [`spadd_csr_csc_with_buffers`].
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn spadd_csr_csr_with_buffers<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+    buffers: (Vec<usize>, Vec<usize>, Vec<<T1 as Add<T2>>::Output>),
+) -> Result<CsrMatrix<<T1 as Add<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Into<<T1 as Add<T2>>::Output> + Add<T2>,
+    T2: Scalar + Into<<T1 as Add<T2>>::Output>,
+    <T1 as Add<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    Ok(spadd_csc_csc_with_buffers(lhs.transpose(), rhs.transpose(), buffers)?.transpose_owned())
+}
+
+/// In-place sparse-sparse matrix addition, for matrices with identical sparsity patterns.
+///
+/// This computes `lhs = lhs + rhs` by updating the values of `lhs` directly, without allocating
+/// a new matrix. This is much cheaper than [`spadd_csr_csr`] and friends when the same pattern is
+/// reused over and over, e.g. when summing many contributions to a stiffness matrix whose pattern
+/// was assembled up front. Both matrices must have the same compression.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape
+/// and sparsity pattern. `lhs` is left unchanged in that case.
+pub fn spadd_assign<T1, T2, MO1, MO2, MI1, MI2, D1, D2, C>(
+    lhs: &mut CsMatrix<T1, MO1, MI1, D1, C>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, C>,
+) -> Result<(), OperationError>
+where
+    T1: Scalar + AddAssign<T2>,
+    T2: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: BorrowMut<[T1]>,
+    D2: Borrow<[T2]>,
+    C: Compression,
+{
+    combine_identical_patterns(lhs, rhs, |l, r| *l += r.clone())
 }
 
 /// Dense-sparse matrix addition.
@@ -338,106 +480,225 @@ where
     spadd_dense_csr(dense, csr)
 }
 
-/// Helper type which defines an iterator that takes two triplet iterators (in the same major
-/// ordering) and outputs a single triplet iterator of `(major, minor, owned_value)`. This iterator
-/// combines duplicate entries via addition i.e. if we have two entries with the same major and
-/// minor indices we add the two values together.
+/// Sparse matrix addition for any combination of storage formats.
 ///
-/// This iterator combinator is not terribly well bound, which is why it is private! First and
-/// foremost, `left_val` and `right_val` need to not be `None` immediately, or this will always
-/// return `None`. Secondly, this iterator is difficult to use directly outside of the above
-/// `spadd_csx_csx` functions because it relies on the fact that both iterators are outputting
-/// triplets in the same major ordering. This means that if you want to add a CSC and CSR matrix.to_view(),
-/// you cannot do the following:
+/// This trait is implemented for every pair of operands supported by the `spadd_x_y` functions in
+/// this module, so that [`spadd`] can pick the right one from the types of its arguments. The
+/// output format is the same as that of the function it dispatches to.
+pub trait SpAdd<Rhs> {
+    /// The type of the result of the addition.
+    type Output;
+
+    /// Computes `self + rhs`.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same way as the `spadd_x_y` function for the formats of `self` and `rhs`.
+    fn spadd(self, rhs: Rhs) -> Result<Self::Output, OperationError>;
+}
+
+/// Computes `lhs + rhs` for any supported combination of CSR, CSC and dense operands.
 ///
-/// ```ignore
-/// let mut left_iter = csr.triplet_iter();
-/// let mut right_iter = csc.triplet_iter();
+/// This dispatches on the types of `lhs` and `rhs` to the matching `spadd_x_y` function in this
+/// module, e.g. [`spadd_csr_csc`] for a CSR and a CSC matrix.
 ///
-/// let left_val = left_iter.next();
-/// let right_val = right_iter.next();
-/// ```
+/// # Errors
 ///
-/// Because the major and minor ordering of those two are reversed. Instead, you'll want to do
-/// something like the following:
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
 ///
-/// ```ignore
-/// let mut left_iter = csr.triplet_iter();
-/// let mut right_iter = csc
-///     .minor_lane_iter()
-///     .enumerate()
-///     .flat_map(|(i, lane)| lane.map(move |(j, value)| (i, j, value)));
+/// # Example
 ///
-/// let left_val = left_iter.next();
-/// let right_val = right_iter.next();
-/// ```
+/// ```rust
+/// use nalgebra_sparse::{
+///     cs::{CscMatrix, CsrMatrix},
+///     ops::serial::spadd::spadd,
+/// };
 ///
-/// The minor lane iterator is necessary so that you can both traverse the two matrices in
-/// the equivalent order (which is needed for the boolean logic below to work) as well as compare
-/// `i` to `i` and `j` to `j` between both left and right values.
+/// let csr = CsrMatrix::<i32>::identity(3);
+/// let csc = CscMatrix::<i32>::identity(3);
 ///
-/// Of course, because addition is commutative, one could just reverse the order of the arguments
-/// and do the same trick; however, it means that the final result will be a column-major matrix
-/// instead of a row-major matrix.
-struct TripletAdditionIter<'a, TL, TR, IL, IR>
+/// let result = spadd(csr.to_view(), csc.to_view()).unwrap();
+/// assert_eq!(result.shape(), (3, 3));
+/// ```
+pub fn spadd<L, R>(lhs: L, rhs: R) -> Result<L::Output, OperationError>
+where
+    L: SpAdd<R>,
+{
+    lhs.spadd(rhs)
+}
+
+impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2> SpAdd<CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>>
+    for CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>
+where
+    T1: Scalar + Into<<T1 as Add<T2>>::Output> + Add<T2>,
+    T2: Scalar + Into<<T1 as Add<T2>>::Output>,
+    <T1 as Add<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    type Output = CsrMatrix<<T1 as Add<T2>>::Output>;
+
+    fn spadd(
+        self,
+        rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spadd_csr_csc(self, rhs)
+    }
+}
+
+impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2> SpAdd<CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>>
+    for CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>
+where
+    T1: Scalar + Into<<T2 as Add<T1>>::Output>,
+    T2: Scalar + Into<<T2 as Add<T1>>::Output> + Add<T1>,
+    <T2 as Add<T1>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    type Output = CsrMatrix<<T2 as Add<T1>>::Output>;
+
+    fn spadd(
+        self,
+        rhs: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spadd_csc_csr(self, rhs)
+    }
+}
+
+impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2> SpAdd<CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>>
+    for CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>
+where
+    T1: Scalar + Into<<T1 as Add<T2>>::Output> + Add<T2>,
+    T2: Scalar + Into<<T1 as Add<T2>>::Output>,
+    <T1 as Add<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    type Output = CscMatrix<<T1 as Add<T2>>::Output>;
+
+    fn spadd(
+        self,
+        rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spadd_csc_csc(self, rhs)
+    }
+}
+
+impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2> SpAdd<CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>>
+    for CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>
 where
-    TL: Clone + Into<<TL as Add<TR>>::Output> + Add<TR>,
-    TR: Clone + Into<<TL as Add<TR>>::Output>,
-    IL: Iterator<Item = (usize, usize, &'a TL)>,
-    IR: Iterator<Item = (usize, usize, &'a TR)>,
+    T1: Scalar + Into<<T1 as Add<T2>>::Output> + Add<T2>,
+    T2: Scalar + Into<<T1 as Add<T2>>::Output>,
+    <T1 as Add<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
 {
-    left_val: Option<(usize, usize, &'a TL)>,
-    right_val: Option<(usize, usize, &'a TR)>,
-    left_iter: IL,
-    right_iter: IR,
+    type Output = CsrMatrix<<T1 as Add<T2>>::Output>;
+
+    fn spadd(
+        self,
+        rhs: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spadd_csr_csr(self, rhs)
+    }
 }
 
-impl<'a, TL, TR, IL, IR> Iterator for TripletAdditionIter<'a, TL, TR, IL, IR>
+impl<T1, T2, R, C, S, MO, MI, D> SpAdd<CsMatrix<T2, MO, MI, D, CompressedColumnStorage>>
+    for Matrix<T1, R, C, S>
 where
-    TL: Clone + Into<<TL as Add<TR>>::Output> + Add<TR>,
-    TR: Clone + Into<<TL as Add<TR>>::Output>,
-    IL: Iterator<Item = (usize, usize, &'a TL)>,
-    IR: Iterator<Item = (usize, usize, &'a TR)>,
+    T1: Scalar + Add<T2, Output = T1>,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T1, R, C> + RawStorageMut<T1, R, C>,
+    T2: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T2]>,
 {
-    type Item = (usize, usize, <TL as Add<TR>>::Output);
-
-    fn next(&mut self) -> Option<Self::Item> {
-        match (self.left_val, self.right_val) {
-            // Neither left nor right are exhausted
-            (Some((il, jl, vl)), Some((ir, jr, vr))) => match (il.cmp(&ir), jl.cmp(&jr)) {
-                (Ordering::Less, _) | (Ordering::Equal, Ordering::Less) => {
-                    self.left_val = self.left_iter.next();
-                    Some((il, jl, vl.clone().into()))
-                }
-
-                (Ordering::Greater, _) | (Ordering::Equal, Ordering::Greater) => {
-                    self.right_val = self.right_iter.next();
-                    Some((ir, jr, vr.clone().into()))
-                }
-
-                (Ordering::Equal, Ordering::Equal) => {
-                    self.left_val = self.left_iter.next();
-                    self.right_val = self.right_iter.next();
-
-                    Some((il, jl, vl.clone() + vr.clone()))
-                }
-            },
-
-            // Only right is exhausted
-            (Some((i, j, v)), None) => {
-                self.left_val = self.left_iter.next();
-                Some((i, j, v.clone().into()))
-            }
-
-            // Only left is exhausted
-            (None, Some((i, j, v))) => {
-                self.right_val = self.right_iter.next();
-                Some((i, j, v.clone().into()))
-            }
-
-            // Both are exhausted
-            (None, None) => None,
-        }
+    type Output = Matrix<T1, R, C, S>;
+
+    fn spadd(
+        self,
+        rhs: CsMatrix<T2, MO, MI, D, CompressedColumnStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spadd_dense_csc(self, rhs)
+    }
+}
+
+impl<T1, T2, R, C, S, MO, MI, D> SpAdd<Matrix<T2, R, C, S>>
+    for CsMatrix<T1, MO, MI, D, CompressedColumnStorage>
+where
+    T2: Scalar + Add<T1, Output = T2>,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T2, R, C> + RawStorageMut<T2, R, C>,
+    T1: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+{
+    type Output = Matrix<T2, R, C, S>;
+
+    fn spadd(self, rhs: Matrix<T2, R, C, S>) -> Result<Self::Output, OperationError> {
+        spadd_csc_dense(self, rhs)
+    }
+}
+
+impl<T1, T2, R, C, S, MO, MI, D> SpAdd<CsMatrix<T2, MO, MI, D, CompressedRowStorage>>
+    for Matrix<T1, R, C, S>
+where
+    T1: Scalar + Add<T2, Output = T1>,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T1, R, C> + RawStorageMut<T1, R, C>,
+    T2: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T2]>,
+{
+    type Output = Matrix<T1, R, C, S>;
+
+    fn spadd(
+        self,
+        rhs: CsMatrix<T2, MO, MI, D, CompressedRowStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spadd_dense_csr(self, rhs)
+    }
+}
+
+impl<T1, T2, R, C, S, MO, MI, D> SpAdd<Matrix<T2, R, C, S>>
+    for CsMatrix<T1, MO, MI, D, CompressedRowStorage>
+where
+    T2: Scalar + Add<T1, Output = T2>,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T2, R, C> + RawStorageMut<T2, R, C>,
+    T1: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+{
+    type Output = Matrix<T2, R, C, S>;
+
+    fn spadd(self, rhs: Matrix<T2, R, C, S>) -> Result<Self::Output, OperationError> {
+        spadd_csr_dense(self, rhs)
     }
 }
 
@@ -814,7 +1075,76 @@ mod tests {
         assert_matrix_eq!(sum, dense_sum);
     }
 
+    #[test]
+    fn spadd_csr_csr_with_buffers_allocates_exactly() {
+        let a = CsrMatrix::<i32>::identity(4);
+        let b = CsrMatrix::<i32>::identity(4);
+
+        let sum = spadd_csr_csr_with_buffers(a.to_view(), b.to_view(), Default::default()).unwrap();
+        assert_matrix_eq!(sum, DMatrix::<i32>::identity(4, 4) * 2);
+
+        let (_, indices, data) = sum.disassemble();
+        assert_eq!(indices.capacity(), 4);
+        assert_eq!(data.capacity(), 4);
+    }
+
+    #[test]
+    fn spadd_assign_accumulates_in_place() {
+        let a = CsrMatrix::try_from_parts(
+            3,
+            3,
+            vec![0, 2, 3],
+            vec![0, 2, 1, 0, 2],
+            vec![1, 2, 3, 4, 5],
+        )
+        .unwrap();
+
+        let mut sum = a.zeros_like();
+        let data_ptr = sum.cs_data().2.as_ptr();
+
+        for _ in 0..3 {
+            sum += a.to_view();
+        }
+
+        assert_eq!(sum.cs_data().2.as_ptr(), data_ptr);
+        assert_matrix_eq!(sum, DMatrix::from(&a) * 3);
+    }
+
+    #[test]
+    fn spadd_assign_rejects_differing_patterns() {
+        let mut lhs = CscMatrix::<i32>::identity(3);
+        let rhs =
+            CscMatrix::try_from_parts(3, 3, vec![0, 1, 2], vec![1, 1, 2], vec![1, 1, 1]).unwrap();
+
+        let err = spadd_assign(&mut lhs, rhs).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+        assert_eq!(lhs.cs_data(), CscMatrix::<i32>::identity(3).cs_data());
+
+        let err = spadd_assign(&mut lhs, CscMatrix::<i32>::identity(4)).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
     proptest! {
+        #[test]
+        fn spadd_dispatches_on_formats(csr in csr_strategy()) {
+            let (nrows, ncols) = csr.shape();
+            let csc = CscMatrix::from(csr.clone());
+            let dense = DMatrix::from(&csr);
+            let expected = dense.clone() * 2;
+
+            prop_assert_matrix_eq!(spadd(csr.to_view(), csr.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spadd(csr.to_view(), csc.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spadd(csc.to_view(), csr.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spadd(csc.to_view(), csc.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spadd(dense.clone(), csr.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spadd(csr.to_view(), dense.clone()).unwrap(), expected);
+            prop_assert_matrix_eq!(spadd(dense.clone(), csc.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spadd(csc.to_view(), dense.clone()).unwrap(), expected);
+
+            let wrong_shape = CsrMatrix::<i32>::zeros(nrows + 1, ncols);
+            prop_assert!(spadd(csr.to_view(), wrong_shape).is_err());
+        }
+
         #[test]
         fn spadd_csr_csr_additive_identity(matrix in csr_strategy()) {
             let (nrows, ncols) = matrix.shape();
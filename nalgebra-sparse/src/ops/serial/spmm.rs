@@ -95,18 +95,31 @@
 //!
 //! Note that at any time one can convert between any of the supported formats. For more info, see
 //! the [`convert`](crate::convert) module.
+//!
+//! When a dense result is wanted instead (e.g. inside an iterative loop), the `_prealloc`
+//! variants such as [`spmm_csr_dense_prealloc`] accumulate `beta * C + alpha * op(A) * B` into a
+//! caller-provided dense matrix without allocating. Their `_with_options` counterparts (e.g.
+//! [`spmv_csr_with_options`]) can sum up the products along every row with pairwise or Kahan
+//! summation instead, for better accuracy on very long rows (see [`SpmmOptions`]).
 
 use crate::{
+    control::{Control, Stage},
     convert::utils::CountToOffsetIter,
-    cs::{CompressedColumnStorage, CompressedRowStorage, CsMatrix, CscMatrix, CsrMatrix},
+    cs::{
+        CompressedColumnStorage, CompressedRowStorage, Compression, CsMatrix, CscMatrix, CsrMatrix,
+    },
     error::{OperationError, OperationErrorKind},
+    interleaved::InterleavedCsMatrix,
+    ops::{Op, SpmmOptions, Summation},
+    quantized::QuantizedCsrMatrix,
+    runlength::RunLengthCsMatrix,
 };
-use nalgebra::{Dim, Matrix, RawStorage, Scalar};
+use nalgebra::{DVector, Dim, Matrix, RawStorage, RealField, Scalar, StorageMut, Vector};
 use num_traits::Zero;
 use std::{
     borrow::Borrow,
     cmp::Ordering,
-    ops::{Add, AddAssign, Mul},
+    ops::{Add, AddAssign, Mul, Sub},
 };
 
 /// The fundamental (fastest) sparse-matrix multiply.
@@ -126,6 +139,34 @@ pub fn spmm_csr_csc<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
     csr: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
     csc: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
 ) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    <T1 as Mul<T2>>::Output: Scalar + AddAssign + Zero,
+    T2: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    spmm_csr_csc_with_control(csr, csc, &Control::default())
+}
+
+/// Behaves like [`spmm_csr_csc`], but stops early if `control` requests it.
+///
+/// `control` is checked, and progress is reported, once per lane of the output matrix.
+///
+/// # Errors
+///
+/// In addition to the errors produced by [`spmm_csr_csc`], this function fails and produces an
+/// [`OperationError`] with kind [`OperationErrorKind::Cancelled`] if `control` requested a stop
+/// before the product was complete.
+pub fn spmm_csr_csc_with_control<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    csr: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
+    csc: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+    control: &Control<'_>,
+) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
 where
     T1: Scalar + Mul<T2>,
     <T1 as Mul<T2>>::Output: Scalar + AddAssign + Zero,
@@ -149,9 +190,18 @@ where
         ));
     }
 
+    let span = span!(
+        "spmm_csr_csc",
+        nrows = rows,
+        ncols = columns,
+        lhs_nnz = csr.nnz(),
+        rhs_nnz = csc.nnz(),
+        nnz = tracing::field::Empty,
+    );
+
     let nnz = csr.nnz().min(csc.nnz());
 
-    let triplets = csr.iter().enumerate().flat_map(move |(i, lane)| {
+    let triplets = csr.iter().enumerate().map(move |(i, lane)| {
         csc.iter()
             .enumerate()
             .filter_map(|(k, mut sublane)| {
@@ -196,12 +246,25 @@ where
     let mut indices = Vec::with_capacity(nnz);
     let mut data = Vec::with_capacity(nnz);
 
-    for (i, k, val) in triplets {
-        counts[i] += 1;
-        indices.push(k);
-        data.push(val);
+    for (lane, lane_triplets) in triplets.enumerate() {
+        if control.checkpoint(Stage::MatrixProduct, lane, rows) {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::Cancelled,
+                String::from("The matrix product was cancelled before completion."),
+            ));
+        }
+
+        for (i, k, val) in lane_triplets {
+            counts[i] += 1;
+            indices.push(k);
+            data.push(val);
+        }
     }
 
+    control.report(Stage::MatrixProduct, rows, rows);
+
+    record!(span, nnz = indices.len());
+
     let offsets = CountToOffsetIter::new(counts).collect();
 
     Ok(unsafe { CsMatrix::from_parts_unchecked(rows, columns, offsets, indices, data) })
@@ -227,6 +290,34 @@ pub fn spmm_csc_csr<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
     csc: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
     csr: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
 ) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    <T1 as Mul<T2>>::Output: Scalar + AddAssign + Zero,
+    T2: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    spmm_csc_csr_with_control(csc, csr, &Control::default())
+}
+
+/// Behaves like [`spmm_csc_csr`], but stops early if `control` requests it.
+///
+/// `control` is checked, and progress is reported, once per lane of the output matrix.
+///
+/// # Errors
+///
+/// In addition to the errors produced by [`spmm_csc_csr`], this function fails and produces an
+/// [`OperationError`] with kind [`OperationErrorKind::Cancelled`] if `control` requested a stop
+/// before the product was complete.
+pub fn spmm_csc_csr_with_control<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    csc: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
+    csr: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+    control: &Control<'_>,
+) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
 where
     T1: Scalar + Mul<T2>,
     <T1 as Mul<T2>>::Output: Scalar + AddAssign + Zero,
@@ -250,64 +341,83 @@ where
         ));
     }
 
+    let span = span!(
+        "spmm_csc_csr",
+        nrows = rows,
+        ncols = columns,
+        lhs_nnz = csc.nnz(),
+        rhs_nnz = csr.nnz(),
+        nnz = tracing::field::Empty,
+    );
+
     let nnz = csc.nnz().min(csr.nnz());
 
-    let triplets = csc
-        .minor_lane_iter()
-        .enumerate()
-        .flat_map(move |(i, lane)| {
-            let lane = lane.map(|(j, v)| (j, v.clone())).collect::<Vec<_>>();
-
-            csr.minor_lane_iter()
-                .enumerate()
-                .filter_map(|(k, mut sublane)| {
-                    let mut lane_iter = lane.iter();
-
-                    let mut lhs = lane_iter.next();
-                    let mut rhs = sublane.next();
-
-                    let mut total = <T1 as Mul<T2>>::Output::zero();
-                    let mut is_nonzero = false;
-
-                    while lhs.is_some() && rhs.is_some() {
-                        let (jl, vl) = lhs.unwrap();
-                        let (jr, vr) = rhs.unwrap();
-
-                        match jl.cmp(&jr) {
-                            Ordering::Less => {
-                                lhs = lane_iter.next();
-                            }
-                            Ordering::Equal => {
-                                total += vl.clone() * vr.clone();
-                                is_nonzero = true;
-                                lhs = lane_iter.next();
-                                rhs = sublane.next();
-                            }
-                            Ordering::Greater => {
-                                rhs = sublane.next();
-                            }
+    let triplets = csc.minor_lane_iter().enumerate().map(move |(i, lane)| {
+        let lane = lane.map(|(j, v)| (j, v.clone())).collect::<Vec<_>>();
+
+        csr.minor_lane_iter()
+            .enumerate()
+            .filter_map(|(k, mut sublane)| {
+                let mut lane_iter = lane.iter();
+
+                let mut lhs = lane_iter.next();
+                let mut rhs = sublane.next();
+
+                let mut total = <T1 as Mul<T2>>::Output::zero();
+                let mut is_nonzero = false;
+
+                while lhs.is_some() && rhs.is_some() {
+                    let (jl, vl) = lhs.unwrap();
+                    let (jr, vr) = rhs.unwrap();
+
+                    match jl.cmp(&jr) {
+                        Ordering::Less => {
+                            lhs = lane_iter.next();
+                        }
+                        Ordering::Equal => {
+                            total += vl.clone() * vr.clone();
+                            is_nonzero = true;
+                            lhs = lane_iter.next();
+                            rhs = sublane.next();
+                        }
+                        Ordering::Greater => {
+                            rhs = sublane.next();
                         }
                     }
+                }
 
-                    if is_nonzero {
-                        Some((i, k, total))
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
-        });
+                if is_nonzero {
+                    Some((i, k, total))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+    });
 
     let mut counts = vec![0usize; rows];
     let mut indices = Vec::with_capacity(nnz);
     let mut data = Vec::with_capacity(nnz);
 
-    for (i, k, val) in triplets {
-        counts[i] += 1;
-        indices.push(k);
-        data.push(val);
+    for (lane, lane_triplets) in triplets.enumerate() {
+        if control.checkpoint(Stage::MatrixProduct, lane, rows) {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::Cancelled,
+                String::from("The matrix product was cancelled before completion."),
+            ));
+        }
+
+        for (i, k, val) in lane_triplets {
+            counts[i] += 1;
+            indices.push(k);
+            data.push(val);
+        }
     }
 
+    control.report(Stage::MatrixProduct, rows, rows);
+
+    record!(span, nnz = indices.len());
+
     let offsets = CountToOffsetIter::new(counts).collect();
 
     Ok(unsafe { CsMatrix::from_parts_unchecked(rows, columns, offsets, indices, data) })
@@ -336,6 +446,34 @@ pub fn spmm_csc_csc<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
     lhs: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
     rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
 ) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Mul<T2>,
+    <T1 as Mul<T2>>::Output: Scalar + AddAssign + Zero,
+    T2: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    spmm_csc_csc_with_control(lhs, rhs, &Control::default())
+}
+
+/// Behaves like [`spmm_csc_csc`], but stops early if `control` requests it.
+///
+/// `control` is checked, and progress is reported, once per lane of the output matrix.
+///
+/// # Errors
+///
+/// In addition to the errors produced by [`spmm_csc_csc`], this function fails and produces an
+/// [`OperationError`] with kind [`OperationErrorKind::Cancelled`] if `control` requested a stop
+/// before the product was complete.
+pub fn spmm_csc_csc_with_control<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+    control: &Control<'_>,
+) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
 where
     T1: Scalar + Mul<T2>,
     <T1 as Mul<T2>>::Output: Scalar + AddAssign + Zero,
@@ -359,64 +497,83 @@ where
         ));
     }
 
+    let span = span!(
+        "spmm_csc_csc",
+        nrows = rows,
+        ncols = columns,
+        lhs_nnz = lhs.nnz(),
+        rhs_nnz = rhs.nnz(),
+        nnz = tracing::field::Empty,
+    );
+
     let nnz = lhs.nnz().min(rhs.nnz());
 
-    let triplets = lhs
-        .minor_lane_iter()
-        .enumerate()
-        .flat_map(move |(i, lane)| {
-            let lane = lane.map(|(j, v)| (j, v.clone())).collect::<Vec<_>>();
-
-            rhs.iter()
-                .enumerate()
-                .filter_map(|(k, mut sublane)| {
-                    let mut lane_iter = lane.iter();
-
-                    let mut lhs = lane_iter.next();
-                    let mut rhs = sublane.next();
-
-                    let mut total = <T1 as Mul<T2>>::Output::zero();
-                    let mut is_nonzero = false;
-
-                    while lhs.is_some() && rhs.is_some() {
-                        let (jl, vl) = lhs.unwrap();
-                        let (jr, vr) = rhs.unwrap();
-
-                        match jl.cmp(&jr) {
-                            Ordering::Less => {
-                                lhs = lane_iter.next();
-                            }
-                            Ordering::Equal => {
-                                total += vl.clone() * vr.clone();
-                                is_nonzero = true;
-                                lhs = lane_iter.next();
-                                rhs = sublane.next();
-                            }
-                            Ordering::Greater => {
-                                rhs = sublane.next();
-                            }
+    let triplets = lhs.minor_lane_iter().enumerate().map(move |(i, lane)| {
+        let lane = lane.map(|(j, v)| (j, v.clone())).collect::<Vec<_>>();
+
+        rhs.iter()
+            .enumerate()
+            .filter_map(|(k, mut sublane)| {
+                let mut lane_iter = lane.iter();
+
+                let mut lhs = lane_iter.next();
+                let mut rhs = sublane.next();
+
+                let mut total = <T1 as Mul<T2>>::Output::zero();
+                let mut is_nonzero = false;
+
+                while lhs.is_some() && rhs.is_some() {
+                    let (jl, vl) = lhs.unwrap();
+                    let (jr, vr) = rhs.unwrap();
+
+                    match jl.cmp(&jr) {
+                        Ordering::Less => {
+                            lhs = lane_iter.next();
+                        }
+                        Ordering::Equal => {
+                            total += vl.clone() * vr.clone();
+                            is_nonzero = true;
+                            lhs = lane_iter.next();
+                            rhs = sublane.next();
+                        }
+                        Ordering::Greater => {
+                            rhs = sublane.next();
                         }
                     }
+                }
 
-                    if is_nonzero {
-                        Some((i, k, total))
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
-        });
+                if is_nonzero {
+                    Some((i, k, total))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+    });
 
     let mut counts = vec![0usize; rows];
     let mut indices = Vec::with_capacity(nnz);
     let mut data = Vec::with_capacity(nnz);
 
-    for (i, k, val) in triplets {
-        counts[i] += 1;
-        indices.push(k);
-        data.push(val);
+    for (lane, lane_triplets) in triplets.enumerate() {
+        if control.checkpoint(Stage::MatrixProduct, lane, rows) {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::Cancelled,
+                String::from("The matrix product was cancelled before completion."),
+            ));
+        }
+
+        for (i, k, val) in lane_triplets {
+            counts[i] += 1;
+            indices.push(k);
+            data.push(val);
+        }
     }
 
+    control.report(Stage::MatrixProduct, rows, rows);
+
+    record!(span, nnz = indices.len());
+
     let offsets = CountToOffsetIter::new(counts).collect();
 
     Ok(unsafe { CsMatrix::from_parts_unchecked(rows, columns, offsets, indices, data) })
@@ -457,7 +614,35 @@ where
     D1: Borrow<[T1]>,
     D2: Borrow<[T2]>,
 {
-    Ok(spmm_csc_csc(rhs.transpose(), lhs.transpose())?.transpose_owned())
+    spmm_csr_csr_with_control(lhs, rhs, &Control::default())
+}
+
+/// Behaves like [`spmm_csr_csr`], but stops early if `control` requests it.
+///
+/// `control` is checked, and progress is reported, once per lane of the output matrix.
+///
+/// # Errors
+///
+/// In addition to the errors produced by [`spmm_csr_csr`], this function fails and produces an
+/// [`OperationError`] with kind [`OperationErrorKind::Cancelled`] if `control` requested a stop
+/// before the product was complete.
+pub fn spmm_csr_csr_with_control<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+    control: &Control<'_>,
+) -> Result<CscMatrix<<T2 as Mul<T1>>::Output>, OperationError>
+where
+    T2: Scalar + Mul<T1>,
+    <T2 as Mul<T1>>::Output: Scalar + AddAssign + Zero,
+    T1: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    Ok(spmm_csc_csc_with_control(rhs.transpose(), lhs.transpose(), control)?.transpose_owned())
 }
 
 /// Sparse-Dense matrix multiplication.
@@ -624,20 +809,20 @@ where
     )
 }
 
-/// Sparse-Dense matrix multiplication.
+/// Sparse-Dense matrix multiplication, for CSR matrices with an interleaved layout.
 ///
-/// This function takes in two matrices, one sparse in CSC format and one dense, and computes the
-/// `CSC * Dense` matrix product.
+/// This computes the same product as [`spmm_csr_dense`], but reads the sparse matrix from an
+/// [`InterleavedCsMatrix`] instead, so that the two layouts can be compared against one another.
 ///
 /// # Errors
 ///
 /// This function fails and produces an [`OperationError`] with kind
 /// [`OperationErrorKind::InvalidPattern`] if the two matrices have incompatible shapes for a
 /// matrix product.
-pub fn spmm_csc_dense<T1, T2, R, C, S, MO, MI, D>(
-    csc: CsMatrix<T1, MO, MI, D, CompressedColumnStorage>,
+pub fn spmm_interleaved_csr_dense<T1, T2, R, C, S>(
+    csr: &InterleavedCsMatrix<T1, CompressedRowStorage>,
     dense: Matrix<T2, R, C, S>,
-) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+) -> Result<CscMatrix<<T1 as Mul<T2>>::Output>, OperationError>
 where
     T2: Scalar,
     R: Dim,
@@ -645,11 +830,8 @@ where
     S: RawStorage<T2, R, C>,
     T1: Scalar + Mul<T2>,
     <T1 as Mul<T2>>::Output: Scalar + Add + Zero,
-    MO: Borrow<[usize]>,
-    MI: Borrow<[usize]>,
-    D: Borrow<[T1]>,
 {
-    let (rows, lc) = csc.shape();
+    let (rows, lc) = csr.shape();
     let (rr, columns) = dense.shape();
 
     if lc != rr {
@@ -661,67 +843,427 @@ where
         ));
     }
 
-    let nnz = csc.nnz();
+    let _span = span!(
+        "spmm_interleaved_csr_dense",
+        rows = rows,
+        columns = columns,
+        nnz = tracing::field::Empty
+    );
 
-    let triplets = csc.minor_lane_iter().enumerate().flat_map(|(i, lane)| {
-        let lane = lane.map(|(j, v)| (j, v.clone())).collect::<Vec<_>>();
+    // See spmm_csr_dense for why we build the transpose of the output here.
+    let mut counts = vec![0usize; columns];
+    let mut indices = Vec::with_capacity(csr.nnz());
+    let mut data = Vec::with_capacity(csr.nnz());
 
-        if lane.is_empty() {
-            Vec::with_capacity(0)
-        } else {
-            (0..columns)
-                .map(|k| {
-                    let dense_col = dense.column(k);
-                    let total = lane
-                        .iter()
-                        .fold(<T1 as Mul<T2>>::Output::zero(), |total, (j, v)| {
-                            total + v.clone() * dense_col[*j].clone()
-                        });
+    for (i, count) in counts.iter_mut().enumerate() {
+        let dense_col = dense.column(i);
 
-                    (i, k, total)
-                })
-                .collect::<Vec<_>>()
-        }
-    });
+        for (k, lane) in csr.iter().enumerate() {
+            if lane.len() == 0 {
+                continue;
+            }
 
-    let mut counts = vec![0usize; rows];
-    let mut indices = Vec::with_capacity(nnz);
-    let mut data = Vec::with_capacity(nnz);
+            let total = lane.fold(<T1 as Mul<T2>>::Output::zero(), |total, (j, v)| {
+                total + (v.clone() * dense_col[j].clone())
+            });
 
-    for (i, k, val) in triplets {
-        counts[i] += 1;
-        indices.push(k);
-        data.push(val);
+            *count += 1;
+            indices.push(k);
+            data.push(total);
+        }
     }
 
+    record!(_span, nnz = data.len());
+
     let offsets = CountToOffsetIter::new(counts).collect();
 
-    Ok(unsafe { CsMatrix::from_parts_unchecked(rows, columns, offsets, indices, data) })
+    Ok(
+        unsafe { CsrMatrix::from_parts_unchecked(columns, rows, offsets, indices, data) }
+            .transpose_owned(),
+    )
 }
 
-/// Sparse-Dense matrix multiplication.
+/// Sparse-Dense matrix multiplication, for CSC matrices with an interleaved layout.
 ///
-/// This function takes in two matrices, one dense and one sparse in CSC format, and computes the
-/// `Dense * CSR` matrix product.
+/// This computes the same product as [`spmm_csc_dense`], but reads the sparse matrix from an
+/// [`InterleavedCsMatrix`] instead, so that the two layouts can be compared against one another.
 ///
 /// # Errors
 ///
 /// This function fails and produces an [`OperationError`] with kind
 /// [`OperationErrorKind::InvalidPattern`] if the two matrices have incompatible shapes for a
 /// matrix product.
-pub fn spmm_dense_csr<T1, T2, R, C, S, MO, MI, D>(
-    dense: Matrix<T1, R, C, S>,
-    csr: CsMatrix<T2, MO, MI, D, CompressedRowStorage>,
-) -> Result<CscMatrix<<T2 as Mul<T1>>::Output>, OperationError>
+pub fn spmm_interleaved_csc_dense<T1, T2, R, C, S>(
+    csc: &InterleavedCsMatrix<T1, CompressedColumnStorage>,
+    dense: Matrix<T2, R, C, S>,
+) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
 where
-    T1: Scalar,
+    T2: Scalar,
     R: Dim,
     C: Dim,
-    S: RawStorage<T1, R, C>,
-    T2: Scalar + Mul<T1>,
-    <T2 as Mul<T1>>::Output: Scalar + Add + Zero,
-    MO: Borrow<[usize]>,
-    MI: Borrow<[usize]>,
+    S: RawStorage<T2, R, C>,
+    T1: Scalar + Mul<T2>,
+    <T1 as Mul<T2>>::Output: Scalar + Add<Output = <T1 as Mul<T2>>::Output> + Zero,
+{
+    let (rows, lc) = csc.shape();
+    let (rr, columns) = dense.shape();
+
+    if lc != rr {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from(
+                "The two matrices have incompatible shapes (M × K1 and K2 × N where K1 ≠ K2)",
+            ),
+        ));
+    }
+
+    let _span = span!(
+        "spmm_interleaved_csc_dense",
+        rows = rows,
+        columns = columns,
+        nnz = tracing::field::Empty
+    );
+
+    // Like spmm_csc_dense, every row of the output with an entry in the sparse matrix is dense.
+    // The rows are accumulated column by column of the sparse matrix, which sums the products of
+    // every output entry in the same order as spmm_csc_dense.
+    let mut occupied = vec![false; rows];
+    let mut sums = vec![<T1 as Mul<T2>>::Output::zero(); rows * columns];
+
+    for (j, lane) in csc.iter().enumerate() {
+        for (i, v) in lane {
+            occupied[i] = true;
+
+            for k in 0..columns {
+                let sum = &mut sums[i * columns + k];
+                *sum = sum.clone() + v.clone() * dense[(j, k)].clone();
+            }
+        }
+    }
+
+    let mut counts = vec![0usize; rows];
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+
+    for (i, _) in occupied
+        .iter()
+        .enumerate()
+        .filter(|(_, occupied)| **occupied)
+    {
+        counts[i] = columns;
+        indices.extend(0..columns);
+        data.extend_from_slice(&sums[i * columns..(i + 1) * columns]);
+    }
+
+    record!(_span, nnz = data.len());
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+
+    Ok(unsafe { CsMatrix::from_parts_unchecked(rows, columns, offsets, indices, data) })
+}
+
+/// Sparse matrix-vector multiplication into a pre-allocated vector, computing
+/// `y <- beta * y + alpha * op(A) * x` for a CSR matrix `A` with an interleaved layout.
+///
+/// This computes the same product as [`spmv_csr`], but reads the sparse matrix from an
+/// [`InterleavedCsMatrix`] instead, so that the two layouts can be compared against one another.
+/// `y` is not read if `beta` is zero.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `x` does not have as many entries as `op(A)` has
+/// columns, or `y` does not have as many entries as `op(A)` has rows. `y` is left untouched in
+/// that case.
+pub fn spmv_interleaved_csr<T, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: Op<&InterleavedCsMatrix<T, CompressedRowStorage>>,
+    x: &Vector<T, R2, S2>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    R1: Dim,
+    S1: StorageMut<T, R1>,
+    R2: Dim,
+    S2: RawStorage<T, R2>,
+{
+    spmv_interleaved_cs(beta, y, alpha, a, x)
+}
+
+/// Sparse matrix-vector multiplication into a pre-allocated vector, computing
+/// `y <- beta * y + alpha * op(A) * x` for a CSC matrix `A` with an interleaved layout.
+///
+/// See [`spmv_interleaved_csr`] for details.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `x` does not have as many entries as `op(A)` has
+/// columns, or `y` does not have as many entries as `op(A)` has rows. `y` is left untouched in
+/// that case.
+pub fn spmv_interleaved_csc<T, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: Op<&InterleavedCsMatrix<T, CompressedColumnStorage>>,
+    x: &Vector<T, R2, S2>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    R1: Dim,
+    S1: StorageMut<T, R1>,
+    R2: Dim,
+    S2: RawStorage<T, R2>,
+{
+    spmv_interleaved_cs(beta, y, alpha, a, x)
+}
+
+/// Computes `y <- beta * y + alpha * op(A) * x` for an interleaved matrix `A` of either
+/// compression, visiting the entries in the same order as [`spmm_cs_dense_prealloc`].
+fn spmv_interleaved_cs<T, CS, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: Op<&InterleavedCsMatrix<T, CS>>,
+    x: &Vector<T, R2, S2>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    CS: Compression,
+    R1: Dim,
+    S1: StorageMut<T, R1>,
+    R2: Dim,
+    S2: RawStorage<T, R2>,
+{
+    let (a, transpose) = match a {
+        Op::NoOp(a) => (a, false),
+        Op::Transpose(a) => (a, true),
+    };
+
+    let (nrows, ncols) = a.shape();
+    let shape = if transpose {
+        (ncols, nrows)
+    } else {
+        (nrows, ncols)
+    };
+    check_dense_product_shapes(shape, x.shape(), y.shape())?;
+
+    let _span = span!("spmv_interleaved_cs", rows = shape.0, nnz = a.nnz());
+
+    if beta.is_zero() {
+        y.fill(T::zero());
+    } else {
+        y.apply(|y_i| *y_i = beta.clone() * y_i.clone());
+    }
+
+    for (major, minor, v) in a.triplet_iter() {
+        let (mut row, mut col) = (CS::nmajor(major, minor), CS::nminor(major, minor));
+
+        if transpose {
+            std::mem::swap(&mut row, &mut col);
+        }
+
+        y[row] += alpha.clone() * v.clone() * x[col].clone();
+    }
+
+    Ok(())
+}
+
+/// Sparse-vector multiply of an int8-quantized CSR matrix and a dense vector, i.e. `y := A * x`.
+///
+/// The products of every row are accumulated with the quantized values converted to `T`, and the
+/// scale of the row is applied once to the final sum. The quantized values are therefore never
+/// dequantized into a full-size array of `T`.
+///
+/// Unlike the sparse-matrix products above, this produces a dense output, since the outputs of
+/// SpMV in inference workloads are typically consumed densely.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the number of columns in `csr` does not match the
+/// length of `x`.
+pub fn spmv_quantized_csr<T, R, S>(
+    csr: &QuantizedCsrMatrix<T>,
+    x: &Vector<T, R, S>,
+) -> Result<DVector<T>, OperationError>
+where
+    T: RealField,
+    R: Dim,
+    S: RawStorage<T, R>,
+{
+    if csr.ncols() != x.len() {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("The matrix must have as many columns as the vector has entries"),
+        ));
+    }
+
+    let _span = span!("spmv_quantized_csr", rows = csr.nrows(), nnz = csr.nnz());
+
+    let y = csr
+        .iter()
+        .zip(csr.scales())
+        .map(|(lane, scale)| {
+            let total = lane.fold(T::zero(), |total, (j, &q)| {
+                total + nalgebra::convert::<f64, T>(q as f64) * x[j].clone()
+            });
+
+            total * scale.clone()
+        })
+        .collect::<Vec<_>>();
+
+    Ok(DVector::from_vec(y))
+}
+
+/// Sparse-vector multiply of a run-length compressed CSR matrix and a dense vector, i.e.
+/// `y := A * x`.
+///
+/// Every run of consecutive columns is processed with a dense inner loop over a contiguous slice
+/// of values and a contiguous range of `x`, without reading any per-entry column indices.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the number of columns in `csr` does not match the
+/// length of `x`.
+pub fn spmv_run_length_csr<T, R, S>(
+    csr: &RunLengthCsMatrix<T, CompressedRowStorage>,
+    x: &Vector<T, R, S>,
+) -> Result<DVector<T>, OperationError>
+where
+    T: Scalar + Zero + Add<Output = T> + Mul<Output = T>,
+    R: Dim,
+    S: RawStorage<T, R>,
+{
+    if csr.ncols() != x.len() {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("The matrix must have as many columns as the vector has entries"),
+        ));
+    }
+
+    let _span = span!(
+        "spmv_run_length_csr",
+        rows = csr.nrows(),
+        nnz = csr.nnz(),
+        nruns = csr.nruns()
+    );
+
+    let y = csr
+        .iter()
+        .map(|lane| {
+            lane.fold(T::zero(), |total, (columns, values)| {
+                columns
+                    .zip(values)
+                    .fold(total, |total, (j, v)| total + v.clone() * x[j].clone())
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(DVector::from_vec(y))
+}
+
+/// Sparse-Dense matrix multiplication.
+///
+/// This function takes in two matrices, one sparse in CSC format and one dense, and computes the
+/// `CSC * Dense` matrix product.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices have incompatible shapes for a
+/// matrix product.
+pub fn spmm_csc_dense<T1, T2, R, C, S, MO, MI, D>(
+    csc: CsMatrix<T1, MO, MI, D, CompressedColumnStorage>,
+    dense: Matrix<T2, R, C, S>,
+) -> Result<CsrMatrix<<T1 as Mul<T2>>::Output>, OperationError>
+where
+    T2: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T2, R, C>,
+    T1: Scalar + Mul<T2>,
+    <T1 as Mul<T2>>::Output: Scalar + Add + Zero,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+{
+    let (rows, lc) = csc.shape();
+    let (rr, columns) = dense.shape();
+
+    if lc != rr {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from(
+                "The two matrices have incompatible shapes (M × K1 and K2 × N where K1 ≠ K2)",
+            ),
+        ));
+    }
+
+    let nnz = csc.nnz();
+
+    let triplets = csc.minor_lane_iter().enumerate().flat_map(|(i, lane)| {
+        let lane = lane.map(|(j, v)| (j, v.clone())).collect::<Vec<_>>();
+
+        if lane.is_empty() {
+            Vec::with_capacity(0)
+        } else {
+            (0..columns)
+                .map(|k| {
+                    let dense_col = dense.column(k);
+                    let total = lane
+                        .iter()
+                        .fold(<T1 as Mul<T2>>::Output::zero(), |total, (j, v)| {
+                            total + v.clone() * dense_col[*j].clone()
+                        });
+
+                    (i, k, total)
+                })
+                .collect::<Vec<_>>()
+        }
+    });
+
+    let mut counts = vec![0usize; rows];
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+
+    for (i, k, val) in triplets {
+        counts[i] += 1;
+        indices.push(k);
+        data.push(val);
+    }
+
+    let offsets = CountToOffsetIter::new(counts).collect();
+
+    Ok(unsafe { CsMatrix::from_parts_unchecked(rows, columns, offsets, indices, data) })
+}
+
+/// Sparse-Dense matrix multiplication.
+///
+/// This function takes in two matrices, one dense and one sparse in CSC format, and computes the
+/// `Dense * CSR` matrix product.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices have incompatible shapes for a
+/// matrix product.
+pub fn spmm_dense_csr<T1, T2, R, C, S, MO, MI, D>(
+    dense: Matrix<T1, R, C, S>,
+    csr: CsMatrix<T2, MO, MI, D, CompressedRowStorage>,
+) -> Result<CscMatrix<<T2 as Mul<T1>>::Output>, OperationError>
+where
+    T1: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T1, R, C>,
+    T2: Scalar + Mul<T1>,
+    <T2 as Mul<T1>>::Output: Scalar + Add + Zero,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
     D: Borrow<[T2]>,
 {
     let (rows, lc) = dense.shape();
@@ -762,7 +1304,7 @@ where
         }
     });
 
-    let mut counts = vec![0usize; rows];
+    let mut counts = vec![0usize; columns];
     let mut indices = Vec::with_capacity(nnz);
     let mut data = Vec::with_capacity(nnz);
 
@@ -782,12 +1324,706 @@ where
     )
 }
 
+/// Sparse-Dense matrix multiplication into a pre-allocated dense matrix, computing
+/// `C <- beta * C + alpha * op(A) * B` for a CSR matrix `A`.
+///
+/// No memory is allocated, so this is suited to loops that repeatedly multiply by the same
+/// operator. `op(A)` may be the transpose of `A`, in which case the transpose is never formed:
+/// the rows of `A` are read as the columns of `Aᵀ`. As in BLAS, `C` is not read if `beta` is
+/// zero, so it may contain arbitrary values (including NaN) in that case.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `op(A)` and `B` have incompatible shapes for a
+/// matrix product, or if `C` does not have the shape of the product. `C` is left untouched in
+/// that case.
+pub fn spmm_csr_dense_prealloc<T, MO, MI, D, R1, C1, S1, R2, C2, S2>(
+    beta: T,
+    c: &mut Matrix<T, R1, C1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedRowStorage>>,
+    b: &Matrix<T, R2, C2, S2>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    C1: Dim,
+    S1: StorageMut<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    match a {
+        Op::NoOp(a) => spmm_cs_dense_prealloc(beta, c, alpha, a.to_view(), b),
+        Op::Transpose(a) => spmm_cs_dense_prealloc(beta, c, alpha, a.transpose(), b),
+    }
+}
+
+/// Sparse-Dense matrix multiplication into a pre-allocated dense matrix, computing
+/// `C <- beta * C + alpha * op(A) * B` for a CSC matrix `A`.
+///
+/// See [`spmm_csr_dense_prealloc`] for details.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `op(A)` and `B` have incompatible shapes for a
+/// matrix product, or if `C` does not have the shape of the product. `C` is left untouched in
+/// that case.
+pub fn spmm_csc_dense_prealloc<T, MO, MI, D, R1, C1, S1, R2, C2, S2>(
+    beta: T,
+    c: &mut Matrix<T, R1, C1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedColumnStorage>>,
+    b: &Matrix<T, R2, C2, S2>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    C1: Dim,
+    S1: StorageMut<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    match a {
+        Op::NoOp(a) => spmm_cs_dense_prealloc(beta, c, alpha, a.to_view(), b),
+        Op::Transpose(a) => spmm_cs_dense_prealloc(beta, c, alpha, a.transpose(), b),
+    }
+}
+
+/// Sparse matrix-vector multiplication into a pre-allocated vector, computing
+/// `y <- beta * y + alpha * op(A) * x` for a CSR matrix `A`.
+///
+/// This is the vector counterpart of [`spmm_csr_dense_prealloc`], and likewise never allocates:
+/// use `Op::Transpose(&a)` to compute products with `Aᵀ`. `y` is not read if `beta` is zero.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `x` does not have as many entries as `op(A)` has
+/// columns, or `y` does not have as many entries as `op(A)` has rows. `y` is left untouched in
+/// that case.
+pub fn spmv_csr<T, MO, MI, D, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedRowStorage>>,
+    x: &Vector<T, R2, S2>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    S1: StorageMut<T, R1>,
+    R2: Dim,
+    S2: RawStorage<T, R2>,
+{
+    spmm_csr_dense_prealloc(beta, y, alpha, a, x)
+}
+
+/// Sparse matrix-vector multiplication into a pre-allocated vector, computing
+/// `y <- beta * y + alpha * op(A) * x` for a CSC matrix `A`.
+///
+/// See [`spmv_csr`] for details.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `x` does not have as many entries as `op(A)` has
+/// columns, or `y` does not have as many entries as `op(A)` has rows. `y` is left untouched in
+/// that case.
+pub fn spmv_csc<T, MO, MI, D, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedColumnStorage>>,
+    x: &Vector<T, R2, S2>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    S1: StorageMut<T, R1>,
+    R2: Dim,
+    S2: RawStorage<T, R2>,
+{
+    spmm_csc_dense_prealloc(beta, y, alpha, a, x)
+}
+
+/// Sparse matrix-vector multiplication for vectors with `block_size` interleaved components per
+/// index, computing `y <- beta * y + alpha * (op(A) ⊗ I) * x` for a CSR matrix `A`.
+///
+/// Here `I` is the `block_size × block_size` identity: every entry `a_ij` of `op(A)` multiplies the
+/// whole block `x[j * block_size..(j + 1) * block_size]` into the block of `y` at `i`. This is how
+/// operators that act identically on every component of a vector field (e.g. the three
+/// displacements of a node in elasticity, or a graph Laplacian applied to vector-valued signals)
+/// are usually stored: as the scalar pattern `A`, without materializing the block matrix. `y` is
+/// not read if `beta` is zero.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `x` does not have `block_size` entries per column of
+/// `op(A)`, or `y` does not have `block_size` entries per row of `op(A)`. `y` is left untouched in
+/// that case.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::DVector;
+/// use nalgebra_sparse::{
+///     cs::CsrMatrix,
+///     ops::{serial::spmm::spmv_csr_blocked, Op},
+/// };
+///
+/// // A 2 × 2 pattern acting on 3 components per node.
+/// let a = CsrMatrix::try_from_parts(2, 2, vec![0, 2], vec![0, 1, 1], vec![2.0, -1.0, 3.0])
+///     .unwrap();
+/// let x = DVector::from_vec(vec![1.0, 2.0, 3.0, 10.0, 20.0, 30.0]);
+/// let mut y = DVector::zeros(6);
+///
+/// spmv_csr_blocked(0.0, &mut y, 1.0, Op::NoOp(&a), &x, 3).unwrap();
+///
+/// assert_eq!(y, DVector::from_vec(vec![-8.0, -16.0, -24.0, 30.0, 60.0, 90.0]));
+/// ```
+pub fn spmv_csr_blocked<T, MO, MI, D, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedRowStorage>>,
+    x: &Vector<T, R2, S2>,
+    block_size: usize,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    S1: StorageMut<T, R1>,
+    R2: Dim,
+    S2: RawStorage<T, R2>,
+{
+    match a {
+        Op::NoOp(a) => spmv_cs_blocked(beta, y, alpha, a.to_view(), x, block_size),
+        Op::Transpose(a) => spmv_cs_blocked(beta, y, alpha, a.transpose(), x, block_size),
+    }
+}
+
+/// Sparse matrix-vector multiplication for vectors with `block_size` interleaved components per
+/// index, computing `y <- beta * y + alpha * (op(A) ⊗ I) * x` for a CSC matrix `A`.
+///
+/// See [`spmv_csr_blocked`] for details.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `x` does not have `block_size` entries per column of
+/// `op(A)`, or `y` does not have `block_size` entries per row of `op(A)`. `y` is left untouched in
+/// that case.
+pub fn spmv_csc_blocked<T, MO, MI, D, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedColumnStorage>>,
+    x: &Vector<T, R2, S2>,
+    block_size: usize,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    S1: StorageMut<T, R1>,
+    R2: Dim,
+    S2: RawStorage<T, R2>,
+{
+    match a {
+        Op::NoOp(a) => spmv_cs_blocked(beta, y, alpha, a.to_view(), x, block_size),
+        Op::Transpose(a) => spmv_cs_blocked(beta, y, alpha, a.transpose(), x, block_size),
+    }
+}
+
+/// Computes `y <- beta * y + alpha * (A ⊗ I) * x` for a sparse matrix `A` of either compression,
+/// where `I` is the `block_size × block_size` identity.
+fn spmv_cs_blocked<T, CS, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: CsMatrix<T, &[usize], &[usize], &[T], CS>,
+    x: &Vector<T, R2, S2>,
+    block_size: usize,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    CS: Compression,
+    R1: Dim,
+    S1: StorageMut<T, R1>,
+    R2: Dim,
+    S2: RawStorage<T, R2>,
+{
+    let (rows, columns) = a.shape();
+
+    if x.nrows() != columns * block_size {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!(
+                "The input vector has {} entries, but {} columns with {} components each need {}.",
+                x.nrows(),
+                columns,
+                block_size,
+                columns * block_size
+            ),
+        ));
+    }
+
+    if y.nrows() != rows * block_size {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!(
+                "The output vector has {} entries, but {} rows with {} components each need {}.",
+                y.nrows(),
+                rows,
+                block_size,
+                rows * block_size
+            ),
+        ));
+    }
+
+    let _span = span!(
+        "spmv_cs_blocked",
+        rows = rows,
+        block_size = block_size,
+        nnz = a.nnz()
+    );
+
+    if beta.is_zero() {
+        y.fill(T::zero());
+    } else {
+        y.apply(|y_i| *y_i = beta.clone() * y_i.clone());
+    }
+
+    for (major, minor, v) in a.triplet_iter() {
+        let row = CS::nmajor(major, minor);
+        let col = CS::nminor(major, minor);
+        let scaled = alpha.clone() * v.clone();
+
+        for component in 0..block_size {
+            y[row * block_size + component] +=
+                scaled.clone() * x[col * block_size + component].clone();
+        }
+    }
+
+    Ok(())
+}
+
+/// Sparse-Dense matrix multiplication into a pre-allocated dense matrix, computing
+/// `C <- beta * C + alpha * op(A) * B` for a CSR matrix `A`, with the given `options`.
+///
+/// With the default options this is the same as [`spmm_csr_dense_prealloc`]. Otherwise, every
+/// entry of `C` is computed as a sum of products along a row of `op(A)`, using the requested
+/// [`Summation`]. The rows of `Aᵀ` are the columns of `A`, which are found by searching through
+/// the lanes of `A` (see [`CsMatrix::minor_lane_iter`]), so this is faster without a transpose.
+/// [`Summation::Pairwise`] allocates one buffer for the products of the longest row.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `op(A)` and `B` have incompatible shapes for a
+/// matrix product, or if `C` does not have the shape of the product. `C` is left untouched in
+/// that case.
+pub fn spmm_csr_dense_prealloc_with_options<T, MO, MI, D, R1, C1, S1, R2, C2, S2>(
+    beta: T,
+    c: &mut Matrix<T, R1, C1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedRowStorage>>,
+    b: &Matrix<T, R2, C2, S2>,
+    options: SpmmOptions,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    C1: Dim,
+    S1: StorageMut<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    match options.summation {
+        Summation::Sequential => spmm_csr_dense_prealloc(beta, c, alpha, a, b),
+        summation => match a {
+            Op::NoOp(a) => spmm_cs_dense_prealloc_summed(beta, c, alpha, a.to_view(), b, summation),
+            Op::Transpose(a) => {
+                spmm_cs_dense_prealloc_summed(beta, c, alpha, a.transpose(), b, summation)
+            }
+        },
+    }
+}
+
+/// Sparse-Dense matrix multiplication into a pre-allocated dense matrix, computing
+/// `C <- beta * C + alpha * op(A) * B` for a CSC matrix `A`, with the given `options`.
+///
+/// See [`spmm_csr_dense_prealloc_with_options`] for details. The rows of `Aᵀ` are the columns of
+/// `A`, so for CSC matrices this is faster with a transpose.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `op(A)` and `B` have incompatible shapes for a
+/// matrix product, or if `C` does not have the shape of the product. `C` is left untouched in
+/// that case.
+pub fn spmm_csc_dense_prealloc_with_options<T, MO, MI, D, R1, C1, S1, R2, C2, S2>(
+    beta: T,
+    c: &mut Matrix<T, R1, C1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedColumnStorage>>,
+    b: &Matrix<T, R2, C2, S2>,
+    options: SpmmOptions,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    C1: Dim,
+    S1: StorageMut<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    match options.summation {
+        Summation::Sequential => spmm_csc_dense_prealloc(beta, c, alpha, a, b),
+        summation => match a {
+            Op::NoOp(a) => spmm_cs_dense_prealloc_summed(beta, c, alpha, a.to_view(), b, summation),
+            Op::Transpose(a) => {
+                spmm_cs_dense_prealloc_summed(beta, c, alpha, a.transpose(), b, summation)
+            }
+        },
+    }
+}
+
+/// Sparse matrix-vector multiplication into a pre-allocated vector, computing
+/// `y <- beta * y + alpha * op(A) * x` for a CSR matrix `A`, with the given `options`.
+///
+/// This is the vector counterpart of [`spmm_csr_dense_prealloc_with_options`].
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `x` does not have as many entries as `op(A)` has
+/// columns, or `y` does not have as many entries as `op(A)` has rows. `y` is left untouched in
+/// that case.
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::DVector;
+/// use nalgebra_sparse::{
+///     cs::CsrMatrix,
+///     ops::{serial::spmm::spmv_csr_with_options, Op, SpmmOptions, Summation},
+/// };
+///
+/// // A single long row: one large entry followed by many tiny ones.
+/// let n = 10_001;
+/// let values = std::iter::once(1.0).chain(std::iter::repeat_n(1e-16, n - 1)).collect();
+/// let a = CsrMatrix::try_from_parts(1, n, vec![0], (0..n).collect(), values).unwrap();
+/// let x = DVector::from_element(n, 1.0);
+///
+/// let options = SpmmOptions { summation: Summation::Kahan };
+/// let mut y = DVector::<f64>::zeros(1);
+/// spmv_csr_with_options(0.0, &mut y, 1.0, Op::NoOp(&a), &x, options).unwrap();
+///
+/// assert!((y[0] - (1.0 + 1e-12)).abs() < 1e-15);
+/// ```
+pub fn spmv_csr_with_options<T, MO, MI, D, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedRowStorage>>,
+    x: &Vector<T, R2, S2>,
+    options: SpmmOptions,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    S1: StorageMut<T, R1>,
+    R2: Dim,
+    S2: RawStorage<T, R2>,
+{
+    spmm_csr_dense_prealloc_with_options(beta, y, alpha, a, x, options)
+}
+
+/// Sparse matrix-vector multiplication into a pre-allocated vector, computing
+/// `y <- beta * y + alpha * op(A) * x` for a CSC matrix `A`, with the given `options`.
+///
+/// This is the vector counterpart of [`spmm_csc_dense_prealloc_with_options`].
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if `x` does not have as many entries as `op(A)` has
+/// columns, or `y` does not have as many entries as `op(A)` has rows. `y` is left untouched in
+/// that case.
+pub fn spmv_csc_with_options<T, MO, MI, D, R1, S1, R2, S2>(
+    beta: T,
+    y: &mut Vector<T, R1, S1>,
+    alpha: T,
+    a: Op<&CsMatrix<T, MO, MI, D, CompressedColumnStorage>>,
+    x: &Vector<T, R2, S2>,
+    options: SpmmOptions,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R1: Dim,
+    S1: StorageMut<T, R1>,
+    R2: Dim,
+    S2: RawStorage<T, R2>,
+{
+    spmm_csc_dense_prealloc_with_options(beta, y, alpha, a, x, options)
+}
+
+/// Checks that `C <- beta * C + alpha * A * B` is well-defined for the given shapes.
+fn check_dense_product_shapes(
+    a: (usize, usize),
+    b: (usize, usize),
+    c: (usize, usize),
+) -> Result<(), OperationError> {
+    let (rows, lc) = a;
+    let (rr, columns) = b;
+
+    if lc != rr {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from(
+                "The two matrices have incompatible shapes (M × K1 and K2 × N where K1 ≠ K2)",
+            ),
+        ));
+    }
+
+    if c != (rows, columns) {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!(
+                "The output matrix has shape {:?}, but the product has shape {:?}.",
+                c,
+                (rows, columns)
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Computes `C <- beta * C + alpha * A * B` for a sparse matrix `A` of either compression.
+fn spmm_cs_dense_prealloc<T, CS, R1, C1, S1, R2, C2, S2>(
+    beta: T,
+    c: &mut Matrix<T, R1, C1, S1>,
+    alpha: T,
+    a: CsMatrix<T, &[usize], &[usize], &[T], CS>,
+    b: &Matrix<T, R2, C2, S2>,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Mul<Output = T>,
+    CS: Compression,
+    R1: Dim,
+    C1: Dim,
+    S1: StorageMut<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    check_dense_product_shapes(a.shape(), b.shape(), c.shape())?;
+
+    let columns = b.ncols();
+
+    let _span = span!(
+        "spmm_cs_dense_prealloc",
+        rows = a.nrows(),
+        columns = columns,
+        nnz = a.nnz()
+    );
+
+    if beta.is_zero() {
+        c.fill(T::zero());
+    } else {
+        c.apply(|x| *x = beta.clone() * x.clone());
+    }
+
+    for (major, minor, v) in a.triplet_iter() {
+        let row = CS::nmajor(major, minor);
+        let col = CS::nminor(major, minor);
+        let scaled = alpha.clone() * v.clone();
+
+        for j in 0..columns {
+            c[(row, j)] += scaled.clone() * b[(col, j)].clone();
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes `C <- beta * C + alpha * A * B` for a sparse matrix `A` of either compression, summing
+/// up the products along every row of `A` with `summation`.
+fn spmm_cs_dense_prealloc_summed<T, CS, R1, C1, S1, R2, C2, S2>(
+    beta: T,
+    c: &mut Matrix<T, R1, C1, S1>,
+    alpha: T,
+    a: CsMatrix<T, &[usize], &[usize], &[T], CS>,
+    b: &Matrix<T, R2, C2, S2>,
+    summation: Summation,
+) -> Result<(), OperationError>
+where
+    T: Scalar + Zero + AddAssign + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+    CS: Compression,
+    R1: Dim,
+    C1: Dim,
+    S1: StorageMut<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    check_dense_product_shapes(a.shape(), b.shape(), c.shape())?;
+
+    let _span = span!(
+        "spmm_cs_dense_prealloc_summed",
+        rows = a.nrows(),
+        columns = b.ncols(),
+        nnz = a.nnz()
+    );
+
+    // Whether the lanes of A are its rows
+    if CS::nmajor(1, 0) == 1 {
+        sum_rows_into(beta, c, alpha, a.iter(), b, summation);
+    } else {
+        sum_rows_into(beta, c, alpha, a.minor_lane_iter(), b, summation);
+    }
+
+    Ok(())
+}
+
+/// Computes `C <- beta * C + alpha * A * B`, where `rows` yields the `(column, value)` entries of
+/// every row of `A`, in order.
+fn sum_rows_into<'a, T, L, I, R1, C1, S1, R2, C2, S2>(
+    beta: T,
+    c: &mut Matrix<T, R1, C1, S1>,
+    alpha: T,
+    rows: L,
+    b: &Matrix<T, R2, C2, S2>,
+    summation: Summation,
+) where
+    T: Scalar + Zero + AddAssign + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+    L: Iterator<Item = I>,
+    I: Iterator<Item = (usize, &'a T)> + Clone,
+    R1: Dim,
+    C1: Dim,
+    S1: StorageMut<T, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<T, R2, C2>,
+{
+    let mut products = Vec::new();
+
+    for (i, row) in rows.enumerate() {
+        for j in 0..b.ncols() {
+            let terms = row.clone().map(|(k, v)| v.clone() * b[(k, j)].clone());
+
+            let sum = match summation {
+                Summation::Sequential => sequential_sum(terms),
+                Summation::Pairwise => {
+                    products.clear();
+                    products.extend(terms);
+                    pairwise_sum(&products)
+                }
+                Summation::Kahan => kahan_sum(terms),
+            };
+
+            c[(i, j)] = if beta.is_zero() {
+                alpha.clone() * sum
+            } else {
+                beta.clone() * c[(i, j)].clone() + alpha.clone() * sum
+            };
+        }
+    }
+}
+
+fn sequential_sum<T, I>(terms: I) -> T
+where
+    T: Zero + AddAssign,
+    I: Iterator<Item = T>,
+{
+    let mut sum = T::zero();
+
+    for term in terms {
+        sum += term;
+    }
+
+    sum
+}
+
+/// The number of terms below which [`pairwise_sum`] sums sequentially.
+const PAIRWISE_BLOCK_SIZE: usize = 8;
+
+fn pairwise_sum<T>(terms: &[T]) -> T
+where
+    T: Scalar + Zero + AddAssign + Add<Output = T>,
+{
+    if terms.len() <= PAIRWISE_BLOCK_SIZE {
+        sequential_sum(terms.iter().cloned())
+    } else {
+        let (left, right) = terms.split_at(terms.len() / 2);
+        pairwise_sum(left) + pairwise_sum(right)
+    }
+}
+
+fn kahan_sum<T, I>(terms: I) -> T
+where
+    T: Scalar + Zero + Add<Output = T> + Sub<Output = T>,
+    I: Iterator<Item = T>,
+{
+    let mut sum = T::zero();
+    let mut compensation = T::zero();
+
+    for term in terms {
+        let corrected = term - compensation.clone();
+        let total = sum.clone() + corrected.clone();
+
+        // The low-order bits of `corrected` that were lost when adding it to `sum`
+        compensation = (total.clone() - sum) - corrected;
+        sum = total;
+    }
+
+    sum
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::proptest::*;
+    use crate::{control::CancellationToken, proptest::*};
     use matrixcompare::{assert_matrix_eq, prop_assert_matrix_eq};
-    use nalgebra::{DMatrix, SMatrix};
+    use nalgebra::{proptest::matrix, DMatrix, SMatrix};
     use proptest::prelude::*;
 
     #[test]
@@ -1028,7 +2264,143 @@ mod tests {
         assert_matrix_eq!(dense_product, product);
     }
 
+    #[test]
+    fn spmv_run_length_csr_handles_explicit_zeros_and_empty_rows() {
+        let matrix =
+            CsrMatrix::try_from_parts(4, 5, vec![0, 2, 2, 2], vec![0, 3, 4], vec![0, 0, 1])
+                .unwrap();
+        let x = DVector::from_fn(5, |i, _| 2 * i as i32 - 3);
+
+        let y = spmv_run_length_csr(&RunLengthCsMatrix::from(&matrix), &x).unwrap();
+
+        assert_eq!(y, DVector::from_column_slice(&[0, 0, 0, 5]));
+    }
+
+    #[test]
+    fn spmm_with_cancelled_control_fails_with_cancelled() {
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let control = Control::new().with_cancellation_token(&token);
+
+        let a = CsrMatrix::<i32>::identity(4);
+        let b = CscMatrix::<i32>::identity(4);
+
+        let errors = [
+            spmm_csr_csc_with_control(a.to_view(), b.to_view(), &control).unwrap_err(),
+            spmm_csc_csr_with_control(b.to_view(), a.to_view(), &control).unwrap_err(),
+            spmm_csc_csc_with_control(b.to_view(), b.to_view(), &control).unwrap_err(),
+            spmm_csr_csr_with_control(a.to_view(), a.to_view(), &control).unwrap_err(),
+        ];
+
+        for error in &errors {
+            assert!(matches!(error.kind(), OperationErrorKind::Cancelled));
+        }
+
+        // A control that never stops produces the same result as the plain product.
+        let control = Control::new();
+
+        assert_matrix_eq!(
+            spmm_csr_csc_with_control(a.to_view(), b.to_view(), &control).unwrap(),
+            spmm_csr_csc(a.to_view(), b.to_view()).unwrap()
+        );
+    }
+
     proptest! {
+        #[test]
+        fn spmm_interleaved_csr_dense_agrees_with_spmm_csr_dense(matrix in csr_strategy()) {
+            let dense = DMatrix::<i32>::from_fn(matrix.ncols(), 3, |i, j| (i + 2 * j) as i32 - 4);
+            let interleaved = InterleavedCsMatrix::from(&matrix);
+
+            let expected = spmm_csr_dense(matrix.to_view(), dense.clone()).unwrap();
+            let product = spmm_interleaved_csr_dense(&interleaved, dense).unwrap();
+
+            prop_assert_matrix_eq!(product, expected);
+        }
+
+        #[test]
+        fn spmm_interleaved_csc_dense_agrees_with_spmm_csc_dense(
+            matrix in csc_strategy(),
+            columns in 0..4usize,
+        ) {
+            let dense = DMatrix::<i32>::from_fn(matrix.ncols(), columns, |i, j| (i + 2 * j) as i32 - 4);
+            let interleaved = InterleavedCsMatrix::from(&matrix);
+
+            let expected = spmm_csc_dense(matrix.to_view(), dense.clone()).unwrap();
+            let product = spmm_interleaved_csc_dense(&interleaved, dense).unwrap();
+
+            prop_assert_matrix_eq!(product, expected);
+            prop_assert_eq!(product.cs_data(), expected.cs_data());
+        }
+
+        #[test]
+        fn spmv_interleaved_agrees_with_spmv(
+            csr in csr_strategy(),
+            beta in -2..3i32,
+            alpha in -2..3i32,
+        ) {
+            let csc = CscMatrix::from(csr.clone());
+            let interleaved_csr = InterleavedCsMatrix::from(&csr);
+            let interleaved_csc = InterleavedCsMatrix::from(&csc);
+
+            fn op<A>(a: A, transpose: bool) -> Op<A> {
+                if transpose {
+                    Op::Transpose(a)
+                } else {
+                    Op::NoOp(a)
+                }
+            }
+
+            for transpose in [false, true] {
+                let (nrows, ncols) = if transpose {
+                    (csr.ncols(), csr.nrows())
+                } else {
+                    csr.shape()
+                };
+                let x = DVector::from_fn(ncols, |i, _| i as i32 - 3);
+                let y = DVector::from_fn(nrows, |i, _| 2 - i as i32);
+
+                let mut expected = y.clone();
+                spmv_csr(beta, &mut expected, alpha, op(&csr, transpose), &x).unwrap();
+
+                let mut from_csr = y.clone();
+                spmv_interleaved_csr(beta, &mut from_csr, alpha, op(&interleaved_csr, transpose), &x).unwrap();
+                prop_assert_eq!(&from_csr, &expected);
+
+                let mut from_csc = y.clone();
+                spmv_csc(beta, &mut from_csc, alpha, op(&csc, transpose), &x).unwrap();
+                prop_assert_eq!(&from_csc, &expected);
+
+                let mut from_interleaved_csc = y;
+                spmv_interleaved_csc(beta, &mut from_interleaved_csc, alpha, op(&interleaved_csc, transpose), &x)
+                    .unwrap();
+                prop_assert_eq!(&from_interleaved_csc, &expected);
+            }
+        }
+
+        #[test]
+        fn spmv_quantized_csr_agrees_with_dequantized_product(
+            matrix in csr(-10.0..10.0, PROPTEST_MATRIX_DIM, PROPTEST_MATRIX_DIM, PROPTEST_MAX_NNZ)
+        ) {
+            let quantized = QuantizedCsrMatrix::quantize(&matrix);
+            let x = DVector::from_fn(matrix.ncols(), |i, _| i as f64 - 2.5);
+
+            let expected = DMatrix::from(&quantized.dequantize()) * &x;
+            let y = spmv_quantized_csr(&quantized, &x).unwrap();
+
+            prop_assert_matrix_eq!(y, expected, comp = abs, tol = 1e-9);
+        }
+
+        #[test]
+        fn spmv_run_length_csr_agrees_with_dense_product(matrix in csr_strategy()) {
+            let x = DVector::from_fn(matrix.ncols(), |i, _| 2 * i as i32 - 3);
+
+            let expected = DMatrix::from(&matrix) * &x;
+            let y = spmv_run_length_csr(&RunLengthCsMatrix::from(&matrix), &x).unwrap();
+
+            prop_assert_eq!(y, expected);
+        }
+
         #[test]
         fn spmm_csr_csr_multiplicative_right_identity(matrix in csr_strategy()) {
             let eye = CsrMatrix::<i32>::identity(matrix.ncols());
@@ -1095,4 +2467,225 @@ mod tests {
             prop_assert_matrix_eq!(product, matrix);
         }
     }
+
+    #[test]
+    fn spmm_dense_prealloc_rejects_mismatched_shapes() {
+        let a = CsrMatrix::<f64>::identity(3);
+        let b = DMatrix::zeros(3, 2);
+
+        let mut c = DMatrix::from_element(2, 2, 1.0);
+        let err = spmm_csr_dense_prealloc(0.0, &mut c, 1.0, Op::NoOp(&a), &b).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+        assert_eq!(c, DMatrix::from_element(2, 2, 1.0));
+
+        let mut c = DMatrix::zeros(3, 2);
+        let err = spmm_csr_dense_prealloc(0.0, &mut c, 1.0, Op::NoOp(&a), &DMatrix::zeros(2, 2))
+            .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn spmm_dense_prealloc_ignores_output_if_beta_is_zero() {
+        let a = CscMatrix::<f64>::identity(2);
+        let b = DMatrix::from_element(2, 2, 3.0);
+        let mut c = DMatrix::from_element(2, 2, f64::NAN);
+
+        spmm_csc_dense_prealloc(0.0, &mut c, 2.0, Op::Transpose(&a), &b).unwrap();
+
+        assert_eq!(c, DMatrix::from_element(2, 2, 6.0));
+    }
+
+    proptest! {
+        #[test]
+        fn spmm_dense_prealloc_agrees_with_dense(
+            (a, transpose, b, c) in (csr_strategy(), any::<bool>(), 0usize..=4)
+                .prop_flat_map(|(a, transpose, n)| {
+                    let (m, k) = if transpose {
+                        (a.ncols(), a.nrows())
+                    } else {
+                        (a.nrows(), a.ncols())
+                    };
+
+                    (
+                        Just(a),
+                        Just(transpose),
+                        matrix(PROPTEST_I32_VALUE_STRATEGY, k, n),
+                        matrix(PROPTEST_I32_VALUE_STRATEGY, m, n),
+                    )
+                }),
+            alpha in -3i32..=3,
+            beta in -3i32..=3,
+        ) {
+            let dense_a = DMatrix::from(&a);
+            let op_a = if transpose { dense_a.transpose() } else { dense_a };
+            let expected = &c * beta + op_a * &b * alpha;
+
+            let csc = CscMatrix::from(a.clone());
+            let (op_csr, op_csc) = if transpose {
+                (Op::Transpose(&a), Op::Transpose(&csc))
+            } else {
+                (Op::NoOp(&a), Op::NoOp(&csc))
+            };
+
+            let mut c_csr = c.clone();
+            spmm_csr_dense_prealloc(beta, &mut c_csr, alpha, op_csr, &b).unwrap();
+            prop_assert_eq!(c_csr, expected.clone());
+
+            let mut c_csc = c.clone();
+            spmm_csc_dense_prealloc(beta, &mut c_csc, alpha, op_csc, &b).unwrap();
+            prop_assert_eq!(c_csc, expected);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn spmv_agrees_with_dense(
+            (a, x, x_t, y, y_t) in csr_strategy().prop_flat_map(|a| {
+                let (m, n) = a.shape();
+                (
+                    Just(a),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, n, 1),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, m, 1),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, m, 1),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, n, 1),
+                )
+            }),
+            alpha in -3i32..=3,
+            beta in -3i32..=3,
+        ) {
+            let dense = DMatrix::from(&a);
+            let csc = CscMatrix::from(a.clone());
+            let x = x.column(0).into_owned();
+            let x_t = x_t.column(0).into_owned();
+            let y = y.column(0).into_owned();
+            let y_t = y_t.column(0).into_owned();
+
+            let expected = &y * beta + &dense * &x * alpha;
+            let expected_t = &y_t * beta + dense.transpose() * &x_t * alpha;
+
+            let mut result = y.clone();
+            spmv_csr(beta, &mut result, alpha, Op::NoOp(&a), &x).unwrap();
+            prop_assert_eq!(&result, &expected);
+
+            let mut result = y.clone();
+            spmv_csc(beta, &mut result, alpha, Op::NoOp(&csc), &x).unwrap();
+            prop_assert_eq!(&result, &expected);
+
+            let mut result = y_t.clone();
+            spmv_csr(beta, &mut result, alpha, Op::Transpose(&a), &x_t).unwrap();
+            prop_assert_eq!(&result, &expected_t);
+
+            let mut result = y_t.clone();
+            spmv_csc(beta, &mut result, alpha, Op::Transpose(&csc), &x_t).unwrap();
+            prop_assert_eq!(&result, &expected_t);
+        }
+
+        #[test]
+        fn spmm_with_options_agrees_with_dense(
+            (a, b, c) in (csr_strategy(), 0..4usize).prop_flat_map(|(a, k)| {
+                let (m, n) = a.shape();
+                (
+                    Just(a),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, n, k),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, m, k),
+                )
+            }),
+            alpha in -3i32..=3,
+            beta in -3i32..=3,
+            summation in prop_oneof![
+                Just(Summation::Sequential),
+                Just(Summation::Pairwise),
+                Just(Summation::Kahan),
+            ],
+        ) {
+            let options = SpmmOptions { summation };
+            let dense = DMatrix::from(&a);
+            let csc = CscMatrix::from(a.clone());
+            let expected = &c * beta + &dense * &b * alpha;
+
+            let mut result = c.clone();
+            spmm_csr_dense_prealloc_with_options(beta, &mut result, alpha, Op::NoOp(&a), &b, options)
+                .unwrap();
+            prop_assert_eq!(&result, &expected);
+
+            let mut result = c.clone();
+            spmm_csc_dense_prealloc_with_options(beta, &mut result, alpha, Op::NoOp(&csc), &b, options)
+                .unwrap();
+            prop_assert_eq!(&result, &expected);
+
+            let a_t = csc.clone().transpose_owned();
+            let csc_t = a.clone().transpose_owned();
+
+            let mut result = c.clone();
+            spmm_csr_dense_prealloc_with_options(beta, &mut result, alpha, Op::Transpose(&a_t), &b, options)
+                .unwrap();
+            prop_assert_eq!(&result, &expected);
+
+            let mut result = c.clone();
+            spmm_csc_dense_prealloc_with_options(beta, &mut result, alpha, Op::Transpose(&csc_t), &b, options)
+                .unwrap();
+            prop_assert_eq!(&result, &expected);
+        }
+    }
+
+    #[test]
+    fn compensated_summation_is_more_accurate_on_long_rows() {
+        let n = 10_001;
+        let values = std::iter::once(1.0)
+            .chain(std::iter::repeat_n(1e-16, n - 1))
+            .collect();
+        let a = CsrMatrix::try_from_parts(1, n, vec![0], (0..n).collect(), values).unwrap();
+        let x = DVector::from_element(n, 1.0);
+        let exact = 1.0 + 1e-12;
+
+        let error = |summation| {
+            let mut y = DVector::<f64>::zeros(1);
+            let options = SpmmOptions { summation };
+            spmv_csr_with_options(0.0, &mut y, 1.0, Op::NoOp(&a), &x, options).unwrap();
+            (y[0] - exact).abs()
+        };
+
+        assert!(error(Summation::Sequential) > 1e-13);
+        assert!(error(Summation::Pairwise) < 1e-15);
+        assert!(error(Summation::Kahan) < 1e-15);
+    }
+
+    proptest! {
+        #[test]
+        fn spmv_blocked_agrees_with_kronecker_product(
+            (a, block_size, x, y) in (csr_strategy(), 0..4usize).prop_flat_map(|(a, k)| {
+                let (m, n) = a.shape();
+                (
+                    Just(a),
+                    Just(k),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, n * k, 1),
+                    matrix(PROPTEST_I32_VALUE_STRATEGY, m * k, 1),
+                )
+            }),
+            alpha in -3i32..=3,
+            beta in -3i32..=3,
+        ) {
+            let dense = DMatrix::from(&a);
+            let csc = CscMatrix::from(a.clone());
+            let x = x.column(0).into_owned();
+            let y = y.column(0).into_owned();
+            let identity = DMatrix::<i32>::identity(block_size, block_size);
+
+            let expected = &y * beta + dense.kronecker(&identity) * &x * alpha;
+
+            let mut result = y.clone();
+            spmv_csr_blocked(beta, &mut result, alpha, Op::NoOp(&a), &x, block_size).unwrap();
+            prop_assert_eq!(&result, &expected);
+
+            let mut result = y.clone();
+            spmv_csc_blocked(beta, &mut result, alpha, Op::NoOp(&csc), &x, block_size).unwrap();
+            prop_assert_eq!(&result, &expected);
+
+            let a_t = csc.transpose_owned();
+            let mut result = y.clone();
+            spmv_csr_blocked(beta, &mut result, alpha, Op::Transpose(&a_t), &x, block_size)
+                .unwrap();
+            prop_assert_eq!(&result, &expected);
+        }
+    }
 }
@@ -12,13 +12,40 @@
 //! are not lower-triangular or upper-triangular, it is first recommended to perform a matrix
 //! factorization or decomposition of some kind. See the [factorization](crate::factorization)
 //! module for a look at what factorizations are provided by this crate.
+//!
+//! Every solver is provided for both storage orders and comes in two flavours: one that takes the
+//! right hand side by value and returns the solution, and one with the `_mut` suffix that
+//! overwrites a borrowed right hand side with the solution. The right hand side can be any dense
+//! matrix, so single vectors (e.g. `DVector`) and multiple right hand sides (e.g. `DMatrix`) are
+//! handled by the same functions. The `_mut` variants additionally take a [`Diagonal`], which can
+//! be used to assume a unit diagonal without storing it (as is common for the `L` factor of an
+//! LU-style factorization).
 
 use crate::{
-    cs::{CompressedColumnStorage, CompressedRowStorage, CsMatrix},
+    cs::{CompressedColumnStorage, CompressedRowStorage, Compression, CsMatrix},
     error::{OperationError, OperationErrorKind},
 };
 use nalgebra::{Dim, Matrix, RawStorage, RawStorageMut, RealField};
-use std::{borrow::Borrow, cmp::Ordering};
+use std::borrow::Borrow;
+
+/// How the diagonal of a triangular matrix is treated by the triangular solvers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diagonal {
+    /// The diagonal is read from the matrix. A diagonal entry that is missing from the sparsity
+    /// pattern or explicitly stored as zero makes the matrix singular.
+    NonUnit,
+
+    /// Every diagonal entry is assumed to be one. Any diagonal entries stored in the matrix are
+    /// ignored and need not be present in the sparsity pattern.
+    Unit,
+}
+
+/// Which triangle of the matrix participates in a solve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Triangle {
+    Lower,
+    Upper,
+}
 
 /// Sparse-dense matrix solver for lower-triangular CSC matrices and a dense right hand side.
 ///
@@ -50,78 +77,49 @@ where
     MI: Borrow<[usize]>,
     D: Borrow<[T]>,
 {
-    let (nrows, ncols) = csc.shape();
-
-    if nrows != ncols {
-        return Err(OperationError::from_kind_and_message(
-            OperationErrorKind::InvalidPattern,
-            String::from("Lefthand matrix is not square."),
-        ));
-    }
-
-    let (dense_rows, dense_cols) = dense.shape();
-
-    if dense_rows != ncols {
-        return Err(OperationError::from_kind_and_message(
-            OperationErrorKind::InvalidPattern,
-            format!(
-                "The righthand matrix has {} rows but {} rows are needed to solve this system.",
-                dense_rows, ncols
-            ),
-        ));
-    }
-
-    for j in 0..dense_cols {
-        let mut dense_column = dense.column_mut(j);
-
-        for (k, mut lane) in csc.iter().enumerate() {
-            let mut diag = None;
-
-            // We first get the diagonal value, ignoring everything above it.
-            while let Some((i, val)) = lane.next() {
-                match i.cmp(&k) {
-                    Ordering::Less => {
-                        // Ignore every value above the diagonal
-                        continue;
-                    }
-
-                    Ordering::Equal => {
-                        // We found the diagonal, break out.
-                        diag = Some(val.clone());
-                        break;
-                    }
-
-                    Ordering::Greater => {
-                        // The diagonal doesn't exist. Since we know the pattern is sorted, and
-                        // we're greater than the index we're searching for, we know that the
-                        // diagonal was not stored (i.e. it is zero).
-                        break;
-                    }
-                }
-            }
-
-            if let Some(a_kk) = diag {
-                dense_column[k] /= a_kk;
-
-                let x_kj = dense_column[k].clone();
-
-                for (i, l_ik) in lane {
-                    let x_ij = &mut dense_column[i];
-                    *x_ij -= l_ik.clone() * x_kj.clone();
-                }
+    spsolve_lower_triangular_csc_dense_mut(csc, &mut dense, Diagonal::NonUnit)?;
+    Ok(dense)
+}
 
-                dense_column[k] = x_kj;
-            } else {
-                // If the diagonal was never found (was None) then that can only mean the matrix is
-                // singular.
-                return Err(OperationError::from_kind_and_message(
-                    OperationErrorKind::Singular,
-                    String::from("Matrix contains at least one diagonal entry that is zero."),
-                ));
-            }
-        }
-    }
+/// Sparse-dense matrix solver for lower-triangular CSR matrices and a dense right hand side.
+///
+/// This is the CSR equivalent of [`spsolve_lower_triangular_csc_dense`], and has the same
+/// requirements and errors.
+pub fn spsolve_lower_triangular_csr_dense<T, R, C, S, MO, MI, D>(
+    csr: CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    mut dense: Matrix<T, R, C, S>,
+) -> Result<Matrix<T, R, C, S>, OperationError>
+where
+    T: RealField,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C> + RawStorageMut<T, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    spsolve_lower_triangular_csr_dense_mut(csr, &mut dense, Diagonal::NonUnit)?;
+    Ok(dense)
+}
 
+/// Sparse-dense matrix solver for upper-triangular CSC matrices and a dense right hand side.
+///
+/// This is the CSC equivalent of [`spsolve_upper_triangular_csr_dense`], and has the same
+/// requirements and errors.
+pub fn spsolve_upper_triangular_csc_dense<T, R, C, S, MO, MI, D>(
+    csc: CsMatrix<T, MO, MI, D, CompressedColumnStorage>,
+    mut dense: Matrix<T, R, C, S>,
+) -> Result<Matrix<T, R, C, S>, OperationError>
+where
+    T: RealField,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C> + RawStorageMut<T, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    spsolve_upper_triangular_csc_dense_mut(csc, &mut dense, Diagonal::NonUnit)?;
     Ok(dense)
 }
 
@@ -155,7 +153,143 @@ where
     MI: Borrow<[usize]>,
     D: Borrow<[T]>,
 {
-    let (nrows, ncols) = csr.shape();
+    spsolve_upper_triangular_csr_dense_mut(csr, &mut dense, Diagonal::NonUnit)?;
+    Ok(dense)
+}
+
+/// In-place solver for lower-triangular CSC matrices and a dense right hand side.
+///
+/// Overwrites `dense` (`B`) with the solution `x` of `A x = B`, treating the diagonal of `A` as
+/// described by `diagonal`. Values in the strict upper triangle of `A` are ignored.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` is not
+/// square, or if `B` has an invalid number of rows.
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::Singular` if `diagonal` is
+/// [`Diagonal::NonUnit`] and `A` has a zero (or missing) diagonal entry.
+///
+/// All checks happen before any work is done, so `dense` is left untouched when an error is
+/// returned.
+pub fn spsolve_lower_triangular_csc_dense_mut<T, R, C, S, MO, MI, D>(
+    csc: CsMatrix<T, MO, MI, D, CompressedColumnStorage>,
+    dense: &mut Matrix<T, R, C, S>,
+    diagonal: Diagonal,
+) -> Result<(), OperationError>
+where
+    T: RealField,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C> + RawStorageMut<T, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    let _span = span!("spsolve_lower_triangular", n = csc.nrows(), nnz = csc.nnz());
+    solve_triangular_mut(&csc, dense, Triangle::Lower, true, diagonal)
+}
+
+/// In-place solver for lower-triangular CSR matrices and a dense right hand side.
+///
+/// See [`spsolve_lower_triangular_csc_dense_mut`] for the semantics and errors.
+pub fn spsolve_lower_triangular_csr_dense_mut<T, R, C, S, MO, MI, D>(
+    csr: CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    dense: &mut Matrix<T, R, C, S>,
+    diagonal: Diagonal,
+) -> Result<(), OperationError>
+where
+    T: RealField,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C> + RawStorageMut<T, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    let _span = span!("spsolve_lower_triangular", n = csr.nrows(), nnz = csr.nnz());
+    solve_triangular_mut(&csr, dense, Triangle::Lower, false, diagonal)
+}
+
+/// In-place solver for upper-triangular CSC matrices and a dense right hand side.
+///
+/// Overwrites `dense` (`B`) with the solution `x` of `A x = B`, treating the diagonal of `A` as
+/// described by `diagonal`. Values in the strict lower triangle of `A` are ignored.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `A` is not
+/// square, or if `B` has an invalid number of rows.
+///
+/// Returns an [`OperationError`] with kind `OperationErrorKind::Singular` if `diagonal` is
+/// [`Diagonal::NonUnit`] and `A` has a zero (or missing) diagonal entry.
+///
+/// All checks happen before any work is done, so `dense` is left untouched when an error is
+/// returned.
+pub fn spsolve_upper_triangular_csc_dense_mut<T, R, C, S, MO, MI, D>(
+    csc: CsMatrix<T, MO, MI, D, CompressedColumnStorage>,
+    dense: &mut Matrix<T, R, C, S>,
+    diagonal: Diagonal,
+) -> Result<(), OperationError>
+where
+    T: RealField,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C> + RawStorageMut<T, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    let _span = span!("spsolve_upper_triangular", n = csc.nrows(), nnz = csc.nnz());
+    solve_triangular_mut(&csc, dense, Triangle::Upper, true, diagonal)
+}
+
+/// In-place solver for upper-triangular CSR matrices and a dense right hand side.
+///
+/// See [`spsolve_upper_triangular_csc_dense_mut`] for the semantics and errors.
+pub fn spsolve_upper_triangular_csr_dense_mut<T, R, C, S, MO, MI, D>(
+    csr: CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    dense: &mut Matrix<T, R, C, S>,
+    diagonal: Diagonal,
+) -> Result<(), OperationError>
+where
+    T: RealField,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C> + RawStorageMut<T, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    let _span = span!("spsolve_upper_triangular", n = csr.nrows(), nnz = csr.nnz());
+    solve_triangular_mut(&csr, dense, Triangle::Upper, false, diagonal)
+}
+
+/// Shared implementation of the triangular solvers.
+///
+/// When the lanes of `cs` are columns (`column_oriented`), each solved entry of `x` is scattered
+/// into the entries that still need to be solved. When the lanes are rows, each entry of `x` is
+/// instead gathered from the entries that have already been solved. In both cases lanes are
+/// visited from the top of the matrix for lower-triangular solves, and from the bottom for
+/// upper-triangular solves.
+fn solve_triangular_mut<T, R, C, S, MO, MI, D, Cmp>(
+    cs: &CsMatrix<T, MO, MI, D, Cmp>,
+    dense: &mut Matrix<T, R, C, S>,
+    triangle: Triangle,
+    column_oriented: bool,
+    diagonal: Diagonal,
+) -> Result<(), OperationError>
+where
+    T: RealField,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C> + RawStorageMut<T, R, C>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    Cmp: Compression,
+{
+    let (nrows, ncols) = cs.shape();
 
     if nrows != ncols {
         return Err(OperationError::from_kind_and_message(
@@ -176,53 +310,207 @@ where
         ));
     }
 
-    for j in (0..dense_cols).rev() {
-        let mut dense_column = dense.column_mut(j);
-
-        for (k, mut lane) in csr.iter().enumerate().rev() {
-            let mut diag = None;
+    let n = nrows;
 
-            // We first get the diagonal value, ignoring everything below it.
-            while let Some((i, val)) = lane.next() {
-                match i.cmp(&k) {
-                    Ordering::Less => {
-                        // Ignore every value below the diagonal
-                        continue;
-                    }
-
-                    Ordering::Equal => {
-                        // We found the diagonal, break out.
-                        diag = Some(val.clone());
-                        break;
-                    }
+    // Gather the diagonal up front, so that a singular matrix is reported before the right hand
+    // side has been modified.
+    let diagonal_values = match diagonal {
+        Diagonal::Unit => None,
+        Diagonal::NonUnit => {
+            let mut values = Vec::with_capacity(n);
 
-                    Ordering::Greater => {
-                        // The diagonal doesn't exist. Since we know the pattern is sorted, and
-                        // we're greater than the index we're searching for, we know that the
-                        // diagonal was not stored (i.e. it is zero).
-                        break;
+            for (k, mut lane) in cs.iter().enumerate() {
+                match lane.find(|&(i, _)| i == k) {
+                    Some((_, a_kk)) if !a_kk.is_zero() => values.push(a_kk.clone()),
+                    _ => {
+                        return Err(OperationError::from_kind_and_message(
+                            OperationErrorKind::Singular,
+                            String::from(
+                                "Matrix contains at least one diagonal entry that is zero.",
+                            ),
+                        ));
                     }
                 }
             }
 
-            if let Some(a_kk) = diag {
-                let mut x_kk = dense_column[k].clone();
+            Some(values)
+        }
+    };
+
+    // Entries in a lane that refer to parts of x that are already solved when lane `k` is
+    // visited, and entries that refer to parts that are still to be solved.
+    let is_solved = |i: usize, k: usize| match triangle {
+        Triangle::Lower => i < k,
+        Triangle::Upper => i > k,
+    };
+    let is_pending = |i: usize, k: usize| match triangle {
+        Triangle::Lower => i > k,
+        Triangle::Upper => i < k,
+    };
+
+    for step in 0..n {
+        let k = match triangle {
+            Triangle::Lower => step,
+            Triangle::Upper => n - 1 - step,
+        };
 
-                for (i, a_ki) in lane {
-                    x_kk -= a_ki.clone() * dense_column[i].clone();
+        let lane = cs.get_lane(k).unwrap();
+
+        for j in 0..dense_cols {
+            let mut x = dense.column_mut(j);
+
+            if column_oriented {
+                if let Some(values) = &diagonal_values {
+                    x[k] /= values[k].clone();
                 }
 
-                dense_column[k] = x_kk / a_kk;
+                let x_k = x[k].clone();
+
+                for (i, a_ik) in lane.clone().filter(|&(i, _)| is_pending(i, k)) {
+                    x[i] -= a_ik.clone() * x_k.clone();
+                }
             } else {
-                // If the diagonal was never found (was None) then that can only mean the matrix is
-                // singular.
-                return Err(OperationError::from_kind_and_message(
-                    OperationErrorKind::Singular,
-                    String::from("Matrix contains at least one diagonal entry that is zero."),
-                ));
+                let mut x_k = x[k].clone();
+
+                for (i, a_ki) in lane.clone().filter(|&(i, _)| is_solved(i, k)) {
+                    x_k -= a_ki.clone() * x[i].clone();
+                }
+
+                if let Some(values) = &diagonal_values {
+                    x_k /= values[k].clone();
+                }
+
+                x[k] = x_k;
             }
         }
     }
 
-    Ok(dense)
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cs::{CscMatrix, CsrMatrix},
+        proptest::*,
+        reference::{solve_lower_triangular, solve_upper_triangular},
+    };
+    use nalgebra::{DMatrix, DVector};
+    use proptest::prelude::*;
+
+    /// Lower-triangular CSR matrices with a non-zero diagonal, alongside a dense right hand side.
+    fn lower_triangular_system() -> impl Strategy<Value = (CsrMatrix<f64>, DMatrix<f64>)> {
+        (1usize..=6)
+            .prop_flat_map(|n| {
+                (
+                    csr(-5.0..=5.0, n..=n, n..=n, PROPTEST_MAX_NNZ),
+                    proptest::collection::vec(-5.0..=5.0, n * 3),
+                )
+            })
+            .prop_map(|(csr, rhs)| {
+                let n = csr.nrows();
+                let mut dense = DMatrix::from(&csr).lower_triangle();
+                dense.fill_diagonal(10.0);
+
+                (CsrMatrix::from(&dense), DMatrix::from_vec(n, 3, rhs))
+            })
+    }
+
+    #[test]
+    fn unit_diagonal_ignores_stored_diagonal() {
+        // [ 4 0 ]
+        // [ 2 0 ]
+        let csr = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![0, 0], vec![4.0, 2.0]).unwrap();
+        let csc = CscMatrix::from(csr.clone());
+        let expected = DVector::from_vec(vec![1.0, 1.0]);
+
+        let mut b = DVector::from_vec(vec![1.0, 3.0]);
+        spsolve_lower_triangular_csr_dense_mut(csr.to_view(), &mut b, Diagonal::Unit).unwrap();
+        assert_eq!(b, expected);
+
+        let mut b = DVector::from_vec(vec![1.0, 3.0]);
+        spsolve_lower_triangular_csc_dense_mut(csc.to_view(), &mut b, Diagonal::Unit).unwrap();
+        assert_eq!(b, expected);
+    }
+
+    #[test]
+    fn errors_leave_right_hand_side_untouched() {
+        let csr = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![0, 0], vec![4.0, 2.0]).unwrap();
+        let original = DVector::from_vec(vec![1.0, 3.0]);
+
+        let mut b = original.clone();
+        let err = spsolve_lower_triangular_csr_dense_mut(csr.to_view(), &mut b, Diagonal::NonUnit)
+            .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::Singular));
+        assert_eq!(b, original);
+
+        let zero_diagonal =
+            CscMatrix::try_from_parts(2, 2, vec![0, 1], vec![0, 1], vec![1.0, 0.0]).unwrap();
+        let err = spsolve_upper_triangular_csc_dense_mut(
+            zero_diagonal.to_view(),
+            &mut b,
+            Diagonal::NonUnit,
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::Singular));
+        assert_eq!(b, original);
+
+        let mut short = DVector::from_vec(vec![1.0]);
+        let err = spsolve_lower_triangular_csr_dense_mut(csr.to_view(), &mut short, Diagonal::Unit)
+            .unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+        assert_eq!(short, DVector::from_vec(vec![1.0]));
+    }
+
+    proptest! {
+        #[test]
+        fn solves_agree_with_reference_for_both_storage_orders((l, b) in lower_triangular_system()) {
+            let l_csc = CscMatrix::from(l.clone());
+            // The transpose of a CSR matrix is a CSC matrix, and vice versa.
+            let u_csc = l.transpose();
+            let u = l_csc.transpose();
+
+            let lower = solve_lower_triangular(&l, &b).unwrap();
+            let upper = solve_upper_triangular(&u, &b).unwrap();
+
+            let close = |x: &DMatrix<f64>, y: &DMatrix<f64>| (x - y).amax() < 1e-9;
+
+            prop_assert!(close(&spsolve_lower_triangular_csr_dense(l.to_view(), b.clone()).unwrap(), &lower));
+            prop_assert!(close(&spsolve_lower_triangular_csc_dense(l_csc.to_view(), b.clone()).unwrap(), &lower));
+            prop_assert!(close(&spsolve_upper_triangular_csr_dense(u.to_view(), b.clone()).unwrap(), &upper));
+            prop_assert!(close(&spsolve_upper_triangular_csc_dense(u_csc.to_view(), b.clone()).unwrap(), &upper));
+        }
+
+        #[test]
+        fn vector_solves_match_matrix_solves((l, b) in lower_triangular_system()) {
+            let solved = spsolve_lower_triangular_csr_dense(l.to_view(), b.clone()).unwrap();
+
+            for j in 0..b.ncols() {
+                let mut column = b.column(j).into_owned();
+                spsolve_lower_triangular_csc_dense_mut(
+                    CscMatrix::from(l.clone()).to_view(),
+                    &mut column,
+                    Diagonal::NonUnit,
+                ).unwrap();
+
+                prop_assert!((column - solved.column(j)).amax() < 1e-9);
+            }
+        }
+
+        #[test]
+        fn unit_diagonal_solves_the_unit_triangular_system((l, b) in lower_triangular_system()) {
+            let mut l_unit = DMatrix::from(&l);
+            l_unit.fill_diagonal(1.0);
+            let u_unit = l_unit.transpose();
+
+            let mut x = b.clone();
+            spsolve_lower_triangular_csr_dense_mut(l.to_view(), &mut x, Diagonal::Unit).unwrap();
+            prop_assert!((&l_unit * &x - &b).amax() < 1e-6);
+
+            let mut x = b.clone();
+            spsolve_upper_triangular_csc_dense_mut(l.transpose(), &mut x, Diagonal::Unit).unwrap();
+            prop_assert!((&u_unit * &x - &b).amax() < 1e-6);
+        }
+    }
 }
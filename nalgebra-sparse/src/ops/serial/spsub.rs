@@ -14,18 +14,26 @@
 //!
 //! One should prefer to pose their problems as a combination of CSX <-> CSX subtractions, or
 //! dense-sparse subtractions.
+//!
+//! The sparse-sparse functions first count the entries of the result, so that its buffers are
+//! allocated with exactly the required size. Each of them also has a `_with_buffers` variant
+//! (e.g. [`spsub_csr_csr_with_buffers`]) which writes the result into caller-provided buffers
+//! instead, so that repeated subtractions can reuse the same allocations.
 
 use crate::{
-    convert::utils::CountToOffsetIter,
-    cs::{CompressedColumnStorage, CompressedRowStorage, CsMatrix, CscMatrix, CsrMatrix},
+    cs::{
+        CompressedColumnStorage, CompressedRowStorage, Compression, CsMatrix, CscMatrix, CsrMatrix,
+    },
     error::{OperationError, OperationErrorKind},
+    ops::serial::merge::{
+        collect_into_buffers, combine_identical_patterns, merge_lanes, union_lane_counts, Merged,
+    },
 };
 use nalgebra::{Dim, Matrix, RawStorage, RawStorageMut, Scalar};
 use num_traits::Zero;
 use std::{
-    borrow::Borrow,
-    cmp::Ordering,
-    ops::{Add, Neg, Sub},
+    borrow::{Borrow, BorrowMut},
+    ops::{Add, Neg, Sub, SubAssign},
 };
 
 /// Sparse-sparse matrix subtraction.
@@ -41,6 +49,36 @@ pub fn spsub_csr_csc<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
     csr: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
     csc: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
 ) -> Result<CsrMatrix<<T1 as Sub<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Into<<T1 as Sub<T2>>::Output> + Sub<T2> + Zero,
+    T2: Scalar,
+    <T1 as Sub<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    spsub_csr_csc_with_buffers(csr, csc, Default::default())
+}
+
+/// Sparse-sparse matrix subtraction into caller-provided buffers.
+///
+/// This is the same as [`spsub_csr_csc`], except that the offsets, indices and data of the
+/// result are written into `buffers`, e.g. the [`disassemble`](CsMatrix::disassemble)d parts of a
+/// matrix that is no longer needed. The buffers are cleared, but their allocations are reused, so
+/// repeatedly computing subtractions of the same size does not allocate.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn spsub_csr_csc_with_buffers<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    csr: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
+    csc: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+    buffers: (Vec<usize>, Vec<usize>, Vec<<T1 as Sub<T2>>::Output>),
+) -> Result<CsrMatrix<<T1 as Sub<T2>>::Output>, OperationError>
 where
     T1: Scalar + Into<<T1 as Sub<T2>>::Output> + Sub<T2> + Zero,
     T2: Scalar,
@@ -62,34 +100,27 @@ where
         ));
     }
 
-    let mut left_iter = csr.triplet_iter();
-    let mut right_iter = csc
+    let counts = union_lane_counts(
+        lrows,
+        csr.triplet_iter().map(|(i, j, _)| (i, j)),
+        csc.minor_lane_iter()
+            .enumerate()
+            .flat_map(|(i, lane)| lane.map(move |(j, _)| (i, j))),
+    );
+
+    let left_iter = csr.triplet_iter();
+    let right_iter = csc
         .minor_lane_iter()
         .enumerate()
         .flat_map(|(i, lane)| lane.map(move |(j, value)| (i, j, value)));
 
-    let left_val = left_iter.next();
-    let right_val = right_iter.next();
-
-    let added_triplets = TripletSubtractionIter {
-        left_val,
-        right_val,
-        left_iter,
-        right_iter,
-    };
-
-    let max_nnz = csr.nnz() + csc.nnz();
-    let mut counts = vec![0; lrows];
-    let mut indices = Vec::with_capacity(max_nnz);
-    let mut data = Vec::with_capacity(max_nnz);
-
-    for (i, j, v) in added_triplets {
-        counts[i] += 1;
-        indices.push(j);
-        data.push(v);
-    }
+    let added_triplets = merge_lanes(left_iter, right_iter, |entry| match entry {
+        Merged::Left(l) => l.clone().into(),
+        Merged::Right(r) => T1::zero() - r.clone(),
+        Merged::Both(l, r) => l.clone() - r.clone(),
+    });
 
-    let offsets = CountToOffsetIter::new(counts).collect();
+    let (offsets, indices, data) = collect_into_buffers(counts, added_triplets, buffers);
 
     Ok(unsafe { CsMatrix::from_parts_unchecked(lrows, lcols, offsets, indices, data) })
 }
@@ -107,6 +138,36 @@ pub fn spsub_csc_csr<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
     csc: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
     csr: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
 ) -> Result<CscMatrix<<T1 as Sub<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Into<<T1 as Sub<T2>>::Output> + Sub<T2> + Zero,
+    T2: Scalar,
+    <T1 as Sub<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    spsub_csc_csr_with_buffers(csc, csr, Default::default())
+}
+
+/// Sparse-sparse matrix subtraction into caller-provided buffers.
+///
+/// This is the same as [`spsub_csc_csr`], except that the offsets, indices and data of the
+/// result are written into `buffers`, e.g. the [`disassemble`](CsMatrix::disassemble)d parts of a
+/// matrix that is no longer needed. The buffers are cleared, but their allocations are reused, so
+/// repeatedly computing subtractions of the same size does not allocate.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn spsub_csc_csr_with_buffers<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    csc: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
+    csr: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+    buffers: (Vec<usize>, Vec<usize>, Vec<<T1 as Sub<T2>>::Output>),
+) -> Result<CscMatrix<<T1 as Sub<T2>>::Output>, OperationError>
 where
     T1: Scalar + Into<<T1 as Sub<T2>>::Output> + Sub<T2> + Zero,
     T2: Scalar,
@@ -128,34 +189,27 @@ where
         ));
     }
 
-    let mut left_iter = csc.triplet_iter();
-    let mut right_iter = csr
+    let counts = union_lane_counts(
+        lcols,
+        csc.triplet_iter().map(|(i, j, _)| (i, j)),
+        csr.minor_lane_iter()
+            .enumerate()
+            .flat_map(|(i, lane)| lane.map(move |(j, _)| (i, j))),
+    );
+
+    let left_iter = csc.triplet_iter();
+    let right_iter = csr
         .minor_lane_iter()
         .enumerate()
         .flat_map(|(i, lane)| lane.map(move |(j, value)| (i, j, value)));
 
-    let left_val = left_iter.next();
-    let right_val = right_iter.next();
-
-    let added_triplets = TripletSubtractionIter {
-        left_val,
-        right_val,
-        left_iter,
-        right_iter,
-    };
-
-    let max_nnz = csr.nnz() + csc.nnz();
-    let mut counts = vec![0; lcols];
-    let mut indices = Vec::with_capacity(max_nnz);
-    let mut data = Vec::with_capacity(max_nnz);
-
-    for (i, j, v) in added_triplets {
-        counts[i] += 1;
-        indices.push(j);
-        data.push(v);
-    }
+    let added_triplets = merge_lanes(left_iter, right_iter, |entry| match entry {
+        Merged::Left(l) => l.clone().into(),
+        Merged::Right(r) => T1::zero() - r.clone(),
+        Merged::Both(l, r) => l.clone() - r.clone(),
+    });
 
-    let offsets = CountToOffsetIter::new(counts).collect();
+    let (offsets, indices, data) = collect_into_buffers(counts, added_triplets, buffers);
 
     Ok(unsafe { CsMatrix::from_parts_unchecked(lrows, lcols, offsets, indices, data) })
 }
@@ -172,6 +226,36 @@ pub fn spsub_csc_csc<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
     lhs: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
     rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
 ) -> Result<CscMatrix<<T1 as Sub<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Into<<T1 as Sub<T2>>::Output> + Sub<T2> + Zero,
+    T2: Scalar,
+    <T1 as Sub<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    spsub_csc_csc_with_buffers(lhs, rhs, Default::default())
+}
+
+/// Sparse-sparse matrix subtraction into caller-provided buffers.
+///
+/// This is the same as [`spsub_csc_csc`], except that the offsets, indices and data of the
+/// result are written into `buffers`, e.g. the [`disassemble`](CsMatrix::disassemble)d parts of a
+/// matrix that is no longer needed. The buffers are cleared, but their allocations are reused, so
+/// repeatedly computing subtractions of the same size does not allocate.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn spsub_csc_csc_with_buffers<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+    buffers: (Vec<usize>, Vec<usize>, Vec<<T1 as Sub<T2>>::Output>),
+) -> Result<CscMatrix<<T1 as Sub<T2>>::Output>, OperationError>
 where
     T1: Scalar + Into<<T1 as Sub<T2>>::Output> + Sub<T2> + Zero,
     T2: Scalar,
@@ -193,31 +277,22 @@ where
         ));
     }
 
-    let mut left_iter = lhs.triplet_iter();
-    let mut right_iter = rhs.triplet_iter();
+    let counts = union_lane_counts(
+        lcols,
+        lhs.triplet_iter().map(|(i, j, _)| (i, j)),
+        rhs.triplet_iter().map(|(i, j, _)| (i, j)),
+    );
 
-    let left_val = left_iter.next();
-    let right_val = right_iter.next();
+    let left_iter = lhs.triplet_iter();
+    let right_iter = rhs.triplet_iter();
 
-    let added_triplets = TripletSubtractionIter {
-        left_val,
-        right_val,
-        left_iter,
-        right_iter,
-    };
+    let added_triplets = merge_lanes(left_iter, right_iter, |entry| match entry {
+        Merged::Left(l) => l.clone().into(),
+        Merged::Right(r) => T1::zero() - r.clone(),
+        Merged::Both(l, r) => l.clone() - r.clone(),
+    });
 
-    let max_nnz = lhs.nnz() + rhs.nnz();
-    let mut counts = vec![0; lcols];
-    let mut indices = Vec::with_capacity(max_nnz);
-    let mut data = Vec::with_capacity(max_nnz);
-
-    for (i, j, v) in added_triplets {
-        counts[i] += 1;
-        indices.push(j);
-        data.push(v);
-    }
-
-    let offsets = CountToOffsetIter::new(counts).collect();
+    let (offsets, indices, data) = collect_into_buffers(counts, added_triplets, buffers);
 
     Ok(unsafe { CsMatrix::from_parts_unchecked(lrows, lcols, offsets, indices, data) })
 }
@@ -245,7 +320,94 @@ where
     D1: Borrow<[T1]>,
     D2: Borrow<[T2]>,
 {
-    Ok(spsub_csc_csc(lhs.transpose(), rhs.transpose())?.transpose_owned())
+    spsub_csr_csr_with_buffers(lhs, rhs, Default::default())
+}
+
+/// Sparse-sparse matrix subtraction into caller-provided buffers.
+///
+/// This is the same as [`spsub_csr_csr`], except that the offsets, indices and data of the
+/// result are written into `buffers`, e.g. the [`disassemble`](CsMatrix::disassemble)d parts of a
+/// matrix that is no longer needed. The buffers are cleared, but their allocations are reused, so
+/// repeatedly computing subtractions of the same size does not allocate.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
+pub fn spsub_csr_csr_with_buffers<T1, T2, MO1, MO2, MI1, MI2, D1, D2>(
+    lhs: CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+    buffers: (Vec<usize>, Vec<usize>, Vec<<T1 as Sub<T2>>::Output>),
+) -> Result<CsrMatrix<<T1 as Sub<T2>>::Output>, OperationError>
+where
+    T1: Scalar + Into<<T1 as Sub<T2>>::Output> + Sub<T2> + Zero,
+    T2: Scalar,
+    <T1 as Sub<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    let (lrows, lcols) = lhs.shape();
+    let (rrows, rcols) = rhs.shape();
+
+    if lrows != rrows || lcols != rcols {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("The two matrices have differing shapes (both should be M × N)"),
+        ));
+    }
+
+    let counts = union_lane_counts(
+        lrows,
+        lhs.triplet_iter().map(|(i, j, _)| (i, j)),
+        rhs.triplet_iter().map(|(i, j, _)| (i, j)),
+    );
+
+    let left_iter = lhs.triplet_iter();
+    let right_iter = rhs.triplet_iter();
+
+    let added_triplets = merge_lanes(left_iter, right_iter, |entry| match entry {
+        Merged::Left(l) => l.clone().into(),
+        Merged::Right(r) => T1::zero() - r.clone(),
+        Merged::Both(l, r) => l.clone() - r.clone(),
+    });
+
+    let (offsets, indices, data) = collect_into_buffers(counts, added_triplets, buffers);
+
+    Ok(unsafe { CsMatrix::from_parts_unchecked(lrows, lcols, offsets, indices, data) })
+}
+
+/// In-place sparse-sparse matrix subtraction, for matrices with identical sparsity patterns.
+///
+/// This computes `lhs = lhs - rhs` by updating the values of `lhs` directly, without allocating
+/// a new matrix. This is much cheaper than [`spsub_csr_csr`] and friends when the same pattern is
+/// reused over and over, e.g. when summing many contributions to a stiffness matrix whose pattern
+/// was assembled up front. Both matrices must have the same compression.
+///
+/// # Errors
+///
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape
+/// and sparsity pattern. `lhs` is left unchanged in that case.
+pub fn spsub_assign<T1, T2, MO1, MO2, MI1, MI2, D1, D2, C>(
+    lhs: &mut CsMatrix<T1, MO1, MI1, D1, C>,
+    rhs: CsMatrix<T2, MO2, MI2, D2, C>,
+) -> Result<(), OperationError>
+where
+    T1: Scalar + SubAssign<T2>,
+    T2: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: BorrowMut<[T1]>,
+    D2: Borrow<[T2]>,
+    C: Compression,
+{
+    combine_identical_patterns(lhs, rhs, |l, r| *l -= r.clone())
 }
 
 /// Dense-sparse matrix subtraction.
@@ -414,7 +576,7 @@ where
         *dense_val = neg;
     }
 
-    for (col, row, val) in csr.triplet_iter() {
+    for (row, col, val) in csr.triplet_iter() {
         let current = dense.index((row, col)).clone();
         *dense.index_mut((row, col)) = current + val.clone();
     }
@@ -422,113 +584,232 @@ where
     Ok(dense)
 }
 
-/// Helper type which defines an iterator that takes two triplet iterators (in the same major
-/// ordering) and outputs a single triplet iterator of `(major, minor, owned_value)`. This iterator
-/// combines duplicate entries via subtraction i.e. if we have two entries with the same major and
-/// minor indices we add the two values together.
+/// Sparse matrix subtraction for any combination of storage formats.
 ///
-/// This iterator combinator is not terribly well bound, which is why it is private! First and
-/// foremost, `left_val` and `right_val` need to not be `None` immediately, or this will always
-/// return `None`. Secondly, this iterator is difficult to use directly outside of the above
-/// `spsub_csx_csx` functions because it relies on the fact that both iterators are outputting
-/// triplets in the same major ordering. This means that if you want to add a CSC and CSR matrix,
-/// you cannot do the following:
+/// This trait is implemented for every pair of operands supported by the `spsub_x_y` functions in
+/// this module, so that [`spsub`] can pick the right one from the types of its arguments. The
+/// output format is the same as that of the function it dispatches to.
+pub trait SpSub<Rhs> {
+    /// The type of the result of the subtraction.
+    type Output;
+
+    /// Computes `self - rhs`.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same way as the `spsub_x_y` function for the formats of `self` and `rhs`.
+    fn spsub(self, rhs: Rhs) -> Result<Self::Output, OperationError>;
+}
+
+/// Computes `lhs - rhs` for any supported combination of CSR, CSC and dense operands.
 ///
-/// ```ignore
-/// let mut left_iter = csr.triplet_iter();
-/// let mut right_iter = csc.triplet_iter();
+/// This dispatches on the types of `lhs` and `rhs` to the matching `spsub_x_y` function in this
+/// module, e.g. [`spsub_csr_csc`] for a CSR and a CSC matrix.
 ///
-/// let left_val = left_iter.next();
-/// let right_val = right_iter.next();
-/// ```
+/// # Errors
 ///
-/// Because the major and minor ordering of those two are reversed. Instead, you'll want to do
-/// something like the following:
+/// This function fails and produces an [`OperationError`] with kind
+/// [`OperationErrorKind::InvalidPattern`] if the two matrices do not have the exact same shape.
 ///
-/// ```ignore
-/// let mut left_iter = csr.triplet_iter();
-/// let mut right_iter = csc
-///     .minor_lane_iter()
-///     .enumerate()
-///     .flat_map(|(i, lane)| lane.map(move |(j, value)| (i, j, value)));
+/// # Example
 ///
-/// let left_val = left_iter.next();
-/// let right_val = right_iter.next();
-/// ```
+/// ```rust
+/// use nalgebra_sparse::{
+///     cs::{CscMatrix, CsrMatrix},
+///     ops::serial::spsub::spsub,
+/// };
 ///
-/// The minor lane iterator is necessary so that you can both traverse the two matrices in
-/// the equivalent order (which is needed for the boolean logic below to work) as well as compare
-/// `i` to `i` and `j` to `j` between both left and right values.
+/// let csr = CsrMatrix::<i32>::identity(3);
+/// let csc = CscMatrix::<i32>::identity(3);
 ///
-/// Of course, because subtraction is commutative, one could just reverse the order of the
-/// arguments and do the same trick; however, it means that the final result will be a column-major
-/// matrix instead of a row-major matrix.
-struct TripletSubtractionIter<'a, TL, TR, IL, IR>
+/// let result = spsub(csr.to_view(), csc.to_view()).unwrap();
+/// assert_eq!(result.shape(), (3, 3));
+/// ```
+pub fn spsub<L, R>(lhs: L, rhs: R) -> Result<L::Output, OperationError>
+where
+    L: SpSub<R>,
+{
+    lhs.spsub(rhs)
+}
+
+impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2> SpSub<CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>>
+    for CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>
 where
-    TL: Scalar + Into<<TL as Sub<TR>>::Output> + Sub<TR> + Zero,
-    TR: Scalar,
-    IL: Iterator<Item = (usize, usize, &'a TL)>,
-    IR: Iterator<Item = (usize, usize, &'a TR)>,
+    T1: Scalar + Into<<T1 as Sub<T2>>::Output> + Sub<T2> + Zero,
+    T2: Scalar,
+    <T1 as Sub<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
 {
-    left_val: Option<(usize, usize, &'a TL)>,
-    right_val: Option<(usize, usize, &'a TR)>,
-    left_iter: IL,
-    right_iter: IR,
+    type Output = CsrMatrix<<T1 as Sub<T2>>::Output>;
+
+    fn spsub(
+        self,
+        rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spsub_csr_csc(self, rhs)
+    }
 }
 
-impl<'a, TL, TR, IL, IR> Iterator for TripletSubtractionIter<'a, TL, TR, IL, IR>
+impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2> SpSub<CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>>
+    for CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>
 where
-    TL: Scalar + Into<<TL as Sub<TR>>::Output> + Sub<TR> + Zero,
-    TR: Scalar,
-    IL: Iterator<Item = (usize, usize, &'a TL)>,
-    IR: Iterator<Item = (usize, usize, &'a TR)>,
+    T1: Scalar + Into<<T1 as Sub<T2>>::Output> + Sub<T2> + Zero,
+    T2: Scalar,
+    <T1 as Sub<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
 {
-    type Item = (usize, usize, <TL as Sub<TR>>::Output);
-
-    fn next(&mut self) -> Option<Self::Item> {
-        match (self.left_val, self.right_val) {
-            // Neither left nor right are exhausted
-            (Some((il, jl, vl)), Some((ir, jr, vr))) => match (il.cmp(&ir), jl.cmp(&jr)) {
-                (Ordering::Less, _) | (Ordering::Equal, Ordering::Less) => {
-                    self.left_val = self.left_iter.next();
-                    Some((il, jl, vl.clone().into()))
-                }
-
-                (Ordering::Greater, _) | (Ordering::Equal, Ordering::Greater) => {
-                    self.right_val = self.right_iter.next();
-                    Some((ir, jr, TL::zero() - vr.clone()))
-                }
-
-                (Ordering::Equal, Ordering::Equal) => {
-                    self.left_val = self.left_iter.next();
-                    self.right_val = self.right_iter.next();
-
-                    Some((il, jl, vl.clone() - vr.clone()))
-                }
-            },
-
-            // Only right is exhausted
-            (Some((i, j, v)), None) => {
-                self.left_val = self.left_iter.next();
-                Some((i, j, v.clone().into()))
-            }
-
-            // Only left is exhausted
-            (None, Some((i, j, v))) => {
-                self.right_val = self.right_iter.next();
-                Some((i, j, TL::zero() - v.clone()))
-            }
-
-            // Both are exhausted
-            (None, None) => None,
-        }
+    type Output = CscMatrix<<T1 as Sub<T2>>::Output>;
+
+    fn spsub(
+        self,
+        rhs: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spsub_csc_csr(self, rhs)
+    }
+}
+
+impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2> SpSub<CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>>
+    for CsMatrix<T1, MO1, MI1, D1, CompressedColumnStorage>
+where
+    T1: Scalar + Into<<T1 as Sub<T2>>::Output> + Sub<T2> + Zero,
+    T2: Scalar,
+    <T1 as Sub<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    type Output = CscMatrix<<T1 as Sub<T2>>::Output>;
+
+    fn spsub(
+        self,
+        rhs: CsMatrix<T2, MO2, MI2, D2, CompressedColumnStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spsub_csc_csc(self, rhs)
+    }
+}
+
+impl<T1, T2, MO1, MO2, MI1, MI2, D1, D2> SpSub<CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>>
+    for CsMatrix<T1, MO1, MI1, D1, CompressedRowStorage>
+where
+    T1: Scalar + Into<<T1 as Sub<T2>>::Output> + Sub<T2> + Zero,
+    T2: Scalar,
+    <T1 as Sub<T2>>::Output: Scalar,
+    MO1: Borrow<[usize]>,
+    MO2: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D1: Borrow<[T1]>,
+    D2: Borrow<[T2]>,
+{
+    type Output = CsrMatrix<<T1 as Sub<T2>>::Output>;
+
+    fn spsub(
+        self,
+        rhs: CsMatrix<T2, MO2, MI2, D2, CompressedRowStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spsub_csr_csr(self, rhs)
+    }
+}
+
+impl<T1, T2, R, C, S, MO, MI, D> SpSub<CsMatrix<T2, MO, MI, D, CompressedColumnStorage>>
+    for Matrix<T1, R, C, S>
+where
+    T1: Scalar + Sub<T2, Output = T1>,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T1, R, C> + RawStorageMut<T1, R, C>,
+    T2: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T2]>,
+{
+    type Output = Matrix<T1, R, C, S>;
+
+    fn spsub(
+        self,
+        rhs: CsMatrix<T2, MO, MI, D, CompressedColumnStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spsub_dense_csc(self, rhs)
+    }
+}
+
+impl<T1, T2, R, C, S, MO, MI, D> SpSub<Matrix<T2, R, C, S>>
+    for CsMatrix<T1, MO, MI, D, CompressedColumnStorage>
+where
+    T2: Scalar + Neg<Output = T2> + Add<T1, Output = T2>,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T2, R, C> + RawStorageMut<T2, R, C>,
+    T1: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+{
+    type Output = Matrix<T2, R, C, S>;
+
+    fn spsub(self, rhs: Matrix<T2, R, C, S>) -> Result<Self::Output, OperationError> {
+        spsub_csc_dense(self, rhs)
+    }
+}
+
+impl<T1, T2, R, C, S, MO, MI, D> SpSub<CsMatrix<T2, MO, MI, D, CompressedRowStorage>>
+    for Matrix<T1, R, C, S>
+where
+    T1: Scalar + Sub<T2, Output = T1>,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T1, R, C> + RawStorageMut<T1, R, C>,
+    T2: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T2]>,
+{
+    type Output = Matrix<T1, R, C, S>;
+
+    fn spsub(
+        self,
+        rhs: CsMatrix<T2, MO, MI, D, CompressedRowStorage>,
+    ) -> Result<Self::Output, OperationError> {
+        spsub_dense_csr(self, rhs)
+    }
+}
+
+impl<T1, T2, R, C, S, MO, MI, D> SpSub<Matrix<T2, R, C, S>>
+    for CsMatrix<T1, MO, MI, D, CompressedRowStorage>
+where
+    T2: Scalar + Neg<Output = T2> + Add<T1, Output = T2>,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T2, R, C> + RawStorageMut<T2, R, C>,
+    T1: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T1]>,
+{
+    type Output = Matrix<T2, R, C, S>;
+
+    fn spsub(self, rhs: Matrix<T2, R, C, S>) -> Result<Self::Output, OperationError> {
+        spsub_csr_dense(self, rhs)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::proptest::*;
+    use crate::{ops::serial::spadd::spadd_csr_csr, proptest::*};
     use matrixcompare::{assert_matrix_eq, prop_assert_matrix_eq};
     use nalgebra::DMatrix;
     use proptest::prelude::*;
@@ -649,7 +930,94 @@ mod tests {
         assert_matrix_eq!(diff, dense_diff);
     }
 
+    #[test]
+    fn spsub_dispatches_on_formats_with_explicit_zeros() {
+        let csr = CsrMatrix::try_from_parts(3, 1, vec![0, 0, 0], vec![0], vec![0]).unwrap();
+        let csc = CscMatrix::from(csr.clone());
+        let dense = DMatrix::from(&csr);
+        let expected = DMatrix::<i32>::zeros(3, 1);
+
+        assert_eq!(csc.nnz(), 1);
+        assert_matrix_eq!(spsub(csr.to_view(), csc.to_view()).unwrap(), expected);
+        assert_matrix_eq!(spsub(csc.to_view(), csr.to_view()).unwrap(), expected);
+        assert_matrix_eq!(spsub(csc.to_view(), csc.to_view()).unwrap(), expected);
+        assert_matrix_eq!(spsub(dense.clone(), csc.to_view()).unwrap(), expected);
+        assert_matrix_eq!(spsub(csr.to_view(), dense).unwrap(), expected);
+    }
+
+    #[test]
+    fn spsub_allocates_exactly_for_overlapping_patterns() {
+        let a = CsrMatrix::<i32>::identity(5);
+        let b = CsrMatrix::<i32>::identity(5);
+
+        let diff = spsub_csr_csr(a.to_view(), b.to_view()).unwrap();
+        let (offsets, indices, data) = diff.disassemble();
+
+        assert_eq!(offsets, vec![0, 1, 2, 3, 4]);
+        assert_eq!(indices.capacity(), 5);
+        assert_eq!(data.capacity(), 5);
+    }
+
+    #[test]
+    fn spsub_with_buffers_reuses_allocations() {
+        let a = CsrMatrix::<i32>::identity(5);
+        let b = CsrMatrix::<i32>::identity(5);
+
+        let buffers = (
+            Vec::with_capacity(16),
+            Vec::with_capacity(16),
+            Vec::with_capacity(16),
+        );
+        let indices_ptr = buffers.1.as_ptr();
+
+        let diff = spsub_csr_csr_with_buffers(a.to_view(), b.to_view(), buffers).unwrap();
+        assert_matrix_eq!(diff, DMatrix::<i32>::zeros(5, 5));
+
+        let (_, indices, _) = diff.disassemble();
+        assert_eq!(indices.as_ptr(), indices_ptr);
+    }
+
     proptest! {
+        #[test]
+        fn spsub_csr_csr_agrees_with_dense_for_any_shape(
+            (a, b) in csr_strategy().prop_flat_map(|a| {
+                let (nrows, ncols) = a.shape();
+                let b = csr(
+                    PROPTEST_I32_VALUE_STRATEGY,
+                    nrows..=nrows,
+                    ncols..=ncols,
+                    PROPTEST_MAX_NNZ,
+                );
+
+                (Just(a), b)
+            })
+        ) {
+            let expected = DMatrix::from(&a) - DMatrix::from(&b);
+            let diff = spsub_csr_csr(a.to_view(), b.to_view()).unwrap();
+
+            prop_assert_matrix_eq!(diff, expected);
+        }
+
+        #[test]
+        fn spsub_dispatches_on_formats(csr in csr_strategy()) {
+            let (nrows, ncols) = csr.shape();
+            let csc = CscMatrix::from(csr.clone());
+            let dense = DMatrix::from(&csr);
+            let expected = DMatrix::<i32>::zeros(nrows, ncols);
+
+            prop_assert_matrix_eq!(spsub(csr.to_view(), csr.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spsub(csr.to_view(), csc.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spsub(csc.to_view(), csr.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spsub(csc.to_view(), csc.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spsub(dense.clone(), csr.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spsub(csr.to_view(), dense.clone()).unwrap(), expected);
+            prop_assert_matrix_eq!(spsub(dense.clone(), csc.to_view()).unwrap(), expected);
+            prop_assert_matrix_eq!(spsub(csc.to_view(), dense.clone()).unwrap(), expected);
+
+            let wrong_shape = CsrMatrix::<i32>::zeros(nrows + 1, ncols);
+            prop_assert!(spsub(csr.to_view(), wrong_shape).is_err());
+        }
+
         #[test]
         fn spsub_csr_csr_subtractive_identity(matrix in csr_strategy()) {
             let (nrows, ncols) = matrix.shape();
@@ -685,5 +1053,30 @@ mod tests {
 
             prop_assert_matrix_eq!(diff, matrix);
         }
+
+        #[test]
+        fn spsub_assign_agrees_with_spsub(matrix in csr_strategy()) {
+            let doubled = spadd_csr_csr(matrix.to_view(), matrix.to_view()).unwrap();
+            let expected = spsub_csr_csr(doubled.to_view(), matrix.to_view()).unwrap();
+
+            let mut diff = doubled;
+            diff -= matrix.to_view();
+
+            prop_assert_eq!(diff.cs_data(), expected.cs_data());
+        }
+
+        #[test]
+        fn spsub_with_buffers_ignores_stale_contents(
+            (a, b, stale) in (csr_strategy(), csr_strategy())
+                .prop_flat_map(|(a, stale)| {
+                    let (nrows, ncols) = a.shape();
+                    (Just(a), csc(PROPTEST_I32_VALUE_STRATEGY, nrows, ncols, PROPTEST_MAX_NNZ), Just(stale))
+                })
+        ) {
+            let expected = DMatrix::from(&a) - DMatrix::from(&b);
+            let diff = spsub_csr_csc_with_buffers(a, b, stale.disassemble()).unwrap();
+
+            prop_assert_matrix_eq!(diff, expected);
+        }
     }
 }
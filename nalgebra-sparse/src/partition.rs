@@ -0,0 +1,471 @@
+//! Partitioning of CSR matrices for distributed-memory solvers.
+//!
+//! The functions in this module split a [`CsrMatrix`] into one sub-matrix per rank (process),
+//! as the groundwork for distributed solvers built on top of the serial kernels in this crate.
+//! Nothing in this module performs any communication: it only computes *what* each rank owns and
+//! which entries of other ranks it needs.
+//!
+//! Two kinds of partitioning are provided:
+//!
+//! - [`partition_rows`] splits the matrix into contiguous blocks of rows (1D partitioning). Each
+//!   rank also owns the same block of the vector `x` in `y = A x`, so every column referenced by
+//!   the rank's rows that lies outside of its own block is a *ghost* (or halo) column, whose
+//!   value has to be fetched from the rank owning it before computing the local product.
+//! - [`partition_blocks`] splits the matrix into a grid of contiguous row and column blocks (2D
+//!   partitioning), where each block only references the columns within its column block.
+//!
+//! Blocks are as balanced as possible in their number of rows / columns, with the first blocks
+//! being one larger than the rest when the dimension does not divide evenly. See
+//! [`block_ranges`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra::DVector;
+//! use nalgebra_sparse::{cs::CsrMatrix, partition::partition_rows};
+//!
+//! let matrix = CsrMatrix::<f64>::identity(4);
+//! let blocks = partition_rows(&matrix, 2).unwrap();
+//!
+//! assert_eq!(blocks[0].rows, 0..2);
+//! assert_eq!(blocks[1].rows, 2..4);
+//!
+//! // The identity has no off-diagonal entries, so no rank needs values from any other.
+//! assert!(blocks.iter().all(|block| block.ghost_columns.is_empty()));
+//! ```
+
+use crate::{
+    convert::utils::CountToOffsetIter,
+    cs::{CompressedRowStorage, CsMatrix, CsrMatrix},
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::Scalar;
+use std::{borrow::Borrow, ops::Range};
+
+/// Splits `0..n` into `parts` contiguous ranges whose lengths differ by at most one.
+///
+/// The first `n % parts` ranges are one longer than the rest. Some ranges are empty if `parts` is
+/// larger than `n`.
+///
+/// # Panics
+///
+/// Panics if `parts` is zero.
+#[must_use]
+pub fn block_ranges(n: usize, parts: usize) -> Vec<Range<usize>> {
+    assert!(parts > 0, "Cannot split a dimension into zero parts.");
+
+    let base = n / parts;
+    let remainder = n % parts;
+
+    let mut start = 0;
+
+    (0..parts)
+        .map(|part| {
+            let len = base + usize::from(part < remainder);
+            let range = start..start + len;
+            start += len;
+            range
+        })
+        .collect()
+}
+
+/// Returns the index of the range in `ranges` that contains `index`.
+fn owner(ranges: &[Range<usize>], index: usize) -> usize {
+    ranges.partition_point(|range| range.end <= index)
+}
+
+/// The part of a row-partitioned matrix owned by a single rank.
+///
+/// See [`partition_rows`].
+#[derive(Debug, Clone)]
+pub struct RowBlock<T>
+where
+    T: Scalar,
+{
+    /// The rank that owns this block.
+    pub rank: usize,
+
+    /// The global rows owned by this rank.
+    pub rows: Range<usize>,
+
+    /// The global columns (i.e. entries of the vector `x` in `y = A x`) owned by this rank.
+    pub owned_columns: Range<usize>,
+
+    /// The sorted global indices of the columns referenced by this block that are owned by other
+    /// ranks.
+    pub ghost_columns: Vec<usize>,
+
+    /// The rank that owns each of the [`ghost_columns`](Self::ghost_columns).
+    pub ghost_owners: Vec<usize>,
+
+    /// The rows of the matrix owned by this rank, in local column numbering.
+    ///
+    /// Local columns `0..owned_columns.len()` are the owned columns, in order, and are followed
+    /// by the ghost columns in the same order as [`ghost_columns`](Self::ghost_columns).
+    pub local: CsrMatrix<T>,
+}
+
+impl<T> RowBlock<T>
+where
+    T: Scalar,
+{
+    /// Maps a local column index of [`local`](Self::local) to its global column index.
+    ///
+    /// Returns `None` if the local column index is out of bounds.
+    #[must_use]
+    pub fn global_column(&self, local: usize) -> Option<usize> {
+        let owned = self.owned_columns.len();
+
+        if local < owned {
+            Some(self.owned_columns.start + local)
+        } else {
+            self.ghost_columns.get(local - owned).copied()
+        }
+    }
+
+    /// Maps a global column index to its local column index in [`local`](Self::local).
+    ///
+    /// Returns `None` if the column is neither owned by this rank nor one of its ghost columns.
+    #[must_use]
+    pub fn local_column(&self, global: usize) -> Option<usize> {
+        if self.owned_columns.contains(&global) {
+            Some(global - self.owned_columns.start)
+        } else {
+            self.ghost_columns
+                .binary_search(&global)
+                .ok()
+                .map(|ghost| self.owned_columns.len() + ghost)
+        }
+    }
+}
+
+/// Partitions a CSR matrix into `parts` contiguous blocks of rows.
+///
+/// The rows and the columns of the matrix are each split with [`block_ranges`], and rank `i` owns
+/// the `i`-th block of both. For square matrices this is the usual distribution where each rank
+/// owns the entries of `x` and `y` (in `y = A x`) that correspond to its rows.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if `parts` is
+/// zero.
+pub fn partition_rows<T, MO, MI, D>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    parts: usize,
+) -> Result<Vec<RowBlock<T>>, OperationError>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    if parts == 0 {
+        return Err(zero_parts());
+    }
+
+    let (nrows, ncols) = csr.shape();
+    let _span = span!(
+        "partition_rows",
+        nrows = nrows,
+        ncols = ncols,
+        parts = parts
+    );
+
+    let row_ranges = block_ranges(nrows, parts);
+    let column_ranges = block_ranges(ncols, parts);
+
+    let blocks = row_ranges
+        .into_iter()
+        .zip(column_ranges.iter().cloned())
+        .enumerate()
+        .map(|(rank, (rows, owned_columns))| {
+            let mut ghost_columns = rows
+                .clone()
+                .flat_map(|row| csr.get_lane(row).unwrap().map(|(col, _)| col))
+                .filter(|col| !owned_columns.contains(col))
+                .collect::<Vec<_>>();
+
+            ghost_columns.sort_unstable();
+            ghost_columns.dedup();
+
+            let ghost_owners = ghost_columns
+                .iter()
+                .map(|&col| owner(&column_ranges, col))
+                .collect();
+
+            let owned = owned_columns.len();
+
+            let mut counts = Vec::with_capacity(rows.len());
+            let mut indices = Vec::new();
+            let mut data = Vec::new();
+
+            for row in rows.clone() {
+                let mut lane = csr
+                    .get_lane(row)
+                    .unwrap()
+                    .map(|(col, value)| {
+                        let local = if owned_columns.contains(&col) {
+                            col - owned_columns.start
+                        } else {
+                            owned + ghost_columns.binary_search(&col).unwrap()
+                        };
+
+                        (local, value.clone())
+                    })
+                    .collect::<Vec<_>>();
+
+                // Owned columns come first in local numbering, followed by ghost columns in
+                // global order, so the lane has to be re-sorted if it references both.
+                lane.sort_unstable_by_key(|&(local, _)| local);
+
+                counts.push(lane.len());
+
+                for (local, value) in lane {
+                    indices.push(local);
+                    data.push(value);
+                }
+            }
+
+            let offsets = CountToOffsetIter::new(counts).collect();
+            let local = unsafe {
+                CsrMatrix::from_parts_unchecked(
+                    rows.len(),
+                    owned + ghost_columns.len(),
+                    offsets,
+                    indices,
+                    data,
+                )
+            };
+
+            RowBlock {
+                rank,
+                rows,
+                owned_columns,
+                ghost_columns,
+                ghost_owners,
+                local,
+            }
+        })
+        .collect();
+
+    Ok(blocks)
+}
+
+/// A single block of a 2D-partitioned matrix.
+///
+/// See [`partition_blocks`].
+#[derive(Debug, Clone)]
+pub struct MatrixBlock<T>
+where
+    T: Scalar,
+{
+    /// The index of the row block (i.e. row in the process grid) of this block.
+    pub row_block: usize,
+
+    /// The index of the column block (i.e. column in the process grid) of this block.
+    pub column_block: usize,
+
+    /// The global rows covered by this block.
+    pub rows: Range<usize>,
+
+    /// The global columns covered by this block.
+    pub columns: Range<usize>,
+
+    /// The entries of the matrix within this block, with indices relative to the start of
+    /// [`rows`](Self::rows) and [`columns`](Self::columns).
+    pub local: CsrMatrix<T>,
+}
+
+/// Partitions a CSR matrix into a `row_parts × column_parts` grid of contiguous blocks.
+///
+/// The rows and columns are split with [`block_ranges`]. The blocks are returned in row-major
+/// order of the grid, i.e. the block at `(i, j)` is at index `i * column_parts + j`.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if either
+/// `row_parts` or `column_parts` is zero.
+pub fn partition_blocks<T, MO, MI, D>(
+    csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    row_parts: usize,
+    column_parts: usize,
+) -> Result<Vec<MatrixBlock<T>>, OperationError>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+{
+    if row_parts == 0 || column_parts == 0 {
+        return Err(zero_parts());
+    }
+
+    let (nrows, ncols) = csr.shape();
+    let _span = span!(
+        "partition_blocks",
+        nrows = nrows,
+        ncols = ncols,
+        row_parts = row_parts,
+        column_parts = column_parts
+    );
+
+    let row_ranges = block_ranges(nrows, row_parts);
+    let column_ranges = block_ranges(ncols, column_parts);
+
+    let mut blocks = Vec::with_capacity(row_parts * column_parts);
+
+    for (row_block, rows) in row_ranges.into_iter().enumerate() {
+        let mut counts = vec![vec![0usize; rows.len()]; column_parts];
+        let mut indices = vec![Vec::new(); column_parts];
+        let mut data = vec![Vec::new(); column_parts];
+
+        for (local_row, row) in rows.clone().enumerate() {
+            // Columns within a lane are sorted, so each lane is split into consecutive runs, one
+            // per column block.
+            for (col, value) in csr.get_lane(row).unwrap() {
+                let column_block = owner(&column_ranges, col);
+
+                counts[column_block][local_row] += 1;
+                indices[column_block].push(col - column_ranges[column_block].start);
+                data[column_block].push(value.clone());
+            }
+        }
+
+        let parts = counts.into_iter().zip(indices).zip(data);
+
+        for (column_block, ((counts, indices), data)) in parts.enumerate() {
+            let columns = column_ranges[column_block].clone();
+            let offsets = CountToOffsetIter::new(counts).collect();
+
+            let local = unsafe {
+                CsrMatrix::from_parts_unchecked(rows.len(), columns.len(), offsets, indices, data)
+            };
+
+            blocks.push(MatrixBlock {
+                row_block,
+                column_block,
+                rows: rows.clone(),
+                columns,
+                local,
+            });
+        }
+    }
+
+    Ok(blocks)
+}
+
+fn zero_parts() -> OperationError {
+    OperationError::from_kind_and_message(
+        OperationErrorKind::InvalidPattern,
+        String::from("A matrix cannot be partitioned into zero parts."),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{proptest::*, SparseEntry};
+    use nalgebra::DVector;
+    use proptest::prelude::*;
+
+    #[test]
+    fn block_ranges_are_balanced() {
+        assert_eq!(block_ranges(10, 3), vec![0..4, 4..7, 7..10]);
+        assert_eq!(block_ranges(2, 4), vec![0..1, 1..2, 2..2, 2..2]);
+        assert_eq!(block_ranges(0, 2), vec![0..0, 0..0]);
+    }
+
+    #[test]
+    fn zero_parts_is_an_error() {
+        let matrix = CsrMatrix::<i32>::identity(3);
+
+        assert!(matches!(
+            partition_rows(&matrix, 0).unwrap_err().kind(),
+            OperationErrorKind::InvalidPattern
+        ));
+        assert!(matches!(
+            partition_blocks(&matrix, 1, 0).unwrap_err().kind(),
+            OperationErrorKind::InvalidPattern
+        ));
+    }
+
+    #[test]
+    fn row_blocks_of_tridiagonal_have_neighbouring_ghosts() {
+        let dense =
+            nalgebra::DMatrix::from_fn(6, 6, |i, j| if i.max(j) - i.min(j) <= 1 { 1 } else { 0 });
+        let matrix = CsrMatrix::from(&dense);
+
+        let blocks = partition_rows(&matrix, 3).unwrap();
+
+        assert_eq!(blocks[0].ghost_columns, vec![2]);
+        assert_eq!(blocks[0].ghost_owners, vec![1]);
+        assert_eq!(blocks[1].ghost_columns, vec![1, 4]);
+        assert_eq!(blocks[1].ghost_owners, vec![0, 2]);
+        assert_eq!(blocks[2].ghost_columns, vec![3]);
+        assert_eq!(blocks[2].ghost_owners, vec![1]);
+
+        // Row 2 references global columns 1, 2 and 3, i.e. ghost 0, owned 0 and owned 1.
+        let middle = &blocks[1];
+        assert_eq!(middle.local.shape(), (2, 4));
+        assert_eq!(middle.local_column(1), Some(2));
+        assert_eq!(middle.local_column(3), Some(1));
+        assert_eq!(middle.local_column(5), None);
+        assert_eq!(middle.global_column(3), Some(4));
+        assert_eq!(middle.global_column(4), None);
+        assert_eq!(middle.local.get_entry(0, 2), Some(SparseEntry::NonZero(&1)));
+    }
+
+    proptest! {
+        #[test]
+        fn row_blocks_reproduce_global_product(matrix in csr_strategy(), parts in 1usize..5) {
+            let blocks = partition_rows(&matrix, parts).unwrap();
+            prop_assert_eq!(blocks.len(), parts);
+
+            let x = DVector::from_fn(matrix.ncols(), |i, _| i as i32 - 2);
+            let expected = nalgebra::DMatrix::from(&matrix) * &x;
+
+            for block in &blocks {
+                prop_assert_eq!(block.ghost_columns.len(), block.ghost_owners.len());
+
+                for (&ghost, &owner) in block.ghost_columns.iter().zip(&block.ghost_owners) {
+                    prop_assert!(!block.owned_columns.contains(&ghost));
+                    prop_assert!(blocks[owner].owned_columns.contains(&ghost));
+                }
+
+                // Gather the owned and ghost entries of x, as a distributed solver would.
+                let local_x = DVector::from_fn(block.local.ncols(), |local, _| {
+                    x[block.global_column(local).unwrap()]
+                });
+                let local_y = nalgebra::DMatrix::from(&block.local) * local_x;
+
+                for (local_row, row) in block.rows.clone().enumerate() {
+                    prop_assert_eq!(local_y[local_row], expected[row]);
+                }
+            }
+        }
+
+        #[test]
+        fn matrix_blocks_cover_every_entry(
+            matrix in csr_strategy(),
+            row_parts in 1usize..4,
+            column_parts in 1usize..4,
+        ) {
+            let blocks = partition_blocks(&matrix, row_parts, column_parts).unwrap();
+            prop_assert_eq!(blocks.len(), row_parts * column_parts);
+
+            let mut entries = blocks
+                .iter()
+                .flat_map(|block| {
+                    block.local.triplet_iter().map(move |(i, j, &v)| {
+                        (block.rows.start + i, block.columns.start + j, v)
+                    })
+                })
+                .collect::<Vec<_>>();
+            entries.sort_unstable();
+
+            let expected = matrix.triplet_iter().map(|(i, j, &v)| (i, j, v)).collect::<Vec<_>>();
+
+            prop_assert_eq!(entries, expected);
+        }
+    }
+}
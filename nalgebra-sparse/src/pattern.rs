@@ -0,0 +1,497 @@
+//! A type for representing the sparsity pattern of a compressed sparse matrix on its own.
+
+use crate::{
+    convert::utils::CountToOffsetIter, cs::check_pattern, error::SparsityPatternFormatError,
+};
+
+/// The sparsity pattern of a compressed sparse matrix, without any values.
+///
+/// A `SparsityPattern` stores the major offsets and minor indices of a compressed sparse matrix,
+/// and upholds the same invariants as the pattern of a [`CsMatrix`](crate::cs::CsMatrix): there
+/// is one offset per major lane, and the minor indices within each lane are sorted, in bounds and
+/// free of duplicates. Unlike `CsMatrix`, a pattern has no notion of rows and columns, only of
+/// major and minor lanes, so the same pattern describes a CSR matrix and the CSC matrix of its
+/// transpose.
+///
+/// This is the natural input of symbolic routines, such as orderings or elimination trees, which
+/// only depend on the structure of a matrix.
+///
+/// # Example
+///
+/// ```rust
+/// use nalgebra_sparse::{cs::CsrMatrix, pattern::SparsityPattern};
+///
+/// let pattern = SparsityPattern::try_from_offsets_and_indices(2, 3, vec![0, 2], vec![0, 2, 1])
+///     .unwrap();
+///
+/// assert_eq!(pattern.lane(0), Some(&[0, 2][..]));
+/// assert_eq!(pattern.entries().collect::<Vec<_>>(), vec![(0, 0), (0, 2), (1, 1)]);
+///
+/// let csr = CsrMatrix::try_from_pattern_and_values(pattern.clone(), vec![1, 2, 3]).unwrap();
+/// assert_eq!(csr.pattern(), pattern);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SparsityPattern {
+    minor_dim: usize,
+    offsets: Vec<usize>,
+    indices: Vec<usize>,
+}
+
+impl SparsityPattern {
+    /// Creates a pattern with `major_dim` empty lanes of length `minor_dim`.
+    #[must_use]
+    pub fn zeros(major_dim: usize, minor_dim: usize) -> Self {
+        Self {
+            minor_dim,
+            offsets: vec![0; major_dim],
+            indices: Vec::new(),
+        }
+    }
+
+    /// Creates a pattern from its major offsets and minor indices, checking every invariant of the
+    /// pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SparsityPatternFormatError`] if `offsets` does not have `major_dim` entries,
+    /// does not start at zero or is not monotonically increasing, or if the indices in any lane
+    /// are out of bounds, not sorted or contain duplicates.
+    pub fn try_from_offsets_and_indices(
+        major_dim: usize,
+        minor_dim: usize,
+        offsets: Vec<usize>,
+        indices: Vec<usize>,
+    ) -> Result<Self, SparsityPatternFormatError> {
+        check_pattern(major_dim, minor_dim, &offsets, &indices)?;
+
+        Ok(unsafe { Self::from_offsets_and_indices_unchecked(minor_dim, offsets, indices) })
+    }
+
+    /// Creates a pattern from parts that are known to uphold the invariants of the pattern.
+    pub(crate) unsafe fn from_offsets_and_indices_unchecked(
+        minor_dim: usize,
+        offsets: Vec<usize>,
+        indices: Vec<usize>,
+    ) -> Self {
+        Self {
+            minor_dim,
+            offsets,
+            indices,
+        }
+    }
+
+    /// The number of major lanes in the pattern.
+    #[inline]
+    #[must_use]
+    pub fn major_dim(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// The length of every major lane in the pattern.
+    #[inline]
+    #[must_use]
+    pub fn minor_dim(&self) -> usize {
+        self.minor_dim
+    }
+
+    /// The number of explicit entries in the pattern.
+    #[inline]
+    #[must_use]
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// The offset of every major lane into the minor indices.
+    #[inline]
+    #[must_use]
+    pub fn major_offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// The minor indices of every explicit entry, in major -> minor order.
+    #[inline]
+    #[must_use]
+    pub fn minor_indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// The minor indices of the major lane with the given index.
+    ///
+    /// Returns `None` iff the major index does not correspond to a lane in the pattern.
+    #[must_use]
+    pub fn lane(&self, major_index: usize) -> Option<&[usize]> {
+        let offset = *self.offsets.get(major_index)?;
+        let upper = self
+            .offsets
+            .get(major_index + 1)
+            .copied()
+            .unwrap_or(self.indices.len());
+
+        Some(&self.indices[offset..upper])
+    }
+
+    /// An iterator over the `(major_index, minor_index)` pair of every explicit entry, in major ->
+    /// minor (i.e. sorted) order.
+    pub fn entries(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.major_dim()).flat_map(move |major_index| {
+            self.lane(major_index)
+                .unwrap_or_default()
+                .iter()
+                .map(move |&minor_index| (major_index, minor_index))
+        })
+    }
+
+    /// Consumes self and returns the major offsets and minor indices of the pattern.
+    pub fn disassemble(self) -> (Vec<usize>, Vec<usize>) {
+        (self.offsets, self.indices)
+    }
+
+    /// Computes the transpose of the pattern, i.e. the pattern with its major and minor lanes
+    /// swapped.
+    #[must_use]
+    pub fn transpose(&self) -> Self {
+        let mut counts = vec![0usize; self.minor_dim];
+
+        for &index in &self.indices {
+            counts[index] += 1;
+        }
+
+        let offsets: Vec<usize> = CountToOffsetIter::new(counts).collect();
+        let mut next = offsets.clone();
+        let mut indices = vec![0; self.nnz()];
+
+        for (major_index, minor_index) in self.entries() {
+            indices[next[minor_index]] = major_index;
+            next[minor_index] += 1;
+        }
+
+        Self {
+            minor_dim: self.major_dim(),
+            offsets,
+            indices,
+        }
+    }
+
+    /// Computes the strongly connected components of the directed graph of a square pattern, in
+    /// which every entry `(i, j)` is an edge from vertex `i` to vertex `j`.
+    ///
+    /// The components are numbered in reverse topological order, i.e. every edge leads from a
+    /// component to a component with the same or a lower number. Ordering the vertices by their
+    /// component therefore permutes the pattern to block lower triangular form (BTF), with one
+    /// diagonal block per component. See [`StronglyConnectedComponents::permutation`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is not square.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nalgebra_sparse::pattern::SparsityPattern;
+    ///
+    /// // 0 → 1 → 2 → 1, and 3 on its own
+    /// let pattern =
+    ///     SparsityPattern::try_from_offsets_and_indices(4, 4, vec![0, 1, 2, 3], vec![1, 2, 1])
+    ///         .unwrap();
+    /// let components = pattern.strongly_connected_components();
+    ///
+    /// assert_eq!(components.count(), 3);
+    /// assert_eq!(components.label(1), components.label(2));
+    /// assert!(components.label(0) > components.label(1));
+    /// ```
+    #[must_use]
+    pub fn strongly_connected_components(&self) -> StronglyConnectedComponents {
+        assert_eq!(
+            self.major_dim(),
+            self.minor_dim(),
+            "The pattern must be square to describe a graph."
+        );
+
+        // Tarjan's algorithm, with an explicit stack of (vertex, next edge) frames in place of
+        // recursion
+        let n = self.major_dim();
+        let unvisited = usize::MAX;
+
+        let mut index = vec![unvisited; n];
+        let mut lowlink = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut frames = Vec::new();
+        let mut labels = vec![0; n];
+        let mut count = 0;
+        let mut next_index = 0;
+
+        for root in 0..n {
+            if index[root] != unvisited {
+                continue;
+            }
+
+            index[root] = next_index;
+            lowlink[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            frames.push((root, 0));
+
+            while let Some((vertex, edge)) = frames.last_mut() {
+                let vertex = *vertex;
+                let lane = self.lane(vertex).unwrap_or_default();
+
+                if let Some(&neighbor) = lane.get(*edge) {
+                    *edge += 1;
+
+                    if index[neighbor] == unvisited {
+                        index[neighbor] = next_index;
+                        lowlink[neighbor] = next_index;
+                        next_index += 1;
+                        stack.push(neighbor);
+                        on_stack[neighbor] = true;
+                        frames.push((neighbor, 0));
+                    } else if on_stack[neighbor] {
+                        lowlink[vertex] = lowlink[vertex].min(index[neighbor]);
+                    }
+
+                    continue;
+                }
+
+                frames.pop();
+
+                if let Some(&(parent, _)) = frames.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[vertex]);
+                }
+
+                if lowlink[vertex] == index[vertex] {
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        labels[member] = count;
+
+                        if member == vertex {
+                            break;
+                        }
+                    }
+
+                    count += 1;
+                }
+            }
+        }
+
+        StronglyConnectedComponents { count, labels }
+    }
+}
+
+/// The strongly connected components of the graph of a square [`SparsityPattern`].
+///
+/// This is produced by [`SparsityPattern::strongly_connected_components`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StronglyConnectedComponents {
+    count: usize,
+    labels: Vec<usize>,
+}
+
+impl StronglyConnectedComponents {
+    /// The number of components.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The component of every vertex.
+    #[must_use]
+    pub fn labels(&self) -> &[usize] {
+        &self.labels
+    }
+
+    /// The component of the given vertex.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vertex is out of bounds.
+    #[must_use]
+    pub fn label(&self, vertex: usize) -> usize {
+        self.labels[vertex]
+    }
+
+    /// The vertices of every component, in ascending order, indexed by component.
+    #[must_use]
+    pub fn members(&self) -> Vec<Vec<usize>> {
+        let mut members = vec![Vec::new(); self.count];
+
+        for (vertex, &label) in self.labels.iter().enumerate() {
+            members[label].push(vertex);
+        }
+
+        members
+    }
+
+    /// The vertices ordered by component, which permutes the pattern to block lower triangular
+    /// form. Entry `k` of the permutation is the vertex that is moved to position `k`.
+    #[must_use]
+    pub fn permutation(&self) -> Vec<usize> {
+        self.members().into_iter().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cs::CsrMatrix, proptest::*};
+    use proptest::prelude::*;
+
+    #[test]
+    fn try_from_offsets_and_indices_checks_invariants() {
+        use SparsityPatternFormatError::*;
+
+        let check = |major_dim, minor_dim, offsets: &[usize], indices: &[usize]| {
+            SparsityPattern::try_from_offsets_and_indices(
+                major_dim,
+                minor_dim,
+                offsets.to_vec(),
+                indices.to_vec(),
+            )
+        };
+
+        assert!(check(2, 2, &[0, 1], &[1, 0]).is_ok());
+        assert_eq!(check(3, 2, &[0, 1], &[1, 0]), Err(InvalidOffsetArrayLength));
+        assert_eq!(check(2, 2, &[1, 1], &[1, 0]), Err(InvalidFirstOffset));
+        assert_eq!(check(2, 2, &[0, 2], &[1]), Err(NonmonotonicOffsets));
+        assert_eq!(check(3, 2, &[0, 2, 1], &[0, 1]), Err(NonmonotonicOffsets));
+        assert_eq!(
+            check(3, 3, &[0, 5, 3], &[0, 1, 2, 0]),
+            Err(NonmonotonicOffsets)
+        );
+        assert_eq!(
+            check(3, 3, &[0, 5, 5], &[0, 1, 2, 0]),
+            Err(NonmonotonicOffsets)
+        );
+        assert_eq!(check(2, 2, &[0, 1], &[1, 2]), Err(MinorIndexOutOfBounds));
+        assert_eq!(check(1, 2, &[0], &[1, 1]), Err(DuplicateEntry));
+        assert_eq!(check(1, 2, &[0], &[1, 0]), Err(NonmonotonicMinorIndices));
+    }
+
+    #[test]
+    fn strongly_connected_components_of_a_chain_of_cycles() {
+        // {0, 1} → {2, 3, 4} → {5}, with 5 a sink and 6 isolated
+        let pattern = SparsityPattern::try_from_offsets_and_indices(
+            7,
+            7,
+            vec![0, 1, 3, 4, 5, 7, 7],
+            vec![1, 0, 2, 3, 4, 2, 5],
+        )
+        .unwrap();
+        let components = pattern.strongly_connected_components();
+        let labels = components.labels();
+
+        assert_eq!(components.count(), 4);
+        assert_eq!(labels[0], labels[1]);
+        assert_eq!(labels[2], labels[3]);
+        assert_eq!(labels[2], labels[4]);
+        assert!(labels[5] < labels[2] && labels[2] < labels[0]);
+        assert_eq!(components.members()[labels[2]], vec![2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be square")]
+    fn strongly_connected_components_reject_rectangular_patterns() {
+        let _ = SparsityPattern::zeros(2, 3).strongly_connected_components();
+    }
+
+    proptest! {
+        #[test]
+        fn strongly_connected_components_give_block_triangular_form(
+            pattern in pattern(0..=8, 0..=8, 20).prop_filter("square", |p| p.major_dim() == p.minor_dim())
+        ) {
+            let components = pattern.strongly_connected_components();
+            let labels = components.labels();
+
+            // Every edge leads to the same or a lower component
+            prop_assert!(pattern.entries().all(|(i, j)| labels[j] <= labels[i]));
+
+            // Within a component, every vertex is reachable from every other one
+            let n = pattern.major_dim();
+            let mut reachable = vec![vec![false; n]; n];
+            for (i, lane) in reachable.iter_mut().enumerate() {
+                lane[i] = true;
+            }
+            for (i, j) in pattern.entries() {
+                reachable[i][j] = true;
+            }
+            for k in 0..n {
+                for i in 0..n {
+                    for j in 0..n {
+                        if reachable[i][k] && reachable[k][j] {
+                            reachable[i][j] = true;
+                        }
+                    }
+                }
+            }
+            for i in 0..n {
+                for j in 0..n {
+                    prop_assert_eq!(labels[i] == labels[j], reachable[i][j] && reachable[j][i]);
+                }
+            }
+
+            let mut permutation = components.permutation();
+            permutation.sort_unstable();
+            prop_assert_eq!(permutation, (0..n).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn pattern_of_csr_agrees_with_entries(csr in csr_strategy()) {
+            let pattern = csr.pattern();
+
+            prop_assert_eq!(pattern.major_dim(), csr.nrows());
+            prop_assert_eq!(pattern.minor_dim(), csr.ncols());
+            prop_assert!(pattern
+                .entries()
+                .eq(csr.triplet_iter().map(|(i, j, _)| (i, j))));
+        }
+
+        #[test]
+        fn transpose_agrees_with_csc_conversion(csr in csr_strategy()) {
+            let transpose = csr.pattern().transpose();
+            let csc = crate::cs::CscMatrix::from(csr.clone());
+
+            prop_assert_eq!(&transpose, &csc.pattern());
+            prop_assert_eq!(transpose.transpose(), csr.pattern());
+        }
+
+        #[test]
+        fn pattern_and_values_round_trip(csr in csr_strategy()) {
+            let (_, _, data) = csr.clone().disassemble();
+            let rebuilt = CsrMatrix::try_from_pattern_and_values(csr.pattern(), data).unwrap();
+
+            prop_assert_eq!(rebuilt.shape(), csr.shape());
+            prop_assert_eq!(rebuilt.cs_data(), csr.cs_data());
+        }
+
+        #[test]
+        fn pattern_strategy_respects_bounds(pattern in pattern(0..=4, 0..=5, 7)) {
+            prop_assert!(pattern.major_dim() <= 4);
+            prop_assert!(pattern.minor_dim() <= 5);
+            prop_assert!(pattern.nnz() <= 7);
+        }
+
+        #[test]
+        fn lane_nonzeros_are_within_range(
+            pattern in pattern_with_lane_nonzeros(1..=5, 2..=6, 1..=2)
+        ) {
+            for major_index in 0..pattern.major_dim() {
+                let len = pattern.lane(major_index).unwrap().len();
+                prop_assert!((1..=2).contains(&len));
+            }
+        }
+
+        #[test]
+        fn banded_pattern_stays_within_band(pattern in banded_pattern(0..=8, 1, 2)) {
+            prop_assert_eq!(pattern.major_dim(), pattern.minor_dim());
+            prop_assert!(pattern.entries().all(|(i, j)| j + 1 >= i && j <= i + 2));
+            prop_assert!((0..pattern.major_dim()).all(|i| pattern.lane(i).unwrap().contains(&i)));
+        }
+
+        #[test]
+        fn symmetric_pattern_equals_its_transpose(pattern in symmetric_pattern(0..=6, 12)) {
+            prop_assert_eq!(&pattern.transpose(), &pattern);
+            prop_assert!((0..pattern.major_dim()).all(|i| pattern.lane(i).unwrap().contains(&i)));
+        }
+    }
+}
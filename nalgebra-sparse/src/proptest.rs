@@ -11,8 +11,10 @@
 mod proptest_patched;
 
 use crate::{
+    convert::utils::CountToOffsetIter,
     coo::CooMatrix,
     cs::{CscMatrix, CsrMatrix},
+    pattern::SparsityPattern,
 };
 use nalgebra::{
     proptest::{matrix, DimRange},
@@ -21,9 +23,14 @@ use nalgebra::{
 use proptest::{
     collection::{btree_set, hash_map, vec},
     prelude::*,
-    sample::Index,
+    sample::{subsequence, Index},
+    strategy::{NewTree, ValueTree},
+    test_runner::TestRunner,
+};
+use std::{
+    cmp::min, collections::BTreeSet, convert::TryFrom, fmt::Debug, iter::repeat,
+    ops::RangeInclusive,
 };
-use std::{cmp::min, convert::TryFrom, fmt::Debug, iter::repeat, ops::RangeInclusive};
 
 fn dense_row_major_coord_strategy(
     nrows: usize,
@@ -314,7 +321,136 @@ pub fn sparsity_pattern(
         })
 }
 
+/// A strategy for generating [`SparsityPattern`]s, independently of any values.
+///
+/// Patterns with few non-zeros are sampled sparsely and patterns with many non-zeros densely, as
+/// in [`sparsity_pattern`].
+pub fn pattern(
+    major_lanes: impl Into<DimRange>,
+    minor_lanes: impl Into<DimRange>,
+    max_nonzeros: usize,
+) -> impl Strategy<Value = SparsityPattern> {
+    sparsity_pattern(major_lanes, minor_lanes, max_nonzeros).prop_map(
+        |((nmajor, nminor), offsets, indices)| {
+            SparsityPattern::try_from_offsets_and_indices(nmajor, nminor, offsets, indices)
+                .expect("Internal error: Generated SparsityPattern is invalid")
+        },
+    )
+}
+
+/// A strategy for generating [`SparsityPattern`]s where the number of explicit entries in every
+/// major lane is drawn from `lane_nonzeros`.
+///
+/// This gives direct control over the distribution of non-zeros across lanes, e.g. `0..=0` for
+/// empty lanes or `1..=1` for exactly one entry per lane. The range is clamped to the minor
+/// dimension of each generated pattern, so lanes are never asked to hold more entries than fit.
+pub fn pattern_with_lane_nonzeros(
+    major_lanes: impl Into<DimRange>,
+    minor_lanes: impl Into<DimRange>,
+    lane_nonzeros: RangeInclusive<usize>,
+) -> impl Strategy<Value = SparsityPattern> {
+    (
+        major_lanes.into().to_range_inclusive(),
+        minor_lanes.into().to_range_inclusive(),
+    )
+        .prop_flat_map(move |(nmajor, nminor)| {
+            let lower = min(*lane_nonzeros.start(), nminor);
+            let upper = min(*lane_nonzeros.end(), nminor).max(lower);
+            let minors: Vec<usize> = (0..nminor).collect();
+            let lanes = vec![subsequence(minors, lower..=upper); nmajor];
+
+            (Just(nminor), lanes)
+        })
+        .prop_map(|(nminor, lanes)| pattern_from_lanes(nminor, lanes))
+}
+
+/// A strategy for generating square, banded [`SparsityPattern`]s.
+///
+/// Every entry lies at most `lower` lanes below and `upper` lanes above the diagonal. The diagonal
+/// is always present, since banded symbolic routines almost always assume it; the remaining
+/// entries of the band are sampled at random.
+pub fn banded_pattern(
+    dim: impl Into<DimRange>,
+    lower: usize,
+    upper: usize,
+) -> impl Strategy<Value = SparsityPattern> {
+    dim.into()
+        .to_range_inclusive()
+        .prop_flat_map(move |n| {
+            let lanes: Vec<_> = (0..n)
+                .map(|i| {
+                    let band: Vec<usize> = (i.saturating_sub(lower)..min(i + upper + 1, n))
+                        .filter(|&j| j != i)
+                        .collect();
+                    let len = band.len();
+
+                    subsequence(band, 0..=len)
+                })
+                .collect();
+
+            (Just(n), lanes)
+        })
+        .prop_map(|(n, lanes)| {
+            let lanes = lanes
+                .into_iter()
+                .enumerate()
+                .map(|(i, mut lane)| {
+                    let position = lane.partition_point(|&j| j < i);
+                    lane.insert(position, i);
+                    lane
+                })
+                .collect();
+
+            pattern_from_lanes(n, lanes)
+        })
+}
+
+/// A strategy for generating square, structurally symmetric [`SparsityPattern`]s with a full
+/// diagonal.
+///
+/// The pattern is the union of a random pattern with at most `max_nonzeros` entries, its transpose
+/// and the diagonal, so the final number of entries may exceed `max_nonzeros`.
+pub fn symmetric_pattern(
+    dim: impl Into<DimRange>,
+    max_nonzeros: usize,
+) -> impl Strategy<Value = SparsityPattern> {
+    dim.into()
+        .to_range_inclusive()
+        .prop_flat_map(move |n| pattern(n..=n, n..=n, max_nonzeros))
+        .prop_map(|pattern| {
+            let n = pattern.major_dim();
+            let entries: BTreeSet<(usize, usize)> = pattern
+                .entries()
+                .chain(pattern.entries().map(|(i, j)| (j, i)))
+                .chain((0..n).map(|i| (i, i)))
+                .collect();
+
+            let (_, offsets, indices) =
+                sparsity_pattern_from_row_major_coords(n, n, entries.into_iter());
+
+            SparsityPattern::try_from_offsets_and_indices(n, n, offsets, indices)
+                .expect("Internal error: Generated SparsityPattern is invalid")
+        })
+}
+
+/// Assembles a pattern from the sorted minor indices of every major lane.
+fn pattern_from_lanes(nminor: usize, lanes: Vec<Vec<usize>>) -> SparsityPattern {
+    let nmajor = lanes.len();
+    let mut offsets = Vec::with_capacity(nmajor);
+    let mut indices = Vec::new();
+
+    for lane in lanes {
+        offsets.push(indices.len());
+        indices.extend(lane);
+    }
+
+    SparsityPattern::try_from_offsets_and_indices(nmajor, nminor, offsets, indices)
+        .expect("Internal error: Generated SparsityPattern is invalid")
+}
+
 /// A strategy for generating CSR matrices.
+///
+/// Failing cases shrink structurally, see [`CsrValueTree`].
 pub fn csr<T>(
     value_strategy: T,
     rows: impl Into<DimRange>,
@@ -327,23 +463,20 @@ where
 {
     let rows = rows.into();
     let cols = cols.into();
-    sparsity_pattern(
-        rows.lower_bound().value()..=rows.upper_bound().value(),
-        cols.lower_bound().value()..=cols.upper_bound().value(),
-        max_nonzeros,
-    )
-    .prop_flat_map(move |(shape, offsets, indices)| {
-        let nnz = indices.len();
-        let data = vec![value_strategy.clone(); nnz];
-        (Just(shape), Just(offsets), Just(indices), data)
-    })
-    .prop_map(|((nmajor, nminor), offsets, indices, data)| {
-        CsrMatrix::try_from_parts(nmajor, nminor, offsets, indices, data)
-            .expect("Internal error: Generated CsrMatrix is invalid")
-    })
+
+    let (min_rows, min_cols) = (rows.lower_bound().value(), cols.lower_bound().value());
+
+    CsrStrategy {
+        pattern: sparsity_pattern(rows, cols, max_nonzeros).boxed(),
+        value_strategy,
+        min_rows,
+        min_cols,
+    }
 }
 
 /// A strategy for generating CSC matrices.
+///
+/// Failing cases shrink structurally, see [`CsrValueTree`].
 pub fn csc<T>(
     value_strategy: T,
     rows: impl Into<DimRange>,
@@ -354,23 +487,229 @@ where
     T: Strategy + Clone + 'static,
     T::Value: Scalar,
 {
-    let rows = rows.into();
-    let cols = cols.into();
-    sparsity_pattern(
-        cols.lower_bound().value()..=cols.upper_bound().value(),
-        rows.lower_bound().value()..=rows.upper_bound().value(),
-        max_nonzeros,
-    )
-    .prop_flat_map(move |(shape, offsets, indices)| {
-        let nnz = indices.len();
-        let data = vec![value_strategy.clone(); nnz];
+    // A CSC matrix shares its data with the CSR matrix of its transpose, so we generate (and
+    // shrink) the transpose instead.
+    csr(value_strategy, cols, rows, max_nonzeros).prop_map(|csr| csr.transpose_owned())
+}
 
-        (Just(shape), Just(offsets), Just(indices), data)
-    })
-    .prop_map(|((nmajor, nminor), offsets, indices, data)| {
-        CscMatrix::try_from_parts(nminor, nmajor, offsets, indices, data)
-            .expect("Internal error: Generated CscMatrix is invalid")
-    })
+/// The strategy behind [`csr`], which produces [`CsrValueTree`]s.
+#[derive(Debug)]
+#[must_use = "strategies do nothing unless used"]
+pub struct CsrStrategy<T> {
+    pattern: BoxedStrategy<((usize, usize), Vec<usize>, Vec<usize>)>,
+    value_strategy: T,
+    min_rows: usize,
+    min_cols: usize,
+}
+
+impl<T> Strategy for CsrStrategy<T>
+where
+    T: Strategy,
+    T::Value: Scalar,
+{
+    type Tree = CsrValueTree<T::Tree>;
+    type Value = CsrMatrix<T::Value>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        // The pattern itself is not shrunk by its own tree: the structural shrinking done by
+        // `CsrValueTree` produces far more readable counterexamples.
+        let ((nrows, ncols), offsets, indices) = self.pattern.new_tree(runner)?.current();
+
+        let mut entries = Vec::with_capacity(indices.len());
+
+        for (row, &offset) in offsets.iter().enumerate() {
+            let upper = offsets.get(row + 1).copied().unwrap_or(indices.len());
+
+            for &col in &indices[offset..upper] {
+                entries.push((row, col, self.value_strategy.new_tree(runner)?));
+            }
+        }
+
+        let included = vec![true; entries.len()];
+
+        Ok(CsrValueTree {
+            nrows,
+            ncols,
+            min_rows: self.min_rows,
+            min_cols: self.min_cols,
+            entries,
+            included,
+            shrink: CsrShrink::DeleteEntry(0),
+            prev_shrink: None,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CsrShrink {
+    DeleteEntry(usize),
+    DeleteRow(usize),
+    DeleteColumn(usize),
+    ShrinkValue(usize),
+}
+
+/// A value tree for CSR matrices that shrinks structurally.
+///
+/// Shrinking proceeds in the following order, so that counterexamples end up with as few lanes,
+/// explicit entries and as small indices as the failing property allows:
+///
+/// 1. Explicit entries are removed one at a time.
+/// 2. Empty rows are removed, shifting every subsequent row up by one.
+/// 3. Empty columns are removed, shifting every subsequent column left by one.
+/// 4. The value of every remaining entry is shrunk by its own value tree.
+///
+/// The matrix is never shrunk below the minimum number of rows and columns of the strategy that
+/// produced it.
+#[derive(Debug, Clone)]
+pub struct CsrValueTree<V> {
+    nrows: usize,
+    ncols: usize,
+    min_rows: usize,
+    min_cols: usize,
+    /// `(row, col, value)` of every entry, in row-major order.
+    entries: Vec<(usize, usize, V)>,
+    included: Vec<bool>,
+    shrink: CsrShrink,
+    prev_shrink: Option<CsrShrink>,
+}
+
+impl<V> ValueTree for CsrValueTree<V>
+where
+    V: ValueTree,
+    V::Value: Scalar,
+{
+    type Value = CsrMatrix<V::Value>;
+
+    fn current(&self) -> Self::Value {
+        let mut counts = vec![0usize; self.nrows];
+        let mut indices = Vec::with_capacity(self.entries.len());
+        let mut data = Vec::with_capacity(self.entries.len());
+
+        for ((row, col, value), _) in self
+            .entries
+            .iter()
+            .zip(&self.included)
+            .filter(|(_, &included)| included)
+        {
+            counts[*row] += 1;
+            indices.push(*col);
+            data.push(value.current());
+        }
+
+        let offsets = CountToOffsetIter::new(counts).collect();
+
+        CsrMatrix::try_from_parts(self.nrows, self.ncols, offsets, indices, data)
+            .expect("Internal error: Shrunk CsrMatrix is invalid")
+    }
+
+    fn simplify(&mut self) -> bool {
+        if let CsrShrink::DeleteEntry(ix) = self.shrink {
+            if ix < self.entries.len() {
+                self.included[ix] = false;
+                self.prev_shrink = Some(self.shrink);
+                self.shrink = CsrShrink::DeleteEntry(ix + 1);
+                return true;
+            }
+
+            // Every deletion that is still in place has been accepted, so the deleted entries
+            // can be dropped for good before the indices start moving around.
+            let included = std::mem::take(&mut self.included);
+            let mut included = included.into_iter();
+            self.entries.retain(|_| included.next().unwrap_or(false));
+            self.included = vec![true; self.entries.len()];
+            self.prev_shrink = None;
+            self.shrink = CsrShrink::DeleteRow(0);
+        }
+
+        while let CsrShrink::DeleteRow(row) = self.shrink {
+            if row >= self.nrows || self.nrows <= self.min_rows {
+                self.shrink = CsrShrink::DeleteColumn(0);
+            } else if self.entries.iter().any(|(i, _, _)| *i == row) {
+                self.shrink = CsrShrink::DeleteRow(row + 1);
+            } else {
+                self.entries
+                    .iter_mut()
+                    .filter(|(i, _, _)| *i > row)
+                    .for_each(|(i, _, _)| *i -= 1);
+                self.nrows -= 1;
+                // The next row has been shifted into `row`, so we stay put.
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+        }
+
+        while let CsrShrink::DeleteColumn(col) = self.shrink {
+            if col >= self.ncols || self.ncols <= self.min_cols {
+                self.shrink = CsrShrink::ShrinkValue(0);
+            } else if self.entries.iter().any(|(_, j, _)| *j == col) {
+                self.shrink = CsrShrink::DeleteColumn(col + 1);
+            } else {
+                self.entries
+                    .iter_mut()
+                    .filter(|(_, j, _)| *j > col)
+                    .for_each(|(_, j, _)| *j -= 1);
+                self.ncols -= 1;
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+        }
+
+        while let CsrShrink::ShrinkValue(ix) = self.shrink {
+            if ix >= self.entries.len() {
+                // Nothing more we can do
+                return false;
+            }
+
+            if self.entries[ix].2.simplify() {
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+
+            self.shrink = CsrShrink::ShrinkValue(ix + 1);
+        }
+
+        unreachable!("Unexpected shrink state")
+    }
+
+    fn complicate(&mut self) -> bool {
+        match self.prev_shrink {
+            None => false,
+            Some(CsrShrink::DeleteEntry(ix)) => {
+                self.included[ix] = true;
+                self.prev_shrink = None;
+                true
+            }
+            Some(CsrShrink::DeleteRow(row)) => {
+                self.entries
+                    .iter_mut()
+                    .filter(|(i, _, _)| *i >= row)
+                    .for_each(|(i, _, _)| *i += 1);
+                self.nrows += 1;
+                self.shrink = CsrShrink::DeleteRow(row + 1);
+                self.prev_shrink = None;
+                true
+            }
+            Some(CsrShrink::DeleteColumn(col)) => {
+                self.entries
+                    .iter_mut()
+                    .filter(|(_, j, _)| *j >= col)
+                    .for_each(|(_, j, _)| *j += 1);
+                self.ncols += 1;
+                self.shrink = CsrShrink::DeleteColumn(col + 1);
+                self.prev_shrink = None;
+                true
+            }
+            Some(CsrShrink::ShrinkValue(ix)) => {
+                if self.entries[ix].2.complicate() {
+                    // We may be able to complicate the value further.
+                    true
+                } else {
+                    self.prev_shrink = None;
+                    false
+                }
+            }
+        }
+    }
 }
 
 /// Range of acceptable matrix dimensions
@@ -492,3 +831,66 @@ pub fn csr_positive_definite() -> impl Strategy<Value = CsrMatrix<f64>> {
 pub fn csc_positive_definite() -> impl Strategy<Value = CscMatrix<f64>> {
     csr_positive_definite().prop_map(|csr| csr.transpose_owned())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::test_runner::{Config, TestError};
+
+    /// Runs `property` against `strategy` and returns the minimal counterexample found.
+    fn minimal_failure<S>(strategy: S, property: impl Fn(&S::Value) -> bool) -> S::Value
+    where
+        S: Strategy,
+    {
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: None,
+            ..Config::default()
+        });
+
+        match runner.run(&strategy, |value| {
+            prop_assert!(property(&value));
+            Ok(())
+        }) {
+            Err(TestError::Fail(_, value)) => value,
+            result => panic!("Expected the property to fail, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn csr_shrinks_to_single_entry() {
+        let csr = minimal_failure(csr(0..=5, 0..=6, 0..=6, 40), |csr| {
+            csr.triplet_iter().all(|(_, _, &v)| v < 3)
+        });
+
+        assert_eq!(csr.shape(), (1, 1));
+        assert_eq!(csr.cs_data(), (&[0][..], &[0][..], &[3][..]));
+    }
+
+    #[test]
+    fn csr_shrinks_without_empty_lanes() {
+        let csr = minimal_failure(csr(0..=5, 0..=6, 0..=6, 40), |csr| csr.nnz() < 2);
+
+        assert_eq!(csr.nnz(), 2);
+        assert!(csr.triplet_iter().all(|(_, _, &v)| v == 0));
+        assert!(csr.iter().all(|lane| lane.len() > 0));
+        assert!((0..csr.ncols()).all(|j| csr.triplet_iter().any(|(_, col, _)| col == j)));
+    }
+
+    #[test]
+    fn csr_respects_minimum_dimensions() {
+        let csr = minimal_failure(csr(0..=5, 2..=6, 3..=6, 40), |csr| csr.nnz() < 1);
+
+        assert_eq!(csr.shape(), (2, 3));
+        assert_eq!(csr.nnz(), 1);
+    }
+
+    #[test]
+    fn csc_shrinks_structurally() {
+        let csc = minimal_failure(csc(0..=5, 0..=6, 0..=6, 40), |csc| {
+            csc.triplet_iter().all(|(_, _, &v)| v < 3)
+        });
+
+        assert_eq!(csc.shape(), (1, 1));
+        assert_eq!(csc.cs_data(), (&[0][..], &[0][..], &[3][..]));
+    }
+}
@@ -0,0 +1,244 @@
+//! A CSR matrix type that stores its values quantized to 8-bit integers.
+//!
+//! Sparse inference workloads (e.g. pruned neural networks) are usually bound by memory bandwidth
+//! rather than by arithmetic. [`QuantizedCsrMatrix`] reduces the size of the values by storing
+//! each of them as an `i8`, along with a single scale per row:
+//!
+//! ```text
+//! value ≈ scale[row] * quantized
+//! ```
+//!
+//! The scale of every row is chosen such that the entry with the largest magnitude in that row
+//! maps to `±127`, and all other entries are rounded to the nearest multiple of the scale. The
+//! error of every entry is therefore at most half of the scale of its row.
+//!
+//! Quantization is lossy, so unlike the other formats in this crate there is no `From`
+//! conversion. Use [`QuantizedCsrMatrix::quantize`] and [`QuantizedCsrMatrix::dequantize`]
+//! explicitly instead. Products are computed with
+//! [`spmv_quantized_csr`](crate::ops::serial::spmm::spmv_quantized_csr), which dequantizes on the
+//! fly.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra_sparse::{cs::CsrMatrix, quantized::QuantizedCsrMatrix};
+//!
+//! let csr = CsrMatrix::try_from_parts(2, 2, vec![0, 2], vec![0, 1, 1], vec![0.5, -1.0, 3.0])
+//!     .unwrap();
+//! let quantized = QuantizedCsrMatrix::quantize(&csr);
+//!
+//! assert_eq!(quantized.values(), &[64, -127, 127]);
+//! assert_eq!(quantized.scales(), &[1.0 / 127.0, 3.0 / 127.0]);
+//! ```
+
+use crate::cs::{CompressedRowStorage, CsMatrix, CsrMatrix};
+use nalgebra::RealField;
+use std::borrow::Borrow;
+
+/// A CSR matrix whose values are quantized to `i8`, with one scale of type `T` per row.
+///
+/// The sparsity pattern has the same layout and invariants as a [`CsrMatrix`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizedCsrMatrix<T> {
+    shape: (usize, usize),
+    offsets: Vec<usize>,
+    indices: Vec<usize>,
+    values: Vec<i8>,
+    scales: Vec<T>,
+}
+
+impl<T> QuantizedCsrMatrix<T>
+where
+    T: RealField,
+{
+    /// Quantizes the values of a CSR matrix, choosing the scale of every row from the entry with
+    /// the largest magnitude in that row.
+    ///
+    /// Rows that are empty or only contain zeros get a scale of zero. The pattern of `csr`,
+    /// including explicit zeros, is preserved as-is.
+    pub fn quantize<MO, MI, D>(csr: &CsMatrix<T, MO, MI, D, CompressedRowStorage>) -> Self
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let (nrows, ncols) = csr.shape();
+        let _span = span!(
+            "quantize_csr",
+            nrows = nrows,
+            ncols = ncols,
+            nnz = csr.nnz()
+        );
+
+        let (offsets, indices, _) = csr.cs_data();
+        let max_quantized = nalgebra::convert::<f64, T>(i8::MAX as f64);
+
+        let mut values = Vec::with_capacity(csr.nnz());
+        let mut scales = Vec::with_capacity(nrows);
+
+        for lane in csr.iter() {
+            let max = lane
+                .clone()
+                .fold(T::zero(), |max, (_, value)| max.max(value.clone().abs()));
+
+            let scale = max / max_quantized.clone();
+
+            values.extend(lane.map(|(_, value)| {
+                if scale.is_zero() {
+                    0
+                } else {
+                    let quantized = (value.clone() / scale.clone()).round();
+
+                    // The division can only overshoot ±127 through rounding errors, so clamp.
+                    nalgebra::try_convert::<T, f64>(quantized)
+                        .map_or(0, |q| q.clamp(-127.0, 127.0) as i8)
+                }
+            }));
+
+            scales.push(scale);
+        }
+
+        Self {
+            shape: (nrows, ncols),
+            offsets: offsets.to_vec(),
+            indices: indices.to_vec(),
+            values,
+            scales,
+        }
+    }
+
+    /// Converts the quantized values back to a [`CsrMatrix`] with the same pattern.
+    #[must_use]
+    pub fn dequantize(&self) -> CsrMatrix<T> {
+        let data = self
+            .iter()
+            .zip(&self.scales)
+            .flat_map(|(lane, scale)| {
+                lane.map(move |(_, &value)| scale.clone() * nalgebra::convert(value as f64))
+            })
+            .collect();
+
+        unsafe {
+            CsrMatrix::from_parts_unchecked(
+                self.shape.0,
+                self.shape.1,
+                self.offsets.clone(),
+                self.indices.clone(),
+                data,
+            )
+        }
+    }
+}
+
+impl<T> QuantizedCsrMatrix<T> {
+    /// The shape of the matrix, as (nrows, ncols).
+    #[inline]
+    #[must_use]
+    pub fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    /// The number of rows in this matrix.
+    #[inline]
+    #[must_use]
+    pub fn nrows(&self) -> usize {
+        self.shape.0
+    }
+
+    /// The number of columns in this matrix.
+    #[inline]
+    #[must_use]
+    pub fn ncols(&self) -> usize {
+        self.shape.1
+    }
+
+    /// Returns the number of non-zero entries in the sparse matrix.
+    #[inline]
+    #[must_use]
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// The row offsets of the matrix.
+    #[must_use]
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// The column indices of every explicit entry, in row-major order.
+    #[must_use]
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// The quantized values of every explicit entry, in row-major order.
+    #[must_use]
+    pub fn values(&self) -> &[i8] {
+        &self.values
+    }
+
+    /// The scale of every row.
+    #[must_use]
+    pub fn scales(&self) -> &[T] {
+        &self.scales
+    }
+
+    /// An iterator over the rows of the matrix, where each row yields `(column, &quantized)`
+    /// pairs.
+    ///
+    /// The scale of each row is not applied; see [`QuantizedCsrMatrix::scales`].
+    pub fn iter(&self) -> impl Iterator<Item = impl Iterator<Item = (usize, &i8)> + '_> + '_ {
+        (0..self.offsets.len()).map(move |row| {
+            let start = self.offsets[row];
+            let end = self
+                .offsets
+                .get(row + 1)
+                .copied()
+                .unwrap_or(self.indices.len());
+
+            self.indices[start..end]
+                .iter()
+                .copied()
+                .zip(&self.values[start..end])
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn quantize_zero_rows_have_zero_scale() {
+        let csr =
+            CsrMatrix::try_from_parts(3, 2, vec![0, 0, 2], vec![0, 1, 0], vec![0.0, 0.0, 2.0])
+                .unwrap();
+        let quantized = QuantizedCsrMatrix::quantize(&csr);
+
+        assert_eq!(quantized.scales(), &[0.0, 0.0, 2.0 / 127.0]);
+        assert_eq!(quantized.values(), &[0, 0, 127]);
+        assert_eq!(quantized.dequantize().cs_data(), csr.cs_data());
+    }
+
+    proptest! {
+        #[test]
+        fn dequantize_error_is_bounded_by_half_the_scale(
+            csr in csr(-100.0..100.0, PROPTEST_MATRIX_DIM, PROPTEST_MATRIX_DIM, PROPTEST_MAX_NNZ)
+        ) {
+            let quantized = QuantizedCsrMatrix::quantize(&csr);
+            let dequantized = quantized.dequantize();
+
+            prop_assert_eq!(dequantized.cs_data().0, csr.cs_data().0);
+            prop_assert_eq!(dequantized.cs_data().1, csr.cs_data().1);
+
+            for (i, j, v) in dequantized.triplet_iter() {
+                let original: f64 = csr.get_entry(i, j).unwrap().into_value();
+                let tolerance: f64 = quantized.scales()[i] * (0.5 + 1e-9);
+
+                prop_assert!((v - original).abs() <= tolerance);
+            }
+        }
+    }
+}
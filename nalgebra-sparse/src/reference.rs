@@ -0,0 +1,564 @@
+//! Naive, dense-backed reference implementations of the sparse matrix operations.
+//!
+//! Every routine in this module converts its sparse arguments into dense matrices and then
+//! performs the operation with plain nested loops. They take `O(nrows * ncols)` memory and are
+//! orders of magnitude slower than the routines in [`ops`](crate::ops), but they are simple
+//! enough to be obviously correct.
+//!
+//! The crate uses them to differential-test the optimized kernels, and they are public so that
+//! users can do the same, or fall back to a known-correct result when debugging. They accept
+//! both CSR and CSC matrices (in any combination) and always return a dense [`DMatrix`].
+//!
+//! **This module is only available if the `proptest-support` feature is enabled**.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra::DMatrix;
+//! use nalgebra_sparse::{cs::CsrMatrix, ops::serial::spadd::spadd_csr_csr, reference};
+//!
+//! let a = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![0, 1], vec![1, 2]).unwrap();
+//! let b = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![1, 0], vec![3, 4]).unwrap();
+//!
+//! let expected = reference::add(&a, &b).unwrap();
+//! let sum = spadd_csr_csr(a.to_view(), b.to_view()).unwrap();
+//!
+//! assert_eq!(DMatrix::from(&sum), expected);
+//! ```
+
+use crate::{
+    cs::{Compression, CsMatrix},
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::{DMatrix, Dim, Matrix, RawStorage, RealField, Scalar};
+use num_traits::Zero;
+use std::{
+    borrow::Borrow,
+    ops::{Add, Div, Mul, Sub},
+};
+
+/// Converts a CSR or CSC matrix into a dense matrix, entry by entry.
+pub fn to_dense<T, MO, MI, D, C>(cs: &CsMatrix<T, MO, MI, D, C>) -> DMatrix<T>
+where
+    T: Scalar + Zero,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let (nrows, ncols) = cs.shape();
+    let mut dense = DMatrix::zeros(nrows, ncols);
+
+    for (major, minor, value) in cs.triplet_iter() {
+        dense[(C::nmajor(major, minor), C::nminor(major, minor))] = value.clone();
+    }
+
+    dense
+}
+
+/// Computes the transpose of a CSR or CSC matrix.
+pub fn transpose<T, MO, MI, D, C>(cs: &CsMatrix<T, MO, MI, D, C>) -> DMatrix<T>
+where
+    T: Scalar + Zero,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    let dense = to_dense(cs);
+    let (nrows, ncols) = dense.shape();
+
+    DMatrix::from_fn(ncols, nrows, |i, j| dense[(j, i)].clone())
+}
+
+/// Computes the sum `A + B` of two sparse matrices.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if the two
+/// matrices do not have the same shape.
+pub fn add<T, MO1, MI1, D1, C1, MO2, MI2, D2, C2>(
+    lhs: &CsMatrix<T, MO1, MI1, D1, C1>,
+    rhs: &CsMatrix<T, MO2, MI2, D2, C2>,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: Scalar + Zero + Add<Output = T>,
+    MO1: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    D1: Borrow<[T]>,
+    C1: Compression,
+    MO2: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D2: Borrow<[T]>,
+    C2: Compression,
+{
+    elementwise(&to_dense(lhs), &to_dense(rhs), |a, b| a + b)
+}
+
+/// Computes the difference `A - B` of two sparse matrices.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if the two
+/// matrices do not have the same shape.
+pub fn sub<T, MO1, MI1, D1, C1, MO2, MI2, D2, C2>(
+    lhs: &CsMatrix<T, MO1, MI1, D1, C1>,
+    rhs: &CsMatrix<T, MO2, MI2, D2, C2>,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: Scalar + Zero + Sub<Output = T>,
+    MO1: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    D1: Borrow<[T]>,
+    C1: Compression,
+    MO2: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D2: Borrow<[T]>,
+    C2: Compression,
+{
+    elementwise(&to_dense(lhs), &to_dense(rhs), |a, b| a - b)
+}
+
+/// Computes the product `A * B` of two sparse matrices.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if the number
+/// of columns of `A` is not equal to the number of rows of `B`.
+pub fn mul<T, MO1, MI1, D1, C1, MO2, MI2, D2, C2>(
+    lhs: &CsMatrix<T, MO1, MI1, D1, C1>,
+    rhs: &CsMatrix<T, MO2, MI2, D2, C2>,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: Scalar + Zero + Add<Output = T> + Mul<Output = T>,
+    MO1: Borrow<[usize]>,
+    MI1: Borrow<[usize]>,
+    D1: Borrow<[T]>,
+    C1: Compression,
+    MO2: Borrow<[usize]>,
+    MI2: Borrow<[usize]>,
+    D2: Borrow<[T]>,
+    C2: Compression,
+{
+    product(&to_dense(lhs), &to_dense(rhs))
+}
+
+/// Computes the product `A * B` of a sparse matrix and a dense matrix.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if the number
+/// of columns of `A` is not equal to the number of rows of `B`.
+pub fn mul_dense<T, MO, MI, D, C, R, K, S>(
+    lhs: &CsMatrix<T, MO, MI, D, C>,
+    rhs: &Matrix<T, R, K, S>,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: Scalar + Zero + Add<Output = T> + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+    R: Dim,
+    K: Dim,
+    S: RawStorage<T, R, K>,
+{
+    product(&to_dense(lhs), &copy_dense(rhs))
+}
+
+/// Computes the product `A * B` of a dense matrix and a sparse matrix.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if the number
+/// of columns of `A` is not equal to the number of rows of `B`.
+pub fn dense_mul<T, MO, MI, D, C, R, K, S>(
+    lhs: &Matrix<T, R, K, S>,
+    rhs: &CsMatrix<T, MO, MI, D, C>,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: Scalar + Zero + Add<Output = T> + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+    R: Dim,
+    K: Dim,
+    S: RawStorage<T, R, K>,
+{
+    product(&copy_dense(lhs), &to_dense(rhs))
+}
+
+/// Multiplies every entry of a sparse matrix by a scalar.
+pub fn scale<T, MO, MI, D, C>(cs: &CsMatrix<T, MO, MI, D, C>, scalar: T) -> DMatrix<T>
+where
+    T: Scalar + Zero + Mul<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    to_dense(cs).map(|value| value * scalar.clone())
+}
+
+/// Divides every entry of a sparse matrix by a scalar.
+pub fn divide<T, MO, MI, D, C>(cs: &CsMatrix<T, MO, MI, D, C>, scalar: T) -> DMatrix<T>
+where
+    T: Scalar + Zero + Div<Output = T>,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+{
+    to_dense(cs).map(|value| value / scalar.clone())
+}
+
+/// Solves `A x = B` by forward substitution, where `A` is lower-triangular.
+///
+/// Entries above the diagonal of `A` are ignored.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if `A` is not
+/// square or `B` does not have as many rows as `A`, and with kind
+/// [`OperationErrorKind::Singular`] if the diagonal of `A` contains a zero.
+pub fn solve_lower_triangular<T, MO, MI, D, C, R, K, S>(
+    lhs: &CsMatrix<T, MO, MI, D, C>,
+    rhs: &Matrix<T, R, K, S>,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: RealField,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+    R: Dim,
+    K: Dim,
+    S: RawStorage<T, R, K>,
+{
+    let n = lhs.nrows();
+    triangular_solve(lhs, rhs, 0..n, |i, k| k < i)
+}
+
+/// Solves `A x = B` by backward substitution, where `A` is upper-triangular.
+///
+/// Entries below the diagonal of `A` are ignored.
+///
+/// # Errors
+///
+/// Returns an [`OperationError`] with kind [`OperationErrorKind::InvalidPattern`] if `A` is not
+/// square or `B` does not have as many rows as `A`, and with kind
+/// [`OperationErrorKind::Singular`] if the diagonal of `A` contains a zero.
+pub fn solve_upper_triangular<T, MO, MI, D, C, R, K, S>(
+    lhs: &CsMatrix<T, MO, MI, D, C>,
+    rhs: &Matrix<T, R, K, S>,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: RealField,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+    R: Dim,
+    K: Dim,
+    S: RawStorage<T, R, K>,
+{
+    let n = lhs.nrows();
+    triangular_solve(lhs, rhs, (0..n).rev(), |i, k| k > i)
+}
+
+/// Solves a triangular system by substituting rows in the order given by `rows`, where
+/// `is_known(i, k)` holds iff the unknowns of row `k` are solved before those of row `i`.
+fn triangular_solve<T, MO, MI, D, C, R, K, S>(
+    lhs: &CsMatrix<T, MO, MI, D, C>,
+    rhs: &Matrix<T, R, K, S>,
+    rows: impl Iterator<Item = usize>,
+    is_known: impl Fn(usize, usize) -> bool,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: RealField,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    C: Compression,
+    R: Dim,
+    K: Dim,
+    S: RawStorage<T, R, K>,
+{
+    let (n, ncols) = lhs.shape();
+
+    if n != ncols {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("Lefthand matrix is not square."),
+        ));
+    }
+
+    if rhs.nrows() != n {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            format!(
+                "The righthand matrix has {} rows but {} rows are needed to solve this system.",
+                rhs.nrows(),
+                n
+            ),
+        ));
+    }
+
+    let a = to_dense(lhs);
+    let mut x = copy_dense(rhs);
+
+    for i in rows {
+        if a[(i, i)].is_zero() {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::Singular,
+                String::from("Matrix contains at least one diagonal entry that is zero."),
+            ));
+        }
+
+        for j in 0..x.ncols() {
+            let mut value = x[(i, j)].clone();
+
+            for k in (0..n).filter(|&k| is_known(i, k)) {
+                value -= a[(i, k)].clone() * x[(k, j)].clone();
+            }
+
+            x[(i, j)] = value / a[(i, i)].clone();
+        }
+    }
+
+    Ok(x)
+}
+
+/// Copies any dense matrix into a `DMatrix`.
+fn copy_dense<T, R, C, S>(dense: &Matrix<T, R, C, S>) -> DMatrix<T>
+where
+    T: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C>,
+{
+    let (nrows, ncols) = dense.shape();
+    DMatrix::from_fn(nrows, ncols, |i, j| dense[(i, j)].clone())
+}
+
+/// Combines two dense matrices of the same shape entry by entry.
+fn elementwise<T>(
+    lhs: &DMatrix<T>,
+    rhs: &DMatrix<T>,
+    f: impl Fn(T, T) -> T,
+) -> Result<DMatrix<T>, OperationError>
+where
+    T: Scalar,
+{
+    if lhs.shape() != rhs.shape() {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from("The two matrices have differing shapes (both should be M × N)"),
+        ));
+    }
+
+    let (nrows, ncols) = lhs.shape();
+
+    Ok(DMatrix::from_fn(nrows, ncols, |i, j| {
+        f(lhs[(i, j)].clone(), rhs[(i, j)].clone())
+    }))
+}
+
+/// Computes the product of two dense matrices with the textbook triple loop.
+fn product<T>(lhs: &DMatrix<T>, rhs: &DMatrix<T>) -> Result<DMatrix<T>, OperationError>
+where
+    T: Scalar + Zero + Add<Output = T> + Mul<Output = T>,
+{
+    if lhs.ncols() != rhs.nrows() {
+        return Err(OperationError::from_kind_and_message(
+            OperationErrorKind::InvalidPattern,
+            String::from(
+                "The two matrices have incompatible shapes (M × K1 and K2 × N where K1 ≠ K2)",
+            ),
+        ));
+    }
+
+    Ok(DMatrix::from_fn(lhs.nrows(), rhs.ncols(), |i, j| {
+        (0..lhs.ncols()).fold(T::zero(), |sum, k| {
+            sum + lhs[(i, k)].clone() * rhs[(k, j)].clone()
+        })
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cs::{CscMatrix, CsrMatrix},
+        ops::serial::{
+            scalar::{sp_cs_neg, sp_cs_scalar_div, sp_cs_scalar_prod},
+            spadd::{spadd_csc_csr, spadd_csr_csr},
+            spmm::{spmm_csc_dense, spmm_csr_csc, spmm_csr_csr, spmm_dense_csr},
+            spsolve::{spsolve_lower_triangular_csc_dense, spsolve_upper_triangular_csr_dense},
+            spsub::{spsub_csc_csc, spsub_csr_csc},
+        },
+        proptest::*,
+    };
+    use proptest::prelude::*;
+
+    /// Pairs of matrices with matching shapes.
+    fn same_shape_pair() -> impl Strategy<Value = (CsrMatrix<i32>, CsrMatrix<i32>)> {
+        csr_strategy().prop_flat_map(|a| {
+            let (nrows, ncols) = a.shape();
+            let b = csr(
+                PROPTEST_I32_VALUE_STRATEGY,
+                nrows..=nrows,
+                ncols..=ncols,
+                PROPTEST_MAX_NNZ,
+            );
+
+            (Just(a), b)
+        })
+    }
+
+    /// Pairs of matrices with compatible shapes for a product.
+    fn product_pair() -> impl Strategy<Value = (CsrMatrix<i32>, CsrMatrix<i32>)> {
+        csr_strategy().prop_flat_map(|a| {
+            let b = csr(
+                PROPTEST_I32_VALUE_STRATEGY,
+                a.ncols()..=a.ncols(),
+                PROPTEST_MATRIX_DIM,
+                PROPTEST_MAX_NNZ,
+            );
+
+            (Just(a), b)
+        })
+    }
+
+    /// Lower-triangular CSR matrices with a non-zero diagonal.
+    fn lower_triangular() -> impl Strategy<Value = CsrMatrix<f64>> {
+        (1usize..=6)
+            .prop_flat_map(|n| csr(-5.0..=5.0, n..=n, n..=n, PROPTEST_MAX_NNZ))
+            .prop_map(|csr| {
+                let mut dense = DMatrix::from(&csr).lower_triangle();
+                dense.fill_diagonal(10.0);
+                CsrMatrix::from(&dense)
+            })
+    }
+
+    #[test]
+    fn solves_report_singular_diagonal() {
+        let csr = CsrMatrix::try_from_parts(2, 2, vec![0, 1], vec![0, 0], vec![1.0, 1.0]).unwrap();
+        let rhs = DMatrix::from_element(2, 1, 1.0);
+
+        let err = solve_lower_triangular(&csr, &rhs).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::Singular));
+
+        let err = solve_upper_triangular(&csr.to_view(), &DMatrix::<f64>::zeros(3, 1)).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn products_with_empty_lanes_and_explicit_zeros_agree_with_reference() {
+        let a = CsrMatrix::<i32>::try_from_parts(1, 1, vec![0], vec![], vec![]).unwrap();
+        let b = CsrMatrix::try_from_parts(1, 2, vec![0], vec![1], vec![0]).unwrap();
+        let b_csc = CscMatrix::from(b.clone());
+        let b_dense = DMatrix::from(&b);
+        let expected = mul(&a, &b).unwrap();
+
+        assert_eq!(expected, DMatrix::zeros(1, 2));
+        assert_eq!(
+            DMatrix::from(&spmm_csr_csr(a.to_view(), b.to_view()).unwrap()),
+            expected
+        );
+        assert_eq!(
+            DMatrix::from(&spmm_csr_csc(a.to_view(), b_csc.to_view()).unwrap()),
+            expected
+        );
+        assert_eq!(mul_dense(&a, &b_dense).unwrap(), expected);
+        assert_eq!(dense_mul(&DMatrix::from(&a), &b_csc).unwrap(), expected);
+
+        let a_csc = CscMatrix::from(a.clone());
+        assert_eq!(
+            DMatrix::from(&spmm_csc_dense(a_csc.to_view(), b_dense).unwrap()),
+            expected
+        );
+        assert_eq!(
+            DMatrix::from(&spmm_dense_csr(DMatrix::from(&a), b.to_view()).unwrap()),
+            expected
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn to_dense_agrees_with_conversions(csr in csr_strategy(), csc in csc_strategy()) {
+            prop_assert_eq!(to_dense(&csr), DMatrix::from(&csr));
+            prop_assert_eq!(to_dense(&csc), DMatrix::from(&csc));
+            prop_assert_eq!(transpose(&csr), DMatrix::from(&csr).transpose());
+        }
+
+        #[test]
+        fn spadd_agrees_with_reference((a, b) in same_shape_pair()) {
+            let csc = CscMatrix::from(b.clone());
+            let expected = add(&a, &b).unwrap();
+
+            prop_assert_eq!(DMatrix::from(&spadd_csr_csr(a.to_view(), b.to_view()).unwrap()), expected.clone());
+            prop_assert_eq!(DMatrix::from(&spadd_csc_csr(csc.to_view(), a.to_view()).unwrap()), expected);
+        }
+
+        #[test]
+        fn spsub_agrees_with_reference((a, b) in same_shape_pair()) {
+            let a_csc = CscMatrix::from(a.clone());
+            let b_csc = CscMatrix::from(b.clone());
+            let expected = sub(&a, &b).unwrap();
+
+            prop_assert_eq!(DMatrix::from(&spsub_csr_csc(a.to_view(), b_csc.to_view()).unwrap()), expected.clone());
+            prop_assert_eq!(DMatrix::from(&spsub_csc_csc(a_csc.to_view(), b_csc.to_view()).unwrap()), expected);
+        }
+
+        #[test]
+        fn spmm_agrees_with_reference((a, b) in product_pair()) {
+            let b_csc = CscMatrix::from(b.clone());
+            let b_dense = DMatrix::from(&b);
+            let expected = mul(&a, &b).unwrap();
+
+            prop_assert_eq!(DMatrix::from(&spmm_csr_csr(a.to_view(), b.to_view()).unwrap()), expected.clone());
+            prop_assert_eq!(DMatrix::from(&spmm_csr_csc(a.to_view(), b_csc.to_view()).unwrap()), expected.clone());
+            prop_assert_eq!(mul_dense(&a, &b_dense).unwrap(), expected.clone());
+            prop_assert_eq!(dense_mul(&DMatrix::from(&a), &b_csc).unwrap(), expected.clone());
+
+            let a_csc = CscMatrix::from(a.clone());
+            prop_assert_eq!(DMatrix::from(&spmm_csc_dense(a_csc.to_view(), b_dense).unwrap()), expected.clone());
+            prop_assert_eq!(DMatrix::from(&spmm_dense_csr(DMatrix::from(&a), b.to_view()).unwrap()), expected);
+        }
+
+        #[test]
+        fn scalar_ops_agree_with_reference(csr in csr_strategy(), scalar in 1..=5i32) {
+            prop_assert_eq!(DMatrix::from(&sp_cs_scalar_prod(csr.to_view(), scalar)), scale(&csr, scalar));
+            prop_assert_eq!(DMatrix::from(&sp_cs_scalar_div(csr.to_view(), scalar)), divide(&csr, scalar));
+            prop_assert_eq!(DMatrix::from(&sp_cs_neg(csr.to_view())), scale(&csr, -1));
+        }
+
+        #[test]
+        fn operators_agree_with_reference(csr in csr_strategy(), scalar in 1..=5i32) {
+            let csc = CscMatrix::from(csr.clone());
+
+            prop_assert_eq!(DMatrix::from(&(scalar * csr.clone())), scale(&csr, scalar));
+            prop_assert_eq!(DMatrix::from(&(scalar * csc.clone())), scale(&csr, scalar));
+            prop_assert_eq!(DMatrix::from(&-csr.clone()), scale(&csr, -1));
+            prop_assert_eq!(DMatrix::from(&-csc), scale(&csr, -1));
+        }
+
+        #[test]
+        fn triangular_solves_agree_with_reference(l in lower_triangular(), seed in dense_strategy()) {
+            let n = l.nrows();
+            let rhs = DMatrix::from_fn(n, seed.ncols(), |i, j| f64::from(seed.get((i, j)).copied().unwrap_or(1)));
+
+            let expected = solve_lower_triangular(&l, &rhs).unwrap();
+            let actual = spsolve_lower_triangular_csc_dense(CscMatrix::from(l.clone()).to_view(), rhs.clone()).unwrap();
+            prop_assert!((actual - &expected).amax() < 1e-9);
+            prop_assert!((DMatrix::from(&l) * &expected - &rhs).amax() < 1e-9);
+
+            let u = CsrMatrix::from(&DMatrix::from(&l).transpose());
+            let expected = solve_upper_triangular(&u, &rhs).unwrap();
+            let actual = spsolve_upper_triangular_csr_dense(u.to_view(), rhs).unwrap();
+            prop_assert!((actual - expected).amax() < 1e-9);
+        }
+    }
+}
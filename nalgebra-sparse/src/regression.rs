@@ -0,0 +1,414 @@
+//! Regularized least-squares regression on sparse design matrices.
+//!
+//! [`ElasticNet`] fits the coefficients `β` of a linear model `y ≈ X β` by minimizing
+//!
+//! ```text
+//! ½ ‖y - X β‖² + λ₁ ‖β‖₁ + ½ λ₂ ‖β‖²
+//! ```
+//!
+//! which is the lasso for `λ₂ = 0`, ridge regression for `λ₁ = 0`, and the elastic net in
+//! between. It uses cyclic coordinate descent, which only ever accesses one column (feature) of
+//! `X` at a time, and therefore takes `X` in CSC format. Every coordinate update costs a pass over
+//! the non-zeros of its column, so a sweep over all features costs `O(nnz(X))`.
+//!
+//! Since the L1 penalty makes most coefficients exactly zero, the solver works on an *active set*:
+//! after a sweep over all features, it only sweeps over the features with non-zero coefficients
+//! until those settle, and then checks with another full sweep whether any other feature has to
+//! enter the model. The solver only reports convergence after a full sweep, so the screened
+//! features are always checked before the solver returns.
+//!
+//! No intercept is fitted, as centering the columns of `X` would destroy its sparsity. Center `y`
+//! (and account for the column means in the model) or add a column of ones to `X` instead.
+
+use crate::{
+    cs::{CompressedColumnStorage, CsMatrix},
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::{DVector, RealField};
+use std::borrow::Borrow;
+
+/// Why an [`ElasticNet`] fit stopped.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ElasticNetStatus {
+    /// A sweep over all features changed the fitted values by less than the requested tolerance.
+    Converged,
+
+    /// The maximum number of sweeps was performed without reaching the tolerance.
+    MaxSweepsReached,
+}
+
+/// The outcome of an [`ElasticNet`] fit.
+#[derive(Debug, Clone)]
+pub struct ElasticNetSolution<T> {
+    /// The fitted coefficients `β`.
+    pub coefficients: DVector<T>,
+
+    /// Why the solver stopped.
+    pub status: ElasticNetStatus,
+
+    /// The number of sweeps over all features.
+    pub full_sweeps: usize,
+
+    /// The number of sweeps over the active set only.
+    pub active_sweeps: usize,
+}
+
+/// Coordinate descent solver for L1/L2-regularized least-squares problems. See the
+/// [module-level documentation](self).
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::{DMatrix, DVector};
+/// use nalgebra_sparse::{
+///     cs::CscMatrix,
+///     regression::{ElasticNet, ElasticNetStatus},
+/// };
+///
+/// let x = CscMatrix::from(&DMatrix::from_row_slice(
+///     4,
+///     3,
+///     &[1.0, 0.0, 0.5, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+/// ));
+/// let y = DVector::from_vec(vec![2.0, 0.1, 2.1, 0.0]);
+///
+/// let fit = ElasticNet::lasso(0.5).with_tolerance(1e-12).solve(&x, &y).unwrap();
+///
+/// assert_eq!(fit.status, ElasticNetStatus::Converged);
+/// // The weak features are screened out entirely
+/// assert!(fit.coefficients[0] > 1.0);
+/// assert_eq!(fit.coefficients[1], 0.0);
+/// assert_eq!(fit.coefficients[2], 0.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ElasticNet<T> {
+    l1: T,
+    l2: T,
+    max_sweeps: usize,
+    tolerance: T,
+}
+
+impl<T> ElasticNet<T>
+where
+    T: RealField,
+{
+    /// Creates a solver with the penalties `λ₁ = l1` and `λ₂ = l2`, at most 1000 sweeps and a
+    /// relative tolerance of `1e-8`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either penalty is negative.
+    #[must_use]
+    pub fn new(l1: T, l2: T) -> Self {
+        assert!(
+            l1 >= T::zero() && l2 >= T::zero(),
+            "The regularization penalties must be non-negative."
+        );
+
+        Self {
+            l1,
+            l2,
+            max_sweeps: 1000,
+            tolerance: nalgebra::convert(1e-8),
+        }
+    }
+
+    /// Creates a solver for the lasso, i.e. with only the L1 penalty `λ₁ = l1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `l1` is negative.
+    #[must_use]
+    pub fn lasso(l1: T) -> Self {
+        Self::new(l1, T::zero())
+    }
+
+    /// Creates a solver for ridge regression, i.e. with only the L2 penalty `λ₂ = l2`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `l2` is negative.
+    #[must_use]
+    pub fn ridge(l2: T) -> Self {
+        Self::new(T::zero(), l2)
+    }
+
+    /// Sets the maximum number of sweeps, counting both full and active-set sweeps.
+    #[must_use]
+    pub fn with_max_sweeps(self, max_sweeps: usize) -> Self {
+        Self { max_sweeps, ..self }
+    }
+
+    /// Sets the tolerance.
+    ///
+    /// A sweep has converged once the largest change it made to the fitted values `X β` by
+    /// updating a single coefficient, `‖x_j‖ |Δβ_j|`, is at most `tolerance * ‖y‖`.
+    #[must_use]
+    pub fn with_tolerance(self, tolerance: T) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Fits the coefficients, starting from zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `y` does not
+    /// have as many rows as `x`.
+    pub fn solve<MO, MI, D>(
+        &self,
+        x: &CsMatrix<T, MO, MI, D, CompressedColumnStorage>,
+        y: &DVector<T>,
+    ) -> Result<ElasticNetSolution<T>, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        self.solve_with_initial_guess(x, y, DVector::zeros(x.ncols()))
+    }
+
+    /// Fits the coefficients, starting from `initial`.
+    ///
+    /// Starting from the solution for a slightly larger penalty makes computing a whole
+    /// regularization path much cheaper than solving for every penalty from scratch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `y` does not
+    /// have as many rows as `x`, or `initial` does not have as many rows as `x` has columns.
+    pub fn solve_with_initial_guess<MO, MI, D>(
+        &self,
+        x: &CsMatrix<T, MO, MI, D, CompressedColumnStorage>,
+        y: &DVector<T>,
+        initial: DVector<T>,
+    ) -> Result<ElasticNetSolution<T>, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let (nrows, ncols) = x.shape();
+
+        if y.len() != nrows || initial.len() != ncols {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!(
+                    "The design matrix has shape {:?}, but y has {} rows and the initial guess \
+                     has {} rows.",
+                    x.shape(),
+                    y.len(),
+                    initial.len()
+                ),
+            ));
+        }
+
+        let _span = span!("elastic_net", nrows = nrows, ncols = ncols, nnz = x.nnz());
+
+        let squared_norms = x
+            .iter()
+            .map(|column| {
+                column.fold(T::zero(), |norm, (_, value)| {
+                    norm + value.clone() * value.clone()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut coefficients = initial;
+        let mut residual = y.clone();
+        for (column, beta) in x.iter().zip(coefficients.iter()) {
+            for (i, value) in column {
+                residual[i] -= value.clone() * beta.clone();
+            }
+        }
+
+        let threshold = self.tolerance.clone() * y.norm();
+        let all_features = (0..ncols).collect::<Vec<_>>();
+
+        let mut full_sweeps = 0;
+        let mut active_sweeps = 0;
+
+        let status = loop {
+            if full_sweeps + active_sweeps >= self.max_sweeps {
+                break ElasticNetStatus::MaxSweepsReached;
+            }
+
+            full_sweeps += 1;
+            let change = self.sweep(
+                x,
+                &squared_norms,
+                &all_features,
+                &mut coefficients,
+                &mut residual,
+            );
+
+            if change <= threshold {
+                break ElasticNetStatus::Converged;
+            }
+
+            let active = (0..ncols)
+                .filter(|&j| !coefficients[j].is_zero())
+                .collect::<Vec<_>>();
+
+            while full_sweeps + active_sweeps < self.max_sweeps {
+                active_sweeps += 1;
+                let change =
+                    self.sweep(x, &squared_norms, &active, &mut coefficients, &mut residual);
+
+                if change <= threshold {
+                    break;
+                }
+            }
+        };
+
+        Ok(ElasticNetSolution {
+            coefficients,
+            status,
+            full_sweeps,
+            active_sweeps,
+        })
+    }
+
+    /// Updates the coefficients of `features` in turn, keeping `residual = y - X β` up to date,
+    /// and returns the largest change `‖x_j‖ |Δβ_j|` of the fitted values.
+    fn sweep<MO, MI, D>(
+        &self,
+        x: &CsMatrix<T, MO, MI, D, CompressedColumnStorage>,
+        squared_norms: &[T],
+        features: &[usize],
+        coefficients: &mut DVector<T>,
+        residual: &mut DVector<T>,
+    ) -> T
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let mut max_change = T::zero();
+
+        for &j in features {
+            let squared_norm = squared_norms[j].clone();
+            let denominator = squared_norm.clone() + self.l2.clone();
+
+            if denominator.is_zero() {
+                continue;
+            }
+
+            let column = x.get_lane(j).unwrap();
+            let old = coefficients[j].clone();
+
+            // The correlation of feature j with the residual of the model without it
+            let rho = column
+                .clone()
+                .fold(squared_norm.clone() * old.clone(), |rho, (i, value)| {
+                    rho + value.clone() * residual[i].clone()
+                });
+
+            let new = soft_threshold(rho, self.l1.clone()) / denominator;
+            let delta = new.clone() - old;
+
+            if delta.is_zero() {
+                continue;
+            }
+
+            for (i, value) in column {
+                residual[i] -= value.clone() * delta.clone();
+            }
+
+            coefficients[j] = new;
+            max_change = max_change.max(delta.abs() * squared_norm.sqrt());
+        }
+
+        max_change
+    }
+}
+
+/// The soft-thresholding operator `sign(x) max(|x| - threshold, 0)`.
+fn soft_threshold<T: RealField>(x: T, threshold: T) -> T {
+    if x > threshold {
+        x - threshold
+    } else if x < -threshold.clone() {
+        x + threshold
+    } else {
+        T::zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cs::CscMatrix;
+    use nalgebra::DMatrix;
+
+    /// A deterministic design matrix with roughly a third of its entries set.
+    fn design(nrows: usize, ncols: usize) -> (DMatrix<f64>, CscMatrix<f64>) {
+        let dense = DMatrix::from_fn(nrows, ncols, |i, j| {
+            let hash = (i * 7 + j * 13 + i * j) % 11;
+            if hash < 4 {
+                hash as f64 - 1.5
+            } else {
+                0.0
+            }
+        });
+
+        let csc = CscMatrix::from(&dense);
+        (dense, csc)
+    }
+
+    #[test]
+    fn ridge_agrees_with_the_normal_equations() {
+        let (dense, x) = design(12, 5);
+        let y = DVector::from_fn(12, |i, _| (i as f64).sin());
+
+        let fit = ElasticNet::ridge(0.7)
+            .with_tolerance(1e-14)
+            .solve(&x, &y)
+            .unwrap();
+
+        let normal = dense.transpose() * &dense + DMatrix::identity(5, 5) * 0.7;
+        let expected = normal.lu().solve(&(dense.transpose() * &y)).unwrap();
+
+        assert_eq!(fit.status, ElasticNetStatus::Converged);
+        assert!((fit.coefficients - expected).norm() < 1e-10);
+    }
+
+    #[test]
+    fn elastic_net_satisfies_the_optimality_conditions() {
+        let (dense, x) = design(20, 8);
+        let y = DVector::from_fn(20, |i, _| (i as f64 * 0.7).cos() * 3.0);
+        let (l1, l2) = (1.5, 0.25);
+
+        let fit = ElasticNet::new(l1, l2)
+            .with_tolerance(1e-14)
+            .solve(&x, &y)
+            .unwrap();
+        assert_eq!(fit.status, ElasticNetStatus::Converged);
+
+        let beta = &fit.coefficients;
+        let gradient = dense.transpose() * (&y - &dense * beta) - beta * l2;
+
+        for j in 0..8 {
+            if beta[j] == 0.0 {
+                assert!(gradient[j].abs() <= l1 + 1e-9);
+            } else {
+                assert!((gradient[j] - l1 * beta[j].signum()).abs() < 1e-9);
+            }
+        }
+
+        // A penalty above max |x_jᵀ y| makes every coefficient zero
+        let max_correlation = (dense.transpose() * &y).amax();
+        let empty = ElasticNet::lasso(max_correlation * 1.01)
+            .solve(&x, &y)
+            .unwrap();
+        assert_eq!(empty.coefficients, DVector::zeros(8));
+        assert_eq!((empty.full_sweeps, empty.active_sweeps), (1, 0));
+
+        assert!(matches!(
+            ElasticNet::lasso(1.0)
+                .solve(&x, &DVector::zeros(3))
+                .unwrap_err()
+                .kind(),
+            OperationErrorKind::InvalidPattern
+        ));
+    }
+}
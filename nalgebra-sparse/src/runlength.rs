@@ -0,0 +1,256 @@
+//! A compressed sparse matrix type that stores runs of consecutive minor indices.
+//!
+//! Matrices from structured grids and meshes tend to have lanes made up of a few runs of
+//! consecutive minor indices (e.g. the three neighbouring columns `j - 1, j, j + 1` of a 1D
+//! stencil, repeated for every layer of a 3D grid). [`CsMatrix`] stores one minor index per
+//! explicit entry regardless, so a kernel has to read as many indices as values.
+//! [`RunLengthCsMatrix`] instead stores every maximal run of consecutive minor indices as a single
+//! `(start, len)` pair, so that kernels can process each run with a dense inner loop, e.g.
+//! [`spmv_run_length_csr`](crate::ops::serial::spmm::spmv_run_length_csr).
+//!
+//! For unstructured matrices where most runs have length one, this layout needs more memory than
+//! [`CsMatrix`]. Use [`RunLengthCsMatrix::nruns`] to check how well a matrix compresses.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra_sparse::{cs::CsrMatrix, runlength::RunLengthCsrMatrix};
+//!
+//! let csr = CsrMatrix::try_from_parts(
+//!     2,
+//!     6,
+//!     vec![0, 4],
+//!     vec![0, 1, 2, 5, 3, 4],
+//!     vec![1, 2, 3, 4, 5, 6],
+//! )
+//! .unwrap();
+//!
+//! let run_length = RunLengthCsrMatrix::from(&csr);
+//!
+//! assert_eq!(run_length.runs(), &[(0, 3), (5, 1), (3, 2)]);
+//! assert_eq!(CsrMatrix::from(run_length).cs_data(), csr.cs_data());
+//! ```
+
+use crate::cs::{CompressedColumnStorage, CompressedRowStorage, Compression};
+use nalgebra::Scalar;
+use std::{marker::PhantomData, ops::Range};
+
+#[cfg(doc)]
+use crate::cs::CsMatrix;
+
+/// A compressed sparse matrix that stores the minor indices of each lane as runs of consecutive
+/// indices.
+///
+/// Every run is stored as a `(start, len)` pair, covering the minor indices `start..start + len`.
+/// The runs within a lane are sorted, non-empty and maximal, i.e. two runs in the same lane are
+/// never adjacent. The values are stored contiguously in major -> minor order, exactly as in
+/// [`CsMatrix`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunLengthCsMatrix<T, CompressionKind>
+where
+    T: Scalar,
+    CompressionKind: Compression,
+{
+    shape: (usize, usize),
+    offsets: Vec<usize>,
+    run_offsets: Vec<usize>,
+    runs: Vec<(usize, usize)>,
+    data: Vec<T>,
+    _phantom: PhantomData<CompressionKind>,
+}
+
+/// An alias for a run-length, row-major compressed sparse matrix.
+pub type RunLengthCsrMatrix<T> = RunLengthCsMatrix<T, CompressedRowStorage>;
+
+/// An alias for a run-length, column-major compressed sparse matrix.
+pub type RunLengthCscMatrix<T> = RunLengthCsMatrix<T, CompressedColumnStorage>;
+
+impl<T, CompressionKind> RunLengthCsMatrix<T, CompressionKind>
+where
+    T: Scalar,
+    CompressionKind: Compression,
+{
+    /// Builds a matrix from parts that are known to uphold the invariants of the type.
+    pub(crate) unsafe fn from_parts_unchecked(
+        nrows: usize,
+        ncols: usize,
+        offsets: Vec<usize>,
+        run_offsets: Vec<usize>,
+        runs: Vec<(usize, usize)>,
+        data: Vec<T>,
+    ) -> Self {
+        Self {
+            shape: (nrows, ncols),
+            offsets,
+            run_offsets,
+            runs,
+            data,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The shape of the matrix, as (nrows, ncols).
+    #[inline]
+    #[must_use]
+    pub fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    /// The number of rows in this matrix.
+    #[inline]
+    #[must_use]
+    pub fn nrows(&self) -> usize {
+        self.shape.0
+    }
+
+    /// The number of columns in this matrix.
+    #[inline]
+    #[must_use]
+    pub fn ncols(&self) -> usize {
+        self.shape.1
+    }
+
+    /// The number of lanes along the major dimension of this matrix.
+    #[inline]
+    pub fn nmajor(&self) -> usize {
+        let (rows, cols) = self.shape;
+        CompressionKind::nmajor(rows, cols)
+    }
+
+    /// Returns the number of non-zero entries in the sparse matrix.
+    #[inline]
+    #[must_use]
+    pub fn nnz(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns the number of runs stored across all lanes of the matrix.
+    ///
+    /// This is the number of `(start, len)` pairs stored in place of the `nnz` minor indices of
+    /// the equivalent [`CsMatrix`].
+    #[inline]
+    #[must_use]
+    pub fn nruns(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// The offsets of each lane into the values of the matrix, as in [`CsMatrix`].
+    #[must_use]
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// The offsets of each lane into the runs of the matrix.
+    #[must_use]
+    pub fn run_offsets(&self) -> &[usize] {
+        &self.run_offsets
+    }
+
+    /// The `(start, len)` pairs of every run, in major -> minor order.
+    #[must_use]
+    pub fn runs(&self) -> &[(usize, usize)] {
+        &self.runs
+    }
+
+    /// The values of every explicit entry, in major -> minor order.
+    #[must_use]
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Consumes self and returns the underlying offsets, run offsets, runs and values.
+    pub fn disassemble(self) -> (Vec<usize>, Vec<usize>, Vec<(usize, usize)>, Vec<T>) {
+        (self.offsets, self.run_offsets, self.runs, self.data)
+    }
+
+    /// An iterator over the lanes of the matrix, where each lane yields a `(minor_indices,
+    /// values)` pair for every run in that lane.
+    ///
+    /// The values of a run are a contiguous slice with one value for every minor index in the
+    /// run.
+    pub fn iter(&self) -> impl Iterator<Item = RunLaneIter<'_, T>> + '_ {
+        (0..self.run_offsets.len()).map(move |major_index| {
+            let start = self.run_offsets[major_index];
+            let end = self
+                .run_offsets
+                .get(major_index + 1)
+                .copied()
+                .unwrap_or(self.runs.len());
+
+            RunLaneIter {
+                runs: self.runs[start..end].iter(),
+                data: &self.data[self.offsets[major_index]..],
+            }
+        })
+    }
+}
+
+/// An iterator over the runs of a single lane in a [`RunLengthCsMatrix`].
+///
+/// Yields the range of minor indices covered by every run, along with the values of the run.
+#[derive(Debug, Clone)]
+pub struct RunLaneIter<'a, T> {
+    runs: std::slice::Iter<'a, (usize, usize)>,
+    data: &'a [T],
+}
+
+impl<'a, T> Iterator for RunLaneIter<'a, T> {
+    type Item = (Range<usize>, &'a [T]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let &(start, len) = self.runs.next()?;
+        let (values, rest) = self.data.split_at(len);
+        self.data = rest;
+
+        Some((start..start + len, values))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.runs.size_hint()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for RunLaneIter<'a, T> {}
+
+/// Splits sorted minor `indices` into maximal runs of consecutive indices.
+///
+/// The runs are appended to `runs`, and never merged with any runs that were already in `runs`.
+pub(crate) fn find_runs(indices: &[usize], runs: &mut Vec<(usize, usize)>) {
+    let first = runs.len();
+
+    for &index in indices {
+        match runs[first..].last_mut() {
+            Some((start, len)) if *start + *len == index => *len += 1,
+            _ => runs.push((index, 1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cs::CsrMatrix;
+
+    #[test]
+    fn runs_do_not_span_lanes() {
+        // Row 0 ends at column 2 and row 1 starts at column 3, which must be separate runs.
+        let csr =
+            CsrMatrix::try_from_parts(3, 4, vec![0, 3, 3], vec![0, 1, 2, 3], vec![1, 2, 3, 4])
+                .unwrap();
+
+        let run_length = RunLengthCsrMatrix::from(&csr);
+
+        assert_eq!(run_length.nruns(), 2);
+        assert_eq!(run_length.runs(), &[(0, 3), (3, 1)]);
+        assert_eq!(run_length.run_offsets(), &[0, 1, 1]);
+
+        let lanes = run_length
+            .iter()
+            .map(|lane| lane.collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        assert_eq!(lanes[0], vec![(0..3, &[1, 2, 3][..])]);
+        assert!(lanes[1].is_empty());
+        assert_eq!(lanes[2], vec![(3..4, &[4][..])]);
+    }
+}
@@ -0,0 +1,306 @@
+//! `Serialize` and `Deserialize` implementations for the sparse matrix and pattern types.
+//!
+//! Every type is serialized as a plain struct of its dimensions and index / value arrays.
+//! Deserialization goes through the same checked constructors as user code (e.g.
+//! [`CsMatrix::try_from_parts`]), so malformed input is rejected with an error rather than
+//! producing a matrix that violates its invariants.
+//!
+//! Note that the compression kind of a [`CsMatrix`] is part of its type and not of the serialized
+//! data, so data serialized from a `CsrMatrix` must be deserialized as a `CsrMatrix`.
+
+use crate::{
+    coo::CooMatrix,
+    cs::{Compression, CsMatrix},
+    pattern::SparsityPattern,
+};
+use nalgebra::Scalar;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+
+#[derive(Serialize)]
+struct SparsityPatternSerializationData<'a> {
+    major_dim: usize,
+    minor_dim: usize,
+    major_offsets: &'a [usize],
+    minor_indices: &'a [usize],
+}
+
+#[derive(Deserialize)]
+struct SparsityPatternDeserializationData {
+    major_dim: usize,
+    minor_dim: usize,
+    major_offsets: Vec<usize>,
+    minor_indices: Vec<usize>,
+}
+
+impl Serialize for SparsityPattern {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        SparsityPatternSerializationData {
+            major_dim: self.major_dim(),
+            minor_dim: self.minor_dim(),
+            major_offsets: self.major_offsets(),
+            minor_indices: self.minor_indices(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SparsityPattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = SparsityPatternDeserializationData::deserialize(deserializer)?;
+
+        SparsityPattern::try_from_offsets_and_indices(
+            data.major_dim,
+            data.minor_dim,
+            data.major_offsets,
+            data.minor_indices,
+        )
+        .map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize)]
+struct CsMatrixSerializationData<'a, T> {
+    nrows: usize,
+    ncols: usize,
+    offsets: &'a [usize],
+    indices: &'a [usize],
+    data: &'a [T],
+}
+
+#[derive(Deserialize)]
+struct CsMatrixDeserializationData<T> {
+    nrows: usize,
+    ncols: usize,
+    offsets: Vec<usize>,
+    indices: Vec<usize>,
+    data: Vec<T>,
+}
+
+impl<T, MajorOffsets, MinorIndices, Data, CompressionKind> Serialize
+    for CsMatrix<T, MajorOffsets, MinorIndices, Data, CompressionKind>
+where
+    T: Scalar + Serialize,
+    MajorOffsets: Borrow<[usize]>,
+    MinorIndices: Borrow<[usize]>,
+    Data: Borrow<[T]>,
+    CompressionKind: Compression,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (offsets, indices, data) = self.cs_data();
+
+        CsMatrixSerializationData {
+            nrows: self.nrows(),
+            ncols: self.ncols(),
+            offsets,
+            indices,
+            data,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T, C> Deserialize<'de> for CsMatrix<T, Vec<usize>, Vec<usize>, Vec<T>, C>
+where
+    T: Scalar + Deserialize<'de>,
+    C: Compression,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = CsMatrixDeserializationData::<T>::deserialize(deserializer)?;
+
+        CsMatrix::try_from_parts(
+            data.nrows,
+            data.ncols,
+            data.offsets,
+            data.indices,
+            data.data,
+        )
+        .map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize)]
+struct CooMatrixSerializationData<'a, T> {
+    nrows: usize,
+    ncols: usize,
+    row_indices: &'a [usize],
+    col_indices: &'a [usize],
+    values: &'a [T],
+}
+
+#[derive(Deserialize)]
+struct CooMatrixDeserializationData<T> {
+    nrows: usize,
+    ncols: usize,
+    row_indices: Vec<usize>,
+    col_indices: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T> Serialize for CooMatrix<T>
+where
+    T: Scalar + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        CooMatrixSerializationData {
+            nrows: self.nrows(),
+            ncols: self.ncols(),
+            row_indices: self.row_indices(),
+            col_indices: self.col_indices(),
+            values: self.values(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for CooMatrix<T>
+where
+    T: Scalar + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = CooMatrixDeserializationData::<T>::deserialize(deserializer)?;
+
+        CooMatrix::try_from_triplets(
+            data.nrows,
+            data.ncols,
+            data.row_indices,
+            data.col_indices,
+            data.values,
+        )
+        .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cs::{CscMatrix, CsrMatrix},
+        proptest::*,
+    };
+    use proptest::prelude::*;
+
+    #[test]
+    fn deserializing_invalid_csr_fails() {
+        let valid = r#"{"nrows":2,"ncols":3,"offsets":[0,2],"indices":[0,2,1],"data":[1,2,3]}"#;
+        assert!(serde_json::from_str::<CsrMatrix<i32>>(valid).is_ok());
+
+        let invalid = [
+            // Wrong number of offsets
+            r#"{"nrows":2,"ncols":3,"offsets":[0,2,3],"indices":[0,2,1],"data":[1,2,3]}"#,
+            // Offsets do not start at zero
+            r#"{"nrows":2,"ncols":3,"offsets":[1,2],"indices":[0,2,1],"data":[1,2,3]}"#,
+            // Final offset past the end of the indices
+            r#"{"nrows":2,"ncols":3,"offsets":[0,4],"indices":[0,2,1],"data":[1,2,3]}"#,
+            // Intermediate offset past the end of the indices
+            r#"{"nrows":3,"ncols":3,"offsets":[0,5,3],"indices":[0,1,2,0],"data":[1,2,3,4]}"#,
+            // Unsorted indices
+            r#"{"nrows":2,"ncols":3,"offsets":[0,2],"indices":[2,0,1],"data":[1,2,3]}"#,
+            // Duplicate indices
+            r#"{"nrows":2,"ncols":3,"offsets":[0,2],"indices":[1,1,1],"data":[1,2,3]}"#,
+            // Index out of bounds
+            r#"{"nrows":2,"ncols":3,"offsets":[0,2],"indices":[0,3,1],"data":[1,2,3]}"#,
+            // Data and indices of different lengths
+            r#"{"nrows":2,"ncols":3,"offsets":[0,2],"indices":[0,2,1],"data":[1,2]}"#,
+        ];
+
+        for json in &invalid {
+            assert!(
+                serde_json::from_str::<CsrMatrix<i32>>(json).is_err(),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn deserializing_invalid_pattern_fails() {
+        let valid = r#"{"major_dim":2,"minor_dim":2,"major_offsets":[0,1],"minor_indices":[1,0]}"#;
+        assert!(serde_json::from_str::<SparsityPattern>(valid).is_ok());
+
+        let invalid = [
+            r#"{"major_dim":2,"minor_dim":2,"major_offsets":[0,1],"minor_indices":[1,2]}"#,
+            r#"{"major_dim":3,"minor_dim":3,"major_offsets":[0,5,3],"minor_indices":[0,1,2,0]}"#,
+        ];
+
+        for json in &invalid {
+            assert!(
+                serde_json::from_str::<SparsityPattern>(json).is_err(),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn deserializing_invalid_coo_fails() {
+        let invalid = [
+            // Row index out of bounds
+            r#"{"nrows":2,"ncols":2,"row_indices":[2],"col_indices":[0],"values":[1]}"#,
+            // Mismatched lengths
+            r#"{"nrows":2,"ncols":2,"row_indices":[0,1],"col_indices":[0],"values":[1]}"#,
+        ];
+
+        for json in &invalid {
+            assert!(
+                serde_json::from_str::<CooMatrix<i32>>(json).is_err(),
+                "{}",
+                json
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn csr_round_trips(csr in csr_strategy()) {
+            let json = serde_json::to_string(&csr).unwrap();
+            let deserialized: CsrMatrix<i32> = serde_json::from_str(&json).unwrap();
+
+            prop_assert_eq!(deserialized.shape(), csr.shape());
+            prop_assert_eq!(deserialized.cs_data(), csr.cs_data());
+        }
+
+        #[test]
+        fn csc_view_round_trips(csc in csc_strategy()) {
+            let json = serde_json::to_string(&csc.to_view()).unwrap();
+            let deserialized: CscMatrix<i32> = serde_json::from_str(&json).unwrap();
+
+            prop_assert_eq!(deserialized.shape(), csc.shape());
+            prop_assert_eq!(deserialized.cs_data(), csc.cs_data());
+        }
+
+        #[test]
+        fn coo_round_trips(coo in coo_strategy()) {
+            let json = serde_json::to_string(&coo).unwrap();
+            let deserialized: CooMatrix<i32> = serde_json::from_str(&json).unwrap();
+
+            prop_assert_eq!(deserialized, coo);
+        }
+
+        #[test]
+        fn pattern_round_trips(pattern in pattern(PROPTEST_MATRIX_DIM, PROPTEST_MATRIX_DIM, PROPTEST_MAX_NNZ)) {
+            let json = serde_json::to_string(&pattern).unwrap();
+            let deserialized: SparsityPattern = serde_json::from_str(&json).unwrap();
+
+            prop_assert_eq!(deserialized, pattern);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Sparse matrices tagged with their dimensions at the type level.
+//!
+//! The operators on plain sparse matrices check that the shapes of their operands are compatible
+//! at runtime, and panic if they are not. In code where the dimensions of the matrices involved
+//! are known statically (e.g. fixed-size stencils, or the blocks of a system of known structure),
+//! a [`ShapedMatrix`] instead tags a sparse matrix with nalgebra dimension types for its rows and
+//! columns, so that mismatched sums and products are rejected by the compiler:
+//!
+//! ```
+//! use nalgebra::{Const, Dynamic};
+//! use nalgebra_sparse::{
+//!     cs::{CscMatrix, CsrMatrix},
+//!     shaped::{ShapedCscMatrix, ShapedCsrMatrix},
+//! };
+//!
+//! let a = ShapedCsrMatrix::<f64, Const<3>, Const<4>>::try_new(CsrMatrix::zeros(3, 4)).unwrap();
+//! let b = ShapedCscMatrix::<f64, Const<4>, Dynamic>::try_new(CscMatrix::zeros(4, 7)).unwrap();
+//!
+//! // A (3 x 4) matrix times a (4 x ?) matrix is a (3 x ?) matrix.
+//! let c = a * b;
+//! assert_eq!(c.shape(), (3, 7));
+//! ```
+//!
+//! Whereas the following does not compile, since the inner dimensions of the product differ:
+//!
+//! ```compile_fail
+//! use nalgebra::Const;
+//! use nalgebra_sparse::{cs::CsrMatrix, shaped::ShapedCsrMatrix};
+//!
+//! let a = ShapedCsrMatrix::<f64, Const<3>, Const<4>>::try_new(CsrMatrix::zeros(3, 4)).unwrap();
+//! let b = ShapedCsrMatrix::<f64, Const<3>, Const<4>>::try_new(CsrMatrix::zeros(3, 4)).unwrap();
+//!
+//! let c = a * b;
+//! ```
+//!
+//! The dimensions of a `ShapedMatrix` are checked once, when it is created. Dimensions that are
+//! only known at runtime can be tagged as [`Dynamic`](nalgebra::Dynamic); the types of two
+//! operands then have to agree exactly, and any remaining runtime mismatch between two `Dynamic`
+//! dimensions panics just as it does for the untagged operators.
+
+use crate::{
+    cs::{
+        CompressedColumnStorage, CompressedMatrix, CompressedRowStorage, CsMatrix, CscMatrix,
+        CsrMatrix,
+    },
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::{Dim, Scalar};
+use std::{
+    borrow::Borrow,
+    marker::PhantomData,
+    ops::{Add, Mul, Sub},
+};
+
+/// A sparse matrix with `R` rows and `C` columns, where `R` and `C` are nalgebra dimension types
+/// (i.e. [`Const`](nalgebra::Const) or [`Dynamic`](nalgebra::Dynamic)).
+///
+/// See the [module-level documentation](crate::shaped) for details.
+#[derive(Debug, Clone)]
+pub struct ShapedMatrix<M, R, C> {
+    matrix: M,
+    _phantom: PhantomData<(R, C)>,
+}
+
+/// A CSR matrix tagged with its dimensions.
+pub type ShapedCsrMatrix<T, R, C> = ShapedMatrix<CsrMatrix<T>, R, C>;
+
+/// A CSC matrix tagged with its dimensions.
+pub type ShapedCscMatrix<T, R, C> = ShapedMatrix<CscMatrix<T>, R, C>;
+
+impl<M, R, C> ShapedMatrix<M, R, C>
+where
+    R: Dim,
+    C: Dim,
+{
+    /// Tags `matrix` with the dimensions `R` and `C`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OperationError`] with kind `OperationErrorKind::InvalidPattern` if `R` or `C`
+    /// is a static dimension that does not match the number of rows or columns of `matrix`.
+    pub fn try_new<T>(matrix: M) -> Result<Self, OperationError>
+    where
+        T: Scalar,
+        M: CompressedMatrix<T>,
+    {
+        let (nrows, ncols) = matrix.shape();
+        let mismatch = |expected: Option<usize>, actual: usize| {
+            // Dynamic dimensions match any size
+            matches!(expected, Some(expected) if expected != actual)
+        };
+
+        if mismatch(R::try_to_usize(), nrows) || mismatch(C::try_to_usize(), ncols) {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!(
+                    "A matrix of shape ({}, {}) cannot be tagged with a shape of ({:?}, {:?}).",
+                    nrows,
+                    ncols,
+                    R::try_to_usize(),
+                    C::try_to_usize()
+                ),
+            ));
+        }
+
+        Ok(Self::new_unchecked(matrix))
+    }
+
+    /// Tags the result of an operation on tagged matrices, whose shape is known to be valid.
+    fn new_unchecked(matrix: M) -> Self {
+        Self {
+            matrix,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Borrows the underlying sparse matrix.
+    pub fn inner(&self) -> &M {
+        &self.matrix
+    }
+
+    /// Removes the dimension tags, returning the underlying sparse matrix.
+    pub fn into_inner(self) -> M {
+        self.matrix
+    }
+
+    /// The shape of the matrix, as `(nrows, ncols)`.
+    pub fn shape<T>(&self) -> (usize, usize)
+    where
+        T: Scalar,
+        M: CompressedMatrix<T>,
+    {
+        self.matrix.shape()
+    }
+}
+
+impl<T, MO, MI, D, R, C> ShapedMatrix<CsMatrix<T, MO, MI, D, CompressedRowStorage>, R, C>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R: Dim,
+    C: Dim,
+{
+    /// Borrows the transpose of the matrix, which is a CSC matrix with the dimensions swapped.
+    pub fn transpose(
+        &self,
+    ) -> ShapedMatrix<CsMatrix<T, &[usize], &[usize], &[T], CompressedColumnStorage>, C, R> {
+        ShapedMatrix::new_unchecked(self.matrix.transpose())
+    }
+}
+
+impl<T, MO, MI, D, R, C> ShapedMatrix<CsMatrix<T, MO, MI, D, CompressedColumnStorage>, R, C>
+where
+    T: Scalar,
+    MO: Borrow<[usize]>,
+    MI: Borrow<[usize]>,
+    D: Borrow<[T]>,
+    R: Dim,
+    C: Dim,
+{
+    /// Borrows the transpose of the matrix, which is a CSR matrix with the dimensions swapped.
+    pub fn transpose(
+        &self,
+    ) -> ShapedMatrix<CsMatrix<T, &[usize], &[usize], &[T], CompressedRowStorage>, C, R> {
+        ShapedMatrix::new_unchecked(self.matrix.transpose())
+    }
+}
+
+impl<M1, M2, R, C> Add<ShapedMatrix<M2, R, C>> for ShapedMatrix<M1, R, C>
+where
+    M1: Add<M2>,
+    R: Dim,
+    C: Dim,
+{
+    type Output = ShapedMatrix<<M1 as Add<M2>>::Output, R, C>;
+
+    fn add(self, rhs: ShapedMatrix<M2, R, C>) -> Self::Output {
+        ShapedMatrix::new_unchecked(self.matrix + rhs.matrix)
+    }
+}
+
+impl<M1, M2, R, C> Sub<ShapedMatrix<M2, R, C>> for ShapedMatrix<M1, R, C>
+where
+    M1: Sub<M2>,
+    R: Dim,
+    C: Dim,
+{
+    type Output = ShapedMatrix<<M1 as Sub<M2>>::Output, R, C>;
+
+    fn sub(self, rhs: ShapedMatrix<M2, R, C>) -> Self::Output {
+        ShapedMatrix::new_unchecked(self.matrix - rhs.matrix)
+    }
+}
+
+impl<M1, M2, R, K, C> Mul<ShapedMatrix<M2, K, C>> for ShapedMatrix<M1, R, K>
+where
+    M1: Mul<M2>,
+    R: Dim,
+    K: Dim,
+    C: Dim,
+{
+    type Output = ShapedMatrix<<M1 as Mul<M2>>::Output, R, C>;
+
+    fn mul(self, rhs: ShapedMatrix<M2, K, C>) -> Self::Output {
+        ShapedMatrix::new_unchecked(self.matrix * rhs.matrix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::{Const, DMatrix, Dynamic};
+
+    #[test]
+    fn try_new_checks_static_dimensions() {
+        let csr = CsrMatrix::<f64>::identity(3);
+
+        assert!(ShapedCsrMatrix::<f64, Const<3>, Const<3>>::try_new(csr.clone()).is_ok());
+        assert!(ShapedCsrMatrix::<f64, Dynamic, Const<3>>::try_new(csr.clone()).is_ok());
+        assert!(ShapedCsrMatrix::<f64, Dynamic, Dynamic>::try_new(csr.clone()).is_ok());
+
+        let err = ShapedCsrMatrix::<f64, Const<3>, Const<2>>::try_new(csr).unwrap_err();
+        assert!(matches!(err.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    #[test]
+    fn operators_match_untagged_operators() {
+        let a = CsrMatrix::from(&DMatrix::from_fn(2, 3, |i, j| (i + 2 * j) as f64));
+        let b = CsrMatrix::from(&DMatrix::from_fn(3, 2, |i, j| (3 * i + j) as f64 - 2.0));
+
+        let a_shaped = ShapedCsrMatrix::<f64, Const<2>, Const<3>>::try_new(a.clone()).unwrap();
+        let b_shaped = ShapedCsrMatrix::<f64, Const<3>, Dynamic>::try_new(b.clone()).unwrap();
+
+        let product = (a_shaped.clone() * b_shaped).into_inner();
+        assert_eq!(
+            DMatrix::from(&product),
+            DMatrix::from(&(a.clone() * b.clone()))
+        );
+
+        let sum = (a_shaped.clone() + a_shaped.clone()).into_inner();
+        assert_eq!(DMatrix::from(&sum), DMatrix::from(&a) * 2.0);
+
+        let difference = (a_shaped.clone() - a_shaped.clone()).into_inner();
+        assert_eq!(DMatrix::from(&difference), DMatrix::zeros(2, 3));
+
+        let transpose = a_shaped.transpose();
+        assert_eq!(transpose.shape(), (3, 2));
+        assert_eq!(
+            DMatrix::from(transpose.inner()),
+            DMatrix::from(&a).transpose()
+        );
+    }
+}
@@ -0,0 +1,428 @@
+//! Sparse operators on masked regular grids.
+//!
+//! Image processing and level-set methods often discretize a PDE on the pixels or voxels of a
+//! 2D or 3D grid, but only inside a region of interest given by a boolean mask. The unknowns are
+//! then the active cells of the mask, and every row of the operator applies the same stencil,
+//! e.g. the 5-point Laplacian, to the neighbours of one active cell.
+//!
+//! - A [`GridDomain`] numbers the active cells of a mask, and maps between grid coordinates and
+//!   matrix rows in both directions. It also moves values between grid-sized arrays and vectors
+//!   with one entry per active cell.
+//! - A [`Stencil`] holds weights at offsets from the centre cell.
+//! - [`GridDomain::operator`] assembles the CSR matrix that applies a stencil on the domain,
+//!   treating neighbours outside of it according to a [`Boundary`] condition.
+//!
+//! Grid cells are stored with the first coordinate varying fastest, like the column-major
+//! storage of nalgebra matrices. The mask of a 2D domain with coordinates `[row, column]` can
+//! therefore be taken straight from the slice of a `DMatrix<bool>`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra::{DMatrix, DVector};
+//! use nalgebra_sparse::stencil::{Boundary, GridDomain, Stencil};
+//!
+//! // A 3 × 4 image, whose region of interest excludes the first column and one more pixel.
+//! let mask = DMatrix::from_fn(3, 4, |i, j| j > 0 && (i, j) != (1, 2));
+//! let domain = GridDomain::try_from_mask([3, 4], mask.as_slice().to_vec()).unwrap();
+//!
+//! assert_eq!(domain.nrows(), 8);
+//! assert_eq!(domain.row([2, 1]), Some(2));
+//! assert_eq!(domain.coordinates(3), [0, 2]);
+//! assert_eq!(domain.row([1, 2]), None);
+//!
+//! // The Laplacian with zero values outside of the region.
+//! let laplacian = domain.operator(&Stencil::<f64, 2>::laplacian(), Boundary::Dirichlet);
+//! assert_eq!(laplacian.shape(), (8, 8));
+//!
+//! // Constant images are in the null space of the Laplacian with zero-flux boundaries.
+//! let neumann = domain.operator(&Stencil::<f64, 2>::laplacian(), Boundary::Neumann);
+//! let ones = DVector::from_element(8, 1.0);
+//! assert_eq!(DMatrix::from(&(&neumann * &ones)), DMatrix::zeros(8, 1));
+//! ```
+
+use crate::{
+    cs::CsrMatrix,
+    error::{OperationError, OperationErrorKind},
+};
+use nalgebra::{DVector, Scalar};
+use num_traits::{One, Zero};
+use std::{
+    convert::TryFrom,
+    ops::{AddAssign, Neg},
+};
+
+/// How a stencil treats neighbours that lie outside of the domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// Values outside of the domain are zero, so the weights of outside neighbours are dropped.
+    Dirichlet,
+
+    /// Values outside of the domain are equal to the value of the centre cell, so the weights of
+    /// outside neighbours are added to the diagonal. For derivative stencils this is a zero-flux
+    /// boundary condition.
+    Neumann,
+}
+
+/// Weights at offsets from the centre cell of a `D`-dimensional grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stencil<T, const D: usize> {
+    entries: Vec<([isize; D], T)>,
+}
+
+impl<T, const D: usize> Stencil<T, D> {
+    /// Creates a stencil from `(offset, weight)` pairs.
+    ///
+    /// The weights of repeated offsets are added together when the stencil is applied.
+    #[must_use]
+    pub fn new(entries: Vec<([isize; D], T)>) -> Self {
+        Self { entries }
+    }
+
+    /// The `(offset, weight)` pairs of the stencil.
+    #[must_use]
+    pub fn entries(&self) -> &[([isize; D], T)] {
+        &self.entries
+    }
+}
+
+impl<T, const D: usize> Stencil<T, D>
+where
+    T: Scalar + One + Neg<Output = T> + AddAssign,
+{
+    /// The standard `2D + 1`-point stencil of the negative Laplacian with unit grid spacing.
+    ///
+    /// The centre weight is `2D`, and each of the `2D` axis neighbours has weight `-1`, i.e. the
+    /// 5-point stencil in 2D and the 7-point stencil in 3D. The resulting operators are positive
+    /// semi-definite.
+    #[must_use]
+    pub fn laplacian() -> Self {
+        let mut centre = T::one();
+
+        for _ in 1..2 * D {
+            centre += T::one();
+        }
+
+        let mut entries = vec![([0; D], centre)];
+
+        for axis in 0..D {
+            for step in [-1, 1] {
+                let mut offset = [0; D];
+                offset[axis] = step;
+                entries.push((offset, -T::one()));
+            }
+        }
+
+        Self { entries }
+    }
+}
+
+/// The active cells of a `D`-dimensional grid, numbered as the rows of sparse operators.
+///
+/// Active cells are numbered in storage order, i.e. with the first coordinate varying fastest.
+/// See the [module-level documentation](self) for an example.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridDomain<const D: usize> {
+    shape: [usize; D],
+    /// The row of every grid cell, or `usize::MAX` for inactive cells.
+    rows: Vec<usize>,
+    /// The linear grid index of every row.
+    cells: Vec<usize>,
+}
+
+impl<const D: usize> GridDomain<D> {
+    /// Creates a domain from a mask with one entry per grid cell, in storage order.
+    ///
+    /// # Errors
+    ///
+    /// This function fails and produces an [`OperationError`] with kind
+    /// [`OperationErrorKind::InvalidPattern`] if the length of `mask` is not the number of cells
+    /// in a grid of the given shape.
+    pub fn try_from_mask(shape: [usize; D], mask: Vec<bool>) -> Result<Self, OperationError> {
+        if shape.iter().try_fold(1usize, |n, &m| n.checked_mul(m)) != Some(mask.len()) {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from("The mask must have one entry per grid cell"),
+            ));
+        }
+
+        let mut rows = vec![usize::MAX; mask.len()];
+        let mut cells = Vec::new();
+
+        for (cell, _) in mask.iter().enumerate().filter(|(_, active)| **active) {
+            rows[cell] = cells.len();
+            cells.push(cell);
+        }
+
+        Ok(Self { shape, rows, cells })
+    }
+
+    /// Creates a domain of the cells for which `active` returns `true`.
+    #[must_use]
+    pub fn from_fn<F>(shape: [usize; D], mut active: F) -> Self
+    where
+        F: FnMut([usize; D]) -> bool,
+    {
+        let ncells = shape.iter().product();
+        let mask = (0..ncells)
+            .map(|cell| active(unravel(&shape, cell)))
+            .collect();
+
+        Self::try_from_mask(shape, mask).expect("The mask has one entry per grid cell")
+    }
+
+    /// Creates a domain of every cell of the grid.
+    #[must_use]
+    pub fn full(shape: [usize; D]) -> Self {
+        Self::from_fn(shape, |_| true)
+    }
+
+    /// The shape of the grid.
+    #[must_use]
+    pub fn shape(&self) -> [usize; D] {
+        self.shape
+    }
+
+    /// The number of active cells, which is the number of rows of the operators on the domain.
+    #[must_use]
+    pub fn nrows(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// The row of the cell at the given coordinates, or `None` if it is inactive or outside of
+    /// the grid.
+    #[must_use]
+    pub fn row(&self, coordinates: [usize; D]) -> Option<usize> {
+        if coordinates.iter().zip(&self.shape).any(|(c, n)| c >= n) {
+            return None;
+        }
+
+        // The coordinates fit in an `isize`, since they are smaller than the length of a `Vec`.
+        let cell = self.linear_index(coordinates.map(|c| c as isize))?;
+        Some(self.rows[cell]).filter(|row| *row != usize::MAX)
+    }
+
+    /// The grid coordinates of the cell of the given row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` is not smaller than [`nrows`](Self::nrows).
+    #[must_use]
+    pub fn coordinates(&self, row: usize) -> [usize; D] {
+        unravel(&self.shape, self.cells[row])
+    }
+
+    /// Collects the values of the active cells from a grid-sized array in storage order, e.g.
+    /// the pixels of an image.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `grid` does not have one entry per grid cell.
+    #[must_use]
+    pub fn gather<T: Scalar>(&self, grid: &[T]) -> DVector<T> {
+        assert_eq!(grid.len(), self.rows.len(), "Grid size mismatch");
+        DVector::from_iterator(self.nrows(), self.cells.iter().map(|c| grid[*c].clone()))
+    }
+
+    /// Writes the values of the active cells into a grid-sized array in storage order, and fills
+    /// the inactive cells with `fill`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` does not have one entry per active cell.
+    #[must_use]
+    pub fn scatter<T: Scalar>(&self, values: &DVector<T>, fill: T) -> Vec<T> {
+        assert_eq!(values.len(), self.nrows(), "Value count mismatch");
+
+        let mut grid = vec![fill; self.rows.len()];
+
+        for (row, cell) in self.cells.iter().enumerate() {
+            grid[*cell] = values[row].clone();
+        }
+
+        grid
+    }
+
+    /// Assembles the operator that applies `stencil` to every active cell.
+    ///
+    /// Row `r` holds the weights of the stencil centred at the cell of row `r`, in the columns
+    /// of the active neighbours. Neighbours that are inactive or outside of the grid are treated
+    /// according to `boundary`. The pattern is structural: entries whose weights add up to zero
+    /// are stored as explicit zeros.
+    #[must_use]
+    pub fn operator<T>(&self, stencil: &Stencil<T, D>, boundary: Boundary) -> CsrMatrix<T>
+    where
+        T: Scalar + Zero + AddAssign,
+    {
+        let n = self.nrows();
+
+        let _span = span!(
+            "stencil_operator",
+            nrows = n,
+            stencil = stencil.entries.len()
+        );
+
+        let mut offsets = Vec::with_capacity(n);
+        let mut indices = Vec::with_capacity(n * stencil.entries.len());
+        let mut data = Vec::with_capacity(n * stencil.entries.len());
+        let mut row_entries = Vec::with_capacity(stencil.entries.len());
+
+        for (row, cell) in self.cells.iter().enumerate() {
+            let centre = unravel(&self.shape, *cell);
+
+            for (offset, weight) in &stencil.entries {
+                let neighbour: [isize; D] =
+                    std::array::from_fn(|axis| centre[axis] as isize + offset[axis]);
+
+                let column = self
+                    .linear_index(neighbour)
+                    .map(|cell| self.rows[cell])
+                    .filter(|column| *column != usize::MAX);
+
+                match (column, boundary) {
+                    (Some(column), _) => row_entries.push((column, weight.clone())),
+                    (None, Boundary::Neumann) => row_entries.push((row, weight.clone())),
+                    (None, Boundary::Dirichlet) => {}
+                }
+            }
+
+            row_entries.sort_by_key(|(column, _)| *column);
+            offsets.push(indices.len());
+
+            for (column, weight) in row_entries.drain(..) {
+                if indices.len() > *offsets.last().unwrap() && indices.last() == Some(&column) {
+                    *data.last_mut().unwrap() += weight;
+                } else {
+                    indices.push(column);
+                    data.push(weight);
+                }
+            }
+        }
+
+        // SAFETY: The columns of every row are sorted, deduplicated and below `n`.
+        unsafe { CsrMatrix::from_parts_unchecked(n, n, offsets, indices, data) }
+    }
+
+    /// The linear grid index of the given coordinates, or `None` if they are outside the grid.
+    fn linear_index(&self, coordinates: [isize; D]) -> Option<usize> {
+        let mut index = 0;
+        let mut stride = 1;
+
+        for (c, n) in coordinates.iter().zip(&self.shape) {
+            let c = usize::try_from(*c).ok().filter(|c| c < n)?;
+
+            index += c * stride;
+            stride *= n;
+        }
+
+        Some(index)
+    }
+}
+
+/// The coordinates of a linear grid index.
+fn unravel<const D: usize>(shape: &[usize; D], mut index: usize) -> [usize; D] {
+    let mut coordinates = [0; D];
+
+    for (c, n) in coordinates.iter_mut().zip(shape) {
+        *c = index % n;
+        index /= n;
+    }
+
+    coordinates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::DMatrix;
+
+    #[test]
+    fn grid_domain_maps_between_cells_and_rows() {
+        assert!(matches!(
+            GridDomain::try_from_mask([2, 3], vec![true; 5])
+                .unwrap_err()
+                .kind(),
+            OperationErrorKind::InvalidPattern
+        ));
+
+        let domain = GridDomain::from_fn([2, 3, 2], |[i, j, k]| (i + j + k) % 2 == 0);
+        assert_eq!(domain.nrows(), 6);
+
+        for row in 0..domain.nrows() {
+            assert_eq!(domain.row(domain.coordinates(row)), Some(row));
+        }
+
+        assert_eq!(domain.row([1, 0, 0]), None);
+        assert_eq!(domain.row([2, 0, 0]), None);
+
+        let grid: Vec<_> = (0..12).collect();
+        let values = domain.gather(&grid);
+        assert_eq!(values.as_slice(), &[0, 3, 4, 7, 8, 11]);
+        assert_eq!(
+            domain.scatter(&values, -1),
+            vec![0, -1, -1, 3, 4, -1, -1, 7, 8, -1, -1, 11]
+        );
+
+        let empty = GridDomain::<2>::full([0, 4]);
+        assert_eq!(empty.nrows(), 0);
+        assert_eq!(
+            empty
+                .operator(&Stencil::<f64, 2>::laplacian(), Boundary::Neumann)
+                .nnz(),
+            0
+        );
+    }
+
+    #[test]
+    fn full_domain_laplacian_is_the_kronecker_sum_of_1d_laplacians() {
+        let (m, n) = (4, 3);
+        let domain = GridDomain::full([m, n]);
+        let laplacian = DMatrix::from(&domain.operator(&Stencil::laplacian(), Boundary::Dirichlet));
+
+        let tridiagonal = |n: usize| {
+            DMatrix::from_fn(n, n, |i, j| match (i as isize - j as isize).abs() {
+                0 => 2.0,
+                1 => -1.0,
+                _ => 0.0,
+            })
+        };
+        let expected = DMatrix::<f64>::identity(n, n).kronecker(&tridiagonal(m))
+            + tridiagonal(n).kronecker(&DMatrix::identity(m, m));
+
+        assert_eq!(laplacian, expected);
+    }
+
+    #[test]
+    fn stencil_operator_applies_boundary_conditions() {
+        // A 1D domain with a gap: cells 0, 1 and 3 are active.
+        let domain = GridDomain::try_from_mask([4], vec![true, true, false, true]).unwrap();
+
+        // A one-sided stencil with a repeated offset.
+        let stencil = Stencil::new(vec![([0], 1.0), ([1], -2.0), ([1], 0.5)]);
+
+        let dirichlet = DMatrix::from(&domain.operator(&stencil, Boundary::Dirichlet));
+        #[rustfmt::skip]
+        let expected = DMatrix::from_row_slice(3, 3, &[
+            1.0, -1.5, 0.0,
+            0.0, 1.0, 0.0,
+            0.0, 0.0, 1.0,
+        ]);
+        assert_eq!(dirichlet, expected);
+
+        let neumann = domain.operator(&stencil, Boundary::Neumann);
+        #[rustfmt::skip]
+        let expected = DMatrix::from_row_slice(3, 3, &[
+            1.0, -1.5, 0.0,
+            0.0, -0.5, 0.0,
+            0.0, 0.0, -0.5,
+        ]);
+        assert_eq!(DMatrix::from(&neumann), expected);
+        assert_eq!(neumann.nnz(), 4);
+
+        // Weights that cancel are kept as explicit zeros.
+        let cancelling = Stencil::new(vec![([0], 1.0), ([-1], -1.0)]);
+        let operator = domain.operator(&cancelling, Boundary::Neumann);
+        assert_eq!(operator.nnz(), 4);
+        assert_eq!(operator.get_entry(0, 0).unwrap().into_value(), 0.0);
+    }
+}
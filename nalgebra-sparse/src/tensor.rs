@@ -0,0 +1,469 @@
+//! A minimal sparse third-order tensor type.
+//!
+//! [`CooTensor`] stores the explicit entries of a third-order tensor in coordinate form, in the
+//! same way as [`CooMatrix`](crate::coo::CooMatrix) does for matrices. It does not provide any
+//! tensor arithmetic of its own. Instead, it can be [unfolded](CooTensor::unfold) into a
+//! [`CsrMatrix`] along any of its modes, so that the matrix kernels in this crate can be used to
+//! build e.g. tensor decompositions. The [mode-n product](CooTensor::mode_product) with a sparse
+//! matrix is provided on top of this, as it is the basic building block for most of them.
+//!
+//! Modes are numbered from zero, so the three modes of a tensor are `0`, `1`, and `2`.
+//!
+//! # Unfolding
+//!
+//! The mode-n unfolding `X_(n)` of an `I_0 × I_1 × I_2` tensor `X` is a matrix with `I_n` rows,
+//! where entry `(i_0, i_1, i_2)` of the tensor is placed at row `i_n` of the unfolding. The column
+//! is formed from the two remaining indices, with the lower mode varying fastest. For example, in
+//! the mode-1 unfolding, entry `(i_0, i_1, i_2)` is placed at `(i_1, i_0 + i_2 * I_0)`. This
+//! matches the convention of Kolda & Bader, "Tensor Decompositions and Applications".
+//!
+//! # Example
+//!
+//! ```rust
+//! use nalgebra_sparse::{cs::CsrMatrix, tensor::CooTensor};
+//!
+//! let mut tensor = CooTensor::new([2, 3, 4]);
+//! tensor.push(0, 1, 2, 1.0);
+//! tensor.push(1, 2, 3, 2.0);
+//!
+//! let unfolded = tensor.unfold(1);
+//! assert_eq!(unfolded.shape(), (3, 8));
+//!
+//! // Scale every mode-0 fibre by two.
+//! let scale = CsrMatrix::<f64>::identity(2) * 2.0;
+//! let scaled = tensor.mode_product(0, &scale).unwrap();
+//!
+//! assert_eq!(scaled.shape(), [2, 3, 4]);
+//! assert_eq!(scaled.values(), &[2.0, 4.0]);
+//! ```
+
+use crate::{
+    coo::CooMatrix,
+    cs::{CompressedRowStorage, CsMatrix, CsrMatrix},
+    error::{OperationError, OperationErrorKind, SparseFormatError, SparseFormatErrorKind},
+    ops::serial::spmm::spmm_csr_csr,
+};
+use nalgebra::Scalar;
+use num_traits::Zero;
+use std::{
+    borrow::Borrow,
+    ops::{Add, AddAssign, Mul},
+};
+
+/// A COO representation of a sparse third-order tensor.
+///
+/// Entries are stored as `(i, j, k, v)` quadruplets. As with [`CooMatrix`], the indices must be in
+/// bounds, but duplicate entries are allowed, and are summed together when the tensor is unfolded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CooTensor<T> {
+    shape: [usize; 3],
+    indices: [Vec<usize>; 3],
+    values: Vec<T>,
+}
+
+impl<T> CooTensor<T> {
+    /// Constructs a zero tensor of the given shape, i.e. one with no explicitly stored entries.
+    #[must_use]
+    pub fn new(shape: [usize; 3]) -> Self {
+        Self {
+            shape,
+            indices: [Vec::new(), Vec::new(), Vec::new()],
+            values: Vec::new(),
+        }
+    }
+
+    /// Tries to construct a tensor from the given shape and a collection of `(i, j, k, v)`
+    /// quadruplets, with one index array per mode.
+    ///
+    /// Returns an error if any of the indices are out of bounds, or if the index and value arrays
+    /// do not all have the same length.
+    pub fn try_from_entries(
+        shape: [usize; 3],
+        indices: [Vec<usize>; 3],
+        values: Vec<T>,
+    ) -> Result<Self, SparseFormatError> {
+        if indices.iter().any(|mode| mode.len() != values.len()) {
+            return Err(SparseFormatError::from_kind_and_msg(
+                SparseFormatErrorKind::InvalidStructure,
+                "Number of indices in every mode and values must be the same.",
+            ));
+        }
+
+        let in_bounds = indices
+            .iter()
+            .zip(&shape)
+            .all(|(mode, &dim)| mode.iter().all(|&i| i < dim));
+
+        if !in_bounds {
+            return Err(SparseFormatError::from_kind_and_msg(
+                SparseFormatErrorKind::IndexOutOfBounds,
+                "Tensor index out of bounds.",
+            ));
+        }
+
+        Ok(Self {
+            shape,
+            indices,
+            values,
+        })
+    }
+
+    /// Pushes a single entry `v` at index `(i, j, k)` into the tensor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of `i`, `j`, or `k` is out of bounds.
+    #[inline]
+    pub fn push(&mut self, i: usize, j: usize, k: usize, v: T) {
+        assert!(i < self.shape[0]);
+        assert!(j < self.shape[1]);
+        assert!(k < self.shape[2]);
+        self.indices[0].push(i);
+        self.indices[1].push(j);
+        self.indices[2].push(k);
+        self.values.push(v);
+    }
+
+    /// Reserves capacity for at least `additional` more entries.
+    pub fn reserve(&mut self, additional: usize) {
+        for mode in &mut self.indices {
+            mode.reserve(additional);
+        }
+
+        self.values.reserve(additional);
+    }
+
+    /// The shape of the tensor, i.e. its dimension along each mode.
+    #[inline]
+    #[must_use]
+    pub fn shape(&self) -> [usize; 3] {
+        self.shape
+    }
+
+    /// The number of explicitly stored entries in the tensor, *including* duplicates.
+    #[inline]
+    #[must_use]
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// The indices of the explicitly stored entries along the given mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mode` is not one of `0`, `1`, or `2`.
+    #[must_use]
+    pub fn indices(&self, mode: usize) -> &[usize] {
+        &self.indices[mode]
+    }
+
+    /// The values of the explicitly stored entries.
+    #[must_use]
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// An iterator over the `(i, j, k, v)` quadruplets of the tensor.
+    pub fn entry_iter(&self) -> impl Iterator<Item = (usize, usize, usize, &T)> {
+        let [is, js, ks] = &self.indices;
+
+        is.iter()
+            .zip(js)
+            .zip(ks)
+            .zip(&self.values)
+            .map(|(((i, j), k), v)| (*i, *j, *k, v))
+    }
+
+    /// Disassembles the tensor into its index arrays (one per mode) and values.
+    pub fn disassemble(self) -> ([Vec<usize>; 3], Vec<T>) {
+        (self.indices, self.values)
+    }
+
+    /// The two modes that are not `mode`, in increasing order.
+    fn other_modes(mode: usize) -> (usize, usize) {
+        match mode {
+            0 => (1, 2),
+            1 => (0, 2),
+            2 => (0, 1),
+            _ => panic!("Third-order tensors only have modes 0, 1, and 2."),
+        }
+    }
+}
+
+impl<T> CooTensor<T>
+where
+    T: Scalar + Add<Output = T>,
+{
+    /// Computes the mode-`mode` unfolding (matricization) of the tensor.
+    ///
+    /// See the [module-level documentation](self) for how entries are laid out in the unfolding.
+    /// Duplicate entries are summed together.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mode` is not one of `0`, `1`, or `2`.
+    #[must_use]
+    pub fn unfold(&self, mode: usize) -> CsrMatrix<T> {
+        let (low, high) = Self::other_modes(mode);
+
+        let nrows = self.shape[mode];
+        let ncols = self.shape[low] * self.shape[high];
+
+        let _span = span!(
+            "tensor_unfold",
+            mode = mode,
+            nrows = nrows,
+            ncols = ncols,
+            nnz = self.nnz()
+        );
+
+        let rows = self.indices[mode].clone();
+        let cols = self.indices[low]
+            .iter()
+            .zip(&self.indices[high])
+            .map(|(&l, &h)| l + h * self.shape[low])
+            .collect();
+
+        let coo = CooMatrix::try_from_triplets(nrows, ncols, rows, cols, self.values.clone())
+            .expect("Unfolded indices are always in bounds.");
+
+        CsrMatrix::from(coo)
+    }
+
+    /// Reverses [`CooTensor::unfold`], producing a tensor of the given shape from its
+    /// mode-`mode` unfolding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with kind [`OperationErrorKind::InvalidPattern`] if the shape of the
+    /// unfolding does not match `shape`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mode` is not one of `0`, `1`, or `2`.
+    pub fn fold<MO, MI, D>(
+        unfolded: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+        mode: usize,
+        shape: [usize; 3],
+    ) -> Result<Self, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let (low, high) = Self::other_modes(mode);
+
+        if unfolded.shape() != (shape[mode], shape[low] * shape[high]) {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                String::from("The shape of the unfolded matrix does not match the tensor shape."),
+            ));
+        }
+
+        let mut tensor = Self::new(shape);
+
+        for (row, col, value) in unfolded.triplet_iter() {
+            let mut index = [0; 3];
+            index[mode] = row;
+            index[low] = col % shape[low];
+            index[high] = col / shape[low];
+
+            tensor.push(index[0], index[1], index[2], value.clone());
+        }
+
+        Ok(tensor)
+    }
+}
+
+impl<T> CooTensor<T>
+where
+    T: Scalar + Add<Output = T> + AddAssign + Mul<Output = T> + Zero,
+{
+    /// Computes the mode-`mode` product `Y = X ×_n U` of this tensor `X` with the matrix `U`.
+    ///
+    /// `U` must have as many columns as the tensor has along `mode`. The result has the same shape
+    /// as the tensor, except that its dimension along `mode` is the number of rows of `U`. This is
+    /// computed as `Y_(n) = U X_(n)`, and the result contains no duplicate entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with kind [`OperationErrorKind::InvalidPattern`] if the number of columns
+    /// of `U` does not match the dimension of the tensor along `mode`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mode` is not one of `0`, `1`, or `2`.
+    pub fn mode_product<MO, MI, D>(
+        &self,
+        mode: usize,
+        matrix: &CsMatrix<T, MO, MI, D, CompressedRowStorage>,
+    ) -> Result<Self, OperationError>
+    where
+        MO: Borrow<[usize]>,
+        MI: Borrow<[usize]>,
+        D: Borrow<[T]>,
+    {
+        let (low, high) = Self::other_modes(mode);
+
+        if matrix.ncols() != self.shape[mode] {
+            return Err(OperationError::from_kind_and_message(
+                OperationErrorKind::InvalidPattern,
+                format!(
+                    "The matrix must have {} columns to multiply along mode {}.",
+                    self.shape[mode], mode
+                ),
+            ));
+        }
+
+        let _span = span!(
+            "tensor_mode_product",
+            mode = mode,
+            nnz = tracing::field::Empty
+        );
+
+        let product = spmm_csr_csr(matrix.to_view(), self.unfold(mode))?;
+
+        let mut shape = self.shape;
+        shape[mode] = matrix.nrows();
+
+        let mut tensor = Self::new(shape);
+        tensor.reserve(product.nnz());
+
+        // The product is in CSC format, so the major index is the column of the unfolding.
+        for (col, row, value) in product.triplet_iter() {
+            let mut index = [0; 3];
+            index[mode] = row;
+            index[low] = col % shape[low];
+            index[high] = col / shape[low];
+
+            tensor.push(index[0], index[1], index[2], value.clone());
+        }
+
+        record!(_span, nnz = tensor.nnz());
+
+        Ok(tensor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::SparseFormatErrorKind, SparseEntry};
+    use proptest::{collection::vec, prelude::*};
+
+    fn tensor_strategy() -> impl Strategy<Value = CooTensor<i32>> {
+        [1usize..5, 1usize..5, 1usize..5].prop_flat_map(|shape| {
+            vec(
+                (0..shape[0], 0..shape[1], 0..shape[2], -5i32..5),
+                0..(shape[0] * shape[1] * shape[2]),
+            )
+            .prop_map(move |entries| {
+                let mut tensor = CooTensor::new(shape);
+
+                for (i, j, k, v) in entries {
+                    tensor.push(i, j, k, v);
+                }
+
+                tensor
+            })
+        })
+    }
+
+    /// Converts a tensor to a dense array indexed by `[i][j][k]`, summing duplicates.
+    fn to_dense(tensor: &CooTensor<i32>) -> Vec<Vec<Vec<i32>>> {
+        let [n0, n1, n2] = tensor.shape();
+        let mut dense = vec![vec![vec![0; n2]; n1]; n0];
+
+        for (i, j, k, v) in tensor.entry_iter() {
+            dense[i][j][k] += v;
+        }
+
+        dense
+    }
+
+    #[test]
+    fn try_from_entries_checks_bounds_and_lengths() {
+        let out_of_bounds =
+            CooTensor::try_from_entries([2, 2, 2], [vec![0], vec![2], vec![0]], vec![1.0]);
+
+        assert_eq!(
+            out_of_bounds.unwrap_err().kind(),
+            &SparseFormatErrorKind::IndexOutOfBounds
+        );
+
+        let mismatched =
+            CooTensor::try_from_entries([2, 2, 2], [vec![0], vec![1], vec![]], vec![1.0]);
+
+        assert_eq!(
+            mismatched.unwrap_err().kind(),
+            &SparseFormatErrorKind::InvalidStructure
+        );
+    }
+
+    #[test]
+    fn unfolding_follows_kolda_bader_layout() {
+        let mut tensor = CooTensor::new([2, 3, 4]);
+        tensor.push(1, 2, 3, 7);
+
+        assert_eq!(
+            tensor.unfold(0).get_entry(1, 2 + 3 * 3),
+            Some(SparseEntry::NonZero(&7))
+        );
+        assert_eq!(
+            tensor.unfold(1).get_entry(2, 1 + 3 * 2),
+            Some(SparseEntry::NonZero(&7))
+        );
+        assert_eq!(
+            tensor.unfold(2).get_entry(3, 1 + 2 * 2),
+            Some(SparseEntry::NonZero(&7))
+        );
+    }
+
+    #[test]
+    fn mode_product_rejects_incompatible_matrix() {
+        let tensor = CooTensor::<i32>::new([2, 3, 4]);
+        let matrix = CsrMatrix::<i32>::identity(2);
+
+        let error = tensor.mode_product(1, &matrix).unwrap_err();
+
+        assert!(matches!(error.kind(), OperationErrorKind::InvalidPattern));
+    }
+
+    proptest! {
+        #[test]
+        fn fold_reverses_unfold(tensor in tensor_strategy(), mode in 0usize..3) {
+            let folded = CooTensor::fold(&tensor.unfold(mode), mode, tensor.shape()).unwrap();
+
+            prop_assert_eq!(to_dense(&folded), to_dense(&tensor));
+        }
+
+        #[test]
+        fn mode_product_agrees_with_dense(tensor in tensor_strategy(), mode in 0usize..3, rows in 1usize..4) {
+            let shape = tensor.shape();
+            let dense_matrix = nalgebra::DMatrix::from_fn(rows, shape[mode], |r, c| {
+                (r as i32) - 2 * (c as i32) + 1
+            });
+            let matrix = CsrMatrix::from(&dense_matrix);
+
+            let product = tensor.mode_product(mode, &matrix).unwrap();
+
+            let mut expected_shape = shape;
+            expected_shape[mode] = rows;
+            prop_assert_eq!(product.shape(), expected_shape);
+
+            let mut expected = vec![vec![vec![0; expected_shape[2]]; expected_shape[1]]; expected_shape[0]];
+
+            for (i, j, k, v) in tensor.entry_iter() {
+                let index = [i, j, k];
+
+                for r in 0..rows {
+                    let mut target = index;
+                    target[mode] = r;
+                    expected[target[0]][target[1]][target[2]] += dense_matrix[(r, index[mode])] * v;
+                }
+            }
+
+            prop_assert_eq!(to_dense(&product), expected);
+        }
+    }
+}
@@ -0,0 +1,301 @@
+//! A type for representing compressed sparse vectors.
+//!
+//! A [`CsVector`] stores the indices of its explicit entries in strictly increasing order, along
+//! with their values. This is exactly the layout of a single lane of a compressed matrix, which is
+//! why [`CsrMatrix::row`](crate::cs::CsMatrix::row) and
+//! [`CscMatrix::col`](crate::cs::CsMatrix::col) can return rows and columns as borrowed
+//! [`CsVectorView`]s without copying anything.
+
+use crate::{
+    cs::check_pattern,
+    error::{SparseFormatError, SparsityPatternFormatError},
+    SparseEntry,
+};
+use nalgebra::{DVector, Dim, RawStorage, Scalar, Vector};
+use num_traits::Zero;
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    marker::PhantomData,
+    ops::{AddAssign, Mul},
+};
+
+/// A sparse vector, generic over the storage of its indices and values.
+///
+/// Like [`CsMatrix`](crate::cs::CsMatrix), the storage can either be owned ([`CsVec`]) or
+/// borrowed ([`CsVectorView`]), e.g. from a lane of a compressed matrix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsVector<T, Indices, Data> {
+    len: usize,
+    indices: Indices,
+    data: Data,
+    _phantom: PhantomData<T>,
+}
+
+/// An owned sparse vector.
+pub type CsVec<T> = CsVector<T, Vec<usize>, Vec<T>>;
+
+/// A sparse vector borrowing its indices and values, e.g. a row of a [`CsrMatrix`] or a column of
+/// a [`CscMatrix`].
+///
+/// [`CsrMatrix`]: crate::cs::CsrMatrix
+/// [`CscMatrix`]: crate::cs::CscMatrix
+pub type CsVectorView<'a, T> = CsVector<T, &'a [usize], &'a [T]>;
+
+impl<T, Indices, Data> CsVector<T, Indices, Data>
+where
+    T: Scalar,
+    Indices: Borrow<[usize]>,
+    Data: Borrow<[T]>,
+{
+    /// Creates a sparse vector without checking any of its invariants.
+    ///
+    /// # Safety
+    ///
+    /// `indices` must be strictly increasing, bounded by `len`, and as long as `data`.
+    pub(crate) unsafe fn from_parts_unchecked(len: usize, indices: Indices, data: Data) -> Self {
+        Self {
+            len,
+            indices,
+            data,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a sparse vector of length `len` from the indices of its explicit entries and their
+    /// values.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `indices` and `data` have different lengths, if an index is not smaller than
+    /// `len`, or if the indices are not strictly increasing.
+    pub fn try_from_parts(
+        len: usize,
+        indices: Indices,
+        data: Data,
+    ) -> Result<Self, SparseFormatError> {
+        if indices.borrow().len() != data.borrow().len() {
+            return Err(SparsityPatternFormatError::DataAndIndicesSizeMismatch.into());
+        }
+
+        check_pattern(1, len, &[0], indices.borrow())?;
+
+        Ok(unsafe { Self::from_parts_unchecked(len, indices, data) })
+    }
+
+    /// The length of the vector, including implicit zeros.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the vector has length zero.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of explicitly stored entries.
+    #[inline]
+    #[must_use]
+    pub fn nnz(&self) -> usize {
+        self.indices.borrow().len()
+    }
+
+    /// The indices of the explicitly stored entries, in increasing order.
+    #[inline]
+    #[must_use]
+    pub fn indices(&self) -> &[usize] {
+        self.indices.borrow()
+    }
+
+    /// The values of the explicitly stored entries, in the same order as [`indices`](Self::indices).
+    #[inline]
+    #[must_use]
+    pub fn values(&self) -> &[T] {
+        self.data.borrow()
+    }
+
+    /// Consumes the vector and returns its indices and values.
+    pub fn disassemble(self) -> (Indices, Data) {
+        (self.indices, self.data)
+    }
+
+    /// An iterator over the `(index, value)` pairs of the explicitly stored entries, in increasing
+    /// order of index.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.indices().iter().copied().zip(self.values())
+    }
+
+    /// Gets the entry at `index`.
+    ///
+    /// Returns `None` if and only if `index` is out of bounds.
+    #[must_use]
+    pub fn get_entry(&self, index: usize) -> Option<SparseEntry<'_, T>> {
+        if index >= self.len {
+            return None;
+        }
+
+        Some(match self.indices().binary_search(&index) {
+            Ok(k) => SparseEntry::NonZero(&self.values()[k]),
+            Err(_) => SparseEntry::Zero,
+        })
+    }
+
+    /// Borrows the vector as a [`CsVectorView`].
+    #[must_use]
+    pub fn to_view(&self) -> CsVectorView<'_, T> {
+        unsafe { CsVector::from_parts_unchecked(self.len, self.indices(), self.values()) }
+    }
+
+    /// Copies the vector into an owned [`CsVec`].
+    #[must_use]
+    pub fn clone_owned(&self) -> CsVec<T> {
+        unsafe {
+            CsVector::from_parts_unchecked(
+                self.len,
+                self.indices().to_vec(),
+                self.values().to_vec(),
+            )
+        }
+    }
+
+    /// Converts the vector into a dense vector.
+    #[must_use]
+    pub fn to_dense(&self) -> DVector<T>
+    where
+        T: Zero,
+    {
+        let mut dense = DVector::zeros(self.len);
+
+        for (i, value) in self.iter() {
+            dense[i] = value.clone();
+        }
+
+        dense
+    }
+
+    /// Computes the dot product of this vector with a dense vector.
+    ///
+    /// Only the explicitly stored entries of `self` are visited, so this costs `O(nnz)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the two vectors have different lengths.
+    #[must_use]
+    pub fn dot<D, S>(&self, dense: &Vector<T, D, S>) -> T
+    where
+        T: Zero + AddAssign + Mul<Output = T>,
+        D: Dim,
+        S: RawStorage<T, D>,
+    {
+        assert_eq!(
+            self.len,
+            dense.nrows(),
+            "The sparse and dense vectors must have the same length."
+        );
+
+        let mut sum = T::zero();
+
+        for (i, value) in self.iter() {
+            sum += value.clone() * dense[i].clone();
+        }
+
+        sum
+    }
+
+    /// Computes the dot product of this vector with another sparse vector.
+    ///
+    /// Only the indices that are explicitly stored in both vectors contribute to the result, which
+    /// is found by merging the two sorted index lists in `O(nnz(self) + nnz(other))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the two vectors have different lengths.
+    #[must_use]
+    pub fn dot_sparse<I2, D2>(&self, other: &CsVector<T, I2, D2>) -> T
+    where
+        T: Zero + AddAssign + Mul<Output = T>,
+        I2: Borrow<[usize]>,
+        D2: Borrow<[T]>,
+    {
+        assert_eq!(
+            self.len, other.len,
+            "The two sparse vectors must have the same length."
+        );
+
+        let (left_indices, left_values) = (self.indices(), self.values());
+        let (right_indices, right_values) = (other.indices(), other.values());
+
+        let mut sum = T::zero();
+        let (mut l, mut r) = (0, 0);
+
+        while l < left_indices.len() && r < right_indices.len() {
+            match left_indices[l].cmp(&right_indices[r]) {
+                Ordering::Less => l += 1,
+                Ordering::Greater => r += 1,
+                Ordering::Equal => {
+                    sum += left_values[l].clone() * right_values[r].clone();
+                    l += 1;
+                    r += 1;
+                }
+            }
+        }
+
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cs::CscMatrix, error::SparseFormatErrorKind, proptest::*};
+    use nalgebra::DMatrix;
+    use proptest::prelude::*;
+
+    #[test]
+    fn vector_rejects_invalid_parts() {
+        let err = CsVec::try_from_parts(4, vec![0, 4], vec![1, 2]).unwrap_err();
+        assert_eq!(err.kind(), &SparseFormatErrorKind::IndexOutOfBounds);
+
+        let err = CsVec::try_from_parts(4, vec![2, 1], vec![1, 2]).unwrap_err();
+        assert_eq!(err.kind(), &SparseFormatErrorKind::InvalidStructure);
+
+        let err = CsVec::try_from_parts(4, vec![1, 1], vec![1, 2]).unwrap_err();
+        assert_eq!(err.kind(), &SparseFormatErrorKind::DuplicateEntry);
+
+        let err = CsVec::try_from_parts(4, vec![1], vec![1, 2]).unwrap_err();
+        assert_eq!(err.kind(), &SparseFormatErrorKind::InvalidStructure);
+
+        let vector = CsVec::try_from_parts(4, vec![1, 3], vec![1, 2]).unwrap();
+        assert_eq!(vector.get_entry(1), Some(SparseEntry::NonZero(&1)));
+        assert_eq!(vector.get_entry(2), Some(SparseEntry::Zero));
+        assert_eq!(vector.get_entry(4), None);
+    }
+
+    proptest! {
+        #[test]
+        fn row_dot_products_agree_with_dense(csr in csr_strategy()) {
+            let dense = DMatrix::from(&csr);
+            let csc = CscMatrix::from(csr.clone());
+
+            for i in 0..csr.nrows() {
+                let row = csr.row(i);
+                prop_assert_eq!(row.to_dense(), dense.row(i).transpose());
+
+                for j in 0..csr.nrows() {
+                    let expected = dense.row(i).dot(&dense.row(j));
+
+                    prop_assert_eq!(row.dot_sparse(&csr.row(j)), expected);
+                    prop_assert_eq!(row.dot(&dense.row(j).transpose()), expected);
+                }
+            }
+
+            for j in 0..csc.ncols() {
+                prop_assert_eq!(csc.col(j).to_dense(), dense.column(j));
+            }
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::assert_panics;
 use nalgebra::DMatrix;
-use nalgebra_sparse::coo::CooMatrix;
-use nalgebra_sparse::error::SparseFormatErrorKind;
+use nalgebra_sparse::coo::{CooMatrix, CooOrder};
+use nalgebra_sparse::cs::{CscMatrix, CsrMatrix};
+use nalgebra_sparse::error::{SparseFormatError, SparseFormatErrorKind};
 
 #[test]
 fn coo_construction_for_valid_data() {
@@ -344,3 +345,241 @@ fn coo_push_matrix_out_of_bounds_entries() {
         assert_panics!(CooMatrix::new(3, 3).push_matrix(2, 2, &inserted));
     }
 }
+
+#[test]
+fn coo_retain_keeps_matching_triplets_in_order() {
+    let mut coo = CooMatrix::try_from_triplets(
+        3,
+        4,
+        vec![0, 1, 1, 2, 1, 0],
+        vec![0, 2, 3, 3, 2, 1],
+        vec![1, 2, 3, 4, 5, 6],
+    )
+    .unwrap();
+
+    assert_eq!(coo.retain(|_, j, &v| j != 3 && v != 6), 3);
+    assert_eq!(coo.row_indices(), &[0, 1, 1]);
+    assert_eq!(coo.col_indices(), &[0, 2, 2]);
+    assert_eq!(coo.values(), &[1, 2, 5]);
+
+    #[rustfmt::skip]
+    let expected = DMatrix::from_row_slice(3, 4, &[
+        1, 0, 0, 0,
+        0, 0, 7, 0,
+        0, 0, 0, 0
+    ]);
+    assert_eq!(DMatrix::from(&coo), expected);
+
+    assert_eq!(coo.retain(|_, _, _| false), 3);
+    assert_eq!(coo.nnz(), 0);
+}
+
+#[test]
+fn coo_push_triplets_validates_the_whole_batch() {
+    let mut coo = CooMatrix::new(3, 2);
+    coo.push(0, 0, 1);
+    coo.push_triplets(&[2, 1], &[1, 0], vec![2, 3]).unwrap();
+    assert_eq!(
+        coo.triplet_iter().collect::<Vec<_>>(),
+        vec![(0, 0, &1), (2, 1, &2), (1, 0, &3)]
+    );
+
+    let kind = |result: Result<(), SparseFormatError>| *result.unwrap_err().kind();
+    assert_eq!(
+        kind(coo.push_triplets(&[0, 1], &[0], vec![4, 5])),
+        SparseFormatErrorKind::InvalidStructure
+    );
+    assert_eq!(
+        kind(coo.push_triplets(&[0], &[0], vec![4, 5])),
+        SparseFormatErrorKind::InvalidStructure
+    );
+    assert_eq!(
+        kind(coo.push_triplets(&[0, 3], &[0, 0], vec![4, 5])),
+        SparseFormatErrorKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        kind(coo.push_triplets(&[0, 1], &[0, 2], vec![4, 5])),
+        SparseFormatErrorKind::IndexOutOfBounds
+    );
+    assert_eq!(coo.nnz(), 3);
+}
+
+#[test]
+fn coo_try_push_rejects_out_of_bounds_entries() {
+    let mut coo = CooMatrix::new(3, 2);
+    coo.try_push(2, 1, 1).unwrap();
+
+    let kind = |result: Result<(), SparseFormatError>| *result.unwrap_err().kind();
+    assert_eq!(
+        kind(coo.try_push(3, 0, 2)),
+        SparseFormatErrorKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        kind(coo.try_push(0, 2, 2)),
+        SparseFormatErrorKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        kind(coo.try_push_matrix(2, 0, &DMatrix::from_element(2, 1, 3))),
+        SparseFormatErrorKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        kind(coo.try_push_matrix(0, 1, &CsrMatrix::<i32>::identity(2))),
+        SparseFormatErrorKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        kind(coo.try_push_matrix(usize::MAX, 0, &DMatrix::from_element(1, 1, 3))),
+        SparseFormatErrorKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        kind(coo.try_extend(vec![(0, 0, 4), (1, 1, 5), (1, 2, 6), (0, 1, 7)])),
+        SparseFormatErrorKind::IndexOutOfBounds
+    );
+    assert_eq!(coo.triplet_iter().collect::<Vec<_>>(), vec![(2, 1, &1)]);
+
+    coo.try_push_matrix(1, 0, &DMatrix::from_element(2, 2, 3))
+        .unwrap();
+    coo.try_extend(vec![(0, 0, 4), (0, 1, 5)]).unwrap();
+    assert_eq!(coo.nnz(), 7);
+    assert_eq!(coo.row_indices(), &[2, 1, 2, 1, 2, 0, 0]);
+    assert_eq!(coo.values(), &[1, 3, 3, 3, 3, 4, 5]);
+}
+
+#[test]
+fn coo_extend_and_collect_triplets() {
+    let mut coo = CooMatrix::new(3, 3);
+    coo.extend(vec![(0, 2, 1), (1, 1, 2), (0, 2, 3)]);
+    assert_eq!(coo.row_indices(), &[0, 1, 0]);
+    assert_eq!(coo.col_indices(), &[2, 1, 2]);
+    assert_eq!(coo.values(), &[1, 2, 3]);
+    assert_panics!(coo.clone().extend(vec![(0, 0, 1), (3, 0, 1)]));
+
+    let collected: CooMatrix<_> = coo.triplet_iter().map(|(i, j, v)| (i, j, *v)).collect();
+    assert_eq!((collected.nrows(), collected.ncols()), (2, 3));
+    assert_eq!(collected.values(), coo.values());
+
+    let empty: CooMatrix<i32> = std::iter::empty().collect();
+    assert_eq!((empty.nrows(), empty.ncols(), empty.nnz()), (0, 0, 0));
+}
+
+#[test]
+fn coo_capacity_management() {
+    let mut coo = CooMatrix::with_capacity(3, 2, 8);
+    assert_eq!((coo.nrows(), coo.ncols(), coo.nnz()), (3, 2, 0));
+    assert!(coo.capacity() >= 8);
+    assert_eq!(coo, CooMatrix::new(3, 2));
+
+    coo.push(2, 1, 1);
+    coo.push(0, 0, 2);
+    coo.reserve(20);
+    assert!(coo.capacity() >= 22);
+
+    coo.shrink_to_fit();
+    assert!(coo.capacity() >= 2 && coo.capacity() < 22);
+    assert_eq!(coo.values(), &[1, 2]);
+}
+
+#[test]
+fn coo_sort_and_combine_duplicates() {
+    let mut coo = CooMatrix::new(3, 2);
+    coo.extend(vec![
+        (2, 0, 1),
+        (0, 1, 2),
+        (2, 0, 3),
+        (0, 0, 4),
+        (1, 1, 5),
+        (0, 1, 6),
+    ]);
+    let dense = DMatrix::from(&coo);
+
+    let mut sorted = coo.clone();
+    sorted.sort(CooOrder::RowMajor);
+    assert_eq!(
+        sorted.triplet_iter().collect::<Vec<_>>(),
+        vec![
+            (0, 0, &4),
+            (0, 1, &2),
+            (0, 1, &6),
+            (1, 1, &5),
+            (2, 0, &1),
+            (2, 0, &3)
+        ]
+    );
+
+    sorted.sort(CooOrder::ColumnMajor);
+    assert_eq!(
+        sorted.triplet_iter().collect::<Vec<_>>(),
+        vec![
+            (0, 0, &4),
+            (2, 0, &1),
+            (2, 0, &3),
+            (0, 1, &2),
+            (0, 1, &6),
+            (1, 1, &5)
+        ]
+    );
+    assert_eq!(DMatrix::from(&sorted), dense);
+
+    let mut summed = coo.clone();
+    assert_eq!(summed.sum_duplicates(), 2);
+    assert_eq!(
+        summed.triplet_iter().collect::<Vec<_>>(),
+        vec![(0, 0, &4), (0, 1, &8), (1, 1, &5), (2, 0, &4)]
+    );
+    assert_eq!(DMatrix::from(&summed), dense);
+    assert_eq!(summed.sum_duplicates(), 0);
+
+    let mut first = coo;
+    assert_eq!(first.combine_duplicates(|earlier, _| earlier), 2);
+    assert_eq!(first.values(), &[4, 2, 5, 1]);
+
+    let mut empty = CooMatrix::<i32>::new(2, 2);
+    assert_eq!(empty.sum_duplicates(), 0);
+    empty.sort(CooOrder::ColumnMajor);
+    assert_eq!(empty.nnz(), 0);
+}
+
+#[test]
+fn coo_push_matrix_sparse_blocks() {
+    let dense = DMatrix::from_row_slice(2, 3, &[1, 0, 2, 0, 3, 0]);
+    let csr = CsrMatrix::from(&dense);
+    let csc = CscMatrix::from(&dense);
+    let coo =
+        CooMatrix::try_from_triplets(2, 3, vec![1, 0, 1], vec![1, 2, 1], vec![3, 2, 4]).unwrap();
+
+    let mut assembled = CooMatrix::new(4, 6);
+    assembled.push_matrix(0, 0, &csr);
+    assembled.push_matrix(2, 3, &csc);
+    assembled.push_matrix(2, 0, &coo);
+    assembled.push_matrix(0, 3, &CsrMatrix::<i32>::zeros(2, 3));
+
+    // Only explicit entries are pushed, and COO duplicates are kept.
+    assert_eq!(assembled.nnz(), 9);
+    assert_eq!(
+        assembled.triplet_iter().skip(3).collect::<Vec<_>>(),
+        vec![
+            (2, 3, &1),
+            (3, 4, &3),
+            (2, 5, &2),
+            (3, 1, &3),
+            (2, 2, &2),
+            (3, 1, &4)
+        ]
+    );
+
+    #[rustfmt::skip]
+    let expected = DMatrix::from_row_slice(4, 6, &[
+        1, 0, 2, 0, 0, 0,
+        0, 3, 0, 0, 0, 0,
+        0, 0, 2, 1, 0, 2,
+        0, 7, 0, 0, 3, 0
+    ]);
+    assert_eq!(DMatrix::from(&assembled), expected);
+
+    assert_panics!(CooMatrix::new(4, 6).push_matrix(3, 0, &csr));
+    assert_panics!(CooMatrix::new(4, 6).push_matrix(0, 4, &coo));
+
+    // Empty blocks fit anywhere within the bounds.
+    let mut empty = CooMatrix::<i32>::new(2, 2);
+    empty.push_matrix(2, 2, &CooMatrix::new(0, 0));
+    assert_eq!(empty.nnz(), 0);
+}
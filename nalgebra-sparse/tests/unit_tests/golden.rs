@@ -0,0 +1,50 @@
+use nalgebra_sparse::cs::CsrMatrix;
+use nalgebra_sparse::golden::{assert_golden, structured_inputs};
+use nalgebra_sparse::ops::serial::{
+    spadd::spadd_csr_csr, spmm::spmm_csr_csc, spsub::spsub_csr_csr,
+};
+use std::path::PathBuf;
+
+fn golden_path(name: &str, operation: &str) -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "golden",
+        &format!("{}_{}.golden", name, operation),
+    ]
+    .iter()
+    .collect()
+}
+
+#[test]
+fn spadd_csr_csr_matches_golden_files() {
+    for (name, a) in structured_inputs() {
+        if a.nrows() == a.ncols() {
+            let transpose = CsrMatrix::from(a.transpose());
+            let sum = spadd_csr_csr(a.to_view(), transpose).unwrap();
+
+            assert_golden(golden_path(name, "spadd"), &sum, 0.0);
+        }
+    }
+}
+
+#[test]
+fn spsub_csr_csr_matches_golden_files() {
+    for (name, a) in structured_inputs() {
+        if a.nrows() == a.ncols() {
+            let transpose = CsrMatrix::from(a.transpose());
+            let difference = spsub_csr_csr(a.to_view(), transpose).unwrap();
+
+            assert_golden(golden_path(name, "spsub"), &difference, 0.0);
+        }
+    }
+}
+
+#[test]
+fn spmm_csr_csc_matches_golden_files() {
+    for (name, a) in structured_inputs() {
+        let product = spmm_csr_csc(a.to_view(), a.transpose()).unwrap();
+
+        assert_golden(golden_path(name, "spmm"), &product, 0.0);
+    }
+}
@@ -1 +1,2 @@
 mod coo;
+mod golden;